@@ -0,0 +1,57 @@
+-- Unified tracking of long-running work (imports, exports, AI generation, backups)
+CREATE TABLE IF NOT EXISTS background_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    job_type VARCHAR(50) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'processing', 'completed', 'failed', 'cancelled')),
+    progress INTEGER NOT NULL DEFAULT 0 CHECK (progress BETWEEN 0 AND 100),
+    parameters JSONB NOT NULL DEFAULT '{}'::jsonb,
+    result JSONB,
+    result_url TEXT,
+    error_message TEXT,
+    cancellable BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_background_jobs_user_created
+    ON background_jobs(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_background_jobs_status
+    ON background_jobs(status) WHERE status IN ('pending', 'processing');
+
+-- Single listing surface across generic jobs and AI generation jobs
+CREATE OR REPLACE VIEW user_jobs AS
+SELECT
+    id,
+    user_id,
+    'background' AS source,
+    job_type,
+    status,
+    progress,
+    result,
+    result_url,
+    error_message,
+    cancellable AND status IN ('pending', 'processing') AS cancellable,
+    created_at,
+    started_at,
+    completed_at
+FROM background_jobs
+UNION ALL
+SELECT
+    id,
+    user_id,
+    'ai_generation' AS source,
+    'ai_' || job_type AS job_type,
+    status,
+    CASE WHEN status = 'completed' THEN 100 ELSE 0 END AS progress,
+    output_data AS result,
+    NULL AS result_url,
+    error_message,
+    status IN ('pending', 'processing') AS cancellable,
+    created_at,
+    started_at,
+    completed_at
+FROM ai_content_generation_jobs;
@@ -0,0 +1,46 @@
+-- Cooperative cancellation and retry lineage for jobs
+ALTER TABLE background_jobs
+    ADD COLUMN IF NOT EXISTS cancel_requested BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS retry_of UUID REFERENCES background_jobs(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS payload BYTEA;
+
+ALTER TABLE ai_content_generation_jobs
+    ADD COLUMN IF NOT EXISTS cancel_requested BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS retry_of UUID REFERENCES ai_content_generation_jobs(id) ON DELETE SET NULL;
+
+CREATE OR REPLACE VIEW user_jobs AS
+SELECT
+    id,
+    user_id,
+    'background' AS source,
+    job_type,
+    status,
+    progress,
+    result,
+    result_url,
+    error_message,
+    cancellable AND status IN ('pending', 'processing') AS cancellable,
+    created_at,
+    started_at,
+    completed_at,
+    retry_of,
+    cancel_requested
+FROM background_jobs
+UNION ALL
+SELECT
+    id,
+    user_id,
+    'ai_generation' AS source,
+    'ai_' || job_type AS job_type,
+    status,
+    CASE WHEN status = 'completed' THEN 100 ELSE 0 END AS progress,
+    output_data AS result,
+    NULL AS result_url,
+    error_message,
+    status IN ('pending', 'processing') AS cancellable,
+    created_at,
+    started_at,
+    completed_at,
+    retry_of,
+    cancel_requested
+FROM ai_content_generation_jobs;
@@ -0,0 +1,8 @@
+-- Scheduling state needed to build study queues
+ALTER TABLE user_card_stats
+    ADD COLUMN IF NOT EXISTS repetitions INTEGER NOT NULL DEFAULT 0;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_card_stats_user_card
+    ON user_card_stats(user_id, card_id);
+CREATE INDEX IF NOT EXISTS idx_user_card_stats_due
+    ON user_card_stats(user_id, next_review_at);
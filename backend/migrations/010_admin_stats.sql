@@ -0,0 +1,11 @@
+-- Admin role for instance-level endpoints. Promote with:
+--   UPDATE users SET is_admin = true WHERE email = '...';
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT false;
+
+-- Token accounting for AI spend reporting
+ALTER TABLE ai_content_generation_jobs
+    ADD COLUMN IF NOT EXISTS tokens_used INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_users_created_at ON users(created_at);
+CREATE INDEX IF NOT EXISTS idx_study_sessions_started_at ON study_sessions(started_at);
@@ -0,0 +1,26 @@
+-- Lapse tracking for leech detection
+ALTER TABLE user_card_stats
+    ADD COLUMN IF NOT EXISTS lapses INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS leech_flagged_at TIMESTAMPTZ;
+
+-- AI suggested rewrites for leech cards
+CREATE TABLE IF NOT EXISTS leech_remediations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    card_id UUID NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+    strategy TEXT NOT NULL CHECK (strategy IN ('simplify', 'split', 'mnemonic')),
+    suggested_cards JSONB NOT NULL,
+    mnemonic TEXT,
+    rationale TEXT,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'rejected', 'superseded')),
+    accuracy_before REAL,
+    times_seen_at_accept INTEGER,
+    times_correct_at_accept INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    decided_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_leech_remediations_user_status
+    ON leech_remediations(user_id, status, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_leech_remediations_card ON leech_remediations(card_id);
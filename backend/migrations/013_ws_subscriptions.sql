@@ -0,0 +1,16 @@
+-- Live WebSocket subscriptions, one row per connection and subscription type
+CREATE TABLE IF NOT EXISTS ws_subscriptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    connection_id TEXT NOT NULL,
+    subscription_type TEXT NOT NULL
+        CHECK (subscription_type IN ('recommendations', 'study_insights', 'progress_updates')),
+    active BOOLEAN NOT NULL DEFAULT true,
+    connected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_ping_at TIMESTAMPTZ,
+    disconnected_at TIMESTAMPTZ,
+    UNIQUE (connection_id, subscription_type)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ws_subscriptions_user_active
+    ON ws_subscriptions(user_id) WHERE active = true;
@@ -0,0 +1,8 @@
+-- Cross-device study state
+ALTER TABLE study_sessions
+    ADD COLUMN IF NOT EXISTS last_card_id UUID REFERENCES cards(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS active_device_id TEXT,
+    ADD COLUMN IF NOT EXISTS last_activity_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_study_sessions_user_open
+    ON study_sessions(user_id, last_activity_at DESC) WHERE completed_at IS NULL;
@@ -0,0 +1,19 @@
+-- Client-side encrypted decks ("privacy vaults"). Card fronts/backs of an
+-- encrypted deck hold ciphertext produced by the client; the server only keeps
+-- what the client needs to derive the key again.
+ALTER TABLE decks ADD COLUMN IF NOT EXISTS is_encrypted BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE decks DROP CONSTRAINT IF EXISTS decks_encrypted_not_public;
+ALTER TABLE decks ADD CONSTRAINT decks_encrypted_not_public
+    CHECK (NOT (is_encrypted AND is_public));
+
+CREATE TABLE IF NOT EXISTS deck_encryption (
+    deck_id UUID PRIMARY KEY REFERENCES decks(id) ON DELETE CASCADE,
+    algorithm TEXT NOT NULL CHECK (algorithm IN ('aes-256-gcm', 'xchacha20-poly1305')),
+    kdf TEXT NOT NULL CHECK (kdf IN ('pbkdf2-sha256', 'argon2id')),
+    kdf_salt TEXT NOT NULL,
+    kdf_params JSONB NOT NULL DEFAULT '{}',
+    key_check TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
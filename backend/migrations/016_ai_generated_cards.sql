@@ -0,0 +1,17 @@
+-- Cards produced by AI generation jobs, kept for review until approved into a deck
+CREATE TABLE IF NOT EXISTS ai_generated_cards (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    job_id UUID NOT NULL REFERENCES ai_content_generation_jobs(id) ON DELETE CASCADE,
+    deck_id UUID REFERENCES decks(id) ON DELETE SET NULL,
+    front TEXT NOT NULL,
+    back TEXT NOT NULL,
+    explanation TEXT,
+    tags TEXT[],
+    difficulty_estimate INTEGER CHECK (difficulty_estimate BETWEEN 1 AND 5),
+    confidence_score REAL CHECK (confidence_score BETWEEN 0 AND 1),
+    source_context TEXT,
+    approved BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_generated_cards_job ON ai_generated_cards(job_id);
@@ -0,0 +1,6 @@
+-- Rejected suggestions are kept (not deleted) so acceptance rates can be measured
+ALTER TABLE ai_generated_cards
+    ADD COLUMN IF NOT EXISTS rejected_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_ai_generated_cards_pending
+    ON ai_generated_cards(job_id) WHERE NOT approved AND rejected_at IS NULL;
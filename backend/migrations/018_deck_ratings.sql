@@ -0,0 +1,12 @@
+-- One 1-5 star rating per user and deck; feeds public deck badges
+CREATE TABLE IF NOT EXISTS deck_ratings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    deck_id UUID NOT NULL REFERENCES decks(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (deck_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_deck_ratings_deck ON deck_ratings(deck_id);
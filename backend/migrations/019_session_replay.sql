@@ -0,0 +1,15 @@
+-- Scheduler decision recorded with each answer so sessions can be replayed.
+-- Rows recorded before this migration have no decision.
+ALTER TABLE card_progress
+    ADD COLUMN IF NOT EXISTS quality SMALLINT,
+    ADD COLUMN IF NOT EXISTS ease_factor_before REAL,
+    ADD COLUMN IF NOT EXISTS interval_days_before INTEGER,
+    ADD COLUMN IF NOT EXISTS repetitions_before INTEGER,
+    ADD COLUMN IF NOT EXISTS ease_factor_after REAL,
+    ADD COLUMN IF NOT EXISTS interval_days_after INTEGER,
+    ADD COLUMN IF NOT EXISTS repetitions_after INTEGER,
+    ADD COLUMN IF NOT EXISTS next_review_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS lapsed BOOLEAN;
+
+CREATE INDEX IF NOT EXISTS idx_card_progress_session_studied
+    ON card_progress(session_id, studied_at, created_at);
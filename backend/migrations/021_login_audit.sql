@@ -0,0 +1,11 @@
+-- Audit trail for login attempts
+ALTER TABLE login_attempts
+    ADD COLUMN IF NOT EXISTS user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    ADD COLUMN IF NOT EXISTS user_agent TEXT,
+    ADD COLUMN IF NOT EXISTS failure_reason TEXT;
+
+-- Clients without a known address are recorded without one
+ALTER TABLE login_attempts ALTER COLUMN ip_address DROP NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_login_attempts_email_time
+    ON login_attempts(email, attempted_at DESC);
@@ -0,0 +1,36 @@
+-- Per-user consent for AI features
+CREATE TABLE IF NOT EXISTS ai_privacy_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    track_analytics BOOLEAN NOT NULL DEFAULT true,
+    enable_ai_recommendations BOOLEAN NOT NULL DEFAULT true,
+    enable_content_generation BOOLEAN NOT NULL DEFAULT true,
+    share_anonymous_data BOOLEAN NOT NULL DEFAULT false,
+    personalized_learning BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Multiple-choice exams generated from a deck's cards
+CREATE TABLE IF NOT EXISTS deck_exams (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    deck_id UUID NOT NULL REFERENCES decks(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    model_name TEXT,
+    tokens_used INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_deck_exams_deck_user ON deck_exams(deck_id, user_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS deck_exam_questions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    exam_id UUID NOT NULL REFERENCES deck_exams(id) ON DELETE CASCADE,
+    card_id UUID REFERENCES cards(id) ON DELETE SET NULL, -- Card the question tests
+    position INTEGER NOT NULL,
+    question TEXT NOT NULL,
+    options TEXT[] NOT NULL,
+    correct_index INTEGER NOT NULL CHECK (correct_index >= 0),
+    explanation TEXT,
+    UNIQUE (exam_id, position)
+);
@@ -0,0 +1,20 @@
+-- Store refresh tokens as SHA-256 hashes and group rotations into families
+ALTER TABLE refresh_tokens
+    ADD COLUMN IF NOT EXISTS token_hash TEXT,
+    ADD COLUMN IF NOT EXISTS family_id UUID,
+    ADD COLUMN IF NOT EXISTS replaced_by UUID REFERENCES refresh_tokens(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS revoked_reason TEXT; -- rotated, logout, password_reset, reuse_detected
+
+-- Existing tokens keep working: hash them in place, each in its own family
+UPDATE refresh_tokens
+SET token_hash = encode(sha256(convert_to(token, 'UTF8')), 'hex'),
+    family_id = id
+WHERE token_hash IS NULL;
+
+ALTER TABLE refresh_tokens
+    ALTER COLUMN token_hash SET NOT NULL,
+    ALTER COLUMN family_id SET NOT NULL,
+    DROP COLUMN IF EXISTS token;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_refresh_tokens_hash ON refresh_tokens(token_hash);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family ON refresh_tokens(family_id) WHERE revoked_at IS NULL;
@@ -0,0 +1,16 @@
+-- Per-user inbound email addresses: <token>@<INBOUND_EMAIL_DOMAIN>
+CREATE TABLE IF NOT EXISTS inbound_email_addresses (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    token TEXT NOT NULL UNIQUE,
+    deck_id UUID REFERENCES decks(id) ON DELETE SET NULL, -- NULL means the Email Inbox deck
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Processed messages, so provider retries do not create duplicate cards
+CREATE TABLE IF NOT EXISTS inbound_email_messages (
+    message_id TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    card_id UUID REFERENCES cards(id) ON DELETE SET NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,15 @@
+-- Expiring read-only tokens for showing one deck without logging in
+CREATE TABLE IF NOT EXISTS deck_guest_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    deck_id UUID NOT NULL REFERENCES decks(id) ON DELETE CASCADE,
+    token TEXT NOT NULL UNIQUE,
+    label TEXT,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    use_count BIGINT NOT NULL DEFAULT 0, -- Requests made with the token
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_deck_guest_tokens_deck ON deck_guest_tokens(deck_id);
@@ -0,0 +1,28 @@
+-- Anki-style answer ratings with a per-deck 2, 3 or 4 button scale
+ALTER TABLE decks
+    ADD COLUMN IF NOT EXISTS rating_scale SMALLINT NOT NULL DEFAULT 4
+        CHECK (rating_scale IN (2, 3, 4));
+
+ALTER TABLE card_progress
+    ADD COLUMN IF NOT EXISTS rating TEXT CHECK (rating IN ('again', 'hard', 'good', 'easy'));
+
+-- Existing answers map onto the same SM-2 quality they were scheduled with
+UPDATE card_progress
+SET rating = CASE status::TEXT
+    WHEN 'forgot' THEN 'again'
+    WHEN 'hard' THEN 'hard'
+    WHEN 'medium' THEN 'good'
+    WHEN 'easy' THEN 'easy'
+END
+WHERE rating IS NULL;
+
+ALTER TABLE card_progress ALTER COLUMN rating SET NOT NULL;
+
+-- Anything but "again" is a successful recall, as the scheduler already
+-- treated it; session counters previously counted "hard" as wrong
+UPDATE study_sessions s
+SET cards_correct = (
+    SELECT COUNT(*) FROM card_progress cp
+    WHERE cp.session_id = s.id AND cp.rating <> 'again'
+)
+WHERE EXISTS(SELECT 1 FROM card_progress cp WHERE cp.session_id = s.id AND cp.rating = 'hard');
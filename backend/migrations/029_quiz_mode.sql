@@ -0,0 +1,18 @@
+-- Multiple-choice questions issued to quiz-mode study sessions. The server
+-- keeps the answer key so sessions are graded without self-reporting.
+CREATE TABLE IF NOT EXISTS study_quiz_questions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES study_sessions(id) ON DELETE CASCADE,
+    card_id UUID NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+    options TEXT[] NOT NULL,
+    correct_index INTEGER NOT NULL CHECK (correct_index >= 0),
+    distractor_source TEXT NOT NULL CHECK (distractor_source IN ('deck', 'ai', 'mixed')),
+    chosen_index INTEGER,
+    progress_id UUID REFERENCES card_progress(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    answered_at TIMESTAMPTZ
+);
+
+-- At most one open question per session
+CREATE UNIQUE INDEX IF NOT EXISTS idx_study_quiz_questions_open
+    ON study_quiz_questions(session_id) WHERE answered_at IS NULL;
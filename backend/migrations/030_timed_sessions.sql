@@ -0,0 +1,9 @@
+-- Timed study sessions. Answers after expires_at are rejected and a
+-- background task completes the session at its limit.
+ALTER TABLE study_sessions
+    ADD COLUMN IF NOT EXISTS time_limit_seconds INTEGER CHECK (time_limit_seconds > 0),
+    ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_study_sessions_expiring
+    ON study_sessions(expires_at)
+    WHERE completed_at IS NULL AND expires_at IS NOT NULL;
@@ -0,0 +1,8 @@
+-- Sessions left unfinished are marked abandoned by a background job. Their
+-- counters are kept as they were; completed_at stays NULL.
+ALTER TABLE study_sessions
+    ADD COLUMN IF NOT EXISTS abandoned_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_study_sessions_open
+    ON study_sessions(COALESCE(last_activity_at, started_at))
+    WHERE completed_at IS NULL AND abandoned_at IS NULL;
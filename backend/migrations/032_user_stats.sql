@@ -0,0 +1,40 @@
+-- Per-user totals maintained as answers are recorded
+CREATE TABLE IF NOT EXISTS user_stats (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    total_cards_studied INTEGER NOT NULL DEFAULT 0,
+    total_study_time_seconds INTEGER NOT NULL DEFAULT 0,
+    current_streak_days INTEGER NOT NULL DEFAULT 0,
+    longest_streak_days INTEGER NOT NULL DEFAULT 0,
+    last_study_date DATE,
+    total_points INTEGER NOT NULL DEFAULT 0,
+    level INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_stats_user ON user_stats(user_id);
+
+-- Backfill totals from answers recorded so far, with the same points
+-- (10 per correct answer, 2 per "again"), time cap and level curve
+-- (level L from 50 * L * (L - 1) points) as StatsService
+INSERT INTO user_stats (user_id, total_cards_studied, total_study_time_seconds, last_study_date,
+                        total_points, level)
+SELECT user_id, total_cards_studied, total_study_time_seconds, last_study_date, total_points,
+       FLOOR((1 + SQRT(1 + 0.08 * total_points)) / 2)::INTEGER
+FROM (
+    SELECT user_id,
+           COUNT(*)::INTEGER as total_cards_studied,
+           (SUM(LEAST(COALESCE(response_time_ms, 0), 300000)) / 1000)::INTEGER as total_study_time_seconds,
+           MAX(studied_at)::DATE as last_study_date,
+           SUM(CASE WHEN rating = 'again' THEN 2 ELSE 10 END)::INTEGER as total_points
+    FROM card_progress
+    GROUP BY user_id
+) totals
+ON CONFLICT (user_id) DO UPDATE
+SET total_cards_studied = EXCLUDED.total_cards_studied,
+    total_study_time_seconds = EXCLUDED.total_study_time_seconds,
+    last_study_date = COALESCE(user_stats.last_study_date, EXCLUDED.last_study_date),
+    total_points = EXCLUDED.total_points,
+    level = EXCLUDED.level,
+    updated_at = NOW();
@@ -0,0 +1,40 @@
+-- Achievement catalog and the achievements each user has earned
+CREATE TABLE IF NOT EXISTS achievements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    icon_name TEXT,
+    points INTEGER NOT NULL DEFAULT 0,
+    criteria_type TEXT NOT NULL,
+    criteria_value INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_achievements_name ON achievements(name);
+
+CREATE TABLE IF NOT EXISTS user_achievements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    achievement_id UUID NOT NULL REFERENCES achievements(id) ON DELETE CASCADE,
+    earned_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_achievements_user_achievement
+    ON user_achievements(user_id, achievement_id);
+
+-- criteria_type is one of:
+--   cards_studied     answers recorded in total
+--   streak_days       longest run of consecutive study days
+--   perfect_sessions  completed sessions with every answer correct
+INSERT INTO achievements (name, description, icon_name, points, criteria_type, criteria_value)
+VALUES
+    ('First Steps', 'Study your first card', 'footprints', 10, 'cards_studied', 1),
+    ('Getting Started', 'Study 100 cards', 'book-open', 25, 'cards_studied', 100),
+    ('Dedicated Learner', 'Study 1,000 cards', 'library', 100, 'cards_studied', 1000),
+    ('Card Master', 'Study 10,000 cards', 'crown', 500, 'cards_studied', 10000),
+    ('On a Roll', 'Study 3 days in a row', 'flame', 15, 'streak_days', 3),
+    ('Week Warrior', 'Study 7 days in a row', 'calendar-check', 50, 'streak_days', 7),
+    ('Monthly Habit', 'Study 30 days in a row', 'trophy', 200, 'streak_days', 30),
+    ('Flawless', 'Complete a session without a wrong answer', 'star', 20, 'perfect_sessions', 1),
+    ('Perfectionist', 'Complete 10 sessions without a wrong answer', 'sparkles', 100, 'perfect_sessions', 10)
+ON CONFLICT (name) DO NOTHING;
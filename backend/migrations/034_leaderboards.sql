@@ -0,0 +1,28 @@
+-- Users who opt out are left out of the leaderboards
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS leaderboard_opt_out BOOLEAN NOT NULL DEFAULT false;
+
+-- Leaderboard standings, refreshed periodically by the server. Weekly points
+-- use the same per-answer points as user_stats (10 correct, 2 "again") for
+-- answers since the start of the ISO week.
+CREATE MATERIALIZED VIEW IF NOT EXISTS leaderboard_standings AS
+SELECT u.id as user_id,
+       u.display_name,
+       COALESCE(week.points, 0)::INTEGER as weekly_points,
+       COALESCE(CASE WHEN s.last_study_date >= CURRENT_DATE - 1 THEN s.current_streak_days END, 0)
+           as current_streak_days,
+       COALESCE(s.longest_streak_days, 0) as longest_streak_days,
+       NOW() as refreshed_at
+FROM users u
+LEFT JOIN user_stats s ON s.user_id = u.id
+LEFT JOIN (
+    SELECT user_id, SUM(CASE WHEN rating = 'again' THEN 2 ELSE 10 END) as points
+    FROM card_progress
+    WHERE studied_at >= date_trunc('week', NOW())
+    GROUP BY user_id
+) week ON week.user_id = u.id
+WHERE u.deleted_at IS NULL AND NOT u.leaderboard_opt_out;
+
+-- Required for REFRESH MATERIALIZED VIEW CONCURRENTLY
+CREATE UNIQUE INDEX IF NOT EXISTS idx_leaderboard_standings_user
+    ON leaderboard_standings(user_id);
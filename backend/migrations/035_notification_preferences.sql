@@ -0,0 +1,15 @@
+-- Per-user email reminder schedule. reminder_days holds ISO weekdays
+-- (1 = Monday ... 7 = Sunday) in the user's timezone.
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    email_reminders_enabled BOOLEAN NOT NULL DEFAULT false,
+    reminder_time TIME NOT NULL DEFAULT '09:00',
+    reminder_days SMALLINT[] NOT NULL DEFAULT '{1,2,3,4,5,6,7}',
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    last_reminder_on DATE, -- User's local date of the last reminder check
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_preferences_enabled
+    ON notification_preferences(user_id) WHERE email_reminders_enabled;
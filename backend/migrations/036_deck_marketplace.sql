@@ -0,0 +1,16 @@
+-- Public deck marketplace: owners describe public decks with tags and a
+-- language, copies and exports by other users are counted as downloads, and
+-- admins feature decks. Ratings use the existing deck_ratings table.
+ALTER TABLE decks
+    ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS language TEXT,
+    ADD COLUMN IF NOT EXISTS download_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS is_featured BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS featured_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_decks_public_tags
+    ON decks USING GIN (tags) WHERE is_public AND deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_decks_public_downloads
+    ON decks(download_count DESC) WHERE is_public AND deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_decks_featured
+    ON decks(featured_at DESC) WHERE is_featured AND is_public AND deleted_at IS NULL;
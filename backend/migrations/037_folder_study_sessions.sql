@@ -0,0 +1,8 @@
+-- Study sessions across every deck in a folder and its subfolders. Such a
+-- session has a folder_id and no deck_id; each answer's deck is its card's.
+ALTER TABLE study_sessions
+    ALTER COLUMN deck_id DROP NOT NULL,
+    ADD COLUMN IF NOT EXISTS folder_id UUID REFERENCES folders(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_study_sessions_folder
+    ON study_sessions(folder_id) WHERE folder_id IS NOT NULL;
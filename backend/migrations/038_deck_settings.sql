@@ -0,0 +1,14 @@
+-- Per-deck study settings. Decks without a row use the defaults.
+CREATE TABLE IF NOT EXISTS deck_settings (
+    deck_id UUID PRIMARY KEY REFERENCES decks(id) ON DELETE CASCADE,
+    new_cards_per_day INTEGER NOT NULL DEFAULT 20 CHECK (new_cards_per_day BETWEEN 0 AND 500),
+    max_reviews_per_day INTEGER NOT NULL DEFAULT 200 CHECK (max_reviews_per_day BETWEEN 0 AND 10000),
+    algorithm TEXT NOT NULL DEFAULT 'sm2' CHECK (algorithm IN ('sm2', 'leitner')),
+    lapse_action TEXT NOT NULL DEFAULT 'relearn' CHECK (lapse_action IN ('relearn', 'reset')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Daily limits count today's answers per user
+CREATE INDEX IF NOT EXISTS idx_card_progress_user_created
+    ON card_progress(user_id, created_at);
@@ -0,0 +1,20 @@
+-- Configurable Leitner boxes per deck, with each card's box kept alongside
+-- its SM-2 state
+ALTER TABLE deck_settings
+    ADD COLUMN IF NOT EXISTS leitner_boxes INTEGER NOT NULL DEFAULT 5
+        CHECK (leitner_boxes BETWEEN 2 AND 10),
+    ADD COLUMN IF NOT EXISTS leitner_promotion TEXT NOT NULL DEFAULT 'step'
+        CHECK (leitner_promotion IN ('step', 'skip_on_easy')),
+    ADD COLUMN IF NOT EXISTS leitner_demotion TEXT NOT NULL DEFAULT 'first_box'
+        CHECK (leitner_demotion IN ('first_box', 'previous_box'));
+
+-- NULL until the card is reviewed on a Leitner deck
+ALTER TABLE user_card_stats
+    ADD COLUMN IF NOT EXISTS leitner_box INTEGER;
+
+-- Leitner decks kept the box in repetitions until now
+UPDATE user_card_stats s
+SET leitner_box = LEAST(s.repetitions, 5)
+FROM cards c
+JOIN deck_settings ds ON ds.deck_id = c.deck_id
+WHERE c.id = s.card_id AND ds.algorithm = 'leitner';
@@ -0,0 +1,4 @@
+-- The activity heatmap sums a user's answers per day over a year; covering
+-- response_time_ms lets it read the index alone
+CREATE INDEX IF NOT EXISTS idx_card_progress_user_studied
+    ON card_progress(user_id, studied_at) INCLUDE (response_time_ms);
@@ -0,0 +1,15 @@
+-- A learner's own markers and notes on a card. Cards without a row have no
+-- flags and no notes.
+CREATE TABLE IF NOT EXISTS user_card_flags (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    card_id UUID NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+    starred BOOLEAN NOT NULL DEFAULT false,
+    suspended BOOLEAN NOT NULL DEFAULT false,
+    leech BOOLEAN NOT NULL DEFAULT false,
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, card_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_card_flags_card ON user_card_flags(card_id);
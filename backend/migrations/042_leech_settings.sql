@@ -0,0 +1,13 @@
+-- Lapses before a card is a leech, and whether leeches are suspended
+ALTER TABLE deck_settings
+    ADD COLUMN IF NOT EXISTS leech_threshold INTEGER NOT NULL DEFAULT 8
+        CHECK (leech_threshold BETWEEN 2 AND 100),
+    ADD COLUMN IF NOT EXISTS leech_action TEXT NOT NULL DEFAULT 'flag'
+        CHECK (leech_action IN ('flag', 'suspend'));
+
+-- Cards detected as leeches before now carry the learner's leech marker too
+INSERT INTO user_card_flags (user_id, card_id, leech)
+SELECT user_id, card_id, true
+FROM user_card_stats
+WHERE leech_flagged_at IS NOT NULL
+ON CONFLICT (user_id, card_id) DO UPDATE SET leech = true;
@@ -0,0 +1,28 @@
+-- Raw study events for analytics, also sent in batches by clients that
+-- studied offline
+CREATE TABLE IF NOT EXISTS study_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    card_id UUID NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+    deck_id UUID NOT NULL REFERENCES decks(id) ON DELETE CASCADE,
+    session_id UUID REFERENCES study_sessions(id) ON DELETE SET NULL,
+    event_type TEXT NOT NULL,
+    outcome TEXT,
+    response_time_ms INTEGER,
+    confidence_rating INTEGER,
+    ease_factor REAL NOT NULL DEFAULT 2.5,
+    interval_days INTEGER NOT NULL DEFAULT 0,
+    repetition_number INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- When the event happened on the client, and where it came in a batch.
+-- Resending a batch inserts nothing twice.
+ALTER TABLE study_events
+    ADD COLUMN IF NOT EXISTS occurred_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS batch_id UUID,
+    ADD COLUMN IF NOT EXISTS batch_index INTEGER;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_study_events_batch
+    ON study_events(user_id, batch_id, batch_index) WHERE batch_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_study_events_user_created ON study_events(user_id, created_at);
@@ -0,0 +1,102 @@
+-- Change log for offline sync: the latest change to each folder, deck and
+-- card, numbered from one sequence so clients can ask for everything after
+-- the last version they saw. Deletions stay as tombstones.
+CREATE SEQUENCE IF NOT EXISTS sync_version_seq;
+
+CREATE TABLE IF NOT EXISTS sync_changes (
+    entity TEXT NOT NULL CHECK (entity IN ('folder', 'deck', 'card')),
+    entity_id UUID NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    op TEXT NOT NULL CHECK (op IN ('upsert', 'delete')),
+    version BIGINT NOT NULL DEFAULT nextval('sync_version_seq'),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (entity, entity_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_changes_user_version ON sync_changes(user_id, version);
+
+-- Every write goes through here, whichever code path made it. Changes are
+-- logged for the owner; a card belongs to its deck's owner. Decks and cards
+-- moved to the trash are deletions.
+CREATE OR REPLACE FUNCTION record_sync_change() RETURNS TRIGGER AS $$
+DECLARE
+    entity_name TEXT := TG_ARGV[0];
+    changed RECORD;
+    owner UUID;
+    operation TEXT := 'upsert';
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+        operation := 'delete';
+    ELSE
+        changed := NEW;
+    END IF;
+
+    IF entity_name = 'folder' THEN
+        owner := changed.user_id;
+    ELSIF entity_name = 'deck' THEN
+        owner := changed.owner_id;
+    ELSE
+        SELECT owner_id INTO owner FROM decks WHERE id = changed.deck_id;
+    END IF;
+
+    -- Nested so folders, which have no deleted_at, never read it
+    IF entity_name <> 'folder' AND TG_OP <> 'DELETE' THEN
+        IF changed.deleted_at IS NOT NULL THEN
+            operation := 'delete';
+        END IF;
+    END IF;
+
+    -- Cards removed along with their deck are covered by the deck's tombstone
+    IF owner IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO sync_changes (entity, entity_id, user_id, op)
+    VALUES (entity_name, changed.id, owner, operation)
+    ON CONFLICT (entity, entity_id) DO UPDATE SET
+        user_id = EXCLUDED.user_id,
+        op = EXCLUDED.op,
+        version = EXCLUDED.version,
+        updated_at = EXCLUDED.updated_at;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS folders_sync_write ON folders;
+CREATE TRIGGER folders_sync_write AFTER INSERT OR DELETE ON folders
+    FOR EACH ROW EXECUTE FUNCTION record_sync_change('folder');
+DROP TRIGGER IF EXISTS folders_sync_update ON folders;
+CREATE TRIGGER folders_sync_update AFTER UPDATE ON folders
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE FUNCTION record_sync_change('folder');
+
+DROP TRIGGER IF EXISTS decks_sync_write ON decks;
+CREATE TRIGGER decks_sync_write AFTER INSERT OR DELETE ON decks
+    FOR EACH ROW EXECUTE FUNCTION record_sync_change('deck');
+DROP TRIGGER IF EXISTS decks_sync_update ON decks;
+CREATE TRIGGER decks_sync_update AFTER UPDATE ON decks
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE FUNCTION record_sync_change('deck');
+
+DROP TRIGGER IF EXISTS cards_sync_write ON cards;
+CREATE TRIGGER cards_sync_write AFTER INSERT OR DELETE ON cards
+    FOR EACH ROW EXECUTE FUNCTION record_sync_change('card');
+DROP TRIGGER IF EXISTS cards_sync_update ON cards;
+CREATE TRIGGER cards_sync_update AFTER UPDATE ON cards
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE FUNCTION record_sync_change('card');
+
+-- Existing content starts at its own version
+INSERT INTO sync_changes (entity, entity_id, user_id, op, updated_at)
+SELECT 'folder', id, user_id, 'upsert', updated_at FROM folders
+ON CONFLICT (entity, entity_id) DO NOTHING;
+
+INSERT INTO sync_changes (entity, entity_id, user_id, op, updated_at)
+SELECT 'deck', id, owner_id, CASE WHEN deleted_at IS NULL THEN 'upsert' ELSE 'delete' END, updated_at
+FROM decks
+ON CONFLICT (entity, entity_id) DO NOTHING;
+
+INSERT INTO sync_changes (entity, entity_id, user_id, op, updated_at)
+SELECT 'card', c.id, d.owner_id, CASE WHEN c.deleted_at IS NULL THEN 'upsert' ELSE 'delete' END, c.updated_at
+FROM cards c
+JOIN decks d ON d.id = c.deck_id
+ON CONFLICT (entity, entity_id) DO NOTHING;
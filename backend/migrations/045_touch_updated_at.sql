@@ -0,0 +1,24 @@
+-- Collection ETags are derived from MAX(updated_at), so every edit to what
+-- the deck and card listings show has to move it, including the writes that
+-- do not set it. Counters such as download_count are left alone.
+CREATE OR REPLACE FUNCTION touch_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at := NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS decks_touch_updated_at ON decks;
+CREATE TRIGGER decks_touch_updated_at
+    BEFORE UPDATE OF title, description, folder_id, is_public, tags, language ON decks
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*)
+    EXECUTE FUNCTION touch_updated_at();
+
+DROP TRIGGER IF EXISTS cards_touch_updated_at ON cards;
+CREATE TRIGGER cards_touch_updated_at
+    BEFORE UPDATE OF deck_id, front, back, position ON cards
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*)
+    EXECUTE FUNCTION touch_updated_at();
+
+CREATE INDEX IF NOT EXISTS idx_cards_deck_updated
+    ON cards(deck_id, updated_at) WHERE deleted_at IS NULL;
@@ -0,0 +1,12 @@
+-- Tokens each user spent on AI features per UTC day and provider, for
+-- quotas and cost reporting. Cost is priced when the tokens are recorded,
+-- so later price changes do not rewrite history.
+CREATE TABLE IF NOT EXISTS ai_usage (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    usage_date DATE NOT NULL,
+    provider TEXT NOT NULL,
+    tokens_used BIGINT NOT NULL DEFAULT 0,
+    request_count INTEGER NOT NULL DEFAULT 0,
+    cost_usd DOUBLE PRECISION NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, usage_date, provider)
+);
@@ -0,0 +1,18 @@
+-- Problems found by deck linting, each with an optional rewrite of the card.
+-- A new lint of a deck replaces the user's unapplied suggestions for it.
+CREATE TABLE IF NOT EXISTS card_lint_suggestions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    deck_id UUID NOT NULL REFERENCES decks(id) ON DELETE CASCADE,
+    card_id UUID NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('answer_leak', 'long_answer', 'duplicate', 'ambiguous')),
+    source TEXT NOT NULL CHECK (source IN ('heuristic', 'ai')),
+    message TEXT NOT NULL,
+    suggested_front TEXT,
+    suggested_back TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    applied_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_card_lint_suggestions_user_deck
+    ON card_lint_suggestions(user_id, deck_id, created_at);
@@ -0,0 +1,12 @@
+-- AI explanations of cards shown during study, cached so repeated requests
+-- are not billed again. An explanation is keyed by the card and the learner's
+-- normalized wrong answer ('' for none) and is stale once the card changes.
+CREATE TABLE IF NOT EXISTS card_explanations (
+    card_id UUID NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+    wrong_answer TEXT NOT NULL DEFAULT '',
+    explanation TEXT NOT NULL,
+    model_name TEXT NOT NULL,
+    tokens_used INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (card_id, wrong_answer)
+);
@@ -0,0 +1,18 @@
+-- Study recommendations derived from each user's study events and schedule.
+-- They are regenerated every few hours and a regeneration expires the ones
+-- still pending. Marking one not helpful keeps it from coming back for a while.
+CREATE TABLE IF NOT EXISTS ai_recommendations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    recommendation_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    confidence_score REAL,
+    shown_at TIMESTAMPTZ,
+    accepted BOOLEAN,
+    feedback TEXT CHECK (feedback IN ('helpful', 'not_helpful', 'ignored')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_recommendations_user_created
+    ON ai_recommendations(user_id, created_at);
@@ -0,0 +1,20 @@
+-- Study habits mined from each user's study events by a background job.
+-- Each user has at most one current pattern per type; a pattern the data no
+-- longer supports is removed on the next analysis.
+CREATE TABLE IF NOT EXISTS learning_patterns (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    pattern_type TEXT NOT NULL CHECK (pattern_type IN ('time_of_day', 'session_length', 'difficulty_preference')),
+    pattern_data JSONB NOT NULL,
+    confidence_score REAL,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    valid_until TIMESTAMPTZ,
+    UNIQUE (user_id, pattern_type)
+);
+
+-- When each user was last analyzed, so users whose history yields no pattern
+-- are not picked up again on every run
+CREATE TABLE IF NOT EXISTS learning_pattern_analyses (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    analyzed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,17 @@
+-- Languages of a deck's two sides as lowercase BCP 47 tags, used to phrase
+-- AI prompts and pick text-to-speech voices. Existing decks take the
+-- marketplace language for both sides.
+ALTER TABLE decks
+    ADD COLUMN IF NOT EXISTS front_language TEXT,
+    ADD COLUMN IF NOT EXISTS back_language TEXT;
+
+UPDATE decks
+SET front_language = language, back_language = language
+WHERE language IS NOT NULL AND front_language IS NULL AND back_language IS NULL;
+
+DROP TRIGGER IF EXISTS decks_touch_updated_at ON decks;
+CREATE TRIGGER decks_touch_updated_at
+    BEFORE UPDATE OF title, description, folder_id, is_public, tags, language,
+        front_language, back_language ON decks
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*)
+    EXECUTE FUNCTION touch_updated_at();
@@ -0,0 +1,9 @@
+-- Decks can keep a reverse sibling (back -> front) for every card. A reverse
+-- card points at the card it was generated from; there is at most one.
+ALTER TABLE deck_settings
+    ADD COLUMN IF NOT EXISTS generate_reverse_cards BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE cards
+    ADD COLUMN IF NOT EXISTS reverse_of UUID REFERENCES cards(id) ON DELETE CASCADE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_cards_reverse_of ON cards(reverse_of);
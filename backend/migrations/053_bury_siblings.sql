@@ -0,0 +1,4 @@
+-- Whether the study queue buries a card's reverse sibling for the rest of
+-- the day once one of the pair has been answered
+ALTER TABLE deck_settings
+    ADD COLUMN IF NOT EXISTS bury_siblings BOOLEAN NOT NULL DEFAULT true;
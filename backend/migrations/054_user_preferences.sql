@@ -0,0 +1,9 @@
+-- Per-user study preferences applied when building study queues
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    new_card_order TEXT NOT NULL DEFAULT 'position'
+        CHECK (new_card_order IN ('position', 'random')),
+    interleave_decks BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
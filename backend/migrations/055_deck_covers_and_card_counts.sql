@@ -0,0 +1,46 @@
+-- Cover images for decks, stored with the other media, and a cached count
+-- of each deck's live cards so listings no longer count cards per deck on
+-- every request.
+ALTER TABLE decks
+    ADD COLUMN IF NOT EXISTS cover_storage_key TEXT,
+    ADD COLUMN IF NOT EXISTS card_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE decks d
+SET card_count = (SELECT COUNT(*) FROM cards c WHERE c.deck_id = d.id AND c.deleted_at IS NULL);
+
+-- Cards in the trash do not count. Moving a card between decks, trashing
+-- it and restoring it all take it out of one count and put it in another.
+CREATE OR REPLACE FUNCTION maintain_deck_card_count() RETURNS TRIGGER AS $$
+BEGIN
+    -- Nested so OLD and NEW are only read when the operation has them
+    IF TG_OP <> 'INSERT' THEN
+        IF OLD.deleted_at IS NULL THEN
+            UPDATE decks SET card_count = card_count - 1 WHERE id = OLD.deck_id;
+        END IF;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        IF NEW.deleted_at IS NULL THEN
+            UPDATE decks SET card_count = card_count + 1 WHERE id = NEW.deck_id;
+        END IF;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS cards_count_write ON cards;
+CREATE TRIGGER cards_count_write AFTER INSERT OR DELETE ON cards
+    FOR EACH ROW EXECUTE FUNCTION maintain_deck_card_count();
+DROP TRIGGER IF EXISTS cards_count_update ON cards;
+CREATE TRIGGER cards_count_update AFTER UPDATE OF deck_id, deleted_at ON cards
+    FOR EACH ROW WHEN (
+        OLD.deck_id IS DISTINCT FROM NEW.deck_id OR OLD.deleted_at IS DISTINCT FROM NEW.deleted_at
+    )
+    EXECUTE FUNCTION maintain_deck_card_count();
+
+-- A new cover has to move the deck listing's ETag
+DROP TRIGGER IF EXISTS decks_touch_updated_at ON decks;
+CREATE TRIGGER decks_touch_updated_at
+    BEFORE UPDATE OF title, description, folder_id, is_public, tags, language,
+        front_language, back_language, cover_storage_key ON decks
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*)
+    EXECUTE FUNCTION touch_updated_at();
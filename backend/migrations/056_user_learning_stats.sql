@@ -0,0 +1,21 @@
+-- Per-user totals over recorded study events, refreshed periodically by the
+-- server. Partial answers count as incorrect; study days are UTC dates.
+CREATE MATERIALIZED VIEW IF NOT EXISTS user_learning_stats AS
+SELECT user_id,
+       COUNT(DISTINCT card_id) as unique_cards_studied,
+       COUNT(*) as total_study_events,
+       AVG(response_time_ms)::INTEGER as avg_response_time_ms,
+       COUNT(*) FILTER (WHERE outcome = 'correct')::INTEGER as total_correct,
+       COUNT(*) FILTER (WHERE outcome IN ('incorrect', 'partial'))::INTEGER as total_incorrect,
+       (COUNT(*) FILTER (WHERE outcome = 'correct')::REAL
+           / NULLIF(COUNT(*) FILTER (WHERE outcome IN ('correct', 'incorrect', 'partial')), 0))::REAL
+           as accuracy_rate,
+       MAX(COALESCE(occurred_at, created_at)) as last_study_time,
+       COUNT(DISTINCT (COALESCE(occurred_at, created_at) AT TIME ZONE 'UTC')::DATE) as study_days,
+       NOW() as refreshed_at
+FROM study_events
+GROUP BY user_id;
+
+-- Required for REFRESH MATERIALIZED VIEW CONCURRENTLY
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_learning_stats_user
+    ON user_learning_stats(user_id);
@@ -0,0 +1,14 @@
+-- Identities at OAuth providers (Google, GitHub) that sign in as a user.
+-- Users created through a provider get a random password they never see.
+CREATE TABLE IF NOT EXISTS linked_accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL CHECK (provider IN ('google', 'github')),
+    provider_user_id TEXT NOT NULL,
+    email TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_login_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, provider_user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_linked_accounts_user ON linked_accounts(user_id);
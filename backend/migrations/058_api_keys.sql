@@ -0,0 +1,19 @@
+-- Keys for scripts and other programmatic clients, sent as X-Api-Key in
+-- place of a bearer token. Only a SHA-256 hash of each key is stored; the
+-- prefix lets users tell their keys apart.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    scope TEXT NOT NULL CHECK (scope IN ('read', 'write')),
+    -- NULL uses RATE_LIMIT_API_KEY_PER_MINUTE
+    rate_limit_per_minute INTEGER CHECK (rate_limit_per_minute > 0),
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_user ON api_keys(user_id);
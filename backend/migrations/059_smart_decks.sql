@@ -0,0 +1,18 @@
+-- Smart decks: saved filters over the cards a user can study, evaluated
+-- whenever a queue is built, so they never hold cards of their own. A study
+-- session of a smart deck has a smart_deck_id and neither a deck_id nor a
+-- folder_id.
+CREATE TABLE IF NOT EXISTS smart_decks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    filter JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_smart_decks_user ON smart_decks(user_id);
+
+ALTER TABLE study_sessions
+    ADD COLUMN IF NOT EXISTS smart_deck_id UUID REFERENCES smart_decks(id) ON DELETE SET NULL;
@@ -0,0 +1,10 @@
+-- Trigram indexes behind /search/suggest. They serve both substring matches
+-- (LIKE '%term%') and fuzzy matches (%) on lowercased deck titles and folder
+-- names, so type-ahead lookups stay fast as libraries grow.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_decks_title_trgm
+    ON decks USING GIN (LOWER(title) gin_trgm_ops) WHERE deleted_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_folders_name_trgm
+    ON folders USING GIN (LOWER(name) gin_trgm_ops);
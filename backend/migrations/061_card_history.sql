@@ -0,0 +1,3 @@
+-- GET /cards/{id}/history reads one user's answers to one card
+CREATE INDEX IF NOT EXISTS idx_card_progress_card_user
+    ON card_progress(card_id, user_id, studied_at);
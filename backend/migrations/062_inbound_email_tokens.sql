@@ -0,0 +1,9 @@
+-- Tokens of signed inbound email requests, so a captured request cannot be
+-- replayed within its timestamp window
+CREATE TABLE IF NOT EXISTS inbound_email_tokens (
+    token TEXT PRIMARY KEY,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_inbound_email_tokens_received_at
+    ON inbound_email_tokens(received_at);
@@ -0,0 +1,58 @@
+-- Order the sync change log by commit rather than by version. Versions are
+-- taken when a row is written, so a transaction can commit after a later
+-- version and a pull in between would move past its changes for good. Each
+-- change now records the id of the transaction that wrote it; pulls only
+-- serve transactions older than every one still running, so nothing can
+-- commit behind a client's cursor.
+ALTER TABLE sync_changes
+    ADD COLUMN IF NOT EXISTS txid BIGINT NOT NULL DEFAULT pg_current_xact_id()::text::BIGINT;
+
+DROP INDEX IF EXISTS idx_sync_changes_user_version;
+CREATE INDEX IF NOT EXISTS idx_sync_changes_user_txid ON sync_changes(user_id, txid, version);
+
+CREATE OR REPLACE FUNCTION record_sync_change() RETURNS TRIGGER AS $$
+DECLARE
+    entity_name TEXT := TG_ARGV[0];
+    changed RECORD;
+    owner UUID;
+    operation TEXT := 'upsert';
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+        operation := 'delete';
+    ELSE
+        changed := NEW;
+    END IF;
+
+    IF entity_name = 'folder' THEN
+        owner := changed.user_id;
+    ELSIF entity_name = 'deck' THEN
+        owner := changed.owner_id;
+    ELSE
+        SELECT owner_id INTO owner FROM decks WHERE id = changed.deck_id;
+    END IF;
+
+    -- Nested so folders, which have no deleted_at, never read it
+    IF entity_name <> 'folder' AND TG_OP <> 'DELETE' THEN
+        IF changed.deleted_at IS NOT NULL THEN
+            operation := 'delete';
+        END IF;
+    END IF;
+
+    -- Cards removed along with their deck are covered by the deck's tombstone
+    IF owner IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO sync_changes (entity, entity_id, user_id, op)
+    VALUES (entity_name, changed.id, owner, operation)
+    ON CONFLICT (entity, entity_id) DO UPDATE SET
+        user_id = EXCLUDED.user_id,
+        op = EXCLUDED.op,
+        version = EXCLUDED.version,
+        txid = EXCLUDED.txid,
+        updated_at = EXCLUDED.updated_at;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
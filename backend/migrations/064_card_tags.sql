@@ -0,0 +1,4 @@
+-- A learner's own tags on a card, kept with their other flags and stored
+-- trimmed and lowercased like deck tags
+ALTER TABLE user_card_flags
+    ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,13 @@
+-- Deck shares sent to addresses without an account. Sharing answers the
+-- same whether or not an address is registered, and whoever signs up with
+-- the address within the invite's lifetime gets the share.
+CREATE TABLE IF NOT EXISTS deck_share_invites (
+    deck_id UUID NOT NULL REFERENCES decks(id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('viewer', 'editor')),
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (deck_id, email)
+);
+
+CREATE INDEX IF NOT EXISTS idx_deck_share_invites_email ON deck_share_invites(email);
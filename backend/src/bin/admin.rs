@@ -0,0 +1,226 @@
+//! Operator CLI for tasks that cannot go through the HTTP API, sharing the
+//! server's configuration and service layer.
+//!
+//! ```text
+//! cargo run --bin admin -- <command> [args]
+//! ```
+
+use anyhow::{anyhow, bail, Context};
+use std::{collections::VecDeque, path::PathBuf};
+use uuid::Uuid;
+use validator::Validate;
+
+use deckoracle_backend::{
+    config::Config,
+    models::{admin::SetPasswordDto, RegisterDto},
+    services::{
+        admin::AdminService, auth::AuthService, import_export::ImportExportService,
+        search::SearchService, trash::TrashService, user::UserService,
+    },
+    state::AppState,
+};
+
+const USAGE: &str = "\
+Usage: admin <command> [args]
+
+Commands:
+  create-user <email> <password> [--name <name>] [--admin]
+      Create a verified account, optionally with admin rights
+  reset-password <email> <new-password>
+      Replace a user's password and sign them out everywhere
+  export-deck <deck-id> [--format <format>] [--output <path>]
+      Export a deck as its owner would; json unless --format is given,
+      written to <deck-id>.<extension> unless --output is given
+  reindex-search
+      Rebuild the search indexes and refresh planner statistics
+  purge-trash
+      Permanently delete decks and cards past the trash retention period";
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into()),
+        )
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || matches!(args[0].as_str(), "-h" | "--help" | "help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if let Err(e) = run(&args).await {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+enum Command {
+    CreateUser { dto: RegisterDto, admin: bool },
+    ResetPassword { email: String, dto: SetPasswordDto },
+    ExportDeck { deck_id: Uuid, format: String, output: Option<PathBuf> },
+    ReindexSearch,
+    PurgeTrash,
+}
+
+/// Read and validate the command line before connecting, so typos fail fast
+fn parse_command(args: &[String]) -> anyhow::Result<Command> {
+    let (name, rest) = args.split_first().expect("checked by main");
+    let mut args = Args::parse(rest, &["--admin"]);
+
+    let command = match name.as_str() {
+        "create-user" => {
+            let dto = RegisterDto {
+                email: args.positional("email")?,
+                password: args.positional("password")?,
+                display_name: args.option("--name"),
+            };
+            dto.validate()?;
+            Command::CreateUser {
+                dto,
+                admin: args.flag("--admin"),
+            }
+        }
+        "reset-password" => {
+            let email = args.positional("email")?;
+            let dto = SetPasswordDto {
+                new_password: args.positional("new-password")?,
+            };
+            dto.validate()?;
+            Command::ResetPassword { email, dto }
+        }
+        "export-deck" => Command::ExportDeck {
+            deck_id: args
+                .positional("deck-id")?
+                .parse()
+                .map_err(|_| anyhow!("deck-id must be a UUID"))?,
+            format: args.option("--format").unwrap_or_else(|| "json".to_string()),
+            output: args.option("--output").map(PathBuf::from),
+        },
+        "reindex-search" => Command::ReindexSearch,
+        "purge-trash" => Command::PurgeTrash,
+        other => bail!("unknown command `{}`\n\n{}", other, USAGE),
+    };
+
+    args.finish()?;
+    Ok(command)
+}
+
+async fn run(args: &[String]) -> anyhow::Result<()> {
+    let command = parse_command(args)?;
+    let config = Config::from_env().context("Failed to load configuration")?;
+    let state = AppState::new(config).await.context("Failed to connect to the database")?;
+    let db = &state.db;
+
+    match command {
+        Command::CreateUser { dto, admin } => {
+            let user = AuthService::register(db, &state.config, dto).await?.user;
+            UserService::mark_email_verified(db, user.id).await?;
+            if admin {
+                AdminService::set_admin(db, user.id, true).await?;
+            }
+            println!("Created user {} ({}){}", user.email, user.id, if admin { " as admin" } else { "" });
+        }
+        Command::ResetPassword { email, dto } => {
+            let user = UserService::find_by_email(db, &email).await?;
+            UserService::set_password(db, user.id, &dto.new_password).await?;
+            println!("Password reset for {}; their sessions were signed out", user.email);
+        }
+        Command::ExportDeck { deck_id, format, output } => {
+            let exporter = state
+                .exporters
+                .get(&format)
+                .ok_or_else(|| anyhow!("unknown export format `{}`", format))?;
+            let owner_id: Uuid =
+                sqlx::query_scalar("SELECT owner_id FROM decks WHERE id = $1 AND deleted_at IS NULL")
+                    .bind(deck_id)
+                    .fetch_optional(db)
+                    .await?
+                    .ok_or_else(|| anyhow!("deck {} not found", deck_id))?;
+
+            // As the owner, with their progress and the deck's attachments
+            let data = ImportExportService::export_deck(
+                db,
+                &state.media,
+                owner_id,
+                deck_id,
+                exporter.as_ref(),
+                true,
+                true,
+            )
+            .await?;
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.{}", deck_id, exporter.extension())));
+            std::fs::write(&output, &data).with_context(|| format!("Failed to write {}", output.display()))?;
+            println!("Wrote {} ({} bytes)", output.display(), data.len());
+        }
+        Command::ReindexSearch => {
+            let indexes = SearchService::reindex(db).await?;
+            println!("Rebuilt {}", indexes.join(", "));
+        }
+        Command::PurgeTrash => {
+            let (decks, cards) = TrashService::purge_expired(db).await?;
+            println!("Purged {} decks and {} cards", decks, cards);
+        }
+    }
+
+    Ok(())
+}
+
+/// Positional arguments and `--name value` options, consumed as commands
+/// read them so leftovers can be reported
+struct Args {
+    positional: VecDeque<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    /// `flags` take no value, so a word after them stays positional
+    fn parse(args: &[String], flags: &[&str]) -> Self {
+        let mut positional = VecDeque::new();
+        let mut options = Vec::new();
+        let mut iter = args.iter().peekable();
+        while let Some(arg) = iter.next() {
+            if flags.contains(&arg.as_str()) {
+                options.push((arg.clone(), None));
+            } else if arg.starts_with("--") {
+                let value = iter.next_if(|next| !next.starts_with("--")).cloned();
+                options.push((arg.clone(), value));
+            } else {
+                positional.push_back(arg.clone());
+            }
+        }
+        Self { positional, options }
+    }
+
+    fn positional(&mut self, name: &str) -> anyhow::Result<String> {
+        self.positional
+            .pop_front()
+            .ok_or_else(|| anyhow!("missing <{}>\n\n{}", name, USAGE))
+    }
+
+    fn option(&mut self, name: &str) -> Option<String> {
+        let index = self.options.iter().position(|(option, _)| option == name)?;
+        self.options.remove(index).1
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        match self.options.iter().position(|(option, _)| option == name) {
+            Some(index) => {
+                self.options.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn finish(self) -> anyhow::Result<()> {
+        if let Some(arg) = self.positional.front() {
+            bail!("unexpected argument `{}`", arg);
+        }
+        if let Some((option, _)) = self.options.first() {
+            bail!("unknown option `{}`", option);
+        }
+        Ok(())
+    }
+}
@@ -1,52 +1,249 @@
+use chrono::{DateTime, Duration, Utc};
 use serde::Deserialize;
 use std::env;
 
+use crate::services::auth::JwtKeyCache;
+
+/// `JWT_SECRET` when none is configured; production refuses to start with it
+pub const DEFAULT_JWT_SECRET: &str = "default-secret-change-this";
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     pub database: DatabaseConfig,
     pub server: ServerConfig,
     pub jwt: JwtConfig,
+    pub oauth: OAuthConfig,
     pub cors: CorsConfig,
     pub upload: UploadConfig,
+    pub storage: StorageConfig,
     pub ai: AiConfig,
+    pub security: SecurityConfig,
+    pub logging: LoggingConfig,
+    pub rate_limit: RateLimitingConfig,
+    pub lockout: LockoutConfig,
+    pub account: AccountConfig,
+    pub grading: GradingConfig,
+    pub study: StudyConfig,
+    pub email: EmailConfig,
+    pub inbound_email: InboundEmailConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct DatabaseConfig {
     pub url: String,
     pub max_connections: u32,
+    /// How long a request waits for a free pool connection before failing
+    pub acquire_timeout_seconds: u64,
+    /// Connection attempts at startup before giving up, so the server can
+    /// start before Postgres is accepting connections
+    pub connect_attempts: u32,
+    /// Wait after the first failed attempt, doubled after each later one
+    pub connect_backoff_ms: u64,
+    pub connect_max_backoff_ms: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    pub environment: String, // `production` turns on startup checks
+    /// Refuse to start when migrations fail; unset means only in production
+    pub strict_migrations: Option<bool>,
 }
 
+/// Access token signing. HS256 signs with the shared `secret`; RS256 and
+/// EdDSA sign with a PEM private key and verify with its public key, so
+/// other services can check tokens without being able to issue them.
 #[derive(Debug, Clone, Deserialize)]
 pub struct JwtConfig {
+    pub algorithm: String, // HS256, RS256 or EdDSA
     pub secret: String,
+    pub private_key: Option<String>, // PEM, read from JWT_PRIVATE_KEY_PATH
+    pub public_key: Option<String>,  // PEM, read from JWT_PUBLIC_KEY_PATH
+    pub key_id: Option<String>,      // `kid` header; defaults to a fingerprint of the key
     pub expiration: i64,
+    pub previous: Option<PreviousJwtKey>,
+    /// Filled from the fields above on first use
+    #[serde(skip)]
+    pub(crate) keys: JwtKeyCache,
+}
+
+/// A retired key whose tokens are still accepted until `valid_until`, so
+/// rotating keys does not sign everyone out
+#[derive(Debug, Clone, Deserialize)]
+pub struct PreviousJwtKey {
+    pub algorithm: String,
+    pub secret: Option<String>,     // HS256
+    pub public_key: Option<String>, // RS256 and EdDSA
+    pub key_id: Option<String>,
+    pub valid_until: DateTime<Utc>,
+}
+
+/// Social sign-in; a provider is offered when its client id is set
+#[derive(Debug, Clone, Deserialize)]
+pub struct OAuthConfig {
+    pub google: Option<OAuthClientConfig>,
+    pub github: Option<OAuthClientConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OAuthClientConfig {
+    pub client_id: String,
+    pub client_secret: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct CorsConfig {
-    pub origin: String,
+    pub origin: String, // Comma-separated list of allowed origins
+    pub max_age_seconds: u64,
+}
+
+impl CorsConfig {
+    pub fn origins(&self) -> Vec<String> {
+        self.origin
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityConfig {
+    pub hsts_max_age_seconds: u64, // 0 disables Strict-Transport-Security
+    pub hsts_include_subdomains: bool,
+    pub referrer_policy: String,
+    pub embed_frame_ancestors: String, // frame-ancestors sources for embeddable routes
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoggingConfig {
+    pub format: String, // `pretty` for people, `json` for log collectors
+    /// Share of successful requests whose completion is logged, 0.0 to 1.0.
+    /// Server errors and slow requests are always logged.
+    pub request_sample_rate: f64,
+    pub slow_request_ms: u64,
+}
+
+impl LoggingConfig {
+    pub fn is_json(&self) -> bool {
+        self.format.eq_ignore_ascii_case("json")
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitingConfig {
+    pub enabled: bool,
+    pub api_requests_per_minute: u32, // Per user, or per client IP when unauthenticated
+    pub auth_attempts: u32, // Login/registration attempts per client IP per 15 minutes
+    pub public_requests_per_minute: u32, // Per client IP on unauthenticated public endpoints
+    pub api_key_requests_per_minute: u32, // Per API key, unless the key sets its own limit
+    pub trust_forwarded_for: bool, // Only enable behind a reverse proxy that sets X-Forwarded-For
+    pub redis_url: Option<String>, // Share limits across replicas; in-memory when unset
+}
+
+/// Per-account login lockout. After `threshold` consecutive failures the
+/// account is locked for `base_seconds`, doubling with each further failure
+/// up to `max_seconds`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LockoutConfig {
+    pub enabled: bool,
+    pub threshold: u32,
+    pub base_seconds: u64,
+    pub max_seconds: u64,
+}
+
+/// Account policy
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountConfig {
+    /// Refuse deck/card creation and AI generation until the account's email
+    /// address is confirmed
+    pub require_email_verification: bool,
+}
+
+/// Typed-answer grading
+#[derive(Debug, Clone, Deserialize)]
+pub struct GradingConfig {
+    /// Minimum similarity (0-1, 1 - edit distance / length) for a typed
+    /// answer to count as correct
+    pub similarity_threshold: f64,
+}
+
+/// Study session housekeeping
+#[derive(Debug, Clone, Deserialize)]
+pub struct StudyConfig {
+    /// Unfinished sessions idle this long are marked abandoned; 0 disables
+    pub abandon_after_hours: u64,
+    /// How long a built study queue is reused; 0 disables the cache
+    pub queue_cache_ttl_seconds: u64,
+    pub queue_cache_max_entries: usize,
+}
+
+/// Email-in card creation; disabled unless both values are set
+/// Outgoing email. Without an SMTP host, messages are only logged.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub from_address: String, // e.g. "DeckOracle <no-reply@example.com>"
+    pub app_url: String, // Frontend base URL used in links
+    pub api_url: String, // This server's public base URL, for download links
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InboundEmailConfig {
+    pub domain: Option<String>, // Receiving domain routed to the inbound webhook
+    pub signing_key: Option<String>, // Mailgun webhook signing key
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct UploadConfig {
     pub max_file_size: usize,
     pub allowed_file_types: Vec<String>,
+    pub allowed_media_types: Vec<String>, // Extensions accepted as card attachments
+    /// Uploaded files larger than this are spooled to `temp_dir` instead of memory
+    pub spool_threshold: usize,
+    pub temp_dir: String,
+    /// Body limit of routes that take JSON or forms; file uploads are capped
+    /// at `max_file_size` instead
+    pub max_body_size: usize,
+    /// Body limit of the inbound email webhook, whose posts carry attachments
+    pub max_inbound_body_size: usize,
+}
+
+/// Where card media is stored
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageConfig {
+    pub backend: String, // local, s3
+    pub local_path: String,
+    pub s3: Option<S3Config>, // Required when backend is s3
+    pub signed_url_ttl_seconds: u64,
+    pub signing_key: String, // Signs download URLs served by this server
+}
+
+/// Any S3-compatible object store (AWS, MinIO, R2), addressed path-style
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct AiConfig {
     pub enabled: bool,
     pub collect_analytics: bool,
+    pub provider: String, // vertex_ai, openai, ollama; requests may pick another configured one
     pub vertex_ai: VertexAiConfig,
+    pub openai: Option<OpenAiConfig>, // Set when OPENAI_BASE_URL or OPENAI_API_KEY is
+    pub ollama: Option<OllamaConfig>, // Set when OLLAMA_URL is
     pub content_generation: ContentGenerationConfig,
     pub recommendations: RecommendationConfig,
+    pub usage: AiUsageConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -58,6 +255,32 @@ pub struct VertexAiConfig {
     pub max_tokens: i32,
     pub temperature: f32,
     pub timeout_seconds: u64,
+    /// Retries of a failed call before giving up; 0 disables retrying
+    pub max_retries: u32,
+    /// First retry delay, doubled on each further retry (with jitter)
+    pub retry_base_delay_ms: u64,
+    /// Consecutive failures that stop calls for `circuit_breaker_cooldown_seconds`; 0 disables
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_cooldown_seconds: u64,
+    pub cost_per_1k_tokens: f64, // USD, for usage reporting
+}
+
+/// Any server speaking the OpenAI chat completions API (OpenAI, vLLM,
+/// llama.cpp, LM Studio)
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenAiConfig {
+    pub base_url: String, // Up to and including the version, e.g. `https://api.openai.com/v1`
+    pub api_key: Option<String>,
+    pub model: String,
+    pub timeout_seconds: u64,
+    pub cost_per_1k_tokens: f64, // USD, for usage reporting
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaConfig {
+    pub base_url: String, // e.g. `http://localhost:11434`
+    pub model: String,
+    pub timeout_seconds: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -65,7 +288,20 @@ pub struct ContentGenerationConfig {
     pub max_cards_per_batch: i32,
     pub min_confidence_score: f32,
     pub supported_formats: Vec<String>,
+    /// Answer with the local model when Vertex AI fails or its circuit is open
     pub use_local_fallback: bool,
+    /// Base URL of an OpenAI-compatible server (Ollama, llama.cpp, vLLM),
+    /// e.g. `http://localhost:11434/v1`
+    pub local_model_url: Option<String>,
+    pub local_model: String,
+}
+
+/// Per-user token quotas over all providers, in UTC days and calendar
+/// months; 0 means unlimited
+#[derive(Debug, Clone, Deserialize)]
+pub struct AiUsageConfig {
+    pub daily_token_limit: i64,
+    pub monthly_token_limit: i64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -79,6 +315,11 @@ impl Config {
     pub fn from_env() -> Result<Self, env::VarError> {
         dotenvy::dotenv().ok();
 
+        let expiration = env::var("JWT_EXPIRATION")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse()
+            .unwrap_or(86400);
+
         Ok(Config {
             database: DatabaseConfig {
                 url: env::var("DATABASE_URL")?,
@@ -86,6 +327,23 @@ impl Config {
                     .unwrap_or_else(|_| "10".to_string())
                     .parse()
                     .unwrap_or(10),
+                acquire_timeout_seconds: env::var("DATABASE_ACQUIRE_TIMEOUT")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .unwrap_or(5),
+                connect_attempts: env::var("DATABASE_CONNECT_ATTEMPTS")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse::<u32>()
+                    .unwrap_or(10)
+                    .max(1),
+                connect_backoff_ms: env::var("DATABASE_CONNECT_BACKOFF_MS")
+                    .unwrap_or_else(|_| "500".to_string())
+                    .parse()
+                    .unwrap_or(500),
+                connect_max_backoff_ms: env::var("DATABASE_CONNECT_MAX_BACKOFF_MS")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()
+                    .unwrap_or(10000),
             },
             server: ServerConfig {
                 host: env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
@@ -93,16 +351,49 @@ impl Config {
                     .unwrap_or_else(|_| "8080".to_string())
                     .parse()
                     .unwrap_or(8080),
+                environment: env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string()),
+                strict_migrations: env::var("MIGRATIONS_STRICT").ok().and_then(|value| value.parse().ok()),
             },
             jwt: JwtConfig {
-                secret: env::var("JWT_SECRET").unwrap_or_else(|_| "default-secret-change-this".to_string()),
-                expiration: env::var("JWT_EXPIRATION")
-                    .unwrap_or_else(|_| "86400".to_string())
-                    .parse()
-                    .unwrap_or(86400),
+                algorithm: env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string()),
+                secret: env::var("JWT_SECRET").unwrap_or_else(|_| DEFAULT_JWT_SECRET.to_string()),
+                private_key: read_key_file("JWT_PRIVATE_KEY_PATH"),
+                public_key: read_key_file("JWT_PUBLIC_KEY_PATH"),
+                key_id: env::var("JWT_KEY_ID").ok().filter(|kid| !kid.is_empty()),
+                expiration,
+                previous: match (
+                    env::var("JWT_PREVIOUS_SECRET").ok().filter(|secret| !secret.is_empty()),
+                    read_key_file("JWT_PREVIOUS_PUBLIC_KEY_PATH"),
+                ) {
+                    (None, None) => None,
+                    (secret, public_key) => Some(PreviousJwtKey {
+                        algorithm: env::var("JWT_PREVIOUS_ALGORITHM")
+                            .or_else(|_| env::var("JWT_ALGORITHM"))
+                            .unwrap_or_else(|_| "HS256".to_string()),
+                        secret,
+                        public_key,
+                        key_id: env::var("JWT_PREVIOUS_KEY_ID").ok().filter(|kid| !kid.is_empty()),
+                        // By default, for as long as a token issued just before the
+                        // restart can live
+                        valid_until: env::var("JWT_PREVIOUS_VALID_UNTIL")
+                            .ok()
+                            .and_then(|until| DateTime::parse_from_rfc3339(&until).ok())
+                            .map(|until| until.with_timezone(&Utc))
+                            .unwrap_or_else(|| Utc::now() + Duration::seconds(expiration)),
+                    }),
+                },
+                keys: JwtKeyCache::default(),
+            },
+            oauth: OAuthConfig {
+                google: oauth_client("OAUTH_GOOGLE"),
+                github: oauth_client("OAUTH_GITHUB"),
             },
             cors: CorsConfig {
                 origin: env::var("CORS_ORIGIN").unwrap_or_else(|_| "http://localhost:5173".to_string()),
+                max_age_seconds: env::var("CORS_MAX_AGE")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
             },
             upload: UploadConfig {
                 max_file_size: env::var("MAX_FILE_SIZE")
@@ -110,10 +401,56 @@ impl Config {
                     .parse()
                     .unwrap_or(10485760),
                 allowed_file_types: env::var("ALLOWED_FILE_TYPES")
-                    .unwrap_or_else(|_| "csv,txt,pdf,docx,doc".to_string())
+                    .unwrap_or_else(|_| "csv,txt,tsv,md,json,xml,pdf,docx,doc".to_string())
                     .split(',')
-                    .map(|s| s.trim().to_string())
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
                     .collect(),
+                allowed_media_types: env::var("ALLOWED_MEDIA_TYPES")
+                    .unwrap_or_else(|_| "png,jpg,jpeg,gif,webp,mp3,ogg,wav,m4a".to_string())
+                    .split(',')
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect(),
+                spool_threshold: env::var("UPLOAD_SPOOL_THRESHOLD")
+                    .unwrap_or_else(|_| "1048576".to_string())
+                    .parse()
+                    .unwrap_or(1048576),
+                temp_dir: env::var("UPLOAD_TEMP_DIR")
+                    .ok()
+                    .filter(|dir| !dir.is_empty())
+                    .unwrap_or_else(|| env::temp_dir().to_string_lossy().into_owned()),
+                max_body_size: env::var("MAX_BODY_SIZE")
+                    .unwrap_or_else(|_| "2097152".to_string())
+                    .parse()
+                    .unwrap_or(2097152),
+                max_inbound_body_size: env::var("MAX_INBOUND_BODY_SIZE")
+                    .unwrap_or_else(|_| "26214400".to_string())
+                    .parse()
+                    .unwrap_or(26214400),
+            },
+            storage: StorageConfig {
+                backend: env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string()),
+                local_path: env::var("STORAGE_LOCAL_PATH").unwrap_or_else(|_| "./data/media".to_string()),
+                s3: match env::var("STORAGE_S3_BUCKET").ok().filter(|b| !b.is_empty()) {
+                    Some(bucket) => Some(S3Config {
+                        endpoint: env::var("STORAGE_S3_ENDPOINT")
+                            .unwrap_or_else(|_| "https://s3.amazonaws.com".to_string()),
+                        bucket,
+                        region: env::var("STORAGE_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                        access_key_id: env::var("STORAGE_S3_ACCESS_KEY_ID").unwrap_or_default(),
+                        secret_access_key: env::var("STORAGE_S3_SECRET_ACCESS_KEY").unwrap_or_default(),
+                    }),
+                    None => None,
+                },
+                signed_url_ttl_seconds: env::var("STORAGE_SIGNED_URL_TTL")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+                // Falls back to the JWT secret so local setups work without extra keys
+                signing_key: env::var("STORAGE_SIGNING_KEY")
+                    .or_else(|_| env::var("JWT_SECRET"))
+                    .unwrap_or_else(|_| DEFAULT_JWT_SECRET.to_string()),
             },
             ai: AiConfig {
                 enabled: env::var("AI_ENABLED")
@@ -124,6 +461,7 @@ impl Config {
                     .unwrap_or_else(|_| "true".to_string())
                     .parse()
                     .unwrap_or(true),
+                provider: env::var("AI_PROVIDER").unwrap_or_else(|_| "vertex_ai".to_string()),
                 vertex_ai: VertexAiConfig {
                     project_id: env::var("VERTEX_AI_PROJECT_ID")
                         .unwrap_or_else(|_| String::new()),
@@ -144,7 +482,58 @@ impl Config {
                         .unwrap_or_else(|_| "30".to_string())
                         .parse()
                         .unwrap_or(30),
+                    max_retries: env::var("VERTEX_AI_MAX_RETRIES")
+                        .unwrap_or_else(|_| "3".to_string())
+                        .parse()
+                        .unwrap_or(3),
+                    retry_base_delay_ms: env::var("VERTEX_AI_RETRY_BASE_DELAY_MS")
+                        .unwrap_or_else(|_| "500".to_string())
+                        .parse()
+                        .unwrap_or(500),
+                    circuit_breaker_threshold: env::var("VERTEX_AI_CIRCUIT_BREAKER_THRESHOLD")
+                        .unwrap_or_else(|_| "5".to_string())
+                        .parse()
+                        .unwrap_or(5),
+                    circuit_breaker_cooldown_seconds: env::var("VERTEX_AI_CIRCUIT_BREAKER_COOLDOWN")
+                        .unwrap_or_else(|_| "60".to_string())
+                        .parse()
+                        .unwrap_or(60),
+                    cost_per_1k_tokens: env::var("VERTEX_AI_COST_PER_1K_TOKENS")
+                        .unwrap_or_else(|_| "0".to_string())
+                        .parse()
+                        .unwrap_or(0.0),
+                },
+                openai: match (
+                    env::var("OPENAI_BASE_URL").ok().filter(|url| !url.is_empty()),
+                    env::var("OPENAI_API_KEY").ok().filter(|key| !key.is_empty()),
+                ) {
+                    (None, None) => None,
+                    (base_url, api_key) => Some(OpenAiConfig {
+                        base_url: base_url.unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
+                        api_key,
+                        model: env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string()),
+                        timeout_seconds: env::var("OPENAI_TIMEOUT")
+                            .unwrap_or_else(|_| "60".to_string())
+                            .parse()
+                            .unwrap_or(60),
+                        cost_per_1k_tokens: env::var("OPENAI_COST_PER_1K_TOKENS")
+                            .unwrap_or_else(|_| "0".to_string())
+                            .parse()
+                            .unwrap_or(0.0),
+                    }),
                 },
+                ollama: env::var("OLLAMA_URL")
+                    .ok()
+                    .filter(|url| !url.is_empty())
+                    .map(|base_url| OllamaConfig {
+                        base_url,
+                        model: env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3.1".to_string()),
+                        // Local models on modest hardware can take a while
+                        timeout_seconds: env::var("OLLAMA_TIMEOUT")
+                            .unwrap_or_else(|_| "120".to_string())
+                            .parse()
+                            .unwrap_or(120),
+                    }),
                 content_generation: ContentGenerationConfig {
                     max_cards_per_batch: env::var("AI_MAX_CARDS_PER_BATCH")
                         .unwrap_or_else(|_| "50".to_string())
@@ -163,6 +552,8 @@ impl Config {
                         .unwrap_or_else(|_| "false".to_string())
                         .parse()
                         .unwrap_or(false),
+                    local_model_url: env::var("AI_LOCAL_MODEL_URL").ok().filter(|url| !url.is_empty()),
+                    local_model: env::var("AI_LOCAL_MODEL").unwrap_or_else(|_| "llama3.1".to_string()),
                 },
                 recommendations: RecommendationConfig {
                     min_events_for_recommendations: env::var("AI_MIN_EVENTS")
@@ -178,6 +569,131 @@ impl Config {
                         .parse()
                         .unwrap_or(10),
                 },
+                usage: AiUsageConfig {
+                    daily_token_limit: env::var("AI_DAILY_TOKEN_LIMIT")
+                        .unwrap_or_else(|_| "0".to_string())
+                        .parse()
+                        .unwrap_or(0),
+                    monthly_token_limit: env::var("AI_MONTHLY_TOKEN_LIMIT")
+                        .unwrap_or_else(|_| "0".to_string())
+                        .parse()
+                        .unwrap_or(0),
+                },
+            },
+            security: SecurityConfig {
+                hsts_max_age_seconds: env::var("SECURITY_HSTS_MAX_AGE")
+                    .unwrap_or_else(|_| "31536000".to_string())
+                    .parse()
+                    .unwrap_or(31536000),
+                hsts_include_subdomains: env::var("SECURITY_HSTS_INCLUDE_SUBDOMAINS")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()
+                    .unwrap_or(true),
+                referrer_policy: env::var("SECURITY_REFERRER_POLICY")
+                    .unwrap_or_else(|_| "strict-origin-when-cross-origin".to_string()),
+                embed_frame_ancestors: env::var("SECURITY_EMBED_FRAME_ANCESTORS")
+                    .unwrap_or_else(|_| "*".to_string()),
+            },
+            logging: LoggingConfig {
+                format: env::var("LOG_FORMAT").unwrap_or_else(|_| "pretty".to_string()),
+                request_sample_rate: env::var("LOG_REQUEST_SAMPLE_RATE")
+                    .unwrap_or_else(|_| "1.0".to_string())
+                    .parse::<f64>()
+                    .unwrap_or(1.0)
+                    .clamp(0.0, 1.0),
+                slow_request_ms: env::var("LOG_SLOW_REQUEST_MS")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()
+                    .unwrap_or(1000),
+            },
+            rate_limit: RateLimitingConfig {
+                enabled: env::var("RATE_LIMIT_ENABLED")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()
+                    .unwrap_or(true),
+                api_requests_per_minute: env::var("RATE_LIMIT_API_PER_MINUTE")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()
+                    .unwrap_or(1000),
+                auth_attempts: env::var("RATE_LIMIT_AUTH_ATTEMPTS")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .unwrap_or(5),
+                public_requests_per_minute: env::var("RATE_LIMIT_PUBLIC_PER_MINUTE")
+                    .unwrap_or_else(|_| "120".to_string())
+                    .parse()
+                    .unwrap_or(120),
+                api_key_requests_per_minute: env::var("RATE_LIMIT_API_KEY_PER_MINUTE")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+                trust_forwarded_for: env::var("RATE_LIMIT_TRUST_FORWARDED_FOR")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
+                redis_url: env::var("RATE_LIMIT_REDIS_URL").ok().filter(|url| !url.is_empty()),
+            },
+            lockout: LockoutConfig {
+                enabled: env::var("LOGIN_LOCKOUT_ENABLED")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()
+                    .unwrap_or(true),
+                threshold: env::var("LOGIN_LOCKOUT_THRESHOLD")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .unwrap_or(5),
+                base_seconds: env::var("LOGIN_LOCKOUT_BASE_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+                max_seconds: env::var("LOGIN_LOCKOUT_MAX_SECONDS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+            },
+            account: AccountConfig {
+                require_email_verification: env::var("REQUIRE_EMAIL_VERIFICATION")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
+            },
+            grading: GradingConfig {
+                similarity_threshold: env::var("GRADING_SIMILARITY_THRESHOLD")
+                    .unwrap_or_else(|_| "0.85".to_string())
+                    .parse::<f64>()
+                    .map(|t| t.clamp(0.0, 1.0))
+                    .unwrap_or(0.85),
+            },
+            study: StudyConfig {
+                abandon_after_hours: env::var("STUDY_SESSION_ABANDON_HOURS")
+                    .unwrap_or_else(|_| "24".to_string())
+                    .parse()
+                    .unwrap_or(24),
+                queue_cache_ttl_seconds: env::var("STUDY_QUEUE_CACHE_TTL_SECONDS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()
+                    .unwrap_or(300),
+                queue_cache_max_entries: env::var("STUDY_QUEUE_CACHE_MAX_ENTRIES")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()
+                    .unwrap_or(10000),
+            },
+            email: EmailConfig {
+                smtp_host: env::var("SMTP_HOST").ok().filter(|h| !h.is_empty()),
+                smtp_port: env::var("SMTP_PORT")
+                    .unwrap_or_else(|_| "587".to_string())
+                    .parse()
+                    .unwrap_or(587),
+                smtp_username: env::var("SMTP_USERNAME").ok().filter(|u| !u.is_empty()),
+                smtp_password: env::var("SMTP_PASSWORD").ok().filter(|p| !p.is_empty()),
+                from_address: env::var("EMAIL_FROM")
+                    .unwrap_or_else(|_| "DeckOracle <no-reply@localhost>".to_string()),
+                app_url: env::var("APP_URL").unwrap_or_else(|_| "http://localhost:5173".to_string()),
+                api_url: env::var("API_URL").unwrap_or_else(|_| "http://localhost:8080".to_string()),
+            },
+            inbound_email: InboundEmailConfig {
+                domain: env::var("INBOUND_EMAIL_DOMAIN").ok().filter(|d| !d.is_empty()),
+                signing_key: env::var("INBOUND_EMAIL_SIGNING_KEY").ok().filter(|k| !k.is_empty()),
             },
         })
     }
@@ -185,4 +701,44 @@ impl Config {
     pub fn get_bind_address(&self) -> String {
         format!("{}:{}", self.server.host, self.server.port)
     }
+
+    pub fn is_production(&self) -> bool {
+        self.server.environment.eq_ignore_ascii_case("production")
+    }
+
+    /// Whether a failed migration stops the server from starting
+    pub fn strict_migrations(&self) -> bool {
+        self.server.strict_migrations.unwrap_or_else(|| self.is_production())
+    }
+
+    /// Settings that are unsafe or unusable, checked before the server starts
+    pub fn validate(&self) -> Result<(), String> {
+        crate::services::auth::AuthService::check_jwt_keys(&self.jwt)?;
+
+        if self.is_production() {
+            if self.jwt.algorithm.eq_ignore_ascii_case("HS256") && self.jwt.secret == DEFAULT_JWT_SECRET {
+                return Err("JWT_SECRET must be set in production".to_string());
+            }
+            if self.storage.signing_key == DEFAULT_JWT_SECRET {
+                return Err("STORAGE_SIGNING_KEY or JWT_SECRET must be set in production".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+// `{prefix}_CLIENT_ID` and `{prefix}_CLIENT_SECRET`, when the id is set
+fn oauth_client(prefix: &str) -> Option<OAuthClientConfig> {
+    let client_id = env::var(format!("{}_CLIENT_ID", prefix)).ok().filter(|id| !id.is_empty())?;
+    Some(OAuthClientConfig {
+        client_id,
+        client_secret: env::var(format!("{}_CLIENT_SECRET", prefix)).unwrap_or_default(),
+    })
+}
+
+// Contents of the file named by `var`; unset or unreadable files are
+// reported by `Config::validate` as missing keys
+fn read_key_file(var: &str) -> Option<String> {
+    let path = env::var(var).ok().filter(|path| !path.is_empty())?;
+    std::fs::read_to_string(path).ok()
 }
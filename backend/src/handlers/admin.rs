@@ -0,0 +1,128 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post, put},
+    Json, Router,
+};
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::AdminUser,
+    models::{
+        admin::{AdminStats, AdminStatsQuery, ConsistencyCheckDto, ConsistencyJobParameters, SeedSummary},
+        job::JobSummary,
+        FeatureDeckDto,
+    },
+    services::{admin::AdminService, job::JobService, marketplace::MarketplaceService, seed::SeedService},
+    state::AppState,
+    utils::{AppError, Result},
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/stats", get(get_stats))
+        .route("/consistency-check", post(start_consistency_check))
+        .route("/decks/:id/featured", put(set_deck_featured))
+        .route("/seed", post(seed_demo_data))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_stats,
+    start_consistency_check,
+    set_deck_featured,
+    seed_demo_data
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "/stats",
+    params(AdminStatsQuery),
+    responses((status = 200, body = AdminStats)),
+    tag = "admin"
+)]
+async fn get_stats(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+    Query(query): Query<AdminStatsQuery>,
+) -> Result<Json<AdminStats>> {
+    let stats = AdminService::instance_stats(&state.db, query.days).await?;
+    Ok(Json(stats))
+}
+
+/// Start a consistency check job; progress and the report are available
+/// through the job center
+#[utoipa::path(
+    post,
+    path = "/consistency-check",
+    request_body(content = Option<ConsistencyCheckDto>),
+    responses((status = 202, description = "Job queued", body = JobSummary)),
+    tag = "admin"
+)]
+async fn start_consistency_check(
+    State(state): State<AppState>,
+    AdminUser(admin_id): AdminUser,
+    dto: Option<Json<ConsistencyCheckDto>>,
+) -> Result<(StatusCode, Json<JobSummary>)> {
+    let Json(dto) = dto.unwrap_or_default();
+    let params = ConsistencyJobParameters {
+        dry_run: dto.dry_run.unwrap_or(false),
+    };
+
+    let job = JobService::enqueue(
+        &state.db,
+        &state.media,
+        &state.queue_cache,
+        admin_id,
+        "consistency_check",
+        serde_json::to_value(&params)?,
+        true,
+    )
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Feature a public deck in the marketplace, or take it off the list
+#[utoipa::path(
+    put,
+    path = "/decks/{id}/featured",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = FeatureDeckDto,
+    responses((status = 204, description = "Featured flag updated")),
+    tag = "admin"
+)]
+async fn set_deck_featured(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<FeatureDeckDto>,
+) -> Result<StatusCode> {
+    MarketplaceService::set_featured(&state.db, id, dto.featured).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Create the demo user with sample decks and several weeks of study
+/// history. Not available in production.
+#[utoipa::path(
+    post,
+    path = "/seed",
+    responses(
+        (status = 201, body = SeedSummary),
+        (status = 400, description = "Demo data already seeded"),
+    ),
+    tag = "admin"
+)]
+async fn seed_demo_data(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+) -> Result<(StatusCode, Json<SeedSummary>)> {
+    if state.config.is_production() {
+        return Err(AppError::NotFound("Seeding is disabled in production".to_string()));
+    }
+
+    let summary = SeedService::seed_demo(&state.db).await?;
+    Ok((StatusCode::CREATED, Json(summary)))
+}
@@ -1,37 +1,106 @@
 use axum::{
-    extract::{Multipart, State},
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
     http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::{get, post},
     Json, Router,
 };
-use serde::{Deserialize, Serialize};
+use futures_util::{stream, Stream};
+use serde::Deserialize;
 use serde_json::json;
+use std::convert::Infallible;
+use utoipa::{OpenApi, ToSchema};
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
-    middleware::auth::UserId,
+    middleware::auth::{OptionalClaims, UserId, VerifiedUser},
+    models::{
+        ai::{
+            AiGeneratedCard, AiPrivacySettings, AiProviderKind, AiRecommendation, AiUsageQuery, AiUsageSummary,
+            ApplyLintSuggestionsDto, DeckLintReport, LintDeckDto, ApproveGeneratedCardsDto, BatchAnalyticsEvent,
+            BatchIngestResult, ExtractedDocument, GeneratedCardsQuery,
+            GenerationEvent, GenerationStreamQuery, LearningInsight, LeechRemediation, LeechRemediationsQuery,
+            GeneratedCardsResult, RecommendationFeedbackDto, RejectGeneratedCardsDto, UpdatePrivacySettingsDto,
+        },
+        Card, DeckRole,
+    },
+    services::{
+        ai_generation::AiGenerationService, ai_privacy::AiPrivacyService, auth::AuthService,
+        card_lint::CardLintService, document::DocumentService, learning_patterns::LearningPatternService,
+        encryption::EncryptionService, leech::LeechService, recommendation::RecommendationService,
+        sharing::SharingService, ai_provider::FlashcardGenerationOptions, ai_usage::AiUsageService, study_events::StudyEventService,
+        upload, webhook::WebhookService,
+    },
     state::AppState,
-    utils::Result,
+    utils::{AppError, Result},
 };
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/generate-cards", post(generate_cards))
-        .route("/generate-deck", post(generate_deck))
+        .route("/generate-cards/stream", get(stream_generated_cards))
+        .route("/generated-cards", get(list_generated_cards))
+        .route("/generated-cards/approve", post(approve_generated_cards))
+        .route("/generated-cards/reject", post(reject_generated_cards))
+        // Uploads are capped by MAX_FILE_SIZE while they stream in
+        .route("/generate-deck", post(generate_deck.layer(DefaultBodyLimit::disable())))
+        .route("/extract", post(extract_document.layer(DefaultBodyLimit::disable())))
+        .route("/lint-deck/:id", post(lint_deck))
+        .route("/lint-deck/:id/apply", post(apply_lint_suggestions))
         .route("/privacy-settings", get(get_privacy_settings).patch(update_privacy_settings))
+        .route("/usage", get(get_usage))
         .route("/recommendations", get(get_recommendations))
+        .route("/recommendations/feedback", post(recommendation_feedback))
+        .route("/insights", get(get_insights))
+        .route("/events/batch", post(ingest_event_batch))
+        .route("/leech-remediations", get(list_leech_remediations))
+        .route("/leech-remediations/:id", get(get_leech_remediation))
+        .route("/leech-remediations/:id/accept", post(accept_leech_remediation))
+        .route("/leech-remediations/:id/reject", post(reject_leech_remediation))
 }
 
-#[derive(Deserialize)]
+#[derive(OpenApi)]
+#[openapi(paths(
+    generate_cards,
+    stream_generated_cards,
+    list_generated_cards,
+    approve_generated_cards,
+    reject_generated_cards,
+    get_privacy_settings,
+    update_privacy_settings,
+    get_usage,
+    get_recommendations,
+    recommendation_feedback,
+    get_insights,
+    ingest_event_batch,
+    generate_deck,
+    extract_document,
+    lint_deck,
+    apply_lint_suggestions,
+    list_leech_remediations,
+    get_leech_remediation,
+    accept_leech_remediation,
+    reject_leech_remediation
+))]
+pub struct ApiDoc;
+
+#[derive(Deserialize, ToSchema)]
 struct GenerateCardsRequest {
     deck_id: Option<Uuid>,
     content_type: String, // "text" or "file"
     content: Option<String>, // For text input
     file: Option<String>, // For file upload (filename)
     options: GenerationOptions,
+    #[serde(default)]
+    stream: bool, // Queue the job and stream its cards from /ai/generate-cards/stream
+    provider: Option<AiProviderKind>, // Defaults to the server's configured provider
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct GenerationOptions {
     #[serde(rename = "maxCards")]
     max_cards: Option<i32>,
@@ -42,127 +111,342 @@ struct GenerationOptions {
     card_format: Option<String>,
 }
 
-#[derive(Serialize)]
-struct GeneratedCard {
-    front: String,
-    back: String,
-    explanation: Option<String>,
-    difficulty: Option<i32>,
+/// Multipart form of `generate_deck`; documentation only
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct GenerateDeckForm {
+    topic: Option<String>,
+    difficulty: Option<String>,
+    card_count: Option<i32>,
+    /// PDF, DOCX or text file to build the deck from
+    #[schema(value_type = Option<String>, format = Binary)]
+    file: Option<Vec<u8>>,
+}
+
+/// Multipart form of `extract_document`; documentation only
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct DocumentUploadForm {
+    /// PDF, DOCX or text file
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
 }
 
-/// Generate flashcards from content using AI
-/// This is a stub implementation that returns mock data
+/// Generate flashcards from text with Vertex AI. The cards are stored with
+/// confidence scores under a generation job; pass the returned `job_id` to
+/// the approve endpoint to move them into a deck. With `stream` set, the job
+/// is only queued and its cards are delivered by `stream_generated_cards`.
+#[utoipa::path(
+    post,
+    path = "/generate-cards",
+    request_body = GenerateCardsRequest,
+    responses(
+        (status = 200, description = "Generated cards, pending approval", body = GeneratedCardsResult),
+        (status = 202, description = "Streaming job queued (`stream: true`)", body = serde_json::Value,
+            example = json!({ "job_id": "3f0a9c2e-5b1d-4e7a-9c1f-2d8b6e4a7c10", "stream_url": "/api/v1/ai/generate-cards/stream?job_id=3f0a9c2e-5b1d-4e7a-9c1f-2d8b6e4a7c10" })),
+    ),
+    tag = "ai"
+)]
 async fn generate_cards(
     State(state): State<AppState>,
-    UserId(user_id): UserId,
+    VerifiedUser(user_id): VerifiedUser,
     Json(request): Json<GenerateCardsRequest>,
-) -> Result<Json<serde_json::Value>> {
-    // Check if AI is enabled
+) -> Result<Response> {
     if !state.config.ai.enabled {
-        return Ok(Json(json!({
-            "error": "AI features are not enabled",
-            "cards": []
-        })));
+        return Err(AppError::BadRequest("AI features are not enabled".to_string()));
     }
+    AiPrivacyService::require_content_generation(&state.db, user_id).await?;
 
-    // For now, return mock data
-    // In production, this would call the Vertex AI service
-    let mock_cards = vec![
-        GeneratedCard {
-            front: "What is the primary purpose of the Rust ownership system?".to_string(),
-            back: "To ensure memory safety without needing a garbage collector by enforcing strict rules about how memory is accessed and managed at compile time.".to_string(),
-            explanation: Some("The ownership system prevents data races, null pointer dereferences, and use-after-free errors.".to_string()),
-            difficulty: Some(3),
-        },
-        GeneratedCard {
-            front: "What are the three rules of ownership in Rust?".to_string(),
-            back: "1. Each value has a single owner\n2. When the owner goes out of scope, the value is dropped\n3. There can only be one mutable reference OR multiple immutable references at a time".to_string(),
-            explanation: Some("These rules are enforced at compile time by the borrow checker.".to_string()),
-            difficulty: Some(4),
-        },
-        GeneratedCard {
-            front: "What is a lifetime in Rust?".to_string(),
-            back: "A lifetime is a construct the compiler uses to ensure all borrows are valid for the duration they are used.".to_string(),
-            explanation: request.options.include_explanations.unwrap_or(false).then(|| 
-                "Lifetimes prevent dangling references by ensuring references don't outlive the data they refer to.".to_string()
-            ),
-            difficulty: Some(5),
-        },
-    ];
+    // Card content of encrypted decks must never reach the AI provider
+    if let Some(deck_id) = request.deck_id {
+        SharingService::require_deck_role(&state.db, deck_id, user_id, DeckRole::Editor).await?;
+        EncryptionService::ensure_plaintext(&state.db, deck_id).await?;
+    }
 
-    // Limit to requested number of cards
-    let max_cards = request.options.max_cards.unwrap_or(10) as usize;
-    let cards: Vec<GeneratedCard> = mock_cards.into_iter().take(max_cards).collect();
+    if request.content_type != "text" {
+        return Err(AppError::BadRequest(
+            "Only text content is supported here; upload files to /ai/generate-deck".to_string(),
+        ));
+    }
+    let content = request
+        .content
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .ok_or(AppError::ValidationError("content is required".to_string()))?;
 
-    Ok(Json(json!({
-        "success": true,
-        "cards": cards,
-        "job_id": Uuid::new_v4(),
-        "message": "Cards generated successfully (mock data)",
-        "provider": "mock",
-        "model": "demo-v1"
-    })))
+    let options = FlashcardGenerationOptions {
+        max_cards: request.options.max_cards,
+        difficulty: request.options.difficulty,
+        format: request.options.card_format,
+        include_explanations: request.options.include_explanations,
+        languages: Default::default(),
+    };
+
+    if request.stream {
+        let job_id = AiGenerationService::create_stream_job(
+            &state.db,
+            &state.config.ai,
+            user_id,
+            request.deck_id,
+            content,
+            options,
+            request.provider,
+        )
+        .await?;
+
+        let stream_url = format!("/api/v1/ai/generate-cards/stream?job_id={}", job_id);
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(json!({ "job_id": job_id, "stream_url": stream_url })),
+        )
+            .into_response());
+    }
+
+    let result = AiGenerationService::generate_cards(
+        &state.db,
+        &state.config.ai,
+        &state.ai_breaker,
+        user_id,
+        request.deck_id,
+        content,
+        options,
+        request.provider,
+    )
+    .await?;
+
+    Ok(Json(result).into_response())
+}
+
+/// Run a queued streaming job and send its cards as server-sent events:
+/// `card` for each stored card, then one of `done`, `cancelled` or `error`.
+/// Accepts the JWT as the `token` query parameter for EventSource clients.
+#[utoipa::path(
+    get,
+    path = "/generate-cards/stream",
+    params(GenerationStreamQuery),
+    responses((
+        status = 200,
+        description = "Server-sent events: `card`, then `done`, `cancelled` or `error`",
+        content_type = "text/event-stream",
+        body = String
+    )),
+    tag = "ai"
+)]
+async fn stream_generated_cards(
+    State(state): State<AppState>,
+    OptionalClaims(claims): OptionalClaims,
+    Query(query): Query<GenerationStreamQuery>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    let user_id = match (claims, query.token.as_deref()) {
+        (Some(claims), _) => claims.sub,
+        (None, Some(token)) => AuthService::validate_jwt(token, &state.config)?.sub,
+        (None, None) => return Err(AppError::Unauthorized),
+    };
+
+    let receiver =
+        AiGenerationService::stream_cards(&state.db, &state.config.ai, &state.ai_breaker, user_id, query.job_id).await?;
+
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        Some((Ok(sse_event(&event)), receiver))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// List the cards produced by a generation job
+#[utoipa::path(
+    get,
+    path = "/generated-cards",
+    params(GeneratedCardsQuery),
+    responses((status = 200, body = Vec<AiGeneratedCard>)),
+    tag = "ai"
+)]
+async fn list_generated_cards(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<GeneratedCardsQuery>,
+) -> Result<Json<Vec<AiGeneratedCard>>> {
+    let cards = AiGenerationService::list_generated_cards(&state.db, user_id, &query).await?;
+    Ok(Json(cards))
+}
+
+/// Copy generated cards into a deck
+#[utoipa::path(
+    post,
+    path = "/generated-cards/approve",
+    request_body = ApproveGeneratedCardsDto,
+    responses((status = 201, description = "Cards added to the deck", body = Vec<Card>)),
+    tag = "ai"
+)]
+async fn approve_generated_cards(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Json(dto): Json<ApproveGeneratedCardsDto>,
+) -> Result<(StatusCode, Json<Vec<Card>>)> {
+    dto.validate()?;
+
+    let cards = AiGenerationService::approve_cards(&state.db, user_id, dto).await?;
+    for card in &cards {
+        WebhookService::dispatch(state.db.clone(), card.deck_id, "card.created", json!(card));
+    }
+    Ok((StatusCode::CREATED, Json(cards)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/generated-cards/reject",
+    request_body = RejectGeneratedCardsDto,
+    responses((status = 200, body = serde_json::Value, example = json!({ "rejected": 3 }))),
+    tag = "ai"
+)]
+async fn reject_generated_cards(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<RejectGeneratedCardsDto>,
+) -> Result<Json<serde_json::Value>> {
+    dto.validate()?;
+
+    let rejected = AiGenerationService::reject_cards(&state.db, user_id, dto).await?;
+    Ok(Json(json!({ "rejected": rejected })))
 }
 
 /// Get user's AI privacy settings
+#[utoipa::path(
+    get,
+    path = "/privacy-settings",
+    responses((status = 200, body = AiPrivacySettings)),
+    tag = "ai"
+)]
 async fn get_privacy_settings(
     State(state): State<AppState>,
     UserId(user_id): UserId,
-) -> Result<Json<serde_json::Value>> {
-    // Return default privacy settings
-    Ok(Json(json!({
-        "user_id": user_id,
-        "track_analytics": true,
-        "enable_ai_recommendations": true,
-        "enable_content_generation": true,
-        "share_anonymous_data": false,
-        "personalized_learning": true
-    })))
+) -> Result<Json<AiPrivacySettings>> {
+    let settings = AiPrivacyService::get(&state.db, user_id).await?;
+    Ok(Json(settings))
 }
 
 /// Update user's AI privacy settings
+#[utoipa::path(
+    patch,
+    path = "/privacy-settings",
+    request_body = UpdatePrivacySettingsDto,
+    responses((status = 200, body = AiPrivacySettings)),
+    tag = "ai"
+)]
 async fn update_privacy_settings(
     State(state): State<AppState>,
     UserId(user_id): UserId,
-    Json(settings): Json<serde_json::Value>,
-) -> Result<StatusCode> {
-    // In production, save to database
-    Ok(StatusCode::OK)
+    Json(dto): Json<UpdatePrivacySettingsDto>,
+) -> Result<Json<AiPrivacySettings>> {
+    let settings = AiPrivacyService::update(&state.db, user_id, dto).await?;
+    Ok(Json(settings))
 }
 
-/// Get AI-powered recommendations for the user
+/// Tokens and estimated cost of the user's AI requests, with their quotas
+#[utoipa::path(
+    get,
+    path = "/usage",
+    params(AiUsageQuery),
+    responses((status = 200, body = AiUsageSummary)),
+    tag = "ai"
+)]
+async fn get_usage(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<AiUsageQuery>,
+) -> Result<Json<AiUsageSummary>> {
+    let usage = AiUsageService::get(&state.db, &state.config.ai, user_id, &query).await?;
+    Ok(Json(usage))
+}
+
+/// Store study events recorded by a client while offline
+#[utoipa::path(
+    post,
+    path = "/events/batch",
+    request_body = BatchAnalyticsEvent,
+    responses((status = 200, body = BatchIngestResult)),
+    tag = "ai"
+)]
+async fn ingest_event_batch(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(batch): Json<BatchAnalyticsEvent>,
+) -> Result<Json<BatchIngestResult>> {
+    batch.validate()?;
+
+    let result = StudyEventService::ingest_batch(&state.db, user_id, batch).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    Ok(Json(result))
+}
+
+/// Study recommendations drawn from the user's study history: their best
+/// time of day, decks with overdue reviews and weak tags. Regenerated every
+/// few hours; empty while recommendations are off in the privacy settings.
+#[utoipa::path(
+    get,
+    path = "/recommendations",
+    responses((status = 200, body = Vec<AiRecommendation>)),
+    tag = "ai"
+)]
 async fn get_recommendations(
     State(state): State<AppState>,
     UserId(user_id): UserId,
-) -> Result<Json<serde_json::Value>> {
-    // Return mock recommendations
-    Ok(Json(json!({
-        "recommendations": [
-            {
-                "type": "study_time",
-                "title": "Optimal Study Time",
-                "description": "Based on your patterns, 2:00 PM - 4:00 PM is your most productive study time",
-                "confidence": 0.85
-            },
-            {
-                "type": "deck_suggestion",
-                "title": "Review Needed",
-                "description": "You haven't reviewed 'Spanish Vocabulary' in 5 days",
-                "action": {
-                    "type": "study",
-                    "deck_id": Uuid::new_v4()
-                },
-                "confidence": 0.92
-            }
-        ]
-    })))
+) -> Result<Json<Vec<AiRecommendation>>> {
+    let recommendations = RecommendationService::list(&state.db, user_id).await?;
+    Ok(Json(recommendations))
+}
+
+/// Mark a recommendation helpful, not helpful or ignored. Recommendations
+/// marked not helpful are not made again for two weeks.
+#[utoipa::path(
+    post,
+    path = "/recommendations/feedback",
+    request_body = RecommendationFeedbackDto,
+    responses((status = 200, body = AiRecommendation)),
+    tag = "ai"
+)]
+async fn recommendation_feedback(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<RecommendationFeedbackDto>,
+) -> Result<Json<AiRecommendation>> {
+    dto.validate()?;
+
+    let recommendation = RecommendationService::record_feedback(&state.db, user_id, dto).await?;
+    Ok(Json(recommendation))
+}
+
+/// Insights into the user's study habits, found by the background
+/// learning-pattern analysis, with suggestions to act on them. Empty while
+/// personalized learning is off in the privacy settings.
+#[utoipa::path(
+    get,
+    path = "/insights",
+    responses((status = 200, body = Vec<LearningInsight>)),
+    tag = "ai"
+)]
+async fn get_insights(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<Vec<LearningInsight>>> {
+    let insights = LearningPatternService::insights(&state.db, user_id).await?;
+    Ok(Json(insights))
 }
 
 /// Generate an entire deck with AI
 /// This endpoint accepts either text input or file upload
+#[utoipa::path(
+    post,
+    path = "/generate-deck",
+    request_body(content = GenerateDeckForm, content_type = "multipart/form-data"),
+    responses((status = 200, body = serde_json::Value)),
+    tag = "ai"
+)]
 async fn generate_deck(
     State(state): State<AppState>,
-    UserId(user_id): UserId,
+    VerifiedUser(user_id): VerifiedUser,
     mut multipart: Multipart,
 ) -> Result<Json<serde_json::Value>> {
     // Check if AI is enabled
@@ -172,14 +456,22 @@ async fn generate_deck(
             "cards": []
         })));
     }
+    AiPrivacyService::require_content_generation(&state.db, user_id).await?;
 
     // Parse multipart form data
     let mut topic: Option<String> = None;
     let mut difficulty: Option<String> = None;
     let mut card_count: Option<i32> = None;
+    let mut document: Option<ExtractedDocument> = None;
     
-    while let Some(field) = multipart.next_field().await.unwrap() {
+    while let Some(field) = multipart.next_field().await? {
         let name = field.name().unwrap_or("").to_string();
+
+        if name == "file" {
+            document = Some(extract_field(&state, field).await?);
+            continue;
+        }
+
         let value = field.text().await.unwrap_or_default();
         
         match name.as_str() {
@@ -191,7 +483,10 @@ async fn generate_deck(
     }
     
     let num_cards = card_count.unwrap_or(20).min(50) as usize;
-    let topic_str = topic.as_deref().unwrap_or("General Knowledge");
+    let topic_str = topic
+        .as_deref()
+        .or(document.as_ref().and_then(|d| d.title.as_deref()))
+        .unwrap_or("General Knowledge");
     
     // Generate mock cards based on the topic
     let mut cards = Vec::new();
@@ -207,12 +502,110 @@ async fn generate_deck(
         "deck_name": format!("{} Flashcards", topic_str),
         "deck_description": format!("AI-generated deck about {}", topic_str),
         "cards": cards,
+        "source_document": document.as_ref().map(|d| json!({
+            "kind": d.kind,
+            "filename": d.filename,
+            "sections": d.sections.len(),
+            "word_count": d.word_count,
+        })),
         "message": "Deck generated successfully (mock data)",
         "provider": "mock",
         "model": "demo-v1"
     })))
 }
 
+/// Review a deck's cards for answer leaks, overly long answers, duplicates
+/// and ambiguous wording. Local checks always run; the AI provider reviews
+/// the cards too unless AI is disabled. Replaces earlier unapplied
+/// suggestions for the deck.
+#[utoipa::path(
+    post,
+    path = "/lint-deck/{id}",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body(content = Option<LintDeckDto>),
+    responses((status = 200, body = DeckLintReport)),
+    tag = "ai"
+)]
+async fn lint_deck(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Path(id): Path<Uuid>,
+    dto: Option<Json<LintDeckDto>>,
+) -> Result<Json<DeckLintReport>> {
+    let Json(dto) = dto.unwrap_or_default();
+    dto.validate()?;
+
+    let report =
+        CardLintService::lint_deck(&state.db, &state.config.ai, &state.ai_breaker, id, user_id, dto).await?;
+    Ok(Json(report))
+}
+
+/// Apply the rewrites of lint suggestions to their cards, all or nothing
+#[utoipa::path(
+    post,
+    path = "/lint-deck/{id}/apply",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = ApplyLintSuggestionsDto,
+    responses((status = 200, description = "The updated cards", body = Vec<Card>)),
+    tag = "ai"
+)]
+async fn apply_lint_suggestions(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<ApplyLintSuggestionsDto>,
+) -> Result<Json<Vec<Card>>> {
+    dto.validate()?;
+
+    let cards = CardLintService::apply(&state.db, id, user_id, dto).await?;
+    Ok(Json(cards))
+}
+
+/// Extract structured sections (headings + paragraphs) from an uploaded
+/// PDF, DOCX or text file
+#[utoipa::path(
+    post,
+    path = "/extract",
+    request_body(content = DocumentUploadForm, content_type = "multipart/form-data"),
+    responses((status = 200, body = ExtractedDocument)),
+    tag = "ai"
+)]
+async fn extract_document(
+    State(state): State<AppState>,
+    UserId(_user_id): UserId,
+    mut multipart: Multipart,
+) -> Result<Json<ExtractedDocument>> {
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::FileUploadError(e.to_string()))?
+    {
+        if field.name() == Some("file") {
+            let document = extract_field(&state, field).await?;
+            return Ok(Json(document));
+        }
+    }
+
+    Err(AppError::FileUploadError("Missing file field".to_string()))
+}
+
+async fn extract_field(
+    state: &AppState,
+    field: axum::extract::multipart::Field<'_>,
+) -> Result<ExtractedDocument> {
+    let file = upload::SpooledFile::read(field, &state.config.upload).await?;
+
+    match file.bytes() {
+        Some(bytes) => DocumentService::extract(bytes, file.filename.as_deref(), file.content_type.as_deref()),
+        // Large uploads are parsed from the spooled file, which lives until
+        // extraction finishes
+        None => {
+            let path = file.path().expect("spooled file has a path");
+            DocumentService::extract_file(path, file.filename.as_deref(), file.content_type.as_deref()).await
+        }
+    }
+}
+
 /// Handle file upload for AI generation
 pub async fn upload_for_generation(
     State(state): State<AppState>,
@@ -228,3 +621,81 @@ pub async fn upload_for_generation(
         "message": "File uploaded successfully"
     })))
 }
+
+/// List AI rewrite suggestions for cards flagged as leeches
+#[utoipa::path(
+    get,
+    path = "/leech-remediations",
+    params(LeechRemediationsQuery),
+    responses((status = 200, body = Vec<LeechRemediation>)),
+    tag = "ai"
+)]
+async fn list_leech_remediations(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<LeechRemediationsQuery>,
+) -> Result<Json<Vec<LeechRemediation>>> {
+    let remediations = LeechService::list_remediations(&state.db, user_id, &query).await?;
+    Ok(Json(remediations))
+}
+
+#[utoipa::path(
+    get,
+    path = "/leech-remediations/{id}",
+    params(("id" = Uuid, Path, description = "Remediation id")),
+    responses((status = 200, body = LeechRemediation)),
+    tag = "ai"
+)]
+async fn get_leech_remediation(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LeechRemediation>> {
+    let remediation = LeechService::get_remediation(&state.db, id, user_id).await?;
+    Ok(Json(remediation))
+}
+
+/// Apply a suggested rewrite to the card
+#[utoipa::path(
+    post,
+    path = "/leech-remediations/{id}/accept",
+    params(("id" = Uuid, Path, description = "Remediation id")),
+    responses((status = 200, body = LeechRemediation)),
+    tag = "ai"
+)]
+async fn accept_leech_remediation(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LeechRemediation>> {
+    let remediation = LeechService::accept(&state.db, id, user_id).await?;
+    Ok(Json(remediation))
+}
+
+#[utoipa::path(
+    post,
+    path = "/leech-remediations/{id}/reject",
+    params(("id" = Uuid, Path, description = "Remediation id")),
+    responses((status = 200, body = LeechRemediation)),
+    tag = "ai"
+)]
+async fn reject_leech_remediation(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LeechRemediation>> {
+    let remediation = LeechService::reject(&state.db, id, user_id).await?;
+    Ok(Json(remediation))
+}
+
+fn sse_event(event: &GenerationEvent) -> Event {
+    let data = match event {
+        GenerationEvent::Card(card) => serde_json::to_value(card),
+        GenerationEvent::Completed(summary) => serde_json::to_value(summary),
+        GenerationEvent::Cancelled => Ok(json!({ "message": "Generation was cancelled" })),
+        GenerationEvent::Failed(message) => Ok(json!({ "message": message })),
+    }
+    .unwrap_or_default();
+
+    Event::default().event(event.name()).data(data.to_string())
+}
@@ -1,64 +1,147 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    routing::{post},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+    routing::{get, post},
     Json, Router,
 };
+use reqwest::Url;
+use utoipa::OpenApi;
 use validator::Validate;
 
 use crate::{
+    middleware::auth::UserId,
     models::{
-        AuthResponse, LoginDto, PasswordResetDto, PasswordResetRequestDto,
-        RefreshTokenDto, RegisterDto,
+        AuthResponse, ClientInfo, LoginAttempt, LoginDto, LoginHistoryQuery, OAuthCallbackQuery,
+        PasswordResetDto, PasswordResetRequestDto, RefreshTokenDto, RegisterDto, UserResponse,
+        VerifyEmailDto,
+    },
+    services::{
+        auth::{AuthService, Claims},
+        oauth::OAuthService,
+        user::UserService,
     },
-    services::auth::{AuthService, Claims},
     state::AppState,
-    utils::{AppError, Result},
+    utils::{AppError, ErrorResponse, Result},
 };
 
 pub fn routes() -> Router<AppState> {
+    credential_routes().merge(session_routes())
+}
+
+/// Endpoints that accept credentials or send emails; brute-force targets
+/// that get a strict rate limit
+pub fn credential_routes() -> Router<AppState> {
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
-        .route("/refresh", post(refresh_token))
-        .route("/logout", post(logout))
         .route("/password-reset/request", post(request_password_reset))
         .route("/password-reset/confirm", post(reset_password))
+        .route("/verify-email", post(verify_email))
+        .route("/oauth/:provider/start", get(oauth_start))
+        .route("/oauth/:provider/callback", get(oauth_callback))
+}
+
+pub fn session_routes() -> Router<AppState> {
+    Router::new()
+        .route("/refresh", post(refresh_token))
+        .route("/logout", post(logout))
+        .route("/login-history", get(login_history))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    register,
+    login,
+    login_history,
+    refresh_token,
+    logout,
+    request_password_reset,
+    reset_password,
+    verify_email,
+    oauth_start,
+    oauth_callback
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    post,
+    path = "/register",
+    request_body = RegisterDto,
+    responses((status = 201, description = "Account created", body = AuthResponse)),
+    security(()),
+    tag = "auth"
+)]
 async fn register(
     State(state): State<AppState>,
     Json(dto): Json<RegisterDto>,
 ) -> Result<(StatusCode, Json<AuthResponse>)> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    dto.validate()?;
 
-    let response = AuthService::register(&state.db, dto).await?;
+    let response = AuthService::register(&state.db, &state.config, dto).await?;
     Ok((StatusCode::CREATED, Json(response)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/login",
+    request_body = LoginDto,
+    responses((status = 200, description = "Access and refresh tokens", body = AuthResponse)),
+    security(()),
+    tag = "auth"
+)]
 async fn login(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(dto): Json<LoginDto>,
 ) -> Result<Json<AuthResponse>> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
-
-    // Check rate limiting
-    AuthService::check_rate_limit(&state.db, &dto.email).await?;
+    dto.validate()?;
 
-    let response = AuthService::login(&state.db, dto).await?;
+    let response = AuthService::login(&state.db, &state.config, dto, &client).await?;
     Ok(Json(response))
 }
 
+#[utoipa::path(
+    get,
+    path = "/login-history",
+    params(LoginHistoryQuery),
+    responses((status = 200, body = Vec<LoginAttempt>)),
+    tag = "auth"
+)]
+async fn login_history(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<LoginHistoryQuery>,
+) -> Result<Json<Vec<LoginAttempt>>> {
+    let attempts = AuthService::login_history(&state.db, user_id, query.limit).await?;
+    Ok(Json(attempts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/refresh",
+    request_body = RefreshTokenDto,
+    responses(
+        (status = 200, description = "Rotated token pair", body = AuthResponse),
+        (status = 401, description = "Expired or reused refresh token; a reused token has code `REFRESH_TOKEN_REUSED`", body = ErrorResponse),
+    ),
+    security(()),
+    tag = "auth"
+)]
 async fn refresh_token(
     State(state): State<AppState>,
     Json(dto): Json<RefreshTokenDto>,
 ) -> Result<Json<AuthResponse>> {
-    let response = AuthService::refresh_token(&state.db, dto).await?;
+    let response = AuthService::refresh_token(&state.db, &state.config, dto).await?;
     Ok(Json(response))
 }
 
+#[utoipa::path(
+    post,
+    path = "/logout",
+    responses((status = 204, description = "Refresh tokens revoked")),
+    tag = "auth"
+)]
 async fn logout(
     State(state): State<AppState>,
     claims: Claims, // This will come from the auth middleware
@@ -67,24 +150,170 @@ async fn logout(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/password-reset/request",
+    request_body = PasswordResetRequestDto,
+    responses((status = 204, description = "Reset email sent if the account exists")),
+    security(()),
+    tag = "auth"
+)]
 async fn request_password_reset(
     State(state): State<AppState>,
     Json(dto): Json<PasswordResetRequestDto>,
 ) -> Result<StatusCode> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    dto.validate()?;
 
     AuthService::request_password_reset(&state.db, dto).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/password-reset/confirm",
+    request_body = PasswordResetDto,
+    responses((status = 204, description = "Password changed")),
+    security(()),
+    tag = "auth"
+)]
 async fn reset_password(
     State(state): State<AppState>,
     Json(dto): Json<PasswordResetDto>,
 ) -> Result<StatusCode> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    dto.validate()?;
 
     AuthService::reset_password(&state.db, dto).await?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Confirm an email address, or an email change, with the token sent to it
+#[utoipa::path(
+    post,
+    path = "/verify-email",
+    request_body = VerifyEmailDto,
+    responses((status = 200, body = UserResponse)),
+    security(()),
+    tag = "auth"
+)]
+async fn verify_email(
+    State(state): State<AppState>,
+    Json(dto): Json<VerifyEmailDto>,
+) -> Result<Json<UserResponse>> {
+    let user = UserService::verify_email(&state.db, &dto.token).await?;
+    Ok(Json(user))
+}
+
+// Holds the state parameter between the redirect to the provider and back
+const OAUTH_STATE_COOKIE: &str = "oauth_state";
+const OAUTH_STATE_MAX_AGE_SECONDS: u64 = 600;
+
+/// Redirect the browser to the provider's sign-in page
+#[utoipa::path(
+    get,
+    path = "/oauth/{provider}/start",
+    params(("provider" = String, Path, description = "`google` or `github`")),
+    responses(
+        (status = 303, description = "Redirect to the provider"),
+        (status = 404, description = "Provider unknown or not configured", body = ErrorResponse),
+    ),
+    security(()),
+    tag = "auth"
+)]
+async fn oauth_start(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+) -> Result<impl IntoResponse> {
+    let provider = OAuthService::provider(&state.config, &provider)?;
+    let oauth_state = AuthService::generate_random_token();
+    let url = OAuthService::authorization_url(&state.config, provider, &oauth_state)?;
+
+    let cookie = state_cookie(&state, &oauth_state, OAUTH_STATE_MAX_AGE_SECONDS);
+    Ok(([(header::SET_COOKIE, cookie)], Redirect::to(&url)))
+}
+
+/// Finish signing in and redirect to the app's `/oauth/callback` page with
+/// the tokens, or an `error`, in the URL fragment
+#[utoipa::path(
+    get,
+    path = "/oauth/{provider}/callback",
+    params(("provider" = String, Path, description = "`google` or `github`"), OAuthCallbackQuery),
+    responses((status = 303, description = "Redirect to the app with `access_token`, `refresh_token`, `token_type` and `expires_in`, or `error`")),
+    security(()),
+    tag = "auth"
+)]
+async fn oauth_callback(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Query(query): Query<OAuthCallbackQuery>,
+    headers: HeaderMap,
+    client: ClientInfo,
+) -> impl IntoResponse {
+    let expected_state = cookie_value(&headers, OAUTH_STATE_COOKIE);
+    let outcome = async {
+        let provider = OAuthService::provider(&state.config, &provider)?;
+        if let Some(error) = &query.error {
+            return Err(AppError::BadRequest(format!("Sign-in was cancelled ({})", error)));
+        }
+        // The state must match the cookie set by this browser's start request
+        if query.state.is_none() || query.state.as_deref() != expected_state {
+            return Err(AppError::BadRequest("Sign-in expired, please try again".to_string()));
+        }
+        let code = query
+            .code
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("Missing authorization code".to_string()))?;
+
+        let profile = OAuthService::fetch_profile(&state.config, provider, code).await?;
+        OAuthService::sign_in(&state.db, &state.config, provider, profile, &client).await
+    }
+    .await;
+
+    let fragment: Vec<(&str, String)> = match outcome {
+        Ok(auth) => vec![
+            ("access_token", auth.access_token),
+            ("refresh_token", auth.refresh_token),
+            ("token_type", auth.token_type),
+            ("expires_in", auth.expires_in.to_string()),
+        ],
+        Err(AppError::BadRequest(message)) | Err(AppError::NotFound(message)) => vec![("error", message)],
+        Err(e) => {
+            tracing::error!("OAuth sign-in failed: {}", e);
+            vec![("error", "Sign-in failed".to_string())]
+        }
+    };
+
+    let cookie = state_cookie(&state, "", 0);
+    ([(header::SET_COOKIE, cookie)], Redirect::to(&app_callback_url(&state, &fragment)))
+}
+
+fn state_cookie(state: &AppState, value: &str, max_age_seconds: u64) -> String {
+    let secure = if state.config.email.api_url.starts_with("https://") { "; Secure" } else { "" };
+    format!(
+        "{}={}; Path=/api/v1/auth/oauth; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        OAUTH_STATE_COOKIE, value, max_age_seconds, secure
+    )
+}
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+// Tokens go in the fragment so they never reach a server log
+fn app_callback_url(state: &AppState, fragment: &[(&str, String)]) -> String {
+    let base = format!("{}/oauth/callback", state.config.email.app_url.trim_end_matches('/'));
+    let Ok(mut url) = Url::parse_with_params(&base, fragment) else {
+        return base;
+    };
+    let params = url.query().map(str::to_owned);
+    url.set_query(None);
+    url.set_fragment(params.as_deref());
+    url.into()
+}
@@ -0,0 +1,119 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use utoipa::OpenApi;
+use validator::Validate;
+
+use crate::{
+    middleware::auth::{UserId, VerifiedUser},
+    models::{CaptureDto, CaptureResult, InboundEmailAddress, UpdateInboundEmailDto},
+    services::{capture::CaptureService, email_inbox::EmailInboxService, webhook::WebhookService},
+    state::AppState,
+    utils::Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(capture))
+        .route("/email", get(get_email_address).put(update_email_address))
+        .route("/email/rotate", post(rotate_email_address))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    capture,
+    get_email_address,
+    update_email_address,
+    rotate_email_address
+))]
+pub struct ApiDoc;
+
+/// Save a text selection from a web page as a card, or queue AI generation
+/// from it
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = CaptureDto,
+    responses(
+        (status = 201, description = "Card saved", body = CaptureResult),
+        (status = 202, description = "AI generation queued", body = CaptureResult),
+    ),
+    tag = "capture"
+)]
+async fn capture(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Json(dto): Json<CaptureDto>,
+) -> Result<(StatusCode, Json<CaptureResult>)> {
+    dto.validate()?;
+
+    let result = CaptureService::capture(&state.db, &state.config.ai, user_id, dto).await?;
+
+    let status = match &result {
+        CaptureResult::Card { card } => {
+            WebhookService::dispatch(state.db.clone(), card.deck_id, "card.created", json!(card));
+            StatusCode::CREATED
+        }
+        CaptureResult::Generation { .. } => StatusCode::ACCEPTED,
+    };
+    Ok((status, Json(result)))
+}
+
+/// Get the address that turns forwarded email into cards
+#[utoipa::path(
+    get,
+    path = "/email",
+    responses((status = 200, body = InboundEmailAddress)),
+    tag = "capture"
+)]
+async fn get_email_address(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<InboundEmailAddress>> {
+    let address =
+        EmailInboxService::get_address(&state.db, &state.config.inbound_email, user_id).await?;
+    Ok(Json(address))
+}
+
+/// Choose which deck receives email
+#[utoipa::path(
+    put,
+    path = "/email",
+    request_body = UpdateInboundEmailDto,
+    responses((status = 200, body = InboundEmailAddress)),
+    tag = "capture"
+)]
+async fn update_email_address(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<UpdateInboundEmailDto>,
+) -> Result<Json<InboundEmailAddress>> {
+    let address = EmailInboxService::set_deck(
+        &state.db,
+        &state.config.inbound_email,
+        user_id,
+        dto.deck_id,
+    )
+    .await?;
+    Ok(Json(address))
+}
+
+/// Issue a new address; mail to the old one is rejected
+#[utoipa::path(
+    post,
+    path = "/email/rotate",
+    responses((status = 200, body = InboundEmailAddress)),
+    tag = "capture"
+)]
+async fn rotate_email_address(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<InboundEmailAddress>> {
+    let address =
+        EmailInboxService::rotate_address(&state.db, &state.config.inbound_email, user_id).await?;
+    Ok(Json(address))
+}
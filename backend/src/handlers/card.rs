@@ -1,98 +1,446 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    handler::Handler,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, patch, post},
     Json, Router,
 };
 use serde::Deserialize;
+use serde_json::json;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
-    middleware::auth::UserId,
-    models::{Card, CreateCardDto, UpdateCardDto},
-    services::card::CardService,
+    middleware::auth::{UserId, VerifiedUser},
+    models::{
+        BulkMoveCardsDto, Card, CardFlags, CardHistory, CardMediaResponse, CardOrder, CreateCardDto, MoveCardDto,
+        MovedCard, RenderFormat, RenderedCard, UpdateCardDto, UpdateCardFlagsDto,
+    },
+    services::{
+        card::CardService, card_flags::CardFlagsService, media::MediaService,
+        study::StudyService, webhook::WebhookService,
+    },
     state::AppState,
-    utils::{AppError, Result},
+    utils::{etag, AppError, CursorPage, CursorParams, ErrorResponse, Result},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct CardsQuery {
     deck_id: Uuid,
 }
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListCardsQuery {
+    deck_id: Uuid,
+    #[serde(default)]
+    order: CardOrder,
+    cursor: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct GetCardQuery {
+    render: Option<RenderFormat>,
+}
+
+/// Multipart form of `upload_card_media`; documentation only
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct MediaUploadForm {
+    /// Image or audio file
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_cards).post(create_card))
         .route("/bulk", post(bulk_create_cards))
+        .route("/move", post(bulk_move_cards))
         .route("/:id", get(get_card).patch(update_card).delete(delete_card))
+        .route("/:id/flags", get(get_card_flags).patch(update_card_flags))
+        .route("/:id/history", get(get_card_history))
+        .route("/:id/move", post(move_card))
+        // Uploads are capped at MAX_FILE_SIZE while streaming instead
+        .route(
+            "/:id/media",
+            get(list_card_media).post(upload_card_media.layer(DefaultBodyLimit::disable())),
+        )
+        .route("/:id/media/:media_id", delete(delete_card_media))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_cards,
+    create_card,
+    bulk_create_cards,
+    bulk_move_cards,
+    get_card,
+    update_card,
+    delete_card,
+    move_card,
+    get_card_flags,
+    update_card_flags,
+    get_card_history,
+    list_card_media,
+    upload_card_media,
+    delete_card_media
+))]
+pub struct ApiDoc;
+
+/// Supports `If-None-Match`; unchanged pages get a bodiless 304
+#[utoipa::path(
+    get,
+    path = "",
+    params(ListCardsQuery),
+    responses(
+        (status = 200, body = CursorPage<Card>),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+    ),
+    tag = "cards"
+)]
 async fn list_cards(
     State(state): State<AppState>,
     UserId(user_id): UserId,
-    Query(query): Query<CardsQuery>,
-) -> Result<Json<Vec<Card>>> {
-    let cards = CardService::list_deck_cards(&state.db, query.deck_id, user_id).await?;
-    Ok(Json(cards))
+    Query(query): Query<ListCardsQuery>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let version = CardService::deck_cards_version(&state.db, query.deck_id, user_id).await?;
+    let etag = etag::etag("cards", &version);
+    if etag::is_fresh(&headers, &etag) {
+        return Ok(etag::not_modified(&etag, etag::REVALIDATE_CACHE_CONTROL));
+    }
+
+    let page = CursorParams {
+        cursor: query.cursor,
+        limit: query.limit.unwrap_or(CursorParams::default().limit),
+    };
+    let cards =
+        CardService::list_deck_cards(&state.db, query.deck_id, user_id, query.order, page).await?;
+    Ok((
+        [
+            (header::CACHE_CONTROL, HeaderValue::from_static(etag::REVALIDATE_CACHE_CONTROL)),
+            (header::ETAG, etag),
+        ],
+        Json(cards),
+    )
+        .into_response())
 }
 
+#[utoipa::path(
+    post,
+    path = "",
+    params(CardsQuery),
+    request_body = CreateCardDto,
+    responses((status = 201, body = Card)),
+    tag = "cards"
+)]
 async fn create_card(
     State(state): State<AppState>,
-    UserId(user_id): UserId,
+    VerifiedUser(user_id): VerifiedUser,
     Query(query): Query<CardsQuery>,
     Json(dto): Json<CreateCardDto>,
 ) -> Result<(StatusCode, Json<Card>)> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    dto.validate()?;
     
     let card = CardService::create_card(&state.db, query.deck_id, user_id, dto).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    WebhookService::dispatch(state.db.clone(), card.deck_id, "card.created", json!(card));
     Ok((StatusCode::CREATED, Json(card)))
 }
 
+/// The `ETag` header can be sent back in `If-Match` when updating the card
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Card id"), GetCardQuery),
+    responses((status = 200, body = RenderedCard)),
+    tag = "cards"
+)]
 async fn get_card(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
-) -> Result<Json<Card>> {
-    let card = CardService::get_card(&state.db, id, user_id).await?;
-    Ok(Json(card))
+    Query(query): Query<GetCardQuery>,
+) -> Result<Response> {
+    let card = CardService::get_rendered_card(&state.db, id, user_id, query.render).await?;
+    let etag = etag::entity_etag("card", card.card.id, card.card.updated_at);
+    Ok(([(header::ETAG, etag)], Json(card)).into_response())
 }
 
+/// Send the card's ETag in `If-Match`, or its `updated_at` in the body, to
+/// get a 409 with the current card instead of overwriting someone else's edit
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Card id"),
+        ("if-match" = Option<String>, Header, description = "ETag of the card the edit is based on"),
+    ),
+    request_body = UpdateCardDto,
+    responses(
+        (status = 200, body = Card),
+        (status = 409, description = "The card changed since; `current` in the body is the card as it is now", body = ErrorResponse),
+    ),
+    tag = "cards"
+)]
 async fn update_card(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
-    Json(dto): Json<UpdateCardDto>,
-) -> Result<Json<Card>> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
-    
+    headers: HeaderMap,
+    Json(mut dto): Json<UpdateCardDto>,
+) -> Result<Response> {
+    dto.validate()?;
+
+    if dto.updated_at.is_none() && headers.contains_key(header::IF_MATCH) {
+        let current = CardService::get_card(&state.db, id, user_id).await?;
+        let current_etag = etag::entity_etag("card", current.id, current.updated_at);
+        if etag::if_match(&headers, &current_etag) == Some(false) {
+            return Err(AppError::edit_conflict("card", current.id, current.updated_at, &current));
+        }
+        dto.updated_at = Some(current.updated_at);
+    }
+
     let card = CardService::update_card(&state.db, id, user_id, dto).await?;
-    Ok(Json(card))
+    state.queue_cache.invalidate_user(user_id).await;
+    WebhookService::dispatch(state.db.clone(), card.deck_id, "card.updated", json!(card));
+    let etag = etag::entity_etag("card", card.id, card.updated_at);
+    Ok(([(header::ETAG, etag)], Json(card)).into_response())
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Card id")),
+    responses((status = 204, description = "Card moved to the trash")),
+    tag = "cards"
+)]
 async fn delete_card(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode> {
-    CardService::delete_card(&state.db, id, user_id).await?;
+    let card = CardService::delete_card(&state.db, id, user_id).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    WebhookService::dispatch(state.db.clone(), card.deck_id, "card.deleted", json!(card));
     Ok(StatusCode::NO_CONTENT)
 }
 
-async fn bulk_create_cards(
+/// Move a card, with its reverse, to another deck the user owns. Study
+/// progress and flags stay with the card.
+#[utoipa::path(
+    post,
+    path = "/{id}/move",
+    params(("id" = Uuid, Path, description = "Card id")),
+    request_body = MoveCardDto,
+    responses((status = 200, body = Vec<MovedCard>)),
+    tag = "cards"
+)]
+async fn move_card(
     State(state): State<AppState>,
     UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<MoveCardDto>,
+) -> Result<Json<Vec<MovedCard>>> {
+    dto.validate()?;
+
+    let moved = CardService::move_cards(&state.db, &[id], dto.target_deck_id, user_id, dto.position).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    dispatch_moved(&state, &moved);
+    Ok(Json(moved))
+}
+
+#[utoipa::path(
+    post,
+    path = "/move",
+    request_body = BulkMoveCardsDto,
+    responses((status = 200, body = Vec<MovedCard>)),
+    tag = "cards"
+)]
+async fn bulk_move_cards(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<BulkMoveCardsDto>,
+) -> Result<Json<Vec<MovedCard>>> {
+    dto.validate()?;
+
+    let moved =
+        CardService::move_cards(&state.db, &dto.card_ids, dto.target_deck_id, user_id, dto.position).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    dispatch_moved(&state, &moved);
+    Ok(Json(moved))
+}
+
+/// Moved cards leave one deck and arrive in another as far as webhooks go
+fn dispatch_moved(state: &AppState, moved: &[MovedCard]) {
+    for moved in moved {
+        WebhookService::dispatch(state.db.clone(), moved.from_deck_id, "card.deleted", json!(moved.card));
+        WebhookService::dispatch(state.db.clone(), moved.card.deck_id, "card.created", json!(moved.card));
+    }
+}
+
+/// The current user's star, suspension and leech flags and notes on a card
+#[utoipa::path(
+    get,
+    path = "/{id}/flags",
+    params(("id" = Uuid, Path, description = "Card id")),
+    responses((status = 200, body = CardFlags)),
+    tag = "cards"
+)]
+async fn get_card_flags(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CardFlags>> {
+    let flags = CardFlagsService::get_flags(&state.db, id, user_id).await?;
+    Ok(Json(flags))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}/flags",
+    params(("id" = Uuid, Path, description = "Card id")),
+    request_body = UpdateCardFlagsDto,
+    responses((status = 200, body = CardFlags)),
+    tag = "cards"
+)]
+async fn update_card_flags(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<UpdateCardFlagsDto>,
+) -> Result<Json<CardFlags>> {
+    dto.validate()?;
+
+    let flags = CardFlagsService::update_flags(&state.db, id, user_id, dto).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    Ok(Json(flags))
+}
+
+/// Every answer the current user gave to the card, oldest first, with the
+/// interval and ease before and after each, to see why it keeps coming back
+#[utoipa::path(
+    get,
+    path = "/{id}/history",
+    params(("id" = Uuid, Path, description = "Card id")),
+    responses((status = 200, body = CardHistory)),
+    tag = "cards"
+)]
+async fn get_card_history(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CardHistory>> {
+    let history = StudyService::get_card_history(&state.db, id, user_id).await?;
+    Ok(Json(history))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bulk",
+    params(CardsQuery),
+    request_body = Vec<CreateCardDto>,
+    responses((status = 201, body = Vec<Card>)),
+    tag = "cards"
+)]
+async fn bulk_create_cards(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
     Query(query): Query<CardsQuery>,
     Json(cards): Json<Vec<CreateCardDto>>,
 ) -> Result<(StatusCode, Json<Vec<Card>>)> {
     // Validate all cards
     for card in &cards {
-        card.validate()
-            .map_err(|e| AppError::ValidationError(e.to_string()))?;
+        card.validate()?;
     }
     
     let created_cards = CardService::bulk_create_cards(&state.db, query.deck_id, user_id, cards).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    for card in &created_cards {
+        WebhookService::dispatch(state.db.clone(), card.deck_id, "card.created", json!(card));
+    }
     Ok((StatusCode::CREATED, Json(created_cards)))
 }
+
+#[utoipa::path(
+    get,
+    path = "/{id}/media",
+    params(("id" = Uuid, Path, description = "Card id")),
+    responses((status = 200, body = Vec<CardMediaResponse>)),
+    tag = "cards"
+)]
+async fn list_card_media(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<CardMediaResponse>>> {
+    let media = MediaService::list(&state.db, &state.media, id, user_id).await?;
+    Ok(Json(media))
+}
+
+/// Attach an image or audio file, sent as the `file` field of a multipart form
+#[utoipa::path(
+    post,
+    path = "/{id}/media",
+    params(("id" = Uuid, Path, description = "Card id")),
+    request_body(content = MediaUploadForm, content_type = "multipart/form-data"),
+    responses((status = 201, body = CardMediaResponse)),
+    tag = "cards"
+)]
+async fn upload_card_media(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<CardMediaResponse>)> {
+    while let Some(mut field) = multipart.next_field().await? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        // Browsers may send a full path; keep only the name
+        let filename = field
+            .file_name()
+            .and_then(|name| name.rsplit(['/', '\\']).next())
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| AppError::FileUploadError("Missing filename".to_string()))?;
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.chunk().await? {
+            if data.len() + chunk.len() > state.media.max_file_size() {
+                return Err(AppError::FileTooLarge {
+                    limit_bytes: state.media.max_file_size(),
+                });
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        let media = MediaService::upload(&state.db, &state.media, id, user_id, &filename, data).await?;
+        return Ok((StatusCode::CREATED, Json(media)));
+    }
+
+    Err(AppError::FileUploadError("Missing file field".to_string()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/media/{media_id}",
+    params(("id" = Uuid, Path, description = "Card id"), ("media_id" = Uuid, Path, description = "Attachment id")),
+    responses((status = 204, description = "Attachment deleted")),
+    tag = "cards"
+)]
+async fn delete_card_media(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((id, media_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    MediaService::delete(&state.db, &state.media, id, media_id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
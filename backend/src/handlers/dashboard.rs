@@ -0,0 +1,36 @@
+use axum::{extract::State, routing::get, Json, Router};
+use utoipa::OpenApi;
+
+use crate::{
+    middleware::auth::UserId,
+    models::Dashboard,
+    services::{dashboard::DashboardService, media::MediaService},
+    state::AppState,
+    utils::Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", get(get_dashboard))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_dashboard))]
+pub struct ApiDoc;
+
+/// Folder tree, decks with due counts, streak and recent sessions for the home screen
+#[utoipa::path(
+    get,
+    path = "",
+    responses((status = 200, body = Dashboard)),
+    tag = "dashboard"
+)]
+async fn get_dashboard(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<Dashboard>> {
+    let mut dashboard = DashboardService::get(&state.db, user_id).await?;
+    for deck in &mut dashboard.decks {
+        MediaService::sign_deck_cover(&state.media, &mut deck.deck);
+    }
+    Ok(Json(dashboard))
+}
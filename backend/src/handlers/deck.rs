@@ -1,19 +1,34 @@
 use axum::{
-    extract::{Path, State},
-    http::{header, StatusCode},
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    handler::Handler,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
-    routing::{delete, get, patch, post},
+    routing::{delete, get, patch, post, put},
     Json, Router,
 };
+use utoipa::{OpenApi, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
-    middleware::auth::UserId,
-    models::{CreateDeckDto, Deck, DeckWithStats, UpdateDeckDto},
-    services::deck::DeckService,
+    middleware::auth::{UserId, VerifiedUser},
+    models::{
+        ai::{DeckExam, DeckExamWithQuestions, GenerateQuizDto},
+        CreateDeckDto, CreateDeckWebhookDto, CreateGuestTokenDto, CreateShareLinkDto,
+        CreatedDeckWebhook, Deck, DeckEncryption, DeckGuestToken, DeckShare, DeckShareInvite, DeckShareLink,
+        DeckRatingScale, DeckRatingSummary, DeckSettings, DeckStatistics, DeckWebhook, DeckWithStats,
+        DecryptDeckDto, EncryptDeckDto, MergeDecksDto, MergeDecksResult, PublicDeck, PublicDeckQuery, RateDeckDto,
+        ShareDeckDto, SharedDeck, SplitDeckDto, SplitDeckResult, UpdateDeckDto, UpdateDeckSettingsDto,
+        UpdateDeckWebhookDto, UpdateRatingScaleDto,
+    },
+    services::{
+        deck::DeckService, deck_merge::DeckMergeService, deck_settings::DeckSettingsService,
+        encryption::EncryptionService, guest::GuestService,
+        marketplace::MarketplaceService, media::MediaService, quiz::QuizService, sharing::SharingService, stats::StatsService,
+        webhook::WebhookService,
+    },
     state::AppState,
-    utils::{AppError, Result},
+    utils::{etag, AppError, ErrorResponse, PaginatedResponse, PaginationParams, Result},
 };
 
 pub fn routes() -> Router<AppState> {
@@ -21,77 +36,402 @@ pub fn routes() -> Router<AppState> {
         .route("/", get(list_decks).post(create_deck))
         .route("/:id", get(get_deck).patch(update_deck).delete(delete_deck))
         .route("/:id/stats", get(get_deck_with_stats))
+        .route("/:id/cover", put(upload_cover.layer(DefaultBodyLimit::disable())).delete(delete_cover))
+        .route("/:id/statistics", get(get_statistics))
+        .route("/:id/rating-scale", get(get_rating_scale).put(update_rating_scale))
+        .route("/:id/settings", get(get_settings).patch(update_settings))
         .route("/:id/csv", post(import_csv).get(export_csv))
+        .route("/:id/encryption", get(get_encryption).put(encrypt_deck))
+        .route("/:id/encryption/disable", post(decrypt_deck))
+        .route("/:id/shares", get(list_shares).post(share_deck))
+        .route("/:id/shares/:user_id", delete(revoke_share))
+        .route("/:id/share-links", get(list_share_links).post(create_share_link))
+        .route("/:id/share-links/:link_id", delete(revoke_share_link))
+        .route("/:id/guest-tokens", get(list_guest_tokens).post(create_guest_token))
+        .route("/:id/guest-tokens/:token_id", delete(revoke_guest_token))
+        .route("/:id/webhooks", get(list_webhooks).post(create_webhook))
+        .route("/:id/webhooks/:webhook_id", patch(update_webhook).delete(delete_webhook))
+        .route("/:id/ai/quiz", get(list_quizzes).post(generate_quiz))
+        .route("/:id/ai/quiz/:exam_id", get(get_quiz))
+        .route("/:id/rate", post(rate_deck))
+        .route("/:id/duplicate", post(duplicate_deck))
+        .route("/:id/split", post(split_deck))
+        .route("/merge", post(merge_decks))
+        .route("/shared", get(list_shared_decks))
+        .route("/public", get(list_public_decks))
+        .route("/share-links/:token/accept", post(accept_share_link))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_decks,
+    create_deck,
+    get_deck,
+    update_deck,
+    delete_deck,
+    get_deck_with_stats,
+    upload_cover,
+    delete_cover,
+    get_statistics,
+    get_rating_scale,
+    update_rating_scale,
+    get_settings,
+    update_settings,
+    import_csv,
+    export_csv,
+    get_encryption,
+    encrypt_deck,
+    decrypt_deck,
+    list_shares,
+    share_deck,
+    revoke_share,
+    list_share_links,
+    create_share_link,
+    revoke_share_link,
+    list_guest_tokens,
+    create_guest_token,
+    revoke_guest_token,
+    list_webhooks,
+    create_webhook,
+    update_webhook,
+    delete_webhook,
+    list_quizzes,
+    generate_quiz,
+    get_quiz,
+    rate_deck,
+    duplicate_deck,
+    split_deck,
+    merge_decks,
+    list_shared_decks,
+    list_public_decks,
+    accept_share_link
+))]
+pub struct ApiDoc;
+
+/// Supports `If-None-Match`; unchanged listings get a bodiless 304
+#[utoipa::path(
+    get,
+    path = "",
+    responses(
+        (status = 200, body = Vec<DeckWithStats>),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+    ),
+    tag = "decks"
+)]
 async fn list_decks(
     State(state): State<AppState>,
     UserId(user_id): UserId,
-) -> Result<Json<Vec<DeckWithStats>>> {
-    let decks = DeckService::list_user_decks(&state.db, user_id).await?;
-    Ok(Json(decks))
+    headers: HeaderMap,
+) -> Result<Response> {
+    let version = DeckService::list_user_decks_version(&state.db, user_id).await?;
+    // Cover links expire, so the listing changes with each signing window
+    let version = format!("{}:{}", version, state.media.url_window());
+    let etag = etag::etag("decks", &version);
+    if etag::is_fresh(&headers, &etag) {
+        return Ok(etag::not_modified(&etag, etag::REVALIDATE_CACHE_CONTROL));
+    }
+
+    let mut decks = DeckService::list_user_decks(&state.db, user_id).await?;
+    for deck in &mut decks {
+        MediaService::sign_deck_cover(&state.media, deck);
+    }
+    Ok((
+        [
+            (header::CACHE_CONTROL, HeaderValue::from_static(etag::REVALIDATE_CACHE_CONTROL)),
+            (header::ETAG, etag),
+        ],
+        Json(decks),
+    )
+        .into_response())
 }
 
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = CreateDeckDto,
+    responses((status = 201, body = Deck)),
+    tag = "decks"
+)]
 async fn create_deck(
     State(state): State<AppState>,
-    UserId(user_id): UserId,
+    VerifiedUser(user_id): VerifiedUser,
     Json(dto): Json<CreateDeckDto>,
 ) -> Result<(StatusCode, Json<Deck>)> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    dto.validate()?;
     
     let deck = DeckService::create_deck(&state.db, user_id, dto).await?;
     Ok((StatusCode::CREATED, Json(deck)))
 }
 
+/// The `ETag` header can be sent back in `If-Match` when updating the deck
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = Deck)),
+    tag = "decks"
+)]
 async fn get_deck(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
-) -> Result<Json<Deck>> {
+) -> Result<Response> {
     let deck = DeckService::get_deck(&state.db, id, user_id).await?;
-    Ok(Json(deck))
+    let etag = etag::entity_etag("deck", deck.id, deck.updated_at);
+    Ok(([(header::ETAG, etag)], Json(deck)).into_response())
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}/stats",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = DeckWithStats)),
+    tag = "decks"
+)]
 async fn get_deck_with_stats(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
 ) -> Result<Json<DeckWithStats>> {
-    let deck_stats = DeckService::get_deck_with_stats(&state.db, id, user_id).await?;
+    let mut deck_stats = DeckService::get_deck_with_stats(&state.db, id, user_id).await?;
+    MediaService::sign_deck_cover(&state.media, &mut deck_stats);
     Ok(Json(deck_stats))
 }
 
+/// Multipart form of `upload_cover`; documentation only
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct CoverUploadForm {
+    /// Image file
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+/// Set the deck's cover image, sent as the `file` field of a multipart form
+#[utoipa::path(
+    put,
+    path = "/{id}/cover",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body(content = CoverUploadForm, content_type = "multipart/form-data"),
+    responses((status = 200, body = DeckWithStats)),
+    tag = "decks"
+)]
+async fn upload_cover(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<Json<DeckWithStats>> {
+    while let Some(mut field) = multipart.next_field().await? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let filename = field
+            .file_name()
+            .and_then(|name| name.rsplit(['/', '\\']).next())
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| AppError::FileUploadError("Missing filename".to_string()))?;
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.chunk().await? {
+            if data.len() + chunk.len() > state.media.max_file_size() {
+                return Err(AppError::FileTooLarge {
+                    limit_bytes: state.media.max_file_size(),
+                });
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        let deck = MediaService::upload_deck_cover(&state.db, &state.media, id, user_id, &filename, data).await?;
+        return Ok(Json(deck));
+    }
+
+    Err(AppError::FileUploadError("Missing file field".to_string()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/cover",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 204, description = "Cover removed")),
+    tag = "decks"
+)]
+async fn delete_cover(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode> {
+    MediaService::delete_deck_cover(&state.db, &state.media, id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/statistics",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = DeckStatistics)),
+    tag = "decks"
+)]
+async fn get_statistics(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DeckStatistics>> {
+    let statistics = StatsService::deck_statistics(&state.db, id, user_id).await?;
+    Ok(Json(statistics))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/rating-scale",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = DeckRatingScale)),
+    tag = "decks"
+)]
+async fn get_rating_scale(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DeckRatingScale>> {
+    let scale = DeckService::get_rating_scale(&state.db, id, user_id).await?;
+    Ok(Json(scale))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}/rating-scale",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = UpdateRatingScaleDto,
+    responses((status = 200, body = DeckRatingScale)),
+    tag = "decks"
+)]
+async fn update_rating_scale(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<UpdateRatingScaleDto>,
+) -> Result<Json<DeckRatingScale>> {
+    dto.validate()?;
+
+    let scale = DeckService::set_rating_scale(&state.db, id, user_id, dto.buttons).await?;
+    Ok(Json(scale))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/settings",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = DeckSettings)),
+    tag = "decks"
+)]
+async fn get_settings(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DeckSettings>> {
+    let settings = DeckSettingsService::get_settings(&state.db, id, user_id).await?;
+    Ok(Json(settings))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}/settings",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = UpdateDeckSettingsDto,
+    responses((status = 200, body = DeckSettings)),
+    tag = "decks"
+)]
+async fn update_settings(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<UpdateDeckSettingsDto>,
+) -> Result<Json<DeckSettings>> {
+    dto.validate()?;
+
+    let settings = DeckSettingsService::update_settings(&state.db, id, user_id, dto).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    Ok(Json(settings))
+}
+
+/// Send the deck's ETag in `If-Match`, or its `updated_at` in the body, to
+/// get a 409 with the current deck instead of overwriting someone else's edit
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Deck id"),
+        ("if-match" = Option<String>, Header, description = "ETag of the deck the edit is based on"),
+    ),
+    request_body = UpdateDeckDto,
+    responses(
+        (status = 200, body = Deck),
+        (status = 409, description = "The deck changed since; `current` in the body is the deck as it is now", body = ErrorResponse),
+    ),
+    tag = "decks"
+)]
 async fn update_deck(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
-    Json(dto): Json<UpdateDeckDto>,
-) -> Result<Json<Deck>> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
-    
+    headers: HeaderMap,
+    Json(mut dto): Json<UpdateDeckDto>,
+) -> Result<Response> {
+    dto.validate()?;
+
+    if dto.updated_at.is_none() && headers.contains_key(header::IF_MATCH) {
+        let current = DeckService::get_deck(&state.db, id, user_id).await?;
+        let current_etag = etag::entity_etag("deck", current.id, current.updated_at);
+        if etag::if_match(&headers, &current_etag) == Some(false) {
+            return Err(AppError::edit_conflict("deck", current.id, current.updated_at, &current));
+        }
+        dto.updated_at = Some(current.updated_at);
+    }
+
     let deck = DeckService::update_deck(&state.db, id, user_id, dto).await?;
-    Ok(Json(deck))
+    let etag = etag::entity_etag("deck", deck.id, deck.updated_at);
+    Ok(([(header::ETAG, etag)], Json(deck)).into_response())
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 204, description = "Deck moved to the trash")),
+    tag = "decks"
+)]
 async fn delete_deck(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode> {
     DeckService::delete_deck(&state.db, id, user_id).await?;
+    state.queue_cache.invalidate_user(user_id).await;
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/{id}/csv",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body(content = String, content_type = "text/csv"),
+    responses((status = 200, description = "Created cards and their count", body = serde_json::Value)),
+    tag = "decks"
+)]
 async fn import_csv(
     State(state): State<AppState>,
-    UserId(user_id): UserId,
+    VerifiedUser(user_id): VerifiedUser,
     Path(id): Path<Uuid>,
     body: String,
 ) -> Result<Json<serde_json::Value>> {
     let cards = DeckService::import_csv(&state.db, id, user_id, body).await?;
-    
+    state.queue_cache.invalidate_user(user_id).await;
+    for card in &cards {
+        WebhookService::dispatch(state.db.clone(), id, "card.created", serde_json::json!(card));
+    }
+
     Ok(Json(serde_json::json!({
         "message": "CSV imported successfully",
         "cards_created": cards.len(),
@@ -99,6 +439,13 @@ async fn import_csv(
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}/csv",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, description = "Deck as CSV", content_type = "text/csv", body = String)),
+    tag = "decks"
+)]
 async fn export_csv(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -122,3 +469,476 @@ async fn export_csv(
     )
         .into_response())
 }
+
+#[utoipa::path(
+    get,
+    path = "/{id}/encryption",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = DeckEncryption)),
+    tag = "decks"
+)]
+async fn get_encryption(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DeckEncryption>> {
+    let metadata = EncryptionService::get_metadata(&state.db, id, user_id).await?;
+    Ok(Json(metadata))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}/encryption",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = EncryptDeckDto,
+    responses((status = 200, body = DeckEncryption)),
+    tag = "decks"
+)]
+async fn encrypt_deck(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<EncryptDeckDto>,
+) -> Result<Json<DeckEncryption>> {
+    dto.validate()?;
+
+    let metadata = EncryptionService::encrypt_deck(&state.db, id, user_id, dto).await?;
+    Ok(Json(metadata))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/encryption/disable",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = DecryptDeckDto,
+    responses((status = 204, description = "Deck stored as plaintext again")),
+    tag = "decks"
+)]
+async fn decrypt_deck(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<DecryptDeckDto>,
+) -> Result<StatusCode> {
+    EncryptionService::decrypt_deck(&state.db, id, user_id, dto).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/shares",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = Vec<DeckShare>)),
+    tag = "decks"
+)]
+async fn list_shares(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DeckShare>>> {
+    let shares = SharingService::list_shares(&state.db, id, user_id).await?;
+    Ok(Json(shares))
+}
+
+/// Share with an email address; addresses without an account get the share
+/// when they sign up
+#[utoipa::path(
+    post,
+    path = "/{id}/shares",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = ShareDeckDto,
+    responses((status = 202, body = DeckShareInvite)),
+    tag = "decks"
+)]
+async fn share_deck(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<ShareDeckDto>,
+) -> Result<(StatusCode, Json<DeckShareInvite>)> {
+    dto.validate()?;
+
+    let invite = SharingService::share_with_user(&state.db, id, user_id, dto).await?;
+    Ok((StatusCode::ACCEPTED, Json(invite)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/shares/{user_id}",
+    params(("id" = Uuid, Path, description = "Deck id"), ("user_id" = Uuid, Path, description = "Collaborator user id")),
+    responses((status = 204, description = "Share revoked")),
+    tag = "decks"
+)]
+async fn revoke_share(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((id, target_user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    SharingService::revoke_share(&state.db, id, user_id, target_user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/share-links",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = Vec<DeckShareLink>)),
+    tag = "decks"
+)]
+async fn list_share_links(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DeckShareLink>>> {
+    let links = SharingService::list_links(&state.db, id, user_id).await?;
+    Ok(Json(links))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/share-links",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = CreateShareLinkDto,
+    responses((status = 201, body = DeckShareLink)),
+    tag = "decks"
+)]
+async fn create_share_link(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<CreateShareLinkDto>,
+) -> Result<(StatusCode, Json<DeckShareLink>)> {
+    dto.validate()?;
+
+    let link = SharingService::create_link(&state.db, id, user_id, dto).await?;
+    Ok((StatusCode::CREATED, Json(link)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/share-links/{link_id}",
+    params(("id" = Uuid, Path, description = "Deck id"), ("link_id" = Uuid, Path, description = "Share link id")),
+    responses((status = 204, description = "Link revoked")),
+    tag = "decks"
+)]
+async fn revoke_share_link(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((id, link_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    SharingService::revoke_link(&state.db, id, user_id, link_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/guest-tokens",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = Vec<DeckGuestToken>)),
+    tag = "decks"
+)]
+async fn list_guest_tokens(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DeckGuestToken>>> {
+    let tokens = GuestService::list_tokens(&state.db, id, user_id).await?;
+    Ok(Json(tokens))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/guest-tokens",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body(content = Option<CreateGuestTokenDto>),
+    responses((status = 201, body = DeckGuestToken)),
+    tag = "decks"
+)]
+async fn create_guest_token(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    dto: Option<Json<CreateGuestTokenDto>>,
+) -> Result<(StatusCode, Json<DeckGuestToken>)> {
+    let Json(dto) = dto.unwrap_or_default();
+    dto.validate()?;
+
+    let token = GuestService::create_token(&state.db, id, user_id, dto).await?;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/guest-tokens/{token_id}",
+    params(("id" = Uuid, Path, description = "Deck id"), ("token_id" = Uuid, Path, description = "Guest token id")),
+    responses((status = 204, description = "Token revoked")),
+    tag = "decks"
+)]
+async fn revoke_guest_token(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((id, token_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    GuestService::revoke_token(&state.db, id, user_id, token_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/shared",
+    responses((status = 200, body = Vec<SharedDeck>)),
+    tag = "decks"
+)]
+async fn list_shared_decks(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<Vec<SharedDeck>>> {
+    let decks = SharingService::list_shared_with_user(&state.db, user_id).await?;
+    Ok(Json(decks))
+}
+
+/// Browse public decks, optionally filtered by tag, language or featured
+#[utoipa::path(
+    get,
+    path = "/public",
+    params(PublicDeckQuery, PaginationParams),
+    responses((status = 200, body = PaginatedResponse<PublicDeck>)),
+    tag = "decks"
+)]
+async fn list_public_decks(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<PublicDeckQuery>,
+    Query(mut pagination): Query<PaginationParams>,
+) -> Result<Json<PaginatedResponse<PublicDeck>>> {
+    pagination.validate();
+    let decks = MarketplaceService::list_public(&state.db, user_id, &query, &pagination).await?;
+    Ok(Json(decks))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/rate",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = RateDeckDto,
+    responses((status = 200, body = DeckRatingSummary)),
+    tag = "decks"
+)]
+async fn rate_deck(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<RateDeckDto>,
+) -> Result<Json<DeckRatingSummary>> {
+    dto.validate()?;
+
+    let summary = MarketplaceService::rate(&state.db, id, user_id, dto.rating).await?;
+    Ok(Json(summary))
+}
+
+/// Copy a public or shared deck into the caller's library
+#[utoipa::path(
+    post,
+    path = "/{id}/duplicate",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 201, description = "Copy in the caller's library", body = Deck)),
+    tag = "decks"
+)]
+async fn duplicate_deck(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<Deck>)> {
+    let deck = MarketplaceService::duplicate(&state.db, id, user_id).await?;
+    Ok((StatusCode::CREATED, Json(deck)))
+}
+
+/// Move the cards matching the filters into a new deck, keeping their
+/// scheduling state
+#[utoipa::path(
+    post,
+    path = "/{id}/split",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = SplitDeckDto,
+    responses((status = 201, description = "New deck and how many cards moved", body = SplitDeckResult)),
+    tag = "decks"
+)]
+async fn split_deck(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<SplitDeckDto>,
+) -> Result<(StatusCode, Json<SplitDeckResult>)> {
+    dto.validate()?;
+
+    let mut result = DeckMergeService::split(&state.db, id, user_id, dto).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    MediaService::sign_deck_cover(&state.media, &mut result.deck);
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+/// Combine decks into one, keeping scheduling state; the emptied decks go
+/// to the trash
+#[utoipa::path(
+    post,
+    path = "/merge",
+    request_body = MergeDecksDto,
+    responses((status = 200, body = MergeDecksResult)),
+    tag = "decks"
+)]
+async fn merge_decks(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Json(dto): Json<MergeDecksDto>,
+) -> Result<Json<MergeDecksResult>> {
+    dto.validate()?;
+
+    let mut result = DeckMergeService::merge(&state.db, user_id, dto).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    MediaService::sign_deck_cover(&state.media, &mut result.deck);
+    Ok(Json(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/share-links/{token}/accept",
+    params(("token" = String, Path, description = "Share link token")),
+    responses((status = 200, body = SharedDeck)),
+    tag = "decks"
+)]
+async fn accept_share_link(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(token): Path<String>,
+) -> Result<Json<SharedDeck>> {
+    let deck = SharingService::accept_link(&state.db, &token, user_id).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    Ok(Json(deck))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/webhooks",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = Vec<DeckWebhook>)),
+    tag = "decks"
+)]
+async fn list_webhooks(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DeckWebhook>>> {
+    let webhooks = WebhookService::list(&state.db, id, user_id).await?;
+    Ok(Json(webhooks))
+}
+
+/// Register a webhook; the signing secret is only returned here
+#[utoipa::path(
+    post,
+    path = "/{id}/webhooks",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body = CreateDeckWebhookDto,
+    responses((status = 201, description = "Webhook with its signing secret", body = CreatedDeckWebhook)),
+    tag = "decks"
+)]
+async fn create_webhook(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<CreateDeckWebhookDto>,
+) -> Result<(StatusCode, Json<CreatedDeckWebhook>)> {
+    dto.validate()?;
+
+    let webhook = WebhookService::create(&state.db, id, user_id, dto).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}/webhooks/{webhook_id}",
+    params(("id" = Uuid, Path, description = "Deck id"), ("webhook_id" = Uuid, Path, description = "Webhook id")),
+    request_body = UpdateDeckWebhookDto,
+    responses((status = 200, body = DeckWebhook)),
+    tag = "decks"
+)]
+async fn update_webhook(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((id, webhook_id)): Path<(Uuid, Uuid)>,
+    Json(dto): Json<UpdateDeckWebhookDto>,
+) -> Result<Json<DeckWebhook>> {
+    dto.validate()?;
+
+    let webhook = WebhookService::update(&state.db, id, webhook_id, user_id, dto).await?;
+    Ok(Json(webhook))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/webhooks/{webhook_id}",
+    params(("id" = Uuid, Path, description = "Deck id"), ("webhook_id" = Uuid, Path, description = "Webhook id")),
+    responses((status = 204, description = "Webhook deleted")),
+    tag = "decks"
+)]
+async fn delete_webhook(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((id, webhook_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    WebhookService::delete(&state.db, id, webhook_id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Generate a multiple-choice exam from the deck's cards
+#[utoipa::path(
+    post,
+    path = "/{id}/ai/quiz",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    request_body(content = Option<GenerateQuizDto>),
+    responses((status = 201, body = DeckExamWithQuestions)),
+    tag = "decks"
+)]
+async fn generate_quiz(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Path(id): Path<Uuid>,
+    dto: Option<Json<GenerateQuizDto>>,
+) -> Result<(StatusCode, Json<DeckExamWithQuestions>)> {
+    let Json(dto) = dto.unwrap_or_default();
+    dto.validate()?;
+
+    let exam = QuizService::generate(&state.db, &state.config.ai, &state.ai_breaker, id, user_id, dto).await?;
+    Ok((StatusCode::CREATED, Json(exam)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/ai/quiz",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = Vec<DeckExam>)),
+    tag = "decks"
+)]
+async fn list_quizzes(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DeckExam>>> {
+    let exams = QuizService::list_exams(&state.db, id, user_id).await?;
+    Ok(Json(exams))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/ai/quiz/{exam_id}",
+    params(("id" = Uuid, Path, description = "Deck id"), ("exam_id" = Uuid, Path, description = "Exam id")),
+    responses((status = 200, body = DeckExamWithQuestions)),
+    tag = "decks"
+)]
+async fn get_quiz(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path((id, exam_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<DeckExamWithQuestions>> {
+    let exam = QuizService::get_exam(&state.db, id, exam_id, user_id).await?;
+    Ok(Json(exam))
+}
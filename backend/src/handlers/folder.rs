@@ -1,28 +1,51 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    response::IntoResponse,
-    routing::{delete, get, patch, post},
+    routing::{get, post},
     Json, Router,
 };
+use utoipa::OpenApi;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     middleware::auth::UserId,
-    models::{CreateFolderDto, Folder, FolderWithContents, UpdateFolderDto},
-    services::folder::FolderService,
+    models::{
+        CreateFolderDto, Folder, FolderNode, FolderWithContents, MoveFolderDto, UpdateFolderDto,
+    },
+    services::{folder::FolderService, media::MediaService},
     state::AppState,
-    utils::{AppError, Result},
+    utils::{ErrorResponse, Result},
 };
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_folders).post(create_folder))
+        .route("/tree", get(get_folder_tree))
         .route("/:id", get(get_folder).patch(update_folder).delete(delete_folder))
+        .route("/:id/move", post(move_folder))
         .route("/:id/contents", get(get_folder_contents))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_folders,
+    create_folder,
+    get_folder_tree,
+    get_folder,
+    update_folder,
+    delete_folder,
+    move_folder,
+    get_folder_contents
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "",
+    responses((status = 200, body = Vec<Folder>)),
+    tag = "folders"
+)]
 async fn list_folders(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -31,62 +54,128 @@ async fn list_folders(
     Ok(Json(folders))
 }
 
+#[utoipa::path(
+    get,
+    path = "/tree",
+    responses((status = 200, description = "Top-level folders with nested children", body = Vec<FolderNode>)),
+    tag = "folders"
+)]
+async fn get_folder_tree(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<Vec<FolderNode>>> {
+    let tree = FolderService::get_tree(&state.db, user_id).await?;
+    Ok(Json(tree))
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = CreateFolderDto,
+    responses((status = 201, body = Folder)),
+    tag = "folders"
+)]
 async fn create_folder(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Json(dto): Json<CreateFolderDto>,
 ) -> Result<(StatusCode, Json<Folder>)> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    dto.validate()?;
     
     let folder = FolderService::create_folder(&state.db, user_id, dto).await?;
     Ok((StatusCode::CREATED, Json(folder)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Folder id")),
+    responses((status = 200, body = Folder)),
+    tag = "folders"
+)]
 async fn get_folder(
     State(state): State<AppState>,
+    UserId(user_id): UserId,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Folder>> {
-    // TODO: Get user_id from auth middleware and verify ownership
-    let user_id = Uuid::new_v4(); // Placeholder
-    
     let folder = FolderService::get_folder(&state.db, id, user_id).await?;
     Ok(Json(folder))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Folder id")),
+    request_body = UpdateFolderDto,
+    responses((status = 200, body = Folder)),
+    tag = "folders"
+)]
 async fn update_folder(
     State(state): State<AppState>,
+    UserId(user_id): UserId,
     Path(id): Path<Uuid>,
     Json(dto): Json<UpdateFolderDto>,
 ) -> Result<Json<Folder>> {
-    dto.validate()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
-    
-    // TODO: Get user_id from auth middleware and verify ownership
-    let user_id = Uuid::new_v4(); // Placeholder
+    dto.validate()?;
     
     let folder = FolderService::update_folder(&state.db, id, user_id, dto).await?;
     Ok(Json(folder))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{id}/move",
+    params(("id" = Uuid, Path, description = "Folder id")),
+    request_body = MoveFolderDto,
+    responses(
+        (status = 200, body = Folder),
+        (status = 400, description = "Move would create a cycle", body = ErrorResponse),
+    ),
+    tag = "folders"
+)]
+async fn move_folder(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<MoveFolderDto>,
+) -> Result<Json<Folder>> {
+    dto.validate()?;
+
+    let folder = FolderService::move_folder(&state.db, id, user_id, dto).await?;
+    Ok(Json(folder))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Folder id")),
+    responses((status = 204, description = "Folder and subfolders deleted; their decks moved to the trash")),
+    tag = "folders"
+)]
 async fn delete_folder(
     State(state): State<AppState>,
+    UserId(user_id): UserId,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode> {
-    // TODO: Get user_id from auth middleware and verify ownership
-    let user_id = Uuid::new_v4(); // Placeholder
-    
     FolderService::delete_folder(&state.db, id, user_id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}/contents",
+    params(("id" = Uuid, Path, description = "Folder id")),
+    responses((status = 200, body = FolderWithContents)),
+    tag = "folders"
+)]
 async fn get_folder_contents(
     State(state): State<AppState>,
+    UserId(user_id): UserId,
     Path(id): Path<Uuid>,
 ) -> Result<Json<FolderWithContents>> {
-    // TODO: Get user_id from auth middleware and verify ownership
-    let user_id = Uuid::new_v4(); // Placeholder
-    
-    let contents = FolderService::get_folder_with_contents(&state.db, id, user_id).await?;
+    let mut contents = FolderService::get_folder_with_contents(&state.db, id, user_id).await?;
+    for deck in &mut contents.decks {
+        MediaService::sign_deck_cover(&state.media, deck);
+    }
     Ok(Json(contents))
 }
@@ -0,0 +1,62 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Extension, Json, Router,
+};
+use utoipa::OpenApi;
+
+use crate::{
+    models::{Card, GuestAccess, GuestDeck, GuestStudyQuery},
+    services::guest::GuestService,
+    state::AppState,
+    utils::Result,
+};
+
+/// Read-only deck access for guest tokens; `create_app` wraps these in the
+/// guest token middleware
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/deck", get(get_deck))
+        .route("/study", get(study))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_deck,
+    study
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "/deck",
+    responses((status = 200, body = GuestDeck)),
+    security(("guest_token" = [])),
+    tag = "guest"
+)]
+async fn get_deck(
+    State(state): State<AppState>,
+    Extension(access): Extension<GuestAccess>,
+) -> Result<Json<GuestDeck>> {
+    let deck = GuestService::deck(&state.db, access).await?;
+    Ok(Json(deck))
+}
+
+/// Cards for a study run that is never saved
+#[utoipa::path(
+    get,
+    path = "/study",
+    params(GuestStudyQuery),
+    responses((status = 200, body = Vec<Card>)),
+    security(("guest_token" = [])),
+    tag = "guest"
+)]
+async fn study(
+    State(state): State<AppState>,
+    Extension(access): Extension<GuestAccess>,
+    Query(query): Query<GuestStudyQuery>,
+) -> Result<Json<Vec<Card>>> {
+    let cards =
+        GuestService::study_cards(&state.db, access, query.shuffle.unwrap_or(false)).await?;
+    Ok(Json(cards))
+}
@@ -5,11 +5,23 @@ use axum::{
     Json,
 };
 use serde::Serialize;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use utoipa::{OpenApi, ToSchema};
 
-use crate::state::AppState;
+use crate::{
+    services::{
+        migrations::{MigrationService, MigrationStatus},
+        queue_cache::QueueCacheStats,
+    },
+    state::AppState,
+    utils::Result,
+};
+
+#[derive(OpenApi)]
+#[openapi(paths(health, health_detailed, migrations, liveness, readiness))]
+pub struct ApiDoc;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct HealthCheck {
     status: String,
     timestamp: u64,
@@ -17,23 +29,40 @@ struct HealthCheck {
     database: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct HealthDetails {
     status: String,
     timestamp: u64,
     version: String,
     database: DatabaseHealth,
+    study_queue_cache: QueueCacheStats,
+    /// Seconds since the server started
     uptime: u64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct DatabaseHealth {
     status: String,
+    /// Milliseconds the `SELECT 1` check took
+    latency_ms: u64,
+    /// Most connections the pool will open
     pool_size: u32,
+    /// Connections currently open
+    connections: u32,
     idle_connections: usize,
+    /// Open connections checked out by requests
+    in_use_connections: u32,
+    acquire_timeout_seconds: u64,
 }
 
 /// Simple health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses((status = 200, body = HealthCheck)),
+    security(()),
+    tag = "health"
+)]
 pub async fn health() -> impl IntoResponse {
     Json(HealthCheck {
         status: "ok".to_string(),
@@ -47,15 +76,25 @@ pub async fn health() -> impl IntoResponse {
 }
 
 /// Detailed health check with database status
+#[utoipa::path(
+    get,
+    path = "/health/detailed",
+    responses((status = 200, body = HealthDetails)),
+    security(()),
+    tag = "health"
+)]
 pub async fn health_detailed(State(state): State<AppState>) -> impl IntoResponse {
     // Check database connection
+    let started = Instant::now();
     let db_status = match sqlx::query("SELECT 1").fetch_one(&state.db).await {
         Ok(_) => "healthy",
         Err(_) => "unhealthy",
     };
+    let latency_ms = started.elapsed().as_millis() as u64;
 
     let pool_options = state.db.options();
     let pool_size = pool_options.get_max_connections();
+    let connections = state.db.size();
     let idle_connections = state.db.num_idle();
 
     Json(HealthDetails {
@@ -67,19 +106,54 @@ pub async fn health_detailed(State(state): State<AppState>) -> impl IntoResponse
         version: env!("CARGO_PKG_VERSION").to_string(),
         database: DatabaseHealth {
             status: db_status.to_string(),
+            latency_ms,
             pool_size,
+            connections,
             idle_connections,
+            in_use_connections: connections.saturating_sub(idle_connections as u32),
+            acquire_timeout_seconds: pool_options.get_acquire_timeout().as_secs(),
         },
-        uptime: 0, // Would need to track server start time for real uptime
+        study_queue_cache: state.queue_cache.stats().await,
+        uptime: state.started_at.elapsed().as_secs(),
     })
 }
 
+/// Applied and pending schema migrations, for operators checking a deploy
+#[utoipa::path(
+    get,
+    path = "/health/migrations",
+    responses((status = 200, body = MigrationStatus)),
+    security(()),
+    tag = "health"
+)]
+pub async fn migrations(State(state): State<AppState>) -> Result<Json<MigrationStatus>> {
+    let status = MigrationService::status(&state.db).await?;
+    Ok(Json(status))
+}
+
 /// Liveness probe for Kubernetes
+#[utoipa::path(
+    get,
+    path = "/liveness",
+    responses((status = 200, description = "Process is up")),
+    security(()),
+    tag = "health"
+)]
 pub async fn liveness() -> StatusCode {
     StatusCode::OK
 }
 
 /// Readiness probe for Kubernetes - checks database
+#[utoipa::path(
+    get,
+    path = "/readiness",
+    responses(
+        (status = 200, description = "Database reachable"),
+        (status = 503, description = "Database unreachable"),
+    ),
+    security(()),
+    tag = "health"
+)]
 pub async fn readiness(State(state): State<AppState>) -> StatusCode {
     match sqlx::query("SELECT 1").fetch_one(&state.db).await {
         Ok(_) => StatusCode::OK,
@@ -1,74 +1,137 @@
 use axum::{
-    body::Bytes,
-    extract::{Multipart, Path, Query, State},
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
     http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
-    middleware::auth::UserId,
+    middleware::auth::{UserId, VerifiedUser},
     models::import_export::*,
-    services::import_export::ImportExportService,
+    services::{exporters::Exporter, import_export::ImportExportService, job::JobService, upload},
     state::AppState,
-    utils::Result,
+    utils::{AppError, Result},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ExportQuery {
-    format: ExportFormat,
+    format: String,
     include_progress: Option<bool>,
     include_media: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct BulkExportQuery {
     deck_ids: String, // Comma-separated UUIDs
-    format: ExportFormat,
+    format: String,
     include_progress: Option<bool>,
     include_media: Option<bool>,
 }
 
+/// Multipart form of `import_deck`; documentation only
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct ImportForm {
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+    format: ImportFormat,
+    /// Quizlet text exports only; detected when omitted
+    term_delimiter: Option<String>,
+    /// Quizlet text exports only; detected when omitted
+    row_delimiter: Option<String>,
+    folder_id: Option<Uuid>,
+    merge_duplicates: Option<bool>,
+    include_progress: Option<bool>,
+    duplicate_strategy: Option<DuplicateStrategy>,
+    /// Anki text exports only
+    html: Option<HtmlHandling>,
+}
+
+/// Multipart form of `validate_import`; documentation only
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct ValidateImportForm {
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+    format: ImportFormat,
+    term_delimiter: Option<String>,
+    row_delimiter: Option<String>,
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
+        .route("/export/formats", get(list_export_formats))
         .route("/export/:deck_id", get(export_deck))
         .route("/export/bulk", get(export_bulk))
-        .route("/import", post(import_deck))
-        .route("/import/validate", post(validate_import))
+        // Uploads are capped at MAX_FILE_SIZE while streaming instead
+        .route("/import", post(import_deck.layer(DefaultBodyLimit::disable())))
+        .route("/import/validate", post(validate_import.layer(DefaultBodyLimit::disable())))
         .route("/templates/:format", get(get_import_template))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_export_formats,
+    export_deck,
+    export_bulk,
+    import_deck,
+    validate_import,
+    get_import_template
+))]
+pub struct ApiDoc;
+
+// List the export formats available in this build
+#[utoipa::path(
+    get,
+    path = "/export/formats",
+    responses((status = 200, body = Vec<ExportFormatInfo>)),
+    tag = "import-export"
+)]
+async fn list_export_formats(
+    State(state): State<AppState>,
+    UserId(_user_id): UserId,
+) -> Json<Vec<ExportFormatInfo>> {
+    Json(state.exporters.formats())
+}
+
 // Export a single deck
+#[utoipa::path(
+    get,
+    path = "/export/{deck_id}",
+    params(("deck_id" = Uuid, Path, description = "Deck id"), ExportQuery),
+    responses((status = 200, description = "Deck file in the requested format, sent as an attachment", content_type = "application/octet-stream", body = Vec<u8>)),
+    tag = "import-export"
+)]
 async fn export_deck(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(deck_id): Path<Uuid>,
     Query(query): Query<ExportQuery>,
 ) -> Result<Response> {
+    let exporter = resolve_exporter(&state, &query.format)?;
     let data = ImportExportService::export_deck(
         &state.db,
+        &state.media,
         user_id,
         deck_id,
-        query.format.clone(),
+        exporter.as_ref(),
         query.include_progress.unwrap_or(false),
         query.include_media.unwrap_or(false),
     )
     .await?;
 
-    let (content_type, file_extension) = match query.format {
-        ExportFormat::Json => ("application/json", "json"),
-        ExportFormat::Csv => ("text/csv", "csv"),
-        ExportFormat::Anki => ("application/json", "json"), // Would be .apkg in production
-        ExportFormat::Markdown => ("text/markdown", "md"),
-    };
-
-    let filename = format!("deck_{}.{}", deck_id, file_extension);
+    let filename = format!("deck_{}.{}", deck_id, exporter.extension());
     
     let mut headers = HeaderMap::new();
-    headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+    headers.insert(header::CONTENT_TYPE, exporter.content_type().parse().unwrap());
     headers.insert(
         header::CONTENT_DISPOSITION,
         format!("attachment; filename=\"{}\"", filename).parse().unwrap(),
@@ -78,6 +141,13 @@ async fn export_deck(
 }
 
 // Export multiple decks
+#[utoipa::path(
+    get,
+    path = "/export/bulk",
+    params(BulkExportQuery),
+    responses((status = 200, description = "Zip archive with one file per deck", content_type = "application/zip", body = Vec<u8>)),
+    tag = "import-export"
+)]
 async fn export_bulk(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -99,45 +169,55 @@ async fn export_bulk(
         ).into_response());
     }
 
-    let data = ImportExportService::export_decks(
-        &state.db,
+    let exporter = resolve_exporter(&state, &query.format)?;
+    let archive = ImportExportService::export_decks(
+        state.db.clone(),
+        state.media.clone(),
         user_id,
         deck_ids,
-        query.format.clone(),
+        exporter,
         query.include_progress.unwrap_or(false),
         query.include_media.unwrap_or(false),
     )
     .await?;
 
-    let (content_type, file_extension) = match query.format {
-        ExportFormat::Json => ("application/json", "json"),
-        ExportFormat::Csv => ("text/csv", "csv"),
-        ExportFormat::Anki => ("application/json", "json"),
-        ExportFormat::Markdown => ("text/markdown", "md"),
-    };
-
-    let filename = format!("decks_export.{}", file_extension);
-    
     let mut headers = HeaderMap::new();
-    headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+    headers.insert(header::CONTENT_TYPE, "application/zip".parse().unwrap());
     headers.insert(
         header::CONTENT_DISPOSITION,
-        format!("attachment; filename=\"{}\"", filename).parse().unwrap(),
+        "attachment; filename=\"decks_export.zip\"".parse().unwrap(),
     );
 
-    Ok((StatusCode::OK, headers, data).into_response())
+    Ok((StatusCode::OK, headers, Body::from_stream(archive)).into_response())
+}
+
+fn resolve_exporter(state: &AppState, format: &str) -> Result<Arc<dyn Exporter>> {
+    state.exporters.get(format).ok_or_else(|| {
+        AppError::BadRequest(format!("Unsupported export format: {}", format))
+    })
 }
 
 // Import deck from uploaded file
+#[utoipa::path(
+    post,
+    path = "/import",
+    request_body(content = ImportForm, content_type = "multipart/form-data"),
+    responses((status = 200, body = ImportResult)),
+    tag = "import-export"
+)]
 async fn import_deck(
     State(state): State<AppState>,
-    UserId(user_id): UserId,
+    VerifiedUser(user_id): VerifiedUser,
     mut multipart: Multipart,
 ) -> Result<Json<ImportResult>> {
-    let mut file_data: Option<Vec<u8>> = None;
+    let mut file: Option<upload::SpooledFile> = None;
     let mut format: Option<ImportFormat> = None;
     let mut folder_id: Option<Uuid> = None;
     let mut merge_duplicates = false;
+    let mut include_progress = false;
+    let mut duplicate_strategy = DuplicateStrategy::default();
+    let mut html = HtmlHandling::default();
+    let mut delimiters = TextDelimiters::default();
 
     // Process multipart form data
     while let Some(field) = multipart.next_field().await? {
@@ -145,18 +225,17 @@ async fn import_deck(
         
         match name.as_str() {
             "file" => {
-                let data = field.bytes().await?;
-                file_data = Some(data.to_vec());
+                file = Some(upload::SpooledFile::read(field, &state.config.upload).await?);
             }
             "format" => {
                 let value = field.text().await?;
-                format = match value.as_str() {
-                    "json" => Some(ImportFormat::Json),
-                    "csv" => Some(ImportFormat::Csv),
-                    "anki" => Some(ImportFormat::Anki),
-                    "markdown" => Some(ImportFormat::Markdown),
-                    _ => None,
-                };
+                format = ImportFormat::from_name(&value);
+            }
+            "term_delimiter" => {
+                delimiters.term = Some(field.text().await?).filter(|value| !value.is_empty());
+            }
+            "row_delimiter" => {
+                delimiters.row = Some(field.text().await?).filter(|value| !value.is_empty());
             }
             "folder_id" => {
                 let value = field.text().await?;
@@ -166,11 +245,30 @@ async fn import_deck(
                 let value = field.text().await?;
                 merge_duplicates = value.parse().unwrap_or(false);
             }
+            "include_progress" => {
+                let value = field.text().await?;
+                include_progress = value.parse().unwrap_or(false);
+            }
+            "duplicate_strategy" => {
+                let value = field.text().await?;
+                duplicate_strategy = DuplicateStrategy::from_name(&value).ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "Unknown duplicate_strategy: {} (expected skip, overwrite or keep_both)",
+                        value
+                    ))
+                })?;
+            }
+            "html" => {
+                let value = field.text().await?;
+                html = HtmlHandling::from_name(&value).ok_or_else(|| {
+                    AppError::BadRequest(format!("Unknown html: {} (expected sanitize or strip)", value))
+                })?;
+            }
             _ => {}
         }
     }
 
-    let file_data = file_data.ok_or_else(|| {
+    let file = file.ok_or_else(|| {
         crate::utils::error::AppError::BadRequest("No file provided".to_string())
     })?;
     
@@ -178,27 +276,83 @@ async fn import_deck(
         crate::utils::error::AppError::BadRequest("No format specified".to_string())
     })?;
 
-    let result = ImportExportService::import_decks(
-        &state.db,
-        user_id,
-        file_data,
+    // Reject files that are not what the format says before parsing them;
+    // the head is enough to tell, and the file is parsed where it was spooled
+    let head = file.head(upload::SNIFF_BYTES).await?;
+    upload::check_import(
+        &state.config.upload,
+        &format,
+        file.filename.as_deref(),
+        file.content_type.as_deref(),
+        &head,
+    )?;
+
+    // Track the import in the job center
+    let params = ImportJobParameters {
         format,
         folder_id,
         merge_duplicates,
+        delimiters,
+        include_progress,
+        duplicate_strategy,
+        html,
+    };
+    let job = JobService::create_job(
+        &state.db,
+        user_id,
+        "import",
+        serde_json::to_value(&params)?,
+        // Kept for retries unless it was too large to hold in memory
+        file.bytes(),
+        true,
     )
     .await?;
+    JobService::mark_processing(&state.db, job.id).await?;
+
+    // Cancelling the job from the job center stops the import and rolls it back
+    let result = match ImportExportService::import_decks(
+        &state.db,
+        &state.media,
+        user_id,
+        file.source(),
+        &params,
+        Some(job.id),
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            JobService::fail_job(&state.db, job.id, &e.to_string()).await?;
+            return Err(e);
+        }
+    };
+    state.queue_cache.invalidate_user(user_id).await;
+
+    let result_url = result
+        .imported_decks
+        .first()
+        .map(|deck| format!("/api/v1/decks/{}", deck.id));
+    JobService::complete_job(&state.db, job.id, serde_json::to_value(&result)?, result_url).await?;
 
     Ok(Json(result))
 }
 
 // Validate import file without actually importing
+#[utoipa::path(
+    post,
+    path = "/import/validate",
+    request_body(content = ValidateImportForm, content_type = "multipart/form-data"),
+    responses((status = 200, body = ImportValidationResult)),
+    tag = "import-export"
+)]
 async fn validate_import(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     mut multipart: Multipart,
 ) -> Result<Json<ImportValidationResult>> {
-    let mut file_data: Option<Vec<u8>> = None;
+    let mut file: Option<upload::SpooledFile> = None;
     let mut format: Option<ImportFormat> = None;
+    let mut delimiters = TextDelimiters::default();
 
     // Process multipart form data
     while let Some(field) = multipart.next_field().await? {
@@ -206,24 +360,23 @@ async fn validate_import(
         
         match name.as_str() {
             "file" => {
-                let data = field.bytes().await?;
-                file_data = Some(data.to_vec());
+                file = Some(upload::SpooledFile::read(field, &state.config.upload).await?);
             }
             "format" => {
                 let value = field.text().await?;
-                format = match value.as_str() {
-                    "json" => Some(ImportFormat::Json),
-                    "csv" => Some(ImportFormat::Csv),
-                    "anki" => Some(ImportFormat::Anki),
-                    "markdown" => Some(ImportFormat::Markdown),
-                    _ => None,
-                };
+                format = ImportFormat::from_name(&value);
+            }
+            "term_delimiter" => {
+                delimiters.term = Some(field.text().await?).filter(|value| !value.is_empty());
+            }
+            "row_delimiter" => {
+                delimiters.row = Some(field.text().await?).filter(|value| !value.is_empty());
             }
             _ => {}
         }
     }
 
-    let file_data = file_data.ok_or_else(|| {
+    let file = file.ok_or_else(|| {
         crate::utils::error::AppError::BadRequest("No file provided".to_string())
     })?;
     
@@ -231,13 +384,31 @@ async fn validate_import(
         crate::utils::error::AppError::BadRequest("No format specified".to_string())
     })?;
 
+    // Reject files that are not what the format says before parsing them;
+    // the head is enough to tell, and the file is parsed where it was spooled
+    let head = file.head(upload::SNIFF_BYTES).await?;
+    upload::check_import(
+        &state.config.upload,
+        &format,
+        file.filename.as_deref(),
+        file.content_type.as_deref(),
+        &head,
+    )?;
+
     // Use the validate_import function from the service
-    let validation = ImportExportService::validate_import(&file_data, &format)?;
+    let validation = ImportExportService::validate_import(file.source(), &format, &delimiters)?;
     
     Ok(Json(validation))
 }
 
 // Get import template for a specific format
+#[utoipa::path(
+    get,
+    path = "/templates/{format}",
+    params(("format" = String, Path, description = "json, csv, markdown, quizlet or anki_text")),
+    responses((status = 200, description = "Sample file to import", content_type = "application/octet-stream", body = Vec<u8>)),
+    tag = "import-export"
+)]
 async fn get_import_template(
     Path(format): Path<String>,
 ) -> Result<Response> {
@@ -285,6 +456,24 @@ async fn get_import_template(
                 ---\n";
             (template.to_vec(), "text/markdown", "md")
         }
+        "quizlet" => {
+            // Quizlet's default text export: tab between term and definition,
+            // one term per line
+            let template = b"Question 1\tAnswer 1\n\
+                Question 2\tAnswer 2\n";
+            (template.to_vec(), "text/plain", "txt")
+        }
+        "anki_text" => {
+            // Anki's Notes in Plain Text export, as written by Anki itself
+            let template = b"#separator:tab\n\
+                #html:true\n\
+                #notetype:Basic\n\
+                #deck:Sample Deck\n\
+                #columns:Front\tBack\n\
+                Question 1\tAnswer 1\n\
+                <b>Question 2</b>\tAnswer 2<br>on two lines\n";
+            (template.to_vec(), "text/tab-separated-values", "txt")
+        }
         _ => {
             return Ok((
                 StatusCode::BAD_REQUEST,
@@ -0,0 +1,99 @@
+use axum::{
+    extract::{FromRequest, Multipart, Request, State},
+    http::{header, StatusCode},
+    routing::post,
+    Form, Json, Router,
+};
+use serde_json::{json, Map, Value};
+use utoipa::OpenApi;
+
+use crate::{
+    models::InboundEmail,
+    services::{
+        email_inbox::{EmailInboxService, InboundOutcome},
+        webhook::WebhookService,
+    },
+    state::AppState,
+    utils::{AppError, ErrorResponse, Result},
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/email", post(receive_email))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    receive_email
+))]
+pub struct ApiDoc;
+
+/// Inbound email webhook. Mailgun posts either urlencoded or multipart forms
+/// depending on whether the message has attachments; attachments are ignored.
+#[utoipa::path(
+    post,
+    path = "/email",
+    request_body(content = InboundEmail, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Card created, or the message was a duplicate", body = serde_json::Value),
+        (status = 406, description = "Message rejected; the provider should not retry", body = serde_json::Value),
+        (status = 401, description = "Invalid webhook signature", body = ErrorResponse),
+    ),
+    security(()),
+    tag = "inbound"
+)]
+async fn receive_email(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<(StatusCode, Json<Value>)> {
+    let email = parse_email(&state, request).await?;
+    EmailInboxService::verify_signature(&state.config.inbound_email, &email)?;
+
+    let response = match EmailInboxService::receive(&state.db, &state.config.inbound_email, &email)
+        .await?
+    {
+        InboundOutcome::Created(card) => {
+            WebhookService::dispatch(state.db.clone(), card.deck_id, "card.created", json!(card));
+            (StatusCode::OK, Json(json!({ "status": "created", "card_id": card.id })))
+        }
+        InboundOutcome::Duplicate => (StatusCode::OK, Json(json!({ "status": "duplicate" }))),
+        // 406 tells Mailgun not to retry
+        InboundOutcome::Rejected(reason) => (
+            StatusCode::NOT_ACCEPTABLE,
+            Json(json!({ "status": "rejected", "reason": reason })),
+        ),
+    };
+    Ok(response)
+}
+
+async fn parse_email(state: &AppState, request: Request) -> Result<InboundEmail> {
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("multipart/form-data"));
+
+    if !is_multipart {
+        let Form(email) = Form::<InboundEmail>::from_request(request, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?;
+        return Ok(email);
+    }
+
+    let mut multipart = Multipart::from_request(request, state)
+        .await
+        .map_err(|e| AppError::BadRequest(e.body_text()))?;
+
+    let mut fields = Map::new();
+    while let Some(field) = multipart.next_field().await? {
+        if field.file_name().is_some() {
+            continue;
+        }
+        let Some(name) = field.name().map(str::to_string) else {
+            continue;
+        };
+        fields.insert(name, Value::String(field.text().await?));
+    }
+
+    serde_json::from_value(Value::Object(fields))
+        .map_err(|e| AppError::BadRequest(format!("Invalid inbound email: {}", e)))
+}
@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::UserId,
+    models::job::{JobSummary, JobsQuery},
+    services::job::JobService,
+    state::AppState,
+    utils::Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_jobs))
+        .route("/:id", get(get_job))
+        .route("/:id/cancel", post(cancel_job))
+        .route("/:id/retry", post(retry_job))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_jobs,
+    get_job,
+    cancel_job,
+    retry_job
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "",
+    params(JobsQuery),
+    responses((status = 200, body = Vec<JobSummary>)),
+    tag = "jobs"
+)]
+async fn list_jobs(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<JobsQuery>,
+) -> Result<Json<Vec<JobSummary>>> {
+    let jobs = JobService::list_user_jobs(&state.db, user_id, &query).await?;
+    Ok(Json(jobs))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Job id")),
+    responses((status = 200, body = JobSummary)),
+    tag = "jobs"
+)]
+async fn get_job(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobSummary>> {
+    let job = JobService::get_user_job(&state.db, id, user_id).await?;
+    Ok(Json(job))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/cancel",
+    params(("id" = Uuid, Path, description = "Job id")),
+    responses((status = 200, body = JobSummary)),
+    tag = "jobs"
+)]
+async fn cancel_job(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobSummary>> {
+    let job = JobService::cancel_job(&state.db, id, user_id).await?;
+    Ok(Json(job))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/retry",
+    params(("id" = Uuid, Path, description = "Job id")),
+    responses((status = 202, description = "Job queued again", body = JobSummary)),
+    tag = "jobs"
+)]
+async fn retry_job(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<JobSummary>)> {
+    let job = JobService::retry_job(
+        &state.db,
+        &state.media,
+        &state.queue_cache,
+        &state.config.ai,
+        id,
+        user_id,
+    )
+    .await?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use utoipa::{IntoParams, OpenApi};
+
+use crate::{
+    services::storage::media_content_type,
+    state::AppState,
+    utils::{AppError, ErrorResponse, Result},
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SignedMediaQuery {
+    expires: i64,
+    signature: String,
+}
+
+/// Downloads for signed media URLs issued when the storage backend cannot
+/// presign its own. The signature is the credential, so these are mounted
+/// outside authentication and rate limited per client IP.
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/*key", get(download_media))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    download_media
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "/{key}",
+    params(("key" = String, Path, description = "Storage key of the file"), SignedMediaQuery),
+    responses(
+        (status = 200, description = "Media file", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 403, description = "Invalid or expired signature", body = ErrorResponse),
+    ),
+    security(()),
+    tag = "media"
+)]
+async fn download_media(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<SignedMediaQuery>,
+) -> Result<Response> {
+    if !state.media.verify_signature(&key, query.expires, &query.signature) {
+        return Err(AppError::Forbidden);
+    }
+
+    let data = state.media.get(&key).await?;
+    let content_type = media_content_type(&key).unwrap_or("application/octet-stream");
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (header::CACHE_CONTROL, HeaderValue::from_static("private, max-age=300")),
+        ],
+        data,
+    )
+        .into_response())
+}
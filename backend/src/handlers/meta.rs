@@ -0,0 +1,101 @@
+use axum::{extract::State, Json};
+use serde::Serialize;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    models::import_export::{ExportFormatInfo, ImportFormat},
+    services::exporters::JSON_FORMAT_VERSION,
+    state::AppState,
+};
+
+/// Major version of the REST API, matching the `/api/v1` prefix
+const API_VERSION: &str = "v1";
+
+#[derive(OpenApi)]
+#[openapi(paths(meta))]
+pub struct ApiDoc;
+
+#[derive(Serialize, ToSchema)]
+struct ApiMeta {
+    api_version: &'static str,
+    server_version: &'static str,
+    formats: FormatMeta,
+    features: FeatureFlags,
+    limits: Limits,
+}
+
+#[derive(Serialize, ToSchema)]
+struct FormatMeta {
+    import: Vec<&'static str>,
+    export: Vec<ExportFormatInfo>,
+    /// Version of the native JSON deck format, written on export and
+    /// accepted on import
+    json_version: &'static str,
+}
+
+#[derive(Serialize, ToSchema)]
+struct FeatureFlags {
+    ai_generation: bool,
+    pdf_export: bool,
+    encrypted_decks: bool,
+    webhooks: bool,
+    websockets: bool,
+    rate_limiting: bool,
+    email_capture: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+struct Limits {
+    max_upload_bytes: usize,
+    allowed_upload_types: Vec<String>,
+    allowed_media_types: Vec<String>,
+    /// `null` when decks are unbounded
+    max_cards_per_deck: Option<u32>,
+    max_ai_cards_per_request: i32,
+    /// `null` when rate limiting is disabled
+    api_requests_per_minute: Option<u32>,
+}
+
+/// Versions, formats, features and limits of this server, so clients can
+/// negotiate instead of hardcoding them
+#[utoipa::path(
+    get,
+    path = "/meta",
+    responses((status = 200, body = ApiMeta)),
+    security(()),
+    tag = "meta"
+)]
+pub async fn meta(State(state): State<AppState>) -> Json<ApiMeta> {
+    let config = &state.config;
+    let rate_limit = &config.rate_limit;
+
+    Json(ApiMeta {
+        api_version: API_VERSION,
+        server_version: env!("CARGO_PKG_VERSION"),
+        formats: FormatMeta {
+            import: ImportFormat::ALL.iter().map(ImportFormat::name).collect(),
+            export: state.exporters.formats(),
+            json_version: JSON_FORMAT_VERSION,
+        },
+        features: FeatureFlags {
+            ai_generation: config.ai.enabled,
+            pdf_export: cfg!(feature = "pdf-export"),
+            encrypted_decks: true,
+            webhooks: true,
+            websockets: true,
+            rate_limiting: rate_limit.enabled,
+            email_capture: config.inbound_email.domain.is_some()
+                && config.inbound_email.signing_key.is_some(),
+        },
+        limits: Limits {
+            max_upload_bytes: config.upload.max_file_size,
+            allowed_upload_types: config.upload.allowed_file_types.clone(),
+            allowed_media_types: config.upload.allowed_media_types.clone(),
+            max_cards_per_deck: None,
+            max_ai_cards_per_request: config.ai.content_generation.max_cards_per_batch,
+            api_requests_per_minute: rate_limit
+                .enabled
+                .then_some(rate_limit.api_requests_per_minute),
+        },
+    })
+}
@@ -1,6 +1,7 @@
 pub mod auth;
 pub mod deck;
 pub mod card;
+pub mod smart_deck;
 pub mod folder;
 pub mod study;
 pub mod progress;
@@ -8,3 +9,16 @@ pub mod import_export;
 pub mod health;
 pub mod search;
 pub mod ai;
+pub mod job;
+pub mod admin;
+pub mod ws;
+pub mod public;
+pub mod meta;
+pub mod capture;
+pub mod inbound;
+pub mod user;
+pub mod guest;
+pub mod trash;
+pub mod media;
+pub mod sync;
+pub mod dashboard;
@@ -5,22 +5,32 @@ use axum::{
 };
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
     middleware::auth::UserId,
+    models::{
+        ai::UserLearningStats, HeatmapQuery, Leaderboard, LeaderboardQuery, LeechCard, LeechesQuery,
+        StudyHeatmap,
+    },
+    services::{
+        leaderboard::LeaderboardService, learning_stats::LearningStatsService, leech::LeechService,
+        stats::StatsService,
+    },
     state::AppState,
     utils::Result,
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ProgressQuery {
     deck_id: Option<Uuid>,
     start_date: Option<DateTime<Utc>>,
     end_date: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ProgressOverview {
     total_cards_studied: i64,
     total_study_time_minutes: i64,
@@ -30,7 +40,7 @@ struct ProgressOverview {
     decks_in_progress: i64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct DeckProgress {
     deck_id: Uuid,
     deck_name: String,
@@ -43,7 +53,7 @@ struct DeckProgress {
     mastery_percentage: f64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct CardPerformance {
     card_id: Uuid,
     front: String,
@@ -56,7 +66,7 @@ struct CardPerformance {
     difficulty_score: f64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct LearningCurve {
     date: DateTime<Utc>,
     cards_studied: i64,
@@ -64,7 +74,7 @@ struct LearningCurve {
     study_time_minutes: i64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct StudyStreak {
     current_streak: i32,
     longest_streak: i32,
@@ -72,7 +82,7 @@ struct StudyStreak {
     study_days: Vec<DateTime<Utc>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct WeeklyProgress {
     week_start: DateTime<Utc>,
     total_cards_studied: i64,
@@ -91,8 +101,35 @@ pub fn routes() -> Router<AppState> {
         .route("/learning-curve", get(get_learning_curve))
         .route("/streaks", get(get_study_streaks))
         .route("/weekly", get(get_weekly_progress))
+        .route("/heatmap", get(get_heatmap))
+        .route("/leeches", get(list_leeches))
+        .route("/leaderboard", get(get_leaderboard))
+        .route("/learning-stats", get(get_learning_stats))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_progress_overview,
+    get_deck_progress,
+    get_specific_deck_progress,
+    get_card_performance,
+    get_learning_curve,
+    get_study_streaks,
+    get_weekly_progress,
+    get_heatmap,
+    list_leeches,
+    get_leaderboard,
+    get_learning_stats
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "/overview",
+    params(ProgressQuery),
+    responses((status = 200, body = ProgressOverview)),
+    tag = "progress"
+)]
 async fn get_progress_overview(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -107,13 +144,13 @@ async fn get_progress_overview(
                 THEN EXTRACT(EPOCH FROM (ss.completed_at - ss.started_at)) / 60
                 ELSE 0 END)::bigint, 0) as "total_study_time_minutes!",
             COALESCE(AVG(CASE 
-                WHEN cp.status = 'easy' THEN 100.0
-                WHEN cp.status = 'medium' THEN 75.0
-                WHEN cp.status = 'hard' THEN 50.0
+                WHEN cp.rating = 'easy' THEN 100.0
+                WHEN cp.rating = 'good' THEN 75.0
+                WHEN cp.rating = 'hard' THEN 50.0
                 ELSE 0.0
             END)::DOUBLE PRECISION, 0.0) as "average_accuracy!",
             COALESCE(
-                (SELECT current_streak FROM user_stats WHERE user_id = $1),
+                (SELECT current_streak_days FROM user_stats WHERE user_id = $1),
                 0
             ) as "streak_days!",
             COUNT(DISTINCT ss.id)::bigint as "total_sessions!",
@@ -137,6 +174,12 @@ async fn get_progress_overview(
     Ok(Json(overview))
 }
 
+#[utoipa::path(
+    get,
+    path = "/decks",
+    responses((status = 200, body = Vec<DeckProgress>)),
+    tag = "progress"
+)]
 async fn get_deck_progress(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -150,33 +193,33 @@ async fn get_deck_progress(
                 d.title as deck_name,
                 COUNT(DISTINCT c.id) as total_cards,
                 COUNT(DISTINCT CASE 
-                    WHEN cp.status IN ('easy', 'medium') AND cp.review_count >= 3
+                    WHEN cp.rating IN ('good', 'easy') AND cp.review_count >= 3
                     THEN c.id 
                 END) as cards_learned,
                 COUNT(DISTINCT CASE 
-                    WHEN cp.status IS NOT NULL AND cp.review_count BETWEEN 1 AND 2 
+                    WHEN cp.rating IS NOT NULL AND cp.review_count BETWEEN 1 AND 2 
                     THEN c.id 
                 END) as cards_reviewing,
                 COUNT(DISTINCT CASE 
-                    WHEN cp.status IS NULL 
+                    WHEN cp.rating IS NULL 
                     THEN c.id 
                 END) as cards_new,
                 AVG(CASE 
-                    WHEN cp.status = 'easy' THEN 100.0
-                    WHEN cp.status = 'medium' THEN 75.0
-                    WHEN cp.status = 'hard' THEN 50.0
+                    WHEN cp.rating = 'easy' THEN 100.0
+                    WHEN cp.rating = 'good' THEN 75.0
+                    WHEN cp.rating = 'hard' THEN 50.0
                     ELSE NULL
                 END) as average_accuracy,
                 MAX(ss.started_at) as last_studied
             FROM decks d
-            INNER JOIN cards c ON c.deck_id = d.id
+            INNER JOIN cards c ON c.deck_id = d.id AND c.deleted_at IS NULL
             LEFT JOIN (
                 SELECT DISTINCT ON (card_id) * 
                 FROM card_progress 
                 ORDER BY card_id, created_at DESC
             ) cp ON cp.card_id = c.id
             LEFT JOIN study_sessions ss ON ss.deck_id = d.id AND ss.user_id = $1
-            WHERE d.owner_id = $1
+            WHERE d.owner_id = $1 AND d.deleted_at IS NULL
             GROUP BY d.id, d.title
         )
         SELECT 
@@ -204,6 +247,13 @@ async fn get_deck_progress(
     Ok(Json(progress))
 }
 
+#[utoipa::path(
+    get,
+    path = "/decks/{deck_id}",
+    params(("deck_id" = Uuid, Path, description = "Deck id")),
+    responses((status = 200, body = DeckProgress)),
+    tag = "progress"
+)]
 async fn get_specific_deck_progress(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -218,33 +268,33 @@ async fn get_specific_deck_progress(
                 d.title as deck_name,
                 COUNT(DISTINCT c.id) as total_cards,
                 COUNT(DISTINCT CASE 
-                    WHEN cp.status IN ('easy', 'medium') AND cp.review_count >= 3
+                    WHEN cp.rating IN ('good', 'easy') AND cp.review_count >= 3
                     THEN c.id 
                 END) as cards_learned,
                 COUNT(DISTINCT CASE 
-                    WHEN cp.status IS NOT NULL AND cp.review_count BETWEEN 1 AND 2 
+                    WHEN cp.rating IS NOT NULL AND cp.review_count BETWEEN 1 AND 2 
                     THEN c.id 
                 END) as cards_reviewing,
                 COUNT(DISTINCT CASE 
-                    WHEN cp.status IS NULL 
+                    WHEN cp.rating IS NULL 
                     THEN c.id 
                 END) as cards_new,
                 AVG(CASE 
-                    WHEN cp.status = 'easy' THEN 100.0
-                    WHEN cp.status = 'medium' THEN 75.0
-                    WHEN cp.status = 'hard' THEN 50.0
+                    WHEN cp.rating = 'easy' THEN 100.0
+                    WHEN cp.rating = 'good' THEN 75.0
+                    WHEN cp.rating = 'hard' THEN 50.0
                     ELSE NULL
                 END) as average_accuracy,
                 MAX(ss.started_at) as last_studied
             FROM decks d
-            INNER JOIN cards c ON c.deck_id = d.id
+            INNER JOIN cards c ON c.deck_id = d.id AND c.deleted_at IS NULL
             LEFT JOIN (
                 SELECT DISTINCT ON (card_id) * 
                 FROM card_progress 
                 ORDER BY card_id, created_at DESC
             ) cp ON cp.card_id = c.id
             LEFT JOIN study_sessions ss ON ss.deck_id = d.id AND ss.user_id = $1
-            WHERE d.id = $2 AND d.owner_id = $1
+            WHERE d.id = $2 AND d.owner_id = $1 AND d.deleted_at IS NULL
             GROUP BY d.id, d.title
         )
         SELECT 
@@ -272,6 +322,13 @@ async fn get_specific_deck_progress(
     Ok(Json(progress))
 }
 
+#[utoipa::path(
+    get,
+    path = "/cards/performance",
+    params(ProgressQuery),
+    responses((status = 200, body = Vec<CardPerformance>)),
+    tag = "progress"
+)]
 async fn get_card_performance(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -285,14 +342,14 @@ async fn get_card_performance(
                 c.id as card_id,
                 c.front,
                 COUNT(cp.id) as total_reviews,
-                COUNT(CASE WHEN cp.status IN ('easy', 'medium') THEN 1 END) as correct_count,
-                COUNT(CASE WHEN cp.status IN ('hard', 'forgot') THEN 1 END) as incorrect_count,
+                COUNT(CASE WHEN cp.rating <> 'again' THEN 1 END) as correct_count,
+                COUNT(CASE WHEN cp.rating = 'again' THEN 1 END) as incorrect_count,
                 AVG(cp.response_time_ms::float) as avg_response_time,
                 MAX(cp.created_at) as last_reviewed
             FROM cards c
             INNER JOIN decks d ON d.id = c.deck_id
             LEFT JOIN card_progress cp ON cp.card_id = c.id
-            WHERE d.owner_id = $1
+            WHERE d.owner_id = $1 AND c.deleted_at IS NULL AND d.deleted_at IS NULL
                 AND ($2::uuid IS NULL OR c.deck_id = $2)
                 AND ($3::timestamptz IS NULL OR cp.created_at >= $3)
                 AND ($4::timestamptz IS NULL OR cp.created_at <= $4)
@@ -337,6 +394,13 @@ async fn get_card_performance(
     Ok(Json(performance))
 }
 
+#[utoipa::path(
+    get,
+    path = "/learning-curve",
+    params(ProgressQuery),
+    responses((status = 200, body = Vec<LearningCurve>)),
+    tag = "progress"
+)]
 async fn get_learning_curve(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -350,9 +414,9 @@ async fn get_learning_curve(
                 DATE(ss.started_at) as study_date,
                 COUNT(DISTINCT cp.card_id) as cards_studied,
                 AVG(CASE 
-                    WHEN cp.status = 'easy' THEN 100.0
-                    WHEN cp.status = 'medium' THEN 75.0
-                    WHEN cp.status = 'hard' THEN 50.0
+                    WHEN cp.rating = 'easy' THEN 100.0
+                    WHEN cp.rating = 'good' THEN 75.0
+                    WHEN cp.rating = 'hard' THEN 50.0
                     ELSE 0.0
                 END) as accuracy,
                 SUM(EXTRACT(EPOCH FROM (
@@ -387,6 +451,12 @@ async fn get_learning_curve(
     Ok(Json(curve))
 }
 
+#[utoipa::path(
+    get,
+    path = "/streaks",
+    responses((status = 200, body = StudyStreak)),
+    tag = "progress"
+)]
 async fn get_study_streaks(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -394,8 +464,8 @@ async fn get_study_streaks(
     let user_stats = sqlx::query!(
         r#"
         SELECT 
-            current_streak,
-            longest_streak,
+            current_streak_days as current_streak,
+            longest_streak_days as longest_streak,
             last_study_date
         FROM user_stats
         WHERE user_id = $1
@@ -431,6 +501,12 @@ async fn get_study_streaks(
     Ok(Json(streak))
 }
 
+#[utoipa::path(
+    get,
+    path = "/weekly",
+    responses((status = 200, body = Vec<WeeklyProgress>)),
+    tag = "progress"
+)]
 async fn get_weekly_progress(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -447,9 +523,9 @@ async fn get_weekly_progress(
                     - ss.started_at
                 )) / 60)::bigint as total_study_time_minutes,
                 AVG(CASE 
-                    WHEN cp.status = 'easy' THEN 100.0
-                    WHEN cp.status = 'medium' THEN 75.0
-                    WHEN cp.status = 'hard' THEN 50.0
+                    WHEN cp.rating = 'easy' THEN 100.0
+                    WHEN cp.rating = 'good' THEN 75.0
+                    WHEN cp.rating = 'hard' THEN 50.0
                     ELSE 0.0
                 END) as average_accuracy,
                 COUNT(DISTINCT ss.id) as sessions_completed,
@@ -481,3 +557,70 @@ async fn get_weekly_progress(
 
     Ok(Json(progress))
 }
+
+#[utoipa::path(
+    get,
+    path = "/heatmap",
+    params(HeatmapQuery),
+    responses(
+        (status = 200, body = StudyHeatmap),
+        (status = 400, description = "Invalid year or unknown timezone")
+    ),
+    tag = "progress"
+)]
+async fn get_heatmap(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<HeatmapQuery>,
+) -> Result<Json<StudyHeatmap>> {
+    let heatmap = StatsService::heatmap(&state.db, user_id, query.year, query.timezone).await?;
+    Ok(Json(heatmap))
+}
+
+/// Cards the user keeps forgetting, with suggested next steps
+#[utoipa::path(
+    get,
+    path = "/leeches",
+    params(LeechesQuery),
+    responses((status = 200, body = Vec<LeechCard>)),
+    tag = "progress"
+)]
+async fn list_leeches(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<LeechesQuery>,
+) -> Result<Json<Vec<LeechCard>>> {
+    let leeches = LeechService::list_leeches(&state.db, user_id, query.deck_id).await?;
+    Ok(Json(leeches))
+}
+
+#[utoipa::path(
+    get,
+    path = "/leaderboard",
+    params(LeaderboardQuery),
+    responses((status = 200, body = Leaderboard)),
+    tag = "progress"
+)]
+async fn get_leaderboard(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Leaderboard>> {
+    let leaderboard = LeaderboardService::get(&state.db, user_id, &query).await?;
+    Ok(Json(leaderboard))
+}
+
+/// Lifetime totals over the user's study events, recomputed periodically
+#[utoipa::path(
+    get,
+    path = "/learning-stats",
+    responses((status = 200, body = UserLearningStats)),
+    tag = "progress"
+)]
+async fn get_learning_stats(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<UserLearningStats>> {
+    let stats = LearningStatsService::get(&state.db, user_id).await?;
+    Ok(Json(stats))
+}
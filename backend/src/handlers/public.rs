@@ -0,0 +1,157 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    models::{DeckBadge, DeckBadgeJson, DeckEmbed},
+    services::{badge::BadgeService, sharing::SharingService},
+    state::AppState,
+    utils::{
+        etag::{etag, is_fresh, not_modified},
+        ErrorResponse, Result,
+    },
+};
+
+/// Badges are embedded in READMEs and course pages, so let browsers and CDNs
+/// keep them for an hour and serve stale copies while revalidating
+const BADGE_CACHE_CONTROL: &str = "public, max-age=3600, s-maxage=3600, stale-while-revalidate=86400";
+
+/// Embedded decks should pick up edits within minutes, and a revoked link
+/// should stop working soon after
+const EMBED_CACHE_CONTROL: &str = "public, max-age=300, s-maxage=300, stale-while-revalidate=3600";
+
+/// Unauthenticated endpoints; `create_app` rate limits them per client IP
+/// and lets pages on `SECURITY_EMBED_FRAME_ANCESTORS` frame them
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/decks/:id/badge.svg", get(deck_badge_svg))
+        .route("/decks/:id/badge.json", get(deck_badge_json))
+        .route("/decks/:share_token", get(deck_embed))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    deck_badge_svg,
+    deck_badge_json,
+    deck_embed
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "/decks/{id}/badge.svg",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses(
+        (status = 200, description = "SVG badge", content_type = "image/svg+xml", body = String),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+    ),
+    security(()),
+    tag = "public"
+)]
+async fn deck_badge_svg(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let badge = BadgeService::deck_badge(&state.db, id).await?;
+    let etag = badge_etag(&badge, "svg");
+    if is_fresh(&headers, &etag) {
+        return Ok(not_modified(&etag, BADGE_CACHE_CONTROL));
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("image/svg+xml; charset=utf-8")),
+            (header::CACHE_CONTROL, HeaderValue::from_static(BADGE_CACHE_CONTROL)),
+            (header::ETAG, etag),
+        ],
+        BadgeService::render_svg(&badge),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/decks/{id}/badge.json",
+    params(("id" = Uuid, Path, description = "Deck id")),
+    responses(
+        (status = 200, description = "Badge in the shields.io endpoint schema", body = DeckBadgeJson),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+    ),
+    security(()),
+    tag = "public"
+)]
+async fn deck_badge_json(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let badge = BadgeService::deck_badge(&state.db, id).await?;
+    let etag = badge_etag(&badge, "json");
+    if is_fresh(&headers, &etag) {
+        return Ok(not_modified(&etag, BADGE_CACHE_CONTROL));
+    }
+
+    Ok((
+        [
+            (header::CACHE_CONTROL, HeaderValue::from_static(BADGE_CACHE_CONTROL)),
+            (header::ETAG, etag),
+        ],
+        Json(BadgeService::to_json(badge)),
+    )
+        .into_response())
+}
+
+/// A shared deck and its cards, for embedding in other pages or previewing
+/// before accepting the link
+#[utoipa::path(
+    get,
+    path = "/decks/{share_token}",
+    params(("share_token" = String, Path, description = "Token of an active share link")),
+    responses(
+        (status = 200, body = DeckEmbed),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+        (status = 404, description = "Unknown, revoked or expired link", body = ErrorResponse),
+    ),
+    security(()),
+    tag = "public"
+)]
+async fn deck_embed(
+    State(state): State<AppState>,
+    Path(share_token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let embed = SharingService::deck_embed(&state.db, &share_token).await?;
+    let version = format!("{}:{}:{}", share_token, embed.updated_at.timestamp_micros(), embed.card_count);
+    let etag = etag("deck-embed", &version);
+    if is_fresh(&headers, &etag) {
+        return Ok(not_modified(&etag, EMBED_CACHE_CONTROL));
+    }
+
+    Ok((
+        [
+            (header::CACHE_CONTROL, HeaderValue::from_static(EMBED_CACHE_CONTROL)),
+            (header::ETAG, etag),
+        ],
+        Json(embed),
+    )
+        .into_response())
+}
+
+// Helper functions
+
+/// The badge only changes with its numbers, so they make a stable validator
+fn badge_etag(badge: &DeckBadge, variant: &str) -> HeaderValue {
+    let rating = badge.average_rating.map_or(0, |r| (r * 10.0).round() as i64);
+    let value = format!(
+        "\"{}-{}-{}-{}-{}\"",
+        variant, badge.deck_id, badge.card_count, rating, badge.rating_count
+    );
+    HeaderValue::from_str(&value).expect("valid ETag")
+}
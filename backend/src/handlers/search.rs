@@ -4,11 +4,13 @@ use axum::{
     routing::get,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
-    models::{Card, Deck, DeckWithStats},
-    services::search::SearchService,
+    middleware::auth::UserId,
+    models::{Card, DeckWithStats},
+    services::{media::MediaService, search::SearchService},
     state::AppState,
     utils::{PaginatedResponse, PaginationParams, Result},
 };
@@ -16,24 +18,63 @@ use crate::{
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(search_all))
+        .route("/suggest", get(suggest))
         .route("/decks", get(search_decks))
         .route("/cards", get(search_cards))
 }
 
-#[derive(Deserialize)]
+#[derive(OpenApi)]
+#[openapi(paths(
+    search_all,
+    suggest,
+    search_decks,
+    search_cards
+))]
+pub struct ApiDoc;
+
+// Pagination is extracted separately: numbers inside a flattened struct
+// arrive as strings from the query string and fail to parse
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SearchQuery {
     q: String,
-    #[serde(flatten)]
-    pagination: PaginationParams,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct SearchResults {
     decks: Vec<DeckWithStats>,
     cards: Vec<CardSearchResult>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SuggestQuery {
+    q: String,
+    /// At most 20; defaults to 8
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum SuggestionKind {
+    Deck,
+    Folder,
+    Tag,
+}
+
+/// One autocomplete entry. `id` is the deck or folder; tags have none.
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+pub struct SearchSuggestion {
+    pub kind: SuggestionKind,
+    pub id: Option<Uuid>,
+    pub label: String,
+    /// Higher is better: prefix matches come first, then word prefixes,
+    /// then substring and fuzzy matches by trigram similarity
+    pub score: f32,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct CardSearchResult {
     #[serde(flatten)]
     pub card: Card,
@@ -41,13 +82,18 @@ pub struct CardSearchResult {
     pub deck_id: Uuid,
 }
 
+#[utoipa::path(
+    get,
+    path = "",
+    params(SearchQuery),
+    responses((status = 200, description = "Top deck and card matches", body = SearchResults)),
+    tag = "search"
+)]
 async fn search_all(
     State(state): State<AppState>,
-    Query(mut query): Query<SearchQuery>,
+    UserId(user_id): UserId,
+    Query(query): Query<SearchQuery>,
 ) -> Result<Json<SearchResults>> {
-    // TODO: Get user_id from auth middleware
-    let user_id = Uuid::new_v4(); // Placeholder
-    
     // Validate and clean search query
     let search_term = query.q.trim();
     if search_term.is_empty() {
@@ -57,15 +103,16 @@ async fn search_all(
         }));
     }
     
-    query.pagination.validate();
-    
     // Search both decks and cards (limited results for overview)
-    let decks = SearchService::search_decks(
+    let mut decks = SearchService::search_decks(
         &state.db,
         user_id,
         search_term,
         5, // Limit to 5 decks in combined search
     ).await?;
+    for deck in &mut decks {
+        MediaService::sign_deck_cover(&state.media, deck);
+    }
     
     let cards = SearchService::search_cards(
         &state.db,
@@ -77,49 +124,88 @@ async fn search_all(
     Ok(Json(SearchResults { decks, cards }))
 }
 
+/// Type-ahead suggestions from the names of the user's decks and folders
+/// and the tags of their decks. Kept to a single indexed query so it can
+/// run on every keystroke; use the other search endpoints for full results.
+#[utoipa::path(
+    get,
+    path = "/suggest",
+    params(SuggestQuery),
+    responses((status = 200, body = Vec<SearchSuggestion>)),
+    tag = "search"
+)]
+async fn suggest(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<SuggestQuery>,
+) -> Result<Json<Vec<SearchSuggestion>>> {
+    let search_term = query.q.trim();
+    if search_term.is_empty() {
+        return Ok(Json(vec![]));
+    }
+
+    let suggestions = SearchService::suggest(&state.db, user_id, search_term, query.limit).await?;
+    Ok(Json(suggestions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/decks",
+    params(SearchQuery, PaginationParams),
+    responses((status = 200, body = PaginatedResponse<DeckWithStats>)),
+    tag = "search"
+)]
 async fn search_decks(
     State(state): State<AppState>,
-    Query(mut query): Query<SearchQuery>,
+    UserId(user_id): UserId,
+    Query(query): Query<SearchQuery>,
+    Query(mut pagination): Query<PaginationParams>,
 ) -> Result<Json<PaginatedResponse<DeckWithStats>>> {
-    // TODO: Get user_id from auth middleware
-    let user_id = Uuid::new_v4(); // Placeholder
-    
     let search_term = query.q.trim();
     if search_term.is_empty() {
-        return Ok(Json(PaginatedResponse::new(vec![], &query.pagination, Some(0))));
+        return Ok(Json(PaginatedResponse::new(vec![], &pagination, Some(0))));
     }
     
-    query.pagination.validate();
+    pagination.validate();
     
-    let decks = SearchService::search_decks_paginated(
+    let mut decks = SearchService::search_decks_paginated(
         &state.db,
         user_id,
         search_term,
-        &query.pagination,
+        &pagination,
     ).await?;
+    for deck in &mut decks.data {
+        MediaService::sign_deck_cover(&state.media, deck);
+    }
     
     Ok(Json(decks))
 }
 
+#[utoipa::path(
+    get,
+    path = "/cards",
+    params(SearchQuery, PaginationParams),
+    responses((status = 200, body = PaginatedResponse<CardSearchResult>)),
+    tag = "search"
+)]
 async fn search_cards(
     State(state): State<AppState>,
-    Query(mut query): Query<SearchQuery>,
+    UserId(user_id): UserId,
+    Query(query): Query<SearchQuery>,
+    Query(mut pagination): Query<PaginationParams>,
 ) -> Result<Json<PaginatedResponse<CardSearchResult>>> {
-    // TODO: Get user_id from auth middleware
-    let user_id = Uuid::new_v4(); // Placeholder
-    
     let search_term = query.q.trim();
     if search_term.is_empty() {
-        return Ok(Json(PaginatedResponse::new(vec![], &query.pagination, Some(0))));
+        return Ok(Json(PaginatedResponse::new(vec![], &pagination, Some(0))));
     }
     
-    query.pagination.validate();
+    pagination.validate();
     
     let cards = SearchService::search_cards_paginated(
         &state.db,
         user_id,
         search_term,
-        &query.pagination,
+        &pagination,
     ).await?;
     
     Ok(Json(cards))
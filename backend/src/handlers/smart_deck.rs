@@ -0,0 +1,120 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use utoipa::OpenApi;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    middleware::auth::UserId,
+    models::{CreateSmartDeckDto, SmartDeck, UpdateSmartDeckDto},
+    services::smart_deck::SmartDeckService,
+    state::AppState,
+    utils::Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_smart_decks).post(create_smart_deck))
+        .route(
+            "/:id",
+            get(get_smart_deck).patch(update_smart_deck).delete(delete_smart_deck),
+        )
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_smart_decks,
+    create_smart_deck,
+    get_smart_deck,
+    update_smart_deck,
+    delete_smart_deck
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "",
+    responses((status = 200, body = Vec<SmartDeck>)),
+    tag = "smart-decks"
+)]
+async fn list_smart_decks(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<Vec<SmartDeck>>> {
+    let smart_decks = SmartDeckService::list(&state.db, user_id).await?;
+    Ok(Json(smart_decks))
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = CreateSmartDeckDto,
+    responses((status = 201, body = SmartDeck)),
+    tag = "smart-decks"
+)]
+async fn create_smart_deck(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<CreateSmartDeckDto>,
+) -> Result<(StatusCode, Json<SmartDeck>)> {
+    dto.validate()?;
+
+    let smart_deck = SmartDeckService::create(&state.db, user_id, dto).await?;
+    Ok((StatusCode::CREATED, Json(smart_deck)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Smart deck id")),
+    responses((status = 200, body = SmartDeck)),
+    tag = "smart-decks"
+)]
+async fn get_smart_deck(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SmartDeck>> {
+    let smart_deck = SmartDeckService::get(&state.db, id, user_id).await?;
+    Ok(Json(smart_deck))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Smart deck id")),
+    request_body = UpdateSmartDeckDto,
+    responses((status = 200, body = SmartDeck)),
+    tag = "smart-decks"
+)]
+async fn update_smart_deck(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<UpdateSmartDeckDto>,
+) -> Result<Json<SmartDeck>> {
+    dto.validate()?;
+
+    let smart_deck = SmartDeckService::update(&state.db, id, user_id, dto).await?;
+    Ok(Json(smart_deck))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Smart deck id")),
+    responses((status = 204, description = "Smart deck deleted; its sessions are kept")),
+    tag = "smart-decks"
+)]
+async fn delete_smart_deck(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode> {
+    SmartDeckService::delete(&state.db, id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
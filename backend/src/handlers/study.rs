@@ -1,40 +1,133 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     routing::{get, post},
     Json, Router,
 };
 use serde::Deserialize;
+use serde_json::json;
+use utoipa::{IntoParams, OpenApi};
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
-    middleware::auth::UserId,
-    models::{CardProgress, CardStatus, CreateStudySessionDto, StudySession},
-    services::study::StudyService,
+    middleware::auth::{UserId, VerifiedUser},
+    models::{
+        ai::{CardExplanation, ExplainCardDto, StudyQueue, StudyQueueQuery, WsMessage},
+        Achievement, AchievementWithStatus, ActiveStudySession, CardProgress,
+        CompletedStudySession, CreateStudySessionDto, GradedCardProgress, QuizAnswerDto,
+        QuizAnswerResult, QuizQuestion, Rating, RecordProgressDto, SessionReplay, SessionStats, StudySession,
+        StudySessionDetails, UserStatsResponse,
+    },
+    services::{
+        achievement::AchievementService, card_explanation::CardExplanationService,
+        leech::LeechService, quiz_session::QuizSessionService,
+        stats::StatsService, study::StudyService, webhook::WebhookService,
+    },
     state::AppState,
-    utils::Result,
+    utils::{AppError, Result},
 };
 
-#[derive(Deserialize)]
+/// Clients identify themselves with this header so sessions can be handed off
+const DEVICE_ID_HEADER: &str = "x-device-id";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct StudySessionsQuery {
     limit: Option<i64>,
 }
 
-#[derive(Deserialize)]
-struct RecordProgressDto {
-    card_id: Uuid,
-    status: CardStatus,
-    response_time_ms: Option<i32>,
-}
-
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/sessions", get(list_sessions).post(create_session))
+        .route("/sessions/active", get(get_active_session))
         .route("/sessions/:id", get(get_session))
         .route("/sessions/:id/complete", post(complete_session))
         .route("/sessions/:id/progress", get(get_session_progress).post(record_progress))
+        .route("/sessions/:id/replay", get(get_session_replay))
+        .route("/sessions/:id/stats", get(get_session_stats))
+        .route("/sessions/:id/quiz-question", get(get_quiz_question).post(answer_quiz_question))
+        .route("/sessions/:id/cards/:card_id/explain", post(explain_card))
+        .route("/queue", get(get_queue))
+        .route("/stats", get(get_stats))
+        .route("/achievements", get(list_achievements))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_queue,
+    get_stats,
+    list_achievements,
+    list_sessions,
+    create_session,
+    get_active_session,
+    get_session,
+    complete_session,
+    get_session_progress,
+    record_progress,
+    get_session_replay,
+    get_session_stats,
+    get_quiz_question,
+    answer_quiz_question,
+    explain_card
+))]
+pub struct ApiDoc;
+
+/// The cards to study next. Cached for a few minutes, or until the user
+/// answers or edits cards.
+#[utoipa::path(
+    get,
+    path = "/queue",
+    params(StudyQueueQuery),
+    responses((status = 200, body = StudyQueue)),
+    tag = "study"
+)]
+async fn get_queue(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<StudyQueueQuery>,
+) -> Result<Json<StudyQueue>> {
+    let queue = state.queue_cache.get_or_build(&state.db, user_id, &query).await?;
+    Ok(Json(queue))
+}
+
+/// Totals, streak, points and level of the current user
+#[utoipa::path(
+    get,
+    path = "/stats",
+    responses((status = 200, body = UserStatsResponse)),
+    tag = "study"
+)]
+async fn get_stats(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<UserStatsResponse>> {
+    let stats = StatsService::get(&state.db, user_id).await?;
+    Ok(Json(stats))
 }
 
+/// The achievement catalog with the current user's progress
+#[utoipa::path(
+    get,
+    path = "/achievements",
+    responses((status = 200, body = Vec<AchievementWithStatus>)),
+    tag = "study"
+)]
+async fn list_achievements(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<Vec<AchievementWithStatus>>> {
+    let achievements = AchievementService::list(&state.db, user_id).await?;
+    Ok(Json(achievements))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions",
+    params(StudySessionsQuery),
+    responses((status = 200, body = Vec<StudySession>)),
+    tag = "study"
+)]
 async fn list_sessions(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -44,33 +137,109 @@ async fn list_sessions(
     Ok(Json(sessions))
 }
 
+#[utoipa::path(
+    post,
+    path = "/sessions",
+    request_body = CreateStudySessionDto,
+    responses((status = 201, body = StudySession)),
+    tag = "study"
+)]
 async fn create_session(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Json(dto): Json<CreateStudySessionDto>,
 ) -> Result<(StatusCode, Json<StudySession>)> {
+    dto.validate()?;
+
     let session = StudyService::create_study_session(&state.db, user_id, dto).await?;
     Ok((StatusCode::CREATED, Json(session)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/sessions/active",
+    params(("x-device-id" = Option<String>, Header, description = "Client device, for session handoff")),
+    responses((status = 200, body = ActiveStudySession)),
+    tag = "study"
+)]
+async fn get_active_session(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    headers: HeaderMap,
+) -> Result<Json<ActiveStudySession>> {
+    let mut active = StudyService::get_active_session(&state.db, user_id).await?;
+
+    if let Some(device_id) = device_id(&headers) {
+        claim_device(&state, active.session.id, user_id, device_id).await?;
+        active.active_device_id = Some(device_id.to_string());
+    }
+
+    Ok(Json(active))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}",
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses((status = 200, body = StudySessionDetails)),
+    tag = "study"
+)]
 async fn get_session(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
-) -> Result<Json<StudySession>> {
+) -> Result<Json<StudySessionDetails>> {
     let session = StudyService::get_study_session(&state.db, id, user_id).await?;
-    Ok(Json(session))
+    let decks = StudyService::session_deck_breakdown(&state.db, id).await?;
+    Ok(Json(StudySessionDetails { session, decks }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/sessions/{id}/complete",
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses((status = 200, body = CompletedStudySession)),
+    tag = "study"
+)]
 async fn complete_session(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(id): Path<Uuid>,
-) -> Result<Json<StudySession>> {
+) -> Result<Json<CompletedStudySession>> {
     let session = StudyService::complete_study_session(&state.db, id, user_id).await?;
-    Ok(Json(session))
+
+    state
+        .ws
+        .publish(user_id, "study_insights", WsMessage::new("session_completed", json!(session)))
+        .await;
+
+    // A folder session notifies each deck it studied
+    let decks = StudyService::session_deck_breakdown(&state.db, session.id).await?;
+    let studied_decks = match session.deck_id {
+        Some(deck_id) => vec![deck_id],
+        None => decks.iter().map(|deck| deck.deck_id).collect(),
+    };
+    for deck_id in studied_decks {
+        WebhookService::dispatch(state.db.clone(), deck_id, "study.completed", json!(session));
+    }
+
+    // A perfect session can earn an achievement
+    let new_achievements = AchievementService::evaluate(&state.db, user_id).await?;
+
+    Ok(Json(CompletedStudySession {
+        session,
+        decks,
+        new_achievements,
+    }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}/progress",
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses((status = 200, body = Vec<CardProgress>)),
+    tag = "study"
+)]
 async fn get_session_progress(
     State(state): State<AppState>,
     UserId(user_id): UserId,
@@ -80,21 +249,228 @@ async fn get_session_progress(
     Ok(Json(progress))
 }
 
+/// Ordered answers of a session with timings and the scheduler's decisions
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}/replay",
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses((status = 200, body = SessionReplay)),
+    tag = "study"
+)]
+async fn get_session_replay(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SessionReplay>> {
+    let replay = StudyService::get_session_replay(&state.db, id, user_id).await?;
+    Ok(Json(replay))
+}
+
+/// Accuracy, response times, rating distribution and hardest cards of a session
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}/stats",
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses((status = 200, body = SessionStats)),
+    tag = "study"
+)]
+async fn get_session_stats(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SessionStats>> {
+    let stats = StudyService::get_session_stats(&state.db, id, user_id).await?;
+    Ok(Json(stats))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{id}/progress",
+    params(("id" = Uuid, Path, description = "Session id"), ("x-device-id" = Option<String>, Header, description = "Client device, for session handoff")),
+    request_body = RecordProgressDto,
+    responses((status = 201, body = GradedCardProgress)),
+    tag = "study"
+)]
 async fn record_progress(
     State(state): State<AppState>,
     UserId(user_id): UserId,
     Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
     Json(dto): Json<RecordProgressDto>,
-) -> Result<(StatusCode, Json<CardProgress>)> {
-    let progress = StudyService::record_card_progress(
+) -> Result<(StatusCode, Json<GradedCardProgress>)> {
+    let mut graded = if dto.user_answer.is_some() {
+        StudyService::record_typed_answer(
+            &state.db,
+            session_id,
+            user_id,
+            &dto,
+            state.config.grading.similarity_threshold,
+        )
+        .await?
+    } else {
+        let rating = dto
+            .rating
+            .or(dto.status.map(Rating::from_status))
+            .ok_or(AppError::ValidationError("rating is required".to_string()))?;
+
+        let progress = StudyService::record_card_progress(
+            &state.db,
+            session_id,
+            dto.card_id,
+            user_id,
+            rating,
+            dto.response_time_ms,
+        )
+        .await?;
+        GradedCardProgress {
+            progress,
+            grade: None,
+            new_achievements: vec![],
+        }
+    };
+
+    graded.new_achievements = after_answer(&state, user_id, &headers, &graded.progress).await?;
+
+    Ok((StatusCode::CREATED, Json(graded)))
+}
+
+/// Multiple-choice question for the next card of a quiz session. Asking
+/// again before answering returns the same question.
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}/quiz-question",
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses((status = 200, body = QuizQuestion)),
+    tag = "study"
+)]
+async fn get_quiz_question(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<QuizQuestion>> {
+    let question = QuizSessionService::next_question(&state.db, session_id, user_id).await?;
+    Ok(Json(question))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{id}/quiz-question",
+    params(("id" = Uuid, Path, description = "Session id"), ("x-device-id" = Option<String>, Header, description = "Client device, for session handoff")),
+    request_body = QuizAnswerDto,
+    responses((status = 201, body = QuizAnswerResult)),
+    tag = "study"
+)]
+async fn answer_quiz_question(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(dto): Json<QuizAnswerDto>,
+) -> Result<(StatusCode, Json<QuizAnswerResult>)> {
+    dto.validate()?;
+
+    let mut result = QuizSessionService::answer(&state.db, session_id, user_id, dto).await?;
+    result.new_achievements = after_answer(&state, user_id, &headers, &result.progress).await?;
+
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+/// A short AI explanation of a card in the session, cached per card and
+/// wrong answer. The body is optional.
+#[utoipa::path(
+    post,
+    path = "/sessions/{id}/cards/{card_id}/explain",
+    params(("id" = Uuid, Path, description = "Session id"), ("card_id" = Uuid, Path, description = "Card id")),
+    request_body(content = Option<ExplainCardDto>),
+    responses((status = 200, body = CardExplanation)),
+    tag = "study"
+)]
+async fn explain_card(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Path((session_id, card_id)): Path<(Uuid, Uuid)>,
+    dto: Option<Json<ExplainCardDto>>,
+) -> Result<Json<CardExplanation>> {
+    let Json(dto) = dto.unwrap_or_default();
+    dto.validate()?;
+
+    let explanation = CardExplanationService::explain(
         &state.db,
+        &state.config.ai,
+        &state.ai_breaker,
         session_id,
-        dto.card_id,
+        card_id,
         user_id,
-        dto.status,
-        dto.response_time_ms,
+        dto,
     )
     .await?;
-    
-    Ok((StatusCode::CREATED, Json(progress)))
+
+    Ok(Json(explanation))
+}
+
+/// Device handoff, live progress, leech remediation and achievements for a
+/// recorded answer. Returns the achievements the answer earned.
+async fn after_answer(
+    state: &AppState,
+    user_id: Uuid,
+    headers: &HeaderMap,
+    progress: &CardProgress,
+) -> Result<Vec<Achievement>> {
+    if let Some(device_id) = device_id(headers) {
+        claim_device(state, progress.session_id, user_id, device_id).await?;
+    }
+
+    state.queue_cache.invalidate_user(user_id).await;
+
+    state
+        .ws
+        .publish(user_id, "progress_updates", WsMessage::new("study_progress", json!(progress)))
+        .await;
+
+    // Only a lapse can push a card over the leech threshold
+    if progress.rating == Rating::Again {
+        LeechService::remediate_if_flagged(
+            state.db.clone(),
+            state.config.ai.clone(),
+            state.ai_breaker.clone(),
+            state.ws.clone(),
+            user_id,
+            progress.card_id,
+        );
+    }
+
+    AchievementService::evaluate(&state.db, user_id).await
+}
+
+fn device_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(DEVICE_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+}
+
+/// Make `device_id` the session's active device and tell the user's other
+/// connections when this takes over from a different device
+async fn claim_device(
+    state: &AppState,
+    session_id: Uuid,
+    user_id: Uuid,
+    device_id: &str,
+) -> Result<()> {
+    let previous =
+        StudyService::claim_session_device(&state.db, session_id, user_id, device_id).await?;
+
+    if let Some(previous_device_id) = previous {
+        let message = WsMessage::new(
+            "session_handoff",
+            json!({
+                "session_id": session_id,
+                "device_id": device_id,
+                "previous_device_id": previous_device_id,
+            }),
+        );
+        state.ws.send_to_user(user_id, message).await;
+    }
+
+    Ok(())
 }
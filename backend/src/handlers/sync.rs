@@ -0,0 +1,69 @@
+use axum::{
+    extract::{Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use utoipa::OpenApi;
+use validator::Validate;
+
+use crate::{
+    middleware::auth::{UserId, VerifiedUser},
+    models::sync::{SyncChangesPage, SyncChangesQuery, SyncPushDto, SyncPushResponse},
+    services::{sync::SyncService, webhook::WebhookService},
+    state::AppState,
+    utils::Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/changes", get(pull_changes))
+        .route("/push", post(push_changes))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    pull_changes,
+    push_changes
+))]
+pub struct ApiDoc;
+
+/// Folders, decks and cards changed since the cursor, with tombstones for deletions
+#[utoipa::path(
+    get,
+    path = "/changes",
+    params(SyncChangesQuery),
+    responses((status = 200, body = SyncChangesPage)),
+    tag = "sync"
+)]
+async fn pull_changes(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Query(query): Query<SyncChangesQuery>,
+) -> Result<Json<SyncChangesPage>> {
+    let page = SyncService::pull(&state.db, user_id, &query).await?;
+    Ok(Json(page))
+}
+
+/// Apply offline changes; conflicts are settled by last write wins. Pushes
+/// create content, so they need a verified email where that is required.
+#[utoipa::path(
+    post,
+    path = "/push",
+    request_body = SyncPushDto,
+    responses((status = 200, body = SyncPushResponse)),
+    tag = "sync"
+)]
+async fn push_changes(
+    State(state): State<AppState>,
+    VerifiedUser(user_id): VerifiedUser,
+    Json(dto): Json<SyncPushDto>,
+) -> Result<Json<SyncPushResponse>> {
+    dto.validate()?;
+    let outcome = SyncService::push(&state.db, user_id, dto).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    for event in outcome.card_events {
+        WebhookService::dispatch(state.db.clone(), event.deck_id, event.event, json!(event.card));
+    }
+    Ok(Json(outcome.response))
+}
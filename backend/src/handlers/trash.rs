@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::UserId,
+    models::{RestoredItem, TrashItem},
+    services::trash::TrashService,
+    state::AppState,
+    utils::Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_trash))
+        .route("/:id/restore", post(restore))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_trash,
+    restore
+))]
+pub struct ApiDoc;
+
+/// Deleted decks and cards that can still be restored
+#[utoipa::path(
+    get,
+    path = "",
+    responses((status = 200, body = Vec<TrashItem>)),
+    tag = "trash"
+)]
+async fn list_trash(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<Vec<TrashItem>>> {
+    let items = TrashService::list(&state.db, user_id).await?;
+    Ok(Json(items))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    params(("id" = Uuid, Path, description = "Deck or card id")),
+    responses((status = 200, body = RestoredItem)),
+    tag = "trash"
+)]
+async fn restore(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RestoredItem>> {
+    let item = TrashService::restore(&state.db, user_id, id).await?;
+    state.queue_cache.invalidate_user(user_id).await;
+    Ok(Json(item))
+}
@@ -0,0 +1,291 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, patch, post},
+    Json, Router,
+};
+use utoipa::OpenApi;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    middleware::auth::UserId,
+    models::{
+        job::JobSummary, ApiKey, ChangePasswordDto, CreateApiKeyDto, CreatedApiKey, DeleteAccountDto,
+        NotificationPreferences, UpdateApiKeyDto,
+        UpdateNotificationPreferencesDto, UpdateProfileDto, UpdateUserPreferencesDto, UserPreferences,
+        UserResponse,
+    },
+    services::{
+        api_key::ApiKeyService, auth::Claims, data_export::DataExportService, notification::NotificationService, preferences::PreferencesService,
+        user::UserService,
+    },
+    state::AppState,
+    utils::Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/me", get(get_me).patch(update_me).delete(delete_me))
+        .route("/me/change-password", post(change_password))
+        .route("/me/verify-email", post(request_verification))
+        .route("/me/export", get(export_data))
+        .route("/me/notifications", get(get_notifications).put(update_notifications))
+        .route("/me/preferences", get(get_preferences).patch(update_preferences))
+        .route("/me/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/me/api-keys/:key_id", patch(update_api_key).delete(delete_api_key))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_me,
+    update_me,
+    delete_me,
+    change_password,
+    request_verification,
+    export_data,
+    get_notifications,
+    update_notifications,
+    get_preferences,
+    update_preferences,
+    list_api_keys,
+    create_api_key,
+    update_api_key,
+    delete_api_key
+))]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "/me",
+    responses((status = 200, body = UserResponse)),
+    tag = "users"
+)]
+async fn get_me(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<UserResponse>> {
+    let user = UserService::get_profile(&state.db, user_id).await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/me",
+    request_body = UpdateProfileDto,
+    responses((status = 200, body = UserResponse)),
+    tag = "users"
+)]
+async fn update_me(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<UpdateProfileDto>,
+) -> Result<Json<UserResponse>> {
+    dto.validate()?;
+
+    let user = UserService::update_profile(&state.db, user_id, dto).await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/change-password",
+    request_body = ChangePasswordDto,
+    responses((status = 204, description = "Password changed")),
+    tag = "users"
+)]
+async fn change_password(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<ChangePasswordDto>,
+) -> Result<StatusCode> {
+    dto.validate()?;
+
+    UserService::change_password(&state.db, user_id, dto).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Send a confirmation link to the account's current email address;
+/// confirm it with `POST /auth/verify-email`
+#[utoipa::path(
+    post,
+    path = "/me/verify-email",
+    responses((status = 202, description = "Verification email sent")),
+    tag = "users"
+)]
+async fn request_verification(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<StatusCode> {
+    UserService::request_verification(&state.db, user_id).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/me",
+    request_body = DeleteAccountDto,
+    responses((status = 204, description = "Account deleted; erased for good after the grace period")),
+    tag = "users"
+)]
+async fn delete_me(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<DeleteAccountDto>,
+) -> Result<StatusCode> {
+    UserService::delete_account(&state.db, user_id, dto).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Build a ZIP of all the account's data in the background and email a
+/// download link; progress shows in the job center
+#[utoipa::path(
+    get,
+    path = "/me/export",
+    responses((status = 202, description = "Export started, or the one already running", body = JobSummary)),
+    tag = "users"
+)]
+async fn export_data(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<(StatusCode, Json<JobSummary>)> {
+    let job = DataExportService::request(&state.db, &state.media, &state.mailer, &state.config.email, user_id)
+        .await?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/notifications",
+    responses((status = 200, body = NotificationPreferences)),
+    tag = "users"
+)]
+async fn get_notifications(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<NotificationPreferences>> {
+    let preferences = NotificationService::get_preferences(&state.db, user_id).await?;
+    Ok(Json(preferences))
+}
+
+#[utoipa::path(
+    put,
+    path = "/me/notifications",
+    request_body = UpdateNotificationPreferencesDto,
+    responses((status = 200, body = NotificationPreferences)),
+    tag = "users"
+)]
+async fn update_notifications(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<UpdateNotificationPreferencesDto>,
+) -> Result<Json<NotificationPreferences>> {
+    dto.validate()?;
+
+    let preferences = NotificationService::update_preferences(&state.db, user_id, dto).await?;
+    Ok(Json(preferences))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/preferences",
+    responses((status = 200, body = UserPreferences)),
+    tag = "users"
+)]
+async fn get_preferences(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Result<Json<UserPreferences>> {
+    let preferences = PreferencesService::get(&state.db, user_id).await?;
+    Ok(Json(preferences))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/me/preferences",
+    request_body = UpdateUserPreferencesDto,
+    responses((status = 200, body = UserPreferences)),
+    tag = "users"
+)]
+async fn update_preferences(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+    Json(dto): Json<UpdateUserPreferencesDto>,
+) -> Result<Json<UserPreferences>> {
+    dto.validate()?;
+
+    let preferences = PreferencesService::update(&state.db, user_id, dto).await?;
+    Ok(Json(preferences))
+}
+
+// API keys are managed with a signed-in session only, so a leaked key cannot
+// mint more keys or raise its own scope
+
+#[utoipa::path(
+    get,
+    path = "/me/api-keys",
+    responses((status = 200, body = Vec<ApiKey>)),
+    tag = "users"
+)]
+async fn list_api_keys(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<Json<Vec<ApiKey>>> {
+    let keys = ApiKeyService::list(&state.db, claims.sub).await?;
+    Ok(Json(keys))
+}
+
+/// Issue a key for `X-Api-Key`; the key itself is only returned here
+#[utoipa::path(
+    post,
+    path = "/me/api-keys",
+    request_body = CreateApiKeyDto,
+    responses((status = 201, description = "Key and its settings", body = CreatedApiKey)),
+    tag = "users"
+)]
+async fn create_api_key(
+    State(state): State<AppState>,
+    claims: Claims,
+    Json(dto): Json<CreateApiKeyDto>,
+) -> Result<(StatusCode, Json<CreatedApiKey>)> {
+    dto.validate()?;
+
+    let key = ApiKeyService::create(&state.db, claims.sub, dto).await?;
+    Ok((StatusCode::CREATED, Json(key)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/me/api-keys/{key_id}",
+    params(("key_id" = Uuid, Path, description = "API key id")),
+    request_body = UpdateApiKeyDto,
+    responses((status = 200, body = ApiKey)),
+    tag = "users"
+)]
+async fn update_api_key(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(key_id): Path<Uuid>,
+    Json(dto): Json<UpdateApiKeyDto>,
+) -> Result<Json<ApiKey>> {
+    dto.validate()?;
+
+    let key = ApiKeyService::update(&state.db, claims.sub, key_id, dto).await?;
+    Ok(Json(key))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/me/api-keys/{key_id}",
+    params(("key_id" = Uuid, Path, description = "API key id")),
+    responses((status = 204, description = "Key revoked")),
+    tag = "users"
+)]
+async fn delete_api_key(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(key_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    ApiKeyService::delete(&state.db, claims.sub, key_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,190 @@
+use axum::{
+    extract::{
+        ws::{rejection::WebSocketUpgradeRejection, Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+use utoipa::{IntoParams, OpenApi};
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::{OptionalClaims, UserId},
+    models::ai::{WsClientMessage, WsMessage, WsTicket},
+    services::ws::{WsSubscriptionService, TICKET_TTL_SECONDS},
+    state::AppState,
+    utils::{AppError, ErrorResponse, Result},
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct WsAuthQuery {
+    ticket: Option<String>, // Browsers cannot set headers on upgrade requests
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(ws_handler))
+        .route("/ticket", post(create_ticket))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    ws_handler,
+    create_ticket
+))]
+pub struct ApiDoc;
+
+/// Issue a single-use ticket for connecting without an Authorization header.
+/// Tickets rather than the access token go in the URL, so the token never
+/// ends up in proxy or access logs.
+#[utoipa::path(
+    post,
+    path = "/ticket",
+    responses(
+        (status = 200, body = WsTicket),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    ),
+    tag = "ws"
+)]
+async fn create_ticket(
+    State(state): State<AppState>,
+    UserId(user_id): UserId,
+) -> Json<WsTicket> {
+    let ticket = state.ws.issue_ticket(user_id).await;
+    Json(WsTicket {
+        ticket,
+        expires_in: TICKET_TTL_SECONDS,
+    })
+}
+
+/// Upgrade to a WebSocket after validating the JWT from the Authorization
+/// header or redeeming the `ticket` query parameter
+#[utoipa::path(
+    get,
+    path = "",
+    params(WsAuthQuery),
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol"),
+        (status = 401, description = "Missing token, or an unknown, used or expired ticket", body = ErrorResponse),
+    ),
+    tag = "ws"
+)]
+async fn ws_handler(
+    ws: std::result::Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+    State(state): State<AppState>,
+    OptionalClaims(claims): OptionalClaims,
+    Query(query): Query<WsAuthQuery>,
+) -> Result<Response> {
+    let user_id = match (claims, query.ticket) {
+        (Some(claims), _) => claims.sub,
+        (None, Some(ticket)) => state
+            .ws
+            .redeem_ticket(&ticket)
+            .await
+            .ok_or(AppError::Unauthorized)?,
+        (None, None) => return Err(AppError::Unauthorized),
+    };
+
+    // Authentication is checked first, so a bad ticket is a 401 whatever the request
+    let ws = match ws {
+        Ok(ws) => ws,
+        Err(rejection) => return Ok(rejection.into_response()),
+    };
+
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, user_id)))
+}
+
+async fn handle_socket(socket: WebSocket, state: AppState, user_id: Uuid) {
+    let connection_id = Uuid::new_v4().to_string();
+    let (sender, mut receiver) = mpsc::unbounded_channel::<WsMessage>();
+    state.ws.register(user_id, &connection_id, sender.clone()).await;
+
+    let (mut socket_tx, mut socket_rx) = socket.split();
+
+    let mut send_task = tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            let Ok(text) = serde_json::to_string(&message) else {
+                continue;
+            };
+            if socket_tx.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let _ = sender.send(WsMessage::new(
+        "connected",
+        json!({ "connection_id": connection_id }),
+    ));
+
+    while let Some(Ok(frame)) = tokio::select! {
+        frame = socket_rx.next() => frame,
+        _ = &mut send_task => None,
+    } {
+        let text = match frame {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let reply = match serde_json::from_str::<WsClientMessage>(&text) {
+            Ok(message) => handle_client_message(&state, user_id, &connection_id, message).await,
+            Err(e) => Err(AppError::BadRequest(format!("Invalid message: {}", e))),
+        };
+
+        let reply = reply.unwrap_or_else(|e| WsMessage::new("error", json!({ "error": e.to_string() })));
+        let _ = sender.send(reply);
+    }
+
+    send_task.abort();
+    state.ws.unregister(user_id, &connection_id).await;
+    if let Err(e) = WsSubscriptionService::disconnect(&state.db, &connection_id).await {
+        tracing::warn!("Failed to record WebSocket disconnect: {}", e);
+    }
+}
+
+async fn handle_client_message(
+    state: &AppState,
+    user_id: Uuid,
+    connection_id: &str,
+    message: WsClientMessage,
+) -> Result<WsMessage> {
+    match message {
+        WsClientMessage::Subscribe { subscription_type } => {
+            let subscription = WsSubscriptionService::subscribe(
+                &state.db,
+                user_id,
+                connection_id,
+                &subscription_type,
+            )
+            .await?;
+            state
+                .ws
+                .set_subscribed(user_id, connection_id, &subscription_type, true)
+                .await;
+            Ok(WsMessage::new("subscribed", json!(subscription)))
+        }
+        WsClientMessage::Unsubscribe { subscription_type } => {
+            WsSubscriptionService::unsubscribe(&state.db, connection_id, &subscription_type).await?;
+            state
+                .ws
+                .set_subscribed(user_id, connection_id, &subscription_type, false)
+                .await;
+            Ok(WsMessage::new(
+                "unsubscribed",
+                json!({ "subscription_type": subscription_type }),
+            ))
+        }
+        WsClientMessage::Ping => {
+            WsSubscriptionService::touch(&state.db, connection_id).await?;
+            Ok(WsMessage::new("pong", json!({})))
+        }
+    }
+}
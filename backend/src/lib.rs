@@ -0,0 +1,148 @@
+pub mod config;
+pub mod handlers;
+pub mod logging;
+pub mod middleware;
+pub mod models;
+pub mod openapi;
+pub mod services;
+pub mod state;
+pub mod utils;
+
+use axum::{
+    extract::DefaultBodyLimit,
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::{from_fn, from_fn_with_state},
+    Router,
+};
+use std::time::Duration;
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    trace::TraceLayer,
+};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{
+    middleware::{
+        guest::require_guest_token,
+        rate_limit::RateLimits,
+        request_id::{request_id, REQUEST_ID_HEADER},
+        security_headers::{security_headers, SecurityHeaders},
+    },
+    state::AppState,
+};
+
+pub fn create_app(state: AppState) -> Router {
+    // Configure CORS
+    let origins: Vec<HeaderValue> = state
+        .config
+        .cors
+        .origins()
+        .iter()
+        .filter_map(|origin| match origin.parse::<HeaderValue>() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid CORS origin: {}", origin);
+                None
+            }
+        })
+        .collect();
+
+    let cors = CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            HeaderName::from_static("x-device-id"),
+            header::IF_NONE_MATCH,
+            REQUEST_ID_HEADER.clone(),
+        ])
+        .expose_headers([header::ETAG, REQUEST_ID_HEADER.clone()])
+        .allow_credentials(true)
+        .max_age(Duration::from_secs(state.config.cors.max_age_seconds));
+
+    let security = SecurityHeaders::from_config(&state.config.security);
+    let on_response = logging::OnResponse::from_config(&state.config.logging);
+
+    // Build the router
+    Router::new()
+        .nest("/api/v1", api_routes(state, &security))
+        .merge(
+            SwaggerUi::new(openapi::SWAGGER_UI_PATH)
+                .url(openapi::OPENAPI_JSON_PATH, openapi::ApiDoc::openapi()),
+        )
+        .layer(from_fn_with_state(security, security_headers))
+        .layer(cors)
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(logging::request_span)
+                .on_response(on_response)
+                // Server errors are logged by `on_response`
+                .on_failure(()),
+        )
+        // Outermost, so the id is set before the trace span opens
+        .layer(from_fn(request_id))
+}
+
+fn api_routes(state: AppState, security: &SecurityHeaders) -> Router {
+    use axum::routing::get;
+
+    let limits = RateLimits::from_config(state.config.clone());
+    limits.spawn_cleanup(Duration::from_secs(300));
+
+    let auth = limits
+        .apply(handlers::auth::credential_routes(), &limits.auth)
+        .merge(handlers::auth::session_routes());
+
+    let authenticated = Router::new()
+        .nest("/users", handlers::user::routes())
+        .nest("/dashboard", handlers::dashboard::routes())
+        .nest("/folders", handlers::folder::routes())
+        .nest("/decks", handlers::deck::routes())
+        .nest("/cards", handlers::card::routes())
+        .nest("/smart-decks", handlers::smart_deck::routes())
+        .nest("/study", handlers::study::routes())
+        .nest("/progress", handlers::progress::routes())
+        .nest("/import-export", handlers::import_export::routes())
+        .nest("/ai", handlers::ai::routes())
+        .nest("/capture", handlers::capture::routes())
+        .nest("/trash", handlers::trash::routes())
+        .nest("/jobs", handlers::job::routes())
+        .nest("/admin", handlers::admin::routes())
+        .nest("/search", handlers::search::routes())
+        .nest("/sync", handlers::sync::routes())
+        .nest("/ws", handlers::ws::routes());
+
+    // Badges and deck embeds are meant to be shown in third-party pages;
+    // the outer security layer leaves the framing policy set here alone
+    let public = handlers::public::routes()
+        .route_layer(from_fn_with_state(security.clone().embeddable(), security_headers));
+
+    let guest = handlers::guest::routes()
+        .route_layer(from_fn_with_state(state.clone(), require_guest_token));
+
+    // JSON bodies are capped per route group; file uploads lift the cap on
+    // their own routes and enforce MAX_FILE_SIZE while streaming
+    let body_limit = DefaultBodyLimit::max(state.config.upload.max_body_size);
+    let inbound_body_limit = DefaultBodyLimit::max(state.config.upload.max_inbound_body_size);
+
+    Router::new()
+        .nest("/auth", auth.layer(body_limit))
+        .merge(limits.apply(authenticated, &limits.api).layer(body_limit))
+        .nest("/public", limits.apply(public, &limits.public).layer(body_limit))
+        .nest(
+            "/inbound",
+            limits.apply(handlers::inbound::routes(), &limits.public).layer(inbound_body_limit),
+        )
+        .nest("/guest", limits.apply(guest, &limits.public).layer(body_limit))
+        .nest("/media", limits.apply(handlers::media::routes(), &limits.public).layer(body_limit))
+        .route("/meta", get(handlers::meta::meta))
+        // Health check endpoints
+        .route("/health", get(handlers::health::health))
+        .route("/health/detailed", get(handlers::health::health_detailed))
+        .route("/health/migrations", get(handlers::health::migrations))
+        .route("/liveness", get(handlers::health::liveness))
+        .route("/readiness", get(handlers::health::readiness))
+        .with_state(state)
+}
@@ -0,0 +1,118 @@
+//! Log output and per-request spans.
+//!
+//! `LOG_FORMAT=json` writes one JSON object per event, with the fields of
+//! the enclosing request span (`request_id`, `method`, `route`, `user_id`,
+//! `status`, `latency_ms`) alongside the event's own, for log collectors.
+//! The default `pretty` format is meant for reading in a terminal.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    response::Response,
+};
+use rand::Rng;
+use std::time::Duration;
+use tracing::{field::Empty, Span};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use uuid::Uuid;
+
+use crate::{config::LoggingConfig, middleware::request_id::REQUEST_ID_HEADER};
+
+const DEFAULT_FILTER: &str = "deckoracle_backend=debug,tower_http=debug";
+
+/// Install the global subscriber. `RUST_LOG` overrides the default filter.
+pub fn init(config: &LoggingConfig) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| DEFAULT_FILTER.into());
+    let registry = tracing_subscriber::registry().with(filter);
+
+    if config.is_json() {
+        registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true)
+                    .with_span_list(false),
+            )
+            .init();
+    } else {
+        registry.with(tracing_subscriber::fmt::layer()).init();
+    }
+}
+
+/// The span every request is handled in. `user_id`, `status` and
+/// `latency_ms` are filled in once known.
+pub fn request_span(request: &Request) -> Span {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    // The route pattern, so logs group by endpoint rather than by id
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or_default();
+
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        // Only the path: query strings can carry tickets and signed links
+        uri = %request.uri().path(),
+        route = %route,
+        request_id = %request_id,
+        user_id = Empty,
+        status = Empty,
+        latency_ms = Empty,
+    )
+}
+
+/// Attach the authenticated user to the current request span
+pub fn record_user_id(user_id: Uuid) {
+    Span::current().record("user_id", tracing::field::display(user_id));
+}
+
+/// Record how the request ended and log it, subject to sampling
+#[derive(Clone)]
+pub struct OnResponse {
+    sample_rate: f64,
+    slow_after: Duration,
+}
+
+impl OnResponse {
+    pub fn from_config(config: &LoggingConfig) -> Self {
+        Self {
+            sample_rate: config.request_sample_rate,
+            slow_after: Duration::from_millis(config.slow_request_ms),
+        }
+    }
+
+    /// Server errors and slow requests are always logged; the rest with
+    /// probability `sample_rate`
+    pub fn should_log(&self, status: u16, latency: Duration) -> bool {
+        status >= 500
+            || latency >= self.slow_after
+            || self.sample_rate >= 1.0
+            || (self.sample_rate > 0.0 && rand::thread_rng().gen_bool(self.sample_rate))
+    }
+}
+
+impl<B> tower_http::trace::OnResponse<B> for OnResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        let status = response.status().as_u16();
+        let latency_ms = latency.as_millis() as u64;
+        span.record("status", status);
+        span.record("latency_ms", latency_ms);
+
+        if !self.should_log(status, latency) {
+            return;
+        }
+        if status >= 500 {
+            tracing::error!(status, latency_ms, "request failed");
+        } else if latency >= self.slow_after {
+            tracing::warn!(status, latency_ms, "slow request");
+        } else {
+            tracing::info!(status, latency_ms, "request completed");
+        }
+    }
+}
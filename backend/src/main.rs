@@ -1,62 +1,109 @@
-mod config;
-mod handlers;
-mod middleware;
-mod models;
-mod services;
-mod state;
-mod utils;
-
-use axum::{
-    http::{header, Method},
-    Router,
-};
 use std::net::SocketAddr;
-use tower_http::{
-    cors::{Any, CorsLayer},
-    trace::TraceLayer,
-};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::{config::Config, state::AppState};
+use deckoracle_backend::{
+    config::Config,
+    create_app, logging,
+    services::{
+        data_export::DataExportService, leaderboard::LeaderboardService,
+        learning_patterns::LearningPatternService, learning_stats::LearningStatsService,
+        migrations::MIGRATOR,
+        seed::SeedService,
+        notification::NotificationService,
+        study::StudyService, trash::TrashService, user::UserService,
+    },
+    state::AppState,
+};
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "deckoracle_backend=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    // Load configuration
+    // Load configuration, then initialize tracing in the configured format
     let config = Config::from_env().expect("Failed to load configuration");
+    logging::init(&config.logging);
+    config.validate().expect("Invalid configuration");
     
     tracing::info!("Starting DeckOracle backend server...");
     
+    // Get bind address
+    let addr: SocketAddr = config
+        .get_bind_address()
+        .parse()
+        .expect("Failed to parse bind address");
+
     // Create application state
-    let state = AppState::new(config.clone())
+    let state = AppState::new(config)
         .await
         .expect("Failed to create application state");
 
-    // Run migrations (skip if already applied)
-    if let Err(e) = sqlx::migrate!("./migrations")
-        .run(&state.db)
-        .await 
-    {
+    // Run migrations; strict mode refuses to serve an outdated schema
+    if let Err(e) = MIGRATOR.run(&state.db).await {
+        if state.config.strict_migrations() {
+            tracing::error!("Migrations failed: {}", e);
+            std::process::exit(1);
+        }
         tracing::warn!("Migration warning (may already be applied): {}", e);
     }
 
-    // Build the application routes
-    let app = create_app(state, config).await;
+    // `cargo run -- seed` fills the database with demo data and exits
+    if std::env::args().nth(1).as_deref() == Some("seed") {
+        if state.config.is_production() {
+            tracing::error!("Seeding is disabled in production");
+            std::process::exit(1);
+        }
+        let summary = SeedService::seed_demo(&state.db)
+            .await
+            .expect("Failed to seed demo data");
+        tracing::info!(
+            "Seeded {} decks with {} cards and {} study sessions; sign in as {} / {}",
+            summary.decks,
+            summary.cards,
+            summary.study_sessions,
+            summary.email,
+            summary.password
+        );
+        return;
+    }
 
-    // Get bind address
-    let addr: SocketAddr = Config::from_env()
-        .expect("Failed to load configuration")
-        .get_bind_address()
-        .parse()
-        .expect("Failed to parse bind address");
+    // Purge trash past its retention period
+    TrashService::spawn_sweeper(state.db.clone(), std::time::Duration::from_secs(3600));
+
+    // Erase deleted accounts after their grace period and expired data exports
+    UserService::spawn_account_eraser(state.db.clone(), std::time::Duration::from_secs(3600));
+    DataExportService::spawn_sweeper(state.db.clone(), state.media.clone(), std::time::Duration::from_secs(3600));
+
+    // Complete timed study sessions that ran out of time and abandon idle ones
+    let abandon_after_hours = state.config.study.abandon_after_hours;
+    StudyService::spawn_session_sweeper(
+        state.db.clone(),
+        std::time::Duration::from_secs(30),
+        (abandon_after_hours > 0).then(|| std::time::Duration::from_secs(abandon_after_hours * 3600)),
+    );
+
+    // Recompute leaderboard standings
+    LeaderboardService::spawn_refresher(state.db.clone(), std::time::Duration::from_secs(600));
+
+    // Recompute lifetime learning totals
+    LearningStatsService::spawn_refresher(state.db.clone(), std::time::Duration::from_secs(600));
+
+    // Mine study events for each user's learning patterns
+    LearningPatternService::spawn_analyzer(state.db.clone(), std::time::Duration::from_secs(3600));
+
+    // Email review reminders at each user's chosen time
+    NotificationService::spawn_reminder_sweeper(
+        state.db.clone(),
+        state.mailer.clone(),
+        state.config.email.clone(),
+        std::time::Duration::from_secs(300),
+    );
+
+    // Keep the study queues of recently active users built, ready for the
+    // next study day once the UTC day rolls over
+    state.queue_cache.clone().spawn_precomputer(
+        state.db.clone(),
+        std::time::Duration::from_secs(state.config.study.queue_cache_ttl_seconds.max(60)),
+    );
+
+    // Build the application routes
+    let app = create_app(state);
 
     tracing::info!("Server listening on {}", addr);
 
@@ -65,49 +112,8 @@ async fn main() {
         .await
         .expect("Failed to bind to address");
     
-    axum::serve(listener, app)
+    // Connection info lets rate limiting key on the client IP
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .expect("Failed to start server");
 }
-
-async fn create_app(state: AppState, config: Config) -> Router {
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(
-            config
-                .cors
-                .origin
-                .parse::<axum::http::HeaderValue>()
-                .unwrap(),
-        )
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
-        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
-        .allow_credentials(true);
-
-    // Build the router
-    Router::new()
-        .nest("/api/v1", api_routes(state))
-        .layer(cors)
-        .layer(TraceLayer::new_for_http())
-}
-
-fn api_routes(state: AppState) -> Router {
-    use axum::routing::get;
-    
-    Router::new()
-        .nest("/auth", handlers::auth::routes())
-        .nest("/folders", handlers::folder::routes())
-        .nest("/decks", handlers::deck::routes())
-        .nest("/cards", handlers::card::routes())
-        .nest("/study", handlers::study::routes())
-        .nest("/progress", handlers::progress::routes())
-        .nest("/import-export", handlers::import_export::routes())
-        .nest("/ai", handlers::ai::routes())
-        // .nest("/search", handlers::search::routes()) // TODO: Implement search
-        // Health check endpoints
-        .route("/health", get(handlers::health::health))
-        .route("/health/detailed", get(handlers::health::health_detailed))
-        .route("/liveness", get(handlers::health::liveness))
-        .route("/readiness", get(handlers::health::readiness))
-        .with_state(state)
-}
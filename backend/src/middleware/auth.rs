@@ -1,9 +1,10 @@
 use axum::{
     async_trait,
-    extract::{FromRef, FromRequestParts},
+    extract::{ConnectInfo, FromRef, FromRequestParts},
     http::{header, request::Parts, StatusCode},
     RequestPartsExt,
 };
+use std::net::SocketAddr;
 use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
@@ -12,7 +13,15 @@ use uuid::Uuid;
 
 use crate::{
     config::Config,
-    services::auth::{AuthService, Claims},
+    logging,
+    middleware::rate_limit::forwarded_client_ip,
+    models::{ApiKeyScope, ClientInfo},
+    services::{
+        admin::AdminService,
+        api_key::{ApiKeyService, API_KEY_HEADER},
+        auth::{AuthService, Claims},
+        user::UserService,
+    },
     state::AppState,
     utils::AppError,
 };
@@ -38,6 +47,7 @@ where
         
         // Validate the JWT token
         let claims = AuthService::validate_jwt(bearer.token(), &app_state.config)?;
+        logging::record_user_id(claims.sub);
 
         Ok(claims)
     }
@@ -67,7 +77,10 @@ where
             
             // Try to validate the JWT token
             match AuthService::validate_jwt(bearer.token(), &app_state.config) {
-                Ok(claims) => Ok(OptionalClaims(Some(claims))),
+                Ok(claims) => {
+                    logging::record_user_id(claims.sub);
+                    Ok(OptionalClaims(Some(claims)))
+                }
                 Err(_) => Ok(OptionalClaims(None)),
             }
         } else {
@@ -76,7 +89,9 @@ where
     }
 }
 
-/// Simple user ID extractor that gets the user ID from JWT claims
+/// User ID from the JWT claims, or from an `X-Api-Key` header. Read-only
+/// keys are refused on anything but safe methods, and each key is held to
+/// its own rate limit.
 pub struct UserId(pub Uuid);
 
 #[async_trait]
@@ -88,8 +103,21 @@ where
     type Rejection = AppError;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        let claims = Claims::from_request_parts(parts, state).await?;
-        Ok(UserId(claims.sub))
+        let Some(key) = parts.headers.get(API_KEY_HEADER) else {
+            let claims = Claims::from_request_parts(parts, state).await?;
+            return Ok(UserId(claims.sub));
+        };
+
+        let app_state = AppState::from_ref(state);
+        let key = key.to_str().map_err(|_| AppError::Unauthorized)?;
+        let api_key = ApiKeyService::authenticate(&app_state.db, key).await?;
+        if api_key.scope == ApiKeyScope::Read && !parts.method.is_safe() {
+            return Err(AppError::Forbidden);
+        }
+        ApiKeyService::check_rate_limit(app_state.api_key_limits.as_ref(), &app_state.config, &api_key).await?;
+        logging::record_user_id(api_key.user_id);
+
+        Ok(UserId(api_key.user_id))
     }
 }
 
@@ -109,3 +137,96 @@ where
         Ok(OptionalUserId(optional_claims.0.map(|c| c.sub)))
     }
 }
+
+/// Admin extractor that requires an authenticated user with the admin flag set
+pub struct AdminUser(pub Uuid);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminUser
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // API keys are for scripts acting as the user, not for administration
+        let user_id = Claims::from_request_parts(parts, state).await?.sub;
+        let app_state = AppState::from_ref(state);
+
+        if !AdminService::is_admin(&app_state.db, user_id).await? {
+            return Err(AppError::Forbidden);
+        }
+
+        Ok(AdminUser(user_id))
+    }
+}
+
+/// User extractor for content-creating writes. When
+/// `REQUIRE_EMAIL_VERIFICATION` is set, accounts whose email address has not
+/// been confirmed are rejected with `EMAIL_NOT_VERIFIED`; otherwise it
+/// behaves like `UserId`.
+pub struct VerifiedUser(pub Uuid);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for VerifiedUser
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let UserId(user_id) = UserId::from_request_parts(parts, state).await?;
+        let app_state = AppState::from_ref(state);
+
+        if app_state.config.account.require_email_verification
+            && !UserService::is_email_verified(&app_state.db, user_id).await?
+        {
+            return Err(AppError::EmailNotVerified);
+        }
+
+        Ok(VerifiedUser(user_id))
+    }
+}
+
+/// Longest user agent kept for auditing
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// Client address and user agent. The address honours X-Forwarded-For under
+/// the same setting as rate limiting.
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let app_state = AppState::from_ref(state);
+
+        let forwarded = app_state
+            .config
+            .rate_limit
+            .trust_forwarded_for
+            .then(|| forwarded_client_ip(&parts.headers))
+            .flatten()
+            .and_then(|ip| ip.parse().ok());
+        let connected = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(MAX_USER_AGENT_LENGTH).collect());
+
+        Ok(ClientInfo {
+            ip_address: forwarded.or(connected),
+            user_agent,
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{services::guest::GuestService, state::AppState, utils::AppError};
+
+/// Admit requests carrying a live guest token as `Authorization: Bearer`,
+/// attaching the `GuestAccess` it grants. Guest routes only serve the deck in
+/// that grant, so a token can never reach another deck or any user route.
+pub async fn require_guest_token(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(AppError::Unauthorized)?;
+
+    let access = GuestService::authenticate(&state.db, token.trim()).await?;
+    request.extensions_mut().insert(access);
+
+    Ok(next.run(request).await)
+}
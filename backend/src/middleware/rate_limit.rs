@@ -1,19 +1,32 @@
 use axum::{
     body::Body,
     extract::{ConnectInfo, Request, State},
-    http::StatusCode,
-    middleware::Next,
+    http::{header, HeaderMap, StatusCode},
+    middleware::{from_fn_with_state, Next},
     response::{IntoResponse, Response},
+    Router,
 };
+use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
+use redis::aio::ConnectionManager;
 use std::{
     collections::HashMap,
     net::SocketAddr,
     sync::Arc,
 };
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock};
 
-use crate::utils::AppError;
+use crate::{config::Config, services::auth::AuthService, state::AppState, utils::AppError};
+
+/// How requests are grouped into buckets
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// Client IP address
+    #[default]
+    Ip,
+    /// Subject of a valid bearer token, falling back to the client IP
+    User,
+}
 
 /// Rate limit configuration
 #[derive(Clone, Debug)]
@@ -31,52 +44,51 @@ impl Default for RateLimitConfig {
     }
 }
 
-/// Store for rate limit tracking
-#[derive(Clone)]
-pub struct RateLimitStore {
-    requests: Arc<RwLock<HashMap<String, Vec<DateTime<Utc>>>>>,
-    config: RateLimitConfig,
-}
+/// Where request history is kept. The in-memory backend is per process;
+/// shared backends let every replica enforce the same limits.
+#[async_trait]
+pub trait RateLimitBackend: Send + Sync {
+    /// Record a request for `key` unless it would exceed `config`, returning
+    /// whether the request is allowed
+    async fn hit(&self, key: &str, config: &RateLimitConfig) -> Result<bool, String>;
 
-impl RateLimitStore {
-    pub fn new(config: RateLimitConfig) -> Self {
-        Self {
-            requests: Arc::new(RwLock::new(HashMap::new())),
-            config,
-        }
-    }
+    /// Drop history that has left the window
+    async fn cleanup(&self, config: &RateLimitConfig);
+}
 
-    pub fn with_defaults() -> Self {
-        Self::new(RateLimitConfig::default())
-    }
+/// Sliding-window history held in process memory
+#[derive(Default)]
+pub struct MemoryRateLimitBackend {
+    requests: RwLock<HashMap<String, Vec<DateTime<Utc>>>>,
+}
 
-    /// Check if a client has exceeded the rate limit
-    async fn check_rate_limit(&self, client_id: &str) -> bool {
+#[async_trait]
+impl RateLimitBackend for MemoryRateLimitBackend {
+    async fn hit(&self, key: &str, config: &RateLimitConfig) -> Result<bool, String> {
         let mut requests = self.requests.write().await;
         let now = Utc::now();
-        let window_start = now - Duration::seconds(self.config.window_seconds);
+        let window_start = now - Duration::seconds(config.window_seconds);
 
         // Get or create request history for this client
-        let client_requests = requests.entry(client_id.to_string()).or_insert_with(Vec::new);
+        let client_requests = requests.entry(key.to_string()).or_insert_with(Vec::new);
 
         // Remove old requests outside the window
         client_requests.retain(|timestamp| *timestamp > window_start);
 
         // Check if limit exceeded
-        if client_requests.len() >= self.config.max_requests as usize {
-            return false; // Rate limit exceeded
+        if client_requests.len() >= config.max_requests as usize {
+            return Ok(false); // Rate limit exceeded
         }
 
         // Add current request
         client_requests.push(now);
-        true
+        Ok(true)
     }
 
-    /// Clean up old entries periodically (should be called by a background task)
-    pub async fn cleanup(&self) {
+    async fn cleanup(&self, config: &RateLimitConfig) {
         let mut requests = self.requests.write().await;
         let now = Utc::now();
-        let window_start = now - Duration::seconds(self.config.window_seconds * 2);
+        let window_start = now - Duration::seconds(config.window_seconds * 2);
 
         // Remove entries that have no recent requests
         requests.retain(|_, timestamps| {
@@ -86,20 +98,203 @@ impl RateLimitStore {
     }
 }
 
+/// Same sliding window as the memory backend, as a sorted set of request
+/// timestamps per key. The script runs atomically, so concurrent replicas
+/// cannot both take the last slot.
+const REDIS_HIT_SCRIPT: &str = r#"
+local now = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+if redis.call('ZCARD', KEYS[1]) >= tonumber(ARGV[3]) then
+    return 0
+end
+redis.call('ZADD', KEYS[1], now, ARGV[4])
+redis.call('PEXPIRE', KEYS[1], window)
+return 1
+"#;
+
+const REDIS_KEY_PREFIX: &str = "deckoracle:ratelimit:";
+
+/// Sliding-window history in Redis, shared by every replica. Keys expire
+/// with their window, so there is nothing to clean up.
+pub struct RedisRateLimitBackend {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+    script: redis::Script,
+}
+
+impl RedisRateLimitBackend {
+    /// Validates the URL; the connection is opened on first use so the
+    /// server can start while Redis is unavailable
+    pub fn new(url: &str) -> redis::RedisResult<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            connection: OnceCell::new(),
+            script: redis::Script::new(REDIS_HIT_SCRIPT),
+        })
+    }
+
+    async fn connection(&self) -> redis::RedisResult<ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl RateLimitBackend for RedisRateLimitBackend {
+    async fn hit(&self, key: &str, config: &RateLimitConfig) -> Result<bool, String> {
+        let mut connection = self.connection().await.map_err(|e| e.to_string())?;
+        let now = Utc::now().timestamp_millis();
+
+        let allowed: i32 = self
+            .script
+            .key(format!("{}{}", REDIS_KEY_PREFIX, key))
+            .arg(now)
+            .arg(config.window_seconds * 1000)
+            .arg(config.max_requests)
+            .arg(format!("{}-{}", now, uuid::Uuid::new_v4())) // Unique per request
+            .invoke_async(&mut connection)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(allowed == 1)
+    }
+
+    async fn cleanup(&self, _config: &RateLimitConfig) {}
+}
+
+/// Store for rate limit tracking
+#[derive(Clone)]
+pub struct RateLimitStore {
+    backend: Arc<dyn RateLimitBackend>,
+    group: &'static str,
+    config: RateLimitConfig,
+    key: RateLimitKey,
+    trust_forwarded_for: bool,
+    app_config: Option<Arc<Config>>, // Needed to validate tokens when keyed by user
+}
+
+impl RateLimitStore {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            backend: Arc::new(MemoryRateLimitBackend::default()),
+            group: "default",
+            config,
+            key: RateLimitKey::Ip,
+            trust_forwarded_for: false,
+            app_config: None,
+        }
+    }
+
+    pub fn with_defaults() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+
+    /// Keep history in `backend`. `group` namespaces this store's keys so
+    /// several stores can share one backend.
+    pub fn with_backend(mut self, backend: Arc<dyn RateLimitBackend>, group: &'static str) -> Self {
+        self.backend = backend;
+        self.group = group;
+        self
+    }
+
+    /// Bucket requests by authenticated user instead of by IP
+    pub fn keyed_by_user(mut self, app_config: Arc<Config>) -> Self {
+        self.key = RateLimitKey::User;
+        self.app_config = Some(app_config);
+        self
+    }
+
+    /// Take the client IP from X-Forwarded-For. Only safe behind a reverse
+    /// proxy that sets the header, since clients can send their own.
+    pub fn trust_forwarded_for(mut self, trust: bool) -> Self {
+        self.trust_forwarded_for = trust;
+        self
+    }
+
+    /// Identify the client a request counts against
+    fn client_key(&self, headers: &HeaderMap, connect_info: Option<&SocketAddr>) -> String {
+        if self.key == RateLimitKey::User {
+            if let Some(user_id) = self.token_subject(headers) {
+                return format!("user:{}", user_id);
+            }
+        }
+
+        let forwarded = self
+            .trust_forwarded_for
+            .then(|| forwarded_client_ip(headers))
+            .flatten();
+
+        match (forwarded, connect_info) {
+            (Some(ip), _) => format!("ip:{}", ip),
+            (None, Some(addr)) => format!("ip:{}", addr.ip()),
+            // Without connection info (e.g. in tests) all requests share one bucket
+            (None, None) => "unknown".to_string(),
+        }
+    }
+
+    fn token_subject(&self, headers: &HeaderMap) -> Option<uuid::Uuid> {
+        let app_config = self.app_config.as_ref()?;
+        let token = headers
+            .get(header::AUTHORIZATION)?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")?;
+
+        // Invalid tokens are keyed by IP so they cannot mint fresh buckets
+        AuthService::validate_jwt(token, app_config).ok().map(|claims| claims.sub)
+    }
+
+    /// Check if a client has exceeded the rate limit. Backend failures let
+    /// the request through rather than taking the API down with the store.
+    async fn check_rate_limit(&self, client_id: &str) -> bool {
+        let key = format!("{}:{}", self.group, client_id);
+        match self.backend.hit(&key, &self.config).await {
+            Ok(allowed) => allowed,
+            Err(e) => {
+                tracing::warn!("Rate limit backend unavailable, allowing request: {}", e);
+                true
+            }
+        }
+    }
+
+    /// Run `cleanup` on a fixed interval in the background
+    pub fn spawn_cleanup(&self, every: std::time::Duration) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                store.cleanup().await;
+            }
+        });
+    }
+
+    /// Clean up old entries periodically (should be called by a background task)
+    pub async fn cleanup(&self) {
+        self.backend.cleanup(&self.config).await;
+    }
+}
+
 /// Rate limiting middleware
 pub async fn rate_limit_middleware(
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     State(store): State<RateLimitStore>,
     request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
-    // Use IP address as client identifier
-    let client_id = addr.ip().to_string();
+    let client_id = store.client_key(
+        request.headers(),
+        connect_info.as_ref().map(|ConnectInfo(addr)| addr),
+    );
 
     // Check rate limit
     if !store.check_rate_limit(&client_id).await {
         return Ok((
             StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, store.config.window_seconds.to_string())],
             "Too many requests. Please try again later.",
         ).into_response());
     }
@@ -116,6 +311,14 @@ pub fn create_auth_rate_limiter() -> RateLimitStore {
     })
 }
 
+/// Create a rate limiter for unauthenticated public endpoints (badges, embeds)
+pub fn create_public_rate_limiter() -> RateLimitStore {
+    RateLimitStore::new(RateLimitConfig {
+        max_requests: 120,   // 120 requests
+        window_seconds: 60,  // per minute
+    })
+}
+
 /// Create a general rate limiter for API endpoints
 pub fn create_api_rate_limiter() -> RateLimitStore {
     RateLimitStore::new(RateLimitConfig {
@@ -123,3 +326,110 @@ pub fn create_api_rate_limiter() -> RateLimitStore {
         window_seconds: 60,  // per minute
     })
 }
+
+/// The rightmost X-Forwarded-For entry, which is the address the nearest
+/// proxy saw; entries further left are supplied by the client
+pub(crate) fn forwarded_client_ip(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-forwarded-for")?
+        .to_str()
+        .ok()?
+        .rsplit(',')
+        .map(str::trim)
+        .find(|entry| !entry.is_empty())
+        .map(str::to_string)
+}
+
+/// Redis backend for `url`, or `None` (in-memory limits) if the URL is invalid
+fn shared_backend(url: &str) -> Option<Arc<dyn RateLimitBackend>> {
+    match RedisRateLimitBackend::new(url) {
+        Ok(backend) => Some(Arc::new(backend)),
+        Err(e) => {
+            tracing::error!("Invalid RATE_LIMIT_REDIS_URL, using in-memory rate limits: {}", e);
+            None
+        }
+    }
+}
+
+/// Backend for limits kept outside the route layers, such as per API key:
+/// Redis when configured, otherwise in process memory
+pub fn backend_from_config(config: &Config) -> Arc<dyn RateLimitBackend> {
+    config
+        .rate_limit
+        .redis_url
+        .as_deref()
+        .and_then(shared_backend)
+        .unwrap_or_else(|| Arc::new(MemoryRateLimitBackend::default()))
+}
+
+/// Limiters for each route group, built from configuration
+#[derive(Clone)]
+pub struct RateLimits {
+    enabled: bool,
+    pub auth: RateLimitStore,
+    pub api: RateLimitStore,
+    pub public: RateLimitStore,
+}
+
+impl RateLimits {
+    pub fn from_config(config: Arc<Config>) -> Self {
+        let settings = &config.rate_limit;
+        let trust = settings.trust_forwarded_for;
+        let shared = settings.redis_url.as_deref().and_then(shared_backend);
+        let store = |config: RateLimitConfig, group: &'static str| {
+            let store = RateLimitStore::new(config).trust_forwarded_for(trust);
+            match &shared {
+                Some(backend) => store.with_backend(backend.clone(), group),
+                None => store,
+            }
+        };
+
+        let auth = store(
+            RateLimitConfig {
+                max_requests: settings.auth_attempts,
+                ..create_auth_rate_limiter().config
+            },
+            "auth",
+        );
+        let public = store(
+            RateLimitConfig {
+                max_requests: settings.public_requests_per_minute,
+                ..create_public_rate_limiter().config
+            },
+            "public",
+        );
+        let api = store(
+            RateLimitConfig {
+                max_requests: settings.api_requests_per_minute,
+                ..create_api_rate_limiter().config
+            },
+            "api",
+        )
+        .keyed_by_user(config.clone());
+
+        Self {
+            enabled: settings.enabled,
+            auth,
+            api,
+            public,
+        }
+    }
+
+    /// Rate limit every route of `router` with `store`. Routes added to the
+    /// router afterwards are not limited.
+    pub fn apply(&self, router: Router<AppState>, store: &RateLimitStore) -> Router<AppState> {
+        if !self.enabled {
+            return router;
+        }
+        router.route_layer(from_fn_with_state(store.clone(), rate_limit_middleware))
+    }
+
+    /// Periodically drop expired entries from all stores
+    pub fn spawn_cleanup(&self, every: std::time::Duration) {
+        if self.enabled {
+            self.auth.spawn_cleanup(every);
+            self.api.spawn_cleanup(every);
+            self.public.spawn_cleanup(every);
+        }
+    }
+}
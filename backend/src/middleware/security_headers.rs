@@ -0,0 +1,94 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::config::SecurityConfig;
+
+/// Security header policy applied to responses.
+///
+/// Headers are only added when the response does not already carry them, so a
+/// handler or an inner route layer can override any of them for its routes.
+#[derive(Clone, Debug)]
+pub struct SecurityHeaders {
+    hsts: Option<HeaderValue>,
+    referrer_policy: HeaderValue,
+    frame_ancestors: HeaderValue,
+    allow_framing: bool,
+}
+
+impl SecurityHeaders {
+    pub fn from_config(config: &SecurityConfig) -> Self {
+        let hsts = (config.hsts_max_age_seconds > 0).then(|| {
+            let mut value = format!("max-age={}", config.hsts_max_age_seconds);
+            if config.hsts_include_subdomains {
+                value.push_str("; includeSubDomains");
+            }
+            HeaderValue::from_str(&value).expect("valid HSTS header")
+        });
+
+        let referrer_policy = HeaderValue::from_str(&config.referrer_policy)
+            .unwrap_or_else(|_| HeaderValue::from_static("strict-origin-when-cross-origin"));
+
+        let frame_ancestors = HeaderValue::from_str(&format!(
+            "frame-ancestors {}",
+            config.embed_frame_ancestors
+        ))
+        .unwrap_or_else(|_| HeaderValue::from_static("frame-ancestors *"));
+
+        Self {
+            hsts,
+            referrer_policy,
+            frame_ancestors,
+            allow_framing: false,
+        }
+    }
+
+    /// Policy for routes meant to be embedded in third-party pages
+    pub fn embeddable(mut self) -> Self {
+        self.allow_framing = true;
+        self
+    }
+
+    fn apply(&self, headers: &mut HeaderMap) {
+        set_default(headers, header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+        set_default(headers, header::REFERRER_POLICY, self.referrer_policy.clone());
+
+        if let Some(hsts) = &self.hsts {
+            set_default(headers, header::STRICT_TRANSPORT_SECURITY, hsts.clone());
+        }
+
+        // An inner layer that already set a CSP owns the framing decision
+        if headers.contains_key(header::CONTENT_SECURITY_POLICY) {
+            return;
+        }
+
+        if self.allow_framing {
+            headers.insert(header::CONTENT_SECURITY_POLICY, self.frame_ancestors.clone());
+        } else {
+            headers.insert(
+                header::CONTENT_SECURITY_POLICY,
+                HeaderValue::from_static("frame-ancestors 'none'"),
+            );
+            set_default(headers, header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+        }
+    }
+}
+
+fn set_default(headers: &mut HeaderMap, name: HeaderName, value: HeaderValue) {
+    headers.entry(name).or_insert(value);
+}
+
+/// Middleware applying a `SecurityHeaders` policy; use with
+/// `axum::middleware::from_fn_with_state`
+pub async fn security_headers(
+    State(policy): State<SecurityHeaders>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    policy.apply(response.headers_mut());
+    response
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminStatsQuery {
+    pub days: Option<i32>, // Size of the reporting window, defaults to 30
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdminStats {
+    pub generated_at: DateTime<Utc>,
+    pub period_days: i32,
+    pub totals: InstanceTotals,
+    pub daily: Vec<DailyInstanceMetrics>,
+    pub job_failure_rates: Vec<JobFailureRate>,
+    pub storage: Vec<TableStorage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct InstanceTotals {
+    pub users: i64,
+    pub decks: i64,
+    pub cards: i64,
+    pub ai_tokens_used: i64,
+    pub database_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DailyInstanceMetrics {
+    pub day: NaiveDate,
+    pub active_users: i64,
+    pub new_registrations: i64,
+    pub decks_created: i64,
+    pub cards_created: i64,
+    pub ai_tokens_used: i64,
+    pub content_bytes_added: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct JobFailureRate {
+    pub job_type: String,
+    pub total: i64,
+    pub completed: i64,
+    pub failed: i64,
+    pub failure_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TableStorage {
+    pub table_name: String,
+    pub total_bytes: i64,
+}
+
+// ============== Consistency Checks ==============
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ConsistencyCheckDto {
+    pub dry_run: Option<bool>, // Only report mismatches, defaults to false
+}
+
+/// Stored as the parameters of a `consistency_check` background job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyJobParameters {
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    pub dry_run: bool,
+    pub total_found: i64,
+    pub total_repaired: i64,
+    pub checks: Vec<ConsistencyCheckResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyCheckResult {
+    pub name: String,
+    pub description: String,
+    pub found: i64,
+    pub repaired: i64,
+    pub sample_ids: Vec<Uuid>, // Up to 20 affected rows, for follow-up
+}
+
+/// What `POST /admin/seed` created. Sign in with `email` and `password` to
+/// browse the demo data.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SeedSummary {
+    pub user_id: Uuid,
+    pub email: String,
+    pub password: String,
+    pub folders: usize,
+    pub decks: usize,
+    pub cards: usize,
+    pub study_sessions: usize,
+    pub answers: usize,
+}
+
+/// New password set by an operator with `admin reset-password`
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SetPasswordDto {
+    #[validate(length(min = 8, max = 128))]
+    #[validate(custom(function = "super::validate_password_strength"))]
+    pub new_password: String,
+}
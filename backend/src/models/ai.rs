@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
@@ -24,22 +25,26 @@ pub struct StudyEvent {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateStudyEventDto {
     pub card_id: Uuid,
     pub deck_id: Uuid,
     pub session_id: Option<Uuid>,
     #[validate(length(min = 1, max = 50))]
     pub event_type: String,
+    #[validate(length(max = 50))]
     pub outcome: Option<String>,
+    #[validate(range(min = 0))]
     pub response_time_ms: Option<i32>,
     #[validate(range(min = 1, max = 5))]
     pub confidence_rating: Option<i32>,
+    /// When it happened on the client; defaults to the batch timestamp
+    pub occurred_at: Option<DateTime<Utc>>,
 }
 
 // ============== AI Privacy Settings ==============
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct AiPrivacySettings {
     pub user_id: Uuid,
     pub track_analytics: bool,
@@ -51,7 +56,7 @@ pub struct AiPrivacySettings {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UpdatePrivacySettingsDto {
     pub track_analytics: Option<bool>,
     pub enable_ai_recommendations: Option<bool>,
@@ -62,12 +67,13 @@ pub struct UpdatePrivacySettingsDto {
 
 // ============== AI Recommendations ==============
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct AiRecommendation {
     pub id: Uuid,
     pub user_id: Uuid,
     pub recommendation_type: String, // 'next_card', 'study_time', 'deck_suggestion', 'review_schedule'
-    pub payload: JsonValue,
+    #[schema(value_type = Object)]
+    pub payload: JsonValue, // RecommendationPayload
     pub confidence_score: Option<f32>,
     pub shown_at: Option<DateTime<Utc>>,
     pub accepted: Option<bool>,
@@ -86,7 +92,7 @@ pub struct RecommendationPayload {
     pub metrics: Option<JsonValue>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RecommendationFeedbackDto {
     pub recommendation_id: Uuid,
     #[validate(length(min = 1, max = 20))]
@@ -96,6 +102,42 @@ pub struct RecommendationFeedbackDto {
 
 // ============== Content Generation ==============
 
+/// Backend that runs a generation request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AiProviderKind {
+    /// Google Cloud Vertex AI
+    VertexAi,
+    /// Any server speaking the OpenAI chat completions API
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// An Ollama server, through its native API
+    Ollama,
+}
+
+impl AiProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AiProviderKind::VertexAi => "vertex_ai",
+            AiProviderKind::OpenAi => "openai",
+            AiProviderKind::Ollama => "ollama",
+        }
+    }
+}
+
+impl std::str::FromStr for AiProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vertex_ai" => Ok(AiProviderKind::VertexAi),
+            "openai" => Ok(AiProviderKind::OpenAi),
+            "ollama" => Ok(AiProviderKind::Ollama),
+            other => Err(format!("Unknown AI provider '{}'", other)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct AiContentGenerationJob {
     pub id: Uuid,
@@ -107,7 +149,7 @@ pub struct AiContentGenerationJob {
     pub input_metadata: Option<JsonValue>,
     pub output_data: Option<JsonValue>,
     pub error_message: Option<String>,
-    pub provider: Option<String>, // 'vertex_ai', 'local_model', 'openai'
+    pub provider: Option<String>, // 'vertex_ai', 'openai', 'ollama'
     pub model_name: Option<String>,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
@@ -143,7 +185,7 @@ pub struct ContentGenerationOptions {
     pub custom_prompt: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct AiGeneratedCard {
     pub id: Uuid,
     pub job_id: Uuid,
@@ -156,14 +198,77 @@ pub struct AiGeneratedCard {
     pub confidence_score: Option<f32>,
     pub source_context: Option<String>,
     pub approved: bool,
+    pub rejected_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+/// Outcome of a generation request; cards stay unapproved until moved into a deck
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GeneratedCardsResult {
+    pub job_id: Uuid,
+    pub provider: String,
+    pub model: String,
+    pub tokens_used: i32,
+    pub cards: Vec<AiGeneratedCard>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GenerationStreamQuery {
+    pub job_id: Uuid,
+    pub token: Option<String>, // EventSource cannot set an Authorization header
+}
+
+/// Progress of a streamed generation job, sent as server-sent events
+#[derive(Debug, Clone)]
+pub enum GenerationEvent {
+    Card(AiGeneratedCard),
+    Completed(GenerationSummary),
+    Cancelled,
+    Failed(String),
+}
+
+impl GenerationEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            GenerationEvent::Card(_) => "card",
+            GenerationEvent::Completed(_) => "done",
+            GenerationEvent::Cancelled => "cancelled",
+            GenerationEvent::Failed(_) => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationSummary {
+    pub job_id: Uuid,
+    pub provider: String,
+    pub model: String,
+    pub tokens_used: i32,
+    pub card_count: usize,
+    pub discarded_low_confidence: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct ApproveGeneratedCardsDto {
+    #[validate(length(min = 1, max = 200))]
     pub card_ids: Vec<Uuid>,
     pub deck_id: Uuid,
-    pub auto_position: Option<bool>, // Auto-assign positions
+    pub auto_position: Option<bool>, // Append after existing cards (default); false inserts at the top
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct RejectGeneratedCardsDto {
+    #[validate(length(min = 1, max = 200))]
+    pub card_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GeneratedCardsQuery {
+    pub job_id: Uuid,
+    pub include_reviewed: Option<bool>,
 }
 
 // ============== User Card Statistics ==============
@@ -182,28 +287,257 @@ pub struct UserCardStats {
     pub next_review_at: Option<DateTime<Utc>>,
     pub ease_factor: f32,
     pub interval_days: i32,
+    pub repetitions: i32,
+    pub lapses: i32,
+    pub leech_flagged_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+// ============== Leech Remediation ==============
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LeechRemediation {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub card_id: Uuid,
+    pub strategy: String, // 'simplify', 'split', 'mnemonic'
+    #[schema(value_type = Object)]
+    pub suggested_cards: JsonValue, // Array of { front, back }
+    pub mnemonic: Option<String>,
+    pub rationale: Option<String>,
+    pub status: String, // 'pending', 'accepted', 'rejected', 'superseded'
+    pub accuracy_before: Option<f32>,
+    pub accuracy_after: Option<f32>, // Accuracy on reviews since the rewrite was accepted
+    pub reviews_since_accept: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeechSuggestion {
+    pub strategy: String,
+    pub cards: Vec<SuggestedCard>,
+    pub mnemonic: Option<String>,
+    pub rationale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedCard {
+    pub front: String,
+    pub back: String,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeechRemediationsQuery {
+    pub status: Option<String>,
+    pub card_id: Option<Uuid>,
+}
+
+// ============== Quiz Generation ==============
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckExam {
+    pub id: Uuid,
+    pub deck_id: Uuid,
+    pub user_id: Uuid,
+    pub title: String,
+    pub model_name: Option<String>,
+    pub tokens_used: i32,
+    pub question_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ExamQuestion {
+    pub id: Uuid,
+    pub card_id: Option<Uuid>,
+    pub position: i32,
+    pub question: String,
+    pub options: Vec<String>,
+    pub correct_index: i32,
+    pub explanation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeckExamWithQuestions {
+    #[serde(flatten)]
+    pub exam: DeckExam,
+    pub questions: Vec<ExamQuestion>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct GenerateQuizDto {
+    #[validate(range(min = 1, max = 50))]
+    pub question_count: Option<i32>,
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+    /// Defaults to the server's configured provider
+    pub provider: Option<AiProviderKind>,
+}
+
+/// A question as returned by the model, before validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedQuizQuestion {
+    pub card: usize, // 1-based number of the card in the prompt
+    pub question: String,
+    pub options: Vec<String>,
+    pub answer: usize, // 0-based index into options
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+// ============== Card Linting ==============
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum LintIssueKind {
+    /// The question gives away its own answer
+    AnswerLeak,
+    /// The answer is long enough to be several facts
+    LongAnswer,
+    /// Another card in the deck asks the same question
+    Duplicate,
+    /// The question allows more than one reasonable answer
+    Ambiguous,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum LintSource {
+    Heuristic,
+    Ai,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CardLintSuggestion {
+    pub id: Uuid,
+    pub deck_id: Uuid,
+    pub card_id: Uuid,
+    pub kind: LintIssueKind,
+    pub source: LintSource,
+    pub message: String,
+    /// Rewrite of the card; both None when there is nothing to apply
+    pub suggested_front: Option<String>,
+    pub suggested_back: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct LintDeckDto {
+    /// Ask the AI provider as well as running the local checks (default true).
+    /// Skipped when AI is disabled on the server or in the privacy settings.
+    pub use_ai: Option<bool>,
+    /// Defaults to the server's configured provider
+    pub provider: Option<AiProviderKind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeckLintReport {
+    pub deck_id: Uuid,
+    pub cards_checked: usize,
+    /// Whether the AI provider reviewed the cards; false means local checks only
+    pub ai_reviewed: bool,
+    pub model: Option<String>,
+    pub tokens_used: i32,
+    pub suggestions: Vec<CardLintSuggestion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ApplyLintSuggestionsDto {
+    #[validate(length(min = 1, max = 500))]
+    pub suggestion_ids: Vec<Uuid>,
+}
+
+/// A problem as reported by the model, before validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedLintIssue {
+    pub card: usize, // 1-based number of the card in the prompt
+    pub kind: String,
+    pub message: String,
+    #[serde(default)]
+    pub front: Option<String>,
+    #[serde(default)]
+    pub back: Option<String>,
+}
+
+// ============== Card Explanations ==============
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ExplainCardDto {
+    /// What the learner answered, so the explanation can address the mistake
+    #[validate(length(max = 500))]
+    pub wrong_answer: Option<String>,
+    /// Defaults to the server's configured provider
+    pub provider: Option<AiProviderKind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardExplanation {
+    pub card_id: Uuid,
+    pub explanation: String,
+    pub model_name: String,
+    /// Tokens the explanation cost when it was generated
+    pub tokens_used: i32,
+    /// Served from the cache without calling the provider
+    pub cached: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+// ============== Usage & Quotas ==============
+
+/// Tokens one provider used for a user on one UTC day
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AiUsageDay {
+    pub usage_date: chrono::NaiveDate,
+    pub provider: String,
+    pub tokens_used: i64,
+    pub request_count: i32,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AiUsageSummary {
+    pub tokens_today: i64,
+    pub tokens_this_month: i64,
+    pub cost_this_month_usd: f64,
+    /// None when unlimited
+    pub daily_token_limit: Option<i64>,
+    pub monthly_token_limit: Option<i64>,
+    /// Newest first
+    pub days: Vec<AiUsageDay>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AiUsageQuery {
+    pub days: Option<i64>, // Days of history, default 30, at most 366
+}
+
 // ============== Learning Patterns ==============
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct LearningPattern {
     pub id: Uuid,
     pub user_id: Uuid,
     pub pattern_type: String, // 'time_of_day', 'session_length', 'difficulty_preference'
+    #[schema(value_type = Object)]
     pub pattern_data: JsonValue,
     pub confidence_score: Option<f32>,
     pub detected_at: DateTime<Utc>,
     pub valid_until: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LearningInsight {
-    pub insight_type: String,
+    pub insight_type: String, // pattern_type of the pattern it describes
     pub title: String,
     pub description: String,
+    #[schema(value_type = Object)]
     pub data: JsonValue,
     pub confidence: f32,
     pub actionable: bool,
@@ -231,6 +565,32 @@ pub struct WsMessage {
     pub timestamp: DateTime<Utc>,
 }
 
+impl WsMessage {
+    pub fn new(message_type: &str, payload: JsonValue) -> Self {
+        Self {
+            message_type: message_type.to_string(),
+            payload,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// Ticket for opening a WebSocket connection from a browser
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WsTicket {
+    pub ticket: String,
+    pub expires_in: u64, // seconds
+}
+
+/// Messages sent by WebSocket clients
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum WsClientMessage {
+    Subscribe { subscription_type: String },
+    Unsubscribe { subscription_type: String },
+    Ping,
+}
+
 // ============== Spaced Repetition Algorithms ==============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -239,6 +599,7 @@ pub struct SpacedRepetitionParams {
     pub ease_factor: f32,
     pub interval: i32,
     pub repetitions: i32,
+    pub leitner_box: Option<i32>, // None until the card is placed in a Leitner box
     pub quality: i32, // 0-5 rating
 }
 
@@ -246,10 +607,49 @@ pub struct SpacedRepetitionParams {
 pub struct SpacedRepetitionResult {
     pub next_interval: i32,
     pub next_ease_factor: f32,
+    pub next_repetitions: i32,
+    pub next_leitner_box: Option<i32>,
     pub next_review_date: DateTime<Utc>,
     pub difficulty_adjustment: f32,
 }
 
+// ============== Document Extraction ==============
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExtractedDocument {
+    pub kind: String, // 'pdf', 'docx', 'txt'
+    pub filename: Option<String>,
+    pub title: Option<String>,
+    pub sections: Vec<DocumentSection>,
+    pub word_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentSection {
+    pub heading: Option<String>,
+    pub level: u8, // 0 for untitled sections, 1-9 for heading depth
+    pub paragraphs: Vec<String>,
+}
+
+impl ExtractedDocument {
+    /// Render the document as outline text for prompts, keeping headings so
+    /// generated cards can follow the document structure
+    pub fn to_prompt_text(&self) -> String {
+        let mut text = String::new();
+        for section in &self.sections {
+            if let Some(heading) = &section.heading {
+                let marker = "#".repeat(section.level.clamp(1, 6) as usize);
+                text.push_str(&format!("{} {}\n", marker, heading));
+            }
+            for paragraph in &section.paragraphs {
+                text.push_str(paragraph);
+                text.push_str("\n\n");
+            }
+        }
+        text
+    }
+}
+
 // ============== Vertex AI Integration ==============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -272,7 +672,10 @@ pub struct VertexAiResponse {
 
 // ============== User Learning Statistics (Materialized View) ==============
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+/// Totals over the user's study events from the `user_learning_stats` view.
+/// Every total is `null` until the user has studied and the view has been
+/// refreshed since.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct UserLearningStats {
     pub user_id: Uuid,
     pub unique_cards_studied: Option<i64>,
@@ -283,6 +686,8 @@ pub struct UserLearningStats {
     pub accuracy_rate: Option<f32>,
     pub last_study_time: Option<DateTime<Utc>>,
     pub study_days: Option<i64>,
+    /// When the view was last recomputed
+    pub refreshed_at: Option<DateTime<Utc>>,
 }
 
 // ============== Study Session Enhancement ==============
@@ -297,9 +702,49 @@ pub struct AiStudySessionConfig {
     pub review_algorithm: String, // 'sm2', 'leitner', 'exponential'
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StudyQueueQuery {
+    /// Queue one deck, or give `folder_id` or `smart_deck_id` instead
+    pub deck_id: Option<Uuid>,
+    /// Queue every deck in a folder and its subfolders, interleaved
+    pub folder_id: Option<Uuid>,
+    /// Queue the cards matching a smart deck's filter
+    pub smart_deck_id: Option<Uuid>,
+    /// Cap on new cards across the queue, below the decks' daily limits
+    pub max_new_cards: Option<i32>,
+    pub focus_weak_cards: Option<bool>,
+    pub include_overdue: Option<bool>,
+    /// Put starred cards ahead of the rest within each group
+    pub prioritize_starred: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StudyQueue {
+    pub deck_id: Option<Uuid>,
+    pub folder_id: Option<Uuid>,
+    pub smart_deck_id: Option<Uuid>,
+    pub generated_at: DateTime<Utc>,
+    pub new: Vec<StudyCardSuggestion>,
+    pub learning: Vec<StudyCardSuggestion>,
+    pub review: Vec<StudyCardSuggestion>,
+    pub counts: StudyQueueCounts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StudyQueueCounts {
+    pub new: usize,
+    pub learning: usize,
+    pub review: usize,
+    pub new_available: usize, // New cards in the deck before the daily limit is applied
+    pub review_available: usize, // Due reviews before the daily review limit is applied
+    pub buried: usize, // Reverse siblings held back because the other card is studied first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StudyCardSuggestion {
     pub card_id: Uuid,
+    pub deck_id: Uuid,
     pub reason: String,
     pub priority_score: f32,
     pub estimated_difficulty: f32,
@@ -309,13 +754,28 @@ pub struct StudyCardSuggestion {
 
 // ============== Batch Processing ==============
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Events recorded while offline, sent together. `batch_id` is chosen by the
+/// client and reused when resending, so a retried batch is not counted twice.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct BatchAnalyticsEvent {
+    #[validate(length(min = 1, max = 500), nested)]
     pub events: Vec<CreateStudyEventDto>,
     pub batch_id: Uuid,
+    /// When the client sent the batch
     pub timestamp: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchIngestResult {
+    pub batch_id: Uuid,
+    pub received: usize,
+    pub inserted: i64,
+    /// Already stored from an earlier attempt at the same batch
+    pub duplicates: i64,
+    /// For cards that do not exist or the user cannot see
+    pub rejected: i64,
+}
+
 // ============== Error Responses ==============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,32 +1,78 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-// Export formats
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum ExportFormat {
-    Json,
-    Csv,
-    Anki,
-    Markdown,
+// Export formats are resolved by name through the exporter registry
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExportFormatInfo {
+    pub name: String,
+    pub content_type: String,
+    pub extension: String,
 }
 
 // Import formats
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ImportFormat {
     Json,
     Csv,
     Anki,
     Markdown,
+    Quizlet,
+    Mnemosyne,
+    SuperMemo,
+    #[serde(rename = "anki_text")]
+    AnkiText,
+}
+
+impl ImportFormat {
+    pub const ALL: &'static [ImportFormat] = &[
+        ImportFormat::Json,
+        ImportFormat::Csv,
+        ImportFormat::Anki,
+        ImportFormat::Markdown,
+        ImportFormat::Quizlet,
+        ImportFormat::Mnemosyne,
+        ImportFormat::SuperMemo,
+        ImportFormat::AnkiText,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|format| format.name() == name).cloned()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImportFormat::Json => "json",
+            ImportFormat::Csv => "csv",
+            ImportFormat::Anki => "anki",
+            ImportFormat::Markdown => "markdown",
+            ImportFormat::Quizlet => "quizlet",
+            ImportFormat::Mnemosyne => "mnemosyne",
+            ImportFormat::SuperMemo => "supermemo",
+            ImportFormat::AnkiText => "anki_text",
+        }
+    }
+
+    /// File extensions an upload in this format may have
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ImportFormat::Json | ImportFormat::Anki => &["json"],
+            ImportFormat::Csv => &["csv", "txt"],
+            ImportFormat::Markdown => &["md", "markdown", "txt"],
+            ImportFormat::Quizlet => &["txt", "csv", "json"],
+            ImportFormat::Mnemosyne | ImportFormat::SuperMemo => &["xml"],
+            ImportFormat::AnkiText => &["txt", "tsv", "csv"],
+        }
+    }
 }
 
 // Export request DTOs
 #[derive(Debug, Deserialize)]
 pub struct ExportDeckRequest {
     pub deck_id: Uuid,
-    pub format: ExportFormat,
+    pub format: String,
     pub include_progress: Option<bool>,
     pub include_media: Option<bool>,
 }
@@ -34,7 +80,7 @@ pub struct ExportDeckRequest {
 #[derive(Debug, Deserialize)]
 pub struct BulkExportRequest {
     pub deck_ids: Vec<Uuid>,
-    pub format: ExportFormat,
+    pub format: String,
     pub include_progress: Option<bool>,
     pub include_media: Option<bool>,
 }
@@ -47,6 +93,75 @@ pub struct ImportDeckRequest {
     pub merge_duplicates: Option<bool>,
 }
 
+// Parameters persisted with an import job so it can be retried
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportJobParameters {
+    pub format: ImportFormat,
+    pub folder_id: Option<Uuid>,
+    pub merge_duplicates: bool,
+    #[serde(default)]
+    pub delimiters: TextDelimiters,
+    /// Carry scheduling history over from formats that have it (Mnemosyne,
+    /// SuperMemo, and JSON exports of the importing account)
+    #[serde(default)]
+    pub include_progress: bool,
+    #[serde(default)]
+    pub duplicate_strategy: DuplicateStrategy,
+    /// What to do with HTML in Anki text fields
+    #[serde(default)]
+    pub html: HtmlHandling,
+}
+
+// What to do with an imported card whose front matches a card already in
+// the target deck, or one earlier in the same import
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStrategy {
+    Skip,
+    Overwrite,
+    #[default]
+    KeepBoth,
+}
+
+impl DuplicateStrategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "skip" => Some(Self::Skip),
+            "overwrite" => Some(Self::Overwrite),
+            "keep_both" => Some(Self::KeepBoth),
+            _ => None,
+        }
+    }
+}
+
+// How HTML fields from an Anki text export become card content
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HtmlHandling {
+    /// Keep the markup, cleaned of scripts and unsafe attributes
+    #[default]
+    Sanitize,
+    /// Reduce the markup to plain text with line breaks
+    Strip,
+}
+
+impl HtmlHandling {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sanitize" => Some(Self::Sanitize),
+            "strip" => Some(Self::Strip),
+            _ => None,
+        }
+    }
+}
+
+// Separators for delimited text imports (Quizlet); detected when not given
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TextDelimiters {
+    pub term: Option<String>,
+    pub row: Option<String>,
+}
+
 // Export data structures
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExportedDeck {
@@ -54,6 +169,10 @@ pub struct ExportedDeck {
     pub title: String,
     pub description: Option<String>,
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub front_language: Option<String>,
+    #[serde(default)]
+    pub back_language: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub cards: Vec<ExportedCard>,
@@ -74,7 +193,7 @@ pub struct ExportedCard {
     pub progress: Option<CardProgressData>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaAttachment {
     pub id: Uuid,
     pub filename: String,
@@ -91,6 +210,10 @@ pub struct CardProgressData {
     pub next_review: Option<DateTime<Utc>>,
     pub ease_factor: f32,
     pub interval_days: i32,
+    #[serde(default)]
+    pub repetitions: i32,
+    #[serde(default)]
+    pub lapses: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -102,6 +225,41 @@ pub struct ExportMetadata {
     pub total_cards: usize,
     pub includes_progress: bool,
     pub includes_media: bool,
+    /// The account whose progress the file holds; progress is only
+    /// restored when the same account imports it
+    #[serde(default)]
+    pub exported_by: Option<Uuid>,
+}
+
+// Bulk exports are a ZIP archive with one file per deck plus manifest.json
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkExportManifest {
+    pub version: String,
+    pub exported_at: DateTime<Utc>,
+    pub platform: String,
+    pub format: String,
+    pub includes_progress: bool,
+    pub includes_media: bool,
+    pub decks: Vec<BulkExportManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkExportManifestEntry {
+    pub deck_id: Uuid,
+    pub title: String,
+    pub file: String,
+    pub total_cards: usize,
+}
+
+// Personal data exports are a ZIP archive of everything stored about the
+// user, described by manifest.json
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataExportManifest {
+    pub version: String,
+    pub exported_at: DateTime<Utc>,
+    pub platform: String,
+    pub user_id: Uuid,
+    pub files: Vec<String>,
 }
 
 // CSV export structures
@@ -114,6 +272,137 @@ pub struct CsvCard {
     pub difficulty: Option<i32>,
 }
 
+// Quizlet API structures (a set, or a list of sets)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuizletSet {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub terms: Vec<QuizletTerm>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuizletTerm {
+    pub term: String,
+    pub definition: String,
+    #[serde(default)]
+    pub rank: Option<i32>,
+}
+
+// Mnemosyne 1.x XML export
+#[derive(Debug, Deserialize)]
+pub struct MnemosyneExport {
+    /// Unix time the collection was started; repetition days count from here
+    #[serde(rename = "@time_of_start", default)]
+    pub time_of_start: Option<i64>,
+    #[serde(rename = "category", default)]
+    pub categories: Vec<MnemosyneCategory>,
+    #[serde(rename = "item", default)]
+    pub items: Vec<MnemosyneItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MnemosyneCategory {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MnemosyneItem {
+    #[serde(rename = "@gr", default)]
+    pub grade: Option<i32>, // Last grade, 0-5; -1 when never seen
+    #[serde(rename = "@e", default)]
+    pub easiness: Option<f32>,
+    #[serde(rename = "@ac_rp", default)]
+    pub acquisition_reps: Option<i32>,
+    #[serde(rename = "@rt_rp", default)]
+    pub retention_reps: Option<i32>,
+    #[serde(rename = "@lps", default)]
+    pub lapses: Option<i32>,
+    #[serde(rename = "@rt_rp_l", default)]
+    pub retention_reps_since_lapse: Option<i32>,
+    #[serde(rename = "@l_rp", default)]
+    pub last_rep: Option<i64>, // Days since time_of_start
+    #[serde(rename = "@n_rp", default)]
+    pub next_rep: Option<i64>, // Days since time_of_start
+    #[serde(rename = "cat", default)]
+    pub category: Option<String>,
+    #[serde(rename = "Q")]
+    pub question: String,
+    #[serde(rename = "A", default)]
+    pub answer: String,
+}
+
+// SuperMemo collection XML export; topics nest items and other topics
+#[derive(Debug, Deserialize)]
+pub struct SuperMemoCollection {
+    #[serde(rename = "SuperMemoElement", default)]
+    pub elements: Vec<SuperMemoElement>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuperMemoElement {
+    #[serde(rename = "Title", default)]
+    pub title: Option<String>,
+    #[serde(rename = "Type", default)]
+    pub element_type: Option<String>, // Topic, Item or Concept
+    #[serde(rename = "Content", default)]
+    pub content: Option<SuperMemoContent>,
+    #[serde(rename = "LearningData", default)]
+    pub learning_data: Option<SuperMemoLearningData>,
+    #[serde(rename = "SuperMemoElement", default)]
+    pub children: Vec<SuperMemoElement>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuperMemoContent {
+    #[serde(rename = "Question", default)]
+    pub question: Option<String>,
+    #[serde(rename = "Answer", default)]
+    pub answer: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuperMemoLearningData {
+    #[serde(rename = "Interval", default)]
+    pub interval: Option<i32>,
+    #[serde(rename = "Repetitions", default)]
+    pub repetitions: Option<i32>,
+    #[serde(rename = "Lapses", default)]
+    pub lapses: Option<i32>,
+    #[serde(rename = "LastRepetition", default)]
+    pub last_repetition: Option<String>, // dd.mm.yyyy
+    #[serde(rename = "AFactor", default)]
+    pub a_factor: Option<f32>,
+    #[serde(rename = "UFactor", default)]
+    pub u_factor: Option<f32>,
+}
+
+// Decks read from SRS exports, with the scheduling state of each card
+#[derive(Debug)]
+pub struct SrsDeck {
+    pub title: String,
+    pub cards: Vec<SrsCard>,
+}
+
+#[derive(Debug)]
+pub struct SrsCard {
+    pub front: String,
+    pub back: String,
+    /// None for cards that were never reviewed
+    pub progress: Option<SrsProgress>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SrsProgress {
+    pub times_seen: i32,
+    pub lapses: i32,
+    pub repetitions: i32,
+    pub ease_factor: f32,
+    pub interval_days: i32,
+    pub last_review: Option<DateTime<Utc>>,
+    pub next_review: DateTime<Utc>,
+}
+
 // Anki export structures
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnkiDeck {
@@ -167,7 +456,7 @@ pub struct AnkiTemplate {
 }
 
 // Import validation
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ImportValidationResult {
     pub is_valid: bool,
     pub errors: Vec<String>,
@@ -176,7 +465,7 @@ pub struct ImportValidationResult {
     pub card_count: usize,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ImportResult {
     pub success: bool,
     pub imported_decks: Vec<ImportedDeck>,
@@ -184,9 +473,33 @@ pub struct ImportResult {
     pub warnings: Vec<String>,
     pub total_cards_imported: usize,
     pub total_decks_imported: usize,
+    pub card_actions: ImportCardActions,
+}
+
+// What happened to each imported card
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ImportCardActions {
+    pub created: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+    pub kept_both: usize,
+}
+
+impl ImportCardActions {
+    /// Cards that ended up in a deck, whether new or overwritten
+    pub fn imported(&self) -> usize {
+        self.created + self.overwritten + self.kept_both
+    }
+
+    pub fn merge(&mut self, other: ImportCardActions) {
+        self.created += other.created;
+        self.skipped += other.skipped;
+        self.overwritten += other.overwritten;
+        self.kept_both += other.kept_both;
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ImportedDeck {
     pub id: Uuid,
     pub title: String,
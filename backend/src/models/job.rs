@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+// ============== Background Jobs ==============
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BackgroundJob {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub job_type: String, // 'import', 'export', 'ai_generation', 'backup'
+    pub status: String, // 'pending', 'processing', 'completed', 'failed', 'cancelled'
+    pub progress: i32, // 0-100
+    pub parameters: JsonValue,
+    pub result: Option<JsonValue>,
+    pub result_url: Option<String>,
+    pub error_message: Option<String>,
+    pub cancellable: bool,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+    pub cancel_requested: bool,
+    pub retry_of: Option<Uuid>, // Original job when this is a retry
+}
+
+/// Row of the `user_jobs` view, which unifies background and AI generation jobs
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct JobSummary {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub source: String, // 'background', 'ai_generation'
+    pub job_type: String,
+    pub status: String,
+    pub progress: i32,
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<JsonValue>,
+    pub result_url: Option<String>,
+    pub error_message: Option<String>,
+    pub cancellable: bool,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub retry_of: Option<Uuid>,
+    pub cancel_requested: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct JobsQuery {
+    pub status: Option<String>,
+    pub job_type: Option<String>,
+    pub limit: Option<i64>,
+}
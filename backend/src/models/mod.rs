@@ -1,9 +1,13 @@
+pub mod admin;
 pub mod ai;
 pub mod import_export;
+pub mod job;
+pub mod sync;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
@@ -17,8 +21,12 @@ pub struct User {
     pub display_name: Option<String>,
     pub email_verified: bool,
     pub email_verified_at: Option<DateTime<Utc>>,
+    pub pending_email: Option<String>, // Requested new email, applied once verified
+    pub leaderboard_opt_out: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
@@ -31,7 +39,7 @@ pub struct CreateUserDto {
 }
 
 // Authentication DTOs
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct LoginDto {
     #[validate(email)]
     pub email: String,
@@ -39,7 +47,7 @@ pub struct LoginDto {
     pub remember_me: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RegisterDto {
     #[validate(email)]
     pub email: String,
@@ -49,7 +57,38 @@ pub struct RegisterDto {
     pub display_name: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Where a request came from, for auditing
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip_address: Option<std::net::IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LoginAttempt {
+    pub attempted_at: DateTime<Utc>,
+    pub success: bool,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub failure_reason: Option<String>, // invalid_credentials, locked_out
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LoginHistoryQuery {
+    pub limit: Option<i64>,
+}
+
+/// Redirect back from an OAuth provider; `error` is set when the user declined
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OAuthCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AuthResponse {
     pub access_token: String,
     pub refresh_token: String,
@@ -58,16 +97,149 @@ pub struct AuthResponse {
     pub user: UserResponse,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub email: String,
     pub display_name: Option<String>,
     pub email_verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_email: Option<String>,
+    pub leaderboard_opt_out: bool,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateProfileDto {
+    #[validate(length(min = 1, max = 100))]
+    pub display_name: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    pub current_password: Option<String>, // Required to change the email
+    pub leaderboard_opt_out: Option<bool>,
+}
+
+/// Email reminder schedule; `reminder_days` are ISO weekdays (1 = Monday)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct NotificationPreferences {
+    pub user_id: Uuid,
+    pub email_reminders_enabled: bool,
+    #[schema(value_type = String, example = "19:00:00")]
+    pub reminder_time: chrono::NaiveTime,
+    pub reminder_days: Vec<i16>,
+    pub timezone: String,
+    pub last_reminder_on: Option<chrono::NaiveDate>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateNotificationPreferencesDto {
+    pub email_reminders_enabled: Option<bool>,
+    #[schema(value_type = Option<String>, example = "19:00:00")]
+    pub reminder_time: Option<chrono::NaiveTime>,
+    pub reminder_days: Option<Vec<i16>>,
+    #[validate(length(min = 1, max = 64))]
+    pub timezone: Option<String>,
+}
+
+/// Order in which a study queue introduces new cards
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum NewCardOrder {
+    /// The order of the cards in their deck
+    #[default]
+    Position,
+    /// Shuffled once a day, so the queue stays the same until midnight UTC
+    Random,
+}
+
+/// How the user's study queues are put together
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserPreferences {
+    pub user_id: Uuid,
+    pub new_card_order: NewCardOrder,
+    /// Take turns between decks in folder queues instead of going deck by deck
+    pub interleave_decks: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateUserPreferencesDto {
+    pub new_card_order: Option<NewCardOrder>,
+    pub interleave_decks: Option<bool>,
+}
+
+/// What an API key may do: `read` keys are limited to GET requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum ApiKeyScope {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub key_prefix: String, // Leading characters of the key, for telling keys apart
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scope: ApiKeyScope,
+    pub rate_limit_per_minute: Option<i32>, // Server default when unset
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub key: String, // Only returned when the key is created
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateApiKeyDto {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub scope: ApiKeyScope,
+    #[validate(range(min = 1, max = 10000))]
+    pub rate_limit_per_minute: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateApiKeyDto {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub scope: Option<ApiKeyScope>,
+    #[validate(range(min = 1, max = 10000))]
+    pub rate_limit_per_minute: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ChangePasswordDto {
+    pub current_password: String,
+    #[validate(length(min = 8, max = 128))]
+    #[validate(custom(function = "validate_password_strength"))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeleteAccountDto {
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VerifyEmailDto {
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RefreshTokenDto {
     pub refresh_token: String,
 }
@@ -76,19 +248,22 @@ pub struct RefreshTokenDto {
 pub struct RefreshToken {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub token: String,
+    pub token_hash: String, // SHA-256 of the token; the token itself is never stored
+    pub family_id: Uuid, // Shared by every rotation of one login
+    pub replaced_by: Option<Uuid>,
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_reason: Option<String>, // rotated, logout, password_reset, password_change, account_deleted, reuse_detected
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct PasswordResetRequestDto {
     #[validate(email)]
     pub email: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct PasswordResetDto {
     pub token: String,
     #[validate(length(min = 8, max = 128))]
@@ -111,7 +286,7 @@ fn validate_password_strength(password: &str) -> Result<(), validator::Validatio
 }
 
 // Folder model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Folder {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -122,7 +297,7 @@ pub struct Folder {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateFolderDto {
     #[validate(length(min = 1, max = 255))]
     pub name: String,
@@ -130,7 +305,7 @@ pub struct CreateFolderDto {
     pub position: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UpdateFolderDto {
     #[validate(length(min = 1, max = 255))]
     pub name: Option<String>,
@@ -138,8 +313,26 @@ pub struct UpdateFolderDto {
     pub position: Option<i32>,
 }
 
+/// A folder with its subfolders, nested to any depth
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FolderNode {
+    #[serde(flatten)]
+    pub folder: Folder,
+    pub children: Vec<FolderNode>,
+}
+
+/// Move a folder, with everything in it, under another folder or to the
+/// top level when `parent_folder_id` is null
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct MoveFolderDto {
+    pub parent_folder_id: Option<Uuid>,
+    /// Position among the new siblings; defaults to the end
+    #[validate(range(min = 0))]
+    pub position: Option<i32>,
+}
+
 // Deck model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Deck {
     pub id: Uuid,
     pub folder_id: Option<Uuid>,
@@ -149,11 +342,15 @@ pub struct Deck {
     pub name: String,   // Keep as name in the API but map to title in DB
     pub description: Option<String>,
     pub is_public: bool,
+    /// Language of the card fronts as a lowercase BCP 47 tag
+    pub front_language: Option<String>,
+    /// Language of the card backs as a lowercase BCP 47 tag
+    pub back_language: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateDeckDto {
     #[validate(length(min = 1, max = 255))]
     pub name: String,
@@ -161,9 +358,15 @@ pub struct CreateDeckDto {
     pub description: Option<String>,
     pub folder_id: Option<Uuid>,
     pub is_public: Option<bool>,
+    /// Language of the card fronts as a BCP 47 tag, e.g. `es`
+    #[validate(length(min = 2, max = 35))]
+    pub front_language: Option<String>,
+    /// Language of the card backs as a BCP 47 tag, e.g. `en-GB`
+    #[validate(length(min = 2, max = 35))]
+    pub back_language: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UpdateDeckDto {
     #[validate(length(min = 1, max = 255))]
     pub name: Option<String>,
@@ -171,10 +374,395 @@ pub struct UpdateDeckDto {
     pub description: Option<String>,
     pub folder_id: Option<Uuid>,
     pub is_public: Option<bool>,
+    /// Marketplace tags; replaces the existing ones
+    pub tags: Option<Vec<String>>,
+    /// Language of the deck's content as a BCP 47 tag, e.g. `en` or `pt-BR`
+    #[validate(length(min = 2, max = 35))]
+    pub language: Option<String>,
+    #[validate(length(min = 2, max = 35))]
+    pub front_language: Option<String>,
+    #[validate(length(min = 2, max = 35))]
+    pub back_language: Option<String>,
+    /// The deck's `updated_at` as last read; the update fails with 409 if
+    /// it has changed since. `If-Match` does the same with the deck's ETag.
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// Deck merge and split
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct MergeDecksDto {
+    /// Decks to combine, in the order their cards are appended
+    #[validate(length(min = 2, max = 50))]
+    pub deck_ids: Vec<Uuid>,
+    /// Deck that receives the cards, one of `deck_ids`; the first when omitted
+    pub target_deck_id: Option<Uuid>,
+    /// Move cards whose front repeats one already in the target to the
+    /// trash instead. Defaults to true.
+    pub deduplicate: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MergeDecksResult {
+    pub deck: DeckWithStats,
+    pub moved_cards: i64,
+    pub duplicates_removed: i64,
+    /// Source decks, now in the trash
+    pub merged_deck_ids: Vec<Uuid>,
+}
+
+/// Cards to move into a new deck. Filters combine, and at least one is
+/// required.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SplitDeckDto {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 1, max = 10000))]
+    pub card_ids: Option<Vec<Uuid>>,
+    /// Text contained in the front or back, ignoring case
+    #[validate(length(min = 1, max = 200))]
+    pub query: Option<String>,
+    /// Cards the caller tagged with at least one of these, ignoring case
+    #[validate(length(min = 1, max = 10))]
+    pub tags: Option<Vec<String>>,
+    /// Match on the caller's own card flags
+    pub starred: Option<bool>,
+    pub suspended: Option<bool>,
+    pub leech: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SplitDeckResult {
+    pub deck: DeckWithStats,
+    pub moved_cards: i64,
+}
+
+// Deck sharing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeckRole {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+impl DeckRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeckRole::Viewer => "viewer",
+            DeckRole::Editor => "editor",
+            DeckRole::Owner => "owner",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "viewer" => Some(DeckRole::Viewer),
+            "editor" => Some(DeckRole::Editor),
+            "owner" => Some(DeckRole::Owner),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckShare {
+    pub id: Uuid,
+    pub deck_id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub role: String, // viewer, editor
+    pub invited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ShareDeckDto {
+    #[validate(email)]
+    pub email: String,
+    pub role: DeckRole,
+}
+
+/// A share sent by email. It reads the same whether or not the address has
+/// an account; one created later with the address gets the share.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeckShareInvite {
+    pub deck_id: Uuid,
+    pub email: String,
+    pub role: String, // viewer, editor
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckShareLink {
+    pub id: Uuid,
+    pub deck_id: Uuid,
+    pub token: String,
+    pub role: String, // viewer, editor
+    pub created_by: Uuid,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateShareLinkDto {
+    pub role: Option<DeckRole>,
+    #[validate(range(min = 1, max = 8760))]
+    pub expires_in_hours: Option<i64>, // No expiry when omitted
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SharedDeck {
+    #[serde(flatten)]
+    pub deck: Deck,
+    pub role: String,
+}
+
+/// A deck behind a share link, as served to anyone holding the link: no ids
+/// or owner details, and card HTML that is safe to put on a page
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeckEmbed {
+    pub title: String,
+    pub description: Option<String>,
+    pub front_language: Option<String>,
+    pub back_language: Option<String>,
+    pub card_count: i64,
+    pub updated_at: DateTime<Utc>, // Latest change to the deck or its cards
+    pub cards: Vec<DeckEmbedCard>, // The first `MAX_EMBED_CARDS` by position
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeckEmbedCard {
+    pub position: i32,
+    pub front: String,
+    pub back: String,
+    pub front_html: String,
+    pub back_html: String,
+}
+
+// Guest (demo) access
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckGuestToken {
+    pub id: Uuid,
+    pub deck_id: Uuid,
+    pub token: String,
+    pub label: Option<String>,
+    pub created_by: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub use_count: i64,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateGuestTokenDto {
+    #[validate(length(max = 100))]
+    pub label: Option<String>,
+    #[validate(range(min = 1, max = 168))]
+    pub expires_in_hours: Option<i64>, // Defaults to 24
+}
+
+/// The deck a guest token grants, set on the request by the guest middleware
+#[derive(Debug, Clone, Copy)]
+pub struct GuestAccess {
+    pub token_id: Uuid,
+    pub deck_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GuestDeck {
+    #[serde(flatten)]
+    pub deck: Deck,
+    pub cards: Vec<Card>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GuestStudyQuery {
+    pub shuffle: Option<bool>,
+}
+
+// Deck webhooks
+pub const WEBHOOK_EVENTS: &[&str] = &["card.created", "card.updated", "card.deleted", "study.completed"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckWebhook {
+    pub id: Uuid,
+    pub deck_id: Uuid,
+    pub created_by: Uuid,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String, // Only returned when the webhook is created
+    pub events: Vec<String>,
+    pub is_active: bool,
+    pub consecutive_failures: i32,
+    pub last_delivery_at: Option<DateTime<Utc>>,
+    pub last_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedDeckWebhook {
+    #[serde(flatten)]
+    pub webhook: DeckWebhook,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateDeckWebhookDto {
+    #[validate(url, length(max = 2048))]
+    pub url: String,
+    #[validate(length(min = 1))]
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateDeckWebhookDto {
+    #[validate(url, length(max = 2048))]
+    pub url: Option<String>,
+    #[validate(length(min = 1))]
+    pub events: Option<Vec<String>>,
+    pub is_active: Option<bool>, // Re-enabling resets the failure count
+}
+
+// Public deck badges
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckBadge {
+    pub deck_id: Uuid,
+    pub card_count: i64,
+    pub average_rating: Option<f64>,
+    pub rating_count: i64,
+}
+
+/// JSON badge in the shields.io endpoint schema, plus the raw numbers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeckBadgeJson {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u8,
+    pub label: String,
+    pub message: String,
+    pub color: String,
+    #[serde(flatten)]
+    pub stats: DeckBadge,
+}
+
+// Public deck marketplace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PublicDeckSort {
+    /// Most downloaded first
+    #[default]
+    Popular,
+    /// Highest average rating first
+    Rating,
+    Newest,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PublicDeckQuery {
+    pub tag: Option<String>,
+    pub language: Option<String>,
+    pub featured: Option<bool>,
+    #[serde(default)]
+    pub sort: PublicDeckSort,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PublicDeck {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub owner_id: Uuid,
+    pub owner_name: Option<String>,
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    pub front_language: Option<String>,
+    pub back_language: Option<String>,
+    pub card_count: i64,
+    pub average_rating: Option<f64>,
+    pub rating_count: i64,
+    pub download_count: i32,
+    pub is_featured: bool,
+    /// The caller's own rating, if any
+    pub your_rating: Option<i16>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+pub struct RateDeckDto {
+    #[validate(range(min = 1, max = 5))]
+    pub rating: i16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckRatingSummary {
+    pub deck_id: Uuid,
+    pub average_rating: Option<f64>,
+    pub rating_count: i64,
+    pub your_rating: Option<i16>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FeatureDeckDto {
+    pub featured: bool,
+}
+
+// Client-side deck encryption
+pub const ENCRYPTION_ALGORITHMS: &[&str] = &["aes-256-gcm", "xchacha20-poly1305"];
+pub const KEY_DERIVATION_FUNCTIONS: &[&str] = &["pbkdf2-sha256", "argon2id"];
+
+/// Everything a client needs to re-derive the deck key from the passphrase.
+/// The passphrase and the key itself never reach the server.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckEncryption {
+    pub deck_id: Uuid,
+    pub algorithm: String, // aes-256-gcm, xchacha20-poly1305
+    pub kdf: String,       // pbkdf2-sha256, argon2id
+    pub kdf_salt: String,  // Base64
+    #[schema(value_type = Object)]
+    pub kdf_params: serde_json::Value, // e.g. {"iterations": 600000}
+    pub key_check: String, // Known value encrypted with the key, to verify the passphrase
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Encrypt a deck, or re-key an already encrypted one. `cards` must carry the
+/// new ciphertext for every card in the deck.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct EncryptDeckDto {
+    pub algorithm: String,
+    pub kdf: String,
+    #[validate(length(min = 1, max = 256))]
+    pub kdf_salt: String,
+    #[schema(value_type = Option<Object>)]
+    pub kdf_params: Option<serde_json::Value>,
+    #[validate(length(min = 1, max = 1024))]
+    pub key_check: String,
+    pub cards: Vec<CardContentDto>,
+}
+
+/// Turn an encrypted deck back into plaintext; `cards` carries the decrypted
+/// content for every card in the deck
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DecryptDeckDto {
+    pub cards: Vec<CardContentDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardContentDto {
+    pub card_id: Uuid,
+    pub front: String,
+    pub back: String,
 }
 
 // Card model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Card {
     pub id: Uuid,
     pub deck_id: Uuid,
@@ -185,7 +773,33 @@ pub struct Card {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+/// A card with its Markdown sides rendered to sanitized HTML
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RenderedCard {
+    #[serde(flatten)]
+    pub card: Card,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendered_front: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendered_back: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    Html,
+}
+
+/// Sort order for listing a deck's cards
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CardOrder {
+    #[default]
+    Position,
+    Created,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateCardDto {
     #[validate(length(min = 1))]
     pub front: String,
@@ -194,11 +808,224 @@ pub struct CreateCardDto {
     pub position: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+// Card media attachments
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CardMedia {
+    pub id: Uuid,
+    pub card_id: Uuid,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Attachment with a time-limited download URL
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CardMediaResponse {
+    #[serde(flatten)]
+    pub media: CardMedia,
+    pub url: String,
+    pub url_expires_at: DateTime<Utc>,
+}
+
+// Web capture (browser extension)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureMode {
+    /// Store the selection as a single card
+    #[default]
+    Card,
+    /// Queue AI generation of cards from the selection
+    Generate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CaptureDto {
+    #[validate(length(min = 1, max = 20000))]
+    pub text: String,
+    #[validate(url, length(max = 2048))]
+    pub source_url: String,
+    #[validate(length(max = 500))]
+    pub page_title: Option<String>,
+    /// Front of the card in card mode; defaults to the page title
+    #[validate(length(min = 1, max = 1000))]
+    pub front: Option<String>,
+    /// Defaults to the user's web captures deck
+    pub deck_id: Option<Uuid>,
+    #[serde(default)]
+    pub mode: CaptureMode,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CaptureResult {
+    Card { card: Card },
+    Generation { job_id: Uuid, deck_id: Uuid, stream_url: String },
+}
+
+// Trash
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TrashItem {
+    pub id: Uuid,
+    pub item_type: String, // deck, card
+    pub title: String, // Deck title or card front
+    pub deck_id: Option<Uuid>, // Set for cards
+    pub deleted_at: DateTime<Utc>,
+    pub purge_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RestoredItem {
+    Deck { deck: Deck },
+    Card { card: Card },
+}
+
+// Email-in card creation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InboundEmailAddress {
+    pub address: String,
+    pub deck_id: Option<Uuid>, // None sends cards to the Email Inbox deck
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateInboundEmailDto {
+    pub deck_id: Option<Uuid>,
+}
+
+/// Message posted by the inbound email provider (Mailgun route fields)
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct InboundEmail {
+    pub recipient: String,
+    pub sender: String,
+    pub from: Option<String>,
+    pub subject: Option<String>,
+    #[serde(rename = "body-plain")]
+    pub body_plain: Option<String>,
+    #[serde(rename = "stripped-text")]
+    pub stripped_text: Option<String>, // Body without quoted replies and signature
+    #[serde(rename = "Message-Id")]
+    pub message_id: Option<String>,
+    pub timestamp: String,
+    pub token: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UpdateCardDto {
     pub front: Option<String>,
     pub back: Option<String>,
     pub position: Option<i32>,
+    /// The card's `updated_at` as last read; the update fails with 409 if
+    /// it has changed since. `If-Match` does the same with the card's ETag.
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct MoveCardDto {
+    pub target_deck_id: Uuid,
+    /// Index among the target deck's cards to insert at; the end when omitted
+    #[validate(range(min = 0))]
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct BulkMoveCardsDto {
+    /// Cards to move, kept in this order in the target deck
+    #[validate(length(min = 1, max = 1000))]
+    pub card_ids: Vec<Uuid>,
+    pub target_deck_id: Uuid,
+    #[validate(range(min = 0))]
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MovedCard {
+    #[serde(flatten)]
+    pub card: Card,
+    pub from_deck_id: Uuid,
+}
+
+/// The current user's markers and notes on a card. Suspended cards are left
+/// out of the study queue. `leech` is set when the card lapses its deck's
+/// leech threshold, and the learner can set or clear it too.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CardFlags {
+    pub card_id: Uuid,
+    pub starred: bool,
+    pub suspended: bool,
+    pub leech: bool,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+    pub updated_at: Option<DateTime<Utc>>, // None until anything is set
+}
+
+impl CardFlags {
+    pub fn unset(card_id: Uuid) -> Self {
+        Self {
+            card_id,
+            starred: false,
+            suspended: false,
+            leech: false,
+            notes: None,
+            tags: Vec::new(),
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateCardFlagsDto {
+    pub starred: Option<bool>,
+    pub suspended: Option<bool>,
+    pub leech: Option<bool>,
+    /// An empty string clears the notes
+    #[validate(length(max = 10000))]
+    pub notes: Option<String>,
+    /// Replaces the existing tags; an empty list clears them
+    pub tags: Option<Vec<String>>,
+}
+
+/// Next step worth taking with a leech
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeechSuggestion {
+    /// AI rewrites are waiting to be accepted or rejected
+    ReviewRemediations,
+    /// The answer is long enough to be several facts; split it into cards
+    Split,
+    /// No notes yet; a mnemonic in the card notes often helps
+    AddMnemonic,
+    /// Set it aside so it stops costing reviews
+    Suspend,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeechesQuery {
+    pub deck_id: Option<Uuid>,
+}
+
+/// A card the user keeps forgetting
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LeechCard {
+    pub card_id: Uuid,
+    pub deck_id: Uuid,
+    pub deck_name: String,
+    pub front: String,
+    pub back: String,
+    pub lapses: i32,
+    pub times_seen: i32,
+    /// Fraction (0-1) of answers that were correct
+    pub accuracy: f64,
+    pub suspended: bool,
+    /// When the card crossed the leech threshold; None if marked by hand
+    pub flagged_at: Option<DateTime<Utc>>,
+    pub pending_remediations: i64,
+    pub suggested_actions: Vec<LeechSuggestion>,
 }
 
 // CSV import/export DTOs
@@ -208,12 +1035,79 @@ pub struct CsvCard {
     pub back: String,
 }
 
+// Smart deck models
+/// Where a card is in its schedule for the current user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SmartDeckStatus {
+    /// Never answered
+    New,
+    /// Due now, overdue or not
+    Due,
+    /// Due for at least a day
+    Overdue,
+}
+
+/// Criteria a card must all meet to be in a smart deck. Omitted criteria
+/// match every card; at least one must be given.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SmartDeckFilter {
+    /// Only cards of these decks; otherwise every deck the user can study
+    pub deck_ids: Option<Vec<Uuid>>,
+    /// Only decks tagged with at least one of these, ignoring case
+    pub tags: Option<Vec<String>>,
+    /// Difficulty from 0.0 (easiest) to 1.0 (hardest), estimated from the
+    /// card's ease factor as in study queue suggestions
+    pub min_difficulty: Option<f32>,
+    pub max_difficulty: Option<f32>,
+    pub status: Option<SmartDeckStatus>,
+    /// Text in the front or back, ignoring case
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SmartDeck {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    #[sqlx(json)]
+    pub filter: SmartDeckFilter,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateSmartDeckDto {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    pub filter: SmartDeckFilter,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateSmartDeckDto {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    /// Replaces the whole filter
+    pub filter: Option<SmartDeckFilter>,
+}
+
 // Study session models
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct StudySession {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub deck_id: Uuid,
+    /// The deck studied; `None` for a folder session
+    pub deck_id: Option<Uuid>,
+    /// Set for a session across every deck in a folder and its subfolders
+    pub folder_id: Option<Uuid>,
+    /// Set for a session of a smart deck's cards
+    pub smart_deck_id: Option<Uuid>,
     pub study_mode: String,
     pub total_cards: i32,
     pub cards_studied: i32,
@@ -221,19 +1115,31 @@ pub struct StudySession {
     pub cards_incorrect: i32,
     pub cards_skipped: i32,
     pub duration_seconds: Option<i32>,
+    pub time_limit_seconds: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Seconds left in an unfinished timed session
+    pub remaining_seconds: Option<i32>,
     pub started_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// Set when the session was left unfinished for too long
+    pub abandoned_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateStudySessionDto {
-    pub deck_id: Uuid,
+    /// Study one deck, or give `folder_id` or `smart_deck_id` instead
+    pub deck_id: Option<Uuid>,
+    /// Study every deck in a folder and its subfolders
+    pub folder_id: Option<Uuid>,
+    /// Study the cards matching a smart deck's filter
+    pub smart_deck_id: Option<Uuid>,
     #[validate(length(min = 1, max = 50))]
     pub study_mode: Option<String>, // standard, quiz, timed, custom
     pub card_ids: Option<Vec<Uuid>>, // For custom study sessions
-    pub time_limit_seconds: Option<i32>, // For timed sessions
+    #[validate(range(min = 1, max = 86400))]
+    pub time_limit_seconds: Option<i32>, // Required for timed sessions
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -246,14 +1152,261 @@ pub struct UpdateStudySessionDto {
     pub completed_at: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ActiveStudySession {
+    #[serde(flatten)]
+    pub session: StudySession,
+    pub active_device_id: Option<String>,
+    pub last_activity_at: Option<DateTime<Utc>>,
+    pub last_card: Option<Card>,
+    pub remaining_cards: Vec<Card>,
+}
+
+/// Everything that happened in a session, in order, for review and debugging
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionReplay {
+    pub session: StudySession,
+    pub events: Vec<SessionReplayEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionReplayEvent {
+    pub sequence: i64, // 1-based position in the session
+    pub progress_id: Uuid,
+    pub card_id: Uuid,
+    pub front: String,
+    pub back: String,
+    pub studied_at: DateTime<Utc>,
+    pub elapsed_ms: i64, // Since the session started
+    pub status: CardStatus,
+    pub rating: Rating,
+    pub response_time_ms: Option<i32>,
+    pub scheduler: Option<ScheduleDecision>, // Missing for answers recorded before replay support
+}
+
+/// SM-2 state of a card before and after one answer
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScheduleDecision {
+    pub quality: i32,
+    pub ease_factor_before: f32,
+    pub interval_days_before: i32,
+    pub repetitions_before: i32,
+    pub ease_factor_after: f32,
+    pub interval_days_after: i32,
+    pub repetitions_after: i32,
+    pub next_review_at: DateTime<Utc>,
+    pub lapsed: bool,
+}
+
+/// Every answer the current user gave to one card, oldest first, to show
+/// why it keeps coming back
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardHistory {
+    pub card: Card,
+    pub total_reviews: i64,
+    /// Answers that sent a learned card back to relearning
+    pub lapses: i64,
+    pub average_response_time_ms: Option<i32>,
+    /// When the card is due next, as scheduled by the latest answer
+    pub next_review_at: Option<DateTime<Utc>>,
+    pub reviews: Vec<CardReview>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardReview {
+    pub progress_id: Uuid,
+    pub session_id: Uuid,
+    pub studied_at: DateTime<Utc>,
+    pub status: CardStatus,
+    pub rating: Rating,
+    pub response_time_ms: Option<i32>,
+    /// What was typed, for typed answers
+    pub user_answer: Option<String>,
+    pub scheduler: Option<ScheduleDecision>, // Missing for answers recorded before replay support
+}
+
+/// Summary of a session's answers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionStats {
+    pub session_id: Uuid,
+    pub total_answers: i64,
+    /// Distinct cards answered
+    pub total_cards: i64,
+    /// Answers rated anything but `again`
+    pub correct_answers: i64,
+    /// Fraction (0-1) of answers that were correct
+    pub accuracy: f64,
+    pub average_response_time_ms: Option<f64>,
+    pub median_response_time_ms: Option<f64>,
+    pub rating_distribution: RatingDistribution,
+    /// Every card answered in the session, hardest first
+    pub cards: Vec<SessionCardStats>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RatingDistribution {
+    pub again: i64,
+    pub hard: i64,
+    pub good: i64,
+    pub easy: i64,
+}
+
+/// One card's answers within a session
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionCardStats {
+    pub card_id: Uuid,
+    pub front: String,
+    pub answers: i64,
+    pub again_count: i64,
+    pub hard_count: i64,
+    pub average_response_time_ms: Option<f64>,
+    pub last_rating: Rating,
+}
+
+// Quiz mode
+/// Where a quiz question's wrong options came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DistractorSource {
+    /// Backs of other cards in the deck
+    Deck,
+    /// Options of AI-generated quiz questions about the card
+    Ai,
+    /// Deck answers topped up with AI options
+    Mixed,
+}
+
+impl DistractorSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DistractorSource::Deck => "deck",
+            DistractorSource::Ai => "ai",
+            DistractorSource::Mixed => "mixed",
+        }
+    }
+}
+
+/// A multiple-choice question for the next card of a quiz session; the
+/// answer key stays on the server
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuizQuestion {
+    pub question_id: Uuid,
+    pub session_id: Uuid,
+    pub card_id: Uuid,
+    pub front: String,
+    pub options: Vec<String>,
+    pub distractor_source: DistractorSource,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct QuizAnswerDto {
+    pub question_id: Uuid,
+    #[validate(range(min = 0))]
+    pub answer_index: i32,
+    pub response_time_ms: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuizAnswerResult {
+    pub question_id: Uuid,
+    pub card_id: Uuid,
+    pub is_correct: bool,
+    pub answer_index: i32,
+    pub correct_index: i32,
+    pub correct_answer: String,
+    pub progress: CardProgress,
+    /// Achievements earned by this answer
+    #[serde(default)]
+    pub new_achievements: Vec<Achievement>,
+}
+
+/// Answers carry a `rating`; `status` is still accepted from older clients.
+/// A `user_answer` is graded against the card back by the server.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RecordProgressDto {
+    pub card_id: Uuid,
+    pub rating: Option<Rating>,
+    pub status: Option<CardStatus>,
+    pub response_time_ms: Option<i32>,
+    pub user_answer: Option<String>,
+}
+
+/// Server-side grade of a typed answer
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnswerGrade {
+    pub is_correct: bool,
+    /// 1 - edit distance / length of the longer normalized text
+    pub similarity: f64,
+    /// The card back as it was compared
+    pub expected: String,
+    /// How the typed answer differs from `expected`; `null` on an exact match
+    pub diff: Option<Vec<DiffSegment>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DiffSegment {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    /// In the card back but not typed
+    Missing,
+    /// Typed but not in the card back
+    Extra,
+}
+
+/// A recorded answer, with its grade when the answer was typed
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GradedCardProgress {
+    #[serde(flatten)]
+    pub progress: CardProgress,
+    pub grade: Option<AnswerGrade>,
+    /// Achievements earned by this answer
+    #[serde(default)]
+    pub new_achievements: Vec<Achievement>,
+}
+
+/// A completed session and the achievements completing it earned
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CompletedStudySession {
+    #[serde(flatten)]
+    pub session: StudySession,
+    pub decks: Vec<SessionDeckBreakdown>,
+    pub new_achievements: Vec<Achievement>,
+}
+
+/// A session with its answers broken down by deck
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StudySessionDetails {
+    #[serde(flatten)]
+    pub session: StudySession,
+    pub decks: Vec<SessionDeckBreakdown>,
+}
+
+/// Answers in a session to cards of one deck
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SessionDeckBreakdown {
+    pub deck_id: Uuid,
+    pub deck_name: String,
+    pub cards_studied: i64,
+    pub cards_correct: i64,
+    pub cards_incorrect: i64,
+}
+
 // Card progress model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct CardProgress {
     pub id: Uuid,
     pub session_id: Uuid,
     pub card_id: Uuid,
     pub user_id: Uuid,
-    pub status: CardStatus,
+    pub status: CardStatus, // Legacy; derived from `rating`
+    pub rating: Rating,
     pub response_time_ms: Option<i32>,
     pub user_answer: Option<String>,
     pub is_correct: Option<bool>,
@@ -270,7 +1423,7 @@ pub struct SubmitCardAnswerDto {
     pub is_correct: Option<bool>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "card_status", rename_all = "lowercase")]
 pub enum CardStatus {
     Easy,
@@ -279,8 +1432,237 @@ pub enum CardStatus {
     Forgot,
 }
 
+impl CardStatus {
+    /// Legacy status stored alongside a rating
+    pub fn from_rating(rating: Rating) -> Self {
+        match rating {
+            Rating::Again => CardStatus::Forgot,
+            Rating::Hard => CardStatus::Hard,
+            Rating::Good => CardStatus::Medium,
+            Rating::Easy => CardStatus::Easy,
+        }
+    }
+}
+
+/// Answer rating with Anki's button semantics. Anything but `Again` is a
+/// successful recall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum Rating {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Rating {
+    pub fn from_status(status: CardStatus) -> Self {
+        match status {
+            CardStatus::Forgot => Rating::Again,
+            CardStatus::Hard => Rating::Hard,
+            CardStatus::Medium => Rating::Good,
+            CardStatus::Easy => Rating::Easy,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Rating::Again => "again",
+            Rating::Hard => "hard",
+            Rating::Good => "good",
+            Rating::Easy => "easy",
+        }
+    }
+
+    pub fn is_correct(self) -> bool {
+        self != Rating::Again
+    }
+}
+
+/// Buttons a deck offers when answering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RatingScale {
+    TwoButton,
+    ThreeButton,
+    FourButton,
+}
+
+impl RatingScale {
+    pub fn from_buttons(buttons: i16) -> Option<Self> {
+        match buttons {
+            2 => Some(RatingScale::TwoButton),
+            3 => Some(RatingScale::ThreeButton),
+            4 => Some(RatingScale::FourButton),
+            _ => None,
+        }
+    }
+
+    pub fn buttons(self) -> i16 {
+        self.ratings().len() as i16
+    }
+
+    pub fn ratings(self) -> &'static [Rating] {
+        match self {
+            RatingScale::TwoButton => &[Rating::Again, Rating::Good],
+            RatingScale::ThreeButton => &[Rating::Again, Rating::Good, Rating::Easy],
+            RatingScale::FourButton => &[Rating::Again, Rating::Hard, Rating::Good, Rating::Easy],
+        }
+    }
+
+    pub fn allows(self, rating: Rating) -> bool {
+        self.ratings().contains(&rating)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeckRatingScale {
+    pub deck_id: Uuid,
+    pub buttons: i16,
+    pub ratings: Vec<Rating>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateRatingScaleDto {
+    #[validate(range(min = 2, max = 4))]
+    pub buttons: i16,
+}
+
+// Per-deck study settings
+
+/// Scheduler that spaces the deck's reviews
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum SchedulingAlgorithm {
+    /// SuperMemo 2: intervals grow by a per-card ease factor
+    Sm2,
+    /// Leitner boxes: recalling a card moves it up to a box with a longer,
+    /// fixed interval; forgetting it moves it down
+    Leitner,
+}
+
+impl SchedulingAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SchedulingAlgorithm::Sm2 => "sm2",
+            SchedulingAlgorithm::Leitner => "leitner",
+        }
+    }
+}
+
+/// What happens when a learned card is forgotten. Either way it is due
+/// again the next day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum LapseAction {
+    /// Relearn it, keeping the ease factor SM-2 lowered
+    Relearn,
+    /// Start over as if it had never been learned, with the default ease
+    Reset,
+}
+
+/// How far a recalled card moves up the Leitner boxes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum LeitnerPromotion {
+    /// `good` and `easy` move up one box
+    Step,
+    /// `good` moves up one box and `easy` two
+    SkipOnEasy,
+}
+
+/// Where a forgotten card goes in the Leitner boxes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum LeitnerDemotion {
+    /// Back to the first box
+    FirstBox,
+    /// Down one box
+    PreviousBox,
+}
+
+/// What happens to a card once it lapses `leech_threshold` times
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum LeechAction {
+    /// Mark it as a leech and keep studying it
+    Flag,
+    /// Mark it and suspend it until the learner unsuspends it
+    Suspend,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeckSettings {
+    pub deck_id: Uuid,
+    /// Unseen cards introduced per day
+    pub new_cards_per_day: i32,
+    /// Reviews of learned cards per day; learning cards are not capped
+    pub max_reviews_per_day: i32,
+    pub algorithm: SchedulingAlgorithm,
+    pub lapse_action: LapseAction,
+    /// Number of Leitner boxes; box `n` is reviewed every 2^(n-1) days
+    pub leitner_boxes: i32,
+    pub leitner_promotion: LeitnerPromotion,
+    pub leitner_demotion: LeitnerDemotion,
+    /// Lapses after which a card is a leech
+    pub leech_threshold: i32,
+    pub leech_action: LeechAction,
+    /// Keep a reverse card (back -> front) for every card in the deck
+    pub generate_reverse_cards: bool,
+    /// Bury a card's sibling for the day once one of them is answered
+    pub bury_siblings: bool,
+}
+
+impl DeckSettings {
+    pub const DEFAULT_NEW_CARDS_PER_DAY: i32 = 20;
+    pub const DEFAULT_MAX_REVIEWS_PER_DAY: i32 = 200;
+    pub const DEFAULT_LEITNER_BOXES: i32 = 5;
+    pub const DEFAULT_LEECH_THRESHOLD: i32 = 8;
+
+    pub fn defaults(deck_id: Uuid) -> Self {
+        Self {
+            deck_id,
+            new_cards_per_day: Self::DEFAULT_NEW_CARDS_PER_DAY,
+            max_reviews_per_day: Self::DEFAULT_MAX_REVIEWS_PER_DAY,
+            algorithm: SchedulingAlgorithm::Sm2,
+            lapse_action: LapseAction::Relearn,
+            leitner_boxes: Self::DEFAULT_LEITNER_BOXES,
+            leitner_promotion: LeitnerPromotion::Step,
+            leitner_demotion: LeitnerDemotion::FirstBox,
+            leech_threshold: Self::DEFAULT_LEECH_THRESHOLD,
+            leech_action: LeechAction::Flag,
+            generate_reverse_cards: false,
+            bury_siblings: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateDeckSettingsDto {
+    #[validate(range(min = 0, max = 500))]
+    pub new_cards_per_day: Option<i32>,
+    #[validate(range(min = 0, max = 10000))]
+    pub max_reviews_per_day: Option<i32>,
+    pub algorithm: Option<SchedulingAlgorithm>,
+    pub lapse_action: Option<LapseAction>,
+    #[validate(range(min = 2, max = 10))]
+    pub leitner_boxes: Option<i32>,
+    pub leitner_promotion: Option<LeitnerPromotion>,
+    pub leitner_demotion: Option<LeitnerDemotion>,
+    #[validate(range(min = 2, max = 100))]
+    pub leech_threshold: Option<i32>,
+    pub leech_action: Option<LeechAction>,
+    pub generate_reverse_cards: Option<bool>,
+    pub bury_siblings: Option<bool>,
+}
+
 // User statistics and gamification
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct UserStats {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -295,6 +1677,132 @@ pub struct UserStats {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserStatsResponse {
+    #[serde(flatten)]
+    pub stats: UserStats,
+    /// Points at which the current level started
+    pub level_points: i32,
+    /// Points at which the next level starts
+    pub next_level_points: i32,
+    pub points_to_next_level: i32,
+    /// Fraction (0-1) of the way from this level to the next
+    pub level_progress: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HeatmapQuery {
+    /// Calendar year; defaults to the current one
+    pub year: Option<i32>,
+    /// IANA timezone days are counted in; defaults to UTC
+    pub timezone: Option<String>,
+}
+
+/// Daily study activity over a calendar year. Entry `i` of `reviews` and
+/// `minutes` is for the day `i` days after `start_date`; every day of the
+/// year has an entry, zero when nothing was studied.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StudyHeatmap {
+    pub year: i32,
+    pub timezone: String,
+    pub start_date: chrono::NaiveDate,
+    /// Answers recorded each day
+    pub reviews: Vec<i32>,
+    /// Minutes spent answering each day, rounded
+    pub minutes: Vec<i32>,
+    pub total_reviews: i64,
+    pub total_minutes: i64,
+    /// Days with at least one answer
+    pub active_days: i32,
+    /// Most answers on a single day, for scaling colors
+    pub max_reviews: i32,
+}
+
+/// How well the user is doing on one deck, in the spirit of Anki's
+/// statistics screen. Built from the user's own answers and scheduling.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeckStatistics {
+    pub deck_id: Uuid,
+    pub total_cards: i64,
+    /// Cards the user has never answered
+    pub new_cards: i64,
+    /// Studied cards with an interval under 21 days
+    pub young_cards: i64,
+    /// Studied cards with an interval of 21 days or more
+    pub mature_cards: i64,
+    pub total_reviews: i64,
+    /// Answers to mature cards
+    pub mature_reviews: i64,
+    /// Share (0-1) of answers to mature cards not rated "again"; null
+    /// before any mature card is reviewed
+    pub true_retention: Option<f64>,
+    /// Share (0-1) of answers to young cards past their first day not
+    /// rated "again"
+    pub young_retention: Option<f64>,
+    pub average_ease: Option<f64>,
+    pub average_interval_days: Option<f64>,
+    /// Average time spent answering a card, in milliseconds
+    pub average_time_per_card_ms: Option<i64>,
+    pub ease_distribution: Vec<EaseBucket>,
+    pub interval_histogram: Vec<IntervalBucket>,
+}
+
+/// Studied cards whose ease factor is at least `min` and below `max`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EaseBucket {
+    pub min: f64,
+    /// Null for the last bucket
+    pub max: Option<f64>,
+    pub cards: i64,
+}
+
+/// Studied cards whose interval is between `min_days` and `max_days`,
+/// inclusive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct IntervalBucket {
+    pub min_days: i32,
+    /// Null for the last bucket
+    pub max_days: Option<i32>,
+    pub cards: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardKind {
+    #[default]
+    WeeklyPoints,
+    Streak,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeaderboardQuery {
+    #[serde(default)]
+    pub board: LeaderboardKind,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    pub user_id: Uuid,
+    pub display_name: Option<String>,
+    pub weekly_points: i32,
+    pub current_streak_days: i32,
+    pub longest_streak_days: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Leaderboard {
+    pub board: LeaderboardKind,
+    pub entries: Vec<LeaderboardEntry>,
+    /// The current user's standing; `null` when opted out or not ranked
+    pub you: Option<LeaderboardEntry>,
+    /// When the standings were last recomputed
+    pub refreshed_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct UserCardStats {
     pub id: Uuid,
@@ -312,7 +1820,7 @@ pub struct UserCardStats {
 }
 
 // Achievement models
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Achievement {
     pub id: Uuid,
     pub name: String,
@@ -332,7 +1840,7 @@ pub struct UserAchievement {
     pub earned_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AchievementWithStatus {
     #[serde(flatten)]
     pub achievement: Achievement,
@@ -342,18 +1850,56 @@ pub struct AchievementWithStatus {
 }
 
 // Response DTOs with counts
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DeckWithStats {
     #[serde(flatten)]
     pub deck: Deck,
     pub card_count: i64,
     pub last_studied: Option<DateTime<Utc>>,
+    pub is_encrypted: bool,
+    /// Signed link to the deck's cover image
+    pub cover_url: Option<String>,
+    #[serde(skip)]
+    pub cover_storage_key: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FolderWithContents {
     #[serde(flatten)]
     pub folder: Folder,
     pub subfolders: Vec<Folder>,
     pub decks: Vec<DeckWithStats>,
 }
+
+// Home screen dashboard
+/// A deck on the dashboard with the cards waiting in it. Counts leave out
+/// suspended cards and ignore the deck's daily limits.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DashboardDeck {
+    #[serde(flatten)]
+    pub deck: DeckWithStats,
+    /// Seen cards whose next review is due
+    pub due_count: i64,
+    /// Cards never studied
+    pub new_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DashboardStreak {
+    /// 0 once a whole day passes without answers
+    pub current_days: i32,
+    pub longest_days: i32,
+    pub studied_today: bool,
+}
+
+/// Everything the home screen shows, in one response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Dashboard {
+    pub folders: Vec<FolderNode>,
+    pub decks: Vec<DashboardDeck>,
+    pub total_due: i64,
+    pub total_new: i64,
+    pub streak: DashboardStreak,
+    /// Latest sessions, newest first
+    pub recent_sessions: Vec<StudySession>,
+}
@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum SyncEntity {
+    Folder,
+    Deck,
+    Card,
+}
+
+impl SyncEntity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SyncEntity::Folder => "folder",
+            SyncEntity::Deck => "deck",
+            SyncEntity::Card => "card",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum SyncOp {
+    Upsert,
+    /// Tombstone: the entity was deleted or moved to the trash
+    Delete,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SyncChangesQuery {
+    /// `next_cursor` from the previous pull; 0 or absent for everything
+    pub since: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Latest state of one entity. `data` is the folder, deck or card as the
+/// regular endpoints return it, and `null` for deletions.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncChange {
+    pub entity: SyncEntity,
+    pub id: Uuid,
+    pub op: SyncOp,
+    pub version: i64,
+    pub updated_at: DateTime<Utc>,
+    #[schema(value_type = Option<Object>)]
+    pub data: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncChangesPage {
+    pub changes: Vec<SyncChange>,
+    /// Pass as `since` on the next pull. Orders commits, not versions.
+    pub next_cursor: i64,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SyncPushDto {
+    #[validate(length(min = 1, max = 500))]
+    pub changes: Vec<SyncPushChange>,
+}
+
+/// A change made on the client. `base_version` is the version the client
+/// last pulled for the entity, `None` for entities it created.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncPushChange {
+    pub entity: SyncEntity,
+    pub id: Uuid,
+    pub op: SyncOp,
+    pub base_version: Option<i64>,
+    /// When the client made the change; decides conflicts
+    pub updated_at: DateTime<Utc>,
+    /// Fields of the entity for upserts: `FolderSyncData`, `DeckSyncData`
+    /// or `CardSyncData`
+    #[schema(value_type = Option<Object>)]
+    pub data: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct FolderSyncData {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub parent_folder_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct DeckSyncData {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    pub folder_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CardSyncData {
+    pub deck_id: Uuid,
+    #[validate(length(min = 1))]
+    pub front: String,
+    #[validate(length(min = 1))]
+    pub back: String,
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPushStatus {
+    /// Stored; `version` is the entity's new version
+    Applied,
+    /// The entity changed on the server since `base_version`; see `resolution`
+    Conflict,
+    /// Invalid, or not the user's to change; see `error`
+    Rejected,
+}
+
+/// Which side of a conflict was kept. The later `updated_at` wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    ClientWins,
+    ServerWins,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncPushResult {
+    pub entity: SyncEntity,
+    pub id: Uuid,
+    pub status: SyncPushStatus,
+    pub resolution: Option<ConflictResolution>,
+    /// The entity's version after the push
+    pub version: Option<i64>,
+    /// The server's copy when it won a conflict
+    pub server: Option<SyncChange>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncPushResponse {
+    pub results: Vec<SyncPushResult>,
+}
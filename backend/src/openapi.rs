@@ -0,0 +1,135 @@
+//! OpenAPI document for the REST API, assembled from the `#[utoipa::path]`
+//! annotations on each handler module and served with Swagger UI at
+//! `/api/v1/docs`.
+
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityScheme},
+        ContentBuilder, Ref, RefOr, Response, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+
+use crate::{handlers, utils::ErrorResponse};
+
+/// Generated document, for SDK generators
+pub const OPENAPI_JSON_PATH: &str = "/api/v1/openapi.json";
+/// Swagger UI rendering that document
+pub const SWAGGER_UI_PATH: &str = "/api/v1/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "DeckOracle API",
+        description = "Flashcard decks, spaced-repetition study and AI card generation. \
+                       Authenticate with `POST /api/v1/auth/login` and send the access token \
+                       as `Authorization: Bearer <token>`; refresh it with `POST /api/v1/auth/refresh`. \
+                       Scripts can send a key from `POST /api/v1/users/me/api-keys` as `X-Api-Key` instead."
+    ),
+    nest(
+        (path = "/api/v1/auth", api = handlers::auth::ApiDoc),
+        (path = "/api/v1/users", api = handlers::user::ApiDoc),
+        (path = "/api/v1/dashboard", api = handlers::dashboard::ApiDoc),
+        (path = "/api/v1/folders", api = handlers::folder::ApiDoc),
+        (path = "/api/v1/decks", api = handlers::deck::ApiDoc),
+        (path = "/api/v1/cards", api = handlers::card::ApiDoc),
+        (path = "/api/v1/smart-decks", api = handlers::smart_deck::ApiDoc),
+        (path = "/api/v1/study", api = handlers::study::ApiDoc),
+        (path = "/api/v1/progress", api = handlers::progress::ApiDoc),
+        (path = "/api/v1/import-export", api = handlers::import_export::ApiDoc),
+        (path = "/api/v1/ai", api = handlers::ai::ApiDoc),
+        (path = "/api/v1/capture", api = handlers::capture::ApiDoc),
+        (path = "/api/v1/trash", api = handlers::trash::ApiDoc),
+        (path = "/api/v1/jobs", api = handlers::job::ApiDoc),
+        (path = "/api/v1/admin", api = handlers::admin::ApiDoc),
+        (path = "/api/v1/search", api = handlers::search::ApiDoc),
+        (path = "/api/v1/sync", api = handlers::sync::ApiDoc),
+        (path = "/api/v1/ws", api = handlers::ws::ApiDoc),
+        (path = "/api/v1/public", api = handlers::public::ApiDoc),
+        (path = "/api/v1/inbound", api = handlers::inbound::ApiDoc),
+        (path = "/api/v1/guest", api = handlers::guest::ApiDoc),
+        (path = "/api/v1/media", api = handlers::media::ApiDoc),
+        (path = "/api/v1", api = handlers::meta::ApiDoc),
+        (path = "/api/v1", api = handlers::health::ApiDoc),
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&SecuritySchemes, &ErrorResponses),
+    security(("bearer_auth" = []), ("api_key" = []))
+)]
+pub struct ApiDoc;
+
+/// Access tokens or API keys for user endpoints, and deck guest tokens for
+/// `/guest`
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                Http::builder()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("Access token from `/auth/login` or `/auth/refresh`"))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-Api-Key",
+                "Key from `/users/me/api-keys`; `read` keys only allow GET requests",
+            ))),
+        );
+        components.add_security_scheme(
+            "guest_token",
+            SecurityScheme::Http(
+                Http::builder()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("Guest token created by the deck owner"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Every `AppError` renders as `ErrorResponse`, so document it once as the
+/// default 4XX and 5XX response of every operation
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ];
+            for operation in operations.into_iter().flatten() {
+                let responses = &mut operation.responses.responses;
+                responses
+                    .entry("4XX".to_string())
+                    .or_insert_with(|| error_response("Client error"));
+                responses
+                    .entry("5XX".to_string())
+                    .or_insert_with(|| error_response("Server error"));
+            }
+        }
+    }
+}
+
+fn error_response(description: &str) -> RefOr<Response> {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name("ErrorResponse")))
+                .build(),
+        )
+        .build()
+        .into()
+}
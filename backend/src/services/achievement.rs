@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Achievement, AchievementWithStatus},
+    services::stats::StatsService,
+    utils::Result,
+};
+
+/// Answers recorded in total
+pub const CARDS_STUDIED: &str = "cards_studied";
+/// Longest run of consecutive study days
+pub const STREAK_DAYS: &str = "streak_days";
+/// Completed sessions in which every answer was correct
+pub const PERFECT_SESSIONS: &str = "perfect_sessions";
+
+#[derive(sqlx::FromRow)]
+struct StatusRow {
+    #[sqlx(flatten)]
+    achievement: Achievement,
+    earned_at: Option<DateTime<Utc>>,
+    progress: Option<i32>,
+}
+
+/// The user's (`$1`) current value for each criteria type
+const PROGRESS_CTE: &str = r#"
+    progress AS (
+        SELECT COALESCE(s.total_cards_studied, 0) as cards_studied,
+               COALESCE(s.longest_streak_days, 0) as streak_days,
+               (
+                   SELECT COUNT(*) FROM study_sessions ss
+                   WHERE ss.user_id = $1 AND ss.completed_at IS NOT NULL
+                       AND ss.cards_studied > 0 AND ss.cards_correct = ss.cards_studied
+               ) as perfect_sessions
+        FROM (SELECT 1) one
+        LEFT JOIN user_stats s ON s.user_id = $1
+    )
+"#;
+
+/// `progress` towards achievement `a` for the criteria types `$2`, `$3`, `$4`
+const PROGRESS_VALUE: &str = r#"
+    CASE a.criteria_type
+        WHEN $2 THEN p.cards_studied
+        WHEN $3 THEN p.streak_days
+        WHEN $4 THEN p.perfect_sessions
+    END
+"#;
+
+/// Awards achievements from the catalog in `achievements` once a user's
+/// progress meets their `criteria_type` / `criteria_value`
+pub struct AchievementService;
+
+impl AchievementService {
+    /// The whole catalog, with whether and when the user earned each one
+    pub async fn list(db: &PgPool, user_id: Uuid) -> Result<Vec<AchievementWithStatus>> {
+        // Progress is capped at the goal, so earned achievements show as complete
+        let sql = format!(
+            r#"
+            WITH {}
+            SELECT a.id, a.name, a.description, a.icon_name, a.points, a.criteria_type,
+                   a.criteria_value, a.created_at, ua.earned_at,
+                   LEAST({}, a.criteria_value)::INT as progress
+            FROM achievements a
+            CROSS JOIN progress p
+            LEFT JOIN user_achievements ua ON ua.achievement_id = a.id AND ua.user_id = $1
+            ORDER BY a.criteria_type, a.criteria_value
+            "#,
+            PROGRESS_CTE, PROGRESS_VALUE
+        );
+        let rows = sqlx::query_as::<_, StatusRow>(&sql)
+            .bind(user_id)
+            .bind(CARDS_STUDIED)
+            .bind(STREAK_DAYS)
+            .bind(PERFECT_SESSIONS)
+            .fetch_all(db)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AchievementWithStatus {
+                achievement: row.achievement,
+                earned: row.earned_at.is_some(),
+                earned_at: row.earned_at,
+                progress: row.progress,
+            })
+            .collect())
+    }
+
+    /// Award every achievement the user now qualifies for and add its points
+    /// to their stats. Returns the newly earned ones; call after each study
+    /// event. Concurrent calls award each achievement once.
+    pub async fn evaluate(db: &PgPool, user_id: Uuid) -> Result<Vec<Achievement>> {
+        let mut tx = db.begin().await?;
+
+        let sql = format!(
+            r#"
+            WITH {},
+            awarded AS (
+                INSERT INTO user_achievements (user_id, achievement_id)
+                SELECT $1, a.id
+                FROM achievements a, progress p
+                WHERE COALESCE({} >= a.criteria_value, false)
+                ON CONFLICT (user_id, achievement_id) DO NOTHING
+                RETURNING achievement_id
+            )
+            SELECT a.id, a.name, a.description, a.icon_name, a.points, a.criteria_type,
+                   a.criteria_value, a.created_at
+            FROM achievements a
+            JOIN awarded ON awarded.achievement_id = a.id
+            ORDER BY a.criteria_type, a.criteria_value
+            "#,
+            PROGRESS_CTE, PROGRESS_VALUE
+        );
+        let earned = sqlx::query_as::<_, Achievement>(&sql)
+            .bind(user_id)
+            .bind(CARDS_STUDIED)
+            .bind(STREAK_DAYS)
+            .bind(PERFECT_SESSIONS)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let points: i32 = earned.iter().map(|a| a.points).sum();
+        if points > 0 {
+            StatsService::add_points(&mut tx, user_id, points).await?;
+        }
+
+        tx.commit().await?;
+        Ok(earned)
+    }
+}
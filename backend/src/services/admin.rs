@@ -0,0 +1,122 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::admin::{
+        AdminStats, DailyInstanceMetrics, InstanceTotals, JobFailureRate, TableStorage,
+    },
+    utils::{AppError, Result},
+};
+
+const DEFAULT_PERIOD_DAYS: i32 = 30;
+const MAX_PERIOD_DAYS: i32 = 365;
+
+pub struct AdminService;
+
+impl AdminService {
+    pub async fn is_admin(db: &PgPool, user_id: Uuid) -> Result<bool> {
+        let is_admin = sqlx::query_scalar::<_, bool>("SELECT is_admin FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?
+            .unwrap_or(false);
+
+        Ok(is_admin)
+    }
+
+    pub async fn set_admin(db: &PgPool, user_id: Uuid, is_admin: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE users SET is_admin = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(is_admin)
+            .execute(db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("User not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn instance_stats(db: &PgPool, days: Option<i32>) -> Result<AdminStats> {
+        let period_days = days.unwrap_or(DEFAULT_PERIOD_DAYS).clamp(1, MAX_PERIOD_DAYS);
+
+        let totals = sqlx::query_as::<_, InstanceTotals>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM users) as users,
+                (SELECT COUNT(*) FROM decks) as decks,
+                (SELECT COUNT(*) FROM cards) as cards,
+                (SELECT COALESCE(SUM(tokens_used), 0)::BIGINT FROM ai_content_generation_jobs) as ai_tokens_used,
+                pg_database_size(current_database()) as database_bytes
+            "#,
+        )
+        .fetch_one(db)
+        .await?;
+
+        let daily = sqlx::query_as::<_, DailyInstanceMetrics>(
+            r#"
+            WITH days AS (
+                SELECT generate_series(CURRENT_DATE - ($1 - 1), CURRENT_DATE, INTERVAL '1 day')::DATE as day
+            )
+            SELECT
+                d.day,
+                (SELECT COUNT(DISTINCT user_id) FROM study_sessions WHERE started_at::DATE = d.day) as active_users,
+                (SELECT COUNT(*) FROM users WHERE created_at::DATE = d.day) as new_registrations,
+                (SELECT COUNT(*) FROM decks WHERE created_at::DATE = d.day) as decks_created,
+                (SELECT COUNT(*) FROM cards WHERE created_at::DATE = d.day) as cards_created,
+                (SELECT COALESCE(SUM(tokens_used), 0)::BIGINT FROM ai_content_generation_jobs
+                 WHERE created_at::DATE = d.day) as ai_tokens_used,
+                (SELECT COALESCE(SUM(octet_length(front) + octet_length(back)), 0)::BIGINT FROM cards
+                 WHERE created_at::DATE = d.day) as content_bytes_added
+            FROM days d
+            ORDER BY d.day
+            "#,
+        )
+        .bind(period_days)
+        .fetch_all(db)
+        .await?;
+
+        let job_failure_rates = sqlx::query_as::<_, JobFailureRate>(
+            r#"
+            SELECT
+                job_type,
+                COUNT(*) as total,
+                COUNT(*) FILTER (WHERE status = 'completed') as completed,
+                COUNT(*) FILTER (WHERE status = 'failed') as failed,
+                COALESCE(
+                    COUNT(*) FILTER (WHERE status = 'failed')::FLOAT8
+                        / NULLIF(COUNT(*) FILTER (WHERE status IN ('completed', 'failed')), 0),
+                    0
+                ) as failure_rate
+            FROM user_jobs
+            WHERE created_at >= NOW() - make_interval(days => $1)
+            GROUP BY job_type
+            ORDER BY job_type
+            "#,
+        )
+        .bind(period_days)
+        .fetch_all(db)
+        .await?;
+
+        let storage = sqlx::query_as::<_, TableStorage>(
+            r#"
+            SELECT relname::TEXT as table_name, pg_total_relation_size(relid) as total_bytes
+            FROM pg_catalog.pg_statio_user_tables
+            ORDER BY total_bytes DESC
+            "#,
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(AdminStats {
+            generated_at: Utc::now(),
+            period_days,
+            totals,
+            daily,
+            job_failure_rates,
+            storage,
+        })
+    }
+}
@@ -0,0 +1,580 @@
+use serde_json::{json, Value as JsonValue};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    config::AiConfig,
+    models::{
+        ai::{
+            AiGeneratedCard, AiProviderKind, ApproveGeneratedCardsDto, GeneratedCardsQuery, GeneratedCardsResult,
+            GenerationEvent, GenerationSummary, RejectGeneratedCardsDto,
+        },
+        Card, DeckRole,
+    },
+    services::{
+        circuit_breaker::CircuitBreaker,
+        encryption::EncryptionService,
+        language::DeckLanguages,
+        sharing::SharingService,
+        ai_provider::{provider_for, resolve_provider, FlashcardGenerationOptions, GeneratedFlashcard},
+        ai_usage::AiUsageService,
+    },
+    utils::{AppError, Result},
+};
+
+/// Confidence assumed when the model does not report one
+const DEFAULT_CONFIDENCE: f32 = 0.6;
+
+/// Card generation through the configured AI provider. Every request is tracked as an
+/// `ai_content_generation_jobs` row and its cards are stored for review.
+pub struct AiGenerationService;
+
+impl AiGenerationService {
+    pub async fn generate_cards(
+        db: &PgPool,
+        ai: &AiConfig,
+        breaker: &Arc<CircuitBreaker>,
+        user_id: Uuid,
+        deck_id: Option<Uuid>,
+        content: &str,
+        options: FlashcardGenerationOptions,
+        provider: Option<AiProviderKind>,
+    ) -> Result<GeneratedCardsResult> {
+        let mut client = provider_for(ai, breaker, provider)?;
+        let kind = client.kind();
+        AiUsageService::check_quota(db, ai, user_id).await?;
+        let options = Self::prepare_options(db, ai, deck_id, options).await?;
+        // The content is kept until the job completes, so a failed job can be retried
+        let metadata = json!({
+            "options": options,
+            "content_length": content.chars().count(),
+            "content": content,
+        });
+        let job_id =
+            Self::create_job(db, user_id, deck_id, "processing", metadata, kind, client.default_model()).await?;
+
+        let batch = match client.generate_flashcards(content, &options).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                tracing::error!("AI provider error for job {}: {}", job_id, e);
+                Self::finish_job(db, job_id, "failed", None, Some(&e.to_string()), 0).await?;
+                return Err(AppError::InternalServerError);
+            }
+        };
+        AiUsageService::record(db, ai, user_id, kind, batch.tokens_used).await?;
+
+        // The job may have been cancelled while the provider was working
+        if Self::is_cancel_requested(db, job_id).await? {
+            Self::finish_job(db, job_id, "cancelled", None, None, batch.tokens_used).await?;
+            return Err(AppError::BadRequest("Generation was cancelled".to_string()));
+        }
+
+        let min_confidence = ai.content_generation.min_confidence_score;
+        let mut cards = Vec::with_capacity(batch.cards.len());
+        let mut discarded = 0;
+        for generated in &batch.cards {
+            match Self::store_card(db, job_id, deck_id, generated, content, min_confidence).await? {
+                Some(card) => cards.push(card),
+                None => discarded += 1,
+            }
+        }
+
+        let output = json!({
+            "card_count": cards.len(),
+            "discarded_low_confidence": discarded,
+            "average_confidence": Self::average_confidence(&cards),
+        });
+        Self::finish_job(db, job_id, "completed", Some(output), None, batch.tokens_used).await?;
+
+        Ok(GeneratedCardsResult {
+            job_id,
+            provider: kind.as_str().to_string(),
+            model: batch.model,
+            tokens_used: batch.tokens_used,
+            cards,
+        })
+    }
+
+    /// Queue a generation job whose cards are produced by `stream_cards`.
+    /// The content is kept on the job only until it completes.
+    pub async fn create_stream_job(
+        db: &PgPool,
+        ai: &AiConfig,
+        user_id: Uuid,
+        deck_id: Option<Uuid>,
+        content: &str,
+        options: FlashcardGenerationOptions,
+        provider: Option<AiProviderKind>,
+    ) -> Result<Uuid> {
+        let (kind, model) = resolve_provider(ai, provider)?;
+        AiUsageService::check_quota(db, ai, user_id).await?;
+        let options = Self::prepare_options(db, ai, deck_id, options).await?;
+        let metadata = json!({
+            "options": options,
+            "content_length": content.chars().count(),
+            "stream": true,
+            "content": content,
+        });
+
+        Self::create_job(db, user_id, deck_id, "pending", metadata, kind, &model).await
+    }
+
+    /// Start a queued streaming job. Cards are stored and sent on the
+    /// returned channel as the model produces them; generation continues to
+    /// completion even if the receiver goes away.
+    pub async fn stream_cards(
+        db: &PgPool,
+        ai: &AiConfig,
+        breaker: &Arc<CircuitBreaker>,
+        user_id: Uuid,
+        job_id: Uuid,
+    ) -> Result<mpsc::Receiver<GenerationEvent>> {
+        // Claim the job so it can only be streamed once
+        let claimed = sqlx::query_as::<_, (Option<Uuid>, JsonValue, Option<String>)>(
+            r#"
+            UPDATE ai_content_generation_jobs
+            SET status = 'processing', started_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND status = 'pending'
+                AND (input_metadata->>'stream')::BOOLEAN
+            RETURNING deck_id, input_metadata, provider
+            "#,
+        )
+        .bind(job_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        let Some((deck_id, metadata, provider)) = claimed else {
+            // Distinguish a job that has already run from one that is not theirs
+            let exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM ai_content_generation_jobs WHERE id = $1 AND user_id = $2)",
+            )
+            .bind(job_id)
+            .bind(user_id)
+            .fetch_one(db)
+            .await?;
+
+            return Err(if exists {
+                AppError::BadRequest("Job is not a pending streaming job".to_string())
+            } else {
+                AppError::NotFound("Resource not found".to_string())
+            });
+        };
+
+        let content = metadata["content"].as_str().unwrap_or_default().to_string();
+        let options: FlashcardGenerationOptions =
+            serde_json::from_value(metadata["options"].clone())?;
+        // Stream with the provider the job was queued for
+        let provider = provider.and_then(|p| p.parse::<AiProviderKind>().ok());
+
+        let (sender, receiver) = mpsc::channel(32);
+        let db = db.clone();
+        let ai = ai.clone();
+        let breaker = breaker.clone();
+        tokio::spawn(async move {
+            let event = match Self::run_stream(&db, &ai, &breaker, provider, user_id, job_id, deck_id, &content, &options, &sender).await {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::error!("Streaming generation job {} failed: {}", job_id, e);
+                    if let Err(e) = Self::finish_job(&db, job_id, "failed", None, Some(&e.to_string()), 0).await {
+                        tracing::error!("Failed to record outcome of job {}: {}", job_id, e);
+                    }
+                    GenerationEvent::Failed("Card generation failed".to_string())
+                }
+            };
+            let _ = sender.send(event).await;
+        });
+
+        Ok(receiver)
+    }
+
+    /// Cards of one of the user's generation jobs; pending ones only unless
+    /// reviewed cards are requested
+    pub async fn list_generated_cards(
+        db: &PgPool,
+        user_id: Uuid,
+        query: &GeneratedCardsQuery,
+    ) -> Result<Vec<AiGeneratedCard>> {
+        let cards = sqlx::query_as::<_, AiGeneratedCard>(
+            r#"
+            SELECT g.*
+            FROM ai_generated_cards g
+            JOIN ai_content_generation_jobs j ON j.id = g.job_id
+            WHERE g.job_id = $1 AND j.user_id = $2
+                AND ($3 OR (NOT g.approved AND g.rejected_at IS NULL))
+            ORDER BY g.created_at, g.id
+            "#,
+        )
+        .bind(query.job_id)
+        .bind(user_id)
+        .bind(query.include_reviewed.unwrap_or(false))
+        .fetch_all(db)
+        .await?;
+
+        Ok(cards)
+    }
+
+    /// Copy pending generated cards into a deck and mark them approved
+    pub async fn approve_cards(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: ApproveGeneratedCardsDto,
+    ) -> Result<Vec<Card>> {
+        SharingService::require_deck_role(db, dto.deck_id, user_id, DeckRole::Editor).await?;
+        EncryptionService::ensure_plaintext(db, dto.deck_id).await?;
+
+        let mut tx = db.begin().await?;
+
+        let pending = sqlx::query_as::<_, AiGeneratedCard>(
+            r#"
+            SELECT g.*
+            FROM ai_generated_cards g
+            JOIN ai_content_generation_jobs j ON j.id = g.job_id
+            WHERE g.id = ANY($1) AND j.user_id = $2
+                AND NOT g.approved AND g.rejected_at IS NULL
+            FOR UPDATE OF g
+            "#,
+        )
+        .bind(&dto.card_ids)
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        Self::require_all_pending(&dto.card_ids, pending.len())?;
+
+        // Keep the order the client submitted
+        let mut ordered = Vec::with_capacity(pending.len());
+        for id in &dto.card_ids {
+            if let Some(card) = pending.iter().find(|c| c.id == *id) {
+                ordered.push(card);
+            }
+        }
+
+        let count = ordered.len() as i32;
+        let start = if dto.auto_position.unwrap_or(true) {
+            sqlx::query_scalar::<_, i32>(
+                "SELECT COALESCE(MAX(position), -1) + 1 FROM cards WHERE deck_id = $1",
+            )
+            .bind(dto.deck_id)
+            .fetch_one(&mut *tx)
+            .await?
+        } else {
+            sqlx::query("UPDATE cards SET position = position + $2 WHERE deck_id = $1")
+                .bind(dto.deck_id)
+                .bind(count)
+                .execute(&mut *tx)
+                .await?;
+            0
+        };
+
+        let mut created = Vec::with_capacity(ordered.len());
+        for (offset, generated) in ordered.into_iter().enumerate() {
+            let back = match &generated.explanation {
+                Some(explanation) if !explanation.trim().is_empty() => {
+                    format!("{}\n\n{}", generated.back, explanation.trim())
+                }
+                _ => generated.back.clone(),
+            };
+
+            let card = sqlx::query_as::<_, Card>(
+                r#"
+                INSERT INTO cards (deck_id, front, back, position)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id, deck_id, front, back, position, created_at, updated_at
+                "#,
+            )
+            .bind(dto.deck_id)
+            .bind(&generated.front)
+            .bind(&back)
+            .bind(start + offset as i32)
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(card);
+        }
+
+        sqlx::query("UPDATE ai_generated_cards SET approved = true, deck_id = $2 WHERE id = ANY($1)")
+            .bind(&dto.card_ids)
+            .bind(dto.deck_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(created)
+    }
+
+    /// Mark pending generated cards as rejected; returns how many were rejected
+    pub async fn reject_cards(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: RejectGeneratedCardsDto,
+    ) -> Result<u64> {
+        let mut tx = db.begin().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE ai_generated_cards g
+            SET rejected_at = NOW()
+            FROM ai_content_generation_jobs j
+            WHERE j.id = g.job_id AND g.id = ANY($1) AND j.user_id = $2
+                AND NOT g.approved AND g.rejected_at IS NULL
+            "#,
+        )
+        .bind(&dto.card_ids)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // All or nothing, matching approval
+        Self::require_all_pending(&dto.card_ids, result.rows_affected() as usize)?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Score a generated card between 0 and 1, starting from the model's own
+    /// estimate and adjusting for whether its cited source exists in the input
+    pub fn confidence_score(card: &GeneratedFlashcard, source_text: &str) -> f32 {
+        let mut score = card.confidence.unwrap_or(DEFAULT_CONFIDENCE).clamp(0.0, 1.0);
+
+        match card.source.as_deref().map(normalize) {
+            Some(quote) if !quote.is_empty() && normalize(source_text).contains(&quote) => {
+                score = (score + 0.1).min(1.0);
+            }
+            // A missing or invented quote suggests the answer is not grounded
+            _ => score *= 0.8,
+        }
+
+        if card.front.trim().chars().count() < 5 || card.back.trim().is_empty() {
+            score *= 0.5;
+        }
+
+        (score * 100.0).round() / 100.0
+    }
+
+    // Helper methods
+
+    async fn run_stream(
+        db: &PgPool,
+        ai: &AiConfig,
+        breaker: &Arc<CircuitBreaker>,
+        provider: Option<AiProviderKind>,
+        user_id: Uuid,
+        job_id: Uuid,
+        deck_id: Option<Uuid>,
+        content: &str,
+        options: &FlashcardGenerationOptions,
+        sender: &mpsc::Sender<GenerationEvent>,
+    ) -> Result<GenerationEvent> {
+        let mut client = provider_for(ai, breaker, provider)?;
+        let mut stream = client
+            .stream_flashcards(content, options)
+            .await
+            .map_err(Self::provider_error)?;
+
+        let min_confidence = ai.content_generation.min_confidence_score;
+        let max_cards = options.max_cards.unwrap_or(10).max(1) as usize;
+        let mut cards = Vec::new();
+        let mut discarded = 0;
+
+        while let Some(generated) = stream.next_card().await.map_err(Self::provider_error)? {
+            if Self::is_cancel_requested(db, job_id).await? {
+                AiUsageService::record(db, ai, user_id, client.kind(), stream.tokens_used()).await?;
+                Self::finish_job(db, job_id, "cancelled", None, None, stream.tokens_used()).await?;
+                return Ok(GenerationEvent::Cancelled);
+            }
+
+            match Self::store_card(db, job_id, deck_id, &generated, content, min_confidence).await? {
+                Some(card) => {
+                    // The receiver may have disconnected; keep generating regardless
+                    let _ = sender.send(GenerationEvent::Card(card.clone())).await;
+                    cards.push(card);
+                }
+                None => discarded += 1,
+            }
+
+            if cards.len() + discarded >= max_cards {
+                break;
+            }
+        }
+
+        let tokens_used = stream.tokens_used();
+        AiUsageService::record(db, ai, user_id, client.kind(), tokens_used).await?;
+        let output = json!({
+            "card_count": cards.len(),
+            "discarded_low_confidence": discarded,
+            "average_confidence": Self::average_confidence(&cards),
+        });
+        Self::finish_job(db, job_id, "completed", Some(output), None, tokens_used).await?;
+
+        Ok(GenerationEvent::Completed(GenerationSummary {
+            job_id,
+            provider: client.kind().as_str().to_string(),
+            model: stream.model().to_string(),
+            tokens_used,
+            card_count: cards.len(),
+            discarded_low_confidence: discarded,
+        }))
+    }
+
+    fn provider_error(e: anyhow::Error) -> AppError {
+        tracing::error!("AI provider error: {}", e);
+        AppError::InternalServerError
+    }
+
+    // Clamp the card count and write cards in the target deck's languages
+    async fn prepare_options(
+        db: &PgPool,
+        ai: &AiConfig,
+        deck_id: Option<Uuid>,
+        options: FlashcardGenerationOptions,
+    ) -> Result<FlashcardGenerationOptions> {
+        let max_cards = ai.content_generation.max_cards_per_batch.max(1);
+        let languages = match deck_id {
+            Some(deck_id) => DeckLanguages::for_deck(db, deck_id).await?,
+            None => options.languages,
+        };
+        Ok(FlashcardGenerationOptions {
+            max_cards: Some(options.max_cards.unwrap_or(10).clamp(1, max_cards)),
+            languages,
+            ..options
+        })
+    }
+
+    async fn create_job(
+        db: &PgPool,
+        user_id: Uuid,
+        deck_id: Option<Uuid>,
+        status: &str,
+        metadata: JsonValue,
+        provider: AiProviderKind,
+        model: &str,
+    ) -> Result<Uuid> {
+        let job_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO ai_content_generation_jobs
+                (user_id, deck_id, job_type, status, input_metadata, provider, model_name, started_at)
+            VALUES ($1, $2, 'generate_questions', $3, $4, $5, $6,
+                    CASE WHEN $3 = 'processing' THEN NOW() END)
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(deck_id)
+        .bind(status)
+        .bind(metadata)
+        .bind(provider.as_str())
+        .bind(model)
+        .fetch_one(db)
+        .await?;
+
+        Ok(job_id)
+    }
+
+    /// Score and store one generated card; `None` if its confidence is too low
+    async fn store_card(
+        db: &PgPool,
+        job_id: Uuid,
+        deck_id: Option<Uuid>,
+        generated: &GeneratedFlashcard,
+        content: &str,
+        min_confidence: f32,
+    ) -> Result<Option<AiGeneratedCard>> {
+        let confidence = Self::confidence_score(generated, content);
+        if confidence < min_confidence {
+            return Ok(None);
+        }
+
+        let card = sqlx::query_as::<_, AiGeneratedCard>(
+            r#"
+            INSERT INTO ai_generated_cards
+                (job_id, deck_id, front, back, explanation, tags, difficulty_estimate,
+                 confidence_score, source_context)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#,
+        )
+        .bind(job_id)
+        .bind(deck_id)
+        .bind(generated.front.trim())
+        .bind(generated.back.trim())
+        .bind(&generated.explanation)
+        .bind(&generated.tags)
+        .bind(generated.difficulty.map(|d| d.clamp(1, 5)))
+        .bind(confidence)
+        .bind(&generated.source)
+        .fetch_one(db)
+        .await?;
+
+        Ok(Some(card))
+    }
+
+    async fn is_cancel_requested(db: &PgPool, job_id: Uuid) -> Result<bool> {
+        let cancelled = sqlx::query_scalar::<_, bool>(
+            "SELECT cancel_requested FROM ai_content_generation_jobs WHERE id = $1",
+        )
+        .bind(job_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(cancelled)
+    }
+
+    fn require_all_pending(requested: &[Uuid], found: usize) -> Result<()> {
+        let unique: std::collections::HashSet<&Uuid> = requested.iter().collect();
+        if unique.len() != requested.len() {
+            return Err(AppError::ValidationError("Duplicate card in request".to_string()));
+        }
+        if found != requested.len() {
+            return Err(AppError::BadRequest(
+                "Some cards were not found or have already been reviewed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn average_confidence(cards: &[AiGeneratedCard]) -> Option<f32> {
+        let scores: Vec<f32> = cards.iter().filter_map(|c| c.confidence_score).collect();
+        (!scores.is_empty()).then(|| scores.iter().sum::<f32>() / scores.len() as f32)
+    }
+
+    async fn finish_job(
+        db: &PgPool,
+        job_id: Uuid,
+        status: &str,
+        output: Option<JsonValue>,
+        error_message: Option<&str>,
+        tokens_used: i32,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE ai_content_generation_jobs
+            SET status = $2, output_data = $3, error_message = $4, tokens_used = $5,
+                completed_at = NOW(),
+                -- Only failed and cancelled jobs keep their input, for retries
+                input_metadata = CASE WHEN $2 = 'completed'
+                    THEN input_metadata - 'content' ELSE input_metadata END
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(status)
+        .bind(output)
+        .bind(error_message)
+        .bind(tokens_used)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Lowercase and collapse whitespace so quotes match across line breaks
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
@@ -0,0 +1,111 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::ai::{AiPrivacySettings, UpdatePrivacySettingsDto},
+    utils::{AppError, Result},
+};
+
+/// Per-user consent for AI features. Users without a stored row get the
+/// column defaults.
+pub struct AiPrivacyService;
+
+impl AiPrivacyService {
+    pub async fn get(db: &PgPool, user_id: Uuid) -> Result<AiPrivacySettings> {
+        let settings = sqlx::query_as::<_, AiPrivacySettings>(
+            r#"
+            INSERT INTO ai_privacy_settings (user_id)
+            VALUES ($1)
+            ON CONFLICT (user_id) DO UPDATE SET user_id = EXCLUDED.user_id
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(settings)
+    }
+
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: UpdatePrivacySettingsDto,
+    ) -> Result<AiPrivacySettings> {
+        // Make sure the row exists so the update has something to change
+        Self::get(db, user_id).await?;
+
+        let settings = sqlx::query_as::<_, AiPrivacySettings>(
+            r#"
+            UPDATE ai_privacy_settings
+            SET track_analytics = COALESCE($2, track_analytics),
+                enable_ai_recommendations = COALESCE($3, enable_ai_recommendations),
+                enable_content_generation = COALESCE($4, enable_content_generation),
+                share_anonymous_data = COALESCE($5, share_anonymous_data),
+                personalized_learning = COALESCE($6, personalized_learning),
+                updated_at = NOW()
+            WHERE user_id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(dto.track_analytics)
+        .bind(dto.enable_ai_recommendations)
+        .bind(dto.enable_content_generation)
+        .bind(dto.share_anonymous_data)
+        .bind(dto.personalized_learning)
+        .fetch_one(db)
+        .await?;
+
+        Ok(settings)
+    }
+
+    /// Whether the user allows their content to be sent for generation
+    pub async fn allows_content_generation(db: &PgPool, user_id: Uuid) -> Result<bool> {
+        let allowed = sqlx::query_scalar::<_, bool>(
+            "SELECT enable_content_generation FROM ai_privacy_settings WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .unwrap_or(true);
+
+        Ok(allowed)
+    }
+
+    /// Whether the user wants recommendations drawn from their study history
+    pub async fn allows_recommendations(db: &PgPool, user_id: Uuid) -> Result<bool> {
+        let allowed = sqlx::query_scalar::<_, bool>(
+            "SELECT enable_ai_recommendations FROM ai_privacy_settings WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .unwrap_or(true);
+
+        Ok(allowed)
+    }
+
+    /// Whether the user wants their study habits analyzed
+    pub async fn allows_personalized_learning(db: &PgPool, user_id: Uuid) -> Result<bool> {
+        let allowed = sqlx::query_scalar::<_, bool>(
+            "SELECT personalized_learning FROM ai_privacy_settings WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .unwrap_or(true);
+
+        Ok(allowed)
+    }
+
+    /// Fail unless the user allows their content to be sent for generation
+    pub async fn require_content_generation(db: &PgPool, user_id: Uuid) -> Result<()> {
+        if !Self::allows_content_generation(db, user_id).await? {
+            return Err(AppError::BadRequest(
+                "AI content generation is disabled in your privacy settings".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,709 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{
+    config::AiConfig,
+    models::ai::{
+        AiProviderKind, ExtractedDocument, GeneratedLintIssue, GeneratedQuizQuestion, LeechSuggestion,
+        VertexAiRequest, VertexAiResponse,
+    },
+    services::{
+        circuit_breaker::CircuitBreaker, ollama::OllamaClient, openai::OpenAiClient,
+        language::DeckLanguages,
+        vertex_ai::{ContentStream, VertexAiClient},
+    },
+    utils::AppError,
+};
+
+/// A text generation backend. Providers only have to answer a prompt; the
+/// prompts and response parsing for cards, quizzes, summaries and leech
+/// remediations are shared, so every provider supports every AI feature.
+#[async_trait]
+pub trait AiProvider: Send {
+    fn kind(&self) -> AiProviderKind;
+    fn default_model(&self) -> &str;
+    async fn generate_content(&mut self, request: VertexAiRequest) -> Result<VertexAiResponse>;
+
+    /// Flashcards from text, with the model and token usage of the call
+    async fn generate_flashcards(
+        &mut self,
+        text: &str,
+        options: &FlashcardGenerationOptions,
+    ) -> Result<FlashcardBatch> {
+        let request = flashcard_request(self.default_model(), text, options);
+        let response = self.generate_content(request).await?;
+        let cards = parse_flashcards(&response.text)?;
+
+        Ok(FlashcardBatch {
+            cards,
+            model: response.model,
+            tokens_used: response.tokens_used,
+        })
+    }
+
+    /// Flashcards yielded as they are generated. Providers that cannot stream
+    /// generate the whole batch before the first card is yielded.
+    async fn stream_flashcards(
+        &mut self,
+        text: &str,
+        options: &FlashcardGenerationOptions,
+    ) -> Result<FlashcardStream> {
+        let batch = self.generate_flashcards(text, options).await?;
+        Ok(FlashcardStream::from_batch(batch))
+    }
+
+    /// Flashcards from an extracted document, keeping its outline so cards
+    /// can be grouped by section
+    async fn generate_flashcards_from_document(
+        &mut self,
+        document: &ExtractedDocument,
+        options: &FlashcardGenerationOptions,
+    ) -> Result<FlashcardBatch> {
+        let mut text = String::new();
+        if let Some(title) = &document.title {
+            text.push_str(&format!("Document title: {}\n\n", title));
+        }
+        text.push_str(
+            "The text below is an outline; lines starting with '#' are section headings. \
+             Spread cards across sections and tag each card with its section heading.\n\n",
+        );
+        text.push_str(&document.to_prompt_text());
+
+        self.generate_flashcards(&text, options).await
+    }
+
+    async fn summarize(&mut self, text: &str, max_length: Option<i32>) -> Result<String> {
+        let max_length = max_length.unwrap_or(500);
+
+        let prompt = format!(
+            r#"Provide a concise summary of the following text in approximately {} words.
+            Focus on the main ideas, key concepts, and important details.
+
+            Text:
+            {}
+
+            Summary:"#,
+            max_length, text
+        );
+
+        let request = VertexAiRequest {
+            prompt,
+            model: self.default_model().to_string(),
+            max_tokens: Some(max_length * 2), // Approximate tokens
+            temperature: Some(0.3), // Lower temperature for more focused summaries
+            top_p: Some(0.9),
+            top_k: Some(30),
+        };
+
+        let response = self.generate_content(request).await?;
+        Ok(response.text)
+    }
+
+    async fn extract_concepts(&mut self, text: &str) -> Result<Vec<String>> {
+        let prompt = format!(
+            r#"Extract the key concepts, terms, and topics from the following text.
+            List each concept on a new line, without numbering or bullets.
+            Focus on important nouns, technical terms, and main ideas.
+
+            Text:
+            {}
+
+            Key concepts:"#,
+            text
+        );
+
+        let request = VertexAiRequest {
+            prompt,
+            model: self.default_model().to_string(),
+            max_tokens: Some(500),
+            temperature: Some(0.2),
+            top_p: Some(0.9),
+            top_k: Some(20),
+        };
+
+        let response = self.generate_content(request).await?;
+
+        Ok(response.text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.trim().to_string())
+            .collect())
+    }
+
+    /// Rewrites for a card the learner keeps forgetting
+    async fn suggest_leech_remediations(&mut self, front: &str, back: &str) -> Result<RemediationBatch> {
+        let prompt = format!(
+            r#"A learner keeps forgetting the following flashcard.
+            Suggest ways to reformulate it so it is easier to remember.
+
+            Provide exactly three suggestions, one per strategy:
+            - "simplify": reword the card with simpler, more specific wording
+            - "split": break the card into two or three smaller cards
+            - "mnemonic": keep the card but add a memorable mnemonic
+
+            Format the output as JSON array with objects containing:
+            - "strategy": one of "simplify", "split", "mnemonic"
+            - "cards": array of objects with "front" and "back"
+            - "mnemonic": the mnemonic (only for the "mnemonic" strategy)
+            - "rationale": one sentence on why this should help
+
+            Front: {}
+            Back: {}
+
+            Suggestions as a valid JSON array:"#,
+            front, back
+        );
+
+        let request = VertexAiRequest {
+            prompt,
+            model: self.default_model().to_string(),
+            max_tokens: Some(1024),
+            temperature: Some(0.5),
+            top_p: Some(0.9),
+            top_k: Some(40),
+        };
+
+        let response = self.generate_content(request).await?;
+        let suggestions: Vec<LeechSuggestion> = serde_json::from_str(json_array(&response.text))?;
+
+        Ok(RemediationBatch {
+            suggestions: suggestions
+                .into_iter()
+                .filter(|s| matches!(s.strategy.as_str(), "simplify" | "split" | "mnemonic"))
+                .filter(|s| !s.cards.is_empty())
+                .collect(),
+            model: response.model,
+            tokens_used: response.tokens_used,
+        })
+    }
+
+    /// A short explanation of a card's answer for a learner, addressing
+    /// their wrong answer when there is one
+    async fn explain_card(
+        &mut self,
+        front: &str,
+        back: &str,
+        wrong_answer: Option<&str>,
+        languages: &DeckLanguages,
+    ) -> Result<VertexAiResponse> {
+        let mistake = match wrong_answer {
+            Some(answer) => format!(
+                "\n            The learner answered: {}\n            Explain briefly why that answer is wrong.\n",
+                answer
+            ),
+            None => String::new(),
+        };
+
+        let prompt = format!(
+            r#"A learner is studying the following flashcard and wants to understand it better.
+            Explain why the answer is correct in at most 120 words of plain text.
+            Give the underlying idea and, if it helps, one example. Do not repeat the question.
+            {}
+
+            Front: {}
+            Back: {}
+            {}
+            Explanation:"#,
+            languages.explanation_instruction(),
+            front,
+            back,
+            mistake
+        );
+
+        let request = VertexAiRequest {
+            prompt,
+            model: self.default_model().to_string(),
+            max_tokens: Some(400),
+            temperature: Some(0.3),
+            top_p: Some(0.9),
+            top_k: Some(30),
+        };
+
+        let mut response = self.generate_content(request).await?;
+        response.text = response.text.trim().to_string();
+        Ok(response)
+    }
+
+    /// Multiple-choice questions about a deck's cards. Distractors come from
+    /// the other cards so they are plausible within the subject.
+    async fn generate_quiz_questions(
+        &mut self,
+        cards: &[(String, String)],
+        count: usize,
+        languages: &DeckLanguages,
+    ) -> Result<QuizBatch> {
+        let numbered: String = cards
+            .iter()
+            .enumerate()
+            .map(|(i, (front, back))| format!("{}. Front: {}\n   Back: {}\n", i + 1, front, back))
+            .collect();
+
+        let prompt = format!(
+            r#"Write {} multiple-choice exam questions based on the flashcards below.
+
+            Requirements:
+            1. Each question tests one card; do not copy the card front verbatim
+            2. Give exactly four options with one correct answer
+            3. Draw the wrong options from the answers of other cards in this deck
+               so they are plausible, and never make them also correct
+            4. Cover as many different cards as possible
+            {}
+
+            Format the output as JSON array with objects containing:
+            - "card": the number of the card the question tests
+            - "question": the question text
+            - "options": array of the four answer options
+            - "answer": index (0-3) of the correct option
+            - "explanation": one sentence on why the answer is correct
+
+            Flashcards:
+            {}
+
+            Generate exactly {} questions as a valid JSON array:"#,
+            count,
+            languages.card_instruction(),
+            numbered,
+            count
+        );
+
+        let request = VertexAiRequest {
+            prompt,
+            model: self.default_model().to_string(),
+            max_tokens: Some(4096),
+            temperature: Some(0.6),
+            top_p: Some(0.95),
+            top_k: Some(40),
+        };
+
+        let response = self.generate_content(request).await?;
+        let questions: Vec<GeneratedQuizQuestion> = serde_json::from_str(json_array(&response.text))?;
+
+        Ok(QuizBatch {
+            questions,
+            model: response.model,
+            tokens_used: response.tokens_used,
+        })
+    }
+
+    /// Quality problems in a deck's cards, with rewrites where the model
+    /// has one
+    async fn review_cards(&mut self, cards: &[(String, String)], languages: &DeckLanguages) -> Result<LintBatch> {
+        let numbered: String = cards
+            .iter()
+            .enumerate()
+            .map(|(i, (front, back))| format!("{}. Front: {}\n   Back: {}\n", i + 1, front, back))
+            .collect();
+
+        let prompt = format!(
+            r#"Review the flashcards below for quality problems. Only report real problems;
+            most cards should have none.
+
+            Problem kinds:
+            - "answer_leak": the front gives away the answer
+            - "long_answer": the back holds several facts and should be shorter or split
+            - "duplicate": the card asks the same thing as another card
+            - "ambiguous": the front allows more than one reasonable answer
+            {}
+
+            Format the output as JSON array with objects containing:
+            - "card": the number of the card
+            - "kind": one of the problem kinds above
+            - "message": one sentence describing the problem
+            - "front": an improved front (only if the front should change)
+            - "back": an improved back (only if the back should change)
+
+            Flashcards:
+            {}
+
+            Problems as a valid JSON array (empty if there are none):"#,
+            languages.card_instruction(),
+            numbered
+        );
+
+        let request = VertexAiRequest {
+            prompt,
+            model: self.default_model().to_string(),
+            max_tokens: Some(4096),
+            temperature: Some(0.2),
+            top_p: Some(0.9),
+            top_k: Some(40),
+        };
+
+        let response = self.generate_content(request).await?;
+        let issues: Vec<GeneratedLintIssue> = serde_json::from_str(json_array(&response.text))?;
+
+        Ok(LintBatch {
+            issues,
+            model: response.model,
+            tokens_used: response.tokens_used,
+        })
+    }
+}
+
+/// The provider a request asked for, or the configured default, with the
+/// model it uses. Fails when that provider has not been configured.
+pub fn resolve_provider(
+    ai: &AiConfig,
+    requested: Option<AiProviderKind>,
+) -> crate::utils::Result<(AiProviderKind, String)> {
+    let kind = match requested {
+        Some(kind) => kind,
+        None => ai.provider.parse().map_err(|e: String| {
+            tracing::error!("Invalid AI_PROVIDER: {}", e);
+            AppError::InternalServerError
+        })?,
+    };
+
+    let model = match kind {
+        AiProviderKind::VertexAi => &ai.vertex_ai.default_model,
+        AiProviderKind::OpenAi => &ai.openai.as_ref().ok_or_else(not_configured(kind))?.model,
+        AiProviderKind::Ollama => &ai.ollama.as_ref().ok_or_else(not_configured(kind))?.model,
+    };
+
+    Ok((kind, model.clone()))
+}
+
+/// Client for the provider `resolve_provider` picks
+pub fn provider_for(
+    ai: &AiConfig,
+    breaker: &Arc<CircuitBreaker>,
+    requested: Option<AiProviderKind>,
+) -> crate::utils::Result<Box<dyn AiProvider>> {
+    let (kind, _) = resolve_provider(ai, requested)?;
+
+    let provider: Box<dyn AiProvider> = match kind {
+        AiProviderKind::VertexAi => Box::new(VertexAiClient::new(ai, breaker.clone())),
+        AiProviderKind::OpenAi => Box::new(OpenAiClient::new(ai.openai.clone().ok_or_else(not_configured(kind))?)),
+        AiProviderKind::Ollama => Box::new(OllamaClient::new(ai.ollama.clone().ok_or_else(not_configured(kind))?)),
+    };
+
+    Ok(provider)
+}
+
+fn not_configured(kind: AiProviderKind) -> impl FnOnce() -> AppError {
+    move || AppError::BadRequest(format!("AI provider '{}' is not configured", kind.as_str()))
+}
+
+// Helper structures for flashcard generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashcardGenerationOptions {
+    pub max_cards: Option<i32>,
+    pub difficulty: Option<String>,
+    pub format: Option<String>,
+    pub include_explanations: Option<bool>,
+    /// Languages of the target deck; filled in from the deck
+    #[serde(default)]
+    pub languages: DeckLanguages,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedFlashcard {
+    pub front: String,
+    pub back: String,
+    pub explanation: Option<String>,
+    pub difficulty: Option<i32>,
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FlashcardBatch {
+    pub cards: Vec<GeneratedFlashcard>,
+    pub model: String,
+    pub tokens_used: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuizBatch {
+    pub questions: Vec<GeneratedQuizQuestion>,
+    pub model: String,
+    pub tokens_used: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct LintBatch {
+    pub issues: Vec<GeneratedLintIssue>,
+    pub model: String,
+    pub tokens_used: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RemediationBatch {
+    pub suggestions: Vec<LeechSuggestion>,
+    pub model: String,
+    pub tokens_used: i32,
+}
+
+pub(crate) fn flashcard_request(model: &str, text: &str, options: &FlashcardGenerationOptions) -> VertexAiRequest {
+    VertexAiRequest {
+        prompt: build_flashcard_prompt(text, options),
+        model: model.to_string(),
+        max_tokens: Some(2048),
+        temperature: Some(0.7),
+        top_p: Some(0.95),
+        top_k: Some(40),
+    }
+}
+
+// Build prompt for flashcard generation
+fn build_flashcard_prompt(text: &str, options: &FlashcardGenerationOptions) -> String {
+    let max_cards = options.max_cards.unwrap_or(10);
+    let difficulty = options.difficulty.as_deref().unwrap_or("medium");
+    let format = options.format.as_deref().unwrap_or("question_answer");
+
+    format!(
+        r#"Generate {} flashcards from the following text.
+        Difficulty level: {}
+        Format: {}
+
+        Requirements:
+        1. Each flashcard should test understanding, not just memorization
+        2. Include a mix of factual and conceptual questions
+        3. Make the answers clear and concise
+        4. If the text contains examples, use them in the flashcards
+        {}
+
+        Format the output as JSON array with objects containing:
+        - "front": the question or prompt
+        - "back": the answer
+        - "explanation": optional additional context (only if helpful)
+        - "difficulty": estimated difficulty (1-5)
+        - "tags": relevant topic tags as array
+        - "source": the short passage from the text that supports the answer, quoted verbatim
+        - "confidence": how certain you are that the answer is correct and fully supported by the text (0.0-1.0)
+
+        Text to process:
+        {}
+
+        Generate exactly {} flashcards as a valid JSON array:"#,
+        max_cards,
+        difficulty,
+        format,
+        options.languages.card_instruction(),
+        text,
+        max_cards
+    )
+}
+
+/// The outermost JSON array in a model response, which may wrap it in prose
+/// or a code fence
+fn json_array(response: &str) -> &str {
+    let json_start = response.find('[').unwrap_or(0);
+    let json_end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
+    response.get(json_start..json_end).unwrap_or(response)
+}
+
+// Parse flashcards from AI response
+pub fn parse_flashcards(response: &str) -> Result<Vec<GeneratedFlashcard>> {
+    match serde_json::from_str::<Vec<GeneratedFlashcard>>(json_array(response)) {
+        Ok(flashcards) => Ok(flashcards),
+        Err(e) => {
+            warn!("Failed to parse flashcards JSON: {}", e);
+            // Try to extract flashcards manually as fallback
+            parse_flashcards_fallback(response)
+        }
+    }
+}
+
+// Fallback parser for flashcards
+fn parse_flashcards_fallback(response: &str) -> Result<Vec<GeneratedFlashcard>> {
+    let mut flashcards = Vec::new();
+
+    // Simple pattern matching for Q&A format
+    let lines: Vec<&str> = response.lines().collect();
+    let mut i = 0;
+
+    while i < lines.len() {
+        if lines[i].contains("Front:") || lines[i].contains("Question:") {
+            let front = lines[i]
+                .replace("Front:", "")
+                .replace("Question:", "")
+                .trim()
+                .to_string();
+
+            if i + 1 < lines.len() && (lines[i + 1].contains("Back:") || lines[i + 1].contains("Answer:")) {
+                let back = lines[i + 1]
+                    .replace("Back:", "")
+                    .replace("Answer:", "")
+                    .trim()
+                    .to_string();
+
+                flashcards.push(GeneratedFlashcard {
+                    front,
+                    back,
+                    explanation: None,
+                    difficulty: Some(3),
+                    tags: vec![],
+                    source: None,
+                    confidence: None,
+                });
+
+                i += 2;
+            } else {
+                i += 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    if flashcards.is_empty() {
+        Err(anyhow::anyhow!("Could not parse any flashcards from response"))
+    } else {
+        Ok(flashcards)
+    }
+}
+
+/// Flashcards parsed from a streamed response as they complete
+pub struct FlashcardStream {
+    content: Option<ContentStream>, // None when the cards were generated in one go
+    model: String,
+    tokens_used: i32,
+    parser: FlashcardStreamParser,
+    text: String,
+    pending: VecDeque<GeneratedFlashcard>,
+    emitted: usize,
+}
+
+impl FlashcardStream {
+    pub(crate) fn new(content: ContentStream) -> Self {
+        Self {
+            model: content.model().to_string(),
+            content: Some(content),
+            tokens_used: 0,
+            parser: FlashcardStreamParser::default(),
+            text: String::new(),
+            pending: VecDeque::new(),
+            emitted: 0,
+        }
+    }
+
+    /// A stream over an already generated batch, for providers that cannot
+    /// stream
+    pub fn from_batch(batch: FlashcardBatch) -> Self {
+        Self {
+            content: None,
+            model: batch.model,
+            tokens_used: batch.tokens_used,
+            parser: FlashcardStreamParser::default(),
+            text: String::new(),
+            pending: batch.cards.into(),
+            emitted: 0,
+        }
+    }
+
+    pub async fn next_card(&mut self) -> Result<Option<GeneratedFlashcard>> {
+        loop {
+            if let Some(card) = self.pending.pop_front() {
+                self.emitted += 1;
+                return Ok(Some(card));
+            }
+
+            let Some(content) = self.content.as_mut() else {
+                return Ok(None);
+            };
+
+            match content.next_text().await? {
+                Some(text) => {
+                    self.pending.extend(self.parser.push(&text));
+                    self.text.push_str(&text);
+                }
+                None => {
+                    // Output that never formed a JSON array gets the
+                    // non-streaming parser's fallback treatment
+                    if self.emitted == 0 && !self.text.is_empty() {
+                        let text = std::mem::take(&mut self.text);
+                        self.pending.extend(parse_flashcards(&text)?);
+                        if !self.pending.is_empty() {
+                            continue;
+                        }
+                    }
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn tokens_used(&self) -> i32 {
+        self.content.as_ref().map_or(self.tokens_used, |c| c.tokens_used())
+    }
+}
+
+/// Incremental parser for a JSON array of flashcard objects. Text can be
+/// pushed in arbitrary pieces; each object is returned once it is complete.
+#[derive(Debug, Default)]
+pub struct FlashcardStreamParser {
+    in_array: bool,
+    finished: bool,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    current: String,
+}
+
+impl FlashcardStreamParser {
+    pub fn push(&mut self, text: &str) -> Vec<GeneratedFlashcard> {
+        let mut cards = Vec::new();
+
+        for c in text.chars() {
+            if self.finished {
+                break;
+            }
+            if !self.in_array {
+                self.in_array = c == '[';
+                continue;
+            }
+            if self.depth == 0 {
+                match c {
+                    '{' => {
+                        self.depth = 1;
+                        self.current.clear();
+                        self.current.push(c);
+                    }
+                    ']' => self.finished = true,
+                    _ => {} // Separators between objects
+                }
+                continue;
+            }
+
+            self.current.push(c);
+            if self.in_string {
+                match c {
+                    _ if self.escaped => self.escaped = false,
+                    '\\' => self.escaped = true,
+                    '"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match c {
+                '"' => self.in_string = true,
+                '{' | '[' => self.depth += 1,
+                '}' | ']' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        match serde_json::from_str::<GeneratedFlashcard>(&self.current) {
+                            Ok(card) => cards.push(card),
+                            Err(e) => warn!("Skipping unparseable streamed flashcard: {}", e),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        cards
+    }
+}
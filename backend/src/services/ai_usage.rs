@@ -0,0 +1,155 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::AiConfig,
+    models::ai::{AiProviderKind, AiUsageDay, AiUsageQuery, AiUsageSummary},
+    utils::{AppError, Result},
+};
+
+const DEFAULT_HISTORY_DAYS: i64 = 30;
+const MAX_HISTORY_DAYS: i64 = 366;
+
+/// Token accounting for AI features. Quotas are checked before a provider
+/// is called and usage is recorded after it answers, so requests running
+/// concurrently can overshoot a quota by at most their own usage.
+pub struct AiUsageService;
+
+impl AiUsageService {
+    /// Refuse once the user has used up today's or this month's tokens
+    pub async fn check_quota(db: &PgPool, ai: &AiConfig, user_id: Uuid) -> Result<()> {
+        let limits = &ai.usage;
+        if limits.daily_token_limit <= 0 && limits.monthly_token_limit <= 0 {
+            return Ok(());
+        }
+
+        let (today, month, _) = Self::totals(db, user_id).await?;
+        let now = Utc::now();
+
+        if limits.daily_token_limit > 0 && today >= limits.daily_token_limit {
+            return Err(AppError::AiQuotaExceeded {
+                message: format!(
+                    "Daily AI quota of {} tokens used up; it resets at midnight UTC",
+                    limits.daily_token_limit
+                ),
+                retry_after_seconds: seconds_until(next_day(now), now),
+            });
+        }
+        if limits.monthly_token_limit > 0 && month >= limits.monthly_token_limit {
+            return Err(AppError::AiQuotaExceeded {
+                message: format!(
+                    "Monthly AI quota of {} tokens used up; it resets on the 1st (UTC)",
+                    limits.monthly_token_limit
+                ),
+                retry_after_seconds: seconds_until(next_month(now), now),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Add one provider call to the user's usage for today
+    pub async fn record(
+        db: &PgPool,
+        ai: &AiConfig,
+        user_id: Uuid,
+        provider: AiProviderKind,
+        tokens_used: i32,
+    ) -> Result<()> {
+        let tokens_used = tokens_used.max(0) as i64;
+        let cost = tokens_used as f64 / 1000.0 * cost_per_1k_tokens(ai, provider);
+
+        sqlx::query(
+            r#"
+            INSERT INTO ai_usage (user_id, usage_date, provider, tokens_used, request_count, cost_usd)
+            VALUES ($1, (NOW() AT TIME ZONE 'UTC')::DATE, $2, $3, 1, $4)
+            ON CONFLICT (user_id, usage_date, provider) DO UPDATE
+            SET tokens_used = ai_usage.tokens_used + EXCLUDED.tokens_used,
+                request_count = ai_usage.request_count + 1,
+                cost_usd = ai_usage.cost_usd + EXCLUDED.cost_usd
+            "#,
+        )
+        .bind(user_id)
+        .bind(provider.as_str())
+        .bind(tokens_used)
+        .bind(cost)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get(db: &PgPool, ai: &AiConfig, user_id: Uuid, query: &AiUsageQuery) -> Result<AiUsageSummary> {
+        let history = query.days.unwrap_or(DEFAULT_HISTORY_DAYS).clamp(1, MAX_HISTORY_DAYS);
+
+        let (tokens_today, tokens_this_month, cost_this_month_usd) = Self::totals(db, user_id).await?;
+
+        let days = sqlx::query_as::<_, AiUsageDay>(
+            r#"
+            SELECT usage_date, provider, tokens_used, request_count, cost_usd
+            FROM ai_usage
+            WHERE user_id = $1 AND usage_date > (NOW() AT TIME ZONE 'UTC')::DATE - $2::INTEGER
+            ORDER BY usage_date DESC, provider
+            "#,
+        )
+        .bind(user_id)
+        .bind(history as i32)
+        .fetch_all(db)
+        .await?;
+
+        Ok(AiUsageSummary {
+            tokens_today,
+            tokens_this_month,
+            cost_this_month_usd,
+            daily_token_limit: Some(ai.usage.daily_token_limit).filter(|l| *l > 0),
+            monthly_token_limit: Some(ai.usage.monthly_token_limit).filter(|l| *l > 0),
+            days,
+        })
+    }
+
+    /// Tokens today, tokens this month and cost this month, in UTC
+    async fn totals(db: &PgPool, user_id: Uuid) -> Result<(i64, i64, f64)> {
+        let totals = sqlx::query_as::<_, (i64, i64, f64)>(
+            r#"
+            SELECT
+                COALESCE(SUM(tokens_used) FILTER (WHERE usage_date = (NOW() AT TIME ZONE 'UTC')::DATE), 0)::BIGINT,
+                COALESCE(SUM(tokens_used), 0)::BIGINT,
+                COALESCE(SUM(cost_usd), 0)::DOUBLE PRECISION
+            FROM ai_usage
+            WHERE user_id = $1
+                AND usage_date >= DATE_TRUNC('month', NOW() AT TIME ZONE 'UTC')::DATE
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(totals)
+    }
+}
+
+fn cost_per_1k_tokens(ai: &AiConfig, provider: AiProviderKind) -> f64 {
+    match provider {
+        AiProviderKind::VertexAi => ai.vertex_ai.cost_per_1k_tokens,
+        AiProviderKind::OpenAi => ai.openai.as_ref().map_or(0.0, |o| o.cost_per_1k_tokens),
+        AiProviderKind::Ollama => 0.0, // Self-hosted
+    }
+}
+
+fn next_day(now: DateTime<Utc>) -> NaiveDate {
+    now.date_naive() + Days::new(1)
+}
+
+fn next_month(now: DateTime<Utc>) -> NaiveDate {
+    let (year, month) = match now.month() {
+        12 => (now.year() + 1, 1),
+        month => (now.year(), month + 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or_else(|| next_day(now))
+}
+
+fn seconds_until(date: NaiveDate, now: DateTime<Utc>) -> u64 {
+    let reset = date.and_time(chrono::NaiveTime::MIN).and_utc();
+    (reset - now).num_seconds().max(1) as u64
+}
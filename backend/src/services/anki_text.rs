@@ -0,0 +1,287 @@
+//! Anki "Notes in Plain Text" files.
+//!
+//! Anki 2.1.55 and later export notes as delimited text, tab-separated by
+//! default, headed by `#key:value` directives: `#separator`, `#html`, a
+//! fixed `#deck` or `#notetype`, `#columns` naming each column, and
+//! `#deck column`, `#notetype column`, `#tags column` and `#guid column`
+//! giving the one-based position of a column that holds that value per row.
+//! Fields are quoted like CSV when they contain the separator, a quote or a
+//! line break. The front and back are the `Front` and `Back` columns when
+//! named, otherwise the first two columns that are not one of the above.
+//! Notes are grouped into one deck per Anki deck.
+
+use ammonia::Builder;
+
+use crate::models::import_export::HtmlHandling;
+
+/// Title for notes that carry no deck name
+pub const DEFAULT_DECK_TITLE: &str = "Imported from Anki";
+
+#[derive(Debug)]
+pub struct AnkiTextDeck {
+    pub title: String,
+    /// Front and back of each note, in file order
+    pub notes: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct ParsedAnkiText {
+    pub decks: Vec<AnkiTextDeck>,
+    /// Rows without both a front and a back
+    pub skipped_rows: usize,
+}
+
+// The directives that affect how rows are read; the others are ignored
+#[derive(Debug, Default)]
+struct Header {
+    separator: Option<u8>,
+    html: Option<bool>,
+    deck: Option<String>,
+    columns: Option<String>,
+    deck_column: Option<usize>,
+    /// Zero-based columns that hold something other than a field
+    meta_columns: Vec<usize>,
+}
+
+impl Header {
+    fn apply(&mut self, directive: &str) -> Result<(), String> {
+        // A `#` line without a colon is a comment
+        let Some((key, raw_value)) = directive.split_once(':') else {
+            return Ok(());
+        };
+        let value = raw_value.trim();
+        let key = key.trim().to_ascii_lowercase();
+        match key.as_str() {
+            "separator" => self.separator = Some(parse_separator(raw_value)?),
+            "html" => self.html = Some(value.eq_ignore_ascii_case("true")),
+            "deck" => self.deck = Some(value.to_string()).filter(|deck| !deck.is_empty()),
+            "columns" => self.columns = Some(raw_value.to_string()),
+            "deck column" | "notetype column" | "tags column" | "guid column" => {
+                let column = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&column| column > 0)
+                    .ok_or_else(|| format!("Invalid column number in #{}: {}", key, value))?;
+                if key == "deck column" {
+                    self.deck_column = Some(column - 1);
+                }
+                self.meta_columns.push(column - 1);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Front and back columns named in `#columns`
+    fn named_fields(&self, separator: u8) -> Option<(usize, usize)> {
+        let names: Vec<&str> = self.columns.as_deref()?.split(separator as char).map(str::trim).collect();
+        let position = |name: &str| names.iter().position(|column| column.eq_ignore_ascii_case(name));
+        position("front").zip(position("back"))
+    }
+}
+
+/// Parse a Notes in Plain Text export. HTML fields are cleaned or reduced
+/// to text according to `html`; fields of a file with `#html:false` are
+/// taken as they are.
+pub fn parse(data: &[u8], html: HtmlHandling) -> Result<ParsedAnkiText, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "Invalid UTF-8 encoding".to_string())?;
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+
+    let mut header = Header::default();
+    let mut body_start = 0;
+    for line in text.split_inclusive('\n') {
+        let Some(directive) = line.strip_prefix('#') else {
+            break;
+        };
+        header.apply(directive.trim_end_matches('\n'))?;
+        body_start += line.len();
+    }
+    let body = &text[body_start..];
+
+    let separator = header.separator.unwrap_or_else(|| detect_separator(body));
+    let named_fields = header.named_fields(separator);
+    let is_html = header.html.unwrap_or(true);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(separator)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(body.as_bytes());
+
+    let mut decks: Vec<AnkiTextDeck> = Vec::new();
+    let mut skipped_rows = 0;
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Invalid Anki text export: {}", e))?;
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let (front, back) = match named_fields {
+            Some((front, back)) => (record.get(front), record.get(back)),
+            None => {
+                let mut fields = record
+                    .iter()
+                    .enumerate()
+                    .filter(|(column, _)| !header.meta_columns.contains(column))
+                    .map(|(_, field)| field);
+                (fields.next(), fields.next())
+            }
+        };
+        let convert = |field: Option<&str>| match (field, is_html) {
+            (None, _) => String::new(),
+            (Some(field), false) => field.trim().to_string(),
+            (Some(field), true) => match html {
+                HtmlHandling::Sanitize => sanitize_html(field),
+                HtmlHandling::Strip => html_to_text(field),
+            },
+        };
+        let (front, back) = (convert(front), convert(back));
+        if front.is_empty() || back.is_empty() {
+            skipped_rows += 1;
+            continue;
+        }
+
+        let title = header
+            .deck_column
+            .and_then(|column| record.get(column))
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .or(header.deck.as_deref())
+            .unwrap_or(DEFAULT_DECK_TITLE);
+        match decks.iter_mut().find(|deck| deck.title == title) {
+            Some(deck) => deck.notes.push((front, back)),
+            None => decks.push(AnkiTextDeck {
+                title: title.to_string(),
+                notes: vec![(front, back)],
+            }),
+        }
+    }
+
+    Ok(ParsedAnkiText { decks, skipped_rows })
+}
+
+/// Clean HTML with the same rules as rendered cards. A field left without
+/// any markup is returned as plain text, with entities decoded.
+pub fn sanitize_html(html: &str) -> String {
+    let clean = Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(html)
+        .to_string();
+    if clean.contains('<') {
+        clean.trim().to_string()
+    } else {
+        decode_entities(&clean).trim().to_string()
+    }
+}
+
+/// Reduce HTML to plain text: line breaks and the ends of blocks become
+/// newlines and other tags are dropped. The HTML is sanitized first, which
+/// removes scripts and styles with their contents and leaves only the
+/// basic entities to decode.
+pub fn html_to_text(html: &str) -> String {
+    let clean = Builder::default().clean(html).to_string();
+    let mut text = String::with_capacity(clean.len());
+    let mut rest = clean.as_str();
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        match tag_name(&rest[start + 1..start + end]).as_str() {
+            "br" | "hr" | "/p" | "/div" | "/li" | "/tr" | "/blockquote" | "/pre" | "/h1" | "/h2" | "/h3"
+            | "/h4" | "/h5" | "/h6" => text.push('\n'),
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    // One blank line at most between paragraphs
+    let decoded = decode_entities(&text).replace('\u{a0}', " ");
+    let mut lines: Vec<&str> = Vec::new();
+    for line in decoded.lines().map(str::trim) {
+        if !(line.is_empty() && lines.last().is_some_and(|last| last.is_empty())) {
+            lines.push(line);
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// Decode named (`&amp;`, `&lt;`, `&gt;`, `&quot;`, `&apos;`, `&nbsp;`)
+/// and numeric character references; anything else is left as written
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| entity_char(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn entity_char(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+// Lowercase name of a tag, with a leading `/` for closing tags
+fn tag_name(tag: &str) -> String {
+    tag.trim_start()
+        .split(|c: char| c.is_whitespace())
+        .next()
+        .unwrap_or("")
+        .trim_end_matches('/')
+        .to_ascii_lowercase()
+}
+
+// Names Anki writes for its separators, or the character itself
+fn parse_separator(value: &str) -> Result<u8, String> {
+    let byte = match value.trim().to_ascii_lowercase().as_str() {
+        "tab" => b'\t',
+        "comma" => b',',
+        "semicolon" => b';',
+        "space" => b' ',
+        "pipe" => b'|',
+        "colon" => b':',
+        _ if value.len() == 1 && value.is_ascii() => value.as_bytes()[0],
+        _ => return Err(format!("Unsupported separator: {}", value)),
+    };
+    Ok(byte)
+}
+
+// Files without a `#separator` line: tab, then semicolon, then comma,
+// whichever the first row contains
+fn detect_separator(body: &str) -> u8 {
+    let first_row = body.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+    [b'\t', b';', b','].into_iter().find(|&separator| first_row.contains(separator as char)).unwrap_or(b'\t')
+}
@@ -0,0 +1,172 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    middleware::rate_limit::{RateLimitBackend, RateLimitConfig},
+    models::{ApiKey, CreateApiKeyDto, CreatedApiKey, UpdateApiKeyDto},
+    services::auth::{hash_token, AuthService},
+    utils::{AppError, Result},
+};
+
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
+// Marks the string as a DeckOracle key, e.g. for secret scanners
+const KEY_PREFIX: &str = "dko_";
+
+// Characters of the key shown in listings, including `KEY_PREFIX`
+const DISPLAY_PREFIX_LENGTH: usize = 12;
+
+const MAX_KEYS_PER_USER: i64 = 25;
+
+/// Long-lived keys for scripts, sent in the `X-Api-Key` header instead of a
+/// bearer token. Keys are stored hashed like refresh tokens and are only
+/// shown once, when created.
+pub struct ApiKeyService;
+
+impl ApiKeyService {
+    pub async fn list(db: &PgPool, user_id: Uuid) -> Result<Vec<ApiKey>> {
+        let keys = sqlx::query_as::<_, ApiKey>(
+            "SELECT * FROM api_keys WHERE user_id = $1 ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(keys)
+    }
+
+    pub async fn create(db: &PgPool, user_id: Uuid, dto: CreateApiKeyDto) -> Result<CreatedApiKey> {
+        if dto.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            return Err(AppError::ValidationError("expires_at must be in the future".to_string()));
+        }
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM api_keys WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(db)
+            .await?;
+        if count >= MAX_KEYS_PER_USER {
+            return Err(AppError::BadRequest(format!(
+                "You can have at most {} API keys",
+                MAX_KEYS_PER_USER
+            )));
+        }
+
+        let key = format!("{}{}", KEY_PREFIX, AuthService::generate_random_token());
+        let api_key = sqlx::query_as::<_, ApiKey>(
+            r#"
+            INSERT INTO api_keys (user_id, name, key_prefix, key_hash, scope, rate_limit_per_minute, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(dto.name.trim())
+        .bind(&key[..DISPLAY_PREFIX_LENGTH])
+        .bind(hash_token(&key))
+        .bind(dto.scope)
+        .bind(dto.rate_limit_per_minute)
+        .bind(dto.expires_at)
+        .fetch_one(db)
+        .await?;
+
+        Ok(CreatedApiKey { api_key, key })
+    }
+
+    pub async fn update(db: &PgPool, user_id: Uuid, key_id: Uuid, dto: UpdateApiKeyDto) -> Result<ApiKey> {
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            UPDATE api_keys
+            SET name = COALESCE($3, name),
+                scope = COALESCE($4, scope),
+                rate_limit_per_minute = COALESCE($5, rate_limit_per_minute),
+                updated_at = NOW()
+            WHERE id = $1 AND user_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(key_id)
+        .bind(user_id)
+        .bind(dto.name.as_deref().map(str::trim))
+        .bind(dto.scope)
+        .bind(dto.rate_limit_per_minute)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("API key not found".to_string()))
+    }
+
+    pub async fn delete(db: &PgPool, user_id: Uuid, key_id: Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM api_keys WHERE id = $1 AND user_id = $2")
+            .bind(key_id)
+            .bind(user_id)
+            .execute(db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("API key not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// The live key matching `key`, marking it used
+    pub async fn authenticate(db: &PgPool, key: &str) -> Result<ApiKey> {
+        if !key.starts_with(KEY_PREFIX) {
+            return Err(AppError::Unauthorized);
+        }
+
+        // last_used_at is only rewritten once a minute so busy keys don't
+        // update their row on every request
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            UPDATE api_keys k
+            SET last_used_at = CASE
+                WHEN k.last_used_at IS NULL OR k.last_used_at < NOW() - INTERVAL '1 minute' THEN NOW()
+                ELSE k.last_used_at
+            END
+            FROM users u
+            WHERE k.key_hash = $1
+                AND (k.expires_at IS NULL OR k.expires_at > NOW())
+                AND u.id = k.user_id
+                AND u.deleted_at IS NULL
+            RETURNING k.*
+            "#,
+        )
+        .bind(hash_token(key))
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::Unauthorized)
+    }
+
+    /// Count a request against the key's per-minute limit. Backend failures
+    /// let the request through, as with the other rate limits.
+    pub async fn check_rate_limit(
+        limits: &dyn RateLimitBackend,
+        config: &Config,
+        api_key: &ApiKey,
+    ) -> Result<()> {
+        if !config.rate_limit.enabled {
+            return Ok(());
+        }
+
+        let limit = RateLimitConfig {
+            max_requests: api_key
+                .rate_limit_per_minute
+                .map(|limit| limit as u32)
+                .unwrap_or(config.rate_limit.api_key_requests_per_minute),
+            window_seconds: 60,
+        };
+        match limits.hit(&format!("api_key:{}", api_key.id), &limit).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(AppError::TooManyRequests {
+                message: "API key rate limit exceeded".to_string(),
+                retry_after_seconds: limit.window_seconds as u64,
+            }),
+            Err(e) => {
+                tracing::warn!("Rate limit backend unavailable, allowing request: {}", e);
+                Ok(())
+            }
+        }
+    }
+}
@@ -2,18 +2,21 @@ use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
+use std::sync::{Arc, OnceLock};
 use uuid::Uuid;
 
 use crate::{
-    config::Config,
+    config::{Config, JwtConfig, LockoutConfig},
     models::{
-        AuthResponse, LoginDto, PasswordResetDto, PasswordResetRequestDto, RefreshToken,
-        RefreshTokenDto, RegisterDto, User, UserResponse,
+        AuthResponse, ClientInfo, LoginAttempt, LoginDto, PasswordResetDto, PasswordResetRequestDto,
+        RefreshToken, RefreshTokenDto, RegisterDto, User, UserResponse,
     },
+    services::sharing::SharingService,
     utils::{AppError, Result},
 };
 
@@ -25,11 +28,22 @@ pub struct Claims {
     pub iat: i64,      // issued at timestamp
 }
 
+// Refresh token revoked because it was exchanged for a new one
+const REVOKED_ROTATED: &str = "rotated";
+
+// Login attempt failure reasons
+const INVALID_CREDENTIALS: &str = "invalid_credentials";
+const LOCKED_OUT: &str = "locked_out";
+
+const DEFAULT_HISTORY_LIMIT: i64 = 20;
+const MAX_HISTORY_LIMIT: i64 = 100;
+
 pub struct AuthService;
 
 impl AuthService {
     pub async fn register(
         db: &PgPool,
+        config: &Config,
         dto: RegisterDto,
     ) -> Result<AuthResponse> {
         // Check if user already exists
@@ -60,10 +74,10 @@ impl AuthService {
         .bind(&dto.display_name)
         .fetch_one(db)
         .await?;
+        SharingService::claim_invites(db, user.id, &user.email).await?;
 
         // Generate tokens
-        let config = Config::from_env().map_err(|e| AppError::ConfigError(e.to_string()))?;
-        let (access_token, refresh_token) = Self::generate_tokens(&user, &config, db).await?;
+        let (access_token, refresh_token) = Self::generate_tokens(&user, config, db).await?;
 
         Ok(AuthResponse {
             access_token,
@@ -76,30 +90,40 @@ impl AuthService {
 
     pub async fn login(
         db: &PgPool,
+        config: &Config,
         dto: LoginDto,
+        client: &ClientInfo,
     ) -> Result<AuthResponse> {
+        // Refuse before checking the password so a locked account cannot be guessed
+        Self::check_lockout(db, config, &dto.email, client).await?;
+
         // Find user
         let user = sqlx::query_as::<_, User>(
-            "SELECT * FROM users WHERE email = $1"
+            "SELECT * FROM users WHERE email = $1 AND deleted_at IS NULL"
         )
         .bind(&dto.email)
         .fetch_optional(db)
-        .await?
-        .ok_or(AppError::Unauthorized)?;
+        .await?;
+
+        let Some(user) = user else {
+            // Unknown emails count towards lockout too, so probing behaves the same
+            Self::record_login_attempt(db, &dto.email, None, client, Some(INVALID_CREDENTIALS)).await?;
+            return Err(AppError::Unauthorized);
+        };
 
         // Verify password
         if !Self::verify_password(&dto.password, &user.password_hash)? {
             // Record failed login attempt
-            Self::record_login_attempt(db, &dto.email, None, false).await?;
+            Self::record_login_attempt(db, &dto.email, Some(user.id), client, Some(INVALID_CREDENTIALS))
+                .await?;
             return Err(AppError::Unauthorized);
         }
 
         // Record successful login attempt
-        Self::record_login_attempt(db, &dto.email, Some(user.id), true).await?;
+        Self::record_login_attempt(db, &dto.email, Some(user.id), client, None).await?;
 
         // Generate tokens
-        let config = Config::from_env().map_err(|e| AppError::ConfigError(e.to_string()))?;
-        let (access_token, refresh_token) = Self::generate_tokens(&user, &config, db).await?;
+        let (access_token, refresh_token) = Self::generate_tokens(&user, config, db).await?;
 
         Ok(AuthResponse {
             access_token,
@@ -110,44 +134,87 @@ impl AuthService {
         })
     }
 
+    /// Recent login attempts against the user's email, newest first
+    pub async fn login_history(db: &PgPool, user_id: Uuid, limit: Option<i64>) -> Result<Vec<LoginAttempt>> {
+        let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+
+        let attempts = sqlx::query_as::<_, LoginAttempt>(
+            r#"
+            SELECT attempted_at, success, host(ip_address) as ip_address, user_agent, failure_reason
+            FROM login_attempts
+            WHERE email = (SELECT email FROM users WHERE id = $1)
+            ORDER BY attempted_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(db)
+        .await?;
+
+        Ok(attempts)
+    }
+
+    /// Exchange a refresh token for new tokens. Every refresh rotates the
+    /// token; presenting a rotated token again means it was copied, so the
+    /// whole family is revoked and every session from that login ends.
     pub async fn refresh_token(
         db: &PgPool,
+        config: &Config,
         dto: RefreshTokenDto,
     ) -> Result<AuthResponse> {
-        // Find and validate refresh token
         let token_record = sqlx::query_as::<_, RefreshToken>(
-            r#"
-            SELECT * FROM refresh_tokens 
-            WHERE token = $1 
-                AND revoked_at IS NULL 
-                AND expires_at > NOW()
-            "#
+            "SELECT * FROM refresh_tokens WHERE token_hash = $1"
         )
-        .bind(&dto.refresh_token)
+        .bind(hash_token(&dto.refresh_token))
         .fetch_optional(db)
         .await?
         .ok_or(AppError::Unauthorized)?;
 
+        if token_record.revoked_reason.as_deref() == Some(REVOKED_ROTATED) {
+            return Err(Self::revoke_family(db, &token_record).await);
+        }
+        if token_record.revoked_at.is_some() || token_record.expires_at <= Utc::now() {
+            return Err(AppError::Unauthorized);
+        }
+
+        // Claim the token atomically; losing the race to a concurrent refresh
+        // is the same as reuse
+        let claimed = sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW(), revoked_reason = $2
+            WHERE id = $1 AND revoked_at IS NULL
+            "#
+        )
+        .bind(token_record.id)
+        .bind(REVOKED_ROTATED)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        if claimed == 0 {
+            return Err(Self::revoke_family(db, &token_record).await);
+        }
+
         // Get user
         let user = sqlx::query_as::<_, User>(
-            "SELECT * FROM users WHERE id = $1"
+            "SELECT * FROM users WHERE id = $1 AND deleted_at IS NULL"
         )
         .bind(token_record.user_id)
         .fetch_optional(db)
         .await?
         .ok_or(AppError::Unauthorized)?;
 
-        // Revoke old refresh token
-        sqlx::query(
-            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE id = $1"
-        )
-        .bind(token_record.id)
-        .execute(db)
-        .await?;
+        // Generate new tokens in the same family
+        let access_token = Self::generate_jwt(&user, config)?;
+        let (replacement_id, refresh_token) =
+            Self::issue_refresh_token(db, user.id, token_record.family_id).await?;
 
-        // Generate new tokens
-        let config = Config::from_env().map_err(|e| AppError::ConfigError(e.to_string()))?;
-        let (access_token, refresh_token) = Self::generate_tokens(&user, &config, db).await?;
+        sqlx::query("UPDATE refresh_tokens SET replaced_by = $2 WHERE id = $1")
+            .bind(token_record.id)
+            .bind(replacement_id)
+            .execute(db)
+            .await?;
 
         Ok(AuthResponse {
             access_token,
@@ -161,7 +228,10 @@ impl AuthService {
     pub async fn logout(db: &PgPool, user_id: Uuid) -> Result<()> {
         // Revoke all refresh tokens for user
         sqlx::query(
-            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL"
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW(), revoked_reason = 'logout'
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#
         )
         .bind(user_id)
         .execute(db)
@@ -176,7 +246,7 @@ impl AuthService {
     ) -> Result<()> {
         // Find user
         let user = sqlx::query_as::<_, User>(
-            "SELECT * FROM users WHERE email = $1"
+            "SELECT * FROM users WHERE email = $1 AND deleted_at IS NULL"
         )
         .bind(&dto.email)
         .fetch_optional(db)
@@ -248,7 +318,10 @@ impl AuthService {
 
         // Revoke all refresh tokens
         sqlx::query(
-            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL"
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW(), revoked_reason = 'password_reset'
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#
         )
         .bind(token_record.user_id)
         .execute(db)
@@ -258,7 +331,7 @@ impl AuthService {
     }
 
     // Helper methods
-    async fn generate_tokens(
+    pub(crate) async fn generate_tokens(
         user: &User,
         config: &Config,
         db: &PgPool,
@@ -266,24 +339,58 @@ impl AuthService {
         // Generate access token
         let access_token = Self::generate_jwt(user, config)?;
 
-        // Generate refresh token
+        // Each login starts a new token family
+        let (_, refresh_token) = Self::issue_refresh_token(db, user.id, Uuid::new_v4()).await?;
+
+        Ok((access_token, refresh_token))
+    }
+
+    /// Store a new refresh token in `family_id`, returning its id and the
+    /// token, which is only ever held by the client
+    async fn issue_refresh_token(db: &PgPool, user_id: Uuid, family_id: Uuid) -> Result<(Uuid, String)> {
         let refresh_token = Self::generate_random_token();
         let expires_at = Utc::now() + Duration::days(30);
 
-        // Store refresh token
-        sqlx::query(
+        let id = sqlx::query_scalar::<_, Uuid>(
             r#"
-            INSERT INTO refresh_tokens (user_id, token, expires_at)
-            VALUES ($1, $2, $3)
+            INSERT INTO refresh_tokens (user_id, token_hash, family_id, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
             "#
         )
-        .bind(user.id)
-        .bind(&refresh_token)
+        .bind(user_id)
+        .bind(hash_token(&refresh_token))
+        .bind(family_id)
         .bind(expires_at)
-        .execute(db)
+        .fetch_one(db)
         .await?;
 
-        Ok((access_token, refresh_token))
+        Ok((id, refresh_token))
+    }
+
+    /// Revoke every live token in the family of a reused token, returning the
+    /// error to report
+    async fn revoke_family(db: &PgPool, token: &RefreshToken) -> AppError {
+        tracing::warn!(
+            "Refresh token reuse detected for user {}; revoking token family {}",
+            token.user_id,
+            token.family_id
+        );
+
+        let revoked = sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW(), revoked_reason = 'reuse_detected'
+            WHERE family_id = $1 AND revoked_at IS NULL
+            "#
+        )
+        .bind(token.family_id)
+        .execute(db)
+        .await;
+
+        match revoked {
+            Ok(_) => AppError::RefreshTokenReused,
+            Err(e) => e.into(),
+        }
     }
 
     fn generate_jwt(user: &User, config: &Config) -> Result<String> {
@@ -296,28 +403,54 @@ impl AuthService {
             iat: Utc::now().timestamp(),
         };
 
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(config.jwt.secret.as_bytes()),
-        )
-        .map_err(|_e| AppError::InternalServerError)?;
+        let keys = jwt_keys(&config.jwt).map_err(|e| {
+            tracing::error!("Cannot sign access tokens: {}", e);
+            AppError::InternalServerError
+        })?;
+        let token = encode(&keys.header, &claims, &keys.signing_key).map_err(|_e| AppError::InternalServerError)?;
 
         Ok(token)
     }
 
+    /// Check a token against the current key and, during its grace window,
+    /// the previous one. Tokens name their key in the `kid` header; older
+    /// tokens without one are tried against both.
     pub fn validate_jwt(token: &str, config: &Config) -> Result<Claims> {
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(config.jwt.secret.as_bytes()),
-            &Validation::default(),
-        )
-        .map_err(|_| AppError::Unauthorized)?;
+        let kid = decode_header(token).map_err(|_| AppError::Unauthorized)?.kid;
+        let keys = jwt_keys(&config.jwt).map_err(|e| {
+            tracing::error!("Cannot verify access tokens: {}", e);
+            AppError::InternalServerError
+        })?;
+
+        keys.verifying_keys(&config.jwt)
+            .filter(|key| kid.as_deref().map_or(true, |kid| kid == key.key_id))
+            .find_map(|key| decode::<Claims>(token, &key.key, &Validation::new(key.algorithm)).ok())
+            .map(|token_data| token_data.claims)
+            .ok_or(AppError::Unauthorized)
+    }
 
-        Ok(token_data.claims)
+    /// Load the configured keys and check that the current pair signs
+    /// tokens it can verify. Run at startup, the keys it loads are the ones
+    /// later requests use.
+    pub fn check_jwt_keys(jwt: &JwtConfig) -> std::result::Result<(), String> {
+        let keys = JwtKeys::parse(jwt)?;
+
+        let claims = Claims {
+            sub: Uuid::nil(),
+            email: String::new(),
+            exp: Utc::now().timestamp() + 60,
+            iat: Utc::now().timestamp(),
+        };
+        let token = encode(&keys.header, &claims, &keys.signing_key)
+            .map_err(|e| format!("Invalid JWT signing key: {}", e))?;
+        decode::<Claims>(&token, &keys.current.key, &Validation::new(keys.current.algorithm))
+            .map_err(|_| "JWT_PUBLIC_KEY_PATH does not match JWT_PRIVATE_KEY_PATH".to_string())?;
+
+        let _ = jwt.keys.0.set(Arc::new(keys));
+        Ok(())
     }
 
-    fn hash_password(password: &str) -> Result<String> {
+    pub(crate) fn hash_password(password: &str) -> Result<String> {
         let salt = SaltString::generate(&mut OsRng);
         let argon2 = Argon2::default();
         
@@ -329,7 +462,7 @@ impl AuthService {
         Ok(password_hash)
     }
 
-    fn verify_password(password: &str, hash: &str) -> Result<bool> {
+    pub(crate) fn verify_password(password: &str, hash: &str) -> Result<bool> {
         let parsed_hash = PasswordHash::new(hash)
             .map_err(|_| AppError::InternalServerError)?;
         
@@ -337,7 +470,7 @@ impl AuthService {
         Ok(argon2.verify_password(password.as_bytes(), &parsed_hash).is_ok())
     }
 
-    fn generate_random_token() -> String {
+    pub(crate) fn generate_random_token() -> String {
         use rand::Rng;
         let mut rng = rand::thread_rng();
         let token: String = (0..32)
@@ -354,57 +487,236 @@ impl AuthService {
         token
     }
 
-    fn user_to_response(user: &User) -> UserResponse {
+    pub(crate) fn user_to_response(user: &User) -> UserResponse {
         UserResponse {
             id: user.id,
             email: user.email.clone(),
             display_name: user.display_name.clone(),
             email_verified: user.email_verified,
+            pending_email: user.pending_email.clone(),
+            leaderboard_opt_out: user.leaderboard_opt_out,
             created_at: user.created_at,
         }
     }
 
-    async fn record_login_attempt(
+    pub(crate) async fn record_login_attempt(
         db: &PgPool,
         email: &str,
         user_id: Option<Uuid>,
-        success: bool,
+        client: &ClientInfo,
+        failure_reason: Option<&str>,
     ) -> Result<()> {
-        // In a real application, you'd get the IP from the request
-        let ip_address = "127.0.0.1";
-        
         sqlx::query(
             r#"
-            INSERT INTO login_attempts (email, ip_address, success)
-            VALUES ($1, $2::inet, $3)
+            INSERT INTO login_attempts (email, user_id, ip_address, user_agent, success, failure_reason)
+            VALUES ($1, $2, $3::inet, $4, $5, $6)
             "#
         )
         .bind(email)
-        .bind(ip_address)
-        .bind(success)
+        .bind(user_id)
+        .bind(client.ip_address.map(|ip| ip.to_string()))
+        .bind(&client.user_agent)
+        .bind(failure_reason.is_none())
+        .bind(failure_reason)
         .execute(db)
         .await?;
 
         Ok(())
     }
 
-    pub async fn check_rate_limit(db: &PgPool, email: &str) -> Result<()> {
-        let attempts = sqlx::query_scalar::<_, i64>(
+    /// Reject the attempt while the account is locked out. Failures since the
+    /// last successful login count; attempts refused by the lockout itself do
+    /// not extend it.
+    async fn check_lockout(db: &PgPool, config: &Config, email: &str, client: &ClientInfo) -> Result<()> {
+        if !config.lockout.enabled {
+            return Ok(());
+        }
+
+        let (failures, last_failure) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
             r#"
-            SELECT COUNT(*) FROM login_attempts
-            WHERE email = $1 
-                AND attempted_at > NOW() - INTERVAL '15 minutes'
+            SELECT COUNT(*), MAX(attempted_at) FROM login_attempts
+            WHERE email = $1
                 AND success = false
+                AND failure_reason IS DISTINCT FROM 'locked_out'
+                AND attempted_at > NOW() - INTERVAL '1 day'
+                AND attempted_at > COALESCE(
+                    (SELECT MAX(attempted_at) FROM login_attempts WHERE email = $1 AND success),
+                    '-infinity'
+                )
             "#
         )
         .bind(email)
         .fetch_one(db)
         .await?;
 
-        if attempts >= 5 {
-            return Err(AppError::BadRequest("Too many login attempts. Please try again later.".to_string()));
+        let (Some(lockout_seconds), Some(last_failure)) =
+            (lockout_seconds(failures as u64, &config.lockout), last_failure)
+        else {
+            return Ok(());
+        };
+
+        let remaining = (last_failure + Duration::seconds(lockout_seconds as i64) - Utc::now()).num_seconds();
+        if remaining <= 0 {
+            return Ok(());
         }
 
-        Ok(())
+        Self::record_login_attempt(db, email, None, client, Some(LOCKED_OUT)).await?;
+        Err(AppError::TooManyRequests {
+            message: "Too many failed login attempts. Please try again later.".to_string(),
+            retry_after_seconds: remaining as u64,
+        })
+    }
+}
+
+/// Lockout after `failures` consecutive failures: none below the threshold,
+/// then the base duration doubling per further failure, capped
+pub fn lockout_seconds(failures: u64, lockout: &LockoutConfig) -> Option<u64> {
+    let over = failures.checked_sub(lockout.threshold as u64)?;
+    let factor = 1u64.checked_shl(over.min(32) as u32).unwrap_or(u64::MAX);
+    Some(lockout.base_seconds.saturating_mul(factor).min(lockout.max_seconds))
+}
+
+/// Hex SHA-256 of a refresh or email verification token. Tokens are random,
+/// so an unsalted fast hash is enough to make a leaked table useless.
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Parsed access token keys, kept with the `JwtConfig` they came from so
+/// PEM keys are parsed once rather than on every request
+#[derive(Clone, Default)]
+pub struct JwtKeyCache(OnceLock<Arc<JwtKeys>>);
+
+impl std::fmt::Debug for JwtKeyCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.get().is_some() { "JwtKeyCache(loaded)" } else { "JwtKeyCache(empty)" })
+    }
+}
+
+// The keys of a config, parsed the first time they are asked for
+fn jwt_keys(jwt: &JwtConfig) -> std::result::Result<&JwtKeys, String> {
+    if let Some(keys) = jwt.keys.0.get() {
+        return Ok(keys);
     }
+    let keys = JwtKeys::parse(jwt)?;
+    Ok(jwt.keys.0.get_or_init(|| Arc::new(keys)))
+}
+
+/// The signing key with the header naming it, and the keys tokens are
+/// checked against
+struct JwtKeys {
+    header: Header,
+    signing_key: EncodingKey,
+    current: JwtVerifyingKey,
+    previous: Option<JwtVerifyingKey>,
+}
+
+impl JwtKeys {
+    fn parse(jwt: &JwtConfig) -> std::result::Result<Self, String> {
+        let current = verifying_key(
+            &jwt.algorithm,
+            Some(&jwt.secret),
+            jwt.public_key.as_deref(),
+            jwt.key_id.as_deref(),
+            "JWT_PUBLIC_KEY_PATH",
+        )?;
+        // A previous key that has already expired is never needed
+        let previous = jwt
+            .previous
+            .as_ref()
+            .filter(|previous| Utc::now() < previous.valid_until)
+            .map(|previous| {
+                verifying_key(
+                    &previous.algorithm,
+                    previous.secret.as_deref(),
+                    previous.public_key.as_deref(),
+                    previous.key_id.as_deref(),
+                    "JWT_PREVIOUS_PUBLIC_KEY_PATH",
+                )
+            })
+            .transpose()?;
+
+        let algorithm = jwt_algorithm(&jwt.algorithm)?;
+        let signing_key = match algorithm {
+            Algorithm::HS256 => EncodingKey::from_secret(jwt.secret.as_bytes()),
+            _ => {
+                let pem = jwt
+                    .private_key
+                    .as_deref()
+                    .ok_or_else(|| format!("{} needs a readable JWT_PRIVATE_KEY_PATH", jwt.algorithm))?;
+                let key = if algorithm == Algorithm::RS256 {
+                    EncodingKey::from_rsa_pem(pem.as_bytes())
+                } else {
+                    EncodingKey::from_ed_pem(pem.as_bytes())
+                };
+                key.map_err(|e| format!("Invalid JWT private key: {}", e))?
+            }
+        };
+
+        let mut header = Header::new(algorithm);
+        header.kid = Some(current.key_id.clone());
+        Ok(Self {
+            header,
+            signing_key,
+            current,
+            previous,
+        })
+    }
+
+    /// The current key first, then the previous one while the config
+    /// still accepts it
+    fn verifying_keys(&self, jwt: &JwtConfig) -> impl Iterator<Item = &JwtVerifyingKey> {
+        let accepted = jwt.previous.as_ref().is_some_and(|previous| Utc::now() < previous.valid_until);
+        std::iter::once(&self.current).chain(self.previous.iter().filter(move |_| accepted))
+    }
+}
+
+/// A key access tokens can be verified with
+struct JwtVerifyingKey {
+    key_id: String,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+fn jwt_algorithm(name: &str) -> std::result::Result<Algorithm, String> {
+    match name.to_ascii_uppercase().as_str() {
+        "HS256" => Ok(Algorithm::HS256),
+        "RS256" => Ok(Algorithm::RS256),
+        "EDDSA" => Ok(Algorithm::EdDSA),
+        _ => Err(format!("Unsupported JWT algorithm {}; use HS256, RS256 or EdDSA", name)),
+    }
+}
+
+// HS256 keys are checked with the secret, the others with their public
+// key. Without a configured id the key is named by a fingerprint of that
+// material, so a key keeps its id when it becomes the previous one.
+fn verifying_key(
+    algorithm: &str,
+    secret: Option<&str>,
+    public_key: Option<&str>,
+    key_id: Option<&str>,
+    public_key_var: &str,
+) -> std::result::Result<JwtVerifyingKey, String> {
+    let algorithm = jwt_algorithm(algorithm)?;
+    let (material, key) = match algorithm {
+        Algorithm::HS256 => {
+            let secret = secret.ok_or("HS256 needs a secret")?;
+            (secret, DecodingKey::from_secret(secret.as_bytes()))
+        }
+        _ => {
+            let pem = public_key.ok_or_else(|| format!("{:?} needs a readable {}", algorithm, public_key_var))?;
+            let key = if algorithm == Algorithm::RS256 {
+                DecodingKey::from_rsa_pem(pem.as_bytes())
+            } else {
+                DecodingKey::from_ed_pem(pem.as_bytes())
+            };
+            (pem, key.map_err(|e| format!("Invalid JWT public key in {}: {}", public_key_var, e))?)
+        }
+    };
+
+    Ok(JwtVerifyingKey {
+        key_id: key_id.map_or_else(|| hex::encode(&Sha256::digest(material.as_bytes())[..8]), str::to_string),
+        algorithm,
+        key,
+    })
 }
@@ -0,0 +1,95 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{DeckBadge, DeckBadgeJson},
+    utils::{AppError, Result},
+};
+
+const LABEL: &str = "flashcards";
+
+/// Embeddable statistics badges for public decks
+pub struct BadgeService;
+
+impl BadgeService {
+    /// Badge numbers for a public deck. Private decks are reported as not
+    /// found so their existence is not revealed.
+    pub async fn deck_badge(db: &PgPool, deck_id: Uuid) -> Result<DeckBadge> {
+        sqlx::query_as::<_, DeckBadge>(
+            r#"
+            SELECT d.id as deck_id,
+                   d.card_count::BIGINT as card_count,
+                   (SELECT AVG(r.rating)::FLOAT8 FROM deck_ratings r WHERE r.deck_id = d.id) as average_rating,
+                   (SELECT COUNT(*) FROM deck_ratings r WHERE r.deck_id = d.id) as rating_count
+            FROM decks d
+            WHERE d.id = $1 AND d.is_public = true AND d.deleted_at IS NULL
+            "#,
+        )
+        .bind(deck_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::DeckNotFound)
+    }
+
+    pub fn message(badge: &DeckBadge) -> String {
+        let cards = match badge.card_count {
+            1 => "1 card".to_string(),
+            n => format!("{} cards", n),
+        };
+
+        match badge.average_rating {
+            Some(rating) if badge.rating_count > 0 => format!("{} | ★ {:.1}", cards, rating),
+            _ => cards,
+        }
+    }
+
+    /// Green for well-rated decks, blue when unrated, amber/red below that
+    pub fn color(badge: &DeckBadge) -> &'static str {
+        match badge.average_rating {
+            _ if badge.rating_count == 0 => "#007ec6",
+            Some(rating) if rating >= 4.0 => "#4c1",
+            Some(rating) if rating >= 3.0 => "#dfb317",
+            _ => "#e05d44",
+        }
+    }
+
+    pub fn to_json(badge: DeckBadge) -> DeckBadgeJson {
+        DeckBadgeJson {
+            schema_version: 1,
+            label: LABEL.to_string(),
+            message: Self::message(&badge),
+            color: Self::color(&badge).to_string(),
+            stats: badge,
+        }
+    }
+
+    /// Render a flat, shields-style SVG badge
+    pub fn render_svg(badge: &DeckBadge) -> String {
+        let message = Self::message(badge);
+        let color = Self::color(badge);
+
+        let label_width = text_width(LABEL);
+        let message_width = text_width(&message);
+        let width = label_width + message_width;
+        let label_x = label_width / 2;
+        let message_x = label_width + message_width / 2;
+        let title = escape_xml(&format!("{}: {}", LABEL, message));
+        let message = escape_xml(&message);
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{title}"><title>{title}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{LABEL}</text><text x="{label_x}" y="14">{LABEL}</text><text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text><text x="{message_x}" y="14">{message}</text></g></svg>"##
+        )
+    }
+}
+
+/// Approximate rendered width of Verdana 11px text plus padding
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,116 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::AiConfig,
+    models::{CaptureDto, CaptureMode, CaptureResult, CreateCardDto, DeckRole},
+    services::{
+        ai_generation::AiGenerationService, ai_privacy::AiPrivacyService,
+        ai_provider::FlashcardGenerationOptions, card::CardService, deck::DeckService,
+        encryption::EncryptionService, sharing::SharingService,
+    },
+    utils::{AppError, Result},
+};
+
+/// Deck that receives captures sent without a target deck
+const CAPTURE_DECK_TITLE: &str = "Web Captures";
+
+/// Cards generated from one selection unless the AI config allows fewer
+const GENERATED_CARDS_PER_CAPTURE: i32 = 5;
+
+/// Text selections sent from the browser extension
+pub struct CaptureService;
+
+impl CaptureService {
+    pub async fn capture(
+        db: &PgPool,
+        ai: &AiConfig,
+        user_id: Uuid,
+        dto: CaptureDto,
+    ) -> Result<CaptureResult> {
+        if dto.mode == CaptureMode::Generate {
+            if !ai.enabled {
+                return Err(AppError::BadRequest("AI features are not enabled".to_string()));
+            }
+            AiPrivacyService::require_content_generation(db, user_id).await?;
+        }
+
+        let deck_id = match dto.deck_id {
+            Some(deck_id) => {
+                SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+                deck_id
+            }
+            None => {
+                DeckService::find_or_create_owned(
+                    db,
+                    user_id,
+                    CAPTURE_DECK_TITLE,
+                    "Text captured from web pages",
+                )
+                .await?
+            }
+        };
+        // Captured text is plaintext, which an encrypted deck must never hold
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+
+        let text = dto.text.trim();
+        let source = match dto.page_title.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(title) => format!("{} ({})", title, dto.source_url),
+            None => dto.source_url.clone(),
+        };
+
+        match dto.mode {
+            CaptureMode::Card => {
+                let front = dto
+                    .front
+                    .as_deref()
+                    .or(dto.page_title.as_deref())
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .unwrap_or(&dto.source_url)
+                    .to_string();
+
+                let card = CardService::create_card(
+                    db,
+                    deck_id,
+                    user_id,
+                    CreateCardDto {
+                        front,
+                        back: format!("{}\n\nSource: {}", text, source),
+                        position: None,
+                    },
+                )
+                .await?;
+
+                Ok(CaptureResult::Card { card })
+            }
+            CaptureMode::Generate => {
+                let content = format!("Source: {}\n\n{}", source, text);
+                let options = FlashcardGenerationOptions {
+                    max_cards: Some(GENERATED_CARDS_PER_CAPTURE),
+                    difficulty: None,
+                    format: None,
+                    include_explanations: Some(true),
+                    languages: Default::default(),
+                };
+
+                let job_id = AiGenerationService::create_stream_job(
+                    db,
+                    ai,
+                    user_id,
+                    Some(deck_id),
+                    &content,
+                    options,
+                    None,
+                )
+                .await?;
+
+                Ok(CaptureResult::Generation {
+                    job_id,
+                    deck_id,
+                    stream_url: format!("/api/v1/ai/generate-cards/stream?job_id={}", job_id),
+                })
+            }
+        }
+    }
+}
@@ -1,52 +1,123 @@
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
-    models::{Card, CreateCardDto, UpdateCardDto},
-    utils::{AppError, Result},
+    models::{
+        Card, CardOrder, CreateCardDto, DeckRole, MovedCard, RenderFormat, RenderedCard, UpdateCardDto,
+    },
+    services::{encryption::EncryptionService, reverse_cards::ReverseCardService, sharing::SharingService},
+    utils::{render_markdown, AppError, CursorPage, CursorParams, Result},
 };
 
 pub struct CardService;
 
 impl CardService {
+    /// One page of a deck's cards, resuming after the cursor's card
     pub async fn list_deck_cards(
         db: &PgPool,
         deck_id: Uuid,
         user_id: Uuid,
-    ) -> Result<Vec<Card>> {
+        order: CardOrder,
+        mut params: CursorParams,
+    ) -> Result<CursorPage<Card>> {
         // First verify deck access
-        let deck_access = sqlx::query!(
-            r#"
-            SELECT EXISTS(
-                SELECT 1 FROM decks
-                WHERE id = $1 AND owner_id = $2
-            ) as "exists!"
-            "#,
-            deck_id,
-            user_id
-        )
-        .fetch_one(db)
-        .await?
-        .exists;
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+        params.validate();
 
-        if !deck_access {
-            return Err(AppError::NotFound("Resource not found".to_string()));
-        }
+        // Ties on the sort column are broken by id so every card has a unique key
+        let cards = match order {
+            CardOrder::Position => {
+                let after = params.key::<(i32, Uuid)>()?;
+                sqlx::query_as::<_, Card>(
+                    r#"
+                    SELECT id, deck_id, front, back, position, created_at, updated_at
+                    FROM cards
+                    WHERE deck_id = $1 AND deleted_at IS NULL
+                        AND ($2::INT IS NULL OR (position, id) > ($2, $3))
+                    ORDER BY position, id
+                    LIMIT $4
+                    "#,
+                )
+                .bind(deck_id)
+                .bind(after.map(|(position, _)| position))
+                .bind(after.map(|(_, id)| id))
+                .bind(params.limit_plus_one() as i64)
+                .fetch_all(db)
+                .await?
+            }
+            CardOrder::Created => {
+                let after = params.key::<(DateTime<Utc>, Uuid)>()?;
+                sqlx::query_as::<_, Card>(
+                    r#"
+                    SELECT id, deck_id, front, back, position, created_at, updated_at
+                    FROM cards
+                    WHERE deck_id = $1 AND deleted_at IS NULL
+                        AND ($2::TIMESTAMPTZ IS NULL OR (created_at, id) > ($2, $3))
+                    ORDER BY created_at, id
+                    LIMIT $4
+                    "#,
+                )
+                .bind(deck_id)
+                .bind(after.map(|(created_at, _)| created_at))
+                .bind(after.map(|(_, id)| id))
+                .bind(params.limit_plus_one() as i64)
+                .fetch_all(db)
+                .await?
+            }
+        };
 
-        let cards = sqlx::query_as!(
-            Card,
-            r#"
-            SELECT id, deck_id, front, back, position, created_at, updated_at
-            FROM cards
-            WHERE deck_id = $1
-            ORDER BY position
-            "#,
-            deck_id
+        Ok(match order {
+            CardOrder::Position => CursorPage::new(cards, &params, |card| (card.position, card.id)),
+            CardOrder::Created => CursorPage::new(cards, &params, |card| (card.created_at, card.id)),
+        })
+    }
+
+    /// Fingerprint of a deck's card list: the count catches additions and
+    /// removals, the latest `updated_at` catches edits
+    pub async fn deck_cards_version(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<String> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+
+        let (count, last_updated) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+            "SELECT COUNT(*), MAX(updated_at) FROM cards WHERE deck_id = $1 AND deleted_at IS NULL",
         )
-        .fetch_all(db)
+        .bind(deck_id)
+        .fetch_one(db)
         .await?;
 
-        Ok(cards)
+        Ok(format!(
+            "{}:{}:{}",
+            deck_id,
+            count,
+            last_updated.map_or(0, |t| t.timestamp_micros())
+        ))
+    }
+
+    /// Fetch a card, optionally rendering its Markdown to sanitized HTML
+    pub async fn get_rendered_card(
+        db: &PgPool,
+        card_id: Uuid,
+        user_id: Uuid,
+        render: Option<RenderFormat>,
+    ) -> Result<RenderedCard> {
+        let card = Self::get_card(db, card_id, user_id).await?;
+
+        let Some(RenderFormat::Html) = render else {
+            return Ok(RenderedCard {
+                card,
+                rendered_front: None,
+                rendered_back: None,
+            });
+        };
+
+        // Ciphertext is not Markdown
+        EncryptionService::ensure_plaintext(db, card.deck_id).await?;
+
+        Ok(RenderedCard {
+            rendered_front: Some(render_markdown(&card.front)),
+            rendered_back: Some(render_markdown(&card.back)),
+            card,
+        })
     }
 
     pub async fn create_card(
@@ -55,22 +126,8 @@ impl CardService {
         user_id: Uuid,
         dto: CreateCardDto,
     ) -> Result<Card> {
-        // Verify deck ownership
-        let deck_owner = sqlx::query!(
-            r#"
-            SELECT owner_id as user_id
-            FROM decks
-            WHERE id = $1
-            "#,
-            deck_id
-        )
-        .fetch_optional(db)
-        .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
-
-        if deck_owner.user_id != user_id {
-            return Err(AppError::Forbidden);
-        }
+        // Verify edit access
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
 
         // Get position if not provided
         let position = match dto.position {
@@ -106,6 +163,7 @@ impl CardService {
         )
         .fetch_one(db)
         .await?;
+        ReverseCardService::generate_if_enabled(db, deck_id).await?;
 
         Ok(card)
     }
@@ -121,14 +179,17 @@ impl CardService {
             SELECT c.id, c.deck_id, c.front, c.back, c.position, c.created_at, c.updated_at
             FROM cards c
             JOIN decks d ON d.id = c.deck_id
-            WHERE c.id = $1 AND d.owner_id = $2
+            WHERE c.id = $1 AND c.deleted_at IS NULL AND d.deleted_at IS NULL AND (
+                d.owner_id = $2 OR d.is_public = true
+                OR EXISTS(SELECT 1 FROM deck_shares s WHERE s.deck_id = d.id AND s.user_id = $2)
+            )
             "#,
             id,
             user_id
         )
         .fetch_optional(db)
         .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+        .ok_or(AppError::CardNotFound)?;
 
         Ok(card)
     }
@@ -139,24 +200,12 @@ impl CardService {
         user_id: Uuid,
         dto: UpdateCardDto,
     ) -> Result<Card> {
-        // Verify ownership through deck
-        let deck_owner = sqlx::query!(
-            r#"
-            SELECT d.owner_id as user_id
-            FROM cards c
-            JOIN decks d ON d.id = c.deck_id
-            WHERE c.id = $1
-            "#,
-            id
-        )
-        .fetch_optional(db)
-        .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
-
-        if deck_owner.user_id != user_id {
-            return Err(AppError::Forbidden);
-        }
+        // Verify edit access through deck
+        let deck_id = Self::card_deck_id(db, id).await?;
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
 
+        // Compared in the same statement, so a concurrent edit either lands
+        // first and fails this one or waits for it
         let card = sqlx::query_as!(
             Card,
             r#"
@@ -165,54 +214,55 @@ impl CardService {
                 front = COALESCE($2, front),
                 back = COALESCE($3, back),
                 position = COALESCE($4, position)
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
+              AND ($5::timestamptz IS NULL OR updated_at = $5)
             RETURNING id, deck_id, front, back, position, created_at, updated_at
             "#,
             id,
             dto.front,
             dto.back,
-            dto.position
+            dto.position,
+            dto.updated_at
         )
-        .fetch_one(db)
+        .fetch_optional(db)
         .await?;
+        let card = match card {
+            Some(card) => card,
+            None if dto.updated_at.is_some() => {
+                let current = Self::get_card(db, id, user_id).await?;
+                return Err(AppError::edit_conflict("card", current.id, current.updated_at, &current));
+            }
+            None => return Err(AppError::CardNotFound),
+        };
+        ReverseCardService::sync_sibling(db, &card).await?;
 
         Ok(card)
     }
 
+    /// Move a card to the trash
     pub async fn delete_card(
         db: &PgPool,
         id: Uuid,
         user_id: Uuid,
-    ) -> Result<()> {
-        // Verify ownership through deck
-        let deck_owner = sqlx::query!(
-            r#"
-            SELECT d.owner_id as user_id
-            FROM cards c
-            JOIN decks d ON d.id = c.deck_id
-            WHERE c.id = $1
-            "#,
-            id
-        )
-        .fetch_optional(db)
-        .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
-
-        if deck_owner.user_id != user_id {
-            return Err(AppError::Forbidden);
-        }
+    ) -> Result<Card> {
+        // Verify edit access through deck
+        let deck_id = Self::card_deck_id(db, id).await?;
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
 
-        sqlx::query!(
+        let card = sqlx::query_as!(
+            Card,
             r#"
-            DELETE FROM cards
-            WHERE id = $1
+            UPDATE cards SET deleted_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, deck_id, front, back, position, created_at, updated_at
             "#,
             id
         )
-        .execute(db)
+        .fetch_one(db)
         .await?;
+        ReverseCardService::delete_reverse(db, id).await?;
 
-        Ok(())
+        Ok(card)
     }
 
     pub async fn bulk_create_cards(
@@ -221,22 +271,8 @@ impl CardService {
         user_id: Uuid,
         cards: Vec<CreateCardDto>,
     ) -> Result<Vec<Card>> {
-        // Verify deck ownership
-        let deck_owner = sqlx::query!(
-            r#"
-            SELECT owner_id as user_id
-            FROM decks
-            WHERE id = $1
-            "#,
-            deck_id
-        )
-        .fetch_optional(db)
-        .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
-
-        if deck_owner.user_id != user_id {
-            return Err(AppError::Forbidden);
-        }
+        // Verify edit access
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
 
         // Get current max position
         let max_position = sqlx::query!(
@@ -278,7 +314,161 @@ impl CardService {
         }
 
         tx.commit().await?;
+        ReverseCardService::generate_if_enabled(db, deck_id).await?;
 
         Ok(created_cards)
     }
+
+    /// Move cards, with their reverse siblings, into a deck the user owns,
+    /// inserted at `position` or appended. The cards keep their ids, so
+    /// every learner's scheduling state and flags follow them. Positions in
+    /// the target and in every source deck are renumbered in the same
+    /// transaction.
+    pub async fn move_cards(
+        db: &PgPool,
+        card_ids: &[Uuid],
+        target_deck_id: Uuid,
+        user_id: Uuid,
+        position: Option<i32>,
+    ) -> Result<Vec<MovedCard>> {
+        let mut requested: Vec<Uuid> = Vec::with_capacity(card_ids.len());
+        for id in card_ids {
+            if !requested.contains(id) {
+                requested.push(*id);
+            }
+        }
+
+        // Originals and their reverses travel together, each reverse right
+        // after its original, in the order the cards were requested
+        let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            WITH originals AS (
+                SELECT ARRAY(
+                    SELECT COALESCE(c.reverse_of, c.id) FROM unnest($1::UUID[]) WITH ORDINALITY AS r(id, ord)
+                    JOIN cards c ON c.id = r.id AND c.deleted_at IS NULL
+                    ORDER BY r.ord
+                ) AS ids
+            )
+            SELECT c.id, c.deck_id FROM cards c, originals o
+            WHERE c.deleted_at IS NULL AND COALESCE(c.reverse_of, c.id) = ANY(o.ids)
+            ORDER BY array_position(o.ids, COALESCE(c.reverse_of, c.id)), c.reverse_of IS NOT NULL
+            "#,
+        )
+        .bind(&requested)
+        .fetch_all(db)
+        .await?;
+        if requested.iter().any(|id| !rows.iter().any(|(card_id, _)| card_id == id)) {
+            return Err(AppError::CardNotFound);
+        }
+        if rows.iter().any(|(_, deck_id)| *deck_id == target_deck_id) {
+            return Err(AppError::BadRequest("Card is already in the target deck".to_string()));
+        }
+
+        let mut source_deck_ids: Vec<Uuid> = Vec::new();
+        for (_, deck_id) in &rows {
+            if !source_deck_ids.contains(deck_id) {
+                source_deck_ids.push(*deck_id);
+            }
+        }
+        for &deck_id in &source_deck_ids {
+            SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+            EncryptionService::ensure_plaintext(db, deck_id).await?;
+        }
+        SharingService::require_deck_role(db, target_deck_id, user_id, DeckRole::Owner).await?;
+        EncryptionService::ensure_plaintext(db, target_deck_id).await?;
+
+        let moving: Vec<Uuid> = rows.iter().map(|(id, _)| *id).collect();
+        let mut tx = db.begin().await?;
+
+        // Lock every deck involved so concurrent moves renumber in turn
+        let mut deck_ids = source_deck_ids.clone();
+        deck_ids.push(target_deck_id);
+        sqlx::query("SELECT id FROM decks WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+            .bind(&deck_ids)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            WITH existing AS (
+                SELECT id, ROW_NUMBER() OVER (ORDER BY position, created_at) - 1 AS idx
+                FROM cards
+                WHERE deck_id = $1 AND deleted_at IS NULL
+            ),
+            bounds AS (
+                SELECT LEAST(COALESCE($3::BIGINT, COUNT(*)), COUNT(*)) AS at FROM existing
+            ),
+            ordered AS (
+                SELECT e.id, CASE WHEN e.idx < b.at THEN e.idx ELSE e.idx + $4 END AS new_position
+                FROM existing e, bounds b
+                UNION ALL
+                SELECT m.id, b.at + m.ord - 1
+                FROM unnest($2::UUID[]) WITH ORDINALITY AS m(id, ord), bounds b
+            )
+            UPDATE cards c SET deck_id = $1, position = o.new_position::INT
+            FROM ordered o
+            WHERE c.id = o.id
+            "#,
+        )
+        .bind(target_deck_id)
+        .bind(&moving)
+        .bind(position.map(i64::from))
+        .bind(moving.len() as i64)
+        .execute(&mut *tx)
+        .await?;
+
+        // Close the gaps the cards left behind
+        sqlx::query(
+            r#"
+            UPDATE cards c SET position = r.idx::INT
+            FROM (
+                SELECT id, ROW_NUMBER() OVER (PARTITION BY deck_id ORDER BY position, created_at) - 1 AS idx
+                FROM cards
+                WHERE deck_id = ANY($1) AND deleted_at IS NULL
+            ) r
+            WHERE c.id = r.id AND c.position <> r.idx
+            "#,
+        )
+        .bind(&source_deck_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        // Pending lint suggestions belong to the deck the card is in now
+        sqlx::query(
+            "UPDATE card_lint_suggestions SET deck_id = $1 WHERE card_id = ANY($2) AND applied_at IS NULL",
+        )
+        .bind(target_deck_id)
+        .bind(&moving)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        ReverseCardService::generate_if_enabled(db, target_deck_id).await?;
+
+        let cards = sqlx::query_as::<_, Card>(
+            "SELECT * FROM cards WHERE id = ANY($1) ORDER BY position",
+        )
+        .bind(&moving)
+        .fetch_all(db)
+        .await?;
+
+        Ok(cards
+            .into_iter()
+            .map(|card| {
+                let from_deck_id = rows
+                    .iter()
+                    .find(|(id, _)| *id == card.id)
+                    .map_or(card.deck_id, |(_, deck_id)| *deck_id);
+                MovedCard { card, from_deck_id }
+            })
+            .collect())
+    }
+
+    async fn card_deck_id(db: &PgPool, card_id: Uuid) -> Result<Uuid> {
+        sqlx::query_scalar::<_, Uuid>("SELECT deck_id FROM cards WHERE id = $1 AND deleted_at IS NULL")
+            .bind(card_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or(AppError::CardNotFound)
+    }
 }
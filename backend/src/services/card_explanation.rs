@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::AiConfig,
+    models::ai::{CardExplanation, ExplainCardDto},
+    services::{
+        ai_privacy::AiPrivacyService, ai_provider::provider_for, ai_usage::AiUsageService,
+        circuit_breaker::CircuitBreaker, encryption::EncryptionService, grading::normalize,
+        language::DeckLanguages, study::StudyService,
+    },
+    utils::{AppError, Result},
+};
+
+/// "Explain this answer" during study. Explanations are cached per card and
+/// wrong answer, so asking again, or another learner making the same mistake,
+/// costs no tokens until the card is edited.
+pub struct CardExplanationService;
+
+impl CardExplanationService {
+    pub async fn explain(
+        db: &PgPool,
+        ai: &AiConfig,
+        breaker: &Arc<CircuitBreaker>,
+        session_id: Uuid,
+        card_id: Uuid,
+        user_id: Uuid,
+        dto: ExplainCardDto,
+    ) -> Result<CardExplanation> {
+        if !ai.enabled {
+            return Err(AppError::BadRequest("AI features are not enabled".to_string()));
+        }
+        let session = StudyService::get_study_session(db, session_id, user_id).await?;
+        let deck_id = StudyService::card_deck(db, &session, card_id).await?;
+        // Card content of encrypted decks must never reach the AI provider
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+        AiPrivacyService::require_content_generation(db, user_id).await?;
+
+        let wrong_answer = dto.wrong_answer.as_deref().map(str::trim).filter(|a| !a.is_empty());
+        // Answers differing only in case, punctuation or spacing share an entry
+        let cache_key = wrong_answer.map(normalize).unwrap_or_default();
+
+        let cached = sqlx::query_as::<_, (String, String, i32, DateTime<Utc>)>(
+            r#"
+            SELECT e.explanation, e.model_name, e.tokens_used, e.created_at
+            FROM card_explanations e
+            JOIN cards c ON c.id = e.card_id
+            WHERE e.card_id = $1 AND e.wrong_answer = $2 AND e.created_at >= c.updated_at
+            "#,
+        )
+        .bind(card_id)
+        .bind(&cache_key)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some((explanation, model_name, tokens_used, created_at)) = cached {
+            return Ok(CardExplanation {
+                card_id,
+                explanation,
+                model_name,
+                tokens_used,
+                cached: true,
+                created_at,
+            });
+        }
+
+        let (front, back) = sqlx::query_as::<_, (String, String)>(
+            "SELECT front, back FROM cards WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(card_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::CardNotFound)?;
+
+        let languages = DeckLanguages::for_deck(db, deck_id).await?;
+
+        let mut client = provider_for(ai, breaker, dto.provider)?;
+        AiUsageService::check_quota(db, ai, user_id).await?;
+        let response = client
+            .explain_card(&front, &back, wrong_answer, &languages)
+            .await
+            .map_err(|e| {
+                tracing::error!("AI provider error: {}", e);
+                AppError::InternalServerError
+            })?;
+        AiUsageService::record(db, ai, user_id, client.kind(), response.tokens_used).await?;
+
+        if response.text.is_empty() {
+            tracing::error!("AI provider returned an empty explanation for card {}", card_id);
+            return Err(AppError::InternalServerError);
+        }
+
+        let created_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            INSERT INTO card_explanations (card_id, wrong_answer, explanation, model_name, tokens_used)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (card_id, wrong_answer) DO UPDATE
+            SET explanation = EXCLUDED.explanation,
+                model_name = EXCLUDED.model_name,
+                tokens_used = EXCLUDED.tokens_used,
+                created_at = NOW()
+            RETURNING created_at
+            "#,
+        )
+        .bind(card_id)
+        .bind(&cache_key)
+        .bind(&response.text)
+        .bind(&response.model)
+        .bind(response.tokens_used)
+        .fetch_one(db)
+        .await?;
+
+        Ok(CardExplanation {
+            card_id,
+            explanation: response.text,
+            model_name: response.model,
+            tokens_used: response.tokens_used,
+            cached: false,
+            created_at,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{CardFlags, UpdateCardFlagsDto},
+    services::{card::CardService, marketplace::normalize_tags},
+    utils::Result,
+};
+
+/// Per-user stars, suspensions, leech markers, notes and tags on cards. Anyone who
+/// can see a card can flag it; flags are private to the user who set them.
+pub struct CardFlagsService;
+
+impl CardFlagsService {
+    pub async fn get_flags(db: &PgPool, card_id: Uuid, user_id: Uuid) -> Result<CardFlags> {
+        CardService::get_card(db, card_id, user_id).await?;
+
+        let flags = sqlx::query_as::<_, CardFlags>(
+            r#"
+            SELECT card_id, starred, suspended, leech, notes, tags, updated_at
+            FROM user_card_flags
+            WHERE user_id = $1 AND card_id = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(flags.unwrap_or_else(|| CardFlags::unset(card_id)))
+    }
+
+    /// Set any of the flags, leaving the others as they are
+    pub async fn update_flags(
+        db: &PgPool,
+        card_id: Uuid,
+        user_id: Uuid,
+        dto: UpdateCardFlagsDto,
+    ) -> Result<CardFlags> {
+        let current = Self::get_flags(db, card_id, user_id).await?;
+        let notes = match dto.notes {
+            Some(notes) if notes.trim().is_empty() => None,
+            Some(notes) => Some(notes),
+            None => current.notes,
+        };
+        let tags = match dto.tags {
+            Some(tags) => normalize_tags(&tags)?,
+            None => current.tags,
+        };
+
+        let flags = sqlx::query_as::<_, CardFlags>(
+            r#"
+            INSERT INTO user_card_flags (user_id, card_id, starred, suspended, leech, notes, tags)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (user_id, card_id) DO UPDATE SET
+                starred = EXCLUDED.starred,
+                suspended = EXCLUDED.suspended,
+                leech = EXCLUDED.leech,
+                notes = EXCLUDED.notes,
+                tags = EXCLUDED.tags,
+                updated_at = NOW()
+            RETURNING card_id, starred, suspended, leech, notes, tags, updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .bind(dto.starred.unwrap_or(current.starred))
+        .bind(dto.suspended.unwrap_or(current.suspended))
+        .bind(dto.leech.unwrap_or(current.leech))
+        .bind(notes)
+        .bind(&tags)
+        .fetch_one(db)
+        .await?;
+
+        Ok(flags)
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::AiConfig,
+    models::{
+        ai::{
+            ApplyLintSuggestionsDto, CardLintSuggestion, DeckLintReport, GeneratedLintIssue,
+            LintDeckDto, LintIssueKind, LintSource,
+        },
+        Card, DeckRole,
+    },
+    services::{
+        ai_privacy::AiPrivacyService, ai_provider::provider_for, ai_usage::AiUsageService,
+        circuit_breaker::CircuitBreaker, duplicates::DuplicateDetector, encryption::EncryptionService,
+        grading::normalize, language::DeckLanguages, leech::LONG_ANSWER_CHARS, sharing::SharingService,
+    },
+    utils::{AppError, Result},
+};
+
+/// Cards sent to the model per lint; the local checks cover the whole deck
+const MAX_AI_CARDS: usize = 100;
+
+/// Shorter answers ("a", "no") turn up in questions by chance
+const MIN_LEAK_CHARS: usize = 3;
+
+const MAX_MESSAGE_CHARS: usize = 500;
+
+/// A problem with one card, before it is stored
+#[derive(Debug, Clone)]
+pub struct LintFinding {
+    pub card_id: Uuid,
+    pub kind: LintIssueKind,
+    pub source: LintSource,
+    pub message: String,
+    pub front: Option<String>,
+    pub back: Option<String>,
+}
+
+impl LintFinding {
+    fn has_fix(&self) -> bool {
+        self.front.is_some() || self.back.is_some()
+    }
+}
+
+/// Card quality review: local heuristics plus, when allowed, the AI
+/// provider. Findings are stored as suggestions the user can apply.
+pub struct CardLintService;
+
+impl CardLintService {
+    pub async fn lint_deck(
+        db: &PgPool,
+        ai: &AiConfig,
+        breaker: &Arc<CircuitBreaker>,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: LintDeckDto,
+    ) -> Result<DeckLintReport> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+        // Ciphertext can neither be checked nor sent to the provider
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+
+        let cards = sqlx::query_as::<_, (Uuid, String, String)>(
+            "SELECT id, front, back FROM cards WHERE deck_id = $1 AND deleted_at IS NULL ORDER BY position, created_at",
+        )
+        .bind(deck_id)
+        .fetch_all(db)
+        .await?;
+
+        let mut findings = heuristic_issues(&cards);
+        let mut ai_reviewed = false;
+        let mut model = None;
+        let mut tokens_used = 0;
+
+        let use_ai = dto.use_ai.unwrap_or(true)
+            && ai.enabled
+            && !cards.is_empty()
+            && AiPrivacyService::allows_content_generation(db, user_id).await?;
+        if use_ai {
+            AiUsageService::check_quota(db, ai, user_id).await?;
+            let mut client = provider_for(ai, breaker, dto.provider)?;
+            let sent = &cards[..cards.len().min(MAX_AI_CARDS)];
+            let content: Vec<(String, String)> =
+                sent.iter().map(|(_, front, back)| (front.clone(), back.clone())).collect();
+            let languages = DeckLanguages::for_deck(db, deck_id).await?;
+
+            // The local checks still stand if the provider fails
+            match client.review_cards(&content, &languages).await {
+                Ok(batch) => {
+                    AiUsageService::record(db, ai, user_id, client.kind(), batch.tokens_used).await?;
+                    merge_ai_issues(&mut findings, sent, batch.issues);
+                    ai_reviewed = true;
+                    model = Some(batch.model);
+                    tokens_used = batch.tokens_used;
+                }
+                Err(e) => tracing::warn!("AI review of deck {} failed: {}", deck_id, e),
+            }
+        }
+
+        let mut tx = db.begin().await?;
+
+        // A new lint supersedes the previous one's unapplied suggestions
+        sqlx::query("DELETE FROM card_lint_suggestions WHERE user_id = $1 AND deck_id = $2 AND applied_at IS NULL")
+            .bind(user_id)
+            .bind(deck_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut suggestions = Vec::with_capacity(findings.len());
+        for finding in findings {
+            let suggestion = sqlx::query_as::<_, CardLintSuggestion>(
+                r#"
+                INSERT INTO card_lint_suggestions
+                    (user_id, deck_id, card_id, kind, source, message, suggested_front, suggested_back)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id, deck_id, card_id, kind, source, message, suggested_front, suggested_back,
+                    created_at, applied_at
+                "#,
+            )
+            .bind(user_id)
+            .bind(deck_id)
+            .bind(finding.card_id)
+            .bind(finding.kind)
+            .bind(finding.source)
+            .bind(&finding.message)
+            .bind(&finding.front)
+            .bind(&finding.back)
+            .fetch_one(&mut *tx)
+            .await?;
+            suggestions.push(suggestion);
+        }
+
+        tx.commit().await?;
+
+        Ok(DeckLintReport {
+            deck_id,
+            cards_checked: cards.len(),
+            ai_reviewed,
+            model,
+            tokens_used,
+            suggestions,
+        })
+    }
+
+    /// Apply the rewrites of the given suggestions, all or nothing. Returns
+    /// the updated cards.
+    pub async fn apply(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: ApplyLintSuggestionsDto,
+    ) -> Result<Vec<Card>> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+
+        let unique: HashSet<&Uuid> = dto.suggestion_ids.iter().collect();
+        if unique.len() != dto.suggestion_ids.len() {
+            return Err(AppError::ValidationError("Duplicate suggestion in request".to_string()));
+        }
+
+        let mut tx = db.begin().await?;
+
+        let suggestions = sqlx::query_as::<_, CardLintSuggestion>(
+            r#"
+            SELECT id, deck_id, card_id, kind, source, message, suggested_front, suggested_back,
+                created_at, applied_at
+            FROM card_lint_suggestions
+            WHERE id = ANY($1) AND user_id = $2 AND deck_id = $3 AND applied_at IS NULL
+                AND (suggested_front IS NOT NULL OR suggested_back IS NOT NULL)
+            ORDER BY created_at, id
+            FOR UPDATE
+            "#,
+        )
+        .bind(&dto.suggestion_ids)
+        .bind(user_id)
+        .bind(deck_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if suggestions.len() != dto.suggestion_ids.len() {
+            return Err(AppError::BadRequest(
+                "Some suggestions were not found, have no fix or have already been applied".to_string(),
+            ));
+        }
+
+        // Several fixes to one card apply in order; the card is returned once
+        let mut updated: Vec<Card> = Vec::new();
+        for suggestion in &suggestions {
+            let card = sqlx::query_as::<_, Card>(
+                r#"
+                UPDATE cards
+                SET front = COALESCE($2, front), back = COALESCE($3, back)
+                WHERE id = $1 AND deleted_at IS NULL
+                RETURNING id, deck_id, front, back, position, created_at, updated_at
+                "#,
+            )
+            .bind(suggestion.card_id)
+            .bind(&suggestion.suggested_front)
+            .bind(&suggestion.suggested_back)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(AppError::CardNotFound)?;
+
+            updated.retain(|c| c.id != card.id);
+            updated.push(card);
+        }
+
+        sqlx::query("UPDATE card_lint_suggestions SET applied_at = NOW() WHERE id = ANY($1)")
+            .bind(&dto.suggestion_ids)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(updated)
+    }
+}
+
+/// Local checks of `(id, front, back)` cards in deck order: answers given
+/// away by the question, overly long answers and repeated questions
+pub fn heuristic_issues(cards: &[(Uuid, String, String)]) -> Vec<LintFinding> {
+    let numbers: HashMap<Uuid, usize> = cards.iter().enumerate().map(|(i, (id, _, _))| (*id, i + 1)).collect();
+    let mut detector = DuplicateDetector::new();
+    let mut findings = Vec::new();
+
+    for (card_id, front, back) in cards {
+        if leaks_answer(front, back) {
+            findings.push(LintFinding {
+                card_id: *card_id,
+                kind: LintIssueKind::AnswerLeak,
+                source: LintSource::Heuristic,
+                message: "The answer appears in the question".to_string(),
+                front: blank_answer(front, back),
+                back: None,
+            });
+        }
+
+        let answer_chars = back.chars().count();
+        if answer_chars > LONG_ANSWER_CHARS {
+            findings.push(LintFinding {
+                card_id: *card_id,
+                kind: LintIssueKind::LongAnswer,
+                source: LintSource::Heuristic,
+                message: format!(
+                    "The answer is {} characters long; consider shortening it or splitting the card",
+                    answer_chars
+                ),
+                front: None,
+                back: None,
+            });
+        }
+
+        if let Some((original, _)) = detector.find(front) {
+            findings.push(LintFinding {
+                card_id: *card_id,
+                kind: LintIssueKind::Duplicate,
+                source: LintSource::Heuristic,
+                message: format!("Asks the same question as card {}", numbers[&original]),
+                front: None,
+                back: None,
+            });
+        }
+        detector.add(*card_id, front);
+    }
+
+    findings
+}
+
+/// Whether the whole answer appears in the question, ignoring case,
+/// punctuation and spacing
+fn leaks_answer(front: &str, back: &str) -> bool {
+    let answer = normalize(back);
+    answer.chars().count() >= MIN_LEAK_CHARS
+        && format!(" {} ", normalize(front)).contains(&format!(" {} ", answer))
+}
+
+/// The question with the answer blanked out, when it appears there
+/// verbatim apart from case
+fn blank_answer(front: &str, back: &str) -> Option<String> {
+    let fold = |text: &str| -> Vec<char> { text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect() };
+    let chars: Vec<char> = front.chars().collect();
+    let haystack = fold(front);
+    let needle = fold(back.trim());
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+
+    let start = haystack.windows(needle.len()).position(|w| w == needle.as_slice())?;
+    let mut blanked: String = chars[..start].iter().collect();
+    blanked.push_str("_____");
+    blanked.extend(&chars[start + needle.len()..]);
+    Some(blanked)
+}
+
+/// Add the model's findings for the cards it was sent. A local finding of
+/// the same kind wins unless only the model offers a fix.
+fn merge_ai_issues(findings: &mut Vec<LintFinding>, cards: &[(Uuid, String, String)], issues: Vec<GeneratedLintIssue>) {
+    for issue in issues {
+        let Some((card_id, front, back)) = issue.card.checked_sub(1).and_then(|i| cards.get(i)) else {
+            continue;
+        };
+        let kind = match issue.kind.as_str() {
+            "answer_leak" => LintIssueKind::AnswerLeak,
+            "long_answer" => LintIssueKind::LongAnswer,
+            "duplicate" => LintIssueKind::Duplicate,
+            "ambiguous" => LintIssueKind::Ambiguous,
+            _ => continue,
+        };
+        let message: String = issue.message.trim().chars().take(MAX_MESSAGE_CHARS).collect();
+        if message.is_empty() {
+            continue;
+        }
+
+        let finding = LintFinding {
+            card_id: *card_id,
+            kind,
+            source: LintSource::Ai,
+            message,
+            front: rewrite(issue.front, front),
+            back: rewrite(issue.back, back),
+        };
+
+        match findings.iter().position(|f| f.card_id == *card_id && f.kind == kind) {
+            Some(i) if !findings[i].has_fix() && finding.has_fix() => findings[i] = finding,
+            Some(_) => {}
+            None => findings.push(finding),
+        }
+    }
+}
+
+/// A proposed text, if it is non-empty and actually changes the card
+fn rewrite(proposed: Option<String>, current: &str) -> Option<String> {
+    proposed
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty() && text != current.trim())
+}
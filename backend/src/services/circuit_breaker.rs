@@ -0,0 +1,69 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Stops calls to a failing upstream for a while so requests fail fast
+/// instead of each waiting out its retries. After `threshold` consecutive
+/// failures the breaker opens; once `cooldown` has passed it lets a single
+/// trial call through, closing again if that call succeeds.
+/// A threshold of 0 disables the breaker.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether a call may go ahead now
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        match state.opened_at {
+            None => true,
+            Some(opened_at) if opened_at.elapsed() >= self.cooldown && !state.trial_in_flight => {
+                state.trial_in_flight = true;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        *state = BreakerState::default();
+    }
+
+    pub fn record_failure(&self) {
+        if self.threshold == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        state.consecutive_failures += 1;
+        // A failed trial call restarts the cooldown
+        if state.trial_in_flight || state.consecutive_failures >= self.threshold {
+            state.opened_at = Some(Instant::now());
+            state.trial_in_flight = false;
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state.lock().expect("circuit breaker lock poisoned").opened_at.is_some()
+    }
+}
@@ -0,0 +1,214 @@
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{models::admin::ConsistencyCheckResult, utils::Result};
+
+const SAMPLE_SIZE: usize = 20;
+
+/// Checks for data that is stored redundantly and can drift from its source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsistencyCheck {
+    /// `card_progress` rows whose card left the session's deck or whose user
+    /// differs from the session owner
+    OrphanedProgress,
+    /// Session counters that disagree with the recorded card progress
+    SessionCounters,
+    /// Decks where several cards share a position
+    DuplicatePositions,
+}
+
+impl ConsistencyCheck {
+    pub const ALL: &'static [ConsistencyCheck] = &[
+        ConsistencyCheck::OrphanedProgress,
+        ConsistencyCheck::SessionCounters,
+        ConsistencyCheck::DuplicatePositions,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ConsistencyCheck::OrphanedProgress => "orphaned_card_progress",
+            ConsistencyCheck::SessionCounters => "session_counters",
+            ConsistencyCheck::DuplicatePositions => "duplicate_card_positions",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ConsistencyCheck::OrphanedProgress => {
+                "Card progress that no longer matches its session's deck or user; deleted"
+            }
+            ConsistencyCheck::SessionCounters => {
+                "Study sessions whose studied/correct counters disagree with card progress; recounted"
+            }
+            ConsistencyCheck::DuplicatePositions => {
+                "Decks with cards sharing a position; renumbered in their current order"
+            }
+        }
+    }
+}
+
+/// Detects and repairs drift in denormalized data. Each check runs in its
+/// own transaction, so a dry run reports exactly what a repair would touch.
+pub struct ConsistencyService;
+
+impl ConsistencyService {
+    pub async fn run_check(
+        db: &PgPool,
+        check: ConsistencyCheck,
+        dry_run: bool,
+    ) -> Result<ConsistencyCheckResult> {
+        let mut tx = db.begin().await?;
+
+        let affected = match check {
+            ConsistencyCheck::OrphanedProgress => Self::find_orphaned_progress(&mut tx).await?,
+            ConsistencyCheck::SessionCounters => Self::find_session_counter_drift(&mut tx).await?,
+            ConsistencyCheck::DuplicatePositions => Self::find_duplicate_positions(&mut tx).await?,
+        };
+
+        let repaired = if dry_run || affected.is_empty() {
+            0
+        } else {
+            match check {
+                ConsistencyCheck::OrphanedProgress => {
+                    Self::delete_progress(&mut tx, &affected).await?
+                }
+                ConsistencyCheck::SessionCounters => {
+                    Self::recount_sessions(&mut tx, &affected).await?
+                }
+                ConsistencyCheck::DuplicatePositions => {
+                    Self::renumber_positions(&mut tx, &affected).await?
+                }
+            }
+        };
+
+        tx.commit().await?;
+
+        if repaired > 0 {
+            tracing::info!("Consistency check {} repaired {} rows", check.name(), repaired);
+        }
+
+        Ok(ConsistencyCheckResult {
+            name: check.name().to_string(),
+            description: check.description().to_string(),
+            found: affected.len() as i64,
+            repaired,
+            sample_ids: affected.into_iter().take(SAMPLE_SIZE).collect(),
+        })
+    }
+
+    // Detection
+
+    async fn find_orphaned_progress(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT cp.id
+            FROM card_progress cp
+            LEFT JOIN study_sessions s ON s.id = cp.session_id
+            LEFT JOIN cards c ON c.id = cp.card_id
+            WHERE s.id IS NULL
+                OR c.id IS NULL
+                OR c.deck_id <> s.deck_id
+                OR cp.user_id <> s.user_id
+            ORDER BY cp.id
+            FOR UPDATE OF cp
+            "#,
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn find_session_counter_drift(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT s.id
+            FROM study_sessions s
+            LEFT JOIN (
+                SELECT session_id,
+                       COUNT(*) as studied,
+                       COUNT(*) FILTER (WHERE rating <> 'again') as correct
+                FROM card_progress
+                GROUP BY session_id
+            ) p ON p.session_id = s.id
+            WHERE s.cards_studied <> COALESCE(p.studied, 0)
+                OR s.cards_correct <> COALESCE(p.correct, 0)
+            ORDER BY s.id
+            FOR UPDATE OF s
+            "#,
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn find_duplicate_positions(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT deck_id
+            FROM cards
+            GROUP BY deck_id
+            HAVING COUNT(*) <> COUNT(DISTINCT position)
+            ORDER BY deck_id
+            "#,
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(ids)
+    }
+
+    // Repairs
+
+    async fn delete_progress(tx: &mut Transaction<'_, Postgres>, ids: &[Uuid]) -> Result<i64> {
+        let result = sqlx::query("DELETE FROM card_progress WHERE id = ANY($1)")
+            .bind(ids)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn recount_sessions(tx: &mut Transaction<'_, Postgres>, ids: &[Uuid]) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE study_sessions s
+            SET cards_studied = (SELECT COUNT(*) FROM card_progress WHERE session_id = s.id),
+                cards_correct = (SELECT COUNT(*) FROM card_progress
+                                 WHERE session_id = s.id AND rating <> 'again'),
+                updated_at = NOW()
+            WHERE s.id = ANY($1)
+            "#,
+        )
+        .bind(ids)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    /// Reassign positions 0..n in the current order, breaking ties by age
+    async fn renumber_positions(tx: &mut Transaction<'_, Postgres>, deck_ids: &[Uuid]) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE cards c
+            SET position = ordered.new_position, updated_at = NOW()
+            FROM (
+                SELECT id,
+                       (ROW_NUMBER() OVER (PARTITION BY deck_id ORDER BY position, created_at, id) - 1)::INTEGER
+                           as new_position
+                FROM cards
+                WHERE deck_id = ANY($1)
+            ) ordered
+            WHERE c.id = ordered.id AND c.position <> ordered.new_position
+            "#,
+        )
+        .bind(deck_ids)
+        .execute(&mut **tx)
+        .await?;
+
+        // Report decks, not individual cards, to match detection
+        Ok(if result.rows_affected() > 0 { deck_ids.len() as i64 } else { 0 })
+    }
+}
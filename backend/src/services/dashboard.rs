@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    models::{Dashboard, DashboardDeck, DashboardStreak, Deck, DeckWithStats},
+    services::{folder::FolderService, stats::StatsService, study::StudyService},
+    utils::Result,
+};
+
+/// Sessions listed on the dashboard
+const RECENT_SESSIONS: i64 = 5;
+
+#[derive(Debug, FromRow)]
+struct DeckRow {
+    id: Uuid,
+    folder_id: Option<Uuid>,
+    user_id: Uuid,
+    name: String,
+    description: Option<String>,
+    is_public: bool,
+    front_language: Option<String>,
+    back_language: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    is_encrypted: bool,
+    cover_storage_key: Option<String>,
+    card_count: i64,
+    last_studied: Option<DateTime<Utc>>,
+    due_count: i64,
+    new_count: i64,
+}
+
+/// The home screen in one response. Its four parts are independent, so
+/// their queries run concurrently.
+pub struct DashboardService;
+
+impl DashboardService {
+    pub async fn get(db: &PgPool, user_id: Uuid) -> Result<Dashboard> {
+        let (folders, decks, stats, recent_sessions) = tokio::try_join!(
+            FolderService::get_tree(db, user_id),
+            Self::decks(db, user_id),
+            StatsService::get(db, user_id),
+            StudyService::get_user_study_sessions(db, user_id, Some(RECENT_SESSIONS)),
+        )?;
+
+        let today = Utc::now().date_naive();
+        Ok(Dashboard {
+            folders,
+            total_due: decks.iter().map(|d| d.due_count).sum(),
+            total_new: decks.iter().map(|d| d.new_count).sum(),
+            decks,
+            streak: DashboardStreak {
+                current_days: stats.stats.current_streak_days,
+                longest_days: stats.stats.longest_streak_days,
+                studied_today: stats.stats.last_study_date == Some(today),
+            },
+            recent_sessions,
+        })
+    }
+
+    /// The user's decks with card counts and due work, in one query
+    async fn decks(db: &PgPool, user_id: Uuid) -> Result<Vec<DashboardDeck>> {
+        let rows = sqlx::query_as::<_, DeckRow>(
+            r#"
+            SELECT d.id, d.folder_id, d.owner_id as user_id, d.title as name, d.description,
+                   d.is_public, d.front_language, d.back_language, d.created_at, d.updated_at, d.is_encrypted,
+                   d.cover_storage_key, d.card_count::BIGINT as card_count,
+                   ss.last_studied,
+                   COALESCE(c.due_count, 0) as due_count,
+                   COALESCE(c.new_count, 0) as new_count
+            FROM decks d
+            LEFT JOIN LATERAL (
+                SELECT COUNT(*) FILTER (
+                           WHERE NOT COALESCE(f.suspended, false) AND COALESCE(s.times_seen, 0) > 0
+                             AND (s.next_review_at IS NULL OR s.next_review_at <= NOW())
+                       ) as due_count,
+                       COUNT(*) FILTER (
+                           WHERE NOT COALESCE(f.suspended, false) AND COALESCE(s.times_seen, 0) = 0
+                       ) as new_count
+                FROM cards
+                LEFT JOIN user_card_stats s ON s.card_id = cards.id AND s.user_id = $1
+                LEFT JOIN user_card_flags f ON f.card_id = cards.id AND f.user_id = $1
+                WHERE cards.deck_id = d.id AND cards.deleted_at IS NULL
+            ) c ON true
+            LEFT JOIN LATERAL (
+                SELECT MAX(started_at) as last_studied
+                FROM study_sessions
+                WHERE deck_id = d.id AND user_id = $1
+            ) ss ON true
+            WHERE d.owner_id = $1 AND d.deleted_at IS NULL
+            ORDER BY d.title
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| DashboardDeck {
+                deck: DeckWithStats {
+                    deck: Deck {
+                        id: r.id,
+                        folder_id: r.folder_id,
+                        user_id: r.user_id,
+                        name: r.name,
+                        description: r.description,
+                        is_public: r.is_public,
+                        front_language: r.front_language,
+                        back_language: r.back_language,
+                        created_at: r.created_at,
+                        updated_at: r.updated_at,
+                    },
+                    card_count: r.card_count,
+                    last_studied: r.last_studied,
+                    is_encrypted: r.is_encrypted,
+                    cover_url: None,
+                    cover_storage_key: r.cover_storage_key,
+                },
+                due_count: r.due_count,
+                new_count: r.new_count,
+            })
+            .collect())
+    }
+}
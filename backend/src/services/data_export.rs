@@ -0,0 +1,302 @@
+use async_zip::{tokio::write::ZipFileWriter, Compression, ZipEntryBuilder};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value as JsonValue};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::EmailConfig,
+    models::{
+        import_export::DataExportManifest,
+        job::{BackgroundJob, JobSummary},
+    },
+    services::{
+        achievement::AchievementService,
+        ai_privacy::AiPrivacyService,
+        exporters::{JsonExporter, JSON_FORMAT_VERSION},
+        import_export::{archive_file_name, ImportExportService, MANIFEST_FILE_NAME},
+        job::JobService,
+        mailer::Mailer,
+        notification::NotificationService,
+        preferences::PreferencesService,
+        stats::StatsService,
+        storage::MediaStore,
+        user::UserService,
+    },
+    utils::{AppError, Result},
+};
+
+pub const DATA_EXPORT_JOB_TYPE: &str = "data_export";
+/// How long the emailed download link works; the archive is deleted after
+pub const DATA_EXPORT_RETENTION_DAYS: i64 = 7;
+
+/// Personal data exports: a ZIP of everything stored about a user, built by
+/// a background job, kept in media storage and linked to by email. Progress
+/// shows in the job center like any other background job.
+pub struct DataExportService;
+
+impl DataExportService {
+    /// Start an export, or return the one already running
+    pub async fn request(
+        db: &PgPool,
+        media: &Arc<MediaStore>,
+        mailer: &Arc<dyn Mailer>,
+        email: &EmailConfig,
+        user_id: Uuid,
+    ) -> Result<JobSummary> {
+        let running = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM background_jobs
+            WHERE user_id = $1 AND job_type = $2 AND status IN ('pending', 'processing')
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .bind(DATA_EXPORT_JOB_TYPE)
+        .fetch_optional(db)
+        .await?;
+        if let Some(id) = running {
+            return JobService::get_user_job(db, id, user_id).await;
+        }
+
+        let job = JobService::create_job(db, user_id, DATA_EXPORT_JOB_TYPE, json!({}), None, false).await?;
+        Self::spawn(db.clone(), media.clone(), mailer.clone(), email.clone(), job.clone());
+
+        JobService::get_user_job(db, job.id, user_id).await
+    }
+
+    /// Build the archive, store it and email the link, recording the outcome
+    /// on the job. Returns the job's result and download URL.
+    pub async fn run(
+        db: &PgPool,
+        media: &MediaStore,
+        mailer: &dyn Mailer,
+        email: &EmailConfig,
+        job: &BackgroundJob,
+    ) -> Result<(JsonValue, String)> {
+        let (archive, files) = Self::build_archive(db, media, job.user_id, job.id).await?;
+        let size_bytes = archive.len();
+        let key = archive_key(job.user_id, job.id);
+        media.put(&key, archive, "application/zip").await?;
+
+        let (url, expires_at) = media.signed_url_valid_for(&key, Duration::days(DATA_EXPORT_RETENTION_DAYS));
+        let url = if url.starts_with('/') {
+            format!("{}{}", email.api_url.trim_end_matches('/'), url)
+        } else {
+            url
+        };
+
+        // The link is on the job too, so a failed email is not fatal
+        let (address, display_name) = sqlx::query_as::<_, (String, Option<String>)>(
+            "SELECT email, display_name FROM users WHERE id = $1",
+        )
+        .bind(job.user_id)
+        .fetch_one(db)
+        .await?;
+        let (subject, body) = export_ready_email(display_name.as_deref(), &url, expires_at);
+        if let Err(e) = mailer.send(&address, &subject, &body).await {
+            tracing::warn!("Data export email to user {} failed: {}", job.user_id, e);
+        }
+
+        let result = json!({
+            "files": files,
+            "size_bytes": size_bytes,
+            "expires_at": expires_at,
+        });
+        Ok((result, url))
+    }
+
+    /// Delete archives whose link has expired, and those of deleted
+    /// accounts. Returns how many were removed.
+    pub async fn purge_expired(db: &PgPool, media: &MediaStore) -> Result<u64> {
+        let expired = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT j.id, j.user_id
+            FROM background_jobs j
+            JOIN users u ON u.id = j.user_id
+            WHERE j.job_type = $1 AND j.status = 'completed' AND j.result_url IS NOT NULL
+              AND (j.completed_at < NOW() - make_interval(days => $2) OR u.deleted_at IS NOT NULL)
+            "#,
+        )
+        .bind(DATA_EXPORT_JOB_TYPE)
+        .bind(DATA_EXPORT_RETENTION_DAYS as i32)
+        .fetch_all(db)
+        .await?;
+
+        let mut purged = 0;
+        for (job_id, user_id) in expired {
+            media.delete(&archive_key(user_id, job_id)).await?;
+            sqlx::query("UPDATE background_jobs SET result_url = NULL, updated_at = NOW() WHERE id = $1")
+                .bind(job_id)
+                .execute(db)
+                .await?;
+            purged += 1;
+        }
+
+        Ok(purged)
+    }
+
+    /// Run `purge_expired` in the background every `every`
+    pub fn spawn_sweeper(db: PgPool, media: Arc<MediaStore>, every: std::time::Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match Self::purge_expired(&db, &media).await {
+                    Ok(0) => {}
+                    Ok(purged) => tracing::info!("Deleted {} expired data exports", purged),
+                    Err(e) => tracing::warn!("Data export sweep failed: {}", e),
+                }
+            }
+        });
+    }
+
+    fn spawn(db: PgPool, media: Arc<MediaStore>, mailer: Arc<dyn Mailer>, email: EmailConfig, job: BackgroundJob) {
+        tokio::spawn(async move {
+            if let Err(e) = JobService::mark_processing(&db, job.id).await {
+                tracing::error!("Failed to start job {}: {}", job.id, e);
+                return;
+            }
+
+            let recorded = match Self::run(&db, &media, mailer.as_ref(), &email, &job).await {
+                Ok((result, url)) => JobService::complete_job(&db, job.id, result, Some(url)).await,
+                Err(e) => {
+                    tracing::error!("Data export for user {} failed: {}", job.user_id, e);
+                    JobService::fail_job(&db, job.id, &e.to_string()).await
+                }
+            };
+
+            if let Err(e) = recorded {
+                tracing::error!("Failed to record outcome of job {}: {}", job.id, e);
+            }
+        });
+    }
+
+    // The user's own decks as JSON with progress and media, then one JSON
+    // file for each other kind of data, then the manifest
+    async fn build_archive(
+        db: &PgPool,
+        media: &MediaStore,
+        user_id: Uuid,
+        job_id: Uuid,
+    ) -> Result<(Vec<u8>, Vec<String>)> {
+        let mut zip = ZipFileWriter::with_tokio(Vec::new());
+        let mut files = Vec::new();
+
+        let deck_ids = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM decks WHERE owner_id = $1 AND deleted_at IS NULL ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+        for (index, deck_id) in deck_ids.iter().enumerate() {
+            let (deck, _, data) =
+                ImportExportService::render_deck(db, media, user_id, *deck_id, &JsonExporter, true, true).await?;
+            let file = format!("decks/{}", archive_file_name(index, &deck.name, "json"));
+            write_entry(&mut zip, &file, &data).await?;
+            files.push(file);
+            JobService::update_progress(db, job_id, ((index + 1) * 80 / deck_ids.len()) as i32).await?;
+        }
+
+        let documents = [
+            ("profile.json", json!(UserService::get_profile(db, user_id).await?)),
+            ("study_history.json", Self::study_history(db, user_id).await?),
+            ("stats.json", json!(StatsService::get(db, user_id).await?)),
+            ("achievements.json", json!(AchievementService::list(db, user_id).await?)),
+            (
+                "ai_settings.json",
+                json!({
+                    "privacy": AiPrivacyService::get(db, user_id).await?,
+                    "usage": Self::rows_as_json(db, "ai_usage", "usage_date", user_id).await?,
+                }),
+            ),
+            (
+                "preferences.json",
+                json!({
+                    "study": PreferencesService::get(db, user_id).await?,
+                    "notifications": NotificationService::get_preferences(db, user_id).await?,
+                }),
+            ),
+        ];
+        for (file, document) in documents {
+            write_entry(&mut zip, file, &serde_json::to_vec_pretty(&document)?).await?;
+            files.push(file.to_string());
+        }
+
+        let manifest = DataExportManifest {
+            version: JSON_FORMAT_VERSION.to_string(),
+            exported_at: Utc::now(),
+            platform: "DeckOracle".to_string(),
+            user_id,
+            files: files.clone(),
+        };
+        write_entry(&mut zip, MANIFEST_FILE_NAME, &serde_json::to_vec_pretty(&manifest)?).await?;
+
+        let archive = zip.close().await.map_err(archive_error)?.into_inner();
+        Ok((archive, files))
+    }
+
+    // Sessions, answers and study events, including those of deleted decks
+    async fn study_history(db: &PgPool, user_id: Uuid) -> Result<JsonValue> {
+        Ok(json!({
+            "sessions": Self::rows_as_json(db, "study_sessions", "started_at", user_id).await?,
+            "answers": Self::rows_as_json(db, "card_progress", "studied_at", user_id).await?,
+            "events": Self::rows_as_json(db, "study_events", "created_at", user_id).await?,
+        }))
+    }
+
+    // Every row of the user's in a table, all columns, ordered by `order_by`
+    async fn rows_as_json(
+        db: &PgPool,
+        table: &'static str,
+        order_by: &'static str,
+        user_id: Uuid,
+    ) -> Result<JsonValue> {
+        let rows = sqlx::query_scalar::<_, JsonValue>(&format!(
+            "SELECT COALESCE(jsonb_agg(to_jsonb(t) ORDER BY t.{}), '[]'::jsonb) FROM {} t WHERE t.user_id = $1",
+            order_by, table
+        ))
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+/// Storage key of a data export archive
+pub fn archive_key(user_id: Uuid, job_id: Uuid) -> String {
+    format!("exports/{}/{}.zip", user_id, job_id)
+}
+
+/// Subject and plain-text body of the email with the download link
+pub fn export_ready_email(display_name: Option<&str>, url: &str, expires_at: DateTime<Utc>) -> (String, String) {
+    let greeting = match display_name {
+        Some(name) if !name.trim().is_empty() => format!("Hi {},", name.trim()),
+        _ => "Hi,".to_string(),
+    };
+    let body = format!(
+        "{}\n\nThe copy of your DeckOracle data you asked for is ready. It has your decks, \
+         study history, stats, achievements and settings.\n\n\
+         Download it: {}\n\n\
+         The link works until {} UTC, after which the file is deleted. \
+         If you did not ask for this export, change your password.\n",
+        greeting,
+        url,
+        expires_at.format("%Y-%m-%d %H:%M"),
+    );
+    ("Your data export is ready".to_string(), body)
+}
+
+async fn write_entry(zip: &mut ZipFileWriter<Vec<u8>>, file: &str, data: &[u8]) -> Result<()> {
+    zip.write_entry_whole(ZipEntryBuilder::new(file.to_string().into(), Compression::Deflate), data)
+        .await
+        .map_err(archive_error)
+}
+
+fn archive_error(e: async_zip::error::ZipError) -> AppError {
+    tracing::error!("Failed to write data export archive: {}", e);
+    AppError::InternalServerError
+}
@@ -1,10 +1,19 @@
+use chrono::{DateTime, Utc};
 use csv::{Reader, Writer};
 use sqlx::PgPool;
 use std::io::Cursor;
 use uuid::Uuid;
 
 use crate::{
-    models::{Card, CreateDeckDto, CsvCard, Deck, DeckWithStats, UpdateDeckDto},
+    models::{
+        Card, CreateDeckDto, CsvCard, Deck, DeckRatingScale, DeckRole, DeckWithStats, RatingScale,
+        UpdateDeckDto,
+    },
+    services::{
+        encryption::EncryptionService,
+        marketplace::{normalize_language, normalize_tags, MarketplaceService},
+        sharing::SharingService,
+    },
     utils::{AppError, Result},
 };
 
@@ -21,15 +30,17 @@ impl DeckService {
                 d.title as name,
                 d.description,
                 d.is_public,
+                d.front_language,
+                d.back_language,
                 d.created_at,
                 d.updated_at,
-                COUNT(c.id) as "card_count!",
-                MAX(ss.started_at) as last_studied
+                d.is_encrypted,
+                d.cover_storage_key,
+                d.card_count::BIGINT as "card_count!",
+                (SELECT MAX(ss.started_at) FROM study_sessions ss
+                 WHERE ss.deck_id = d.id AND ss.user_id = d.owner_id) as last_studied
             FROM decks d
-            LEFT JOIN cards c ON c.deck_id = d.id
-            LEFT JOIN study_sessions ss ON ss.deck_id = d.id AND ss.user_id = d.owner_id
-            WHERE d.owner_id = $1
-            GROUP BY d.id
+            WHERE d.owner_id = $1 AND d.deleted_at IS NULL
             ORDER BY d.title
             "#,
             user_id
@@ -45,17 +56,53 @@ impl DeckService {
                 name: r.name,
                 description: r.description,
                 is_public: r.is_public,
+                front_language: r.front_language,
+                back_language: r.back_language,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             },
             card_count: r.card_count,
             last_studied: r.last_studied,
+            is_encrypted: r.is_encrypted,
+            cover_url: None,
+            cover_storage_key: r.cover_storage_key,
         })
         .collect();
 
         Ok(decks)
     }
 
+    /// Fingerprint of everything `list_user_decks` returns: it changes when
+    /// a deck is added, edited or deleted, a card is added or removed, or a
+    /// session is started. Cheap enough to run before every listing.
+    pub async fn list_user_decks_version(db: &PgPool, user_id: Uuid) -> Result<String> {
+        let (deck_count, decks_updated, card_count, last_studied) = sqlx::query_as::<
+            _,
+            (i64, Option<DateTime<Utc>>, i64, Option<DateTime<Utc>>),
+        >(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM decks WHERE owner_id = $1 AND deleted_at IS NULL),
+                (SELECT MAX(updated_at) FROM decks WHERE owner_id = $1),
+                (SELECT COALESCE(SUM(card_count), 0)::BIGINT FROM decks
+                 WHERE owner_id = $1 AND deleted_at IS NULL),
+                (SELECT MAX(started_at) FROM study_sessions WHERE user_id = $1)
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(format!(
+            "{}:{}:{}:{}:{}",
+            user_id,
+            deck_count,
+            decks_updated.map_or(0, |t| t.timestamp_micros()),
+            card_count,
+            last_studied.map_or(0, |t| t.timestamp_micros()),
+        ))
+    }
+
     pub async fn create_deck(
         db: &PgPool,
         user_id: Uuid,
@@ -82,18 +129,23 @@ impl DeckService {
             }
         }
 
+        let front_language = dto.front_language.as_deref().map(normalize_language);
+        let back_language = dto.back_language.as_deref().map(normalize_language);
+
         let deck = sqlx::query_as!(
             Deck,
             r#"
-            INSERT INTO decks (owner_id, folder_id, title, description, is_public)
-            VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, folder_id, owner_id as user_id, title as name, description, is_public, created_at, updated_at
+            INSERT INTO decks (owner_id, folder_id, title, description, is_public, front_language, back_language)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, folder_id, owner_id as user_id, title as name, description, is_public, front_language, back_language, created_at, updated_at
             "#,
             user_id,
             dto.folder_id,
             dto.name,
             dto.description,
-            dto.is_public.unwrap_or(false)
+            dto.is_public.unwrap_or(false),
+            front_language,
+            back_language
         )
         .fetch_one(db)
         .await?;
@@ -101,20 +153,60 @@ impl DeckService {
         Ok(deck)
     }
 
+    /// The oldest deck the user owns with this title, created if there is
+    /// none. Used for system decks such as capture inboxes.
+    pub async fn find_or_create_owned(
+        db: &PgPool,
+        user_id: Uuid,
+        title: &str,
+        description: &str,
+    ) -> Result<Uuid> {
+        let existing = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM decks WHERE owner_id = $1 AND title = $2 AND deleted_at IS NULL ORDER BY created_at LIMIT 1",
+        )
+        .bind(user_id)
+        .bind(title)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some(deck_id) = existing {
+            return Ok(deck_id);
+        }
+
+        let deck = Self::create_deck(
+            db,
+            user_id,
+            CreateDeckDto {
+                name: title.to_string(),
+                description: Some(description.to_string()),
+                folder_id: None,
+                is_public: Some(false),
+                front_language: None,
+                back_language: None,
+            },
+        )
+        .await?;
+
+        Ok(deck.id)
+    }
+
     pub async fn get_deck(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<Deck> {
         let deck = sqlx::query_as!(
             Deck,
             r#"
-            SELECT id, folder_id, owner_id as user_id, title as name, description, is_public, created_at, updated_at
+            SELECT id, folder_id, owner_id as user_id, title as name, description, is_public, front_language, back_language, created_at, updated_at
             FROM decks
-            WHERE id = $1 AND (owner_id = $2 OR is_public = true)
+            WHERE id = $1 AND deleted_at IS NULL AND (
+                owner_id = $2 OR is_public = true
+                OR EXISTS(SELECT 1 FROM deck_shares s WHERE s.deck_id = decks.id AND s.user_id = $2)
+            )
             "#,
             id,
             user_id
         )
         .fetch_optional(db)
         .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+        .ok_or(AppError::DeckNotFound)?;
 
         Ok(deck)
     }
@@ -133,22 +225,27 @@ impl DeckService {
                 d.title as name,
                 d.description,
                 d.is_public,
+                d.front_language,
+                d.back_language,
                 d.created_at,
                 d.updated_at,
-                COUNT(c.id) as "card_count!",
-                MAX(ss.started_at) as last_studied
+                d.is_encrypted,
+                d.cover_storage_key,
+                d.card_count::BIGINT as "card_count!",
+                (SELECT MAX(ss.started_at) FROM study_sessions ss
+                 WHERE ss.deck_id = d.id AND ss.user_id = $2) as last_studied
             FROM decks d
-            LEFT JOIN cards c ON c.deck_id = d.id
-            LEFT JOIN study_sessions ss ON ss.deck_id = d.id AND ss.user_id = $2
-            WHERE d.id = $1 AND (d.owner_id = $2 OR d.is_public = true)
-            GROUP BY d.id
+            WHERE d.id = $1 AND d.deleted_at IS NULL AND (
+                d.owner_id = $2 OR d.is_public = true
+                OR EXISTS(SELECT 1 FROM deck_shares s WHERE s.deck_id = d.id AND s.user_id = $2)
+            )
             "#,
             id,
             user_id
         )
         .fetch_optional(db)
         .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+        .ok_or(AppError::DeckNotFound)?;
 
         Ok(DeckWithStats {
             deck: Deck {
@@ -158,11 +255,16 @@ impl DeckService {
                 name: deck_stats.name,
                 description: deck_stats.description,
                 is_public: deck_stats.is_public,
+                front_language: deck_stats.front_language,
+                back_language: deck_stats.back_language,
                 created_at: deck_stats.created_at,
                 updated_at: deck_stats.updated_at,
             },
             card_count: deck_stats.card_count,
             last_studied: deck_stats.last_studied,
+            is_encrypted: deck_stats.is_encrypted,
+            cover_url: None,
+            cover_storage_key: deck_stats.cover_storage_key,
         })
     }
 
@@ -172,22 +274,14 @@ impl DeckService {
         user_id: Uuid,
         dto: UpdateDeckDto,
     ) -> Result<Deck> {
-        // Verify ownership
-        let existing = sqlx::query!(
-            r#"
-            SELECT owner_id as user_id
-            FROM decks
-            WHERE id = $1
-            "#,
-            id
-        )
-        .fetch_optional(db)
-        .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
-
-        if existing.user_id != user_id {
+        // Editors may change content; moving or publishing stays with the owner
+        let role = SharingService::require_deck_role(db, id, user_id, DeckRole::Editor).await?;
+        if role != DeckRole::Owner && (dto.folder_id.is_some() || dto.is_public.is_some()) {
             return Err(AppError::Forbidden);
         }
+        if dto.is_public == Some(true) && EncryptionService::is_encrypted(db, id).await? {
+            return Err(AppError::BadRequest("Encrypted decks cannot be made public".to_string()));
+        }
 
         // Verify folder ownership if folder_id is being updated
         if let Some(folder_id) = dto.folder_id {
@@ -210,36 +304,59 @@ impl DeckService {
             }
         }
 
+        let tags = dto.tags.as_deref().map(normalize_tags).transpose()?;
+        let language = dto.language.as_deref().map(normalize_language);
+        let front_language = dto.front_language.as_deref().map(normalize_language);
+        let back_language = dto.back_language.as_deref().map(normalize_language);
+
         let deck = sqlx::query_as!(
             Deck,
             r#"
             UPDATE decks
             SET 
-                title = COALESCE($3, title),
-                description = COALESCE($4, description),
-                folder_id = COALESCE($5, folder_id),
-                is_public = COALESCE($6, is_public)
-            WHERE id = $1 AND owner_id = $2
-            RETURNING id, folder_id, owner_id as user_id, title as name, description, is_public, created_at, updated_at
+                title = COALESCE($2, title),
+                description = COALESCE($3, description),
+                folder_id = COALESCE($4, folder_id),
+                is_public = COALESCE($5, is_public),
+                tags = COALESCE($6, tags),
+                language = COALESCE($7, language),
+                front_language = COALESCE($8, front_language),
+                back_language = COALESCE($9, back_language)
+            WHERE id = $1
+              AND ($10::timestamptz IS NULL OR updated_at = $10)
+            RETURNING id, folder_id, owner_id as user_id, title as name, description, is_public, front_language, back_language, created_at, updated_at
             "#,
             id,
-            user_id,
             dto.name,
             dto.description,
             dto.folder_id,
-            dto.is_public
+            dto.is_public,
+            tags.as_deref(),
+            language,
+            front_language,
+            back_language,
+            dto.updated_at
         )
-        .fetch_one(db)
+        .fetch_optional(db)
         .await?;
 
-        Ok(deck)
+        match deck {
+            Some(deck) => Ok(deck),
+            None if dto.updated_at.is_some() => {
+                let current = Self::get_deck(db, id, user_id).await?;
+                Err(AppError::edit_conflict("deck", current.id, current.updated_at, &current))
+            }
+            None => Err(AppError::DeckNotFound),
+        }
     }
 
+    /// Move a deck to the trash. It can be restored until the trash sweeper
+    /// purges it.
     pub async fn delete_deck(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<()> {
         let result = sqlx::query!(
             r#"
-            DELETE FROM decks
-            WHERE id = $1 AND owner_id = $2
+            UPDATE decks SET deleted_at = NOW()
+            WHERE id = $1 AND owner_id = $2 AND deleted_at IS NULL
             "#,
             id,
             user_id
@@ -248,23 +365,71 @@ impl DeckService {
         .await?;
 
         if result.rows_affected() == 0 {
-            return Err(AppError::NotFound("Resource not found".to_string()));
+            return Err(AppError::DeckNotFound);
         }
 
         Ok(())
     }
 
+    pub async fn get_rating_scale(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<DeckRatingScale> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+        let scale = Self::rating_scale_of(db, deck_id).await?;
+        Ok(Self::rating_scale_response(deck_id, scale))
+    }
+
+    /// Change the answer buttons offered for the deck. Past answers keep
+    /// their ratings; the scheduler maps every rating the same way on any scale.
+    pub async fn set_rating_scale(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        buttons: i16,
+    ) -> Result<DeckRatingScale> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+        let scale = RatingScale::from_buttons(buttons)
+            .ok_or(AppError::BadRequest("Rating scale must have 2, 3 or 4 buttons".to_string()))?;
+
+        sqlx::query("UPDATE decks SET rating_scale = $2, updated_at = NOW() WHERE id = $1")
+            .bind(deck_id)
+            .bind(scale.buttons())
+            .execute(db)
+            .await?;
+
+        Ok(Self::rating_scale_response(deck_id, scale))
+    }
+
+    pub async fn rating_scale_of(db: &PgPool, deck_id: Uuid) -> Result<RatingScale> {
+        let buttons = sqlx::query_scalar::<_, i16>("SELECT rating_scale FROM decks WHERE id = $1")
+            .bind(deck_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or(AppError::DeckNotFound)?;
+
+        Ok(RatingScale::from_buttons(buttons).unwrap_or(RatingScale::FourButton))
+    }
+
+    fn rating_scale_response(deck_id: Uuid, scale: RatingScale) -> DeckRatingScale {
+        DeckRatingScale {
+            deck_id,
+            buttons: scale.buttons(),
+            ratings: scale.ratings().to_vec(),
+        }
+    }
+
     pub async fn import_csv(
         db: &PgPool,
         deck_id: Uuid,
         user_id: Uuid,
         csv_content: String,
     ) -> Result<Vec<Card>> {
-        // Verify deck ownership
-        let deck = Self::get_deck(db, deck_id, user_id).await?;
-        if deck.user_id != user_id {
-            return Err(AppError::Forbidden);
-        }
+        // Verify edit access
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+        // Server-side imports would store plaintext in an encrypted deck
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
 
         // Parse CSV
         let mut reader = Reader::from_reader(Cursor::new(csv_content));
@@ -318,6 +483,7 @@ impl DeckService {
     ) -> Result<String> {
         // Verify deck access (owner or public)
         let deck = Self::get_deck(db, deck_id, user_id).await?;
+        MarketplaceService::record_download(db, deck_id, user_id).await?;
 
         // Get all cards for the deck
         let cards = sqlx::query_as!(
@@ -325,7 +491,7 @@ impl DeckService {
             r#"
             SELECT id, deck_id, front, back, position, created_at, updated_at
             FROM cards
-            WHERE deck_id = $1
+            WHERE deck_id = $1 AND deleted_at IS NULL
             ORDER BY position
             "#,
             deck_id
@@ -0,0 +1,243 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{DeckRole, MergeDecksDto, MergeDecksResult, SplitDeckDto, SplitDeckResult},
+    services::{
+        deck::DeckService,
+        duplicates::{DuplicateDetector, MatchKind},
+        encryption::EncryptionService,
+        marketplace::normalize_tags,
+        reverse_cards::ReverseCardService,
+        sharing::SharingService,
+    },
+    utils::{AppError, Result},
+};
+
+/// Reorganizing decks by moving cards between them. Cards keep their ids,
+/// so every learner's scheduling state, flags and history go with them.
+/// Only owners can merge or split, and encrypted decks are left alone since
+/// their cards cannot be compared or filtered.
+pub struct DeckMergeService;
+
+impl DeckMergeService {
+    /// Append the cards of `deck_ids` to the target deck, then move the
+    /// emptied decks to the trash. Cards whose front matches one already
+    /// kept, exactly or once normalized, go to the trash instead of moving.
+    pub async fn merge(db: &PgPool, user_id: Uuid, dto: MergeDecksDto) -> Result<MergeDecksResult> {
+        let mut deck_ids: Vec<Uuid> = Vec::with_capacity(dto.deck_ids.len());
+        for deck_id in dto.deck_ids {
+            if !deck_ids.contains(&deck_id) {
+                deck_ids.push(deck_id);
+            }
+        }
+        if deck_ids.len() < 2 {
+            return Err(AppError::ValidationError("Merging needs at least two different decks".to_string()));
+        }
+        let target_id = dto.target_deck_id.unwrap_or(deck_ids[0]);
+        if !deck_ids.contains(&target_id) {
+            return Err(AppError::ValidationError("target_deck_id must be one of deck_ids".to_string()));
+        }
+        for &deck_id in &deck_ids {
+            SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+            EncryptionService::ensure_plaintext(db, deck_id).await?;
+        }
+        let sources: Vec<Uuid> = deck_ids.iter().copied().filter(|id| *id != target_id).collect();
+
+        let mut tx = db.begin().await?;
+
+        let locked: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM (SELECT id FROM decks WHERE id = ANY($1) AND deleted_at IS NULL FOR UPDATE) d",
+        )
+        .bind(&deck_ids)
+        .fetch_one(&mut *tx)
+        .await?;
+        if locked as usize != deck_ids.len() {
+            return Err(AppError::DeckNotFound);
+        }
+
+        // The target's cards first, so they win over any duplicates
+        let cards = sqlx::query_as::<_, (Uuid, Uuid, String, Option<Uuid>)>(
+            r#"
+            SELECT id, deck_id, front, reverse_of FROM cards
+            WHERE deck_id = ANY($1) AND deleted_at IS NULL
+            ORDER BY deck_id <> $2, array_position($1, deck_id), position, created_at
+            "#,
+        )
+        .bind(&deck_ids)
+        .bind(target_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let deduplicate = dto.deduplicate.unwrap_or(true);
+        let mut detector = DuplicateDetector::new();
+        let (mut moved, mut duplicates, mut reverses) = (Vec::new(), Vec::new(), Vec::new());
+        for (card_id, deck_id, front, reverse_of) in cards {
+            if let Some(original) = reverse_of {
+                if deck_id != target_id {
+                    reverses.push((card_id, original));
+                }
+                continue;
+            }
+            if deck_id == target_id {
+                detector.add(card_id, &front);
+                continue;
+            }
+            let duplicate = deduplicate
+                && matches!(detector.find(&front), Some((_, MatchKind::Exact | MatchKind::Normalized)));
+            if duplicate {
+                duplicates.push(card_id);
+            } else {
+                detector.add(card_id, &front);
+                moved.push(card_id);
+            }
+        }
+        // Reverses go wherever their originals go
+        moved.extend(
+            reverses
+                .into_iter()
+                .filter(|(_, original)| !duplicates.contains(original))
+                .map(|(card_id, _)| card_id),
+        );
+
+        sqlx::query(
+            r#"
+            UPDATE cards c
+            SET deck_id = $1, position = (base.last + m.ord)::INT
+            FROM unnest($2::UUID[]) WITH ORDINALITY AS m(id, ord),
+                 (SELECT COALESCE(MAX(position), -1) AS last FROM cards WHERE deck_id = $1) base
+            WHERE c.id = m.id
+            "#,
+        )
+        .bind(target_id)
+        .bind(&moved)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE cards SET deleted_at = NOW() WHERE (id = ANY($1) OR reverse_of = ANY($1)) AND deleted_at IS NULL",
+        )
+        .bind(&duplicates)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE decks SET deleted_at = NOW() WHERE id = ANY($1)")
+            .bind(&sources)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        // Moved cards get reverses if the target keeps them
+        ReverseCardService::generate_if_enabled(db, target_id).await?;
+
+        Ok(MergeDecksResult {
+            deck: DeckService::get_deck_with_stats(db, target_id, user_id).await?,
+            moved_cards: moved.len() as i64,
+            duplicates_removed: duplicates.len() as i64,
+            merged_deck_ids: sources,
+        })
+    }
+
+    /// Move the deck's cards matching every filter in `dto`, with their
+    /// reverses, into a new deck in the same folder
+    pub async fn split(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: SplitDeckDto,
+    ) -> Result<SplitDeckResult> {
+        if dto.card_ids.is_none()
+            && dto.query.is_none()
+            && dto.tags.is_none()
+            && dto.starred.is_none()
+            && dto.suspended.is_none()
+            && dto.leech.is_none()
+        {
+            return Err(AppError::ValidationError(
+                "Choose the cards to move with card_ids, query, tags or a flag".to_string(),
+            ));
+        }
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+
+        let search_pattern = dto.query.as_deref().map(contains_pattern);
+        let tags = dto.tags.as_deref().map(normalize_tags).transpose()?;
+        if tags.as_ref().is_some_and(|tags| tags.is_empty()) {
+            return Err(AppError::ValidationError("tags cannot be empty".to_string()));
+        }
+
+        let mut tx = db.begin().await?;
+
+        let new_deck_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO decks (owner_id, folder_id, title, is_public, front_language, back_language)
+            SELECT owner_id, folder_id, $2, false, front_language, back_language
+            FROM decks WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id
+            "#,
+        )
+        .bind(deck_id)
+        .bind(dto.name.trim())
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::DeckNotFound)?;
+
+        let moved = sqlx::query(
+            r#"
+            WITH selected AS (
+                SELECT c.id FROM cards c
+                LEFT JOIN user_card_flags f ON f.card_id = c.id AND f.user_id = $3
+                WHERE c.deck_id = $1 AND c.deleted_at IS NULL
+                  AND ($4::UUID[] IS NULL OR c.id = ANY($4))
+                  AND ($5::TEXT IS NULL
+                       OR LOWER(c.front) LIKE LOWER($5) ESCAPE '\' OR LOWER(c.back) LIKE LOWER($5) ESCAPE '\')
+                  AND ($6::BOOLEAN IS NULL OR COALESCE(f.starred, false) = $6)
+                  AND ($7::BOOLEAN IS NULL OR COALESCE(f.suspended, false) = $7)
+                  AND ($8::BOOLEAN IS NULL OR COALESCE(f.leech, false) = $8)
+                  AND ($9::TEXT[] IS NULL OR COALESCE(f.tags, '{}') && $9)
+            ),
+            moving AS (
+                SELECT c.id, ROW_NUMBER() OVER (ORDER BY c.position, c.created_at) - 1 AS ord
+                FROM cards c
+                WHERE c.deck_id = $1 AND c.deleted_at IS NULL
+                  AND (c.id IN (SELECT id FROM selected) OR c.reverse_of IN (SELECT id FROM selected))
+            )
+            UPDATE cards c SET deck_id = $2, position = m.ord::INT
+            FROM moving m
+            WHERE c.id = m.id
+            "#,
+        )
+        .bind(deck_id)
+        .bind(new_deck_id)
+        .bind(user_id)
+        .bind(&dto.card_ids)
+        .bind(&search_pattern)
+        .bind(dto.starred)
+        .bind(dto.suspended)
+        .bind(dto.leech)
+        .bind(&tags)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // Dropping the transaction also discards the new deck
+        if moved == 0 {
+            return Err(AppError::BadRequest("No cards match the filter".to_string()));
+        }
+
+        tx.commit().await?;
+
+        Ok(SplitDeckResult {
+            deck: DeckService::get_deck_with_stats(db, new_deck_id, user_id).await?,
+            moved_cards: moved as i64,
+        })
+    }
+}
+
+/// A `LIKE` pattern matching text that contains `query`, with the pattern
+/// characters in `query` taken literally
+fn contains_pattern(query: &str) -> String {
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
@@ -0,0 +1,119 @@
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    models::{DeckRole, DeckSettings, UpdateDeckSettingsDto},
+    services::{reverse_cards::ReverseCardService, sharing::SharingService},
+    utils::Result,
+};
+
+/// Daily limits and scheduling preferences of a deck. They belong to the
+/// deck, so everyone studying a shared deck studies it the owner's way.
+pub struct DeckSettingsService;
+
+impl DeckSettingsService {
+    pub async fn get_settings(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<DeckSettings> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+        Self::settings_of(db, deck_id).await
+    }
+
+    /// Change any of the settings. A new algorithm applies from each card's
+    /// next answer, starting from the interval it already has; fewer Leitner
+    /// boxes move cards in the removed boxes to the new last one. Turning
+    /// reverse cards on creates them; turning them off trashes them.
+    pub async fn update_settings(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: UpdateDeckSettingsDto,
+    ) -> Result<DeckSettings> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+        let current = Self::settings_of(db, deck_id).await?;
+
+        let settings = sqlx::query_as::<_, DeckSettings>(
+            r#"
+            INSERT INTO deck_settings (
+                deck_id, new_cards_per_day, max_reviews_per_day, algorithm, lapse_action,
+                leitner_boxes, leitner_promotion, leitner_demotion, leech_threshold, leech_action,
+                generate_reverse_cards, bury_siblings
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (deck_id) DO UPDATE SET
+                new_cards_per_day = EXCLUDED.new_cards_per_day,
+                max_reviews_per_day = EXCLUDED.max_reviews_per_day,
+                algorithm = EXCLUDED.algorithm,
+                lapse_action = EXCLUDED.lapse_action,
+                leitner_boxes = EXCLUDED.leitner_boxes,
+                leitner_promotion = EXCLUDED.leitner_promotion,
+                leitner_demotion = EXCLUDED.leitner_demotion,
+                leech_threshold = EXCLUDED.leech_threshold,
+                leech_action = EXCLUDED.leech_action,
+                generate_reverse_cards = EXCLUDED.generate_reverse_cards,
+                bury_siblings = EXCLUDED.bury_siblings,
+                updated_at = NOW()
+            RETURNING deck_id, new_cards_per_day, max_reviews_per_day, algorithm, lapse_action,
+                      leitner_boxes, leitner_promotion, leitner_demotion, leech_threshold, leech_action,
+                      generate_reverse_cards, bury_siblings
+            "#,
+        )
+        .bind(deck_id)
+        .bind(dto.new_cards_per_day.unwrap_or(current.new_cards_per_day))
+        .bind(dto.max_reviews_per_day.unwrap_or(current.max_reviews_per_day))
+        .bind(dto.algorithm.unwrap_or(current.algorithm))
+        .bind(dto.lapse_action.unwrap_or(current.lapse_action))
+        .bind(dto.leitner_boxes.unwrap_or(current.leitner_boxes))
+        .bind(dto.leitner_promotion.unwrap_or(current.leitner_promotion))
+        .bind(dto.leitner_demotion.unwrap_or(current.leitner_demotion))
+        .bind(dto.leech_threshold.unwrap_or(current.leech_threshold))
+        .bind(dto.leech_action.unwrap_or(current.leech_action))
+        .bind(dto.generate_reverse_cards.unwrap_or(current.generate_reverse_cards))
+        .bind(dto.bury_siblings.unwrap_or(current.bury_siblings))
+        .fetch_one(db)
+        .await?;
+
+        match (current.generate_reverse_cards, settings.generate_reverse_cards) {
+            (false, true) => {
+                ReverseCardService::enable(db, deck_id).await?;
+            }
+            (true, false) => {
+                ReverseCardService::disable(db, deck_id).await?;
+            }
+            _ => {}
+        }
+
+        Ok(settings)
+    }
+
+    pub async fn settings_of(db: &PgPool, deck_id: Uuid) -> Result<DeckSettings> {
+        let mut settings = Self::settings_for(db, &[deck_id]).await?;
+        Ok(settings
+            .remove(&deck_id)
+            .unwrap_or_else(|| DeckSettings::defaults(deck_id)))
+    }
+
+    /// Settings of several decks at once; decks without a row get defaults
+    pub async fn settings_for(db: &PgPool, deck_ids: &[Uuid]) -> Result<HashMap<Uuid, DeckSettings>> {
+        let stored = sqlx::query_as::<_, DeckSettings>(
+            r#"
+            SELECT deck_id, new_cards_per_day, max_reviews_per_day, algorithm, lapse_action,
+                   leitner_boxes, leitner_promotion, leitner_demotion, leech_threshold, leech_action,
+                   generate_reverse_cards, bury_siblings
+            FROM deck_settings
+            WHERE deck_id = ANY($1)
+            "#,
+        )
+        .bind(deck_ids)
+        .fetch_all(db)
+        .await?;
+
+        let mut settings: HashMap<Uuid, DeckSettings> =
+            stored.into_iter().map(|s| (s.deck_id, s)).collect();
+        for deck_id in deck_ids {
+            settings
+                .entry(*deck_id)
+                .or_insert_with(|| DeckSettings::defaults(*deck_id));
+        }
+        Ok(settings)
+    }
+}
@@ -0,0 +1,401 @@
+use async_zip::{
+    base::write::ZipFileWriter, error::ZipError, tokio::read::seek::ZipFileReader, Compression,
+    ZipEntryBuilder,
+};
+use docx_rs::{DocumentChild, Paragraph, Table, TableCellContent, TableChild, TableRowChild};
+use std::path::Path;
+use tokio::io::{AsyncReadExt, BufReader};
+
+use crate::{
+    models::ai::{DocumentSection, ExtractedDocument},
+    services::upload::{self, FileContent, OLE2_MAGIC, PDF_MAGIC, SNIFF_BYTES, ZIP_MAGIC},
+    utils::{AppError, Result},
+};
+
+/// DOCX parts holding embedded images and objects rather than text
+const DOCX_MEDIA_PREFIXES: &[&str] = &["word/media/", "word/embeddings/"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Pdf,
+    Docx,
+    LegacyDoc,
+    Text,
+}
+
+impl DocumentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentKind::Pdf => "pdf",
+            DocumentKind::Docx => "docx",
+            DocumentKind::LegacyDoc => "doc",
+            DocumentKind::Text => "txt",
+        }
+    }
+}
+
+/// Text extraction for uploaded source documents
+pub struct DocumentService;
+
+impl DocumentService {
+    /// Detect the document type from its content, falling back to the declared
+    /// content type and file extension when the bytes are not conclusive
+    pub fn detect_kind(
+        bytes: &[u8],
+        filename: Option<&str>,
+        content_type: Option<&str>,
+    ) -> Option<DocumentKind> {
+        if bytes.starts_with(PDF_MAGIC) {
+            return Some(DocumentKind::Pdf);
+        }
+        if bytes.starts_with(OLE2_MAGIC) {
+            return Some(DocumentKind::LegacyDoc);
+        }
+        if bytes.starts_with(ZIP_MAGIC) {
+            // DOCX is a zip archive with the main part at word/document.xml
+            let is_docx = bytes
+                .windows(b"word/document.xml".len())
+                .any(|w| w == b"word/document.xml");
+            return is_docx.then_some(DocumentKind::Docx);
+        }
+
+        let extension = filename
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, ext)| ext.to_ascii_lowercase());
+
+        match (content_type, extension.as_deref()) {
+            (Some("application/pdf"), _) | (_, Some("pdf")) => None, // Claimed PDF without a PDF header
+            (Some(ct), _) if ct.starts_with("text/") => Some(DocumentKind::Text),
+            (_, Some("txt" | "md" | "csv")) => Some(DocumentKind::Text),
+            // `bytes` may be the head of a larger file
+            _ if upload::sniff(bytes) == Some(FileContent::Text) => Some(DocumentKind::Text),
+            _ => None,
+        }
+    }
+
+    pub fn extract(
+        bytes: &[u8],
+        filename: Option<&str>,
+        content_type: Option<&str>,
+    ) -> Result<ExtractedDocument> {
+        let kind = Self::detect_kind(bytes, filename, content_type)
+            .ok_or(AppError::FileUploadError("Unsupported or corrupt document".to_string()))?;
+
+        let sections = match kind {
+            DocumentKind::Pdf => Self::extract_pdf(bytes)?,
+            DocumentKind::Docx => Self::extract_docx(bytes)?,
+            DocumentKind::Text => Self::extract_text(bytes)?,
+            DocumentKind::LegacyDoc => {
+                return Err(AppError::FileUploadError(
+                    "Legacy .doc files are not supported, please save the document as .docx"
+                        .to_string(),
+                ))
+            }
+        };
+
+        Self::into_document(kind, filename, sections)
+    }
+
+    /// Like `extract`, for an upload spooled to disk, without reading the
+    /// whole file into memory: PDFs are parsed from the file, and DOCX files
+    /// are read without their images and embedded objects.
+    pub async fn extract_file(
+        path: &Path,
+        filename: Option<&str>,
+        content_type: Option<&str>,
+    ) -> Result<ExtractedDocument> {
+        let mut head = Vec::with_capacity(SNIFF_BYTES);
+        tokio::fs::File::open(path)
+            .await
+            .map_err(|e| read_error(path, e))?
+            .take(SNIFF_BYTES as u64)
+            .read_to_end(&mut head)
+            .await
+            .map_err(|e| read_error(path, e))?;
+        let owned_name = filename.map(str::to_string);
+
+        if head.starts_with(PDF_MAGIC) {
+            let path = path.to_path_buf();
+            return blocking(move || {
+                let document = lopdf::Document::load(&path)
+                    .map_err(|e| AppError::FileUploadError(format!("Invalid PDF file: {}", e)))?;
+                Self::into_document(DocumentKind::Pdf, owned_name.as_deref(), Self::pdf_sections(&document))
+            })
+            .await;
+        }
+
+        if head.starts_with(ZIP_MAGIC) {
+            let Some(archive) = Self::docx_without_media(path).await? else {
+                return Err(AppError::FileUploadError("Unsupported or corrupt document".to_string()));
+            };
+            return blocking(move || {
+                let sections = Self::extract_docx(&archive)?;
+                Self::into_document(DocumentKind::Docx, owned_name.as_deref(), sections)
+            })
+            .await;
+        }
+
+        // Anything else is only extracted as text, which is kept whole anyway
+        match Self::detect_kind(&head, filename, content_type) {
+            Some(DocumentKind::Text) => {
+                let bytes = tokio::fs::read(path).await.map_err(|e| read_error(path, e))?;
+                Self::into_document(DocumentKind::Text, filename, Self::extract_text(&bytes)?)
+            }
+            Some(DocumentKind::LegacyDoc) => Err(AppError::FileUploadError(
+                "Legacy .doc files are not supported, please save the document as .docx".to_string(),
+            )),
+            _ => Err(AppError::FileUploadError("Unsupported or corrupt document".to_string())),
+        }
+    }
+
+    /// Copy the parts of a DOCX that hold its text into a new archive in
+    /// memory, one part at a time. Images usually make up most of a
+    /// document's size and are left out. `None` when the archive has no
+    /// `word/document.xml`, so is not a DOCX.
+    async fn docx_without_media(path: &Path) -> Result<Option<Vec<u8>>> {
+        let file = tokio::fs::File::open(path).await.map_err(|e| read_error(path, e))?;
+        let mut reader = ZipFileReader::with_tokio(BufReader::new(file))
+            .await
+            .map_err(invalid_docx)?;
+
+        let names = reader
+            .file()
+            .entries()
+            .iter()
+            .map(|entry| entry.filename().as_str().map(str::to_string))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(invalid_docx)?;
+        if !names.iter().any(|name| name == "word/document.xml") {
+            return Ok(None);
+        }
+
+        let mut writer = ZipFileWriter::new(Vec::new());
+        for (index, name) in names.into_iter().enumerate() {
+            if DOCX_MEDIA_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+                continue;
+            }
+            let mut data = Vec::new();
+            reader
+                .reader_with_entry(index)
+                .await
+                .map_err(invalid_docx)?
+                .read_to_end_checked(&mut data)
+                .await
+                .map_err(invalid_docx)?;
+            writer
+                .write_entry_whole(ZipEntryBuilder::new(name.into(), Compression::Stored), &data)
+                .await
+                .map_err(invalid_docx)?;
+        }
+
+        Ok(Some(writer.close().await.map_err(invalid_docx)?))
+    }
+
+    /// Drop empty sections and count words and the title
+    fn into_document(
+        kind: DocumentKind,
+        filename: Option<&str>,
+        sections: Vec<DocumentSection>,
+    ) -> Result<ExtractedDocument> {
+        let sections: Vec<DocumentSection> = sections
+            .into_iter()
+            .filter(|s| s.heading.is_some() || !s.paragraphs.is_empty())
+            .collect();
+
+        if sections.is_empty() {
+            return Err(AppError::FileUploadError(
+                "No text could be extracted from the document".to_string(),
+            ));
+        }
+
+        let word_count = sections
+            .iter()
+            .flat_map(|s| s.heading.iter().chain(s.paragraphs.iter()))
+            .map(|text| text.split_whitespace().count())
+            .sum();
+
+        let title = sections
+            .iter()
+            .find(|s| s.level == 1)
+            .and_then(|s| s.heading.clone());
+
+        Ok(ExtractedDocument {
+            kind: kind.as_str().to_string(),
+            filename: filename.map(str::to_string),
+            title,
+            sections,
+            word_count,
+        })
+    }
+
+    fn extract_docx(bytes: &[u8]) -> Result<Vec<DocumentSection>> {
+        let docx = docx_rs::read_docx(bytes)
+            .map_err(|e| AppError::FileUploadError(format!("Invalid DOCX file: {}", e)))?;
+
+        let mut sections = vec![DocumentSection {
+            heading: None,
+            level: 0,
+            paragraphs: Vec::new(),
+        }];
+
+        for child in &docx.document.children {
+            match child {
+                DocumentChild::Paragraph(paragraph) => {
+                    let text = paragraph.raw_text().trim().to_string();
+                    if text.is_empty() {
+                        continue;
+                    }
+
+                    match Self::heading_level(paragraph) {
+                        Some(level) => sections.push(DocumentSection {
+                            heading: Some(text),
+                            level,
+                            paragraphs: Vec::new(),
+                        }),
+                        None => sections.last_mut().unwrap().paragraphs.push(text),
+                    }
+                }
+                DocumentChild::Table(table) => {
+                    let rows = Self::table_rows(table);
+                    sections.last_mut().unwrap().paragraphs.extend(rows);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(sections)
+    }
+
+    /// Map Word heading styles ("Title", "Heading1".."Heading9") to outline levels
+    fn heading_level(paragraph: &Paragraph) -> Option<u8> {
+        let style = paragraph.property.style.as_ref()?.val.to_ascii_lowercase();
+        if style == "title" {
+            return Some(1);
+        }
+
+        style
+            .strip_prefix("heading")
+            .and_then(|level| level.trim().parse::<u8>().ok())
+            .filter(|level| (1..=9).contains(level))
+    }
+
+    /// Flatten a table into one paragraph per row with cells separated by " | "
+    fn table_rows(table: &Table) -> Vec<String> {
+        table
+            .rows
+            .iter()
+            .filter_map(|TableChild::TableRow(row)| {
+                let cells: Vec<String> = row
+                    .cells
+                    .iter()
+                    .map(|TableRowChild::TableCell(cell)| {
+                        cell.children
+                            .iter()
+                            .filter_map(|content| match content {
+                                TableCellContent::Paragraph(p) => Some(p.raw_text()),
+                                _ => None,
+                            })
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                            .trim()
+                            .to_string()
+                    })
+                    .collect();
+
+                let row_text = cells.join(" | ");
+                (!row_text.replace('|', "").trim().is_empty()).then_some(row_text)
+            })
+            .collect()
+    }
+
+    fn extract_pdf(bytes: &[u8]) -> Result<Vec<DocumentSection>> {
+        let document = lopdf::Document::load_mem(bytes)
+            .map_err(|e| AppError::FileUploadError(format!("Invalid PDF file: {}", e)))?;
+        Ok(Self::pdf_sections(&document))
+    }
+
+    fn pdf_sections(document: &lopdf::Document) -> Vec<DocumentSection> {
+        let mut sections = Vec::new();
+        for page_number in document.get_pages().keys() {
+            let text = document.extract_text(&[*page_number]).unwrap_or_default();
+            // PDFs carry no heading structure, so each page becomes a section
+            sections.push(DocumentSection {
+                heading: Some(format!("Page {}", page_number)),
+                level: 2,
+                paragraphs: Self::split_paragraphs(&text),
+            });
+        }
+
+        sections
+    }
+
+    fn extract_text(bytes: &[u8]) -> Result<Vec<DocumentSection>> {
+        let text = String::from_utf8_lossy(bytes);
+        let mut sections = vec![DocumentSection {
+            heading: None,
+            level: 0,
+            paragraphs: Vec::new(),
+        }];
+
+        // Treat Markdown-style "#" lines as headings
+        for block in Self::split_paragraphs(&text) {
+            let hashes = block.chars().take_while(|c| *c == '#').count();
+            if (1..=6).contains(&hashes) && !block.contains('\n') {
+                sections.push(DocumentSection {
+                    heading: Some(block[hashes..].trim().to_string()),
+                    level: hashes as u8,
+                    paragraphs: Vec::new(),
+                });
+            } else {
+                sections.last_mut().unwrap().paragraphs.push(block);
+            }
+        }
+
+        Ok(sections)
+    }
+
+    /// Split on blank lines (and on heading lines) into trimmed paragraphs
+    fn split_paragraphs(text: &str) -> Vec<String> {
+        let mut paragraphs = Vec::new();
+        let mut current = Vec::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                if !current.is_empty() {
+                    paragraphs.push(current.join("\n"));
+                    current.clear();
+                }
+                if !line.is_empty() {
+                    paragraphs.push(line.to_string());
+                }
+            } else {
+                current.push(line);
+            }
+        }
+
+        if !current.is_empty() {
+            paragraphs.push(current.join("\n"));
+        }
+
+        paragraphs
+    }
+}
+
+/// Parsing is CPU-bound, so it runs off the async workers
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| {
+        tracing::error!("Document extraction task failed: {}", e);
+        AppError::InternalServerError
+    })?
+}
+
+fn read_error(path: &Path, error: std::io::Error) -> AppError {
+    tracing::error!("Reading spooled document {} failed: {}", path.display(), error);
+    AppError::InternalServerError
+}
+
+fn invalid_docx(error: ZipError) -> AppError {
+    AppError::FileUploadError(format!("Invalid DOCX file: {}", error))
+}
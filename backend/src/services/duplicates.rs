@@ -0,0 +1,83 @@
+//! Duplicate card detection for imports.
+//!
+//! Fronts are compared exactly, then normalized (case, punctuation and
+//! spacing ignored, as when grading typed answers), then by edit distance
+//! between the normalized texts.
+
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::services::grading::{levenshtein, normalize, MAX_GRADED_CHARS};
+
+/// Normalized fronts at least this similar count as duplicates
+pub const FUZZY_SIMILARITY: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Exact,
+    Normalized,
+    Fuzzy,
+}
+
+#[derive(Default)]
+pub struct DuplicateDetector {
+    exact: HashMap<String, Uuid>,
+    normalized: HashMap<String, Uuid>,
+    /// Normalized front, its length in chars and the card, for fuzzy matching
+    fronts: Vec<(String, usize, Uuid)>,
+}
+
+impl DuplicateDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember a card so later fronts are matched against it. The first
+    /// card added wins when several share a front.
+    pub fn add(&mut self, card_id: Uuid, front: &str) {
+        self.exact.entry(front.to_string()).or_insert(card_id);
+        let normalized = normalize(front);
+        if normalized.is_empty() {
+            return;
+        }
+        if !self.normalized.contains_key(&normalized) {
+            let chars = normalized.chars().count();
+            if chars <= MAX_GRADED_CHARS {
+                self.fronts.push((normalized.clone(), chars, card_id));
+            }
+            self.normalized.insert(normalized, card_id);
+        }
+    }
+
+    /// The card whose front `front` duplicates, if any, and how closely.
+    /// Fronts with nothing left after normalization only match exactly.
+    pub fn find(&self, front: &str) -> Option<(Uuid, MatchKind)> {
+        if let Some(card_id) = self.exact.get(front) {
+            return Some((*card_id, MatchKind::Exact));
+        }
+        let normalized = normalize(front);
+        if normalized.is_empty() {
+            return None;
+        }
+        if let Some(card_id) = self.normalized.get(&normalized) {
+            return Some((*card_id, MatchKind::Normalized));
+        }
+
+        let chars = normalized.chars().count();
+        if chars > MAX_GRADED_CHARS {
+            return None;
+        }
+        self.fronts
+            .iter()
+            // Skip fronts whose length alone rules out a close enough match
+            .filter(|(_, other_chars, _)| {
+                let longest = chars.max(*other_chars) as f64;
+                (chars.abs_diff(*other_chars) as f64) <= (1.0 - FUZZY_SIMILARITY) * longest
+            })
+            .find(|(other, other_chars, _)| {
+                let longest = chars.max(*other_chars) as f64;
+                1.0 - levenshtein(&normalized, other) as f64 / longest >= FUZZY_SIMILARITY
+            })
+            .map(|(_, _, card_id)| (*card_id, MatchKind::Fuzzy))
+    }
+}
@@ -0,0 +1,360 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::InboundEmailConfig,
+    models::{Card, CreateCardDto, DeckRole, InboundEmail, InboundEmailAddress},
+    services::{
+        card::CardService, deck::DeckService, encryption::EncryptionService,
+        sharing::SharingService,
+    },
+    utils::{AppError, Result},
+};
+
+/// Deck that receives email when the user has not chosen one
+const INBOX_DECK_TITLE: &str = "Email Inbox";
+
+const ADDRESS_TOKEN_LENGTH: usize = 16;
+
+/// Signed requests older or newer than this are refused as replays
+const MAX_TIMESTAMP_SKEW_SECONDS: i64 = 300;
+
+/// Subject prefixes added by mail clients when forwarding or replying
+const SUBJECT_PREFIXES: &[&str] = &["fwd:", "fw:", "re:"];
+
+/// What happened to an inbound message
+#[derive(Debug)]
+pub enum InboundOutcome {
+    Created(Card),
+    /// Already processed, or a replay of a signed request already seen;
+    /// providers retry deliveries
+    Duplicate,
+    /// Not accepted, with the reason. Providers should not retry these.
+    Rejected(&'static str),
+}
+
+/// Email-in card creation. Each user gets a private address; forwarding a
+/// message to it from the account email creates a card from subject and body.
+pub struct EmailInboxService;
+
+impl EmailInboxService {
+    /// The user's address, created on first use
+    pub async fn get_address(
+        db: &PgPool,
+        config: &InboundEmailConfig,
+        user_id: Uuid,
+    ) -> Result<InboundEmailAddress> {
+        let domain = Self::domain(config)?;
+
+        let row = sqlx::query_as::<_, (String, Option<Uuid>, chrono::DateTime<chrono::Utc>)>(
+            r#"
+            INSERT INTO inbound_email_addresses (user_id, token)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET user_id = EXCLUDED.user_id
+            RETURNING token, deck_id, created_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(generate_address_token())
+        .fetch_one(db)
+        .await?;
+
+        Ok(to_address(row, domain))
+    }
+
+    /// Replace the address, e.g. after it leaked. The old one stops working.
+    pub async fn rotate_address(
+        db: &PgPool,
+        config: &InboundEmailConfig,
+        user_id: Uuid,
+    ) -> Result<InboundEmailAddress> {
+        let domain = Self::domain(config)?;
+
+        let row = sqlx::query_as::<_, (String, Option<Uuid>, chrono::DateTime<chrono::Utc>)>(
+            r#"
+            INSERT INTO inbound_email_addresses (user_id, token)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET token = EXCLUDED.token, updated_at = NOW()
+            RETURNING token, deck_id, created_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(generate_address_token())
+        .fetch_one(db)
+        .await?;
+
+        Ok(to_address(row, domain))
+    }
+
+    /// Choose the deck that receives email; `None` restores the Email Inbox
+    pub async fn set_deck(
+        db: &PgPool,
+        config: &InboundEmailConfig,
+        user_id: Uuid,
+        deck_id: Option<Uuid>,
+    ) -> Result<InboundEmailAddress> {
+        if let Some(deck_id) = deck_id {
+            SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+            // Email arrives in plaintext, which an encrypted deck must never hold
+            EncryptionService::ensure_plaintext(db, deck_id).await?;
+        }
+        Self::get_address(db, config, user_id).await?;
+
+        sqlx::query(
+            "UPDATE inbound_email_addresses SET deck_id = $2, updated_at = NOW() WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(deck_id)
+        .execute(db)
+        .await?;
+
+        Self::get_address(db, config, user_id).await
+    }
+
+    /// Check the provider's HMAC-SHA256 signature over timestamp and token,
+    /// and that the timestamp is within a few minutes of now. The signature
+    /// does not cover the message itself, so `receive` also refuses tokens
+    /// it has seen before.
+    pub fn verify_signature(config: &InboundEmailConfig, email: &InboundEmail) -> Result<()> {
+        let key = config
+            .signing_key
+            .as_deref()
+            .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+        let signature = hex::decode(&email.signature).map_err(|_| AppError::Unauthorized)?;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(email.timestamp.as_bytes());
+        mac.update(email.token.as_bytes());
+        mac.verify_slice(&signature).map_err(|_| AppError::Unauthorized)?;
+
+        let timestamp: i64 = email.timestamp.parse().map_err(|_| AppError::Unauthorized)?;
+        if (Utc::now().timestamp() - timestamp).abs() > MAX_TIMESTAMP_SKEW_SECONDS {
+            return Err(AppError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Turn a verified inbound message into a card
+    pub async fn receive(
+        db: &PgPool,
+        config: &InboundEmailConfig,
+        email: &InboundEmail,
+    ) -> Result<InboundOutcome> {
+        let domain = Self::domain(config)?;
+        if !Self::claim_token(db, &email.token).await? {
+            return Ok(InboundOutcome::Duplicate);
+        }
+
+        let outcome = Self::process(db, domain, email).await;
+        if outcome.is_err() {
+            // Let the provider's retry, which reuses the token, try again
+            sqlx::query("DELETE FROM inbound_email_tokens WHERE token = $1")
+                .bind(&email.token)
+                .execute(db)
+                .await?;
+        }
+        outcome
+    }
+
+    // Helper methods
+
+    /// Record a signed request's token; false if it was already used
+    async fn claim_token(db: &PgPool, token: &str) -> Result<bool> {
+        // Tokens older than the timestamp window either way can no longer
+        // pass verification
+        sqlx::query("DELETE FROM inbound_email_tokens WHERE received_at < NOW() - INTERVAL '10 minutes'")
+            .execute(db)
+            .await?;
+
+        let claimed = sqlx::query(
+            "INSERT INTO inbound_email_tokens (token) VALUES ($1) ON CONFLICT (token) DO NOTHING",
+        )
+        .bind(token)
+        .execute(db)
+        .await?
+        .rows_affected()
+            > 0;
+
+        Ok(claimed)
+    }
+
+    async fn process(db: &PgPool, domain: &str, email: &InboundEmail) -> Result<InboundOutcome> {
+        let Some(token) = recipient_token(&email.recipient, domain) else {
+            return Ok(InboundOutcome::Rejected("unknown recipient"));
+        };
+
+        let recipient = sqlx::query_as::<_, (Uuid, String, Option<Uuid>)>(
+            r#"
+            SELECT a.user_id, u.email, a.deck_id
+            FROM inbound_email_addresses a
+            JOIN users u ON u.id = a.user_id
+            WHERE a.token = $1 AND u.deleted_at IS NULL
+            "#,
+        )
+        .bind(&token)
+        .fetch_optional(db)
+        .await?;
+
+        let Some((user_id, account_email, deck_id)) = recipient else {
+            return Ok(InboundOutcome::Rejected("unknown recipient"));
+        };
+
+        // Only the account holder may add cards by email
+        let account_email = account_email.to_lowercase();
+        let from = email.from.as_deref().map(mailbox_address);
+        if from.as_deref() != Some(account_email.as_str())
+            && mailbox_address(&email.sender) != account_email
+        {
+            return Ok(InboundOutcome::Rejected("sender does not match the account email"));
+        }
+
+        let front = email.subject.as_deref().map(clean_subject).unwrap_or_default();
+        let back = email
+            .stripped_text
+            .as_deref()
+            .or(email.body_plain.as_deref())
+            .map(str::trim)
+            .unwrap_or_default()
+            .to_string();
+        if front.is_empty() || back.is_empty() {
+            return Ok(InboundOutcome::Rejected("subject and body are required"));
+        }
+
+        if let Some(message_id) = &email.message_id {
+            let first_delivery = sqlx::query(
+                r#"
+                INSERT INTO inbound_email_messages (message_id, user_id)
+                VALUES ($1, $2)
+                ON CONFLICT (message_id) DO NOTHING
+                "#,
+            )
+            .bind(message_id)
+            .bind(user_id)
+            .execute(db)
+            .await?
+            .rows_affected()
+                > 0;
+
+            if !first_delivery {
+                return Ok(InboundOutcome::Duplicate);
+            }
+        }
+
+        let card = match Self::create_card(db, user_id, deck_id, front, back).await {
+            Ok(card) => card,
+            Err(e) => {
+                // Let the provider's retry try again
+                if let Some(message_id) = &email.message_id {
+                    sqlx::query("DELETE FROM inbound_email_messages WHERE message_id = $1")
+                        .bind(message_id)
+                        .execute(db)
+                        .await?;
+                }
+                return Err(e);
+            }
+        };
+
+        if let Some(message_id) = &email.message_id {
+            sqlx::query("UPDATE inbound_email_messages SET card_id = $2 WHERE message_id = $1")
+                .bind(message_id)
+                .bind(card.id)
+                .execute(db)
+                .await?;
+        }
+
+        Ok(InboundOutcome::Created(card))
+    }
+
+    fn domain(config: &InboundEmailConfig) -> Result<&str> {
+        config
+            .domain
+            .as_deref()
+            .ok_or(AppError::BadRequest("Inbound email is not configured".to_string()))
+    }
+
+    async fn create_card(
+        db: &PgPool,
+        user_id: Uuid,
+        deck_id: Option<Uuid>,
+        front: String,
+        back: String,
+    ) -> Result<Card> {
+        let deck_id = match deck_id {
+            Some(deck_id) => deck_id,
+            None => {
+                DeckService::find_or_create_owned(
+                    db,
+                    user_id,
+                    INBOX_DECK_TITLE,
+                    "Cards created by email",
+                )
+                .await?
+            }
+        };
+
+        CardService::create_card(
+            db,
+            deck_id,
+            user_id,
+            CreateCardDto {
+                front,
+                back,
+                position: None,
+            },
+        )
+        .await
+    }
+}
+
+fn to_address(
+    (token, deck_id, created_at): (String, Option<Uuid>, chrono::DateTime<chrono::Utc>),
+    domain: &str,
+) -> InboundEmailAddress {
+    InboundEmailAddress {
+        address: format!("{}@{}", token, domain),
+        deck_id,
+        created_at,
+    }
+}
+
+/// Lowercase alphanumerics, since mail systems may fold the local part's case
+fn generate_address_token() -> String {
+    const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::thread_rng();
+    (0..ADDRESS_TOKEN_LENGTH)
+        .map(|_| CHARSET[rng.gen_range(0..CHARSET.len())] as char)
+        .collect()
+}
+
+/// Local part of a recipient at our domain
+fn recipient_token(recipient: &str, domain: &str) -> Option<String> {
+    let address = mailbox_address(recipient);
+    let (local, recipient_domain) = address.rsplit_once('@')?;
+    (recipient_domain == domain.to_lowercase() && !local.is_empty()).then(|| local.to_string())
+}
+
+/// `Name <user@example.com>` → `user@example.com`, lowercased
+fn mailbox_address(mailbox: &str) -> String {
+    let address = match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox,
+    };
+    address.trim().to_lowercase()
+}
+
+/// Strip forwarding and reply prefixes such as `Fwd: Re:`
+fn clean_subject(subject: &str) -> String {
+    let mut subject = subject.trim();
+    while let Some(prefix) = SUBJECT_PREFIXES
+        .iter()
+        .find(|p| subject.len() >= p.len() && subject[..p.len()].eq_ignore_ascii_case(p))
+    {
+        subject = subject[prefix.len()..].trim_start();
+    }
+    subject.to_string()
+}
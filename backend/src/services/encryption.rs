@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        CardContentDto, DeckEncryption, DeckRole, DecryptDeckDto, EncryptDeckDto,
+        ENCRYPTION_ALGORITHMS, KEY_DERIVATION_FUNCTIONS,
+    },
+    services::sharing::SharingService,
+    utils::{AppError, Result},
+};
+
+/// Privacy vaults: decks whose card content is encrypted on the client.
+///
+/// The server never sees the passphrase or key. It stores ciphertext as the
+/// card front/back and keeps features that need readable content (search, AI,
+/// server-side imports) away from these decks.
+pub struct EncryptionService;
+
+impl EncryptionService {
+    pub async fn get_metadata(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<DeckEncryption> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+
+        sqlx::query_as::<_, DeckEncryption>("SELECT * FROM deck_encryption WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or(AppError::NotFound("Deck is not encrypted".to_string()))
+    }
+
+    pub async fn is_encrypted(db: &PgPool, deck_id: Uuid) -> Result<bool> {
+        let encrypted = sqlx::query_scalar::<_, bool>("SELECT is_encrypted FROM decks WHERE id = $1")
+            .bind(deck_id)
+            .fetch_optional(db)
+            .await?
+            .unwrap_or(false);
+
+        Ok(encrypted)
+    }
+
+    /// Reject operations that need to read or write plaintext card content
+    pub async fn ensure_plaintext(db: &PgPool, deck_id: Uuid) -> Result<()> {
+        if Self::is_encrypted(db, deck_id).await? {
+            return Err(AppError::BadRequest(
+                "This feature is not available for encrypted decks".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Encrypt a deck or rotate the key of an already encrypted one
+    pub async fn encrypt_deck(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: EncryptDeckDto,
+    ) -> Result<DeckEncryption> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+
+        if !ENCRYPTION_ALGORITHMS.contains(&dto.algorithm.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "Unsupported algorithm: {}",
+                dto.algorithm
+            )));
+        }
+        if !KEY_DERIVATION_FUNCTIONS.contains(&dto.kdf.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "Unsupported key derivation function: {}",
+                dto.kdf
+            )));
+        }
+
+        let is_public = sqlx::query_scalar::<_, bool>("SELECT is_public FROM decks WHERE id = $1")
+            .bind(deck_id)
+            .fetch_one(db)
+            .await?;
+        if is_public {
+            return Err(AppError::BadRequest(
+                "Public decks cannot be encrypted; make the deck private first".to_string(),
+            ));
+        }
+
+        let mut tx = db.begin().await?;
+        Self::replace_content(&mut tx, deck_id, &dto.cards).await?;
+
+        sqlx::query("UPDATE decks SET is_encrypted = true, updated_at = NOW() WHERE id = $1")
+            .bind(deck_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let metadata = sqlx::query_as::<_, DeckEncryption>(
+            r#"
+            INSERT INTO deck_encryption (deck_id, algorithm, kdf, kdf_salt, kdf_params, key_check)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (deck_id) DO UPDATE SET
+                algorithm = EXCLUDED.algorithm,
+                kdf = EXCLUDED.kdf,
+                kdf_salt = EXCLUDED.kdf_salt,
+                kdf_params = EXCLUDED.kdf_params,
+                key_check = EXCLUDED.key_check,
+                updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(deck_id)
+        .bind(&dto.algorithm)
+        .bind(&dto.kdf)
+        .bind(&dto.kdf_salt)
+        .bind(dto.kdf_params.unwrap_or_else(|| serde_json::json!({})))
+        .bind(&dto.key_check)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(metadata)
+    }
+
+    /// Store decrypted content and drop the encryption metadata
+    pub async fn decrypt_deck(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: DecryptDeckDto,
+    ) -> Result<()> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+        if !Self::is_encrypted(db, deck_id).await? {
+            return Err(AppError::BadRequest("Deck is not encrypted".to_string()));
+        }
+
+        let mut tx = db.begin().await?;
+        Self::replace_content(&mut tx, deck_id, &dto.cards).await?;
+
+        sqlx::query("DELETE FROM deck_encryption WHERE deck_id = $1")
+            .bind(deck_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE decks SET is_encrypted = false, updated_at = NOW() WHERE id = $1")
+            .bind(deck_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Helper methods
+
+    /// Overwrite every card of the deck. The submitted set has to match the
+    /// deck exactly so no card is left in the old encoding.
+    async fn replace_content(
+        tx: &mut Transaction<'_, Postgres>,
+        deck_id: Uuid,
+        cards: &[CardContentDto],
+    ) -> Result<()> {
+        let existing: HashSet<Uuid> =
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM cards WHERE deck_id = $1 FOR UPDATE")
+                .bind(deck_id)
+                .fetch_all(&mut **tx)
+                .await?
+                .into_iter()
+                .collect();
+
+        let submitted: HashSet<Uuid> = cards.iter().map(|c| c.card_id).collect();
+        if submitted.len() != cards.len() {
+            return Err(AppError::ValidationError("Duplicate card in request".to_string()));
+        }
+        if submitted != existing {
+            return Err(AppError::ValidationError(format!(
+                "Content must be provided for all {} cards of the deck and no others",
+                existing.len()
+            )));
+        }
+        if cards.iter().any(|c| c.front.is_empty() || c.back.is_empty()) {
+            return Err(AppError::ValidationError("Card content cannot be empty".to_string()));
+        }
+
+        for card in cards {
+            sqlx::query("UPDATE cards SET front = $2, back = $3, updated_at = NOW() WHERE id = $1")
+                .bind(card.card_id)
+                .bind(&card.front)
+                .bind(&card.back)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use super::{ExportContext, Exporter};
+use crate::{
+    models::import_export::{AnkiCard, AnkiDeck, AnkiField, AnkiModel, AnkiNote, AnkiTemplate},
+    utils::Result,
+};
+
+pub struct AnkiExporter;
+
+impl Exporter for AnkiExporter {
+    fn name(&self) -> &'static str {
+        "anki"
+    }
+
+    // JSON representation of the collection; would be an .apkg in production
+    fn content_type(&self) -> &'static str {
+        "application/json"
+    }
+
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn export(&self, ctx: &ExportContext<'_>) -> Result<Vec<u8>> {
+        // Create Anki model (note type)
+        let model = AnkiModel {
+            id: 1,
+            name: "Basic".to_string(),
+            flds: vec![
+                AnkiField { name: "Front".to_string(), ord: 0 },
+                AnkiField { name: "Back".to_string(), ord: 1 },
+            ],
+            tmpls: vec![
+                AnkiTemplate {
+                    name: "Card 1".to_string(),
+                    qfmt: "{{Front}}".to_string(),
+                    afmt: "{{FrontSide}}<hr id=\"answer\">{{Back}}".to_string(),
+                },
+            ],
+        };
+
+        // Convert cards to Anki format
+        let anki_notes: Vec<AnkiNote> = ctx
+            .cards
+            .iter()
+            .enumerate()
+            .map(|(i, card)| AnkiNote {
+                id: i as i64 + 1,
+                guid: card.id.to_string(),
+                mid: 1,
+                fields: vec![card.front.clone(), card.back.clone()],
+                tags: vec![],
+            })
+            .collect();
+
+        let anki_cards: Vec<AnkiCard> = ctx
+            .cards
+            .iter()
+            .enumerate()
+            .map(|(i, _card)| {
+                let progress = ctx.progress.get(i).and_then(Option::as_ref);
+                AnkiCard {
+                    nid: i as i64 + 1,
+                    ord: 0,
+                    did: 1,
+                    due: 0,
+                    ivl: progress.map_or(0, |p| p.interval_days),
+                    factor: progress.map_or(2500, |p| (p.ease_factor * 1000.0) as i32),
+                    reps: progress.map_or(0, |p| p.review_count),
+                    lapses: progress.map_or(0, |p| p.lapses),
+                }
+            })
+            .collect();
+
+        let anki_deck = AnkiDeck {
+            name: ctx.deck.name.clone(),
+            desc: ctx.deck.description.clone().unwrap_or_default(),
+            cards: anki_cards,
+            notes: anki_notes,
+            models: vec![model],
+        };
+
+        let json = serde_json::to_vec(&anki_deck)?;
+        Ok(json)
+    }
+}
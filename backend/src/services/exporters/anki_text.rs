@@ -0,0 +1,55 @@
+use csv::{QuoteStyle, WriterBuilder};
+use std::fmt::Write;
+
+use super::{ExportContext, Exporter};
+use crate::utils::{render_markdown, Result};
+
+/// Anki's "Notes in Plain Text" format: tab-separated Basic notes with HTML
+/// fields, headed by the directives Anki reads on import
+pub struct AnkiTextExporter;
+
+impl Exporter for AnkiTextExporter {
+    fn name(&self) -> &'static str {
+        "anki_text"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/tab-separated-values"
+    }
+
+    fn extension(&self) -> &'static str {
+        "txt"
+    }
+
+    fn export(&self, ctx: &ExportContext<'_>) -> Result<Vec<u8>> {
+        let mut header = String::new();
+        writeln!(header, "#separator:tab")?;
+        writeln!(header, "#html:true")?;
+        writeln!(header, "#notetype:Basic")?;
+        writeln!(header, "#deck:{}", ctx.deck.name.replace(['\r', '\n'], " "))?;
+        writeln!(header, "#columns:Front\tBack")?;
+
+        // Fields with tabs, quotes or line breaks are quoted, as Anki does
+        let mut wtr = WriterBuilder::new()
+            .delimiter(b'\t')
+            .quote_style(QuoteStyle::Necessary)
+            .from_writer(header.into_bytes());
+        for card in ctx.cards {
+            wtr.write_record([field_html(&card.front), field_html(&card.back)])?;
+        }
+
+        let data = wtr.into_inner()?;
+        Ok(data)
+    }
+}
+
+// Card Markdown as Anki field HTML; a lone paragraph is unwrapped so plain
+// text stays plain
+fn field_html(markdown: &str) -> String {
+    let html = render_markdown(markdown);
+    let html = html.trim();
+    match html.strip_prefix("<p>").and_then(|inner| inner.strip_suffix("</p>")) {
+        Some(inner) if !inner.contains("<p>") => inner.replace('\n', "<br>"),
+        _ => html.to_string(),
+    }
+}
@@ -0,0 +1,49 @@
+use csv::Writer;
+
+use super::{ExportContext, Exporter};
+use crate::{models::import_export::CsvCard, utils::Result};
+
+pub struct CsvExporter;
+
+impl Exporter for CsvExporter {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/csv"
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn export(&self, ctx: &ExportContext<'_>) -> Result<Vec<u8>> {
+        let mut wtr = Writer::from_writer(vec![]);
+
+        // Write header
+        wtr.write_record(["Front", "Back", "Tags", "Explanation", "Difficulty"])?;
+
+        // Write cards
+        for card in ctx.cards {
+            let csv_card = CsvCard {
+                front: card.front.clone(),
+                back: card.back.clone(),
+                tags: String::new(),
+                explanation: String::new(),
+                difficulty: None,
+            };
+
+            wtr.write_record(&[
+                csv_card.front,
+                csv_card.back,
+                csv_card.tags,
+                csv_card.explanation,
+                csv_card.difficulty.map_or(String::new(), |d| d.to_string()),
+            ])?;
+        }
+
+        let data = wtr.into_inner()?;
+        Ok(data)
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt::Write;
+
+use super::{ExportContext, Exporter};
+use crate::utils::Result;
+
+/// Standalone printable page with one block per card
+pub struct HtmlExporter;
+
+impl Exporter for HtmlExporter {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/html; charset=utf-8"
+    }
+
+    fn extension(&self) -> &'static str {
+        "html"
+    }
+
+    fn export(&self, ctx: &ExportContext<'_>) -> Result<Vec<u8>> {
+        let title = escape_html(&ctx.deck.name);
+        let mut html = String::new();
+
+        writeln!(html, "<!DOCTYPE html>")?;
+        writeln!(html, "<html lang=\"en\">\n<head>")?;
+        writeln!(html, "<meta charset=\"utf-8\">")?;
+        writeln!(html, "<title>{}</title>", title)?;
+        writeln!(
+            html,
+            "<style>body{{font-family:sans-serif;max-width:48rem;margin:2rem auto}}\
+             .card{{border:1px solid #ccc;border-radius:6px;padding:1rem;margin:1rem 0;page-break-inside:avoid}}\
+             .back{{border-top:1px dashed #ccc;margin-top:.75rem;padding-top:.75rem}}</style>"
+        )?;
+        writeln!(html, "</head>\n<body>")?;
+        writeln!(html, "<h1>{}</h1>", title)?;
+        if let Some(desc) = &ctx.deck.description {
+            writeln!(html, "<p>{}</p>", escape_html(desc))?;
+        }
+
+        for (i, card) in ctx.cards.iter().enumerate() {
+            writeln!(html, "<section class=\"card\" id=\"card-{}\">", i + 1)?;
+            writeln!(html, "<div class=\"front\">{}</div>", multiline(&card.front))?;
+            writeln!(html, "<div class=\"back\">{}</div>", multiline(&card.back))?;
+            writeln!(html, "</section>")?;
+        }
+
+        writeln!(html, "</body>\n</html>")?;
+        Ok(html.into_bytes())
+    }
+}
+
+fn multiline(text: &str) -> String {
+    escape_html(text).replace('\n', "<br>")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,73 @@
+use chrono::Utc;
+
+use super::{ExportContext, Exporter};
+use crate::{
+    models::import_export::{ExportMetadata, ExportedCard, ExportedDeck},
+    utils::Result,
+};
+
+/// Version written to `metadata.version`; bump when the layout changes
+pub const FORMAT_VERSION: &str = "1.0";
+
+/// Native DeckOracle format; round-trips through the JSON importer
+pub struct JsonExporter;
+
+impl Exporter for JsonExporter {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/json"
+    }
+
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn export(&self, ctx: &ExportContext<'_>) -> Result<Vec<u8>> {
+        let exported_cards: Vec<ExportedCard> = ctx
+            .cards
+            .iter()
+            .enumerate()
+            .map(|(i, card)| ExportedCard {
+                id: card.id,
+                front: card.front.clone(),
+                back: card.back.clone(),
+                explanation: None,
+                tags: vec![],
+                difficulty: None,
+                media: ctx.media.get(i).cloned().unwrap_or_default(),
+                created_at: card.created_at,
+                updated_at: card.updated_at,
+                progress: ctx.progress.get(i).cloned().flatten(),
+            })
+            .collect();
+
+        let total_cards = exported_cards.len();
+        let exported_deck = ExportedDeck {
+            id: ctx.deck.id,
+            title: ctx.deck.name.clone(),
+            description: ctx.deck.description.clone(),
+            tags: vec![],
+            front_language: ctx.deck.front_language.clone(),
+            back_language: ctx.deck.back_language.clone(),
+            created_at: ctx.deck.created_at,
+            updated_at: ctx.deck.updated_at,
+            cards: exported_cards,
+            metadata: ExportMetadata {
+                version: FORMAT_VERSION.to_string(),
+                exported_at: Utc::now(),
+                platform: "DeckOracle".to_string(),
+                format: self.name().to_string(),
+                total_cards,
+                includes_progress: !ctx.progress.is_empty(),
+                includes_media: !ctx.media.is_empty(),
+                exported_by: (!ctx.progress.is_empty()).then_some(ctx.user_id),
+            },
+        };
+
+        let json = serde_json::to_vec_pretty(&exported_deck)?;
+        Ok(json)
+    }
+}
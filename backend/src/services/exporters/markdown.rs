@@ -0,0 +1,42 @@
+use std::fmt::Write;
+
+use super::{ExportContext, Exporter};
+use crate::utils::Result;
+
+/// Same layout the Markdown importer and template use
+pub struct MarkdownExporter;
+
+impl Exporter for MarkdownExporter {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/markdown"
+    }
+
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn export(&self, ctx: &ExportContext<'_>) -> Result<Vec<u8>> {
+        let mut markdown = String::new();
+
+        // Write deck header
+        writeln!(markdown, "# {}", ctx.deck.name)?;
+        if let Some(desc) = &ctx.deck.description {
+            writeln!(markdown, "\n{}\n", desc)?;
+        }
+        writeln!(markdown, "---\n")?;
+
+        // Write cards
+        for (i, card) in ctx.cards.iter().enumerate() {
+            writeln!(markdown, "## Card {}", i + 1)?;
+            writeln!(markdown, "\n**Front:** {}", card.front)?;
+            writeln!(markdown, "\n**Back:** {}", card.back)?;
+            writeln!(markdown, "\n---\n")?;
+        }
+
+        Ok(markdown.into_bytes())
+    }
+}
@@ -0,0 +1,103 @@
+//! Deck export formats.
+//!
+//! Every format is a self-contained module implementing [`Exporter`] and is
+//! looked up by name through an [`ExporterRegistry`]. Adding a format means
+//! writing the module and registering it in [`ExporterRegistry::with_builtin`];
+//! optional formats are compiled in behind a Cargo feature.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        import_export::{CardProgressData, ExportFormatInfo, MediaAttachment},
+        Card, Deck,
+    },
+    utils::Result,
+};
+
+mod anki;
+mod anki_text;
+mod csv;
+mod html;
+mod json;
+mod markdown;
+#[cfg(feature = "pdf-export")]
+mod pdf;
+
+pub use anki::AnkiExporter;
+pub use anki_text::AnkiTextExporter;
+pub use self::csv::CsvExporter;
+pub use html::HtmlExporter;
+pub use json::{JsonExporter, FORMAT_VERSION as JSON_FORMAT_VERSION};
+pub use markdown::MarkdownExporter;
+#[cfg(feature = "pdf-export")]
+pub use pdf::PdfExporter;
+
+/// Everything an exporter gets to see about a deck
+pub struct ExportContext<'a> {
+    pub deck: &'a Deck,
+    pub cards: &'a [Card],
+    /// The user exporting, whose progress `progress` holds
+    pub user_id: Uuid,
+    /// Per-card progress in card order, `None` for cards the user has not
+    /// studied; empty unless progress was requested
+    pub progress: &'a [Option<CardProgressData>],
+    /// Per-card attachments in card order; empty unless media was requested
+    pub media: &'a [Vec<MediaAttachment>],
+}
+
+pub trait Exporter: Send + Sync {
+    /// Format name used in the `format` query parameter
+    fn name(&self) -> &'static str;
+    fn content_type(&self) -> &'static str;
+    fn extension(&self) -> &'static str;
+    fn export(&self, ctx: &ExportContext<'_>) -> Result<Vec<u8>>;
+}
+
+/// Format name → exporter lookup
+#[derive(Clone, Default)]
+pub struct ExporterRegistry {
+    exporters: BTreeMap<&'static str, Arc<dyn Exporter>>,
+}
+
+impl ExporterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with every format compiled into this build
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(JsonExporter);
+        registry.register(CsvExporter);
+        registry.register(AnkiExporter);
+        registry.register(AnkiTextExporter);
+        registry.register(MarkdownExporter);
+        registry.register(HtmlExporter);
+        #[cfg(feature = "pdf-export")]
+        registry.register(PdfExporter);
+        registry
+    }
+
+    /// Add a format, replacing any existing exporter with the same name
+    pub fn register<E: Exporter + 'static>(&mut self, exporter: E) {
+        self.exporters.insert(exporter.name(), Arc::new(exporter));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Exporter>> {
+        self.exporters.get(name.to_ascii_lowercase().as_str()).cloned()
+    }
+
+    pub fn formats(&self) -> Vec<ExportFormatInfo> {
+        self.exporters
+            .values()
+            .map(|exporter| ExportFormatInfo {
+                name: exporter.name().to_string(),
+                content_type: exporter.content_type().to_string(),
+                extension: exporter.extension().to_string(),
+            })
+            .collect()
+    }
+}
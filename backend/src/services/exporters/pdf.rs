@@ -0,0 +1,135 @@
+use lopdf::{
+    content::{Content, Operation},
+    dictionary, Document, Object, Stream,
+};
+
+use super::{ExportContext, Exporter};
+use crate::utils::{AppError, Result};
+
+// A4 in points
+const PAGE_WIDTH: i64 = 595;
+const PAGE_HEIGHT: i64 = 842;
+const MARGIN: i64 = 50;
+const FONT_SIZE: i64 = 11;
+const LINE_HEIGHT: i64 = 15;
+const WRAP_COLUMNS: usize = 90;
+
+/// Printable card list using the built-in Helvetica font. Text outside
+/// Latin-1 is replaced, since standard fonts carry no other glyphs.
+pub struct PdfExporter;
+
+impl Exporter for PdfExporter {
+    fn name(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/pdf"
+    }
+
+    fn extension(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn export(&self, ctx: &ExportContext<'_>) -> Result<Vec<u8>> {
+        let mut lines = vec![ctx.deck.name.clone(), String::new()];
+        if let Some(desc) = &ctx.deck.description {
+            lines.extend(wrap(desc));
+            lines.push(String::new());
+        }
+        for (i, card) in ctx.cards.iter().enumerate() {
+            lines.extend(wrap(&format!("{}. Q: {}", i + 1, card.front)));
+            lines.extend(wrap(&format!("A: {}", card.back)));
+            lines.push(String::new());
+        }
+
+        let lines_per_page = ((PAGE_HEIGHT - 2 * MARGIN) / LINE_HEIGHT) as usize;
+
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+
+        let mut page_ids = Vec::new();
+        for chunk in lines.chunks(lines_per_page) {
+            let mut operations = vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), FONT_SIZE.into()]),
+                Operation::new("TL", vec![LINE_HEIGHT.into()]),
+                Operation::new("Td", vec![MARGIN.into(), (PAGE_HEIGHT - MARGIN).into()]),
+            ];
+            for line in chunk {
+                operations.push(Operation::new("Tj", vec![Object::string_literal(latin1(line))]));
+                operations.push(Operation::new("T*", vec![]));
+            }
+            operations.push(Operation::new("ET", vec![]));
+
+            let content = Content { operations }.encode().map_err(pdf_error)?;
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+            page_ids.push(doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            }));
+        }
+
+        let page_count = page_ids.len() as i64;
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => page_ids.into_iter().map(Object::from).collect::<Vec<_>>(),
+                "Count" => page_count,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), PAGE_WIDTH.into(), PAGE_HEIGHT.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.compress();
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).map_err(pdf_error)?;
+        Ok(buffer)
+    }
+}
+
+fn pdf_error(e: impl std::fmt::Display) -> AppError {
+    tracing::error!("PDF export failed: {}", e);
+    AppError::InternalServerError
+}
+
+/// Greedy word wrap, splitting explicit newlines first
+fn wrap(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > WRAP_COLUMNS {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn latin1(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+        .collect()
+}
@@ -1,8 +1,12 @@
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::{
-    models::{CreateFolderDto, Deck, DeckWithStats, Folder, FolderWithContents, UpdateFolderDto},
+    models::{
+        CreateFolderDto, Deck, DeckWithStats, Folder, FolderNode, FolderWithContents, MoveFolderDto,
+        UpdateFolderDto,
+    },
     utils::{AppError, Result},
 };
 
@@ -84,11 +88,37 @@ impl FolderService {
         )
         .fetch_optional(db)
         .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+        .ok_or(AppError::FolderNotFound)?;
 
         Ok(folder)
     }
 
+    /// The user's folders nested under their parents, fetched in one query
+    pub async fn get_tree(db: &PgPool, user_id: Uuid) -> Result<Vec<FolderNode>> {
+        let folders = sqlx::query_as::<_, Folder>(
+            r#"
+            WITH RECURSIVE tree AS (
+                SELECT id, user_id, parent_folder_id, name, position, created_at, updated_at, 0 as depth
+                FROM folders
+                WHERE user_id = $1 AND parent_folder_id IS NULL
+                UNION ALL
+                SELECT f.id, f.user_id, f.parent_folder_id, f.name, f.position, f.created_at, f.updated_at,
+                       t.depth + 1
+                FROM folders f
+                JOIN tree t ON f.parent_folder_id = t.id
+            )
+            SELECT id, user_id, parent_folder_id, name, position, created_at, updated_at
+            FROM tree
+            ORDER BY depth, position, name
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(build_tree(folders))
+    }
+
     pub async fn update_folder(
         db: &PgPool,
         id: Uuid,
@@ -96,7 +126,20 @@ impl FolderService {
         dto: UpdateFolderDto,
     ) -> Result<Folder> {
         // First check if folder exists and belongs to user
-        let _existing = Self::get_folder(db, id, user_id).await?;
+        let existing = Self::get_folder(db, id, user_id).await?;
+
+        // A new parent goes through the same checks and renumbering as a move
+        let mut position = dto.position;
+        if let Some(parent_folder_id) = dto.parent_folder_id {
+            if existing.parent_folder_id != Some(parent_folder_id) {
+                let move_dto = MoveFolderDto {
+                    parent_folder_id: Some(parent_folder_id),
+                    position,
+                };
+                Self::move_folder(db, id, user_id, move_dto).await?;
+                position = None;
+            }
+        }
 
         let folder = sqlx::query_as!(
             Folder,
@@ -104,16 +147,14 @@ impl FolderService {
             UPDATE folders
             SET 
                 name = COALESCE($3, name),
-                parent_folder_id = COALESCE($4, parent_folder_id),
-                position = COALESCE($5, position)
+                position = COALESCE($4, position)
             WHERE id = $1 AND user_id = $2
             RETURNING id, user_id, parent_folder_id, name, position, created_at, updated_at
             "#,
             id,
             user_id,
             dto.name,
-            dto.parent_folder_id,
-            dto.position
+            position
         )
         .fetch_one(db)
         .await?;
@@ -121,22 +162,191 @@ impl FolderService {
         Ok(folder)
     }
 
+    /// Move a folder and everything in it under a new parent, or to the top
+    /// level. A folder cannot move into itself or one of its subfolders.
+    /// Positions are renumbered from 0 in both the old and the new parent.
+    pub async fn move_folder(
+        db: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+        dto: MoveFolderDto,
+    ) -> Result<Folder> {
+        let existing = Self::get_folder(db, id, user_id).await?;
+
+        if let Some(parent_id) = dto.parent_folder_id {
+            if parent_id == id {
+                return Err(AppError::BadRequest("A folder cannot be moved into itself".to_string()));
+            }
+            Self::get_folder(db, parent_id, user_id).await?;
+            if Self::is_ancestor(db, id, parent_id).await? {
+                return Err(AppError::BadRequest(
+                    "A folder cannot be moved into one of its own subfolders".to_string(),
+                ));
+            }
+        }
+
+        let mut tx = db.begin().await?;
+
+        let mut siblings = Self::child_ids(&mut tx, user_id, dto.parent_folder_id).await?;
+        siblings.retain(|&sibling| sibling != id);
+        let position = dto
+            .position
+            .map(|p| (p.max(0) as usize).min(siblings.len()))
+            .unwrap_or(siblings.len());
+        siblings.insert(position, id);
+
+        sqlx::query("UPDATE folders SET parent_folder_id = $3 WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .bind(dto.parent_folder_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::renumber(&mut tx, &siblings).await?;
+
+        if existing.parent_folder_id != dto.parent_folder_id {
+            let old_siblings = Self::child_ids(&mut tx, user_id, existing.parent_folder_id).await?;
+            Self::renumber(&mut tx, &old_siblings).await?;
+        }
+
+        tx.commit().await?;
+
+        Self::get_folder(db, id, user_id).await
+    }
+
+    /// Delete a folder with all of its subfolders. Decks inside any of them
+    /// move to the trash and come back at the top level when restored.
     pub async fn delete_folder(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<()> {
-        let result = sqlx::query!(
+        let folder = Self::get_folder(db, id, user_id).await?;
+
+        let mut tx = db.begin().await?;
+
+        let subtree = sqlx::query_scalar::<_, Uuid>(
             r#"
-            DELETE FROM folders
-            WHERE id = $1 AND user_id = $2
+            WITH RECURSIVE subtree AS (
+                SELECT id FROM folders WHERE id = $1 AND user_id = $2
+                UNION
+                SELECT f.id FROM folders f JOIN subtree s ON f.parent_folder_id = s.id
+            )
+            SELECT id FROM subtree
             "#,
-            id,
-            user_id
         )
-        .execute(db)
+        .bind(id)
+        .bind(user_id)
+        .fetch_all(&mut *tx)
         .await?;
 
+        sqlx::query(
+            r#"
+            UPDATE decks
+            SET folder_id = NULL, deleted_at = COALESCE(deleted_at, NOW())
+            WHERE folder_id = ANY($1)
+            "#,
+        )
+        .bind(&subtree)
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query("DELETE FROM folders WHERE id = ANY($1) AND user_id = $2")
+            .bind(&subtree)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
         if result.rows_affected() == 0 {
-            return Err(AppError::NotFound("Resource not found".to_string()));
+            return Err(AppError::FolderNotFound);
         }
 
+        let siblings = Self::child_ids(&mut tx, user_id, folder.parent_folder_id).await?;
+        Self::renumber(&mut tx, &siblings).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Decks in the user's folder and all of its subfolders, excluding the
+    /// trash
+    pub async fn subtree_deck_ids(db: &PgPool, folder_id: Uuid, user_id: Uuid) -> Result<Vec<Uuid>> {
+        Self::get_folder(db, folder_id, user_id).await?;
+
+        let deck_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            WITH RECURSIVE subtree AS (
+                SELECT id FROM folders WHERE id = $1 AND user_id = $2
+                UNION
+                SELECT f.id FROM folders f JOIN subtree s ON f.parent_folder_id = s.id
+            )
+            SELECT d.id FROM decks d
+            WHERE d.folder_id IN (SELECT id FROM subtree) AND d.deleted_at IS NULL
+            ORDER BY d.title, d.id
+            "#,
+        )
+        .bind(folder_id)
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(deck_ids)
+    }
+
+    /// Whether `ancestor_id` is `folder_id` or above it in the hierarchy
+    async fn is_ancestor(db: &PgPool, ancestor_id: Uuid, folder_id: Uuid) -> Result<bool> {
+        // UNION rather than UNION ALL stops at any cycle already in the data
+        let is_ancestor = sqlx::query_scalar::<_, bool>(
+            r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT id, parent_folder_id FROM folders WHERE id = $2
+                UNION
+                SELECT f.id, f.parent_folder_id
+                FROM folders f
+                JOIN ancestors a ON f.id = a.parent_folder_id
+            )
+            SELECT EXISTS(SELECT 1 FROM ancestors WHERE id = $1)
+            "#,
+        )
+        .bind(ancestor_id)
+        .bind(folder_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(is_ancestor)
+    }
+
+    /// Ids of the folders directly under `parent_id`, in display order
+    async fn child_ids(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        parent_id: Option<Uuid>,
+    ) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM folders
+            WHERE user_id = $1 AND parent_folder_id IS NOT DISTINCT FROM $2
+            ORDER BY position, name
+            "#,
+        )
+        .bind(user_id)
+        .bind(parent_id)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Number positions 0, 1, 2, ... in the given order
+    async fn renumber(tx: &mut Transaction<'_, Postgres>, ordered_ids: &[Uuid]) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE folders f
+            SET position = o.ordinality - 1
+            FROM unnest($1::UUID[]) WITH ORDINALITY AS o(id, ordinality)
+            WHERE f.id = o.id AND f.position <> o.ordinality - 1
+            "#,
+        )
+        .bind(ordered_ids)
+        .execute(&mut **tx)
+        .await?;
+
         Ok(())
     }
 
@@ -173,15 +383,17 @@ impl FolderService {
                 d.title as name,
                 d.description,
                 d.is_public,
+                d.front_language,
+                d.back_language,
                 d.created_at,
                 d.updated_at,
-                COUNT(c.id) as "card_count!",
-                MAX(ss.started_at) as last_studied
+                d.is_encrypted,
+                d.cover_storage_key,
+                d.card_count::BIGINT as "card_count!",
+                (SELECT MAX(ss.started_at) FROM study_sessions ss
+                 WHERE ss.deck_id = d.id AND ss.user_id = d.owner_id) as last_studied
             FROM decks d
-            LEFT JOIN cards c ON c.deck_id = d.id
-            LEFT JOIN study_sessions ss ON ss.deck_id = d.id AND ss.user_id = d.owner_id
-            WHERE d.folder_id = $1 AND d.owner_id = $2
-            GROUP BY d.id
+            WHERE d.folder_id = $1 AND d.owner_id = $2 AND d.deleted_at IS NULL
             ORDER BY d.title
             "#,
             id,
@@ -198,11 +410,16 @@ impl FolderService {
                 name: r.name,
                 description: r.description,
                 is_public: r.is_public,
+                front_language: r.front_language,
+                back_language: r.back_language,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             },
             card_count: r.card_count,
             last_studied: r.last_studied,
+            is_encrypted: r.is_encrypted,
+            cover_url: None,
+            cover_storage_key: r.cover_storage_key,
         })
         .collect();
 
@@ -213,3 +430,28 @@ impl FolderService {
         })
     }
 }
+
+/// Nest folders under their parents. Siblings keep their order in
+/// `folders`; folders whose parent is missing are left out.
+pub fn build_tree(folders: Vec<Folder>) -> Vec<FolderNode> {
+    let mut children: HashMap<Option<Uuid>, Vec<Folder>> = HashMap::new();
+    for folder in folders {
+        children.entry(folder.parent_folder_id).or_default().push(folder);
+    }
+    attach(None, &mut children)
+}
+
+fn attach(parent_id: Option<Uuid>, children: &mut HashMap<Option<Uuid>, Vec<Folder>>) -> Vec<FolderNode> {
+    children
+        .remove(&parent_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|folder| {
+            let nested = attach(Some(folder.id), children);
+            FolderNode {
+                folder,
+                children: nested,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,139 @@
+use crate::models::{AnswerGrade, DiffOp, DiffSegment};
+
+/// Answers longer than this (after normalization) are only compared for
+/// equality; the edit-distance table grows with the product of both lengths
+pub const MAX_GRADED_CHARS: usize = 500;
+
+/// Grade a typed answer against a card back. Both are normalized first, so
+/// case, punctuation and spacing never count against the user. The answer
+/// is correct when its similarity (1 - edit distance / longer length)
+/// reaches `threshold`. Wrong answers carry a character diff of the
+/// normalized texts.
+pub fn grade(expected: &str, given: &str, threshold: f64) -> AnswerGrade {
+    let expected = normalize(expected);
+    let given = normalize(given);
+    let expected_chars: Vec<char> = expected.chars().collect();
+    let given_chars: Vec<char> = given.chars().collect();
+
+    if expected_chars.len() > MAX_GRADED_CHARS || given_chars.len() > MAX_GRADED_CHARS {
+        let is_correct = expected == given;
+        return AnswerGrade {
+            is_correct,
+            similarity: if is_correct { 1.0 } else { 0.0 },
+            expected,
+            diff: None,
+        };
+    }
+
+    let table = distance_table(&expected_chars, &given_chars);
+    let distance = table[expected_chars.len()][given_chars.len()];
+    let longest = expected_chars.len().max(given_chars.len());
+    let similarity = if longest == 0 {
+        1.0
+    } else {
+        1.0 - distance as f64 / longest as f64
+    };
+    let is_correct = !given.is_empty() && similarity >= threshold;
+
+    AnswerGrade {
+        is_correct,
+        similarity,
+        diff: (distance > 0).then(|| diff(&table, &expected_chars, &given_chars)),
+        expected,
+    }
+}
+
+/// Lowercase, drop punctuation and collapse whitespace
+pub fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Levenshtein distance between `a` and `b` in characters
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    distance_table(&a, &b)[a.len()][b.len()]
+}
+
+/// `table[i][j]` is the distance between the first `i` chars of `a` and the
+/// first `j` chars of `b`
+fn distance_table(a: &[char], b: &[char]) -> Vec<Vec<usize>> {
+    let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in table.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in table[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = table[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            table[i][j] = substitution.min(table[i - 1][j] + 1).min(table[i][j - 1] + 1);
+        }
+    }
+    table
+}
+
+/// Walk the table back from the end to recover one cheapest edit script.
+/// Within each run of edits the missing expected text comes before the
+/// extra typed text, so a substitution reads as "expected, then typed".
+fn diff(table: &[Vec<usize>], expected: &[char], given: &[char]) -> Vec<DiffSegment> {
+    let (mut i, mut j) = (expected.len(), given.len());
+    let mut steps = Vec::with_capacity(i.max(j));
+
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && expected[i - 1] == given[j - 1] && table[i][j] == table[i - 1][j - 1] {
+            steps.push((DiffOp::Equal, expected[i - 1]));
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && j > 0 && table[i][j] == table[i - 1][j - 1] + 1 {
+            steps.push((DiffOp::Extra, given[j - 1]));
+            steps.push((DiffOp::Missing, expected[i - 1]));
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && table[i][j] == table[i - 1][j] + 1 {
+            steps.push((DiffOp::Missing, expected[i - 1]));
+            i -= 1;
+        } else {
+            steps.push((DiffOp::Extra, given[j - 1]));
+            j -= 1;
+        }
+    }
+
+    let mut segments = Vec::new();
+    let (mut missing, mut extra) = (String::new(), String::new());
+    let mut equal = String::new();
+    for (op, c) in steps.into_iter().rev() {
+        match op {
+            DiffOp::Equal => {
+                flush_edits(&mut segments, &mut missing, &mut extra);
+                equal.push(c);
+            }
+            DiffOp::Missing | DiffOp::Extra => {
+                push_segment(&mut segments, DiffOp::Equal, &mut equal);
+                if op == DiffOp::Missing { missing.push(c) } else { extra.push(c) }
+            }
+        }
+    }
+    push_segment(&mut segments, DiffOp::Equal, &mut equal);
+    flush_edits(&mut segments, &mut missing, &mut extra);
+    segments
+}
+
+fn flush_edits(segments: &mut Vec<DiffSegment>, missing: &mut String, extra: &mut String) {
+    push_segment(segments, DiffOp::Missing, missing);
+    push_segment(segments, DiffOp::Extra, extra);
+}
+
+fn push_segment(segments: &mut Vec<DiffSegment>, op: DiffOp, text: &mut String) {
+    if !text.is_empty() {
+        segments.push(DiffSegment { op, text: std::mem::take(text) });
+    }
+}
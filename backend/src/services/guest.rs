@@ -0,0 +1,157 @@
+use chrono::{Duration, Utc};
+use rand::seq::SliceRandom;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Card, CreateGuestTokenDto, Deck, DeckGuestToken, DeckRole, GuestAccess, GuestDeck},
+    services::{auth::AuthService, encryption::EncryptionService, sharing::SharingService},
+    utils::{AppError, Result},
+};
+
+const DEFAULT_EXPIRY_HOURS: i64 = 24;
+
+/// Read-only guest tokens for demoing a deck, e.g. on a projector. A token
+/// grants viewing and studying one deck; nothing a guest does is saved.
+pub struct GuestService;
+
+impl GuestService {
+    pub async fn create_token(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: CreateGuestTokenDto,
+    ) -> Result<DeckGuestToken> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+        // Guests have no key to read ciphertext with
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+
+        let hours = dto.expires_in_hours.unwrap_or(DEFAULT_EXPIRY_HOURS);
+
+        let token = sqlx::query_as::<_, DeckGuestToken>(
+            r#"
+            INSERT INTO deck_guest_tokens (deck_id, token, label, created_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(deck_id)
+        .bind(AuthService::generate_random_token())
+        .bind(&dto.label)
+        .bind(user_id)
+        .bind(Utc::now() + Duration::hours(hours))
+        .fetch_one(db)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Tokens for a deck with their usage counts, newest first
+    pub async fn list_tokens(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<DeckGuestToken>> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+
+        let tokens = sqlx::query_as::<_, DeckGuestToken>(
+            "SELECT * FROM deck_guest_tokens WHERE deck_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(deck_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    pub async fn revoke_token(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        token_id: Uuid,
+    ) -> Result<()> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE deck_guest_tokens SET revoked_at = NOW()
+            WHERE id = $1 AND deck_id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(token_id)
+        .bind(deck_id)
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Resource not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a live token to the deck it grants, counting the use
+    pub async fn authenticate(db: &PgPool, token: &str) -> Result<GuestAccess> {
+        let access = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            UPDATE deck_guest_tokens
+            SET use_count = use_count + 1, last_used_at = NOW()
+            WHERE token = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            RETURNING id, deck_id
+            "#,
+        )
+        .bind(token)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+        Ok(GuestAccess {
+            token_id: access.0,
+            deck_id: access.1,
+        })
+    }
+
+    pub async fn deck(db: &PgPool, access: GuestAccess) -> Result<GuestDeck> {
+        let deck = sqlx::query_as::<_, Deck>(
+            r#"
+            SELECT id, folder_id, owner_id, title, description, is_public, front_language, back_language, created_at, updated_at
+            FROM decks
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(access.deck_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::DeckNotFound)?;
+
+        let cards = Self::cards(db, access.deck_id).await?;
+
+        Ok(GuestDeck { deck, cards })
+    }
+
+    /// Cards to study in order, or shuffled. Guests have no progress, so
+    /// every card is offered.
+    pub async fn study_cards(db: &PgPool, access: GuestAccess, shuffle: bool) -> Result<Vec<Card>> {
+        let mut cards = Self::cards(db, access.deck_id).await?;
+        if shuffle {
+            cards.shuffle(&mut rand::thread_rng());
+        }
+        Ok(cards)
+    }
+
+    // Helper methods
+
+    async fn cards(db: &PgPool, deck_id: Uuid) -> Result<Vec<Card>> {
+        // The deck may have been encrypted after the token was issued
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+
+        let cards = sqlx::query_as::<_, Card>(
+            "SELECT * FROM cards WHERE deck_id = $1 AND deleted_at IS NULL ORDER BY position",
+        )
+        .bind(deck_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(cards)
+    }
+}
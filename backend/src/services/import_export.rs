@@ -1,52 +1,233 @@
-use chrono::Utc;
-use csv::Writer;
-use sqlx::PgPool;
-use std::fmt::Write;
+use async_zip::{tokio::write::ZipFileWriter, Compression, ZipEntryBuilder};
+use axum::body::Bytes;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures_util::{stream, Stream, StreamExt};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead},
+    sync::Arc,
+};
+use tokio::{io::DuplexStream, sync::oneshot};
+use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 
 use crate::{
     models::{
-        Card, Deck,
+        Card, Deck, DeckRole,
         import_export::*,
     },
+    services::{
+        anki_text,
+        duplicates::DuplicateDetector,
+        exporters::{ExportContext, Exporter, JSON_FORMAT_VERSION},
+        job::JobService,
+        marketplace::{normalize_language, MarketplaceService},
+        media::MediaService,
+        quizlet,
+        reverse_cards::ReverseCardService,
+        scheduler::{DEFAULT_EASE_FACTOR, MIN_EASE_FACTOR},
+        sharing::SharingService,
+        storage::MediaStore,
+        upload::UploadSource,
+    },
     utils::{error::AppError, Result},
 };
 
+/// Name of the manifest inside bulk export archives
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+/// Longest deck title kept in archive file names
+const MAX_FILE_STEM_CHARS: usize = 60;
+/// How far the archive writer may run ahead of the client
+const ARCHIVE_BUFFER_BYTES: usize = 64 * 1024;
+/// Imports running as a job check for cancellation this often, in cards
+const IMPORT_CHECKPOINT_CARDS: usize = 100;
+
 pub struct ImportExportService;
 
+#[derive(FromRow)]
+struct ProgressRow {
+    card_id: Uuid,
+    times_seen: i32,
+    times_correct: i32,
+    last_seen_at: Option<DateTime<Utc>>,
+    next_review_at: Option<DateTime<Utc>>,
+    ease_factor: f32,
+    interval_days: i32,
+    repetitions: i32,
+    lapses: i32,
+}
+
+// The background job an import runs as
+#[derive(Clone)]
+struct ImportJob {
+    db: PgPool,
+    id: Uuid,
+}
+
+// Adds imported cards to one deck, checking each front against the deck's
+// existing cards and the cards imported before it
+struct CardImporter {
+    deck_id: Uuid,
+    strategy: DuplicateStrategy,
+    detector: DuplicateDetector,
+    next_position: i32,
+    actions: ImportCardActions,
+    job: Option<ImportJob>,
+    seen: usize,
+}
+
+impl CardImporter {
+    async fn for_deck(
+        tx: &mut Transaction<'_, Postgres>,
+        deck_id: Uuid,
+        strategy: DuplicateStrategy,
+        job: Option<&ImportJob>,
+    ) -> Result<Self> {
+        let existing = sqlx::query_as::<_, (Uuid, String, i32)>(
+            "SELECT id, front, position FROM cards WHERE deck_id = $1 AND deleted_at IS NULL ORDER BY position",
+        )
+        .bind(deck_id)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        let mut detector = DuplicateDetector::new();
+        let mut next_position = 0;
+        for (card_id, front, position) in &existing {
+            detector.add(*card_id, front);
+            next_position = next_position.max(position + 1);
+        }
+
+        Ok(Self {
+            deck_id,
+            strategy,
+            detector,
+            next_position,
+            actions: ImportCardActions::default(),
+            job: job.cloned(),
+            seen: 0,
+        })
+    }
+
+    // Returns the card the imported data went into, or None when it was
+    // skipped as a duplicate
+    async fn import(
+        &mut self,
+        tx: &mut Transaction<'_, Postgres>,
+        front: &str,
+        back: &str,
+    ) -> Result<Option<Uuid>> {
+        // A cancelled job fails the import, rolling back its transaction
+        self.seen += 1;
+        if let Some(job) = &self.job {
+            if self.seen % IMPORT_CHECKPOINT_CARDS == 0
+                && !JobService::should_continue(&job.db, job.id).await?
+            {
+                return Err(AppError::BadRequest("Import was cancelled".to_string()));
+            }
+        }
+
+        let duplicate = self.detector.find(front).map(|(card_id, _)| card_id);
+        match (duplicate, self.strategy) {
+            (Some(_), DuplicateStrategy::Skip) => {
+                self.actions.skipped += 1;
+                Ok(None)
+            }
+            (Some(card_id), DuplicateStrategy::Overwrite) => {
+                sqlx::query("UPDATE cards SET front = $2, back = $3, updated_at = NOW() WHERE id = $1")
+                    .bind(card_id)
+                    .bind(front)
+                    .bind(back)
+                    .execute(&mut **tx)
+                    .await?;
+                self.actions.overwritten += 1;
+                Ok(Some(card_id))
+            }
+            (duplicate, _) => {
+                let card_id = Uuid::new_v4();
+                sqlx::query!(
+                    r#"
+                    INSERT INTO cards (id, deck_id, front, back, position, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    "#,
+                    card_id,
+                    self.deck_id,
+                    front,
+                    back,
+                    self.next_position,
+                    Utc::now(),
+                    Utc::now()
+                )
+                .execute(&mut **tx)
+                .await?;
+                self.next_position += 1;
+                self.detector.add(card_id, front);
+
+                if duplicate.is_some() {
+                    self.actions.kept_both += 1;
+                } else {
+                    self.actions.created += 1;
+                }
+                Ok(Some(card_id))
+            }
+        }
+    }
+}
+
 impl ImportExportService {
     // Export a single deck
     pub async fn export_deck(
         db: &PgPool,
+        store: &MediaStore,
         user_id: Uuid,
         deck_id: Uuid,
-        format: ExportFormat,
+        exporter: &dyn Exporter,
         include_progress: bool,
         include_media: bool,
     ) -> Result<Vec<u8>> {
+        // Verify read access (owner, collaborator or public)
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+
+        let (_, _, data) =
+            Self::render_deck(db, store, user_id, deck_id, exporter, include_progress, include_media)
+                .await?;
+        MarketplaceService::record_download(db, deck_id, user_id).await?;
+        Ok(data)
+    }
+
+    // Load a deck and run it through the exporter, returning the deck and its
+    // card count alongside the file. Callers check access first.
+    pub(crate) async fn render_deck(
+        db: &PgPool,
+        store: &MediaStore,
+        user_id: Uuid,
+        deck_id: Uuid,
+        exporter: &dyn Exporter,
+        include_progress: bool,
+        include_media: bool,
+    ) -> Result<(Deck, usize, Vec<u8>)> {
         // Get deck details
-        let deck = sqlx::query_as!(
+        let deck: Deck = sqlx::query_as!(
             Deck,
             r#"
             SELECT id, folder_id, owner_id as user_id, title as name, 
-                   description, is_public, created_at, updated_at
+                   description, is_public, front_language, back_language, created_at, updated_at
             FROM decks
-            WHERE id = $1 AND owner_id = $2
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
-            deck_id,
-            user_id
+            deck_id
         )
         .fetch_one(db)
         .await
-        .map_err(|_| AppError::NotFound("Deck not found".to_string()))?;
+        .map_err(|_| AppError::DeckNotFound)?;
 
         // Get cards for the deck
-        let cards = sqlx::query_as!(
+        let cards: Vec<Card> = sqlx::query_as!(
             Card,
             r#"
             SELECT id, deck_id, front, back, position, created_at, updated_at
             FROM cards
-            WHERE deck_id = $1
+            WHERE deck_id = $1 AND deleted_at IS NULL
             ORDER BY position
             "#,
             deck_id
@@ -56,58 +237,161 @@ impl ImportExportService {
 
         // Get progress data if requested
         let card_progress = if include_progress {
-            Self::get_card_progress(db, user_id, deck_id).await?
+            Self::get_card_progress(db, user_id, &cards).await?
+        } else {
+            vec![]
+        };
+
+        // Inline attachments if requested
+        let media = if include_media {
+            let card_ids: Vec<Uuid> = cards.iter().map(|card| card.id).collect();
+            MediaService::export_attachments(db, store, &card_ids).await?
         } else {
             vec![]
         };
 
         // Convert to export format
-        match format {
-            ExportFormat::Json => Self::export_as_json(deck, cards, card_progress),
-            ExportFormat::Csv => Self::export_as_csv(deck, cards),
-            ExportFormat::Anki => Self::export_as_anki(deck, cards, card_progress),
-            ExportFormat::Markdown => Self::export_as_markdown(deck, cards),
-        }
+        let data = exporter.export(&ExportContext {
+            deck: &deck,
+            cards: &cards,
+            user_id,
+            progress: &card_progress,
+            media: &media,
+        })?;
+        Ok((deck, cards.len(), data))
     }
 
-    // Export multiple decks
+    // Export multiple decks as a ZIP archive with one file per deck and a
+    // manifest.json. Access to every deck is checked up front so permission
+    // errors are still reported as such; the archive itself is written by a
+    // background task one deck at a time and streamed to the caller. A
+    // failure mid-archive ends the stream with an error.
     pub async fn export_decks(
-        db: &PgPool,
+        db: PgPool,
+        store: Arc<MediaStore>,
         user_id: Uuid,
         deck_ids: Vec<Uuid>,
-        format: ExportFormat,
+        exporter: Arc<dyn Exporter>,
         include_progress: bool,
         include_media: bool,
-    ) -> Result<Vec<u8>> {
-        let mut all_data = Vec::new();
-
+    ) -> Result<impl Stream<Item = io::Result<Bytes>> + Send + 'static> {
+        let mut unique_ids = Vec::with_capacity(deck_ids.len());
         for deck_id in deck_ids {
-            let deck_data = Self::export_deck(
+            if !unique_ids.contains(&deck_id) {
+                SharingService::require_deck_role(&db, deck_id, user_id, DeckRole::Viewer).await?;
+                unique_ids.push(deck_id);
+            }
+        }
+
+        let (reader, writer) = tokio::io::duplex(ARCHIVE_BUFFER_BYTES);
+        let (failed_tx, failed_rx) = oneshot::channel::<String>();
+
+        tokio::spawn(async move {
+            let result = Self::write_archive(
+                writer,
+                &db,
+                &store,
+                user_id,
+                &unique_ids,
+                exporter.as_ref(),
+                (include_progress, include_media),
+            )
+            .await;
+            if let Err(e) = result {
+                tracing::error!("Bulk export for user {} failed: {}", user_id, e);
+                let _ = failed_tx.send(e);
+            }
+        });
+
+        // The writer is dropped before a failure is reported, so the reader
+        // always sees the end of the data first
+        let failure = stream::once(failed_rx).filter_map(|failed| async move {
+            failed
+                .ok()
+                .map(|message| Err(io::Error::other(format!("Export failed: {}", message))))
+        });
+        Ok(ReaderStream::new(reader).chain(failure))
+    }
+
+    async fn write_archive(
+        writer: DuplexStream,
+        db: &PgPool,
+        store: &MediaStore,
+        user_id: Uuid,
+        deck_ids: &[Uuid],
+        exporter: &dyn Exporter,
+        (include_progress, include_media): (bool, bool),
+    ) -> std::result::Result<(), String> {
+        let mut zip = ZipFileWriter::with_tokio(writer);
+        let mut entries = Vec::with_capacity(deck_ids.len());
+
+        for (index, deck_id) in deck_ids.iter().enumerate() {
+            let (deck, total_cards, data) = Self::render_deck(
                 db,
+                store,
                 user_id,
-                deck_id,
-                format.clone(),
+                *deck_id,
+                exporter,
                 include_progress,
                 include_media,
             )
-            .await?;
-            all_data.extend_from_slice(&deck_data);
+            .await
+            .map_err(|e| e.to_string())?;
+            MarketplaceService::record_download(db, *deck_id, user_id)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let file = archive_file_name(index, &deck.name, exporter.extension());
+            zip.write_entry_whole(ZipEntryBuilder::new(file.clone().into(), Compression::Deflate), &data)
+                .await
+                .map_err(|e| e.to_string())?;
+            entries.push(BulkExportManifestEntry {
+                deck_id: deck.id,
+                title: deck.name,
+                file,
+                total_cards,
+            });
         }
 
-        Ok(all_data)
+        let manifest = BulkExportManifest {
+            version: JSON_FORMAT_VERSION.to_string(),
+            exported_at: Utc::now(),
+            platform: "DeckOracle".to_string(),
+            format: exporter.name().to_string(),
+            includes_progress: include_progress,
+            includes_media: include_media,
+            decks: entries,
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+        zip.write_entry_whole(
+            ZipEntryBuilder::new(MANIFEST_FILE_NAME.to_string().into(), Compression::Deflate),
+            &manifest,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        zip.close().await.map_err(|e| e.to_string())?;
+        Ok(())
     }
 
-    // Import decks from data
+    // Import decks from an upload. CSV, JSON and Markdown files are read as
+    // they are parsed; the other formats are parsed from the whole file.
     pub async fn import_decks(
         db: &PgPool,
+        store: &MediaStore,
         user_id: Uuid,
-        data: Vec<u8>,
-        format: ImportFormat,
-        folder_id: Option<Uuid>,
-        merge_duplicates: bool,
+        source: UploadSource<'_>,
+        params: &ImportJobParameters,
+        job_id: Option<Uuid>,
     ) -> Result<ImportResult> {
+        let folder_id = params.folder_id;
+        let job = job_id.map(|id| ImportJob { db: db.clone(), id });
+        let job = job.as_ref();
+        let merge_duplicates = params.merge_duplicates;
+        let strategy = params.duplicate_strategy;
+
         // Validate import data
-        let validation = Self::validate_import(&data, &format)?;
+        let validation = Self::validate_import(source, &params.format, &params.delimiters)?;
         if !validation.is_valid {
             return Ok(ImportResult {
                 success: false,
@@ -116,189 +400,62 @@ impl ImportExportService {
                 warnings: validation.warnings,
                 total_cards_imported: 0,
                 total_decks_imported: 0,
+                card_actions: ImportCardActions::default(),
             });
         }
 
         // Parse and import based on format
-        match format {
-            ImportFormat::Json => Self::import_from_json(db, user_id, data, folder_id, merge_duplicates).await,
-            ImportFormat::Csv => Self::import_from_csv(db, user_id, data, folder_id, merge_duplicates).await,
-            ImportFormat::Anki => Self::import_from_anki(db, user_id, data, folder_id, merge_duplicates).await,
-            ImportFormat::Markdown => Self::import_from_markdown(db, user_id, data, folder_id, merge_duplicates).await,
-        }
-    }
-
-    // Format-specific export functions
-    fn export_as_json(deck: Deck, cards: Vec<Card>, progress: Vec<CardProgressData>) -> Result<Vec<u8>> {
-        let exported_cards: Vec<ExportedCard> = cards
-            .into_iter()
-            .enumerate()
-            .map(|(i, card)| ExportedCard {
-                id: card.id,
-                front: card.front,
-                back: card.back,
-                explanation: None,
-                tags: vec![],
-                difficulty: None,
-                media: vec![],
-                created_at: card.created_at,
-                updated_at: card.updated_at,
-                progress: progress.get(i).cloned(),
-            })
-            .collect();
-
-        let total_cards = exported_cards.len();
-        let exported_deck = ExportedDeck {
-            id: deck.id,
-            title: deck.name,
-            description: deck.description,
-            tags: vec![],
-            created_at: deck.created_at,
-            updated_at: deck.updated_at,
-            cards: exported_cards,
-            metadata: ExportMetadata {
-                version: "1.0".to_string(),
-                exported_at: Utc::now(),
-                platform: "DeckOracle".to_string(),
-                format: "json".to_string(),
-                total_cards,
-                includes_progress: !progress.is_empty(),
-                includes_media: false,
-            },
-        };
-
-        let json = serde_json::to_vec_pretty(&exported_deck)?;
-        Ok(json)
-    }
-
-    fn export_as_csv(_deck: Deck, cards: Vec<Card>) -> Result<Vec<u8>> {
-        let mut wtr = Writer::from_writer(vec![]);
-        
-        // Write header
-        wtr.write_record(&["Front", "Back", "Tags", "Explanation", "Difficulty"])?;
-        
-        // Write cards
-        for card in cards {
-            let csv_card = CsvCard {
-                front: card.front,
-                back: card.back,
-                tags: String::new(),
-                explanation: String::new(),
-                difficulty: None,
-            };
-            
-            wtr.write_record(&[
-                csv_card.front,
-                csv_card.back,
-                csv_card.tags,
-                csv_card.explanation,
-                csv_card.difficulty.map_or(String::new(), |d| d.to_string()),
-            ])?;
-        }
-        
-        let data = wtr.into_inner()?;
-        Ok(data)
-    }
-
-    fn export_as_anki(deck: Deck, cards: Vec<Card>, progress: Vec<CardProgressData>) -> Result<Vec<u8>> {
-        // Create Anki model (note type)
-        let model = AnkiModel {
-            id: 1,
-            name: "Basic".to_string(),
-            flds: vec![
-                AnkiField { name: "Front".to_string(), ord: 0 },
-                AnkiField { name: "Back".to_string(), ord: 1 },
-            ],
-            tmpls: vec![
-                AnkiTemplate {
-                    name: "Card 1".to_string(),
-                    qfmt: "{{Front}}".to_string(),
-                    afmt: "{{FrontSide}}<hr id=\"answer\">{{Back}}".to_string(),
-                },
-            ],
-        };
-
-        // Convert cards to Anki format
-        let anki_notes: Vec<AnkiNote> = cards
-            .iter()
-            .enumerate()
-            .map(|(i, card)| AnkiNote {
-                id: i as i64 + 1,
-                guid: card.id.to_string(),
-                mid: 1,
-                fields: vec![card.front.clone(), card.back.clone()],
-                tags: vec![],
-            })
-            .collect();
-
-        let anki_cards: Vec<AnkiCard> = cards
-            .iter()
-            .enumerate()
-            .map(|(i, _card)| {
-                let progress = progress.get(i);
-                AnkiCard {
-                    nid: i as i64 + 1,
-                    ord: 0,
-                    did: 1,
-                    due: 0,
-                    ivl: progress.map_or(0, |p| p.interval_days),
-                    factor: progress.map_or(2500, |p| (p.ease_factor * 1000.0) as i32),
-                    reps: progress.map_or(0, |p| p.review_count),
-                    lapses: 0,
-                }
-            })
-            .collect();
-
-        let anki_deck = AnkiDeck {
-            name: deck.name,
-            desc: deck.description.unwrap_or_default(),
-            cards: anki_cards,
-            notes: anki_notes,
-            models: vec![model],
-        };
-
-        // For now, return JSON representation
-        // In production, this would create a proper .apkg file
-        let json = serde_json::to_vec(&anki_deck)?;
-        Ok(json)
-    }
-
-    fn export_as_markdown(deck: Deck, cards: Vec<Card>) -> Result<Vec<u8>> {
-        let mut markdown = String::new();
-        
-        // Write deck header
-        writeln!(markdown, "# {}", deck.name)?;
-        if let Some(desc) = deck.description {
-            writeln!(markdown, "\n{}\n", desc)?;
-        }
-        writeln!(markdown, "---\n")?;
-
-        // Write cards
-        for (i, card) in cards.iter().enumerate() {
-            writeln!(markdown, "## Card {}", i + 1)?;
-            writeln!(markdown, "\n**Front:** {}", card.front)?;
-            writeln!(markdown, "\n**Back:** {}", card.back)?;
-            writeln!(markdown, "\n---\n")?;
+        match params.format {
+            ImportFormat::Json => {
+                Self::import_from_json(db, store, user_id, source, folder_id, merge_duplicates, params.include_progress, strategy, job)
+                    .await
+            }
+            ImportFormat::Csv => Self::import_from_csv(db, user_id, source, folder_id, strategy, job).await,
+            ImportFormat::Anki => Self::import_from_anki(db, user_id, source, folder_id, strategy, job).await,
+            ImportFormat::Markdown => Self::import_from_markdown(db, user_id, source, folder_id, strategy, job).await,
+            ImportFormat::Quizlet => {
+                let data = source.read_all()?;
+                Self::import_from_quizlet(db, user_id, &data, folder_id, &params.delimiters, strategy, job).await
+            }
+            ImportFormat::AnkiText => {
+                let data = source.read_all()?;
+                Self::import_from_anki_text(db, user_id, &data, folder_id, params.html, strategy, job).await
+            }
+            ImportFormat::Mnemosyne => {
+                let decks = parse_mnemosyne(&source.read_all()?, Utc::now()).map_err(AppError::BadRequest)?;
+                Self::import_srs_decks(db, user_id, decks, folder_id, params.include_progress, strategy, job).await
+            }
+            ImportFormat::SuperMemo => {
+                let decks = parse_supermemo(&source.read_all()?, Utc::now()).map_err(AppError::BadRequest)?;
+                Self::import_srs_decks(db, user_id, decks, folder_id, params.include_progress, strategy, job).await
+            }
         }
-
-        Ok(markdown.into_bytes())
     }
 
     // Format-specific import functions
+
+    // Progress in the file is restored only into the account that exported
+    // it, since it describes that user's memory of the cards
+    #[allow(clippy::too_many_arguments)]
     async fn import_from_json(
         db: &PgPool,
+        store: &MediaStore,
         user_id: Uuid,
-        data: Vec<u8>,
+        source: UploadSource<'_>,
         folder_id: Option<Uuid>,
         merge_duplicates: bool,
+        include_progress: bool,
+        strategy: DuplicateStrategy,
+        job: Option<&ImportJob>,
     ) -> Result<ImportResult> {
-        let exported_deck: ExportedDeck = serde_json::from_slice(&data)?;
+        let exported_deck: ExportedDeck = serde_json::from_reader(source.reader()?)?;
+        let own_progress = exported_deck.metadata.exported_by == Some(user_id);
         
         let mut tx = db.begin().await?;
         
         // Check if deck with same name exists
         let existing_deck = sqlx::query!(
-            "SELECT id FROM decks WHERE owner_id = $1 AND title = $2",
+            "SELECT id FROM decks WHERE owner_id = $1 AND title = $2 AND deleted_at IS NULL",
             user_id,
             exported_deck.title
         )
@@ -315,8 +472,9 @@ impl ImportExportService {
             let new_deck_id = Uuid::new_v4();
             sqlx::query!(
                 r#"
-                INSERT INTO decks (id, owner_id, folder_id, title, description, is_public, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                INSERT INTO decks (id, owner_id, folder_id, title, description, is_public,
+                                   front_language, back_language, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
                 "#,
                 new_deck_id,
                 user_id,
@@ -324,6 +482,8 @@ impl ImportExportService {
                 exported_deck.title,
                 exported_deck.description,
                 false,
+                exported_deck.front_language.as_deref().map(normalize_language),
+                exported_deck.back_language.as_deref().map(normalize_language),
                 Utc::now(),
                 Utc::now()
             )
@@ -333,29 +493,51 @@ impl ImportExportService {
         };
 
         // Import cards
-        let mut imported_cards = 0;
-        for (position, card) in exported_deck.cards.iter().enumerate() {
-            sqlx::query!(
-                r#"
-                INSERT INTO cards (id, deck_id, front, back, position, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7)
-                ON CONFLICT (id) DO NOTHING
-                "#,
-                Uuid::new_v4(),
-                deck_id,
-                card.front,
-                card.back,
-                position as i32,
-                Utc::now(),
-                Utc::now()
-            )
-            .execute(&mut *tx)
-            .await?;
-            imported_cards += 1;
+        let mut importer = CardImporter::for_deck(&mut tx, deck_id, strategy, job).await?;
+        let mut warnings = Vec::new();
+        let mut history_skipped = 0;
+        for card in &exported_deck.cards {
+            let Some(card_id) = importer.import(&mut tx, &card.front, &card.back).await? else {
+                continue;
+            };
+
+            for attachment in &card.media {
+                if let Some(warning) =
+                    MediaService::import_attachment(&mut tx, store, card_id, user_id, attachment).await?
+                {
+                    warnings.push(warning);
+                }
+            }
+
+            if let Some(progress) = &card.progress {
+                if include_progress && own_progress {
+                    Self::restore_progress(&mut tx, user_id, card_id, progress).await?;
+                } else {
+                    history_skipped += 1;
+                }
+            }
         }
 
         tx.commit().await?;
+        if history_skipped > 0 {
+            warnings.push(if own_progress {
+                format!(
+                    "Review history of {} cards was not imported; set include_progress to keep it",
+                    history_skipped
+                )
+            } else {
+                format!(
+                    "Review history of {} cards was not imported because it was exported from another account",
+                    history_skipped
+                )
+            });
+        }
+        // Only a merged deck can already keep reverse cards
+        if existing_deck.is_some() {
+            ReverseCardService::generate_if_enabled(db, deck_id).await?;
+        }
 
+        let imported_cards = importer.actions.imported();
         Ok(ImportResult {
             success: true,
             imported_decks: vec![ImportedDeck {
@@ -365,34 +547,23 @@ impl ImportExportService {
                 was_merged: existing_deck.is_some(),
             }],
             errors: vec![],
-            warnings: vec![],
+            warnings,
             total_cards_imported: imported_cards,
             total_decks_imported: 1,
+            card_actions: importer.actions,
         })
     }
 
     async fn import_from_csv(
         db: &PgPool,
         user_id: Uuid,
-        data: Vec<u8>,
+        source: UploadSource<'_>,
         folder_id: Option<Uuid>,
-        _merge_duplicates: bool,
+        strategy: DuplicateStrategy,
+        job: Option<&ImportJob>,
     ) -> Result<ImportResult> {
-        let mut rdr = csv::Reader::from_reader(&data[..]);
-        let mut cards = Vec::new();
-
-        for result in rdr.records() {
-            let record = result?;
-            if record.len() >= 2 {
-                cards.push(CsvCard {
-                    front: record[0].to_string(),
-                    back: record[1].to_string(),
-                    tags: record.get(2).unwrap_or("").to_string(),
-                    explanation: record.get(3).unwrap_or("").to_string(),
-                    difficulty: record.get(4).and_then(|s| s.parse().ok()),
-                });
-            }
-        }
+        // Rows are imported as they are read rather than collected first
+        let mut rdr = csv::Reader::from_reader(source.reader()?);
 
         // Create a new deck for CSV import
         let deck_id = Uuid::new_v4();
@@ -418,50 +589,43 @@ impl ImportExportService {
         .await?;
 
         // Import cards
-        for (position, card) in cards.iter().enumerate() {
-            sqlx::query!(
-                r#"
-                INSERT INTO cards (id, deck_id, front, back, position, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7)
-                "#,
-                Uuid::new_v4(),
-                deck_id,
-                card.front,
-                card.back,
-                position as i32,
-                Utc::now(),
-                Utc::now()
-            )
-            .execute(&mut *tx)
-            .await?;
+        let mut importer = CardImporter::for_deck(&mut tx, deck_id, strategy, job).await?;
+        for result in rdr.records() {
+            let record = result?;
+            if record.len() >= 2 {
+                importer.import(&mut tx, &record[0], &record[1]).await?;
+            }
         }
 
         tx.commit().await?;
 
+        let imported_cards = importer.actions.imported();
         Ok(ImportResult {
             success: true,
             imported_decks: vec![ImportedDeck {
                 id: deck_id,
                 title: deck_title.clone(),
-                card_count: cards.len(),
+                card_count: imported_cards,
                 was_merged: false,
             }],
             errors: vec![],
             warnings: vec![],
-            total_cards_imported: cards.len(),
+            total_cards_imported: imported_cards,
             total_decks_imported: 1,
+            card_actions: importer.actions,
         })
     }
 
     async fn import_from_anki(
         db: &PgPool,
         user_id: Uuid,
-        data: Vec<u8>,
+        source: UploadSource<'_>,
         folder_id: Option<Uuid>,
-        _merge_duplicates: bool,
+        strategy: DuplicateStrategy,
+        job: Option<&ImportJob>,
     ) -> Result<ImportResult> {
         // Parse Anki JSON (simplified - real implementation would handle .apkg files)
-        let anki_deck: AnkiDeck = serde_json::from_slice(&data)?;
+        let anki_deck: AnkiDeck = serde_json::from_reader(source.reader()?)?;
 
         let deck_id = Uuid::new_v4();
         let mut tx = db.begin().await?;
@@ -484,53 +648,41 @@ impl ImportExportService {
         .await?;
 
         // Import notes as cards
-        for (position, note) in anki_deck.notes.iter().enumerate() {
+        let mut importer = CardImporter::for_deck(&mut tx, deck_id, strategy, job).await?;
+        for note in &anki_deck.notes {
             if note.fields.len() >= 2 {
-                sqlx::query!(
-                    r#"
-                    INSERT INTO cards (id, deck_id, front, back, position, created_at, updated_at)
-                    VALUES ($1, $2, $3, $4, $5, $6, $7)
-                    "#,
-                    Uuid::new_v4(),
-                    deck_id,
-                    note.fields[0],
-                    note.fields[1],
-                    position as i32,
-                    Utc::now(),
-                    Utc::now()
-                )
-                .execute(&mut *tx)
-                .await?;
+                importer.import(&mut tx, &note.fields[0], &note.fields[1]).await?;
             }
         }
 
         tx.commit().await?;
 
+        let imported_cards = importer.actions.imported();
         Ok(ImportResult {
             success: true,
             imported_decks: vec![ImportedDeck {
                 id: deck_id,
                 title: anki_deck.name.clone(),
-                card_count: anki_deck.notes.len(),
+                card_count: imported_cards,
                 was_merged: false,
             }],
             errors: vec![],
             warnings: vec![],
-            total_cards_imported: anki_deck.notes.len(),
+            total_cards_imported: imported_cards,
             total_decks_imported: 1,
+            card_actions: importer.actions,
         })
     }
 
     async fn import_from_markdown(
         db: &PgPool,
         user_id: Uuid,
-        data: Vec<u8>,
+        source: UploadSource<'_>,
         folder_id: Option<Uuid>,
-        merge_duplicates: bool,
+        strategy: DuplicateStrategy,
+        job: Option<&ImportJob>,
     ) -> Result<ImportResult> {
-        let content = String::from_utf8(data)?;
-        let lines: Vec<&str> = content.lines().collect();
-        
+
         let mut deck_title = "Imported from Markdown".to_string();
         let mut deck_description: Option<String> = None;
         let mut cards = Vec::new();
@@ -538,7 +690,10 @@ impl ImportExportService {
         let mut in_front = false;
         let mut in_back = false;
 
-        for line in lines {
+        for line in source.reader()?.lines() {
+            let line = line.map_err(|_| {
+                AppError::BadRequest("Invalid UTF-8 encoding in Markdown file".to_string())
+            })?;
             if line.starts_with("# ") {
                 deck_title = line[2..].trim().to_string();
             } else if line.starts_with("## Card") {
@@ -587,53 +742,372 @@ impl ImportExportService {
         .execute(&mut *tx)
         .await?;
 
-        for (position, (front, back)) in cards.iter().enumerate() {
+        let mut importer = CardImporter::for_deck(&mut tx, deck_id, strategy, job).await?;
+        for (front, back) in &cards {
+            importer.import(&mut tx, front, back).await?;
+        }
+
+        tx.commit().await?;
+
+        let imported_cards = importer.actions.imported();
+        Ok(ImportResult {
+            success: true,
+            imported_decks: vec![ImportedDeck {
+                id: deck_id,
+                title: deck_title.clone(),
+                card_count: imported_cards,
+                was_merged: false,
+            }],
+            errors: vec![],
+            warnings: vec![],
+            total_cards_imported: imported_cards,
+            total_decks_imported: 1,
+            card_actions: importer.actions,
+        })
+    }
+
+    async fn import_from_quizlet(
+        db: &PgPool,
+        user_id: Uuid,
+        data: &[u8],
+        folder_id: Option<Uuid>,
+        delimiters: &TextDelimiters,
+        strategy: DuplicateStrategy,
+        job: Option<&ImportJob>,
+    ) -> Result<ImportResult> {
+        let parsed = quizlet::parse(data, delimiters).map_err(AppError::BadRequest)?;
+
+        let mut warnings = Vec::new();
+        if parsed.skipped_rows > 0 {
+            warnings.push(format!(
+                "Skipped {} rows without both a term and a definition",
+                parsed.skipped_rows
+            ));
+        }
+
+        // Each set becomes its own deck
+        let mut tx = db.begin().await?;
+        let mut imported_decks = Vec::with_capacity(parsed.sets.len());
+        let mut card_actions = ImportCardActions::default();
+        for set in &parsed.sets {
+            let deck_id = Uuid::new_v4();
             sqlx::query!(
                 r#"
-                INSERT INTO cards (id, deck_id, front, back, position, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                INSERT INTO decks (id, owner_id, folder_id, title, description, is_public, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
                 "#,
-                Uuid::new_v4(),
                 deck_id,
-                front,
-                back,
-                position as i32,
+                user_id,
+                folder_id,
+                set.title,
+                set.description.clone().or_else(|| Some("Imported from Quizlet".to_string())),
+                false,
                 Utc::now(),
                 Utc::now()
             )
             .execute(&mut *tx)
             .await?;
+
+            let mut importer = CardImporter::for_deck(&mut tx, deck_id, strategy, job).await?;
+            for term in &set.terms {
+                importer.import(&mut tx, &term.term, &term.definition).await?;
+            }
+
+            if set.terms.is_empty() {
+                warnings.push(format!("Set \"{}\" contains no terms", set.title));
+            }
+            imported_decks.push(ImportedDeck {
+                id: deck_id,
+                title: set.title.clone(),
+                card_count: importer.actions.imported(),
+                was_merged: false,
+            });
+            card_actions.merge(importer.actions);
         }
 
         tx.commit().await?;
 
         Ok(ImportResult {
             success: true,
-            imported_decks: vec![ImportedDeck {
+            total_decks_imported: imported_decks.len(),
+            imported_decks,
+            errors: vec![],
+            warnings,
+            total_cards_imported: card_actions.imported(),
+            card_actions,
+        })
+    }
+
+    // Anki's Notes in Plain Text export: one deck per Anki deck named in the file
+    async fn import_from_anki_text(
+        db: &PgPool,
+        user_id: Uuid,
+        data: &[u8],
+        folder_id: Option<Uuid>,
+        html: HtmlHandling,
+        strategy: DuplicateStrategy,
+        job: Option<&ImportJob>,
+    ) -> Result<ImportResult> {
+        let parsed = anki_text::parse(data, html).map_err(AppError::BadRequest)?;
+
+        let mut warnings = Vec::new();
+        if parsed.skipped_rows > 0 {
+            warnings.push(format!(
+                "Skipped {} rows without both a front and a back",
+                parsed.skipped_rows
+            ));
+        }
+
+        let mut tx = db.begin().await?;
+        let mut imported_decks = Vec::with_capacity(parsed.decks.len());
+        let mut card_actions = ImportCardActions::default();
+        for deck in &parsed.decks {
+            let deck_id = Uuid::new_v4();
+            sqlx::query!(
+                r#"
+                INSERT INTO decks (id, owner_id, folder_id, title, description, is_public, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+                deck_id,
+                user_id,
+                folder_id,
+                deck.title,
+                Some("Imported from Anki".to_string()),
+                false,
+                Utc::now(),
+                Utc::now()
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            let mut importer = CardImporter::for_deck(&mut tx, deck_id, strategy, job).await?;
+            for (front, back) in &deck.notes {
+                importer.import(&mut tx, front, back).await?;
+            }
+
+            imported_decks.push(ImportedDeck {
                 id: deck_id,
-                title: deck_title.clone(),
-                card_count: cards.len(),
+                title: deck.title.clone(),
+                card_count: importer.actions.imported(),
                 was_merged: false,
-            }],
+            });
+            card_actions.merge(importer.actions);
+        }
+
+        tx.commit().await?;
+
+        Ok(ImportResult {
+            success: true,
+            total_decks_imported: imported_decks.len(),
+            imported_decks,
             errors: vec![],
-            warnings: vec![],
-            total_cards_imported: cards.len(),
-            total_decks_imported: 1,
+            warnings,
+            total_cards_imported: card_actions.imported(),
+            card_actions,
+        })
+    }
+
+    // Mnemosyne and SuperMemo: one deck per category or topic, optionally
+    // with each card's scheduling state copied into user_card_stats
+    async fn import_srs_decks(
+        db: &PgPool,
+        user_id: Uuid,
+        decks: Vec<SrsDeck>,
+        folder_id: Option<Uuid>,
+        include_progress: bool,
+        strategy: DuplicateStrategy,
+        job: Option<&ImportJob>,
+    ) -> Result<ImportResult> {
+        let mut tx = db.begin().await?;
+        let mut imported_decks = Vec::with_capacity(decks.len());
+        let mut card_actions = ImportCardActions::default();
+        let mut history_skipped = 0;
+
+        for deck in &decks {
+            let deck_id = Uuid::new_v4();
+            sqlx::query!(
+                r#"
+                INSERT INTO decks (id, owner_id, folder_id, title, description, is_public, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+                deck_id,
+                user_id,
+                folder_id,
+                deck.title,
+                None::<String>,
+                false,
+                Utc::now(),
+                Utc::now()
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            let mut importer = CardImporter::for_deck(&mut tx, deck_id, strategy, job).await?;
+            for card in &deck.cards {
+                let Some(card_id) = importer.import(&mut tx, &card.front, &card.back).await? else {
+                    continue;
+                };
+
+                let Some(progress) = &card.progress else {
+                    continue;
+                };
+                if !include_progress {
+                    history_skipped += 1;
+                    continue;
+                }
+                // The exports only count lapses, so every other review is
+                // taken as correct
+                sqlx::query(
+                    r#"
+                    INSERT INTO user_card_stats (
+                        user_id, card_id, times_seen, times_correct, times_incorrect,
+                        last_seen_at, next_review_at, ease_factor, interval_days, repetitions, lapses
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                    ON CONFLICT (user_id, card_id) DO NOTHING
+                    "#,
+                )
+                .bind(user_id)
+                .bind(card_id)
+                .bind(progress.times_seen)
+                .bind((progress.times_seen - progress.lapses).max(0))
+                .bind(progress.lapses.min(progress.times_seen))
+                .bind(progress.last_review)
+                .bind(progress.next_review)
+                .bind(progress.ease_factor)
+                .bind(progress.interval_days)
+                .bind(progress.repetitions)
+                .bind(progress.lapses)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            imported_decks.push(ImportedDeck {
+                id: deck_id,
+                title: deck.title.clone(),
+                card_count: importer.actions.imported(),
+                was_merged: false,
+            });
+            card_actions.merge(importer.actions);
+        }
+
+        tx.commit().await?;
+
+        let mut warnings = Vec::new();
+        if history_skipped > 0 {
+            warnings.push(format!(
+                "Review history of {} cards was not imported; set include_progress to keep it",
+                history_skipped
+            ));
+        }
+        Ok(ImportResult {
+            success: true,
+            total_decks_imported: imported_decks.len(),
+            imported_decks,
+            errors: vec![],
+            warnings,
+            total_cards_imported: card_actions.imported(),
+            card_actions,
         })
     }
 
     // Helper functions
+
+    // The user's scheduling state for each card, in card order; None for
+    // cards they have never studied
     async fn get_card_progress(
-        _db: &PgPool,
-        _user_id: Uuid,
-        _deck_id: Uuid,
-    ) -> Result<Vec<CardProgressData>> {
-        // Query card progress from database
-        // This is a simplified version
-        Ok(vec![])
+        db: &PgPool,
+        user_id: Uuid,
+        cards: &[Card],
+    ) -> Result<Vec<Option<CardProgressData>>> {
+        let card_ids: Vec<Uuid> = cards.iter().map(|card| card.id).collect();
+        let rows = sqlx::query_as::<_, ProgressRow>(
+            r#"
+            SELECT card_id, COALESCE(times_seen, 0) as times_seen, COALESCE(times_correct, 0) as times_correct,
+                   last_seen_at, next_review_at, COALESCE(ease_factor, $3) as ease_factor,
+                   COALESCE(interval_days, 0) as interval_days, repetitions, lapses
+            FROM user_card_stats
+            WHERE user_id = $1 AND card_id = ANY($2) AND COALESCE(times_seen, 0) > 0
+            "#,
+        )
+        .bind(user_id)
+        .bind(&card_ids)
+        .bind(DEFAULT_EASE_FACTOR)
+        .fetch_all(db)
+        .await?;
+
+        let mut progress: HashMap<Uuid, CardProgressData> = rows
+            .into_iter()
+            .map(|row| {
+                let data = CardProgressData {
+                    review_count: row.times_seen,
+                    correct_count: row.times_correct,
+                    last_reviewed: row.last_seen_at,
+                    next_review: row.next_review_at,
+                    ease_factor: row.ease_factor,
+                    interval_days: row.interval_days,
+                    repetitions: row.repetitions,
+                    lapses: row.lapses,
+                };
+                (row.card_id, data)
+            })
+            .collect();
+        Ok(card_ids.iter().map(|id| progress.remove(id)).collect())
     }
 
-    pub fn validate_import(data: &[u8], format: &ImportFormat) -> Result<ImportValidationResult> {
+    // Write exported scheduling state back for an imported card. A card the
+    // user has reviewed since the export keeps its newer state.
+    async fn restore_progress(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        card_id: Uuid,
+        progress: &CardProgressData,
+    ) -> Result<()> {
+        let seen = progress.review_count.max(0);
+        let correct = progress.correct_count.clamp(0, seen);
+        sqlx::query(
+            r#"
+            INSERT INTO user_card_stats (
+                user_id, card_id, times_seen, times_correct, times_incorrect,
+                last_seen_at, next_review_at, ease_factor, interval_days, repetitions, lapses
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (user_id, card_id) DO UPDATE
+            SET times_seen = EXCLUDED.times_seen,
+                times_correct = EXCLUDED.times_correct,
+                times_incorrect = EXCLUDED.times_incorrect,
+                last_seen_at = EXCLUDED.last_seen_at,
+                next_review_at = EXCLUDED.next_review_at,
+                ease_factor = EXCLUDED.ease_factor,
+                interval_days = EXCLUDED.interval_days,
+                repetitions = EXCLUDED.repetitions,
+                lapses = EXCLUDED.lapses
+            WHERE user_card_stats.last_seen_at IS NULL
+               OR user_card_stats.last_seen_at < EXCLUDED.last_seen_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .bind(seen)
+        .bind(correct)
+        .bind(seen - correct)
+        .bind(progress.last_reviewed)
+        .bind(progress.next_review)
+        .bind(progress.ease_factor.clamp(MIN_EASE_FACTOR, MAX_IMPORTED_EASE_FACTOR))
+        .bind(progress.interval_days.max(0))
+        .bind(progress.repetitions.max(0))
+        .bind(progress.lapses.max(0))
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn validate_import(
+        source: UploadSource<'_>,
+        format: &ImportFormat,
+        delimiters: &TextDelimiters,
+    ) -> Result<ImportValidationResult> {
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
         let mut deck_count = 0;
@@ -641,7 +1115,7 @@ impl ImportExportService {
 
         match format {
             ImportFormat::Json => {
-                match serde_json::from_slice::<ExportedDeck>(data) {
+                match serde_json::from_reader::<_, ExportedDeck>(source.reader()?) {
                     Ok(deck) => {
                         deck_count = 1;
                         card_count = deck.cards.len();
@@ -655,7 +1129,7 @@ impl ImportExportService {
                 }
             }
             ImportFormat::Csv => {
-                let rdr = csv::Reader::from_reader(data);
+                let rdr = csv::Reader::from_reader(source.reader()?);
                 card_count = rdr.into_records().count();
                 deck_count = 1;
                 if card_count == 0 {
@@ -663,7 +1137,7 @@ impl ImportExportService {
                 }
             }
             ImportFormat::Anki => {
-                match serde_json::from_slice::<AnkiDeck>(data) {
+                match serde_json::from_reader::<_, AnkiDeck>(source.reader()?) {
                     Ok(deck) => {
                         deck_count = 1;
                         card_count = deck.notes.len();
@@ -677,9 +1151,14 @@ impl ImportExportService {
                 }
             }
             ImportFormat::Markdown => {
-                if let Ok(content) = String::from_utf8(data.to_vec()) {
+                let cards: io::Result<usize> = source
+                    .reader()?
+                    .lines()
+                    .map(|line| line.map(|line| line.matches("## Card").count()))
+                    .sum();
+                if let Ok(cards) = cards {
                     deck_count = 1;
-                    card_count = content.matches("## Card").count();
+                    card_count = cards;
                     if card_count == 0 {
                         warnings.push("Markdown file contains no cards".to_string());
                     }
@@ -687,6 +1166,55 @@ impl ImportExportService {
                     errors.push("Invalid UTF-8 encoding in Markdown file".to_string());
                 }
             }
+            ImportFormat::Quizlet => match quizlet::parse(&source.read_all()?, delimiters) {
+                Ok(parsed) => {
+                    deck_count = parsed.sets.len();
+                    card_count = parsed.sets.iter().map(|set| set.terms.len()).sum();
+                    if card_count == 0 {
+                        errors.push("Quizlet export contains no terms".to_string());
+                    }
+                    if parsed.skipped_rows > 0 {
+                        warnings.push(format!(
+                            "{} rows have no term or definition and will be skipped",
+                            parsed.skipped_rows
+                        ));
+                    }
+                }
+                Err(e) => errors.push(e),
+            },
+            ImportFormat::AnkiText => match anki_text::parse(&source.read_all()?, HtmlHandling::default()) {
+                Ok(parsed) => {
+                    deck_count = parsed.decks.len();
+                    card_count = parsed.decks.iter().map(|deck| deck.notes.len()).sum();
+                    if card_count == 0 {
+                        errors.push("Anki text export contains no notes".to_string());
+                    }
+                    if parsed.skipped_rows > 0 {
+                        warnings.push(format!(
+                            "{} rows have no front or back and will be skipped",
+                            parsed.skipped_rows
+                        ));
+                    }
+                }
+                Err(e) => errors.push(e),
+            },
+            ImportFormat::Mnemosyne | ImportFormat::SuperMemo => {
+                let data = source.read_all()?;
+                let parsed = match format {
+                    ImportFormat::Mnemosyne => parse_mnemosyne(&data, Utc::now()),
+                    _ => parse_supermemo(&data, Utc::now()),
+                };
+                match parsed {
+                    Ok(decks) => {
+                        deck_count = decks.len();
+                        card_count = decks.iter().map(|deck| deck.cards.len()).sum();
+                        if card_count == 0 {
+                            errors.push("Export contains no cards".to_string());
+                        }
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
         }
 
         Ok(ImportValidationResult {
@@ -698,3 +1226,201 @@ impl ImportExportService {
         })
     }
 }
+
+/// File name for the `index`-th deck in a bulk export archive: a one-based
+/// sequence number keeps names unique, followed by the title reduced to
+/// lowercase ASCII words joined by dashes
+pub fn archive_file_name(index: usize, title: &str, extension: &str) -> String {
+    let mut stem = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            stem.push(c.to_ascii_lowercase());
+        } else if !stem.is_empty() && !stem.ends_with('-') {
+            stem.push('-');
+        }
+        if stem.len() >= MAX_FILE_STEM_CHARS {
+            break;
+        }
+    }
+    let stem = stem.trim_end_matches('-');
+    let stem = if stem.is_empty() { "deck" } else { stem };
+    format!("{:03}-{}.{}", index + 1, stem, extension)
+}
+
+/// Deck for Mnemosyne cards without a category
+pub const MNEMOSYNE_DEFAULT_DECK: &str = "Imported from Mnemosyne";
+/// Deck for SuperMemo items outside any topic
+pub const SUPERMEMO_DEFAULT_DECK: &str = "Imported from SuperMemo";
+/// Highest ease factor kept from imported scheduling data. SuperMemo's
+/// A-factors in particular run far above what SM-2 ever reaches.
+pub const MAX_IMPORTED_EASE_FACTOR: f32 = 4.0;
+
+/// Read a Mnemosyne 1.x XML export: one deck per category, in the order
+/// categories are declared. Repetition days are relative to the
+/// collection's `time_of_start`; without it, reviewed cards are due `now`.
+pub fn parse_mnemosyne(data: &[u8], now: DateTime<Utc>) -> std::result::Result<Vec<SrsDeck>, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "Invalid UTF-8 encoding".to_string())?;
+    let export: MnemosyneExport =
+        quick_xml::de::from_str(text).map_err(|e| format!("Invalid Mnemosyne XML: {}", e))?;
+    let start = export.time_of_start.and_then(|secs| DateTime::from_timestamp(secs, 0));
+
+    let mut decks: Vec<SrsDeck> = export
+        .categories
+        .iter()
+        .map(|category| SrsDeck {
+            title: category.name.clone(),
+            cards: vec![],
+        })
+        .collect();
+    for item in export.items {
+        let progress = mnemosyne_progress(&item, start, now);
+        let title = item
+            .category
+            .filter(|category| !category.trim().is_empty())
+            .unwrap_or_else(|| MNEMOSYNE_DEFAULT_DECK.to_string());
+        let card = SrsCard {
+            front: item.question,
+            back: item.answer,
+            progress,
+        };
+        match decks.iter_mut().find(|deck| deck.title == title) {
+            Some(deck) => deck.cards.push(card),
+            None => decks.push(SrsDeck {
+                title,
+                cards: vec![card],
+            }),
+        }
+    }
+
+    decks.retain(|deck| !deck.cards.is_empty());
+    Ok(decks)
+}
+
+fn mnemosyne_progress(
+    item: &MnemosyneItem,
+    start: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<SrsProgress> {
+    let times_seen = item.acquisition_reps.unwrap_or(0) + item.retention_reps.unwrap_or(0);
+    let grade = item.grade.unwrap_or(-1);
+    if times_seen == 0 || grade < 0 {
+        return None;
+    }
+
+    let day = |days: Option<i64>| start.zip(days).map(|(start, days)| start + Duration::days(days));
+    let last_review = day(item.last_rep);
+    // Grades 0 and 1 leave a card unlearned, due again right away
+    let (interval_days, repetitions, next_review) = if grade < 2 {
+        (0, 0, now)
+    } else {
+        let interval = match (item.last_rep, item.next_rep) {
+            (Some(last), Some(next)) => (next - last).max(0) as i32,
+            _ => 0,
+        };
+        (
+            interval,
+            item.retention_reps_since_lapse.unwrap_or(0),
+            day(item.next_rep).unwrap_or(now),
+        )
+    };
+
+    Some(SrsProgress {
+        times_seen,
+        lapses: item.lapses.unwrap_or(0),
+        repetitions,
+        ease_factor: imported_ease_factor(item.easiness),
+        interval_days,
+        last_review,
+        next_review,
+    })
+}
+
+/// Read a SuperMemo collection XML export: one deck per topic, named by
+/// its title, holding the items directly inside it. Items without a
+/// `LastRepetition` date are due `now`.
+pub fn parse_supermemo(data: &[u8], now: DateTime<Utc>) -> std::result::Result<Vec<SrsDeck>, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "Invalid UTF-8 encoding".to_string())?;
+    let collection: SuperMemoCollection =
+        quick_xml::de::from_str(text).map_err(|e| format!("Invalid SuperMemo XML: {}", e))?;
+
+    let mut decks = Vec::new();
+    collect_supermemo(&collection.elements, SUPERMEMO_DEFAULT_DECK, now, &mut decks);
+    decks.retain(|deck| !deck.cards.is_empty());
+    Ok(decks)
+}
+
+fn collect_supermemo(elements: &[SuperMemoElement], title: &str, now: DateTime<Utc>, decks: &mut Vec<SrsDeck>) {
+    for element in elements {
+        let question = element.content.as_ref().and_then(|content| content.question.as_deref());
+        if let Some(question) = question.filter(|question| !question.trim().is_empty()) {
+            let card = SrsCard {
+                front: question.trim().to_string(),
+                back: element
+                    .content
+                    .as_ref()
+                    .and_then(|content| content.answer.as_deref())
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+                progress: element.learning_data.as_ref().and_then(|data| supermemo_progress(data, now)),
+            };
+            match decks.iter_mut().find(|deck| deck.title == title) {
+                Some(deck) => deck.cards.push(card),
+                None => decks.push(SrsDeck {
+                    title: title.to_string(),
+                    cards: vec![card],
+                }),
+            }
+        }
+
+        if !element.children.is_empty() {
+            let topic = element
+                .title
+                .as_deref()
+                .map(str::trim)
+                .filter(|topic| !topic.is_empty())
+                .unwrap_or(title);
+            collect_supermemo(&element.children, topic, now, decks);
+        }
+    }
+}
+
+fn supermemo_progress(data: &SuperMemoLearningData, now: DateTime<Utc>) -> Option<SrsProgress> {
+    let times_seen = data.repetitions.unwrap_or(0);
+    if times_seen <= 0 {
+        return None;
+    }
+
+    let last_review = data
+        .last_repetition
+        .as_deref()
+        .and_then(|date| {
+            NaiveDate::parse_from_str(date.trim(), "%d.%m.%Y")
+                .or_else(|_| NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d"))
+                .ok()
+        })
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc());
+    let interval_days = data.interval.unwrap_or(0).max(0);
+
+    Some(SrsProgress {
+        times_seen,
+        lapses: data.lapses.unwrap_or(0),
+        // SM-2 only looks at repetitions to pick the first two intervals,
+        // so the total serves for any card past those
+        repetitions: times_seen,
+        // The U-factor (interval growth) is the closest thing to an SM-2 ease
+        ease_factor: imported_ease_factor(data.u_factor.or(data.a_factor)),
+        interval_days,
+        last_review,
+        next_review: last_review
+            .map(|last| last + Duration::days(interval_days as i64))
+            .unwrap_or(now),
+    })
+}
+
+fn imported_ease_factor(ease: Option<f32>) -> f32 {
+    ease.filter(|ease| ease.is_finite())
+        .unwrap_or(DEFAULT_EASE_FACTOR)
+        .clamp(MIN_EASE_FACTOR, MAX_IMPORTED_EASE_FACTOR)
+}
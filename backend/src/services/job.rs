@@ -0,0 +1,441 @@
+use serde_json::{json, Value as JsonValue};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::AiConfig,
+    models::{
+        admin::{ConsistencyJobParameters, ConsistencyReport},
+        import_export::ImportJobParameters,
+        job::{BackgroundJob, JobSummary, JobsQuery},
+    },
+    services::{
+        ai_usage::AiUsageService,
+        consistency::{ConsistencyCheck, ConsistencyService},
+        import_export::ImportExportService,
+        queue_cache::QueueCache,
+        storage::MediaStore,
+        upload::UploadSource,
+    },
+    utils::{AppError, Result},
+};
+
+/// Background job types that can be re-run from their stored parameters.
+/// AI generation jobs can always be retried while they keep their input.
+const RETRYABLE_JOB_TYPES: &[&str] = &["import", "consistency_check"];
+
+pub struct JobService;
+
+impl JobService {
+    /// List the user's jobs across all job sources, newest first
+    pub async fn list_user_jobs(
+        db: &PgPool,
+        user_id: Uuid,
+        query: &JobsQuery,
+    ) -> Result<Vec<JobSummary>> {
+        let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+        let jobs = sqlx::query_as::<_, JobSummary>(
+            r#"
+            SELECT id, user_id, source, job_type, status, progress, result, result_url,
+                   error_message, cancellable, created_at, started_at, completed_at,
+                   retry_of, cancel_requested
+            FROM user_jobs
+            WHERE user_id = $1
+                AND ($2::text IS NULL OR status = $2)
+                AND ($3::text IS NULL OR job_type = $3)
+            ORDER BY created_at DESC
+            LIMIT $4
+            "#
+        )
+        .bind(user_id)
+        .bind(&query.status)
+        .bind(&query.job_type)
+        .bind(limit)
+        .fetch_all(db)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    pub async fn get_user_job(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<JobSummary> {
+        let job = sqlx::query_as::<_, JobSummary>(
+            r#"
+            SELECT id, user_id, source, job_type, status, progress, result, result_url,
+                   error_message, cancellable, created_at, started_at, completed_at,
+                   retry_of, cancel_requested
+            FROM user_jobs
+            WHERE id = $1 AND user_id = $2
+            "#
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+
+        Ok(job)
+    }
+
+    pub async fn create_job(
+        db: &PgPool,
+        user_id: Uuid,
+        job_type: &str,
+        parameters: JsonValue,
+        payload: Option<&[u8]>,
+        cancellable: bool,
+    ) -> Result<BackgroundJob> {
+        let job = sqlx::query_as::<_, BackgroundJob>(
+            r#"
+            INSERT INTO background_jobs (user_id, job_type, parameters, payload, cancellable)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(user_id)
+        .bind(job_type)
+        .bind(parameters)
+        .bind(payload)
+        .bind(cancellable)
+        .fetch_one(db)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// Create a job and run it in the background right away
+    pub async fn enqueue(
+        db: &PgPool,
+        media: &Arc<MediaStore>,
+        queue_cache: &Arc<QueueCache>,
+        user_id: Uuid,
+        job_type: &str,
+        parameters: JsonValue,
+        cancellable: bool,
+    ) -> Result<JobSummary> {
+        let job = Self::create_job(db, user_id, job_type, parameters, None, cancellable).await?;
+        Self::spawn(db.clone(), media.clone(), queue_cache.clone(), job.clone());
+
+        Self::get_user_job(db, job.id, user_id).await
+    }
+
+    pub async fn mark_processing(db: &PgPool, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE background_jobs
+            SET status = 'processing', started_at = COALESCE(started_at, NOW()), updated_at = NOW()
+            WHERE id = $1 AND status = 'pending'
+            "#
+        )
+        .bind(id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_progress(db: &PgPool, id: Uuid, progress: i32) -> Result<()> {
+        sqlx::query(
+            "UPDATE background_jobs SET progress = $2, updated_at = NOW() WHERE id = $1"
+        )
+        .bind(id)
+        .bind(progress.clamp(0, 100))
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn complete_job(
+        db: &PgPool,
+        id: Uuid,
+        result: JsonValue,
+        result_url: Option<String>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE background_jobs
+            SET status = 'completed', progress = 100, result = $2, result_url = $3,
+                payload = NULL, completed_at = NOW(), updated_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .bind(result)
+        .bind(result_url)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failure, unless the job was cancelled, which also makes its
+    /// worker give up with an error
+    pub async fn fail_job(db: &PgPool, id: Uuid, error_message: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE background_jobs
+            SET status = 'failed', error_message = $2, completed_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND status <> 'cancelled'
+            "#
+        )
+        .bind(id)
+        .bind(error_message)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Request cancellation. Pending jobs are cancelled immediately; running
+    /// jobs are flagged and stop at their next checkpoint.
+    pub async fn cancel_job(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<JobSummary> {
+        let job = Self::get_user_job(db, id, user_id).await?;
+
+        if !matches!(job.status.as_str(), "pending" | "processing") {
+            return Err(AppError::BadRequest("Job has already finished".to_string()));
+        }
+        if !job.cancellable {
+            return Err(AppError::BadRequest("Job cannot be cancelled".to_string()));
+        }
+
+        let table = match job.source.as_str() {
+            "ai_generation" => "ai_content_generation_jobs",
+            _ => "background_jobs",
+        };
+
+        sqlx::query(&format!(
+            r#"
+            UPDATE {table}
+            SET cancel_requested = true,
+                status = CASE WHEN status = 'pending' THEN 'cancelled' ELSE status END,
+                completed_at = CASE WHEN status = 'pending' THEN NOW() ELSE completed_at END
+            WHERE id = $1 AND user_id = $2
+            "#
+        ))
+        .bind(id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+        Self::get_user_job(db, id, user_id).await
+    }
+
+    /// Re-run a failed or cancelled job with its original parameters. The new
+    /// job links back to the one it retries. AI generation jobs are queued
+    /// again as streaming jobs, started from `/ai/generate-cards/stream`.
+    pub async fn retry_job(
+        db: &PgPool,
+        media: &Arc<MediaStore>,
+        queue_cache: &Arc<QueueCache>,
+        ai: &AiConfig,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<JobSummary> {
+        let summary = Self::get_user_job(db, id, user_id).await?;
+
+        if !matches!(summary.status.as_str(), "failed" | "cancelled") {
+            return Err(AppError::BadRequest(
+                "Only failed or cancelled jobs can be retried".to_string(),
+            ));
+        }
+        if summary.source == "ai_generation" {
+            return Self::retry_generation(db, ai, id, user_id).await;
+        }
+        if summary.source != "background" || !RETRYABLE_JOB_TYPES.contains(&summary.job_type.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Jobs of type '{}' cannot be retried",
+                summary.job_type
+            )));
+        }
+
+        // Uploads spooled to disk are imported from the file and not kept
+        if summary.job_type == "import" {
+            let has_payload: bool = sqlx::query_scalar(
+                "SELECT payload IS NOT NULL FROM background_jobs WHERE id = $1"
+            )
+            .bind(id)
+            .fetch_one(db)
+            .await?;
+            if !has_payload {
+                return Err(AppError::BadRequest(
+                    "The imported file was not kept; upload it again".to_string(),
+                ));
+            }
+        }
+
+        let retry = sqlx::query_as::<_, BackgroundJob>(
+            r#"
+            INSERT INTO background_jobs (user_id, job_type, parameters, payload, cancellable, retry_of)
+            SELECT user_id, job_type, parameters, payload, true, id
+            FROM background_jobs
+            WHERE id = $1 AND user_id = $2
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Self::spawn(db.clone(), media.clone(), queue_cache.clone(), retry.clone());
+
+        Self::get_user_job(db, retry.id, user_id).await
+    }
+
+    /// Record progress and report whether the job should keep going. Workers
+    /// call this between batches; a pending cancellation is applied here.
+    pub async fn checkpoint(db: &PgPool, id: Uuid, progress: i32) -> Result<bool> {
+        Self::record_checkpoint(db, id, Some(progress)).await
+    }
+
+    /// `checkpoint` for workers that cannot tell how far along they are
+    pub async fn should_continue(db: &PgPool, id: Uuid) -> Result<bool> {
+        Self::record_checkpoint(db, id, None).await
+    }
+
+    async fn record_checkpoint(db: &PgPool, id: Uuid, progress: Option<i32>) -> Result<bool> {
+        let cancel_requested = sqlx::query_scalar::<_, bool>(
+            r#"
+            UPDATE background_jobs
+            SET progress = COALESCE($2, progress), updated_at = NOW()
+            WHERE id = $1
+            RETURNING cancel_requested
+            "#
+        )
+        .bind(id)
+        .bind(progress.map(|p| p.clamp(0, 100)))
+        .fetch_optional(db)
+        .await?
+        .unwrap_or(true);
+
+        if cancel_requested {
+            sqlx::query(
+                r#"
+                UPDATE background_jobs
+                SET status = 'cancelled', completed_at = NOW(), updated_at = NOW()
+                WHERE id = $1
+                "#
+            )
+            .bind(id)
+            .execute(db)
+            .await?;
+        }
+
+        Ok(!cancel_requested)
+    }
+
+    /// Run a job on the Tokio runtime, recording the outcome on the job row
+    fn spawn(db: PgPool, media: Arc<MediaStore>, queue_cache: Arc<QueueCache>, job: BackgroundJob) {
+        tokio::spawn(async move {
+            if let Err(e) = Self::mark_processing(&db, job.id).await {
+                tracing::error!("Failed to start job {}: {}", job.id, e);
+                return;
+            }
+
+            let outcome = match job.job_type.as_str() {
+                "import" => Self::run_import(&db, &media, &queue_cache, &job).await,
+                "consistency_check" => Self::run_consistency_check(&db, &job).await,
+                other => Err(AppError::BadRequest(format!("Unknown job type '{}'", other))),
+            };
+
+            let recorded = match outcome {
+                Ok(Some(result)) => Self::complete_job(&db, job.id, result, None).await,
+                Ok(None) => Ok(()), // Cancelled at a checkpoint
+                Err(e) => Self::fail_job(&db, job.id, &e.to_string()).await,
+            };
+
+            if let Err(e) = recorded {
+                tracing::error!("Failed to record outcome of job {}: {}", job.id, e);
+            }
+        });
+    }
+
+    async fn run_import(
+        db: &PgPool,
+        media: &MediaStore,
+        queue_cache: &QueueCache,
+        job: &BackgroundJob,
+    ) -> Result<Option<JsonValue>> {
+        let params: ImportJobParameters = serde_json::from_value(job.parameters.clone())?;
+        let payload = sqlx::query_scalar::<_, Option<Vec<u8>>>(
+            "SELECT payload FROM background_jobs WHERE id = $1"
+        )
+        .bind(job.id)
+        .fetch_one(db)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Import data is no longer available".to_string()))?;
+
+        if !Self::checkpoint(db, job.id, 10).await? {
+            return Ok(None);
+        }
+
+        let result = ImportExportService::import_decks(
+            db,
+            media,
+            job.user_id,
+            UploadSource::Bytes(&payload),
+            &params,
+            Some(job.id),
+        )
+        .await?;
+        queue_cache.invalidate_user(job.user_id).await;
+
+        Ok(Some(json!(result)))
+    }
+
+    /// Queue a failed or cancelled generation again with the same input
+    async fn retry_generation(
+        db: &PgPool,
+        ai: &AiConfig,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<JobSummary> {
+        AiUsageService::check_quota(db, ai, user_id).await?;
+
+        let retry_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO ai_content_generation_jobs
+                (user_id, deck_id, job_type, status, input_metadata, provider, model_name, retry_of)
+            SELECT user_id, deck_id, job_type, 'pending',
+                   input_metadata || '{"stream": true}'::jsonb, provider, model_name, id
+            FROM ai_content_generation_jobs
+            WHERE id = $1 AND user_id = $2 AND input_metadata ? 'content'
+            RETURNING id
+            "#
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest("The input of this generation is no longer available".to_string())
+        })?;
+
+        Self::get_user_job(db, retry_id, user_id).await
+    }
+
+    async fn run_consistency_check(db: &PgPool, job: &BackgroundJob) -> Result<Option<JsonValue>> {
+        let params: ConsistencyJobParameters = serde_json::from_value(job.parameters.clone())?;
+        let checks = ConsistencyCheck::ALL;
+
+        let mut results = Vec::with_capacity(checks.len());
+        for (i, check) in checks.iter().enumerate() {
+            let progress = (i * 100 / checks.len()) as i32;
+            if !Self::checkpoint(db, job.id, progress).await? {
+                return Ok(None);
+            }
+            results.push(ConsistencyService::run_check(db, *check, params.dry_run).await?);
+        }
+
+        let report = ConsistencyReport {
+            dry_run: params.dry_run,
+            total_found: results.iter().map(|r| r.found).sum(),
+            total_repaired: results.iter().map(|r| r.repaired).sum(),
+            checks: results,
+        };
+
+        Ok(Some(json!(report)))
+    }
+}
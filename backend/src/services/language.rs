@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::utils::{AppError, Result};
+
+/// English names of common languages by primary subtag, for prompts
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("ar", "Arabic"),
+    ("cs", "Czech"),
+    ("da", "Danish"),
+    ("de", "German"),
+    ("el", "Greek"),
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fi", "Finnish"),
+    ("fr", "French"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("hu", "Hungarian"),
+    ("id", "Indonesian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("la", "Latin"),
+    ("nl", "Dutch"),
+    ("no", "Norwegian"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("th", "Thai"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("vi", "Vietnamese"),
+    ("zh", "Chinese"),
+];
+
+/// Region used for a voice when a tag names only the language
+const DEFAULT_VOICE_REGIONS: &[(&str, &str)] = &[
+    ("ar", "SA"),
+    ("da", "DK"),
+    ("de", "DE"),
+    ("el", "GR"),
+    ("en", "US"),
+    ("es", "ES"),
+    ("fr", "FR"),
+    ("hi", "IN"),
+    ("it", "IT"),
+    ("ja", "JP"),
+    ("ko", "KR"),
+    ("nl", "NL"),
+    ("pt", "BR"),
+    ("sv", "SE"),
+    ("zh", "CN"),
+];
+
+/// The languages of a deck's fronts and backs, as stored lowercase BCP 47
+/// tags. Either side may be unknown.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeckLanguages {
+    pub front: Option<String>,
+    pub back: Option<String>,
+}
+
+impl DeckLanguages {
+    pub async fn for_deck(db: &PgPool, deck_id: Uuid) -> Result<Self> {
+        let (front, back) = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+            "SELECT front_language, back_language FROM decks WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(deck_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::DeckNotFound)?;
+
+        Ok(Self { front, back })
+    }
+
+    /// Sentence for AI prompts that write card content, asking for each
+    /// side in its language. Empty when neither language is known.
+    pub fn card_instruction(&self) -> String {
+        let front = self.front.as_deref().map(language_name);
+        let back = self.back.as_deref().map(language_name);
+        match (front, back) {
+            (Some(front), Some(back)) if front == back => format!("Write all card text in {}.", front),
+            (Some(front), Some(back)) => format!(
+                "Card fronts are in {} and card backs are in {}; write questions and fronts in {} and answers and backs in {}.",
+                front, back, front, back
+            ),
+            (Some(front), None) => format!("Card fronts are in {}; write questions and fronts in {}.", front, front),
+            (None, Some(back)) => format!("Card backs are in {}; write answers and backs in {}.", back, back),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Sentence for AI prompts that write prose for the learner. Backs are
+    /// usually in the learner's own language, so they win over fronts.
+    pub fn explanation_instruction(&self) -> String {
+        match self.back.as_deref().or(self.front.as_deref()) {
+            Some(tag) => format!("Write the explanation in {}.", language_name(tag)),
+            None => String::new(),
+        }
+    }
+}
+
+/// English name of the language a tag names, e.g. `Portuguese` for `pt-br`.
+/// Unknown languages are described by their tag.
+pub fn language_name(tag: &str) -> String {
+    let primary = primary_subtag(tag);
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(code, _)| *code == primary)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("the language tagged \"{}\"", tag))
+}
+
+/// Locale of the text-to-speech voice for a language tag, e.g. `pt-BR` for
+/// `pt-br` or `de-DE` for `de`. Tags without a region get the language's
+/// most common one; None when there is neither.
+pub fn tts_voice_locale(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let primary = primary_subtag(tag);
+    if primary.is_empty() {
+        return None;
+    }
+
+    let region = tag
+        .split('-')
+        .skip(1)
+        .find(|subtag| subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(str::to_ascii_uppercase)
+        .or_else(|| {
+            DEFAULT_VOICE_REGIONS
+                .iter()
+                .find(|(code, _)| *code == primary)
+                .map(|(_, region)| region.to_string())
+        })?;
+
+    Some(format!("{}-{}", primary, region))
+}
+
+fn primary_subtag(tag: &str) -> String {
+    tag.trim().split('-').next().unwrap_or_default().to_ascii_lowercase()
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Leaderboard, LeaderboardEntry, LeaderboardKind, LeaderboardQuery},
+    utils::Result,
+};
+
+const DEFAULT_LIMIT: i64 = 10;
+const MAX_LIMIT: i64 = 100;
+
+/// Weekly points and streak rankings, read from the `leaderboard_standings`
+/// materialized view. The view is recomputed by a background refresher, so
+/// standings can lag recent answers by one refresh interval.
+pub struct LeaderboardService;
+
+impl LeaderboardService {
+    pub async fn get(db: &PgPool, user_id: Uuid, query: &LeaderboardQuery) -> Result<Leaderboard> {
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+        // Opting out applies immediately, not only from the next refresh; users
+        // with nothing to rank on are left out
+        let sql = format!(
+            r#"
+            WITH ranked AS (
+                SELECT RANK() OVER (ORDER BY {order}) as rank,
+                       l.user_id, l.display_name, l.weekly_points,
+                       l.current_streak_days, l.longest_streak_days
+                FROM leaderboard_standings l
+                JOIN users u ON u.id = l.user_id
+                WHERE NOT u.leaderboard_opt_out AND u.deleted_at IS NULL AND {score} > 0
+            )
+            SELECT * FROM ranked
+            WHERE rank <= $2 OR user_id = $1
+            ORDER BY rank, user_id
+            "#,
+            order = Self::order(query.board),
+            score = Self::score(query.board),
+        );
+        let rows = sqlx::query_as::<_, LeaderboardEntry>(&sql)
+            .bind(user_id)
+            .bind(limit)
+            .fetch_all(db)
+            .await?;
+
+        let you = rows.iter().find(|entry| entry.user_id == user_id).cloned();
+        let entries = rows
+            .into_iter()
+            .filter(|entry| entry.rank <= limit)
+            .take(limit as usize)
+            .collect();
+
+        let refreshed_at = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            "SELECT MAX(refreshed_at) FROM leaderboard_standings",
+        )
+        .fetch_one(db)
+        .await?;
+
+        Ok(Leaderboard {
+            board: query.board,
+            entries,
+            you,
+            refreshed_at,
+        })
+    }
+
+    pub async fn refresh(db: &PgPool) -> Result<()> {
+        sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY leaderboard_standings")
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Run `refresh` in the background every `every`
+    pub fn spawn_refresher(db: PgPool, every: std::time::Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::refresh(&db).await {
+                    tracing::warn!("Leaderboard refresh failed: {}", e);
+                }
+            }
+        });
+    }
+
+    fn order(board: LeaderboardKind) -> &'static str {
+        match board {
+            LeaderboardKind::WeeklyPoints => "l.weekly_points DESC",
+            LeaderboardKind::Streak => "l.longest_streak_days DESC, l.current_streak_days DESC",
+        }
+    }
+
+    fn score(board: LeaderboardKind) -> &'static str {
+        match board {
+            LeaderboardKind::WeeklyPoints => "l.weekly_points",
+            LeaderboardKind::Streak => "l.longest_streak_days",
+        }
+    }
+}
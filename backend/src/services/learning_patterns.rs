@@ -0,0 +1,449 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::ai::{LearningInsight, LearningPattern},
+    services::{
+        ai_privacy::AiPrivacyService,
+        recommendation::{answers_by_hour, best_study_window, confidence, ANALYSIS_DAYS},
+    },
+    utils::Result,
+};
+
+/// Users are analyzed again once their last analysis is this old
+const REANALYZE_HOURS: i32 = 24;
+
+/// Users analyzed per run of the background job
+const BATCH_SIZE: i64 = 100;
+
+/// A pattern is shown for this long unless a later analysis replaces it
+const PATTERN_VALID_DAYS: i32 = 7;
+
+/// Answers further apart than this belong to different sessions
+const SESSION_GAP_MINUTES: i32 = 30;
+
+/// Shorter sessions say nothing about session length
+const MIN_SESSION_ANSWERS: i64 = 5;
+const MIN_SESSIONS: usize = 6;
+const MIN_BUCKET_SESSIONS: i64 = 2;
+
+/// Session lengths compared, in minutes from the first to the last answer
+const SESSION_BUCKETS: [(i32, Option<i32>); 5] =
+    [(0, Some(10)), (10, Some(20)), (20, Some(30)), (30, Some(45)), (45, None)];
+
+/// Answers needed before difficulty preferences mean anything
+const MIN_DIFFICULTY_ANSWERS: i64 = 50;
+const MIN_BAND_ANSWERS: i64 = 10;
+
+/// How much better a session length, or worse a difficulty band, has to be
+/// to count as a pattern
+const MIN_ACCURACY_GAP: f64 = 0.05;
+
+/// Hard cards answered correctly less often than this need rewriting
+const STRUGGLING_ACCURACY: f64 = 0.6;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeOfDayPattern {
+    pub start_hour: u32,
+    pub end_hour: u32,
+    pub timezone: String,
+    pub answers: i64,
+    pub accuracy: f64,
+    pub overall_accuracy: f64,
+}
+
+/// The session length at which the user answers best
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionLengthPattern {
+    pub min_minutes: i32,
+    /// None for the open-ended longest bucket
+    pub max_minutes: Option<i32>,
+    pub sessions: i64,
+    pub accuracy: f64,
+    pub overall_accuracy: f64,
+}
+
+/// Answers on cards of one difficulty, judged by the card's ease factor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyBand {
+    pub band: String, // 'hard', 'medium', 'easy'
+    pub answers: i64,
+    pub share: f64,
+    pub accuracy: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyProfile {
+    pub bands: Vec<DifficultyBand>,
+    /// The band most answers are on
+    pub preferred: String,
+    /// The band answered worst, when it is clearly worse than the best
+    pub weakest: Option<String>,
+}
+
+/// Study habits mined from study events by a background job, and the
+/// insights shown for them
+pub struct LearningPatternService;
+
+impl LearningPatternService {
+    /// Insights for the user's current patterns, most confident first.
+    /// Empty while personalized learning is off in the privacy settings.
+    pub async fn insights(db: &PgPool, user_id: Uuid) -> Result<Vec<LearningInsight>> {
+        if !AiPrivacyService::allows_personalized_learning(db, user_id).await? {
+            return Ok(Vec::new());
+        }
+
+        let patterns = sqlx::query_as::<_, LearningPattern>(
+            r#"
+            SELECT * FROM learning_patterns
+            WHERE user_id = $1 AND (valid_until IS NULL OR valid_until > NOW())
+            ORDER BY confidence_score DESC NULLS LAST, pattern_type
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(patterns.iter().filter_map(insight).collect())
+    }
+
+    /// Detect the user's patterns and replace the stored ones
+    pub async fn analyze_user(db: &PgPool, user_id: Uuid) -> Result<Vec<LearningPattern>> {
+        let (timezone, hours) = answers_by_hour(db, user_id).await?;
+
+        let sessions = sqlx::query_as::<_, (i32, i64, i64)>(
+            r#"
+            WITH answers AS (
+                SELECT COALESCE(occurred_at, created_at) as at, outcome = 'correct' as correct
+                FROM study_events
+                WHERE user_id = $1 AND outcome IN ('correct', 'incorrect', 'partial')
+                    AND COALESCE(occurred_at, created_at) > NOW() - make_interval(days => $2)
+            ),
+            marked AS (
+                SELECT at, correct,
+                       CASE WHEN at - LAG(at) OVER (ORDER BY at) <= make_interval(mins => $3)
+                           THEN 0 ELSE 1 END as starts_session
+                FROM answers
+            ),
+            numbered AS (
+                SELECT at, correct,
+                       SUM(starts_session) OVER (ORDER BY at ROWS UNBOUNDED PRECEDING) as session
+                FROM marked
+            )
+            SELECT EXTRACT(EPOCH FROM MAX(at) - MIN(at))::INTEGER,
+                   COUNT(*), COUNT(*) FILTER (WHERE correct)
+            FROM numbered
+            GROUP BY session
+            "#,
+        )
+        .bind(user_id)
+        .bind(ANALYSIS_DAYS)
+        .bind(SESSION_GAP_MINUTES)
+        .fetch_all(db)
+        .await?;
+
+        let bands = sqlx::query_as::<_, (String, i64, i64)>(
+            r#"
+            SELECT CASE WHEN ease_factor < 2.0 THEN 'hard'
+                        WHEN ease_factor < 2.6 THEN 'medium'
+                        ELSE 'easy' END as band,
+                   COUNT(*), COUNT(*) FILTER (WHERE outcome = 'correct')
+            FROM study_events
+            WHERE user_id = $1 AND outcome IN ('correct', 'incorrect', 'partial')
+                AND COALESCE(occurred_at, created_at) > NOW() - make_interval(days => $2)
+            GROUP BY band
+            "#,
+        )
+        .bind(user_id)
+        .bind(ANALYSIS_DAYS)
+        .fetch_all(db)
+        .await?;
+
+        let mut detected: Vec<(&str, serde_json::Value, f64)> = Vec::new();
+        if let Some(window) = best_study_window(&hours) {
+            let gap = window.accuracy - window.overall_accuracy;
+            let pattern = TimeOfDayPattern {
+                start_hour: window.start_hour,
+                end_hour: (window.start_hour + 2) % 24,
+                timezone,
+                answers: window.answers,
+                accuracy: window.accuracy,
+                overall_accuracy: window.overall_accuracy,
+            };
+            detected.push(("time_of_day", json!(pattern), confidence(gap, window.answers)));
+        }
+        if let Some(pattern) = session_length_sweet_spot(&sessions) {
+            let gap = pattern.accuracy - pattern.overall_accuracy;
+            // Each session stands for roughly ten answers
+            detected.push(("session_length", json!(pattern), confidence(gap, pattern.sessions * 10)));
+        }
+        if let Some(profile) = difficulty_profile(&bands) {
+            let answers = profile.bands.iter().map(|b| b.answers).sum();
+            let best = profile.bands.iter().map(|b| b.accuracy).fold(0.0, f64::max);
+            let worst = profile.bands.iter().map(|b| b.accuracy).fold(1.0, f64::min);
+            let spread = best - worst;
+            detected.push(("difficulty_preference", json!(profile), confidence(spread, answers)));
+        }
+
+        let types: Vec<String> = detected.iter().map(|(kind, _, _)| kind.to_string()).collect();
+        let mut tx = db.begin().await?;
+
+        // Patterns the data no longer supports
+        sqlx::query("DELETE FROM learning_patterns WHERE user_id = $1 AND pattern_type <> ALL($2)")
+            .bind(user_id)
+            .bind(&types)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut patterns = Vec::with_capacity(detected.len());
+        for (kind, data, confidence) in detected {
+            let pattern = sqlx::query_as::<_, LearningPattern>(
+                r#"
+                INSERT INTO learning_patterns (user_id, pattern_type, pattern_data, confidence_score, valid_until)
+                VALUES ($1, $2, $3, $4, NOW() + make_interval(days => $5))
+                ON CONFLICT (user_id, pattern_type) DO UPDATE
+                SET pattern_data = EXCLUDED.pattern_data,
+                    confidence_score = EXCLUDED.confidence_score,
+                    detected_at = NOW(),
+                    valid_until = EXCLUDED.valid_until
+                RETURNING *
+                "#,
+            )
+            .bind(user_id)
+            .bind(kind)
+            .bind(data)
+            .bind(confidence as f32)
+            .bind(PATTERN_VALID_DAYS)
+            .fetch_one(&mut *tx)
+            .await?;
+            patterns.push(pattern);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO learning_pattern_analyses (user_id) VALUES ($1)
+            ON CONFLICT (user_id) DO UPDATE SET analyzed_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(patterns)
+    }
+
+    /// Analyze users with recent study events who have not been analyzed
+    /// for a day, longest waiting first. Returns how many were analyzed.
+    pub async fn analyze_due(db: &PgPool) -> Result<usize> {
+        let users = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT u.user_id
+            FROM (
+                SELECT DISTINCT user_id FROM study_events
+                WHERE COALESCE(occurred_at, created_at) > NOW() - make_interval(days => $1)
+            ) u
+            LEFT JOIN learning_pattern_analyses a ON a.user_id = u.user_id
+            LEFT JOIN ai_privacy_settings p ON p.user_id = u.user_id
+            WHERE COALESCE(p.personalized_learning, true)
+                AND (a.analyzed_at IS NULL OR a.analyzed_at < NOW() - make_interval(hours => $2))
+            ORDER BY a.analyzed_at NULLS FIRST
+            LIMIT $3
+            "#,
+        )
+        .bind(ANALYSIS_DAYS)
+        .bind(REANALYZE_HOURS)
+        .bind(BATCH_SIZE)
+        .fetch_all(db)
+        .await?;
+
+        for user_id in &users {
+            // One user's failure should not hold up the rest
+            if let Err(e) = Self::analyze_user(db, *user_id).await {
+                tracing::warn!("Learning pattern analysis for user {} failed: {}", user_id, e);
+            }
+        }
+
+        Ok(users.len())
+    }
+
+    /// Run `analyze_due` in the background every `every`
+    pub fn spawn_analyzer(db: PgPool, every: std::time::Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match Self::analyze_due(&db).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::debug!("Analyzed learning patterns of {} users", count),
+                    Err(e) => tracing::warn!("Learning pattern analysis failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// The session length bucket with the best accuracy, given sessions as
+/// (seconds from first to last answer, answers, correct answers). None when
+/// there are too few sessions or no length is clearly better.
+pub fn session_length_sweet_spot(sessions: &[(i32, i64, i64)]) -> Option<SessionLengthPattern> {
+    let sessions: Vec<&(i32, i64, i64)> = sessions.iter().filter(|s| s.1 >= MIN_SESSION_ANSWERS).collect();
+    if sessions.len() < MIN_SESSIONS {
+        return None;
+    }
+    let answers: i64 = sessions.iter().map(|s| s.1).sum();
+    let overall_accuracy = sessions.iter().map(|s| s.2).sum::<i64>() as f64 / answers as f64;
+
+    let mut best: Option<SessionLengthPattern> = None;
+    for (min_minutes, max_minutes) in SESSION_BUCKETS {
+        let members: Vec<_> = sessions
+            .iter()
+            .filter(|s| {
+                let minutes = s.0 / 60;
+                minutes >= min_minutes && max_minutes.map_or(true, |max| minutes < max)
+            })
+            .collect();
+        let count = members.len() as i64;
+        if count < MIN_BUCKET_SESSIONS {
+            continue;
+        }
+        let accuracy = members.iter().map(|s| s.2).sum::<i64>() as f64
+            / members.iter().map(|s| s.1).sum::<i64>() as f64;
+        let better = best.as_ref().map_or(true, |b| {
+            accuracy > b.accuracy || (accuracy == b.accuracy && count > b.sessions)
+        });
+        if better {
+            best = Some(SessionLengthPattern {
+                min_minutes,
+                max_minutes,
+                sessions: count,
+                accuracy,
+                overall_accuracy,
+            });
+        }
+    }
+
+    best.filter(|b| b.accuracy - b.overall_accuracy >= MIN_ACCURACY_GAP)
+}
+
+/// How the user's answers spread over hard, medium and easy cards, given as
+/// (band, answers, correct answers). None when there are too few answers.
+pub fn difficulty_profile(bands: &[(String, i64, i64)]) -> Option<DifficultyProfile> {
+    let total: i64 = bands.iter().map(|b| b.1).sum();
+    if total < MIN_DIFFICULTY_ANSWERS {
+        return None;
+    }
+
+    let profile: Vec<DifficultyBand> = ["hard", "medium", "easy"]
+        .iter()
+        .filter_map(|name| bands.iter().find(|b| b.0 == *name))
+        .filter(|b| b.1 > 0)
+        .map(|(band, answers, correct)| DifficultyBand {
+            band: band.clone(),
+            answers: *answers,
+            share: *answers as f64 / total as f64,
+            accuracy: *correct as f64 / *answers as f64,
+        })
+        .collect();
+
+    let preferred = profile.iter().max_by_key(|b| b.answers)?.band.clone();
+
+    let judged: Vec<&DifficultyBand> = profile.iter().filter(|b| b.answers >= MIN_BAND_ANSWERS).collect();
+    let worst = judged.iter().min_by(|a, b| a.accuracy.total_cmp(&b.accuracy));
+    let best = judged.iter().max_by(|a, b| a.accuracy.total_cmp(&b.accuracy));
+    let weakest = match (worst, best) {
+        (Some(worst), Some(best)) if best.accuracy - worst.accuracy >= MIN_ACCURACY_GAP * 2.0 => {
+            Some(worst.band.clone())
+        }
+        _ => None,
+    };
+
+    Some(DifficultyProfile { bands: profile, preferred, weakest })
+}
+
+fn insight(pattern: &LearningPattern) -> Option<LearningInsight> {
+    let data = pattern.pattern_data.clone();
+    let confidence = pattern.confidence_score.unwrap_or(0.5);
+    let percent = |value: f64| (value * 100.0).round();
+
+    let (title, description, suggestions) = match pattern.pattern_type.as_str() {
+        "time_of_day" => {
+            let p: TimeOfDayPattern = serde_json::from_value(data.clone()).ok()?;
+            (
+                "Your best study hours".to_string(),
+                format!(
+                    "You answer {}% of cards correctly between {:02}:00 and {:02}:00 ({}), compared with {}% overall.",
+                    percent(p.accuracy),
+                    p.start_hour,
+                    p.end_hour,
+                    p.timezone,
+                    percent(p.overall_accuracy)
+                ),
+                vec![
+                    format!("Schedule your reviews between {:02}:00 and {:02}:00", p.start_hour, p.end_hour),
+                    format!("Set your review reminder for {:02}:00", p.start_hour),
+                ],
+            )
+        }
+        "session_length" => {
+            let p: SessionLengthPattern = serde_json::from_value(data.clone()).ok()?;
+            let length = match p.max_minutes {
+                Some(max) => format!("{}–{} minutes", p.min_minutes, max),
+                None => format!("over {} minutes", p.min_minutes),
+            };
+            let mut suggestions = vec![format!("Aim for study sessions of {}", length)];
+            if let Some(max) = p.max_minutes {
+                suggestions.push(format!("Take a break after about {} minutes", max));
+            }
+            (
+                "Your ideal session length".to_string(),
+                format!(
+                    "In sessions of {} you answer {}% of cards correctly, compared with {}% overall.",
+                    length,
+                    percent(p.accuracy),
+                    percent(p.overall_accuracy)
+                ),
+                suggestions,
+            )
+        }
+        "difficulty_preference" => {
+            let p: DifficultyProfile = serde_json::from_value(data.clone()).ok()?;
+            let preferred = p.bands.iter().find(|b| b.band == p.preferred)?;
+            let mut suggestions = Vec::new();
+            if let Some(weak) = p.bands.iter().find(|b| Some(&b.band) == p.weakest.as_ref()) {
+                if weak.band == "hard" && weak.accuracy < STRUGGLING_ACCURACY {
+                    suggestions.push("Rewrite or split your hardest cards; deck linting can suggest fixes".to_string());
+                } else {
+                    suggestions.push(format!("Give {} cards some extra practice", weak.band));
+                }
+            }
+            if p.preferred == "easy" && preferred.share > 0.6 {
+                suggestions.push("Most of your reviews are easy cards; add new cards to keep progressing".to_string());
+            }
+            (
+                "How you handle card difficulty".to_string(),
+                format!(
+                    "{}% of your answers are on {} cards, which you answer correctly {}% of the time.",
+                    percent(preferred.share),
+                    preferred.band,
+                    percent(preferred.accuracy)
+                ),
+                suggestions,
+            )
+        }
+        _ => return None,
+    };
+
+    Some(LearningInsight {
+        insight_type: pattern.pattern_type.clone(),
+        title,
+        description,
+        data,
+        confidence,
+        actionable: !suggestions.is_empty(),
+        suggestions,
+    })
+}
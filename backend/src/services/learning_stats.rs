@@ -0,0 +1,51 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{models::ai::UserLearningStats, utils::Result};
+
+/// Lifetime study totals, read from the `user_learning_stats` materialized
+/// view. Like the leaderboard it is recomputed by a background refresher,
+/// so totals can lag recent answers by one refresh interval.
+pub struct LearningStatsService;
+
+impl LearningStatsService {
+    /// The user's row, or empty totals for users the view has no events for
+    pub async fn get(db: &PgPool, user_id: Uuid) -> Result<UserLearningStats> {
+        let stats = sqlx::query_as::<_, UserLearningStats>(
+            r#"
+            SELECT $1::UUID as user_id, s.unique_cards_studied, s.total_study_events,
+                   s.avg_response_time_ms, s.total_correct, s.total_incorrect, s.accuracy_rate,
+                   s.last_study_time, s.study_days,
+                   COALESCE(s.refreshed_at, (SELECT MAX(refreshed_at) FROM user_learning_stats))
+                       as refreshed_at
+            FROM (SELECT 1) one
+            LEFT JOIN user_learning_stats s ON s.user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(stats)
+    }
+
+    pub async fn refresh(db: &PgPool) -> Result<()> {
+        sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY user_learning_stats")
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Run `refresh` in the background every `every`
+    pub fn spawn_refresher(db: PgPool, every: std::time::Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::refresh(&db).await {
+                    tracing::warn!("Learning stats refresh failed: {}", e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,466 @@
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::AiConfig,
+    models::{
+        ai::{self, LeechRemediation, LeechRemediationsQuery, SuggestedCard, WsMessage},
+        DeckRole, LeechAction, LeechCard, LeechSuggestion,
+    },
+    services::{
+        ai_provider::provider_for, ai_usage::AiUsageService, circuit_breaker::CircuitBreaker,
+        encryption::EncryptionService, sharing::SharingService, ws::WsHub,
+    },
+    utils::{AppError, Result},
+};
+
+/// Answers longer than this are suggested for splitting into several cards
+pub(crate) const LONG_ANSWER_CHARS: usize = 200;
+/// Ways a remediation may rewrite a card; one suggestion is kept for each
+pub const REMEDIATION_STRATEGIES: &[&str] = &["simplify", "split", "mnemonic"];
+/// Most cards a suggestion may turn the leech into
+pub const MAX_SUGGESTED_CARDS: usize = 3;
+
+#[derive(sqlx::FromRow)]
+struct LeechRow {
+    card_id: Uuid,
+    deck_id: Uuid,
+    deck_name: String,
+    front: String,
+    back: String,
+    lapses: i32,
+    times_seen: i32,
+    times_correct: i32,
+    suspended: bool,
+    has_notes: bool,
+    flagged_at: Option<DateTime<Utc>>,
+    pending_remediations: i64,
+}
+
+const REMEDIATION_COLUMNS: &str = r#"
+    r.id, r.user_id, r.card_id, r.strategy, r.suggested_cards, r.mnemonic, r.rationale,
+    r.status, r.accuracy_before,
+    CASE WHEN r.status = 'accepted' AND s.times_seen > r.times_seen_at_accept
+        THEN (s.times_correct - r.times_correct_at_accept)::REAL
+            / (s.times_seen - r.times_seen_at_accept)
+    END as accuracy_after,
+    CASE WHEN r.status = 'accepted'
+        THEN GREATEST(s.times_seen - r.times_seen_at_accept, 0)
+    END as reviews_since_accept,
+    r.created_at, r.decided_at
+"#;
+
+pub struct LeechService;
+
+impl LeechService {
+    /// Generate remediation suggestions in the background if the card has been
+    /// flagged as a leech and has no suggestions since it was flagged
+    pub fn remediate_if_flagged(
+        db: PgPool,
+        ai: AiConfig,
+        breaker: Arc<CircuitBreaker>,
+        ws: Arc<WsHub>,
+        user_id: Uuid,
+        card_id: Uuid,
+    ) {
+        if !ai.enabled {
+            return;
+        }
+
+        tokio::spawn(async move {
+            match Self::generate_suggestions(&db, &ai, &breaker, user_id, card_id).await {
+                Ok(remediations) if !remediations.is_empty() => {
+                    let message = WsMessage::new(
+                        "leech_remediation",
+                        json!({ "card_id": card_id, "suggestions": remediations }),
+                    );
+                    ws.publish(user_id, "recommendations", message).await;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("Leech remediation for card {} failed: {}", card_id, e);
+                }
+            }
+        });
+    }
+
+    pub async fn generate_suggestions(
+        db: &PgPool,
+        ai: &AiConfig,
+        breaker: &Arc<CircuitBreaker>,
+        user_id: Uuid,
+        card_id: Uuid,
+    ) -> Result<Vec<LeechRemediation>> {
+        let card = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT c.front, c.back
+            FROM user_card_stats s
+            JOIN cards c ON c.id = s.card_id
+            JOIN decks d ON d.id = c.deck_id
+            WHERE s.user_id = $1 AND s.card_id = $2
+                AND s.leech_flagged_at IS NOT NULL
+                AND NOT d.is_encrypted
+                AND c.deleted_at IS NULL AND d.deleted_at IS NULL
+                AND NOT EXISTS(
+                    SELECT 1 FROM leech_remediations r
+                    WHERE r.user_id = $1 AND r.card_id = $2 AND r.created_at >= s.leech_flagged_at
+                )
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .fetch_optional(db)
+        .await?;
+
+        let Some((front, back)) = card else {
+            return Ok(Vec::new());
+        };
+
+        let mut client = provider_for(ai, breaker, None)?;
+        AiUsageService::check_quota(db, ai, user_id).await?;
+        let batch = client
+            .suggest_leech_remediations(&front, &back)
+            .await
+            .map_err(|e| {
+                tracing::error!("AI provider error: {}", e);
+                AppError::InternalServerError
+            })?;
+        AiUsageService::record(db, ai, user_id, client.kind(), batch.tokens_used).await?;
+
+        let suggestions = usable_suggestions(batch.suggestions);
+        let mut tx = db.begin().await?;
+        let mut ids = Vec::with_capacity(suggestions.len());
+        for suggestion in suggestions {
+            let id = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO leech_remediations (user_id, card_id, strategy, suggested_cards, mnemonic, rationale)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id
+                "#,
+            )
+            .bind(user_id)
+            .bind(card_id)
+            .bind(&suggestion.strategy)
+            .bind(json!(suggestion.cards))
+            .bind(&suggestion.mnemonic)
+            .bind(&suggestion.rationale)
+            .fetch_one(&mut *tx)
+            .await?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+
+        let mut remediations = Vec::with_capacity(ids.len());
+        for id in ids {
+            remediations.push(Self::get_remediation(db, id, user_id).await?);
+        }
+
+        Ok(remediations)
+    }
+
+    pub async fn list_remediations(
+        db: &PgPool,
+        user_id: Uuid,
+        query: &LeechRemediationsQuery,
+    ) -> Result<Vec<LeechRemediation>> {
+        let sql = format!(
+            r#"
+            SELECT {}
+            FROM leech_remediations r
+            LEFT JOIN user_card_stats s ON s.user_id = r.user_id AND s.card_id = r.card_id
+            WHERE r.user_id = $1
+                AND ($2::TEXT IS NULL OR r.status = $2)
+                AND ($3::UUID IS NULL OR r.card_id = $3)
+            ORDER BY r.created_at DESC
+            "#,
+            REMEDIATION_COLUMNS
+        );
+
+        let remediations = sqlx::query_as::<_, LeechRemediation>(&sql)
+            .bind(user_id)
+            .bind(&query.status)
+            .bind(query.card_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(remediations)
+    }
+
+    pub async fn get_remediation(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<LeechRemediation> {
+        let sql = format!(
+            r#"
+            SELECT {}
+            FROM leech_remediations r
+            LEFT JOIN user_card_stats s ON s.user_id = r.user_id AND s.card_id = r.card_id
+            WHERE r.id = $1 AND r.user_id = $2
+            "#,
+            REMEDIATION_COLUMNS
+        );
+
+        sqlx::query_as::<_, LeechRemediation>(&sql)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or(AppError::NotFound("Resource not found".to_string()))
+    }
+
+    /// Apply a suggestion to the card. The first suggested card replaces the
+    /// original; any further cards from a split are added after it.
+    pub async fn accept(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<LeechRemediation> {
+        let remediation = Self::get_remediation(db, id, user_id).await?;
+        if remediation.status != "pending" {
+            return Err(AppError::BadRequest("Suggestion has already been decided".to_string()));
+        }
+
+        let (deck_id, position) = sqlx::query_as::<_, (Uuid, i32)>(
+            "SELECT deck_id, position FROM cards WHERE id = $1",
+        )
+        .bind(remediation.card_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+
+        let cards: Vec<SuggestedCard> = serde_json::from_value(remediation.suggested_cards.clone())
+            .map_err(|e| AppError::BadRequest(format!("Invalid suggestion: {}", e)))?;
+        let (first, rest) = cards
+            .split_first()
+            .ok_or(AppError::BadRequest("Suggestion contains no cards".to_string()))?;
+
+        let back = match &remediation.mnemonic {
+            Some(mnemonic) => format!("{}\n\nMnemonic: {}", first.back, mnemonic),
+            None => first.back.clone(),
+        };
+
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE cards SET front = $2, back = $3, updated_at = NOW() WHERE id = $1")
+            .bind(remediation.card_id)
+            .bind(&first.front)
+            .bind(&back)
+            .execute(&mut *tx)
+            .await?;
+
+        if !rest.is_empty() {
+            // Make room directly after the original card
+            sqlx::query("UPDATE cards SET position = position + $3 WHERE deck_id = $1 AND position > $2")
+                .bind(deck_id)
+                .bind(position)
+                .bind(rest.len() as i32)
+                .execute(&mut *tx)
+                .await?;
+
+            for (offset, card) in rest.iter().enumerate() {
+                sqlx::query("INSERT INTO cards (deck_id, front, back, position) VALUES ($1, $2, $3, $4)")
+                    .bind(deck_id)
+                    .bind(&card.front)
+                    .bind(&card.back)
+                    .bind(position + 1 + offset as i32)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        // Snapshot current accuracy so later reviews can be compared against it
+        sqlx::query(
+            r#"
+            UPDATE leech_remediations r
+            SET status = 'accepted',
+                decided_at = NOW(),
+                accuracy_before = CASE WHEN s.times_seen > 0
+                    THEN s.times_correct::REAL / s.times_seen END,
+                times_seen_at_accept = COALESCE(s.times_seen, 0),
+                times_correct_at_accept = COALESCE(s.times_correct, 0)
+            FROM user_card_stats s
+            WHERE r.id = $1 AND s.user_id = r.user_id AND s.card_id = r.card_id
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE leech_remediations SET status = 'superseded', decided_at = NOW()
+            WHERE user_id = $1 AND card_id = $2 AND status = 'pending' AND id <> $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(remediation.card_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE user_card_stats SET lapses = 0, leech_flagged_at = NULL WHERE user_id = $1 AND card_id = $2",
+        )
+        .bind(user_id)
+        .bind(remediation.card_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Self::get_remediation(db, id, user_id).await
+    }
+
+    pub async fn reject(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<LeechRemediation> {
+        let result = sqlx::query(
+            r#"
+            UPDATE leech_remediations SET status = 'rejected', decided_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND status = 'pending'
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            // Distinguish a missing suggestion from one that was already decided
+            Self::get_remediation(db, id, user_id).await?;
+            return Err(AppError::BadRequest("Suggestion has already been decided".to_string()));
+        }
+
+        Self::get_remediation(db, id, user_id).await
+    }
+
+    /// Mark a card that just crossed its deck's leech threshold, suspending
+    /// it if the deck says so
+    pub async fn mark_leech(
+        db: &PgPool,
+        user_id: Uuid,
+        card_id: Uuid,
+        action: LeechAction,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_card_flags (user_id, card_id, leech, suspended)
+            VALUES ($1, $2, true, $3)
+            ON CONFLICT (user_id, card_id) DO UPDATE SET
+                leech = true,
+                suspended = user_card_flags.suspended OR EXCLUDED.suspended,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .bind(action == LeechAction::Suspend)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The user's leeches, most forgotten first, with what to do about each
+    pub async fn list_leeches(
+        db: &PgPool,
+        user_id: Uuid,
+        deck_id: Option<Uuid>,
+    ) -> Result<Vec<LeechCard>> {
+        let rows = sqlx::query_as::<_, LeechRow>(
+            r#"
+            SELECT c.id as card_id, c.deck_id, d.title as deck_name, c.front, c.back,
+                   COALESCE(s.lapses, 0) as lapses,
+                   COALESCE(s.times_seen, 0) as times_seen,
+                   COALESCE(s.times_correct, 0) as times_correct,
+                   f.suspended, f.notes IS NOT NULL as has_notes,
+                   s.leech_flagged_at as flagged_at,
+                   (
+                       SELECT COUNT(*) FROM leech_remediations r
+                       WHERE r.user_id = f.user_id AND r.card_id = c.id AND r.status = 'pending'
+                   ) as pending_remediations
+            FROM user_card_flags f
+            JOIN cards c ON c.id = f.card_id
+            JOIN decks d ON d.id = c.deck_id
+            LEFT JOIN user_card_stats s ON s.user_id = f.user_id AND s.card_id = f.card_id
+            WHERE f.user_id = $1 AND f.leech
+                AND ($2::UUID IS NULL OR c.deck_id = $2)
+                AND c.deleted_at IS NULL AND d.deleted_at IS NULL
+            ORDER BY lapses DESC, s.leech_flagged_at DESC NULLS LAST, c.id
+            "#,
+        )
+        .bind(user_id)
+        .bind(deck_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| LeechCard {
+                suggested_actions: suggested_actions(
+                    &row.back,
+                    row.has_notes,
+                    row.suspended,
+                    row.pending_remediations,
+                ),
+                accuracy: if row.times_seen > 0 {
+                    row.times_correct as f64 / row.times_seen as f64
+                } else {
+                    0.0
+                },
+                card_id: row.card_id,
+                deck_id: row.deck_id,
+                deck_name: row.deck_name,
+                front: row.front,
+                back: row.back,
+                lapses: row.lapses,
+                times_seen: row.times_seen,
+                suspended: row.suspended,
+                flagged_at: row.flagged_at,
+                pending_remediations: row.pending_remediations,
+            })
+            .collect())
+    }
+}
+
+/// What the model suggested, limited to what can be stored and applied: the
+/// first suggestion for each known strategy, with at most
+/// `MAX_SUGGESTED_CARDS` complete cards
+pub fn usable_suggestions(suggestions: Vec<ai::LeechSuggestion>) -> Vec<ai::LeechSuggestion> {
+    let mut kept: Vec<ai::LeechSuggestion> = Vec::new();
+    for mut suggestion in suggestions {
+        if !REMEDIATION_STRATEGIES.contains(&suggestion.strategy.as_str())
+            || kept.iter().any(|k| k.strategy == suggestion.strategy)
+        {
+            continue;
+        }
+        suggestion
+            .cards
+            .retain(|card| !card.front.trim().is_empty() && !card.back.trim().is_empty());
+        suggestion.cards.truncate(MAX_SUGGESTED_CARDS);
+        if !suggestion.cards.is_empty() {
+            kept.push(suggestion);
+        }
+    }
+    kept
+}
+
+/// What to do about a leech, most useful first
+pub fn suggested_actions(
+    back: &str,
+    has_notes: bool,
+    suspended: bool,
+    pending_remediations: i64,
+) -> Vec<LeechSuggestion> {
+    let mut actions = Vec::new();
+    if pending_remediations > 0 {
+        actions.push(LeechSuggestion::ReviewRemediations);
+    }
+    if back.chars().count() > LONG_ANSWER_CHARS {
+        actions.push(LeechSuggestion::Split);
+    }
+    if !has_notes {
+        actions.push(LeechSuggestion::AddMnemonic);
+    }
+    if !suspended {
+        actions.push(LeechSuggestion::Suspend);
+    }
+    actions
+}
@@ -0,0 +1,94 @@
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use std::sync::Arc;
+
+use crate::{
+    config::EmailConfig,
+    utils::{AppError, Result},
+};
+
+/// Sends plain-text email to users
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<()>;
+}
+
+/// SMTP delivery, with STARTTLS on the configured port
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(host: &str, config: &EmailConfig) -> std::result::Result<Self, String> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| format!("Invalid SMTP_HOST: {}", e))?
+            .port(config.smtp_port);
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config
+                .from_address
+                .parse()
+                .map_err(|e| format!("Invalid EMAIL_FROM: {}", e))?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<()> {
+        let to: Mailbox = to
+            .parse()
+            .map_err(|_| AppError::BadRequest(format!("Invalid recipient address: {}", to)))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .body(body.to_string())
+            .map_err(|e| {
+                tracing::error!("Failed to build email: {}", e);
+                AppError::InternalServerError
+            })?;
+
+        self.transport.send(message).await.map_err(|e| {
+            tracing::error!("SMTP delivery failed: {}", e);
+            AppError::InternalServerError
+        })?;
+        Ok(())
+    }
+}
+
+/// Logs messages instead of sending them, for development without SMTP
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<()> {
+        tracing::info!("Email to {} ({}):\n{}", to, subject, body);
+        Ok(())
+    }
+}
+
+/// SMTP when a host is configured, otherwise the log mailer
+pub fn from_config(config: &EmailConfig) -> std::result::Result<Arc<dyn Mailer>, String> {
+    Ok(match &config.smtp_host {
+        Some(host) => Arc::new(SmtpMailer::new(host, config)?),
+        None => Arc::new(LogMailer),
+    })
+}
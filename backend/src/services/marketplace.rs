@@ -0,0 +1,226 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Deck, DeckRatingSummary, DeckRole, PublicDeck, PublicDeckQuery, PublicDeckSort},
+    services::{encryption::EncryptionService, sharing::SharingService},
+    utils::{AppError, PaginatedResponse, PaginationParams, Result},
+};
+
+/// Most tags a deck can carry
+pub const MAX_TAGS: usize = 10;
+/// Longest tag, in characters
+pub const MAX_TAG_CHARS: usize = 32;
+
+/// Discovery of public decks: browsing with filters, star ratings, download
+/// counts and featured decks picked by admins
+pub struct MarketplaceService;
+
+impl MarketplaceService {
+    pub async fn list_public(
+        db: &PgPool,
+        user_id: Uuid,
+        query: &PublicDeckQuery,
+        params: &PaginationParams,
+    ) -> Result<PaginatedResponse<PublicDeck>> {
+        let sql = format!(
+            r#"
+            SELECT d.id, d.title as name, d.description, d.owner_id, u.display_name as owner_name,
+                   d.tags, d.language, d.front_language, d.back_language, d.download_count, d.is_featured, d.created_at, d.updated_at,
+                   d.card_count::BIGINT as card_count,
+                   r.average_rating, COALESCE(r.rating_count, 0) as rating_count,
+                   (SELECT rating FROM deck_ratings WHERE deck_id = d.id AND user_id = $1) as your_rating
+            FROM decks d
+            JOIN users u ON u.id = d.owner_id
+            LEFT JOIN (
+                SELECT deck_id, AVG(rating)::FLOAT8 as average_rating, COUNT(*) as rating_count
+                FROM deck_ratings
+                GROUP BY deck_id
+            ) r ON r.deck_id = d.id
+            WHERE d.is_public AND d.deleted_at IS NULL AND u.deleted_at IS NULL
+                AND ($2::TEXT IS NULL OR $2 = ANY(d.tags))
+                AND ($3::TEXT IS NULL OR EXISTS(
+                    SELECT 1 FROM unnest(ARRAY[d.language, d.front_language, d.back_language]) l
+                    WHERE l = $3 OR l LIKE $3 || '-%'
+                ))
+                AND ($4::BOOLEAN IS NULL OR d.is_featured = $4)
+            ORDER BY {order}, d.id
+            LIMIT $5 OFFSET $6
+            "#,
+            order = Self::order(query.sort),
+        );
+        let decks = sqlx::query_as::<_, PublicDeck>(&sql)
+            .bind(user_id)
+            .bind(query.tag.as_deref().map(normalize_tag))
+            .bind(query.language.as_deref().map(normalize_language))
+            .bind(query.featured)
+            .bind(params.limit_plus_one() as i64)
+            .bind(params.offset() as i64)
+            .fetch_all(db)
+            .await?;
+
+        Ok(PaginatedResponse::new(decks, params, None))
+    }
+
+    /// Rate a public deck from 1 to 5 stars, replacing any earlier rating by
+    /// the same user. Owners cannot rate their own decks.
+    pub async fn rate(db: &PgPool, deck_id: Uuid, user_id: Uuid, rating: i16) -> Result<DeckRatingSummary> {
+        let owner_id = sqlx::query_scalar::<_, Uuid>(
+            "SELECT owner_id FROM decks WHERE id = $1 AND is_public AND deleted_at IS NULL",
+        )
+        .bind(deck_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::DeckNotFound)?;
+        if owner_id == user_id {
+            return Err(AppError::BadRequest("You cannot rate your own deck".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO deck_ratings (deck_id, user_id, rating) VALUES ($1, $2, $3)
+            ON CONFLICT (deck_id, user_id) DO UPDATE SET rating = EXCLUDED.rating, updated_at = NOW()
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .bind(rating)
+        .execute(db)
+        .await?;
+
+        let summary = sqlx::query_as::<_, DeckRatingSummary>(
+            r#"
+            SELECT $1::UUID as deck_id, AVG(rating)::FLOAT8 as average_rating, COUNT(*) as rating_count,
+                   MAX(rating) FILTER (WHERE user_id = $2) as your_rating
+            FROM deck_ratings
+            WHERE deck_id = $1
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(summary)
+    }
+
+    /// Count a copy or export of a public deck by someone other than its owner
+    pub async fn record_download(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE decks SET download_count = download_count + 1
+            WHERE id = $1 AND is_public AND owner_id <> $2
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Copy a deck the user can read into their own library as a new private
+    /// deck with the same cards. Attachments and study progress stay behind.
+    pub async fn duplicate(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<Deck> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+        if EncryptionService::is_encrypted(db, deck_id).await? {
+            return Err(AppError::BadRequest("Encrypted decks cannot be duplicated".to_string()));
+        }
+
+        let mut tx = db.begin().await?;
+        let deck = sqlx::query_as::<_, Deck>(
+            r#"
+            INSERT INTO decks (id, owner_id, title, description, is_public, tags, language,
+                               front_language, back_language, created_at, updated_at)
+            SELECT gen_random_uuid(), $2, title, description, false, tags, language,
+                   front_language, back_language, NOW(), NOW()
+            FROM decks
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, folder_id, owner_id, title, description, is_public, front_language, back_language, created_at, updated_at
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::DeckNotFound)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO cards (id, deck_id, front, back, position, created_at, updated_at)
+            SELECT gen_random_uuid(), $2, front, back, position, NOW(), NOW()
+            FROM cards
+            WHERE deck_id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(deck_id)
+        .bind(deck.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Self::record_download(db, deck_id, user_id).await?;
+        Ok(deck)
+    }
+
+    /// Feature or unfeature a public deck (admin only)
+    pub async fn set_featured(db: &PgPool, deck_id: Uuid, featured: bool) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE decks
+            SET is_featured = $2, featured_at = CASE WHEN $2 THEN NOW() END
+            WHERE id = $1 AND deleted_at IS NULL AND (is_public OR NOT $2)
+            "#,
+        )
+        .bind(deck_id)
+        .bind(featured)
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::DeckNotFound);
+        }
+        Ok(())
+    }
+
+    fn order(sort: PublicDeckSort) -> &'static str {
+        match sort {
+            PublicDeckSort::Popular => "d.download_count DESC, r.rating_count DESC NULLS LAST",
+            PublicDeckSort::Rating => "r.average_rating DESC NULLS LAST, r.rating_count DESC NULLS LAST",
+            PublicDeckSort::Newest => "d.created_at DESC",
+        }
+    }
+}
+
+/// Tags are compared case-insensitively, so they are stored trimmed and
+/// lowercased
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Language tags are compared case-insensitively, e.g. `pt-BR` and `pt-br`
+pub fn normalize_language(language: &str) -> String {
+    language.trim().to_lowercase()
+}
+
+/// Normalize a deck's or card's tags, dropping blanks and repeats. Fails when there
+/// are more than `MAX_TAGS` or one is longer than `MAX_TAG_CHARS`.
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter().map(|tag| normalize_tag(tag)) {
+        if tag.is_empty() || normalized.contains(&tag) {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_CHARS {
+            return Err(AppError::ValidationError(format!(
+                "Tags can be at most {} characters",
+                MAX_TAG_CHARS
+            )));
+        }
+        normalized.push(tag);
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(AppError::ValidationError(format!("At most {} tags are allowed", MAX_TAGS)));
+    }
+    Ok(normalized)
+}
@@ -0,0 +1,272 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{import_export::MediaAttachment, CardMedia, CardMediaResponse, DeckRole, DeckWithStats},
+    services::{
+        card::CardService, deck::DeckService, encryption::EncryptionService,
+        sharing::SharingService, storage::MediaStore,
+    },
+    utils::{AppError, Result},
+};
+
+/// Images and audio attached to cards. Rows in `card_media` describe each
+/// attachment; the bytes live in the configured storage backend under
+/// `cards/{card_id}/{media_id}.{ext}`. Deck cover images are kept under
+/// `decks/{deck_id}/` and referenced from the deck itself.
+pub struct MediaService;
+
+impl MediaService {
+    pub async fn list(
+        db: &PgPool,
+        store: &MediaStore,
+        card_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<CardMediaResponse>> {
+        // Read access to the card
+        CardService::get_card(db, card_id, user_id).await?;
+
+        let media = Self::card_media(db, &[card_id]).await?;
+        Ok(media.into_iter().map(|m| Self::with_url(store, m)).collect())
+    }
+
+    pub async fn upload(
+        db: &PgPool,
+        store: &MediaStore,
+        card_id: Uuid,
+        user_id: Uuid,
+        filename: &str,
+        data: Vec<u8>,
+    ) -> Result<CardMediaResponse> {
+        let card = CardService::get_card(db, card_id, user_id).await?;
+        SharingService::require_deck_role(db, card.deck_id, user_id, DeckRole::Editor).await?;
+        // Attachments are stored as uploaded, which would leak vault content
+        EncryptionService::ensure_plaintext(db, card.deck_id).await?;
+
+        let content_type = store.check_upload(filename, data.len())?;
+        let mut tx = db.begin().await?;
+        let media = Self::insert(&mut tx, store, card_id, Some(user_id), filename, content_type, data).await?;
+        tx.commit().await?;
+
+        Ok(Self::with_url(store, media))
+    }
+
+    pub async fn delete(
+        db: &PgPool,
+        store: &MediaStore,
+        card_id: Uuid,
+        media_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<()> {
+        let card = CardService::get_card(db, card_id, user_id).await?;
+        SharingService::require_deck_role(db, card.deck_id, user_id, DeckRole::Editor).await?;
+
+        let storage_key = sqlx::query_scalar::<_, String>(
+            "DELETE FROM card_media WHERE id = $1 AND card_id = $2 RETURNING storage_key",
+        )
+        .bind(media_id)
+        .bind(card_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+
+        store.delete(&storage_key).await
+    }
+
+    /// Set a deck's cover image, replacing the previous one
+    pub async fn upload_deck_cover(
+        db: &PgPool,
+        store: &MediaStore,
+        deck_id: Uuid,
+        user_id: Uuid,
+        filename: &str,
+        data: Vec<u8>,
+    ) -> Result<DeckWithStats> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+
+        let content_type = store.check_upload(filename, data.len())?;
+        if !content_type.starts_with("image/") {
+            return Err(AppError::FileUploadError("Deck covers must be images".to_string()));
+        }
+        let extension = filename.rsplit_once('.').map_or("bin", |(_, ext)| ext).to_ascii_lowercase();
+        // A fresh key per upload, so links to the old cover stop working
+        let storage_key = format!("decks/{}/cover-{}.{}", deck_id, Uuid::new_v4(), extension);
+        store.put(&storage_key, data, content_type).await?;
+
+        let previous = Self::replace_deck_cover(db, deck_id, Some(&storage_key)).await?;
+        if let Some(previous) = previous {
+            Self::delete_object(store, &previous).await;
+        }
+
+        let mut deck = DeckService::get_deck_with_stats(db, deck_id, user_id).await?;
+        Self::sign_deck_cover(store, &mut deck);
+        Ok(deck)
+    }
+
+    pub async fn delete_deck_cover(
+        db: &PgPool,
+        store: &MediaStore,
+        deck_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<()> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+
+        let previous = Self::replace_deck_cover(db, deck_id, None)
+            .await?
+            .ok_or(AppError::NotFound("Deck has no cover".to_string()))?;
+        store.delete(&previous).await
+    }
+
+    /// Fill in the signed link to a deck's cover. Links last until the end
+    /// of the next signing window so ETag-cached listings stay usable.
+    pub fn sign_deck_cover(store: &MediaStore, deck: &mut DeckWithStats) {
+        deck.cover_url = deck
+            .cover_storage_key
+            .as_deref()
+            .map(|key| store.window_signed_url(key).0);
+    }
+
+    /// Attachments of `card_ids` with their contents inlined as base64, in
+    /// the same order as the ids
+    pub async fn export_attachments(
+        db: &PgPool,
+        store: &MediaStore,
+        card_ids: &[Uuid],
+    ) -> Result<Vec<Vec<MediaAttachment>>> {
+        let mut by_card = vec![Vec::new(); card_ids.len()];
+        for media in Self::card_media(db, card_ids).await? {
+            let data = store.get(&media.storage_key).await?;
+            if let Some(i) = card_ids.iter().position(|id| *id == media.card_id) {
+                by_card[i].push(MediaAttachment {
+                    id: media.id,
+                    filename: media.filename,
+                    content_type: media.content_type,
+                    data: Some(BASE64.encode(data)),
+                    url: None,
+                });
+            }
+        }
+        Ok(by_card)
+    }
+
+    /// Store an exported attachment on a newly imported card. Attachments
+    /// without inline data are skipped and reported as a warning.
+    pub async fn import_attachment(
+        tx: &mut Transaction<'_, Postgres>,
+        store: &MediaStore,
+        card_id: Uuid,
+        user_id: Uuid,
+        attachment: &MediaAttachment,
+    ) -> Result<Option<String>> {
+        let Some(data) = &attachment.data else {
+            return Ok(Some(format!(
+                "Skipped media '{}': only inline attachments can be imported",
+                attachment.filename
+            )));
+        };
+        let data = BASE64.decode(data).map_err(|e| {
+            AppError::BadRequest(format!("Invalid media data for '{}': {}", attachment.filename, e))
+        })?;
+
+        let content_type = store.check_upload(&attachment.filename, data.len())?;
+        Self::insert(tx, store, card_id, Some(user_id), &attachment.filename, content_type, data).await?;
+        Ok(None)
+    }
+
+    // Helper functions
+
+    async fn card_media(db: &PgPool, card_ids: &[Uuid]) -> Result<Vec<CardMedia>> {
+        let media = sqlx::query_as::<_, CardMedia>(
+            r#"
+            SELECT id, card_id, storage_key, filename, content_type, size_bytes, created_at
+            FROM card_media
+            WHERE card_id = ANY($1)
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(card_ids)
+        .fetch_all(db)
+        .await?;
+
+        Ok(media)
+    }
+
+    /// Write the object first so a row never points at missing bytes; if the
+    /// transaction later rolls back the object is merely orphaned
+    async fn insert(
+        tx: &mut Transaction<'_, Postgres>,
+        store: &MediaStore,
+        card_id: Uuid,
+        uploaded_by: Option<Uuid>,
+        filename: &str,
+        content_type: &str,
+        data: Vec<u8>,
+    ) -> Result<CardMedia> {
+        let id = Uuid::new_v4();
+        let extension = filename.rsplit_once('.').map_or("bin", |(_, ext)| ext).to_ascii_lowercase();
+        let storage_key = format!("cards/{}/{}.{}", card_id, id, extension);
+        let size_bytes = data.len() as i64;
+
+        store.put(&storage_key, data, content_type).await?;
+
+        let media = sqlx::query_as::<_, CardMedia>(
+            r#"
+            INSERT INTO card_media (id, card_id, storage_key, filename, content_type, size_bytes, uploaded_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, card_id, storage_key, filename, content_type, size_bytes, created_at
+            "#,
+        )
+        .bind(id)
+        .bind(card_id)
+        .bind(&storage_key)
+        .bind(filename)
+        .bind(content_type)
+        .bind(size_bytes)
+        .bind(uploaded_by)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(media)
+    }
+
+    /// Point a deck at a new cover, returning the key of the one it replaced
+    async fn replace_deck_cover(
+        db: &PgPool,
+        deck_id: Uuid,
+        storage_key: Option<&str>,
+    ) -> Result<Option<String>> {
+        let previous = sqlx::query_scalar::<_, Option<String>>(
+            r#"
+            UPDATE decks d SET cover_storage_key = $2
+            FROM (SELECT id, cover_storage_key FROM decks WHERE id = $1 FOR UPDATE) old
+            WHERE d.id = old.id AND d.deleted_at IS NULL
+            RETURNING old.cover_storage_key
+            "#,
+        )
+        .bind(deck_id)
+        .bind(storage_key)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::DeckNotFound)?;
+
+        Ok(previous)
+    }
+
+    /// The database no longer refers to the object, so failing to remove it
+    /// only leaves it orphaned
+    async fn delete_object(store: &MediaStore, key: &str) {
+        if let Err(e) = store.delete(key).await {
+            tracing::warn!("Failed to delete replaced media {}: {}", key, e);
+        }
+    }
+
+    fn with_url(store: &MediaStore, media: CardMedia) -> CardMediaResponse {
+        let (url, url_expires_at) = store.signed_url(&media.storage_key);
+        CardMediaResponse {
+            media,
+            url,
+            url_expires_at,
+        }
+    }
+}
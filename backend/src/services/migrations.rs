@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{migrate::Migrator, PgPool};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use crate::utils::Result;
+
+/// The migrations in `./migrations`, embedded at build time
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MigrationStatus {
+    /// `up_to_date`, `pending`, or `failed` when a migration did not finish
+    pub status: String,
+    pub applied: Vec<AppliedMigration>,
+    pub pending: Vec<PendingMigration>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: DateTime<Utc>,
+    pub success: bool,
+    /// The file changed after it was applied
+    pub checksum_mismatch: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct MigrationRow {
+    version: i64,
+    description: String,
+    installed_on: DateTime<Utc>,
+    success: bool,
+    checksum: Vec<u8>,
+}
+
+/// Schema migrations of this build against what the database has applied
+pub struct MigrationService;
+
+impl MigrationService {
+    pub async fn status(db: &PgPool) -> Result<MigrationStatus> {
+        // The table only exists once the first migration has run
+        let has_table: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(db)
+            .await?;
+        let rows = if has_table {
+            sqlx::query_as::<_, MigrationRow>(
+                r#"
+                SELECT version, description, installed_on, success, checksum
+                FROM _sqlx_migrations
+                ORDER BY version
+                "#,
+            )
+            .fetch_all(db)
+            .await?
+        } else {
+            Vec::new()
+        };
+
+        let known: HashMap<i64, &[u8]> = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| (migration.version, migration.checksum.as_ref()))
+            .collect();
+
+        let pending: Vec<PendingMigration> = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .filter(|migration| !rows.iter().any(|row| row.version == migration.version && row.success))
+            .map(|migration| PendingMigration {
+                version: migration.version,
+                description: migration.description.to_string(),
+            })
+            .collect();
+
+        let applied: Vec<AppliedMigration> = rows
+            .into_iter()
+            .map(|row| AppliedMigration {
+                checksum_mismatch: known
+                    .get(&row.version)
+                    .is_some_and(|checksum| *checksum != row.checksum.as_slice()),
+                version: row.version,
+                description: row.description,
+                installed_on: row.installed_on,
+                success: row.success,
+            })
+            .collect();
+
+        let status = if applied.iter().any(|migration| !migration.success) {
+            "failed"
+        } else if !pending.is_empty() {
+            "pending"
+        } else {
+            "up_to_date"
+        };
+
+        Ok(MigrationStatus {
+            status: status.to_string(),
+            applied,
+            pending,
+        })
+    }
+}
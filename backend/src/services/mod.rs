@@ -1,8 +1,69 @@
 pub mod auth;
 pub mod card;
+pub mod card_flags;
 pub mod deck;
+pub mod deck_settings;
 pub mod folder;
 pub mod study;
 pub mod import_export;
 pub mod search;
 pub mod vertex_ai;
+pub mod ai_provider;
+pub mod openai;
+pub mod ollama;
+pub mod ai_usage;
+pub mod card_lint;
+pub mod card_explanation;
+pub mod circuit_breaker;
+pub mod job;
+pub mod scheduler;
+pub mod study_queue;
+pub mod admin;
+pub mod sharing;
+pub mod leech;
+pub mod ws;
+pub mod document;
+pub mod exporters;
+pub mod encryption;
+pub mod ai_generation;
+pub mod consistency;
+pub mod badge;
+pub mod webhook;
+pub mod ai_privacy;
+pub mod recommendation;
+pub mod learning_patterns;
+pub mod quiz;
+pub mod capture;
+pub mod email_inbox;
+pub mod user;
+pub mod guest;
+pub mod trash;
+pub mod storage;
+pub mod media;
+pub mod quiz_session;
+pub mod grading;
+pub mod stats;
+pub mod study_events;
+pub mod sync;
+pub mod achievement;
+pub mod leaderboard;
+pub mod learning_stats;
+pub mod oauth;
+pub mod api_key;
+pub mod deck_merge;
+pub mod smart_deck;
+pub mod mailer;
+pub mod notification;
+pub mod quizlet;
+pub mod duplicates;
+pub mod dashboard;
+pub mod marketplace;
+pub mod language;
+pub mod reverse_cards;
+pub mod preferences;
+pub mod data_export;
+pub mod anki_text;
+pub mod upload;
+pub mod migrations;
+pub mod seed;
+pub mod queue_cache;
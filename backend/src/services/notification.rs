@@ -0,0 +1,200 @@
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::EmailConfig,
+    models::{NotificationPreferences, UpdateNotificationPreferencesDto},
+    services::mailer::Mailer,
+    utils::{AppError, Result},
+};
+
+#[derive(sqlx::FromRow)]
+struct DueReminder {
+    user_id: Uuid,
+    email: String,
+    display_name: Option<String>,
+    cards_due: i64,
+    decks_due: i64,
+}
+
+/// Daily review reminders by email. Each user picks a local time and the
+/// weekdays to be reminded on; a background task emails users with cards
+/// due once their reminder time has passed that day.
+pub struct NotificationService;
+
+impl NotificationService {
+    pub async fn get_preferences(db: &PgPool, user_id: Uuid) -> Result<NotificationPreferences> {
+        // Defaults until the user saves a schedule
+        let preferences = sqlx::query_as::<_, NotificationPreferences>(
+            r#"
+            INSERT INTO notification_preferences (user_id) VALUES ($1)
+            ON CONFLICT (user_id) DO UPDATE SET user_id = EXCLUDED.user_id
+            RETURNING user_id, email_reminders_enabled, reminder_time, reminder_days, timezone,
+                      last_reminder_on, updated_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(preferences)
+    }
+
+    pub async fn update_preferences(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: UpdateNotificationPreferencesDto,
+    ) -> Result<NotificationPreferences> {
+        if let Some(days) = &dto.reminder_days {
+            if days.is_empty() || days.iter().any(|day| !(1..=7).contains(day)) {
+                return Err(AppError::ValidationError(
+                    "reminder_days must be ISO weekdays from 1 (Monday) to 7 (Sunday)".to_string(),
+                ));
+            }
+        }
+        let mut days = dto.reminder_days;
+        if let Some(days) = &mut days {
+            days.sort_unstable();
+            days.dedup();
+        }
+
+        if let Some(timezone) = &dto.timezone {
+            let known = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM pg_timezone_names WHERE name = $1)",
+            )
+            .bind(timezone)
+            .fetch_one(db)
+            .await?;
+            if !known {
+                return Err(AppError::ValidationError(format!("Unknown timezone: {}", timezone)));
+            }
+        }
+
+        Self::get_preferences(db, user_id).await?;
+        let preferences = sqlx::query_as::<_, NotificationPreferences>(
+            r#"
+            UPDATE notification_preferences
+            SET email_reminders_enabled = COALESCE($2, email_reminders_enabled),
+                reminder_time = COALESCE($3, reminder_time),
+                reminder_days = COALESCE($4, reminder_days),
+                timezone = COALESCE($5, timezone),
+                updated_at = NOW()
+            WHERE user_id = $1
+            RETURNING user_id, email_reminders_enabled, reminder_time, reminder_days, timezone,
+                      last_reminder_on, updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(dto.email_reminders_enabled)
+        .bind(dto.reminder_time)
+        .bind(days)
+        .bind(dto.timezone)
+        .fetch_one(db)
+        .await?;
+
+        Ok(preferences)
+    }
+
+    /// Email every user whose reminder time passed within the last hour in
+    /// their timezone, on one of their reminder days, and who has cards
+    /// due. Each user is checked at most once per local day, even across
+    /// several server instances. Returns how many reminders were sent.
+    pub async fn send_due_reminders(db: &PgPool, mailer: &dyn Mailer, email: &EmailConfig) -> Result<u64> {
+        let reminders = sqlx::query_as::<_, DueReminder>(
+            r#"
+            WITH local AS (
+                SELECT user_id, NOW() AT TIME ZONE timezone as local_now
+                FROM notification_preferences
+                WHERE email_reminders_enabled
+            ),
+            claimed AS (
+                UPDATE notification_preferences p
+                SET last_reminder_on = l.local_now::DATE
+                FROM local l, users u
+                WHERE p.user_id = l.user_id AND u.id = p.user_id AND u.deleted_at IS NULL
+                    AND EXTRACT(ISODOW FROM l.local_now)::SMALLINT = ANY(p.reminder_days)
+                    AND l.local_now >= l.local_now::DATE + p.reminder_time
+                    AND l.local_now < l.local_now::DATE + p.reminder_time + INTERVAL '1 hour'
+                    AND (p.last_reminder_on IS NULL OR p.last_reminder_on < l.local_now::DATE)
+                RETURNING p.user_id, u.email, u.display_name
+            )
+            SELECT c.user_id, c.email, c.display_name,
+                   COUNT(*) as cards_due, COUNT(DISTINCT cd.deck_id) as decks_due
+            FROM claimed c
+            JOIN user_card_stats s ON s.user_id = c.user_id AND s.next_review_at <= NOW()
+            JOIN cards cd ON cd.id = s.card_id AND cd.deleted_at IS NULL
+            JOIN decks d ON d.id = cd.deck_id AND d.deleted_at IS NULL
+            GROUP BY c.user_id, c.email, c.display_name
+            "#,
+        )
+        .fetch_all(db)
+        .await?;
+
+        let mut sent = 0;
+        for reminder in reminders {
+            let (subject, body) = reminder_email(
+                reminder.display_name.as_deref(),
+                reminder.cards_due,
+                reminder.decks_due,
+                &email.app_url,
+            );
+            match mailer.send(&reminder.email, &subject, &body).await {
+                Ok(()) => sent += 1,
+                Err(e) => tracing::warn!("Review reminder to user {} failed: {}", reminder.user_id, e),
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Run `send_due_reminders` in the background every `every`
+    pub fn spawn_reminder_sweeper(
+        db: PgPool,
+        mailer: Arc<dyn Mailer>,
+        email: EmailConfig,
+        every: std::time::Duration,
+    ) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match Self::send_due_reminders(&db, mailer.as_ref(), &email).await {
+                    Ok(0) => {}
+                    Ok(sent) => tracing::info!("Sent {} review reminders via {}", sent, mailer.name()),
+                    Err(e) => tracing::warn!("Review reminder sweep failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Subject and plain-text body of a review reminder
+pub fn reminder_email(
+    display_name: Option<&str>,
+    cards_due: i64,
+    decks_due: i64,
+    app_url: &str,
+) -> (String, String) {
+    let cards = plural(cards_due, "card", "cards");
+    let decks = plural(decks_due, "deck", "decks");
+    let subject = format!("{} due for review", cards);
+    let greeting = match display_name {
+        Some(name) if !name.trim().is_empty() => format!("Hi {},", name.trim()),
+        _ => "Hi,".to_string(),
+    };
+    let body = format!(
+        "{}\n\nYou have {} due across {}. A short session now keeps them fresh.\n\n\
+         Start reviewing: {}/study\n\n\
+         You can change or turn off these reminders in your notification settings.\n",
+        greeting,
+        cards,
+        decks,
+        app_url.trim_end_matches('/'),
+    );
+    (subject, body)
+}
+
+fn plural(count: i64, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
@@ -0,0 +1,344 @@
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::{
+    config::{Config, OAuthClientConfig},
+    models::{AuthResponse, ClientInfo, User},
+    services::{auth::AuthService, sharing::SharingService},
+    utils::{AppError, Result},
+};
+
+// GitHub's API refuses requests without a User-Agent
+const USER_AGENT: &str = "DeckOracle";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OAuthProvider {
+    Google,
+    GitHub,
+}
+
+impl OAuthProvider {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "google" => Some(Self::Google),
+            "github" => Some(Self::GitHub),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Google => "google",
+            Self::GitHub => "github",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Google => "Google",
+            Self::GitHub => "GitHub",
+        }
+    }
+
+    fn authorize_endpoint(self) -> &'static str {
+        match self {
+            Self::Google => "https://accounts.google.com/o/oauth2/v2/auth",
+            Self::GitHub => "https://github.com/login/oauth/authorize",
+        }
+    }
+
+    fn token_endpoint(self) -> &'static str {
+        match self {
+            Self::Google => "https://oauth2.googleapis.com/token",
+            Self::GitHub => "https://github.com/login/oauth/access_token",
+        }
+    }
+
+    fn scope(self) -> &'static str {
+        match self {
+            Self::Google => "openid email profile",
+            Self::GitHub => "read:user user:email",
+        }
+    }
+
+    fn client(self, config: &Config) -> Option<&OAuthClientConfig> {
+        match self {
+            Self::Google => config.oauth.google.as_ref(),
+            Self::GitHub => config.oauth.github.as_ref(),
+        }
+    }
+}
+
+/// The identity a provider vouches for
+#[derive(Debug, Clone)]
+pub struct OAuthProfile {
+    pub provider_user_id: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GoogleUserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    id: i64,
+    login: String,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+pub struct OAuthService;
+
+impl OAuthService {
+    /// Resolve a provider name from the URL, refusing ones without credentials
+    pub fn provider(config: &Config, name: &str) -> Result<OAuthProvider> {
+        OAuthProvider::from_name(name)
+            .filter(|provider| provider.client(config).is_some())
+            .ok_or_else(|| AppError::NotFound(format!("Sign-in provider '{}' is not available", name)))
+    }
+
+    /// Where to send the browser to sign in; `state` comes back on the callback
+    pub fn authorization_url(config: &Config, provider: OAuthProvider, state: &str) -> Result<String> {
+        let client = Self::client(config, provider)?;
+        let redirect_uri = Self::redirect_uri(config, provider);
+        let url = Url::parse_with_params(
+            provider.authorize_endpoint(),
+            &[
+                ("client_id", client.client_id.as_str()),
+                ("redirect_uri", redirect_uri.as_str()),
+                ("response_type", "code"),
+                ("scope", provider.scope()),
+                ("state", state),
+            ],
+        )
+        .map_err(|_| AppError::InternalServerError)?;
+
+        Ok(url.into())
+    }
+
+    /// Trade the callback's code for an access token and read the profile
+    pub async fn fetch_profile(config: &Config, provider: OAuthProvider, code: &str) -> Result<OAuthProfile> {
+        let client = Self::client(config, provider)?;
+        let http = Client::new();
+        let redirect_uri = Self::redirect_uri(config, provider);
+
+        let token = http
+            .post(provider.token_endpoint())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&[
+                ("client_id", client.client_id.as_str()),
+                ("client_secret", client.client_secret.as_str()),
+                ("code", code),
+                ("redirect_uri", redirect_uri.as_str()),
+                ("grant_type", "authorization_code"),
+            ])
+            .send()
+            .await
+            .map_err(|e| Self::provider_error(provider, e))?
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| Self::provider_error(provider, e))?;
+
+        let Some(access_token) = token.access_token else {
+            tracing::warn!("{} rejected the authorization code: {:?}", provider.label(), token.error);
+            return Err(AppError::BadRequest(format!("{} sign-in failed", provider.label())));
+        };
+
+        match provider {
+            OAuthProvider::Google => {
+                let info: GoogleUserInfo = Self::get_json(
+                    &http,
+                    provider,
+                    "https://openidconnect.googleapis.com/v1/userinfo",
+                    &access_token,
+                )
+                .await?;
+                Ok(OAuthProfile {
+                    provider_user_id: info.sub,
+                    email: info.email,
+                    email_verified: info.email_verified,
+                    display_name: info.name,
+                })
+            }
+            OAuthProvider::GitHub => {
+                let user: GitHubUser =
+                    Self::get_json(&http, provider, "https://api.github.com/user", &access_token).await?;
+                let emails: Vec<GitHubEmail> =
+                    Self::get_json(&http, provider, "https://api.github.com/user/emails", &access_token).await?;
+                let primary = emails.into_iter().find(|e| e.primary);
+                Ok(OAuthProfile {
+                    provider_user_id: user.id.to_string(),
+                    email_verified: primary.as_ref().is_some_and(|e| e.verified),
+                    email: primary.map(|e| e.email),
+                    display_name: user.name.or(Some(user.login)),
+                })
+            }
+        }
+    }
+
+    /// Sign in as the user linked to the provider identity. Unlinked
+    /// identities are linked to the account with the same verified email, or
+    /// get a new account. An unverified local account is never linked, since
+    /// whoever registered it may not own the address.
+    pub async fn sign_in(
+        db: &PgPool,
+        config: &Config,
+        provider: OAuthProvider,
+        profile: OAuthProfile,
+        client: &ClientInfo,
+    ) -> Result<AuthResponse> {
+        let mut tx = db.begin().await?;
+
+        let linked = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE linked_accounts la SET last_login_at = NOW(), email = COALESCE($3, la.email)
+            FROM users u
+            WHERE la.provider = $1 AND la.provider_user_id = $2 AND u.id = la.user_id AND u.deleted_at IS NULL
+            RETURNING u.*
+            "#,
+        )
+        .bind(provider.name())
+        .bind(&profile.provider_user_id)
+        .bind(&profile.email)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let user = match linked {
+            Some(user) => user,
+            None => {
+                let email = profile
+                    .email
+                    .as_deref()
+                    .filter(|_| profile.email_verified)
+                    .ok_or_else(|| {
+                        AppError::BadRequest(format!(
+                            "Your {} account has no verified email address",
+                            provider.label()
+                        ))
+                    })?;
+
+                let existing = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1 FOR UPDATE")
+                    .bind(email)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+
+                let user = match existing {
+                    Some(user) if user.deleted_at.is_some() => return Err(AppError::Unauthorized),
+                    Some(user) if !user.email_verified => {
+                        return Err(AppError::BadRequest(
+                            "An account with this email already exists. Sign in with your password and \
+                             verify your email address before signing in with another provider."
+                                .to_string(),
+                        ));
+                    }
+                    Some(user) => user,
+                    None => {
+                        // Unusable until a password reset sets one
+                        let password_hash = AuthService::hash_password(&AuthService::generate_random_token())?;
+                        let user = sqlx::query_as::<_, User>(
+                            r#"
+                            INSERT INTO users (email, password_hash, display_name, email_verified, email_verified_at)
+                            VALUES ($1, $2, $3, true, NOW())
+                            RETURNING *
+                            "#,
+                        )
+                        .bind(email)
+                        .bind(&password_hash)
+                        .bind(&profile.display_name)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                        SharingService::claim_invites(&mut *tx, user.id, email).await?;
+                        user
+                    }
+                };
+
+                sqlx::query(
+                    r#"
+                    INSERT INTO linked_accounts (user_id, provider, provider_user_id, email)
+                    VALUES ($1, $2, $3, $4)
+                    "#,
+                )
+                .bind(user.id)
+                .bind(provider.name())
+                .bind(&profile.provider_user_id)
+                .bind(email)
+                .execute(&mut *tx)
+                .await?;
+
+                user
+            }
+        };
+
+        tx.commit().await?;
+
+        AuthService::record_login_attempt(db, &user.email, Some(user.id), client, None).await?;
+        let (access_token, refresh_token) = AuthService::generate_tokens(&user, config, db).await?;
+
+        Ok(AuthResponse {
+            access_token,
+            refresh_token,
+            token_type: "Bearer".to_string(),
+            expires_in: config.jwt.expiration,
+            user: AuthService::user_to_response(&user),
+        })
+    }
+
+    fn client(config: &Config, provider: OAuthProvider) -> Result<&OAuthClientConfig> {
+        provider.client(config).ok_or_else(|| {
+            AppError::NotFound(format!("Sign-in provider '{}' is not available", provider.name()))
+        })
+    }
+
+    fn redirect_uri(config: &Config, provider: OAuthProvider) -> String {
+        format!(
+            "{}/api/v1/auth/oauth/{}/callback",
+            config.email.api_url.trim_end_matches('/'),
+            provider.name()
+        )
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        http: &Client,
+        provider: OAuthProvider,
+        url: &str,
+        access_token: &str,
+    ) -> Result<T> {
+        http.get(url)
+            .bearer_auth(access_token)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| Self::provider_error(provider, e))?
+            .json::<T>()
+            .await
+            .map_err(|e| Self::provider_error(provider, e))
+    }
+
+    fn provider_error(provider: OAuthProvider, error: reqwest::Error) -> AppError {
+        tracing::error!("{} sign-in request failed: {}", provider.label(), error);
+        AppError::BadRequest(format!("{} sign-in failed", provider.label()))
+    }
+}
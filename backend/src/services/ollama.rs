@@ -0,0 +1,92 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::time::timeout;
+use tracing::error;
+
+use crate::{
+    config::OllamaConfig,
+    models::ai::{AiProviderKind, VertexAiRequest, VertexAiResponse},
+    services::ai_provider::AiProvider,
+};
+
+#[derive(Debug, Deserialize)]
+struct GenerateResponse {
+    model: Option<String>,
+    response: String,
+    done_reason: Option<String>,
+    prompt_eval_count: Option<i32>,
+    eval_count: Option<i32>,
+}
+
+/// Client for Ollama's native `/api/generate`, which unlike its OpenAI
+/// compatibility layer honours `top_k`
+pub struct OllamaClient {
+    config: OllamaConfig,
+    http_client: Client,
+}
+
+impl OllamaClient {
+    pub fn new(config: OllamaConfig) -> Self {
+        Self {
+            config: OllamaConfig {
+                base_url: config.base_url.trim_end_matches('/').to_string(),
+                ..config
+            },
+            http_client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl AiProvider for OllamaClient {
+    fn kind(&self) -> AiProviderKind {
+        AiProviderKind::Ollama
+    }
+
+    fn default_model(&self) -> &str {
+        &self.config.model
+    }
+
+    async fn generate_content(&mut self, request: VertexAiRequest) -> Result<VertexAiResponse> {
+        let body = json!({
+            "model": request.model,
+            "prompt": request.prompt,
+            "stream": false,
+            "options": {
+                "temperature": request.temperature,
+                "top_p": request.top_p,
+                "top_k": request.top_k,
+                "num_predict": request.max_tokens,
+            },
+        });
+
+        let response = timeout(
+            std::time::Duration::from_secs(self.config.timeout_seconds),
+            self.http_client
+                .post(format!("{}/api/generate", self.config.base_url))
+                .json(&body)
+                .send(),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("Ollama did not respond within {}s", self.config.timeout_seconds))??;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            error!("Ollama API error ({}): {}", status, error_text);
+            return Err(anyhow::anyhow!("Ollama API error ({}): {}", status, error_text));
+        }
+
+        let generated: GenerateResponse = response.json().await?;
+
+        Ok(VertexAiResponse {
+            text: generated.response,
+            tokens_used: generated.prompt_eval_count.unwrap_or(0) + generated.eval_count.unwrap_or(0),
+            model: generated.model.unwrap_or(request.model),
+            finish_reason: generated.done_reason.unwrap_or_default(),
+        })
+    }
+}
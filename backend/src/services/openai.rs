@@ -0,0 +1,115 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::time::timeout;
+use tracing::error;
+
+use crate::{
+    config::OpenAiConfig,
+    models::ai::{AiProviderKind, VertexAiRequest, VertexAiResponse},
+    services::ai_provider::AiProvider,
+};
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatChoice>,
+    model: Option<String>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    total_tokens: i32,
+}
+
+/// Client for the OpenAI chat completions API, which OpenAI itself and most
+/// self-hosted servers (vLLM, llama.cpp, LM Studio, Ollama's `/v1`) speak
+pub struct OpenAiClient {
+    config: OpenAiConfig,
+    http_client: Client,
+}
+
+impl OpenAiClient {
+    pub fn new(config: OpenAiConfig) -> Self {
+        Self {
+            config: OpenAiConfig {
+                base_url: config.base_url.trim_end_matches('/').to_string(),
+                ..config
+            },
+            http_client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl AiProvider for OpenAiClient {
+    fn kind(&self) -> AiProviderKind {
+        AiProviderKind::OpenAi
+    }
+
+    fn default_model(&self) -> &str {
+        &self.config.model
+    }
+
+    async fn generate_content(&mut self, request: VertexAiRequest) -> Result<VertexAiResponse> {
+        // Chat completions has no top_k
+        let body = json!({
+            "model": request.model,
+            "messages": [{ "role": "user", "content": request.prompt }],
+            "temperature": request.temperature,
+            "top_p": request.top_p,
+            "max_tokens": request.max_tokens,
+            "stream": false,
+        });
+
+        let mut builder = self
+            .http_client
+            .post(format!("{}/chat/completions", self.config.base_url))
+            .json(&body);
+        if let Some(api_key) = &self.config.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+
+        let response = timeout(
+            std::time::Duration::from_secs(self.config.timeout_seconds),
+            builder.send(),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("Model server did not respond within {}s", self.config.timeout_seconds))??;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            error!("Chat completions API error ({}): {}", status, error_text);
+            return Err(anyhow::anyhow!("Chat completions API error ({}): {}", status, error_text));
+        }
+
+        let completion: ChatCompletionResponse = response.json().await?;
+        let tokens_used = completion.usage.map_or(0, |u| u.total_tokens);
+        let choice = completion
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No valid response from the model server"))?;
+
+        Ok(VertexAiResponse {
+            text: choice.message.content.unwrap_or_default(),
+            tokens_used,
+            model: completion.model.unwrap_or(request.model),
+            finish_reason: choice.finish_reason.unwrap_or_default(),
+        })
+    }
+}
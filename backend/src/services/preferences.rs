@@ -0,0 +1,55 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{UpdateUserPreferencesDto, UserPreferences},
+    utils::Result,
+};
+
+/// Per-user study preferences. Users without a stored row get the column
+/// defaults.
+pub struct PreferencesService;
+
+impl PreferencesService {
+    pub async fn get(db: &PgPool, user_id: Uuid) -> Result<UserPreferences> {
+        let preferences = sqlx::query_as::<_, UserPreferences>(
+            r#"
+            INSERT INTO user_preferences (user_id) VALUES ($1)
+            ON CONFLICT (user_id) DO UPDATE SET user_id = EXCLUDED.user_id
+            RETURNING user_id, new_card_order, interleave_decks, updated_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(preferences)
+    }
+
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: UpdateUserPreferencesDto,
+    ) -> Result<UserPreferences> {
+        // Make sure the row exists so the update has something to change
+        Self::get(db, user_id).await?;
+
+        let preferences = sqlx::query_as::<_, UserPreferences>(
+            r#"
+            UPDATE user_preferences
+            SET new_card_order = COALESCE($2, new_card_order),
+                interleave_decks = COALESCE($3, interleave_decks),
+                updated_at = NOW()
+            WHERE user_id = $1
+            RETURNING user_id, new_card_order, interleave_decks, updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(dto.new_card_order)
+        .bind(dto.interleave_decks)
+        .fetch_one(db)
+        .await?;
+
+        Ok(preferences)
+    }
+}
@@ -0,0 +1,360 @@
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    config::StudyConfig,
+    models::ai::{StudyQueue, StudyQueueQuery},
+    services::study_queue::StudyQueueService,
+    utils::Result,
+};
+
+/// Hit counts of the study queue cache since the server started
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueueCacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    /// Users with cached queues or a queue being built
+    pub users: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Share of lookups served from the cache, 0-1
+    pub hit_rate: f64,
+    /// Users whose queues were dropped after they studied or edited cards
+    pub invalidations: u64,
+    /// Queues built ahead of time by the background task
+    pub precomputed: u64,
+}
+
+struct CachedQueue {
+    queue: StudyQueue,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct UserQueues {
+    queues: HashMap<StudyQueueQuery, CachedQueue>,
+    /// Queues whose build started before this are stale. Only kept while
+    /// one of the user's queues is being built.
+    invalidated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct Entries {
+    users: HashMap<Uuid, UserQueues>,
+    len: usize,
+}
+
+/// In-memory cache of built study queues, keyed by user and query.
+///
+/// A queue stays valid until the user answers or edits cards (the handlers
+/// call `invalidate_user`), the UTC day rolls over and daily limits reset,
+/// or the TTL passes, which bounds how long cards becoming due or changes
+/// made by other members of a shared deck can go unnoticed.
+pub struct QueueCache {
+    entries: RwLock<Entries>,
+    /// Queue builds in progress per user
+    building: Mutex<HashMap<Uuid, usize>>,
+    ttl: Duration,
+    max_entries: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+    precomputed: AtomicU64,
+}
+
+impl QueueCache {
+    /// A zero `ttl` or `max_entries` disables caching
+    pub fn new(ttl: std::time::Duration, max_entries: usize) -> Self {
+        Self {
+            entries: RwLock::new(Entries::default()),
+            building: Mutex::new(HashMap::new()),
+            ttl: Duration::from_std(ttl).unwrap_or(Duration::zero()),
+            max_entries,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+            precomputed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_config(config: &StudyConfig) -> Self {
+        Self::new(
+            std::time::Duration::from_secs(config.queue_cache_ttl_seconds),
+            config.queue_cache_max_entries,
+        )
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.ttl > Duration::zero() && self.max_entries > 0
+    }
+
+    /// The user's queue for `query`, built with `StudyQueueService` on a miss
+    pub async fn get_or_build(
+        &self,
+        db: &PgPool,
+        user_id: Uuid,
+        query: &StudyQueueQuery,
+    ) -> Result<StudyQueue> {
+        if !self.is_enabled() {
+            return StudyQueueService::build_queue(db, user_id, query).await;
+        }
+
+        let now = Utc::now();
+        if let Some(queue) = self.get(user_id, query, now).await {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(queue);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let _building = self.start_build(user_id);
+        match StudyQueueService::build_queue(db, user_id, query).await {
+            Ok(queue) => {
+                self.insert(user_id, query.clone(), queue.clone(), now).await;
+                Ok(queue)
+            }
+            Err(e) => {
+                self.abandon_build(user_id).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Drop the user's queues; their next request rebuilds them
+    pub async fn invalidate_user(&self, user_id: Uuid) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut entries = self.entries.write().await;
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+        if self.builds_in_progress(user_id) > 0 {
+            // Remember when, so queues built from data read before now are not cached
+            let user = entries.users.entry(user_id).or_default();
+            let dropped = user.queues.len();
+            user.queues.clear();
+            user.invalidated_at = Some(Utc::now());
+            entries.len -= dropped;
+        } else if let Some(user) = entries.users.remove(&user_id) {
+            entries.len -= user.queues.len();
+        }
+    }
+
+    pub async fn stats(&self) -> QueueCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+
+        let entries = self.entries.read().await;
+        QueueCacheStats {
+            enabled: self.is_enabled(),
+            entries: entries.len,
+            users: entries.users.len(),
+            hits,
+            misses,
+            hit_rate: if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 },
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            precomputed: self.precomputed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Build the deck queues of users who studied in the last day, for each
+    /// deck they studied, unless a cached queue outlasts `horizon`. Right
+    /// after the UTC day rolls over every entry has expired, so this
+    /// prepares the new study day before those users come back.
+    /// Returns the number of queues built.
+    pub async fn precompute(&self, db: &PgPool, horizon: std::time::Duration) -> Result<usize> {
+        if !self.is_enabled() {
+            return Ok(0);
+        }
+
+        let pairs: Vec<(Uuid, Uuid)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT s.user_id, s.deck_id
+            FROM study_sessions s
+            JOIN decks d ON d.id = s.deck_id AND d.deleted_at IS NULL
+            WHERE s.started_at >= NOW() - INTERVAL '1 day'
+              AND s.deleted_at IS NULL
+            LIMIT $1
+            "#,
+        )
+        .bind(self.max_entries as i64)
+        .fetch_all(db)
+        .await?;
+
+        let horizon = Utc::now() + Duration::from_std(horizon).unwrap_or(Duration::zero());
+        let mut built = 0;
+        for (user_id, deck_id) in pairs {
+            let query = StudyQueueQuery {
+                deck_id: Some(deck_id),
+                ..Default::default()
+            };
+            if self.expires_at(user_id, &query).await.is_some_and(|expires_at| expires_at > horizon) {
+                continue;
+            }
+
+            let now = Utc::now();
+            let _building = self.start_build(user_id);
+            match StudyQueueService::build_queue(db, user_id, &query).await {
+                Ok(queue) => {
+                    if self.insert(user_id, query, queue, now).await {
+                        built += 1;
+                    }
+                }
+                // Access to the deck may have been revoked since
+                Err(e) => {
+                    self.abandon_build(user_id).await;
+                    tracing::debug!("Skipped precomputing queue of deck {} for {}: {}", deck_id, user_id, e)
+                }
+            }
+        }
+
+        self.precomputed.fetch_add(built as u64, Ordering::Relaxed);
+        Ok(built)
+    }
+
+    /// Run `precompute` in the background every `every`
+    pub fn spawn_precomputer(self: Arc<Self>, db: PgPool, every: std::time::Duration) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match self.precompute(&db, every).await {
+                    Ok(0) => {}
+                    Ok(built) => tracing::debug!("Precomputed {} study queues", built),
+                    Err(e) => tracing::warn!("Study queue precomputation failed: {}", e),
+                }
+            }
+        });
+    }
+
+    async fn get(&self, user_id: Uuid, query: &StudyQueueQuery, now: DateTime<Utc>) -> Option<StudyQueue> {
+        let entries = self.entries.read().await;
+        let cached = entries.users.get(&user_id)?.queues.get(query)?;
+        (cached.expires_at > now).then(|| cached.queue.clone())
+    }
+
+    async fn expires_at(&self, user_id: Uuid, query: &StudyQueueQuery) -> Option<DateTime<Utc>> {
+        let entries = self.entries.read().await;
+        Some(entries.users.get(&user_id)?.queues.get(query)?.expires_at)
+    }
+
+    /// Count a build of one of the user's queues until the guard is dropped
+    fn start_build(&self, user_id: Uuid) -> BuildGuard<'_> {
+        *self.building.lock().unwrap().entry(user_id).or_insert(0) += 1;
+        BuildGuard { cache: self, user_id }
+    }
+
+    fn builds_in_progress(&self, user_id: Uuid) -> usize {
+        self.building.lock().unwrap().get(&user_id).copied().unwrap_or(0)
+    }
+
+    /// Cache a queue whose build started at `built_at`, unless the user was
+    /// invalidated meanwhile or the cache is full of live entries. Called
+    /// while the build is still counted as in progress.
+    async fn insert(
+        &self,
+        user_id: Uuid,
+        query: StudyQueueQuery,
+        queue: StudyQueue,
+        built_at: DateTime<Utc>,
+    ) -> bool {
+        let mut entries = self.entries.write().await;
+        let stale = entries.users.get(&user_id).is_some_and(|user| {
+            user.invalidated_at.is_some_and(|invalidated_at| invalidated_at >= built_at)
+        });
+        self.forget_invalidation(&mut entries, user_id);
+        if stale {
+            return false;
+        }
+
+        let now = Utc::now();
+        if entries.len >= self.max_entries {
+            self.evict_expired(&mut entries, now);
+            if entries.len >= self.max_entries {
+                return false;
+            }
+        }
+
+        let user = entries.users.entry(user_id).or_default();
+        let entry = CachedQueue {
+            queue,
+            expires_at: (built_at + self.ttl).min(next_day_start(built_at)),
+        };
+        if user.queues.insert(query, entry).is_none() {
+            entries.len += 1;
+        }
+        true
+    }
+
+    /// A build that is not cached still has to clean up after an invalidation
+    async fn abandon_build(&self, user_id: Uuid) {
+        let mut entries = self.entries.write().await;
+        self.forget_invalidation(&mut entries, user_id);
+    }
+
+    /// Once the last build in progress has finished, drop the user's
+    /// invalidation time, and the user with it if nothing is cached
+    fn forget_invalidation(&self, entries: &mut Entries, user_id: Uuid) {
+        if self.builds_in_progress(user_id) > 1 {
+            return;
+        }
+        if let Some(user) = entries.users.get_mut(&user_id) {
+            user.invalidated_at = None;
+            if user.queues.is_empty() {
+                entries.users.remove(&user_id);
+            }
+        }
+    }
+
+    fn evict_expired(&self, entries: &mut Entries, now: DateTime<Utc>) {
+        let building = self.building.lock().unwrap();
+        let mut evicted = 0;
+        entries.users.retain(|user_id, user| {
+            let before = user.queues.len();
+            user.queues.retain(|_, cached| cached.expires_at > now);
+            evicted += before - user.queues.len();
+            // Keep invalidations that builds in progress still have to see
+            !user.queues.is_empty() || building.contains_key(user_id)
+        });
+        entries.len -= evicted;
+    }
+}
+
+/// Marks a queue build as in progress for as long as it lives, including
+/// when the request building it is dropped halfway
+struct BuildGuard<'a> {
+    cache: &'a QueueCache,
+    user_id: Uuid,
+}
+
+impl Drop for BuildGuard<'_> {
+    fn drop(&mut self) {
+        let mut building = self.cache.building.lock().unwrap();
+        if let Some(count) = building.get_mut(&self.user_id) {
+            *count -= 1;
+            if *count == 0 {
+                building.remove(&self.user_id);
+            }
+        }
+    }
+}
+
+/// Daily limits and sibling burying reset at midnight UTC
+fn next_day_start(at: DateTime<Utc>) -> DateTime<Utc> {
+    (at.date_naive() + Duration::days(1)).and_time(NaiveTime::MIN).and_utc()
+}
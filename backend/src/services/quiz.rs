@@ -0,0 +1,221 @@
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    config::AiConfig,
+    models::{
+        ai::{DeckExam, DeckExamWithQuestions, ExamQuestion, GenerateQuizDto, GeneratedQuizQuestion},
+        DeckRole,
+    },
+    services::{
+        ai_privacy::AiPrivacyService, ai_provider::provider_for, ai_usage::AiUsageService,
+        circuit_breaker::CircuitBreaker, encryption::EncryptionService, language::DeckLanguages,
+        sharing::SharingService,
+    },
+    utils::{AppError, Result},
+};
+
+const DEFAULT_QUESTION_COUNT: i32 = 10;
+
+/// Fewer cards than this leave too little material for distractors
+const MIN_CARDS: i64 = 4;
+
+/// Cards sent to the model per quiz; larger decks are sampled
+const MAX_SOURCE_CARDS: i64 = 200;
+
+const MIN_OPTIONS: usize = 3;
+const MAX_OPTIONS: usize = 6;
+
+const EXAM_COLUMNS: &str = r#"
+    e.id, e.deck_id, e.user_id, e.title, e.model_name, e.tokens_used,
+    (SELECT COUNT(*) FROM deck_exam_questions q WHERE q.exam_id = e.id) as question_count,
+    e.created_at
+"#;
+
+/// Multiple-choice exams generated from a deck's own cards
+pub struct QuizService;
+
+impl QuizService {
+    pub async fn generate(
+        db: &PgPool,
+        ai: &AiConfig,
+        breaker: &Arc<CircuitBreaker>,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: GenerateQuizDto,
+    ) -> Result<DeckExamWithQuestions> {
+        if !ai.enabled {
+            return Err(AppError::BadRequest("AI features are not enabled".to_string()));
+        }
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+        // Card content of encrypted decks must never reach the AI provider
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+        AiPrivacyService::require_content_generation(db, user_id).await?;
+
+        let cards = sqlx::query_as::<_, (Uuid, String, String)>(
+            "SELECT id, front, back FROM cards WHERE deck_id = $1 AND deleted_at IS NULL ORDER BY random() LIMIT $2",
+        )
+        .bind(deck_id)
+        .bind(MAX_SOURCE_CARDS)
+        .fetch_all(db)
+        .await?;
+
+        if (cards.len() as i64) < MIN_CARDS {
+            return Err(AppError::BadRequest(format!(
+                "A deck needs at least {} cards to generate a quiz",
+                MIN_CARDS
+            )));
+        }
+
+        let count = dto.question_count.unwrap_or(DEFAULT_QUESTION_COUNT).max(1) as usize;
+        let content: Vec<(String, String)> = cards
+            .iter()
+            .map(|(_, front, back)| (front.clone(), back.clone()))
+            .collect();
+        let languages = DeckLanguages::for_deck(db, deck_id).await?;
+
+        let mut client = provider_for(ai, breaker, dto.provider)?;
+        AiUsageService::check_quota(db, ai, user_id).await?;
+        let batch = client
+            .generate_quiz_questions(&content, count, &languages)
+            .await
+            .map_err(|e| {
+                tracing::error!("AI provider error: {}", e);
+                AppError::InternalServerError
+            })?;
+        AiUsageService::record(db, ai, user_id, client.kind(), batch.tokens_used).await?;
+
+        let questions: Vec<(Uuid, GeneratedQuizQuestion)> = batch
+            .questions
+            .into_iter()
+            .filter_map(|q| {
+                let card_id = cards.get(q.card.checked_sub(1)?)?.0;
+                Some((card_id, normalize_question(q)?))
+            })
+            .take(count)
+            .collect();
+
+        if questions.is_empty() {
+            tracing::error!("AI provider returned no usable quiz questions for deck {}", deck_id);
+            return Err(AppError::InternalServerError);
+        }
+
+        let mut tx = db.begin().await?;
+
+        let title = match dto.title {
+            Some(title) => title,
+            None => sqlx::query_scalar::<_, String>("SELECT title || ' quiz' FROM decks WHERE id = $1")
+                .bind(deck_id)
+                .fetch_one(&mut *tx)
+                .await?,
+        };
+
+        let exam_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO deck_exams (deck_id, user_id, title, model_name, tokens_used)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .bind(&title)
+        .bind(&batch.model)
+        .bind(batch.tokens_used)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (position, (card_id, question)) in questions.into_iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO deck_exam_questions
+                    (exam_id, card_id, position, question, options, correct_index, explanation)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+            )
+            .bind(exam_id)
+            .bind(card_id)
+            .bind(position as i32)
+            .bind(&question.question)
+            .bind(&question.options)
+            .bind(question.answer as i32)
+            .bind(&question.explanation)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Self::get_exam(db, deck_id, exam_id, user_id).await
+    }
+
+    /// The user's exams for a deck, newest first
+    pub async fn list_exams(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<Vec<DeckExam>> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+
+        let sql = format!(
+            "SELECT {} FROM deck_exams e WHERE e.deck_id = $1 AND e.user_id = $2 ORDER BY e.created_at DESC",
+            EXAM_COLUMNS
+        );
+        let exams = sqlx::query_as::<_, DeckExam>(&sql)
+            .bind(deck_id)
+            .bind(user_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(exams)
+    }
+
+    pub async fn get_exam(
+        db: &PgPool,
+        deck_id: Uuid,
+        exam_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<DeckExamWithQuestions> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+
+        let sql = format!(
+            "SELECT {} FROM deck_exams e WHERE e.id = $1 AND e.deck_id = $2 AND e.user_id = $3",
+            EXAM_COLUMNS
+        );
+        let exam = sqlx::query_as::<_, DeckExam>(&sql)
+            .bind(exam_id)
+            .bind(deck_id)
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+
+        let questions = sqlx::query_as::<_, ExamQuestion>(
+            r#"
+            SELECT id, card_id, position, question, options, correct_index, explanation
+            FROM deck_exam_questions
+            WHERE exam_id = $1
+            ORDER BY position
+            "#,
+        )
+        .bind(exam_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(DeckExamWithQuestions { exam, questions })
+    }
+}
+
+/// Trim a generated question and reject it unless it has a sensible number
+/// of distinct options and the answer points at one of them
+fn normalize_question(mut question: GeneratedQuizQuestion) -> Option<GeneratedQuizQuestion> {
+    question.question = question.question.trim().to_string();
+    question.options = question.options.iter().map(|o| o.trim().to_string()).collect();
+
+    let distinct: HashSet<String> = question.options.iter().map(|o| o.to_lowercase()).collect();
+    let valid = !question.question.is_empty()
+        && (MIN_OPTIONS..=MAX_OPTIONS).contains(&question.options.len())
+        && distinct.len() == question.options.len()
+        && !distinct.contains("")
+        && question.answer < question.options.len();
+
+    valid.then_some(question)
+}
@@ -0,0 +1,306 @@
+use chrono::{DateTime, Utc};
+use rand::seq::SliceRandom;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        DistractorSource, QuizAnswerDto, QuizAnswerResult, QuizQuestion, Rating, StudySession,
+    },
+    services::study::{StudyService, QUIZ_STUDY_MODE},
+    utils::{AppError, Result},
+};
+
+/// Wrong options shown next to the card's answer
+pub const DISTRACTOR_COUNT: usize = 3;
+
+#[derive(sqlx::FromRow)]
+struct QuestionRow {
+    id: Uuid,
+    session_id: Uuid,
+    card_id: Uuid,
+    front: String,
+    options: Vec<String>,
+    distractor_source: String,
+    created_at: DateTime<Utc>,
+}
+
+impl QuestionRow {
+    fn into_question(self) -> QuizQuestion {
+        let distractor_source = match self.distractor_source.as_str() {
+            "ai" => DistractorSource::Ai,
+            "mixed" => DistractorSource::Mixed,
+            _ => DistractorSource::Deck,
+        };
+        QuizQuestion {
+            question_id: self.id,
+            session_id: self.session_id,
+            card_id: self.card_id,
+            front: self.front,
+            options: self.options,
+            distractor_source,
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Quiz-mode study sessions: each card is asked as a multiple-choice
+/// question and graded by the server. Distractors are other answers from the
+/// same deck, topped up from AI-generated quizzes about the card when the
+/// deck has too few distinct answers.
+pub struct QuizSessionService;
+
+impl QuizSessionService {
+    /// The session's open question, or a new one for the next unanswered card
+    pub async fn next_question(db: &PgPool, session_id: Uuid, user_id: Uuid) -> Result<QuizQuestion> {
+        let session = Self::quiz_session(db, session_id, user_id).await?;
+
+        if let Some(open) = Self::open_question(db, session_id).await? {
+            return Ok(open);
+        }
+
+        let (card_id, back) = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT c.id, c.back FROM cards c
+            WHERE c.deck_id = $1 AND c.deleted_at IS NULL
+                AND NOT EXISTS(
+                    SELECT 1 FROM card_progress cp
+                    WHERE cp.session_id = $2 AND cp.card_id = c.id
+                )
+            ORDER BY c.position, c.id
+            LIMIT 1
+            "#,
+        )
+        .bind(session.deck_id)
+        .bind(session_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("No cards left in this quiz".to_string()))?;
+
+        let deck_answers = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT back FROM (
+                SELECT DISTINCT ON (lower(trim(back))) back
+                FROM cards
+                WHERE deck_id = $1 AND id <> $2 AND deleted_at IS NULL
+            ) answers
+            ORDER BY random()
+            LIMIT $3
+            "#,
+        )
+        .bind(session.deck_id)
+        .bind(card_id)
+        .bind(DISTRACTOR_COUNT as i64 * 2) // Spare rows for answers equal to this card's
+        .fetch_all(db)
+        .await?;
+
+        // Only consult AI quizzes when the deck alone cannot fill the options
+        let ai_answers = if distinct_distractors(&back, &deck_answers) < DISTRACTOR_COUNT {
+            Self::ai_distractors(db, card_id, user_id).await?
+        } else {
+            vec![]
+        };
+
+        let (options, correct_index, source) = build_options(&back, &deck_answers, &ai_answers)
+            .ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "A quiz needs {} other distinct answers for each card; add more cards or generate an AI quiz for this deck",
+                    DISTRACTOR_COUNT
+                ))
+            })?;
+
+        // A concurrent request may have opened a question first; the unique
+        // index keeps one, so fall back to whichever won
+        sqlx::query(
+            r#"
+            INSERT INTO study_quiz_questions (session_id, card_id, options, correct_index, distractor_source)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(session_id)
+        .bind(card_id)
+        .bind(&options)
+        .bind(correct_index as i32)
+        .bind(source.as_str())
+        .execute(db)
+        .await?;
+
+        Self::open_question(db, session_id)
+            .await?
+            .ok_or(AppError::InternalServerError)
+    }
+
+    /// Grade the open question and record the answer as the card's review:
+    /// a correct choice counts as "good", a wrong one as "again"
+    pub async fn answer(
+        db: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+        dto: QuizAnswerDto,
+    ) -> Result<QuizAnswerResult> {
+        let session = Self::quiz_session(db, session_id, user_id).await?;
+
+        // Claiming the question first means a double submit is graded once
+        let (card_id, options, correct_index) = sqlx::query_as::<_, (Uuid, Vec<String>, i32)>(
+            r#"
+            UPDATE study_quiz_questions
+            SET answered_at = NOW(), chosen_index = $3
+            WHERE id = $1 AND session_id = $2 AND answered_at IS NULL
+                AND $3 >= 0 AND $3 < cardinality(options)
+            RETURNING card_id, options, correct_index
+            "#,
+        )
+        .bind(dto.question_id)
+        .bind(session_id)
+        .bind(dto.answer_index)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest("Question is not open or the answer is out of range".to_string())
+        })?;
+
+        let is_correct = dto.answer_index == correct_index;
+        let rating = if is_correct { Rating::Good } else { Rating::Again };
+        let mut progress =
+            StudyService::record_answer(db, &session, card_id, rating, dto.response_time_ms).await?;
+
+        StudyService::set_answer(db, &mut progress, &options[dto.answer_index as usize], is_correct).await?;
+        sqlx::query("UPDATE study_quiz_questions SET progress_id = $2 WHERE id = $1")
+            .bind(dto.question_id)
+            .bind(progress.id)
+            .execute(db)
+            .await?;
+
+        Ok(QuizAnswerResult {
+            question_id: dto.question_id,
+            card_id,
+            is_correct,
+            answer_index: dto.answer_index,
+            correct_index,
+            correct_answer: options[correct_index as usize].clone(),
+            progress,
+            new_achievements: vec![],
+        })
+    }
+
+    // Helper functions
+
+    async fn quiz_session(db: &PgPool, session_id: Uuid, user_id: Uuid) -> Result<StudySession> {
+        let session = StudyService::get_study_session(db, session_id, user_id).await?;
+
+        if session.study_mode != QUIZ_STUDY_MODE {
+            return Err(AppError::BadRequest(format!(
+                "Session is not a quiz; create it with study_mode \"{}\"",
+                QUIZ_STUDY_MODE
+            )));
+        }
+        if session.completed_at.is_some() {
+            return Err(AppError::BadRequest("Session is already completed".to_string()));
+        }
+        StudyService::ensure_accepting_answers(&session)?;
+
+        Ok(session)
+    }
+
+    async fn open_question(db: &PgPool, session_id: Uuid) -> Result<Option<QuizQuestion>> {
+        let row = sqlx::query_as::<_, QuestionRow>(
+            r#"
+            SELECT q.id, q.session_id, q.card_id, c.front, q.options, q.distractor_source, q.created_at
+            FROM study_quiz_questions q
+            JOIN cards c ON c.id = q.card_id
+            WHERE q.session_id = $1 AND q.answered_at IS NULL
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.map(QuestionRow::into_question))
+    }
+
+    /// Wrong options from the user's most recent AI quiz question on the card
+    async fn ai_distractors(db: &PgPool, card_id: Uuid, user_id: Uuid) -> Result<Vec<String>> {
+        let question = sqlx::query_as::<_, (Vec<String>, i32)>(
+            r#"
+            SELECT q.options, q.correct_index
+            FROM deck_exam_questions q
+            JOIN deck_exams e ON e.id = q.exam_id
+            WHERE q.card_id = $1 AND e.user_id = $2
+            ORDER BY e.created_at DESC, q.position
+            LIMIT 1
+            "#,
+        )
+        .bind(card_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(question
+            .map(|(options, correct_index)| {
+                options
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i as i32 != correct_index)
+                    .map(|(_, option)| option)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+/// Shuffle the correct answer in among up to [`DISTRACTOR_COUNT`]
+/// distractors, preferring deck answers. Candidates equal to the answer or
+/// to each other (ignoring case and surrounding whitespace) are skipped.
+/// Returns the options, the index of the correct one and where the
+/// distractors came from, or `None` when there are too few candidates.
+pub fn build_options(
+    correct: &str,
+    deck_answers: &[String],
+    ai_answers: &[String],
+) -> Option<(Vec<String>, usize, DistractorSource)> {
+    let mut seen = HashSet::from([normalize(correct)]);
+    let mut pick = |candidates: &[String], limit: usize| -> Vec<String> {
+        candidates
+            .iter()
+            .filter(|candidate| !candidate.trim().is_empty() && seen.insert(normalize(candidate)))
+            .take(limit)
+            .cloned()
+            .collect()
+    };
+
+    let mut distractors = pick(deck_answers, DISTRACTOR_COUNT);
+    let from_deck = distractors.len();
+    distractors.extend(pick(ai_answers, DISTRACTOR_COUNT - from_deck));
+    if distractors.len() < DISTRACTOR_COUNT {
+        return None;
+    }
+
+    let source = match from_deck {
+        DISTRACTOR_COUNT => DistractorSource::Deck,
+        0 => DistractorSource::Ai,
+        _ => DistractorSource::Mixed,
+    };
+
+    let mut options = distractors;
+    options.push(correct.to_string());
+    options.shuffle(&mut rand::thread_rng());
+    let correct_index = options.iter().position(|option| option == correct)?;
+
+    Some((options, correct_index, source))
+}
+
+fn distinct_distractors(correct: &str, answers: &[String]) -> usize {
+    let correct = normalize(correct);
+    answers
+        .iter()
+        .map(|answer| normalize(answer))
+        .filter(|answer| !answer.is_empty() && *answer != correct)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase()
+}
@@ -0,0 +1,132 @@
+//! Quizlet imports.
+//!
+//! Quizlet exports a set as delimited text: each row is a term and its
+//! definition, with a separator between the two and another between rows,
+//! both configurable (tab / comma / custom and newline / semicolon / custom).
+//! Their API returns sets as JSON instead. Both are parsed into
+//! [`QuizletSet`]s here; each set becomes one deck.
+
+use crate::models::import_export::{QuizletSet, QuizletTerm, TextDelimiters};
+
+/// Term separators tried when none is given, in order of preference
+pub const TERM_DELIMITERS: &[&str] = &["\t", " - ", ",", ":"];
+/// Row separators tried when none is given, in order of preference
+pub const ROW_DELIMITERS: &[&str] = &["\n", ";"];
+
+/// Title for a text export, which does not carry the set's name
+pub const TEXT_SET_TITLE: &str = "Imported from Quizlet";
+
+#[derive(Debug)]
+pub struct ParsedQuizlet {
+    pub sets: Vec<QuizletSet>,
+    /// Text rows without both a term and a definition
+    pub skipped_rows: usize,
+}
+
+/// Parse a Quizlet text export or API JSON. Delimiters only apply to text
+/// and are detected when not given.
+pub fn parse(data: &[u8], delimiters: &TextDelimiters) -> Result<ParsedQuizlet, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "Invalid UTF-8 encoding".to_string())?;
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+
+    match text.trim_start().chars().next() {
+        Some('{') => {
+            let set: QuizletSet =
+                serde_json::from_str(&text).map_err(|e| format!("Invalid Quizlet JSON: {}", e))?;
+            Ok(ParsedQuizlet {
+                sets: vec![sorted(set)],
+                skipped_rows: 0,
+            })
+        }
+        Some('[') => {
+            let sets: Vec<QuizletSet> =
+                serde_json::from_str(&text).map_err(|e| format!("Invalid Quizlet JSON: {}", e))?;
+            Ok(ParsedQuizlet {
+                sets: sets.into_iter().map(sorted).collect(),
+                skipped_rows: 0,
+            })
+        }
+        _ => parse_text(&text, delimiters),
+    }
+}
+
+fn parse_text(text: &str, delimiters: &TextDelimiters) -> Result<ParsedQuizlet, String> {
+    let given_term = delimiters.term.as_deref().map(unescape_delimiter);
+    let given_row = delimiters.row.as_deref().map(unescape_delimiter);
+    if given_term.as_deref() == Some("") || given_row.as_deref() == Some("") {
+        return Err("Delimiters cannot be empty".to_string());
+    }
+
+    let (term_delimiter, row_delimiter) =
+        detect_delimiters(text, given_term.as_deref(), given_row.as_deref());
+    if term_delimiter == row_delimiter {
+        return Err("Term and row delimiters must differ".to_string());
+    }
+
+    let mut terms = Vec::new();
+    let mut skipped_rows = 0;
+    for row in text.split(row_delimiter).map(str::trim).filter(|row| !row.is_empty()) {
+        match row.split_once(term_delimiter) {
+            Some((term, definition)) if !term.trim().is_empty() && !definition.trim().is_empty() => {
+                terms.push(QuizletTerm {
+                    term: term.trim().to_string(),
+                    definition: definition.trim().to_string(),
+                    rank: None,
+                });
+            }
+            _ => skipped_rows += 1,
+        }
+    }
+
+    Ok(ParsedQuizlet {
+        sets: vec![QuizletSet {
+            title: TEXT_SET_TITLE.to_string(),
+            description: None,
+            terms,
+        }],
+        skipped_rows,
+    })
+}
+
+/// Pick the term and row separators that split the most rows into a term
+/// and a definition, preferring earlier candidates on ties. A separator
+/// that is already known is kept fixed.
+pub fn detect_delimiters<'a>(
+    text: &str,
+    term: Option<&'a str>,
+    row: Option<&'a str>,
+) -> (&'a str, &'a str) {
+    let terms: Vec<&str> = term.map(|t| vec![t]).unwrap_or_else(|| TERM_DELIMITERS.to_vec());
+    let rows: Vec<&str> = row.map(|r| vec![r]).unwrap_or_else(|| ROW_DELIMITERS.to_vec());
+
+    let mut best = (terms[0], rows[0]);
+    let mut best_matched = 0;
+    for &row_delimiter in &rows {
+        for &term_delimiter in &terms {
+            if term_delimiter == row_delimiter {
+                continue;
+            }
+            let matched = text
+                .split(row_delimiter)
+                .map(str::trim)
+                .filter(|row| !row.is_empty() && row.contains(term_delimiter))
+                .count();
+            if matched > best_matched {
+                best = (term_delimiter, row_delimiter);
+                best_matched = matched;
+            }
+        }
+    }
+    best
+}
+
+/// Delimiters typed into a form arrive escaped: `\t` is a tab and `\n` a newline
+pub fn unescape_delimiter(delimiter: &str) -> String {
+    delimiter.replace("\\t", "\t").replace("\\n", "\n")
+}
+
+// The API returns terms with their position in the set
+fn sorted(mut set: QuizletSet) -> QuizletSet {
+    set.terms.sort_by_key(|term| term.rank.unwrap_or(i32::MAX));
+    set
+}
@@ -0,0 +1,435 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::ai::{AiRecommendation, RecommendationFeedbackDto, RecommendationPayload},
+    services::ai_privacy::AiPrivacyService,
+    utils::{AppError, Result},
+};
+
+/// Recommendations are regenerated when the newest is older than this
+const REFRESH_HOURS: i32 = 6;
+
+/// Study events further back than this are not analyzed
+pub(crate) const ANALYSIS_DAYS: i32 = 30;
+
+/// Answers needed before time-of-day patterns mean anything
+const MIN_PATTERN_ANSWERS: i64 = 50;
+
+/// Answers needed in a two-hour window for it to be recommended
+const MIN_WINDOW_ANSWERS: i64 = 15;
+
+/// How much better than overall a window has to be
+const MIN_ACCURACY_LIFT: f64 = 0.05;
+
+/// Answers needed before a tag can be called weak
+const MIN_TAG_ANSWERS: i64 = 20;
+
+/// Tags answered correctly less often than this are weak
+const WEAK_TAG_ACCURACY: f64 = 0.7;
+
+const MAX_WEAK_TAGS: usize = 2;
+const MAX_OVERDUE_DECKS: i64 = 3;
+
+/// A recommendation marked not helpful is not made again for this long
+const DISMISSED_DAYS: i32 = 14;
+
+/// The two-hour window of the day, in the user's timezone, in which they
+/// answer best
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StudyWindow {
+    pub start_hour: u32,
+    pub answers: i64,
+    pub accuracy: f64,
+    pub overall_accuracy: f64,
+}
+
+/// A recommendation before it is stored
+struct Candidate {
+    kind: &'static str,
+    payload: RecommendationPayload,
+    confidence: f64,
+    expires_in_hours: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct OverdueDeck {
+    id: Uuid,
+    title: String,
+    overdue: i64,
+    days_overdue: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct TagAccuracy {
+    tag: String,
+    answers: i64,
+    correct: i64,
+    deck_ids: Vec<Uuid>,
+}
+
+/// Study recommendations drawn from the user's study events and schedule:
+/// when they study best, decks with overdue reviews and deck tags they
+/// keep getting wrong
+pub struct RecommendationService;
+
+impl RecommendationService {
+    /// The user's current recommendations, most confident first. Marks them
+    /// as shown and regenerates them when they are stale. Empty while the
+    /// user has recommendations turned off.
+    pub async fn list(db: &PgPool, user_id: Uuid) -> Result<Vec<AiRecommendation>> {
+        if !AiPrivacyService::allows_recommendations(db, user_id).await? {
+            return Ok(Vec::new());
+        }
+
+        let fresh = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM ai_recommendations
+                WHERE user_id = $1 AND created_at > NOW() - make_interval(hours => $2)
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(REFRESH_HOURS)
+        .fetch_one(db)
+        .await?;
+        if !fresh {
+            Self::refresh(db, user_id).await?;
+        }
+
+        let mut recommendations = sqlx::query_as::<_, AiRecommendation>(
+            r#"
+            UPDATE ai_recommendations
+            SET shown_at = COALESCE(shown_at, NOW())
+            WHERE user_id = $1 AND feedback IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        recommendations.sort_by(|a, b| {
+            b.confidence_score
+                .partial_cmp(&a.confidence_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.created_at.cmp(&b.created_at))
+        });
+
+        Ok(recommendations)
+    }
+
+    /// Analyze the user's history and replace their pending recommendations
+    pub async fn refresh(db: &PgPool, user_id: Uuid) -> Result<()> {
+        let (timezone, hours) = answers_by_hour(db, user_id).await?;
+
+        let mut candidates = Vec::new();
+        if let Some(window) = best_study_window(&hours) {
+            candidates.push(study_time_candidate(&window, &timezone));
+        }
+        candidates.extend(Self::overdue_candidates(db, user_id).await?);
+        candidates.extend(Self::weak_tag_candidates(db, user_id, overall_accuracy(&hours)).await?);
+
+        let mut tx = db.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE ai_recommendations SET expires_at = NOW()
+            WHERE user_id = $1 AND feedback IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        for candidate in candidates {
+            let action_data = candidate.payload.action_data.clone();
+            sqlx::query(
+                r#"
+                INSERT INTO ai_recommendations (user_id, recommendation_type, payload, confidence_score, expires_at)
+                SELECT $1, $2, $3, $4, NOW() + make_interval(hours => $5)
+                WHERE NOT EXISTS(
+                    SELECT 1 FROM ai_recommendations
+                    WHERE user_id = $1 AND recommendation_type = $2
+                        AND payload->'action_data' = $6 AND feedback = 'not_helpful'
+                        AND created_at > NOW() - make_interval(days => $7)
+                )
+                "#,
+            )
+            .bind(user_id)
+            .bind(candidate.kind)
+            .bind(json!(candidate.payload))
+            .bind(candidate.confidence as f32)
+            .bind(candidate.expires_in_hours)
+            .bind(action_data)
+            .bind(DISMISSED_DAYS)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn record_feedback(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: RecommendationFeedbackDto,
+    ) -> Result<AiRecommendation> {
+        if !matches!(dto.feedback.as_str(), "helpful" | "not_helpful" | "ignored") {
+            return Err(AppError::ValidationError(
+                "feedback must be helpful, not_helpful or ignored".to_string(),
+            ));
+        }
+
+        sqlx::query_as::<_, AiRecommendation>(
+            r#"
+            UPDATE ai_recommendations
+            SET feedback = $3, accepted = $4, shown_at = COALESCE(shown_at, NOW())
+            WHERE id = $1 AND user_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(dto.recommendation_id)
+        .bind(user_id)
+        .bind(&dto.feedback)
+        .bind(dto.accepted)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Recommendation not found".to_string()))
+    }
+
+    /// Decks with reviews more than a day overdue, most overdue cards first
+    async fn overdue_candidates(db: &PgPool, user_id: Uuid) -> Result<Vec<Candidate>> {
+        let decks = sqlx::query_as::<_, OverdueDeck>(
+            r#"
+            SELECT d.id, d.title, COUNT(*) as overdue,
+                   EXTRACT(DAY FROM NOW() - MIN(s.next_review_at))::INTEGER as days_overdue
+            FROM user_card_stats s
+            JOIN cards c ON c.id = s.card_id AND c.deleted_at IS NULL
+            JOIN decks d ON d.id = c.deck_id AND d.deleted_at IS NULL
+            LEFT JOIN user_card_flags f ON f.card_id = c.id AND f.user_id = $1
+            WHERE s.user_id = $1 AND s.times_seen > 0 AND NOT COALESCE(f.suspended, false)
+                AND s.next_review_at < NOW() - INTERVAL '1 day'
+            GROUP BY d.id, d.title
+            ORDER BY overdue DESC, d.title
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(MAX_OVERDUE_DECKS)
+        .fetch_all(db)
+        .await?;
+
+        Ok(decks
+            .into_iter()
+            .map(|deck| Candidate {
+                kind: "review_schedule",
+                payload: RecommendationPayload {
+                    title: format!("Catch up on {}", deck.title),
+                    description: format!(
+                        "{} {} in '{}' {} overdue, the oldest by {} days",
+                        deck.overdue,
+                        if deck.overdue == 1 { "card" } else { "cards" },
+                        deck.title,
+                        if deck.overdue == 1 { "is" } else { "are" },
+                        deck.days_overdue
+                    ),
+                    action_type: "study_deck".to_string(),
+                    action_data: json!({ "deck_id": deck.id }),
+                    reason: Some("Reviews done late are more likely to be forgotten".to_string()),
+                    metrics: Some(json!({ "overdue_cards": deck.overdue, "days_overdue": deck.days_overdue })),
+                },
+                // More overdue cards, more reason to catch up
+                confidence: (0.6 + deck.overdue as f64 / 100.0).min(0.95),
+                expires_in_hours: 24,
+            })
+            .collect())
+    }
+
+    /// Deck tags the user answers correctly less often than usual
+    async fn weak_tag_candidates(
+        db: &PgPool,
+        user_id: Uuid,
+        overall_accuracy: Option<f64>,
+    ) -> Result<Vec<Candidate>> {
+        let Some(overall) = overall_accuracy else {
+            return Ok(Vec::new());
+        };
+
+        let tags = sqlx::query_as::<_, TagAccuracy>(
+            r#"
+            SELECT tag, COUNT(*) as answers, COUNT(*) FILTER (WHERE e.outcome = 'correct') as correct,
+                   ARRAY_AGG(DISTINCT d.id) as deck_ids
+            FROM study_events e
+            JOIN decks d ON d.id = e.deck_id AND d.deleted_at IS NULL
+            CROSS JOIN LATERAL UNNEST(d.tags) as tag
+            WHERE e.user_id = $1 AND e.outcome IN ('correct', 'incorrect', 'partial')
+                AND COALESCE(e.occurred_at, e.created_at) > NOW() - make_interval(days => $2)
+            GROUP BY tag
+            HAVING COUNT(*) >= $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(ANALYSIS_DAYS)
+        .bind(MIN_TAG_ANSWERS)
+        .fetch_all(db)
+        .await?;
+
+        let mut weak: Vec<(TagAccuracy, f64)> = tags
+            .into_iter()
+            .map(|t| {
+                let accuracy = t.correct as f64 / t.answers as f64;
+                (t, accuracy)
+            })
+            .filter(|(_, accuracy)| *accuracy < WEAK_TAG_ACCURACY && *accuracy < overall)
+            .collect();
+        weak.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(weak
+            .into_iter()
+            .take(MAX_WEAK_TAGS)
+            .map(|(tag, accuracy)| Candidate {
+                kind: "deck_suggestion",
+                payload: RecommendationPayload {
+                    title: format!("Focus on {}", tag.tag),
+                    description: format!(
+                        "You answer {:.0}% of '{}' cards correctly, compared with {:.0}% overall",
+                        accuracy * 100.0,
+                        tag.tag,
+                        overall * 100.0
+                    ),
+                    action_type: "study_tag".to_string(),
+                    action_data: json!({ "tag": tag.tag, "deck_ids": tag.deck_ids }),
+                    reason: Some("Extra practice on weak topics lifts overall retention".to_string()),
+                    metrics: Some(json!({
+                        "answers": tag.answers,
+                        "accuracy": accuracy,
+                        "overall_accuracy": overall,
+                    })),
+                },
+                confidence: confidence(overall - accuracy, tag.answers),
+                expires_in_hours: 72,
+            })
+            .collect())
+    }
+}
+
+/// The user's reminder timezone (UTC when unset) and their recent answers
+/// by hour of day in it, as (hour, answers, correct answers)
+pub(crate) async fn answers_by_hour(db: &PgPool, user_id: Uuid) -> Result<(String, Vec<(i32, i64, i64)>)> {
+    let timezone = sqlx::query_scalar::<_, String>(
+        "SELECT timezone FROM notification_preferences WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .unwrap_or_else(|| "UTC".to_string());
+
+    let hours = sqlx::query_as::<_, (i32, i64, i64)>(
+        r#"
+        SELECT EXTRACT(HOUR FROM COALESCE(occurred_at, created_at) AT TIME ZONE $2)::INTEGER as hour,
+               COUNT(*), COUNT(*) FILTER (WHERE outcome = 'correct')
+        FROM study_events
+        WHERE user_id = $1 AND outcome IN ('correct', 'incorrect', 'partial')
+            AND COALESCE(occurred_at, created_at) > NOW() - make_interval(days => $3)
+        GROUP BY hour
+        "#,
+    )
+    .bind(user_id)
+    .bind(&timezone)
+    .bind(ANALYSIS_DAYS)
+    .fetch_all(db)
+    .await?;
+
+    Ok((timezone, hours))
+}
+
+/// The best two-hour window of `hours`, given as (hour of day, answers,
+/// correct answers). None when there are too few answers or no window is
+/// clearly better than the rest of the day.
+pub fn best_study_window(hours: &[(i32, i64, i64)]) -> Option<StudyWindow> {
+    let mut answers = [0i64; 24];
+    let mut correct = [0i64; 24];
+    for &(hour, count, right) in hours {
+        if let Ok(hour) = usize::try_from(hour) {
+            if hour < 24 {
+                answers[hour] += count;
+                correct[hour] += right;
+            }
+        }
+    }
+
+    let total: i64 = answers.iter().sum();
+    if total < MIN_PATTERN_ANSWERS {
+        return None;
+    }
+    let overall_accuracy = correct.iter().sum::<i64>() as f64 / total as f64;
+
+    let mut best: Option<StudyWindow> = None;
+    for start in 0..24 {
+        let next = (start + 1) % 24;
+        let window_answers = answers[start] + answers[next];
+        if window_answers < MIN_WINDOW_ANSWERS {
+            continue;
+        }
+        let accuracy = (correct[start] + correct[next]) as f64 / window_answers as f64;
+        let better = best.as_ref().map_or(true, |b| {
+            accuracy > b.accuracy || (accuracy == b.accuracy && window_answers > b.answers)
+        });
+        if better {
+            best = Some(StudyWindow {
+                start_hour: start as u32,
+                answers: window_answers,
+                accuracy,
+                overall_accuracy,
+            });
+        }
+    }
+
+    best.filter(|w| w.accuracy - w.overall_accuracy >= MIN_ACCURACY_LIFT)
+}
+
+pub(crate) fn overall_accuracy(hours: &[(i32, i64, i64)]) -> Option<f64> {
+    let answers: i64 = hours.iter().map(|h| h.1).sum();
+    let correct: i64 = hours.iter().map(|h| h.2).sum();
+    (answers >= MIN_PATTERN_ANSWERS).then(|| correct as f64 / answers as f64)
+}
+
+fn study_time_candidate(window: &StudyWindow, timezone: &str) -> Candidate {
+    let end_hour = (window.start_hour + 2) % 24;
+    Candidate {
+        kind: "study_time",
+        payload: RecommendationPayload {
+            title: "Your best study time".to_string(),
+            description: format!(
+                "You answer {:.0}% of cards correctly between {:02}:00 and {:02}:00 ({}), compared with {:.0}% overall",
+                window.accuracy * 100.0,
+                window.start_hour,
+                end_hour,
+                timezone,
+                window.overall_accuracy * 100.0
+            ),
+            action_type: "schedule_study".to_string(),
+            action_data: json!({ "start_hour": window.start_hour, "end_hour": end_hour, "timezone": timezone }),
+            reason: Some(format!("Based on your answers over the last {} days", ANALYSIS_DAYS)),
+            metrics: Some(json!({
+                "answers": window.answers,
+                "accuracy": window.accuracy,
+                "overall_accuracy": window.overall_accuracy,
+            })),
+        },
+        confidence: confidence(window.accuracy - window.overall_accuracy, window.answers),
+        expires_in_hours: 7 * 24,
+    }
+}
+
+/// Larger gaps backed by more answers are more trustworthy
+pub(crate) fn confidence(gap: f64, answers: i64) -> f64 {
+    let sample = (answers as f64 / 100.0).min(1.0);
+    ((0.5 + gap * 2.0).min(0.95) * (0.5 + sample / 2.0)).clamp(0.1, 0.95)
+}
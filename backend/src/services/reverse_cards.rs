@@ -0,0 +1,110 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{models::Card, services::deck_settings::DeckSettingsService, utils::Result};
+
+/// Reverse siblings (back -> front) for decks with `generate_reverse_cards`
+/// on. A reverse card is an ordinary card that points at its original
+/// through `reverse_of`; it is studied and scheduled on its own, and the
+/// study queue buries one sibling for the day once the other is answered.
+pub struct ReverseCardService;
+
+impl ReverseCardService {
+    /// Create reverses for the deck's cards that have none yet, if the deck
+    /// keeps reverse cards. Returns how many were created.
+    pub async fn generate_if_enabled(db: &PgPool, deck_id: Uuid) -> Result<u64> {
+        let settings = DeckSettingsService::settings_of(db, deck_id).await?;
+        if !settings.generate_reverse_cards {
+            return Ok(0);
+        }
+        Self::generate_missing(db, deck_id).await
+    }
+
+    /// The setting was turned on: bring back the reverses trashed when it
+    /// was turned off, up to date with their originals, and create the rest
+    pub async fn enable(db: &PgPool, deck_id: Uuid) -> Result<u64> {
+        let restored = sqlx::query(
+            r#"
+            UPDATE cards r
+            SET deleted_at = NULL, front = o.back, back = o.front
+            FROM cards o
+            WHERE r.reverse_of = o.id AND o.deck_id = $1
+              AND o.deleted_at IS NULL AND r.deleted_at IS NOT NULL
+            "#,
+        )
+        .bind(deck_id)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        Ok(restored + Self::generate_missing(db, deck_id).await?)
+    }
+
+    /// The setting was turned off: move the deck's reverse cards to the
+    /// trash. Their study history stays in case the setting comes back.
+    pub async fn disable(db: &PgPool, deck_id: Uuid) -> Result<u64> {
+        let trashed = sqlx::query(
+            "UPDATE cards SET deleted_at = NOW() WHERE deck_id = $1 AND reverse_of IS NOT NULL AND deleted_at IS NULL",
+        )
+        .bind(deck_id)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        Ok(trashed)
+    }
+
+    /// Copy an edited card's sides, swapped, onto its sibling
+    pub async fn sync_sibling(db: &PgPool, card: &Card) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE cards s
+            SET front = $3, back = $2
+            FROM cards c
+            WHERE c.id = $1 AND s.id <> c.id AND s.deleted_at IS NULL
+              AND (s.reverse_of = c.id OR s.id = c.reverse_of)
+              AND (s.front <> $3 OR s.back <> $2)
+            "#,
+        )
+        .bind(card.id)
+        .bind(&card.front)
+        .bind(&card.back)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Trash the reverse of a card that was moved to the trash
+    pub async fn delete_reverse(db: &PgPool, card_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE cards SET deleted_at = NOW() WHERE reverse_of = $1 AND deleted_at IS NULL")
+            .bind(card_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    // Reverses go after the deck's existing cards, in the order of their
+    // originals. A card whose reverse is in the trash does not get another.
+    async fn generate_missing(db: &PgPool, deck_id: Uuid) -> Result<u64> {
+        let created = sqlx::query(
+            r#"
+            INSERT INTO cards (deck_id, front, back, position, reverse_of)
+            SELECT o.deck_id, o.back, o.front,
+                   ((SELECT COALESCE(MAX(position), -1) FROM cards WHERE deck_id = $1)
+                       + ROW_NUMBER() OVER (ORDER BY o.position, o.created_at))::INT,
+                   o.id
+            FROM cards o
+            WHERE o.deck_id = $1 AND o.deleted_at IS NULL AND o.reverse_of IS NULL
+              AND NOT EXISTS(SELECT 1 FROM cards r WHERE r.reverse_of = o.id)
+            "#,
+        )
+        .bind(deck_id)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        Ok(created)
+    }
+}
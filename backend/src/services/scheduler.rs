@@ -0,0 +1,143 @@
+use chrono::{Duration, Utc};
+
+use crate::models::{
+    ai::{SpacedRepetitionParams, SpacedRepetitionResult},
+    CardStatus, DeckSettings, LeitnerDemotion, LeitnerPromotion, Rating, SchedulingAlgorithm,
+};
+
+pub const DEFAULT_EASE_FACTOR: f32 = 2.5;
+pub const MIN_EASE_FACTOR: f32 = 1.3;
+
+/// Schedule a review with the deck's algorithm, on the SM-2 0-5 quality
+/// scale whatever the algorithm
+pub fn schedule(settings: &DeckSettings, params: &SpacedRepetitionParams) -> SpacedRepetitionResult {
+    match settings.algorithm {
+        SchedulingAlgorithm::Sm2 => Sm2Scheduler::schedule(params),
+        SchedulingAlgorithm::Leitner => LeitnerScheduler::from_settings(settings).schedule(params),
+    }
+}
+
+/// SM-2 spaced repetition scheduler
+pub struct Sm2Scheduler;
+
+impl Sm2Scheduler {
+    /// Map an answer rating onto the SM-2 0-5 quality scale. Every rating but
+    /// `Again` is a pass (quality 3 or more), whatever scale the deck uses.
+    pub fn quality_for_rating(rating: Rating) -> i32 {
+        match rating {
+            Rating::Easy => 5,
+            Rating::Good => 4,
+            Rating::Hard => 3,
+            Rating::Again => 1,
+        }
+    }
+
+    /// Map a legacy card status onto the SM-2 0-5 quality scale
+    pub fn quality_for_status(status: CardStatus) -> i32 {
+        Self::quality_for_rating(Rating::from_status(status))
+    }
+
+    pub fn schedule(params: &SpacedRepetitionParams) -> SpacedRepetitionResult {
+        let quality = params.quality.clamp(0, 5);
+        let q = (5 - quality) as f32;
+
+        let (next_repetitions, next_interval) = if quality < 3 {
+            // Failed recall restarts the repetition sequence
+            (0, 1)
+        } else {
+            let repetitions = params.repetitions + 1;
+            let interval = match repetitions {
+                1 => 1,
+                2 => 6,
+                _ => ((params.interval.max(1) as f32) * params.ease_factor).round() as i32,
+            };
+            (repetitions, interval)
+        };
+
+        let next_ease_factor =
+            (params.ease_factor + 0.1 - q * (0.08 + q * 0.02)).max(MIN_EASE_FACTOR);
+
+        SpacedRepetitionResult {
+            next_interval,
+            next_ease_factor,
+            next_repetitions,
+            // Boxes are only tracked while the deck uses Leitner; switching
+            // back places cards by their interval again
+            next_leitner_box: None,
+            next_review_date: Utc::now() + Duration::days(next_interval as i64),
+            difficulty_adjustment: next_ease_factor - params.ease_factor,
+        }
+    }
+}
+
+/// Leitner box scheduler. Box `n` (from 1) is reviewed every 2^(n-1) days;
+/// box 0 holds cards not yet recalled, reviewed the next day. Ease factors
+/// are left untouched, and `repetitions` counts consecutive recalls so lapses
+/// are detected as with SM-2.
+#[derive(Debug, Clone, Copy)]
+pub struct LeitnerScheduler {
+    pub boxes: i32,
+    pub promotion: LeitnerPromotion,
+    pub demotion: LeitnerDemotion,
+}
+
+impl LeitnerScheduler {
+    pub fn from_settings(settings: &DeckSettings) -> Self {
+        Self {
+            boxes: settings.leitner_boxes.max(1),
+            promotion: settings.leitner_promotion,
+            demotion: settings.leitner_demotion,
+        }
+    }
+
+    /// Days between reviews of cards in `leitner_box`
+    pub fn interval_for(&self, leitner_box: i32) -> i32 {
+        match leitner_box.clamp(0, self.boxes) {
+            0 => 1,
+            n => 1 << (n - 1),
+        }
+    }
+
+    /// Box for a card scheduled by SM-2 until now: the highest one whose
+    /// interval does not exceed the card's current interval
+    pub fn box_for_interval(&self, interval: i32) -> i32 {
+        (1..=self.boxes)
+            .take_while(|&n| self.interval_for(n) <= interval)
+            .last()
+            .unwrap_or(0)
+    }
+
+    pub fn schedule(&self, params: &SpacedRepetitionParams) -> SpacedRepetitionResult {
+        let quality = params.quality.clamp(0, 5);
+        let current_box = match params.leitner_box {
+            Some(leitner_box) => leitner_box.clamp(0, self.boxes),
+            None => self.box_for_interval(params.interval),
+        };
+
+        let next_box = match quality {
+            0..=2 => match self.demotion {
+                LeitnerDemotion::FirstBox => 0,
+                LeitnerDemotion::PreviousBox => (current_box - 1).max(0),
+            },
+            // A hard recall keeps the card where it is, once it is in a box
+            3 => current_box.max(1),
+            4 => current_box + 1,
+            _ => match self.promotion {
+                LeitnerPromotion::Step => current_box + 1,
+                LeitnerPromotion::SkipOnEasy => current_box + 2,
+            },
+        }
+        .min(self.boxes);
+        let next_interval = self.interval_for(next_box);
+        let next_repetitions = if quality >= 3 { params.repetitions + 1 } else { 0 };
+
+        SpacedRepetitionResult {
+            next_interval,
+            next_ease_factor: params.ease_factor,
+            next_repetitions,
+            next_leitner_box: Some(next_box),
+            next_review_date: Utc::now() + Duration::days(next_interval as i64),
+            difficulty_adjustment: 0.0,
+        }
+    }
+}
@@ -2,14 +2,43 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
-    handlers::search::CardSearchResult,
+    handlers::search::{CardSearchResult, SearchSuggestion},
     models::{Card, Deck, DeckWithStats},
     utils::{PaginatedResponse, PaginationParams, Result},
 };
 
+const DEFAULT_SUGGESTIONS: i64 = 8;
+const MAX_SUGGESTIONS: i64 = 20;
+
+/// Longest term used for suggestions, in characters; the rest is ignored
+const MAX_SUGGEST_CHARS: usize = 100;
+
+/// Indexes behind deck, folder and suggestion searches
+const SEARCH_INDEXES: &[&str] = &["idx_decks_title_trgm", "idx_folders_name_trgm"];
+
+/// Tables whose planner statistics searches depend on
+const SEARCH_TABLES: &[&str] = &["decks", "folders", "cards"];
+
 pub struct SearchService;
 
 impl SearchService {
+    /// Rebuild the search indexes without blocking writes and refresh
+    /// planner statistics, e.g. after a bulk import. Returns the indexes
+    /// rebuilt.
+    pub async fn reindex(db: &PgPool) -> Result<Vec<&'static str>> {
+        for index in SEARCH_INDEXES {
+            // CONCURRENTLY cannot run in a transaction, so each is its own statement
+            sqlx::query(&format!("REINDEX INDEX CONCURRENTLY {}", index))
+                .execute(db)
+                .await?;
+        }
+        for table in SEARCH_TABLES {
+            sqlx::query(&format!("ANALYZE {}", table)).execute(db).await?;
+        }
+
+        Ok(SEARCH_INDEXES.to_vec())
+    }
+
     /// Search decks by name or description
     pub async fn search_decks(
         db: &PgPool,
@@ -28,16 +57,19 @@ impl SearchService {
                 d.title as name,
                 d.description,
                 d.is_public,
+                d.front_language,
+                d.back_language,
                 d.created_at,
                 d.updated_at,
-                COUNT(c.id) as "card_count!",
-                MAX(ss.started_at) as last_studied
+                d.is_encrypted,
+                d.cover_storage_key,
+                d.card_count::BIGINT as "card_count!",
+                (SELECT MAX(ss.started_at) FROM study_sessions ss
+                 WHERE ss.deck_id = d.id AND ss.user_id = $1) as last_studied
             FROM decks d
-            LEFT JOIN cards c ON c.deck_id = d.id
-            LEFT JOIN study_sessions ss ON ss.deck_id = d.id AND ss.user_id = $1
             WHERE (d.owner_id = $1 OR d.is_public = true)
+              AND d.deleted_at IS NULL
               AND (LOWER(d.title) LIKE LOWER($2) OR LOWER(d.description) LIKE LOWER($2))
-            GROUP BY d.id
             ORDER BY 
                 CASE WHEN LOWER(d.title) LIKE LOWER($2) THEN 0 ELSE 1 END,
                 d.title
@@ -58,11 +90,16 @@ impl SearchService {
                 name: r.name,
                 description: r.description,
                 is_public: r.is_public,
+                front_language: r.front_language,
+                back_language: r.back_language,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             },
             card_count: r.card_count,
             last_studied: r.last_studied,
+            is_encrypted: r.is_encrypted,
+            cover_url: None,
+            cover_storage_key: r.cover_storage_key,
         })
         .collect();
 
@@ -89,16 +126,19 @@ impl SearchService {
                 d.title as name,
                 d.description,
                 d.is_public,
+                d.front_language,
+                d.back_language,
                 d.created_at,
                 d.updated_at,
-                COUNT(c.id) as "card_count!",
-                MAX(ss.started_at) as last_studied
+                d.is_encrypted,
+                d.cover_storage_key,
+                d.card_count::BIGINT as "card_count!",
+                (SELECT MAX(ss.started_at) FROM study_sessions ss
+                 WHERE ss.deck_id = d.id AND ss.user_id = $1) as last_studied
             FROM decks d
-            LEFT JOIN cards c ON c.deck_id = d.id
-            LEFT JOIN study_sessions ss ON ss.deck_id = d.id AND ss.user_id = $1
             WHERE (d.owner_id = $1 OR d.is_public = true)
+              AND d.deleted_at IS NULL
               AND (LOWER(d.title) LIKE LOWER($2) OR LOWER(d.description) LIKE LOWER($2))
-            GROUP BY d.id
             ORDER BY 
                 CASE WHEN LOWER(d.title) LIKE LOWER($2) THEN 0 ELSE 1 END,
                 d.title
@@ -120,11 +160,16 @@ impl SearchService {
                 name: r.name,
                 description: r.description,
                 is_public: r.is_public,
+                front_language: r.front_language,
+                back_language: r.back_language,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             },
             card_count: r.card_count,
             last_studied: r.last_studied,
+            is_encrypted: r.is_encrypted,
+            cover_url: None,
+            cover_storage_key: r.cover_storage_key,
         })
         .collect();
 
@@ -134,6 +179,7 @@ impl SearchService {
             SELECT COUNT(DISTINCT d.id) as "count!"
             FROM decks d
             WHERE (d.owner_id = $1 OR d.is_public = true)
+              AND d.deleted_at IS NULL
               AND (LOWER(d.title) LIKE LOWER($2) OR LOWER(d.description) LIKE LOWER($2))
             "#,
             user_id,
@@ -146,6 +192,59 @@ impl SearchService {
         Ok(PaginatedResponse::new(decks, params, Some(total)))
     }
 
+    /// Ranked type-ahead suggestions: titles of decks the user owns or
+    /// was shared, their folder names and their decks' tags. Substring and
+    /// fuzzy matches both use the trigram indexes on titles and names.
+    pub async fn suggest(
+        db: &PgPool,
+        user_id: Uuid,
+        search_term: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<SearchSuggestion>> {
+        let term: String = search_term.to_lowercase().chars().take(MAX_SUGGEST_CHARS).collect();
+        // Typed `%` and `_` match themselves
+        let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let limit = limit.unwrap_or(DEFAULT_SUGGESTIONS).clamp(1, MAX_SUGGESTIONS);
+
+        let suggestions = sqlx::query_as::<_, SearchSuggestion>(
+            r#"
+            WITH visible_decks AS (
+                SELECT d.id, d.title, d.tags FROM decks d
+                WHERE d.deleted_at IS NULL
+                  AND (d.owner_id = $1
+                       OR EXISTS(SELECT 1 FROM deck_shares s WHERE s.deck_id = d.id AND s.user_id = $1))
+            ),
+            candidates AS (
+                SELECT 'deck' AS kind, id, title AS label FROM visible_decks
+                WHERE LOWER(title) LIKE '%' || $3 || '%' OR LOWER(title) % $2
+                UNION ALL
+                SELECT 'folder', id, name FROM folders
+                WHERE user_id = $1 AND (LOWER(name) LIKE '%' || $3 || '%' OR LOWER(name) % $2)
+                UNION ALL
+                SELECT DISTINCT 'tag', NULL::UUID, tag FROM visible_decks, unnest(tags) AS tag
+                WHERE tag LIKE '%' || $3 || '%'
+            )
+            SELECT kind, id, label,
+                   (CASE
+                        WHEN LOWER(label) LIKE $3 || '%' THEN 2
+                        WHEN LOWER(label) LIKE '% ' || $3 || '%' THEN 1
+                        ELSE 0
+                    END + similarity(LOWER(label), $2))::REAL AS score
+            FROM candidates
+            ORDER BY score DESC, LENGTH(label), label
+            LIMIT $4
+            "#,
+        )
+        .bind(user_id)
+        .bind(&term)
+        .bind(&escaped)
+        .bind(limit)
+        .fetch_all(db)
+        .await?;
+
+        Ok(suggestions)
+    }
+
     /// Search cards by front or back content
     pub async fn search_cards(
         db: &PgPool,
@@ -169,6 +268,8 @@ impl SearchService {
             FROM cards c
             JOIN decks d ON d.id = c.deck_id
             WHERE (d.owner_id = $1 OR d.is_public = true)
+              AND NOT d.is_encrypted
+              AND c.deleted_at IS NULL AND d.deleted_at IS NULL
               AND (LOWER(c.front) LIKE LOWER($2) OR LOWER(c.back) LIKE LOWER($2))
             ORDER BY 
                 CASE WHEN LOWER(c.front) LIKE LOWER($2) THEN 0 ELSE 1 END,
@@ -225,6 +326,8 @@ impl SearchService {
             FROM cards c
             JOIN decks d ON d.id = c.deck_id
             WHERE (d.owner_id = $1 OR d.is_public = true)
+              AND NOT d.is_encrypted
+              AND c.deleted_at IS NULL AND d.deleted_at IS NULL
               AND (LOWER(c.front) LIKE LOWER($2) OR LOWER(c.back) LIKE LOWER($2))
             ORDER BY 
                 CASE WHEN LOWER(c.front) LIKE LOWER($2) THEN 0 ELSE 1 END,
@@ -261,6 +364,8 @@ impl SearchService {
             FROM cards c
             JOIN decks d ON d.id = c.deck_id
             WHERE (d.owner_id = $1 OR d.is_public = true)
+              AND NOT d.is_encrypted
+              AND c.deleted_at IS NULL AND d.deleted_at IS NULL
               AND (LOWER(c.front) LIKE LOWER($2) OR LOWER(c.back) LIKE LOWER($2))
             "#,
             user_id,
@@ -0,0 +1,468 @@
+use chrono::{DateTime, Duration, Utc};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        admin::SeedSummary, ai::SpacedRepetitionParams, CardStatus, CreateCardDto, CreateDeckDto,
+        CreateFolderDto, Rating,
+    },
+    services::{
+        auth::AuthService,
+        card::CardService,
+        deck::DeckService,
+        folder::FolderService,
+        scheduler::{Sm2Scheduler, DEFAULT_EASE_FACTOR},
+    },
+    utils::{AppError, Result},
+};
+
+pub const DEMO_EMAIL: &str = "demo@deckoracle.dev";
+pub const DEMO_PASSWORD: &str = "DeckOracle-demo-1";
+
+/// Days of study history simulated before today
+const HISTORY_DAYS: i64 = 42;
+
+/// Cards introduced per deck on each simulated study day
+const NEW_CARDS_PER_DAY: usize = 4;
+
+/// Same history on every run, so screenshots and walkthroughs match
+const RNG_SEED: u64 = 0xDEC0;
+
+struct SampleDeck {
+    name: &'static str,
+    description: &'static str,
+    /// Index into `FOLDERS`
+    folder: Option<usize>,
+    languages: Option<(&'static str, &'static str)>,
+    cards: &'static [(&'static str, &'static str)],
+}
+
+/// Folder names with the index of their parent, parents first
+const FOLDERS: &[(&str, Option<usize>)] = &[("Languages", None), ("Spanish", Some(0)), ("Science", None)];
+
+const DECKS: &[SampleDeck] = &[
+    SampleDeck {
+        name: "Spanish Basics",
+        description: "Everyday words and greetings",
+        folder: Some(1),
+        languages: Some(("es", "en")),
+        cards: &[
+            ("hola", "hello"),
+            ("gracias", "thank you"),
+            ("por favor", "please"),
+            ("la casa", "the house"),
+            ("el perro", "the dog"),
+            ("el gato", "the cat"),
+            ("el agua", "the water"),
+            ("la manzana", "the apple"),
+            ("el libro", "the book"),
+            ("la ciudad", "the city"),
+            ("buenos días", "good morning"),
+            ("¿Cómo estás?", "How are you?"),
+        ],
+    },
+    SampleDeck {
+        name: "Spanish Verbs",
+        description: "Common verbs in the infinitive",
+        folder: Some(1),
+        languages: Some(("es", "en")),
+        cards: &[
+            ("hablar", "to speak"),
+            ("comer", "to eat"),
+            ("vivir", "to live"),
+            ("tener", "to have"),
+            ("hacer", "to do, to make"),
+            ("ir", "to go"),
+            ("querer", "to want"),
+            ("poder", "to be able to"),
+            ("saber", "to know (a fact)"),
+            ("conocer", "to know (a person or place)"),
+        ],
+    },
+    SampleDeck {
+        name: "Cell Biology",
+        description: "Organelles and what they do",
+        folder: Some(2),
+        languages: None,
+        cards: &[
+            ("What does the mitochondrion produce?", "ATP, through cellular respiration"),
+            ("Where are ribosomes assembled?", "In the nucleolus"),
+            ("What does the rough endoplasmic reticulum do?", "Synthesizes and folds proteins for export"),
+            ("What is the role of the Golgi apparatus?", "Modifies, sorts and packages proteins"),
+            ("What do lysosomes contain?", "Digestive enzymes"),
+            ("Which organelle performs photosynthesis?", "The chloroplast"),
+            ("What is the cell membrane made of?", "A phospholipid bilayer with embedded proteins"),
+            ("What does the cytoskeleton do?", "Gives the cell shape and moves organelles"),
+            ("Where is DNA stored in eukaryotic cells?", "In the nucleus"),
+        ],
+    },
+    SampleDeck {
+        name: "World Capitals",
+        description: "Capital cities around the world",
+        folder: None,
+        languages: None,
+        cards: &[
+            ("France", "Paris"),
+            ("Japan", "Tokyo"),
+            ("Canada", "Ottawa"),
+            ("Australia", "Canberra"),
+            ("Brazil", "Brasília"),
+            ("Kenya", "Nairobi"),
+            ("Norway", "Oslo"),
+            ("Argentina", "Buenos Aires"),
+            ("India", "New Delhi"),
+            ("Egypt", "Cairo"),
+            ("South Korea", "Seoul"),
+        ],
+    },
+];
+
+/// Scheduling state of one card while its history is simulated
+struct CardHistory {
+    id: Uuid,
+    ease_factor: f32,
+    interval: i32,
+    repetitions: i32,
+    lapses: i32,
+    due_day: Option<i64>,
+    times_seen: i32,
+    times_correct: i32,
+    total_response_ms: i64,
+    last_seen_at: Option<DateTime<Utc>>,
+}
+
+/// Demo content for local development and onboarding: a verified user with
+/// a folder tree, decks of realistic cards and several weeks of simulated
+/// study history, so dashboards and progress charts have data to show.
+pub struct SeedService;
+
+impl SeedService {
+    pub async fn seed_demo(db: &PgPool) -> Result<SeedSummary> {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1)")
+            .bind(DEMO_EMAIL)
+            .fetch_one(db)
+            .await?;
+        if exists {
+            return Err(AppError::BadRequest(format!(
+                "Demo data is already seeded; sign in as {}",
+                DEMO_EMAIL
+            )));
+        }
+
+        let user_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO users (email, password_hash, display_name, email_verified)
+            VALUES ($1, $2, 'Demo Learner', true)
+            RETURNING id
+            "#,
+        )
+        .bind(DEMO_EMAIL)
+        .bind(AuthService::hash_password(DEMO_PASSWORD)?)
+        .fetch_one(db)
+        .await?;
+
+        let mut folder_ids: Vec<Uuid> = Vec::new();
+        for (name, parent) in FOLDERS {
+            let folder = FolderService::create_folder(
+                db,
+                user_id,
+                CreateFolderDto {
+                    name: name.to_string(),
+                    parent_folder_id: parent.map(|index| folder_ids[index]),
+                    position: None,
+                },
+            )
+            .await?;
+            folder_ids.push(folder.id);
+        }
+
+        let mut decks = Vec::new();
+        for sample in DECKS {
+            let deck = DeckService::create_deck(
+                db,
+                user_id,
+                CreateDeckDto {
+                    name: sample.name.to_string(),
+                    description: Some(sample.description.to_string()),
+                    folder_id: sample.folder.map(|index| folder_ids[index]),
+                    is_public: Some(false),
+                    front_language: sample.languages.map(|(front, _)| front.to_string()),
+                    back_language: sample.languages.map(|(_, back)| back.to_string()),
+                },
+            )
+            .await?;
+            let cards = sample
+                .cards
+                .iter()
+                .map(|(front, back)| CreateCardDto {
+                    front: front.to_string(),
+                    back: back.to_string(),
+                    position: None,
+                })
+                .collect();
+            let cards = CardService::bulk_create_cards(db, deck.id, user_id, cards).await?;
+            decks.push((deck.id, cards));
+        }
+
+        let mut histories: Vec<(Uuid, Vec<CardHistory>)> = decks
+            .iter()
+            .map(|(deck_id, cards)| {
+                let cards = cards
+                    .iter()
+                    .map(|card| CardHistory {
+                        id: card.id,
+                        ease_factor: DEFAULT_EASE_FACTOR,
+                        interval: 0,
+                        repetitions: 0,
+                        lapses: 0,
+                        due_day: None,
+                        times_seen: 0,
+                        times_correct: 0,
+                        total_response_ms: 0,
+                        last_seen_at: None,
+                    })
+                    .collect();
+                (*deck_id, cards)
+            })
+            .collect();
+        let (study_sessions, answers) = Self::simulate_history(db, user_id, &mut histories).await?;
+
+        Ok(SeedSummary {
+            user_id,
+            email: DEMO_EMAIL.to_string(),
+            password: DEMO_PASSWORD.to_string(),
+            folders: folder_ids.len(),
+            decks: decks.len(),
+            cards: decks.iter().map(|(_, cards)| cards.len()).sum(),
+            study_sessions,
+            answers,
+        })
+    }
+
+    /// Study each deck most days of the last `HISTORY_DAYS`: the cards due
+    /// that day plus a few new ones, scheduled with SM-2 as real answers
+    /// are. The last week is never skipped so the streak is current.
+    async fn simulate_history(
+        db: &PgPool,
+        user_id: Uuid,
+        histories: &mut [(Uuid, Vec<CardHistory>)],
+    ) -> Result<(usize, usize)> {
+        let mut rng = StdRng::seed_from_u64(RNG_SEED);
+        let today = Utc::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc();
+        let mut study_days = BTreeSet::new();
+        let mut study_sessions = 0;
+        let mut answers = 0;
+
+        let mut tx = db.begin().await?;
+        for day in 0..HISTORY_DAYS {
+            let days_ago = HISTORY_DAYS - day;
+            if days_ago > 7 && rng.gen_bool(0.2) {
+                continue;
+            }
+
+            for (index, (deck_id, cards)) in histories.iter_mut().enumerate() {
+                if rng.gen_bool(0.3) {
+                    continue;
+                }
+
+                let mut new_cards = 0;
+                let studied: Vec<usize> = (0..cards.len())
+                    .filter(|&i| match cards[i].due_day {
+                        Some(due_day) => due_day <= day,
+                        None if new_cards < NEW_CARDS_PER_DAY => {
+                            new_cards += 1;
+                            true
+                        }
+                        None => false,
+                    })
+                    .collect();
+                if studied.is_empty() {
+                    continue;
+                }
+
+                let started_at = today - Duration::days(days_ago)
+                    + Duration::hours(18)
+                    + Duration::minutes(25 * index as i64 + rng.gen_range(0..20));
+                let session_id: Uuid = sqlx::query_scalar(
+                    r#"
+                    INSERT INTO study_sessions (user_id, deck_id, study_mode, total_cards, started_at, created_at)
+                    VALUES ($1, $2, 'standard', $3, $4, $4)
+                    RETURNING id
+                    "#,
+                )
+                .bind(user_id)
+                .bind(*deck_id)
+                .bind(studied.len() as i32)
+                .bind(started_at)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                let cards_studied = studied.len() as i32;
+                let mut studied_at = started_at;
+                let mut correct = 0;
+                for i in studied {
+                    let card = &mut cards[i];
+                    let rating = Self::sample_rating(&mut rng, card.repetitions);
+                    let response_time_ms: i32 = rng.gen_range(1500..12000);
+                    studied_at += Duration::milliseconds(response_time_ms as i64 + 1000);
+
+                    sqlx::query(
+                        r#"
+                        INSERT INTO card_progress (session_id, card_id, user_id, status, rating,
+                                                   response_time_ms, is_correct, studied_at, created_at)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+                        "#,
+                    )
+                    .bind(session_id)
+                    .bind(card.id)
+                    .bind(user_id)
+                    .bind(CardStatus::from_rating(rating))
+                    .bind(rating)
+                    .bind(response_time_ms)
+                    .bind(rating.is_correct())
+                    .bind(studied_at)
+                    .execute(&mut *tx)
+                    .await?;
+
+                    let result = Sm2Scheduler::schedule(&SpacedRepetitionParams {
+                        algorithm: "sm2".to_string(),
+                        ease_factor: card.ease_factor,
+                        interval: card.interval,
+                        repetitions: card.repetitions,
+                        leitner_box: None,
+                        quality: Sm2Scheduler::quality_for_rating(rating),
+                    });
+                    if rating.is_correct() {
+                        correct += 1;
+                        card.times_correct += 1;
+                    } else if card.repetitions > 0 {
+                        card.lapses += 1;
+                    }
+                    card.ease_factor = result.next_ease_factor;
+                    card.interval = result.next_interval;
+                    card.repetitions = result.next_repetitions;
+                    card.due_day = Some(day + result.next_interval as i64);
+                    card.times_seen += 1;
+                    card.total_response_ms += response_time_ms as i64;
+                    card.last_seen_at = Some(studied_at);
+                    answers += 1;
+                }
+
+                sqlx::query(
+                    r#"
+                    UPDATE study_sessions
+                    SET cards_studied = $2, cards_correct = $3, cards_incorrect = $2 - $3,
+                        duration_seconds = EXTRACT(EPOCH FROM ($4 - started_at))::INTEGER,
+                        completed_at = $4, updated_at = $4
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(session_id)
+                .bind(cards_studied)
+                .bind(correct)
+                .bind(studied_at)
+                .execute(&mut *tx)
+                .await?;
+
+                study_sessions += 1;
+                study_days.insert(day);
+            }
+        }
+
+        for card in histories.iter().flat_map(|(_, cards)| cards) {
+            let Some(last_seen_at) = card.last_seen_at else {
+                continue;
+            };
+            sqlx::query(
+                r#"
+                INSERT INTO user_card_stats (
+                    user_id, card_id, times_seen, times_correct, times_incorrect,
+                    average_response_time_ms, last_seen_at, next_review_at,
+                    ease_factor, interval_days, repetitions, lapses
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                "#,
+            )
+            .bind(user_id)
+            .bind(card.id)
+            .bind(card.times_seen)
+            .bind(card.times_correct)
+            .bind(card.times_seen - card.times_correct)
+            .bind((card.total_response_ms / card.times_seen as i64) as i32)
+            .bind(last_seen_at)
+            .bind(today + Duration::days(card.due_day.unwrap_or(HISTORY_DAYS) - HISTORY_DAYS))
+            .bind(card.ease_factor)
+            .bind(card.interval)
+            .bind(card.repetitions)
+            .bind(card.lapses)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let (current_streak, longest_streak) = Self::streaks(&study_days);
+        // Totals, points and level as StatsService counts them per answer
+        sqlx::query(
+            r#"
+            INSERT INTO user_stats (user_id, total_cards_studied, total_study_time_seconds,
+                                    current_streak_days, longest_streak_days, last_study_date,
+                                    total_points, level)
+            SELECT $1, total_cards_studied, total_study_time_seconds, $2, $3, last_study_date,
+                   total_points, FLOOR((1 + SQRT(1 + 0.08 * total_points)) / 2)::INTEGER
+            FROM (
+                SELECT COUNT(*)::INTEGER as total_cards_studied,
+                       (SUM(LEAST(COALESCE(response_time_ms, 0), 300000)) / 1000)::INTEGER
+                           as total_study_time_seconds,
+                       MAX(studied_at)::DATE as last_study_date,
+                       SUM(CASE WHEN rating = 'again' THEN 2 ELSE 10 END)::INTEGER as total_points
+                FROM card_progress
+                WHERE user_id = $1
+            ) totals
+            "#,
+        )
+        .bind(user_id)
+        .bind(current_streak)
+        .bind(longest_streak)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok((study_sessions, answers))
+    }
+
+    /// Mostly passes, with more misses on cards not yet learned
+    fn sample_rating(rng: &mut StdRng, repetitions: i32) -> Rating {
+        let miss_rate = if repetitions == 0 { 0.3 } else { 0.1 };
+        let roll: f64 = rng.gen();
+        if roll < miss_rate {
+            Rating::Again
+        } else if roll < miss_rate + 0.15 {
+            Rating::Hard
+        } else if roll < 0.8 {
+            Rating::Good
+        } else {
+            Rating::Easy
+        }
+    }
+
+    /// Current streak (through yesterday, the last simulated day) and the
+    /// longest run of consecutive study days
+    fn streaks(study_days: &BTreeSet<i64>) -> (i32, i32) {
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous = None;
+        for &day in study_days {
+            run = if previous == Some(day - 1) { run + 1 } else { 1 };
+            longest = longest.max(run);
+            previous = Some(day);
+        }
+        let current = if previous == Some(HISTORY_DAYS - 1) { run } else { 0 };
+        (current, longest)
+    }
+}
@@ -0,0 +1,428 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        Card, CreateShareLinkDto, Deck, DeckEmbed, DeckEmbedCard, DeckRole, DeckShare, DeckShareInvite,
+        DeckShareLink, ShareDeckDto, SharedDeck,
+    },
+    services::{auth::AuthService, encryption::EncryptionService},
+    utils::{render_markdown, AppError, Result},
+};
+
+/// Embeds show at most this many cards; `card_count` has the full number
+pub const MAX_EMBED_CARDS: i64 = 500;
+
+/// Email invites to addresses without an account lapse after this many days
+pub const INVITE_TTL_DAYS: i32 = 30;
+
+#[derive(sqlx::FromRow)]
+struct SharedDeckRow {
+    #[sqlx(flatten)]
+    deck: Deck,
+    role: String,
+}
+
+pub struct SharingService;
+
+impl SharingService {
+    /// Resolve the caller's effective role on a deck. Returns `Ok(None)` when
+    /// the deck exists but the user has no access to it.
+    pub async fn deck_role(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<Option<DeckRole>> {
+        let row = sqlx::query_as::<_, (Uuid, bool, Option<String>)>(
+            r#"
+            SELECT d.owner_id, d.is_public, s.role
+            FROM decks d
+            LEFT JOIN deck_shares s ON s.deck_id = d.id AND s.user_id = $2
+            WHERE d.id = $1 AND d.deleted_at IS NULL
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::DeckNotFound)?;
+
+        let (owner_id, is_public, shared_role) = row;
+        if owner_id == user_id {
+            return Ok(Some(DeckRole::Owner));
+        }
+
+        let role = shared_role.as_deref().and_then(DeckRole::parse);
+        Ok(role.or(is_public.then_some(DeckRole::Viewer)))
+    }
+
+    /// Require at least `required` on a deck. Decks the user cannot see are
+    /// reported as not found; visible decks with too little access are forbidden.
+    pub async fn require_deck_role(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        required: DeckRole,
+    ) -> Result<DeckRole> {
+        match Self::deck_role(db, deck_id, user_id).await? {
+            Some(role) if role >= required => Ok(role),
+            None if required == DeckRole::Viewer => {
+                Err(AppError::DeckNotFound)
+            }
+            _ => Err(AppError::Forbidden),
+        }
+    }
+
+    pub async fn list_shares(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<Vec<DeckShare>> {
+        Self::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+
+        let shares = sqlx::query_as::<_, DeckShare>(
+            r#"
+            SELECT s.id, s.deck_id, s.user_id, u.email, u.display_name, s.role,
+                   s.invited_by, s.created_at, s.updated_at
+            FROM deck_shares s
+            JOIN users u ON u.id = s.user_id
+            WHERE s.deck_id = $1
+            ORDER BY s.created_at
+            "#,
+        )
+        .bind(deck_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(shares)
+    }
+
+    /// Share with the account registered to the email, or keep an invite
+    /// for whoever registers it. The answer is the same either way, so
+    /// sharing cannot be used to find out which addresses have accounts.
+    pub async fn share_with_user(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: ShareDeckDto,
+    ) -> Result<DeckShareInvite> {
+        Self::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+        let role = Self::shareable_role(dto.role)?;
+
+        let target_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE email = $1")
+            .bind(&dto.email)
+            .fetch_optional(db)
+            .await?;
+
+        match target_id {
+            Some(target_id) if target_id == user_id => {
+                return Err(AppError::BadRequest("Cannot share a deck with its owner".to_string()));
+            }
+            Some(target_id) => {
+                Self::upsert_share(db, deck_id, target_id, role, Some(user_id)).await?;
+            }
+            None => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO deck_share_invites (deck_id, email, role, invited_by)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (deck_id, email) DO UPDATE SET
+                        role = EXCLUDED.role,
+                        invited_by = EXCLUDED.invited_by,
+                        created_at = NOW()
+                    "#,
+                )
+                .bind(deck_id)
+                .bind(&dto.email)
+                .bind(role.as_str())
+                .bind(user_id)
+                .execute(db)
+                .await?;
+            }
+        }
+
+        Ok(DeckShareInvite {
+            deck_id,
+            email: dto.email,
+            role: role.as_str().to_string(),
+        })
+    }
+
+    /// Turn the invites sent to a newly registered email into shares.
+    /// Invites older than `INVITE_TTL_DAYS` are dropped unclaimed.
+    pub async fn claim_invites<'e, E: PgExecutor<'e>>(executor: E, user_id: Uuid, email: &str) -> Result<u64> {
+        let claimed = sqlx::query(
+            r#"
+            WITH invites AS (
+                DELETE FROM deck_share_invites WHERE email = $2
+                RETURNING deck_id, role, invited_by, created_at
+            )
+            INSERT INTO deck_shares (deck_id, user_id, role, invited_by)
+            SELECT deck_id, $1, role, invited_by FROM invites
+            WHERE created_at > NOW() - make_interval(days => $3)
+            ON CONFLICT (deck_id, user_id) DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(email)
+        .bind(INVITE_TTL_DAYS)
+        .execute(executor)
+        .await?
+        .rows_affected();
+
+        Ok(claimed)
+    }
+
+    pub async fn revoke_share(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        target_user_id: Uuid,
+    ) -> Result<()> {
+        Self::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+
+        let result = sqlx::query("DELETE FROM deck_shares WHERE deck_id = $1 AND user_id = $2")
+            .bind(deck_id)
+            .bind(target_user_id)
+            .execute(db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Resource not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn list_links(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<DeckShareLink>> {
+        Self::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+
+        let links = sqlx::query_as::<_, DeckShareLink>(
+            "SELECT * FROM deck_share_links WHERE deck_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(deck_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(links)
+    }
+
+    pub async fn create_link(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: CreateShareLinkDto,
+    ) -> Result<DeckShareLink> {
+        Self::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+        let role = Self::shareable_role(dto.role.unwrap_or(DeckRole::Viewer))?;
+        let expires_at: Option<DateTime<Utc>> =
+            dto.expires_in_hours.map(|hours| Utc::now() + Duration::hours(hours));
+
+        let link = sqlx::query_as::<_, DeckShareLink>(
+            r#"
+            INSERT INTO deck_share_links (deck_id, token, role, created_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(deck_id)
+        .bind(AuthService::generate_random_token())
+        .bind(role.as_str())
+        .bind(user_id)
+        .bind(expires_at)
+        .fetch_one(db)
+        .await?;
+
+        Ok(link)
+    }
+
+    pub async fn revoke_link(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        link_id: Uuid,
+    ) -> Result<()> {
+        Self::require_deck_role(db, deck_id, user_id, DeckRole::Owner).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE deck_share_links SET revoked_at = NOW()
+            WHERE id = $1 AND deck_id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(link_id)
+        .bind(deck_id)
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Resource not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Redeem a share link for the calling user
+    pub async fn accept_link(db: &PgPool, token: &str, user_id: Uuid) -> Result<SharedDeck> {
+        let link = sqlx::query_as::<_, DeckShareLink>(
+            r#"
+            SELECT * FROM deck_share_links
+            WHERE token = $1
+                AND revoked_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(token)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::BadRequest("Invalid or expired share link".to_string()))?;
+
+        let link_role = DeckRole::parse(&link.role).unwrap_or(DeckRole::Viewer);
+        let current = Self::deck_role(db, link.deck_id, user_id).await?;
+
+        // Never downgrade an owner or an existing editor
+        if current.map_or(true, |role| role < link_role) {
+            Self::upsert_share(db, link.deck_id, user_id, link_role, Some(link.created_by))
+                .await?;
+        }
+
+        let deck = Self::fetch_deck(db, link.deck_id).await?;
+        let role = Self::deck_role(db, link.deck_id, user_id)
+            .await?
+            .unwrap_or(link_role);
+
+        Ok(SharedDeck {
+            deck,
+            role: role.as_str().to_string(),
+        })
+    }
+
+    pub async fn list_shared_with_user(db: &PgPool, user_id: Uuid) -> Result<Vec<SharedDeck>> {
+        let rows = sqlx::query_as::<_, SharedDeckRow>(
+            r#"
+            SELECT d.id, d.folder_id, d.owner_id, d.title, d.description, d.is_public,
+                   d.front_language, d.back_language, d.created_at, d.updated_at, s.role
+            FROM deck_shares s
+            JOIN decks d ON d.id = s.deck_id
+            WHERE s.user_id = $1 AND d.deleted_at IS NULL
+            ORDER BY d.title
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SharedDeck {
+                deck: row.deck,
+                role: row.role,
+            })
+            .collect())
+    }
+
+    /// Read-only view of the deck behind an active share link, for pages
+    /// that embed or preview it without signing in
+    pub async fn deck_embed(db: &PgPool, token: &str) -> Result<DeckEmbed> {
+        let deck_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT deck_id FROM deck_share_links
+            WHERE token = $1
+                AND revoked_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(token)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("Invalid or expired share link".to_string()))?;
+
+        // Ciphertext is of no use to readers without the key
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+        let deck = Self::fetch_deck(db, deck_id).await?;
+
+        let (card_count, cards_updated_at) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+            "SELECT COUNT(*), MAX(updated_at) FROM cards WHERE deck_id = $1 AND deleted_at IS NULL",
+        )
+        .bind(deck_id)
+        .fetch_one(db)
+        .await?;
+
+        let cards = sqlx::query_as::<_, Card>(
+            "SELECT * FROM cards WHERE deck_id = $1 AND deleted_at IS NULL ORDER BY position LIMIT $2",
+        )
+        .bind(deck_id)
+        .bind(MAX_EMBED_CARDS)
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|card| DeckEmbedCard {
+            position: card.position,
+            front_html: render_markdown(&card.front),
+            back_html: render_markdown(&card.back),
+            front: card.front,
+            back: card.back,
+        })
+        .collect();
+
+        Ok(DeckEmbed {
+            title: deck.name,
+            description: deck.description,
+            front_language: deck.front_language,
+            back_language: deck.back_language,
+            card_count,
+            updated_at: cards_updated_at.map_or(deck.updated_at, |at| at.max(deck.updated_at)),
+            cards,
+        })
+    }
+
+    // Helper methods
+
+    fn shareable_role(role: DeckRole) -> Result<DeckRole> {
+        if role == DeckRole::Owner {
+            return Err(AppError::BadRequest(
+                "Ownership cannot be granted through sharing".to_string(),
+            ));
+        }
+        Ok(role)
+    }
+
+    async fn upsert_share(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        role: DeckRole,
+        invited_by: Option<Uuid>,
+    ) -> Result<Uuid> {
+        let id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO deck_shares (deck_id, user_id, role, invited_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (deck_id, user_id)
+            DO UPDATE SET role = EXCLUDED.role, updated_at = NOW()
+            RETURNING id
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .bind(role.as_str())
+        .bind(invited_by)
+        .fetch_one(db)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn fetch_deck(db: &PgPool, deck_id: Uuid) -> Result<Deck> {
+        let deck = sqlx::query_as::<_, Deck>(
+            r#"
+            SELECT id, folder_id, owner_id, title, description, is_public, front_language, back_language, created_at, updated_at
+            FROM decks
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(deck_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::DeckNotFound)?;
+
+        Ok(deck)
+    }
+}
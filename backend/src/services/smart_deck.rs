@@ -0,0 +1,266 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        CreateSmartDeckDto, DeckRole, SmartDeck, SmartDeckFilter, SmartDeckStatus,
+        UpdateSmartDeckDto,
+    },
+    services::{marketplace::normalize_tags, scheduler::DEFAULT_EASE_FACTOR, sharing::SharingService},
+    utils::{AppError, Result},
+};
+
+const MAX_SMART_DECKS_PER_USER: i64 = 100;
+
+/// Most decks a filter can name in `deck_ids`
+const MAX_FILTER_DECKS: usize = 100;
+
+/// Longest `query`, in characters
+const MAX_QUERY_CHARS: usize = 200;
+
+/// Saved filters that act as virtual decks. A smart deck holds no cards;
+/// its filter is evaluated over the decks the user owns or was shared each
+/// time a study queue is built, so it follows new answers and edits.
+pub struct SmartDeckService;
+
+impl SmartDeckService {
+    pub async fn list(db: &PgPool, user_id: Uuid) -> Result<Vec<SmartDeck>> {
+        let smart_decks = sqlx::query_as::<_, SmartDeck>(
+            "SELECT * FROM smart_decks WHERE user_id = $1 ORDER BY name, created_at",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(smart_decks)
+    }
+
+    pub async fn get(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<SmartDeck> {
+        sqlx::query_as::<_, SmartDeck>("SELECT * FROM smart_decks WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or(AppError::NotFound("Smart deck not found".to_string()))
+    }
+
+    pub async fn create(db: &PgPool, user_id: Uuid, dto: CreateSmartDeckDto) -> Result<SmartDeck> {
+        let filter = Self::validate_filter(db, user_id, dto.filter).await?;
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM smart_decks WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(db)
+            .await?;
+        if count >= MAX_SMART_DECKS_PER_USER {
+            return Err(AppError::BadRequest(format!(
+                "You can have at most {} smart decks",
+                MAX_SMART_DECKS_PER_USER
+            )));
+        }
+
+        let smart_deck = sqlx::query_as::<_, SmartDeck>(
+            r#"
+            INSERT INTO smart_decks (user_id, name, description, filter)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(dto.name.trim())
+        .bind(&dto.description)
+        .bind(sqlx::types::Json(&filter))
+        .fetch_one(db)
+        .await?;
+
+        Ok(smart_deck)
+    }
+
+    pub async fn update(
+        db: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+        dto: UpdateSmartDeckDto,
+    ) -> Result<SmartDeck> {
+        let filter = match dto.filter {
+            Some(filter) => Some(Self::validate_filter(db, user_id, filter).await?),
+            None => None,
+        };
+
+        sqlx::query_as::<_, SmartDeck>(
+            r#"
+            UPDATE smart_decks
+            SET name = COALESCE($3, name),
+                description = COALESCE($4, description),
+                filter = COALESCE($5, filter),
+                updated_at = NOW()
+            WHERE id = $1 AND user_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(dto.name.as_deref().map(str::trim))
+        .bind(&dto.description)
+        .bind(filter.as_ref().map(sqlx::types::Json))
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("Smart deck not found".to_string()))
+    }
+
+    /// Sessions of the smart deck are kept, detached from it
+    pub async fn delete(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM smart_decks WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Smart deck not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// The decks a filter draws cards from: its `deck_ids`, or every deck
+    /// the user owns or was shared, narrowed to its `tags`. Decks the user
+    /// has since lost access to are dropped.
+    pub async fn deck_ids(db: &PgPool, user_id: Uuid, filter: &SmartDeckFilter) -> Result<Vec<Uuid>> {
+        let deck_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT d.id FROM decks d
+            WHERE d.deleted_at IS NULL
+              AND CASE WHEN $2::UUID[] IS NULL
+                  THEN d.owner_id = $1
+                       OR EXISTS(SELECT 1 FROM deck_shares s WHERE s.deck_id = d.id AND s.user_id = $1)
+                  ELSE d.id = ANY($2)
+                       AND (d.owner_id = $1 OR d.is_public
+                            OR EXISTS(SELECT 1 FROM deck_shares s WHERE s.deck_id = d.id AND s.user_id = $1))
+              END
+              AND ($3::TEXT[] IS NULL OR d.tags && $3)
+            ORDER BY d.title, d.id
+            "#,
+        )
+        .bind(user_id)
+        .bind(&filter.deck_ids)
+        .bind(&filter.tags)
+        .fetch_all(db)
+        .await?;
+
+        Ok(deck_ids)
+    }
+
+    /// Ids of the cards matching every criterion of the filter for the user,
+    /// in deck and position order
+    pub async fn card_ids(db: &PgPool, user_id: Uuid, filter: &SmartDeckFilter) -> Result<Vec<Uuid>> {
+        let deck_ids = Self::deck_ids(db, user_id, filter).await?;
+        let search_pattern = filter.query.as_deref().map(|query| format!("%{}%", query));
+        let status = filter.status.map(|status| match status {
+            SmartDeckStatus::New => "new",
+            SmartDeckStatus::Due => "due",
+            SmartDeckStatus::Overdue => "overdue",
+        });
+
+        // Difficulty mirrors the study queue's estimate from the ease factor
+        let card_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT c.id FROM cards c
+            LEFT JOIN user_card_stats s ON s.card_id = c.id AND s.user_id = $2
+            WHERE c.deck_id = ANY($1) AND c.deleted_at IS NULL
+              AND ($3::TEXT IS NULL OR LOWER(c.front) LIKE LOWER($3) OR LOWER(c.back) LIKE LOWER($3))
+              AND CASE $4::TEXT
+                  WHEN 'new' THEN COALESCE(s.times_seen, 0) = 0
+                  WHEN 'due' THEN COALESCE(s.times_seen, 0) > 0
+                                  AND (s.next_review_at IS NULL OR s.next_review_at <= NOW())
+                  WHEN 'overdue' THEN COALESCE(s.times_seen, 0) > 0
+                                      AND s.next_review_at <= NOW() - INTERVAL '1 day'
+                  ELSE true
+              END
+              AND GREATEST(0, LEAST(1, ($5 + 0.5 - COALESCE(s.ease_factor, $5)) / 1.7))
+                  BETWEEN COALESCE($6, 0) AND COALESCE($7, 1)
+            ORDER BY array_position($1, c.deck_id), c.position, c.created_at
+            "#,
+        )
+        .bind(&deck_ids)
+        .bind(user_id)
+        .bind(&search_pattern)
+        .bind(status)
+        .bind(DEFAULT_EASE_FACTOR)
+        .bind(filter.min_difficulty)
+        .bind(filter.max_difficulty)
+        .fetch_all(db)
+        .await?;
+
+        Ok(card_ids)
+    }
+
+    /// Check a filter and normalize its tags and query. Named decks must be
+    /// visible to the user.
+    async fn validate_filter(
+        db: &PgPool,
+        user_id: Uuid,
+        mut filter: SmartDeckFilter,
+    ) -> Result<SmartDeckFilter> {
+        if let Some(tags) = &filter.tags {
+            let tags = normalize_tags(tags)?;
+            if tags.is_empty() {
+                return Err(AppError::ValidationError("tags cannot be empty".to_string()));
+            }
+            filter.tags = Some(tags);
+        }
+
+        if let Some(query) = &filter.query {
+            let query = query.trim();
+            if query.is_empty() || query.chars().count() > MAX_QUERY_CHARS {
+                return Err(AppError::ValidationError(format!(
+                    "query must be 1 to {} characters",
+                    MAX_QUERY_CHARS
+                )));
+            }
+            filter.query = Some(query.to_string());
+        }
+
+        for difficulty in [filter.min_difficulty, filter.max_difficulty].into_iter().flatten() {
+            if !(0.0..=1.0).contains(&difficulty) {
+                return Err(AppError::ValidationError(
+                    "Difficulty must be between 0.0 and 1.0".to_string(),
+                ));
+            }
+        }
+        if let (Some(min), Some(max)) = (filter.min_difficulty, filter.max_difficulty) {
+            if min > max {
+                return Err(AppError::ValidationError(
+                    "min_difficulty cannot be above max_difficulty".to_string(),
+                ));
+            }
+        }
+
+        if let Some(deck_ids) = &mut filter.deck_ids {
+            deck_ids.sort();
+            deck_ids.dedup();
+            if deck_ids.is_empty() || deck_ids.len() > MAX_FILTER_DECKS {
+                return Err(AppError::ValidationError(format!(
+                    "deck_ids must list 1 to {} decks",
+                    MAX_FILTER_DECKS
+                )));
+            }
+            for &deck_id in deck_ids.iter() {
+                SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+            }
+        }
+
+        if filter.deck_ids.is_none()
+            && filter.tags.is_none()
+            && filter.min_difficulty.is_none()
+            && filter.max_difficulty.is_none()
+            && filter.status.is_none()
+            && filter.query.is_none()
+        {
+            return Err(AppError::ValidationError(
+                "A smart deck filter needs at least one criterion".to_string(),
+            ));
+        }
+
+        Ok(filter)
+    }
+}
@@ -0,0 +1,354 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        DeckRole, DeckStatistics, EaseBucket, IntervalBucket, Rating, StudyHeatmap, UserStats,
+        UserStatsResponse,
+    },
+    services::{scheduler::DEFAULT_EASE_FACTOR, sharing::SharingService},
+    utils::{AppError, Result},
+};
+
+/// Points for an answer rated anything but "again"
+pub const CORRECT_ANSWER_POINTS: i32 = 10;
+/// Points for showing up, even when the answer was forgotten
+pub const AGAIN_ANSWER_POINTS: i32 = 2;
+/// Longest response time counted as study time, so an answer left open
+/// overnight does not add hours
+pub const MAX_COUNTED_RESPONSE_MS: i32 = 5 * 60 * 1000;
+/// Interval from which a card counts as mature, as in Anki
+pub const MATURE_INTERVAL_DAYS: i32 = 21;
+/// Lower bounds of the ease distribution buckets; the last one is open
+const EASE_BUCKETS: [f64; 9] = [1.3, 1.5, 1.7, 1.9, 2.1, 2.3, 2.5, 2.7, 2.9];
+/// Lower bounds of the interval histogram buckets in days; the last one is
+/// open
+const INTERVAL_BUCKETS: [i32; 9] = [0, 1, 2, 4, 8, 15, 31, 91, 181];
+
+/// Running per-user totals in `user_stats`: cards studied, study time,
+/// daily streak, points and level. Updated in the same transaction that
+/// records each answer.
+pub struct StatsService;
+
+impl StatsService {
+    pub async fn get(db: &PgPool, user_id: Uuid) -> Result<UserStatsResponse> {
+        let stats = sqlx::query_as::<_, UserStats>(
+            r#"
+            INSERT INTO user_stats (user_id) VALUES ($1)
+            ON CONFLICT (user_id) DO UPDATE SET user_id = EXCLUDED.user_id
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(Self::response(stats))
+    }
+
+    /// Count an answer towards the user's totals
+    pub async fn record_answer(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        rating: Rating,
+        response_time_ms: Option<i32>,
+    ) -> Result<UserStats> {
+        let seconds = response_time_ms.unwrap_or(0).clamp(0, MAX_COUNTED_RESPONSE_MS) / 1000;
+
+        // The streak grows on the first answer of a day that follows a day
+        // with answers, and restarts after a gap
+        let mut stats = sqlx::query_as::<_, UserStats>(
+            r#"
+            INSERT INTO user_stats (user_id, total_cards_studied, total_study_time_seconds,
+                                    current_streak_days, longest_streak_days, last_study_date,
+                                    total_points)
+            VALUES ($1, 1, $2, 1, 1, CURRENT_DATE, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET total_cards_studied = user_stats.total_cards_studied + 1,
+                total_study_time_seconds = user_stats.total_study_time_seconds + EXCLUDED.total_study_time_seconds,
+                total_points = user_stats.total_points + EXCLUDED.total_points,
+                current_streak_days = CASE
+                    WHEN user_stats.last_study_date = CURRENT_DATE THEN user_stats.current_streak_days
+                    WHEN user_stats.last_study_date = CURRENT_DATE - 1 THEN user_stats.current_streak_days + 1
+                    ELSE 1
+                END,
+                longest_streak_days = GREATEST(user_stats.longest_streak_days, CASE
+                    WHEN user_stats.last_study_date = CURRENT_DATE THEN user_stats.current_streak_days
+                    WHEN user_stats.last_study_date = CURRENT_DATE - 1 THEN user_stats.current_streak_days + 1
+                    ELSE 1
+                END),
+                last_study_date = CURRENT_DATE,
+                updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(seconds)
+        .bind(answer_points(rating))
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Self::sync_level(tx, &mut stats).await?;
+        Ok(stats)
+    }
+
+    /// Award bonus points, e.g. for an achievement
+    pub async fn add_points(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        points: i32,
+    ) -> Result<UserStats> {
+        let mut stats = sqlx::query_as::<_, UserStats>(
+            r#"
+            INSERT INTO user_stats (user_id, total_points) VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET total_points = user_stats.total_points + EXCLUDED.total_points, updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(points)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Self::sync_level(tx, &mut stats).await?;
+        Ok(stats)
+    }
+
+    async fn sync_level(tx: &mut Transaction<'_, Postgres>, stats: &mut UserStats) -> Result<()> {
+        let level = level_for_points(stats.total_points);
+        if level != stats.level {
+            sqlx::query("UPDATE user_stats SET level = $2 WHERE user_id = $1")
+                .bind(stats.user_id)
+                .bind(level)
+                .execute(&mut **tx)
+                .await?;
+            stats.level = level;
+        }
+        Ok(())
+    }
+
+    /// Answers and study minutes for every day of `year`, with days counted
+    /// in `timezone` (UTC when not given)
+    pub async fn heatmap(
+        db: &PgPool,
+        user_id: Uuid,
+        year: Option<i32>,
+        timezone: Option<String>,
+    ) -> Result<StudyHeatmap> {
+        let year = year.unwrap_or_else(|| Utc::now().year());
+        let (Some(start_date), Some(end_date)) = (
+            NaiveDate::from_ymd_opt(year, 1, 1).filter(|_| (1970..=9999).contains(&year)),
+            NaiveDate::from_ymd_opt(year + 1, 1, 1),
+        ) else {
+            return Err(AppError::ValidationError(format!("Invalid year: {}", year)));
+        };
+
+        let timezone = timezone.unwrap_or_else(|| "UTC".to_string());
+        let known = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM pg_timezone_names WHERE name = $1)",
+        )
+        .bind(&timezone)
+        .fetch_one(db)
+        .await?;
+        if !known {
+            return Err(AppError::ValidationError(format!("Unknown timezone: {}", timezone)));
+        }
+
+        let days = sqlx::query_as::<_, (NaiveDate, i64, i64)>(
+            r#"
+            SELECT (studied_at AT TIME ZONE $2)::DATE as day,
+                   COUNT(*) as reviews,
+                   SUM(LEAST(GREATEST(COALESCE(response_time_ms, 0), 0), $4))::BIGINT as time_ms
+            FROM card_progress
+            WHERE user_id = $1
+              AND studied_at >= make_timestamptz($3, 1, 1, 0, 0, 0, $2)
+              AND studied_at < make_timestamptz($3 + 1, 1, 1, 0, 0, 0, $2)
+            GROUP BY day
+            "#,
+        )
+        .bind(user_id)
+        .bind(&timezone)
+        .bind(year)
+        .bind(MAX_COUNTED_RESPONSE_MS)
+        .fetch_all(db)
+        .await?;
+
+        let len = (end_date - start_date).num_days() as usize;
+        let mut reviews = vec![0; len];
+        let mut time_ms = vec![0i64; len];
+        for (day, count, ms) in days {
+            let index = (day - start_date).num_days() as usize;
+            if index < len {
+                reviews[index] = count as i32;
+                time_ms[index] = ms;
+            }
+        }
+
+        Ok(StudyHeatmap {
+            year,
+            timezone,
+            start_date,
+            minutes: time_ms.iter().map(|ms| ((ms + 30_000) / 60_000) as i32).collect(),
+            total_reviews: reviews.iter().map(|&r| r as i64).sum(),
+            total_minutes: (time_ms.iter().sum::<i64>() + 30_000) / 60_000,
+            active_days: reviews.iter().filter(|&&r| r > 0).count() as i32,
+            max_reviews: reviews.iter().copied().max().unwrap_or(0),
+            reviews,
+        })
+    }
+
+    /// Card maturity, retention, ease and interval spread and answer time
+    /// for the user's study of a deck they can view
+    pub async fn deck_statistics(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<DeckStatistics> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+
+        // (times_seen, ease_factor, interval_days) of every live card
+        let cards = sqlx::query_as::<_, (Option<i32>, Option<f32>, Option<i32>)>(
+            r#"
+            SELECT s.times_seen, s.ease_factor, s.interval_days
+            FROM cards c
+            LEFT JOIN user_card_stats s ON s.card_id = c.id AND s.user_id = $2
+            WHERE c.deck_id = $1 AND c.deleted_at IS NULL
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        let (total_reviews, mature_reviews, mature_correct, young_reviews, young_correct, time_ms) =
+            sqlx::query_as::<_, (i64, i64, i64, i64, i64, Option<f64>)>(
+                r#"
+                SELECT COUNT(*),
+                       COUNT(*) FILTER (WHERE cp.interval_days_before >= $3),
+                       COUNT(*) FILTER (WHERE cp.interval_days_before >= $3 AND cp.rating <> 'again'),
+                       COUNT(*) FILTER (WHERE cp.interval_days_before BETWEEN 1 AND $3 - 1),
+                       COUNT(*) FILTER (WHERE cp.interval_days_before BETWEEN 1 AND $3 - 1 AND cp.rating <> 'again'),
+                       (AVG(LEAST(cp.response_time_ms, $4)) FILTER (WHERE cp.response_time_ms > 0))::FLOAT8
+                FROM card_progress cp
+                JOIN cards c ON c.id = cp.card_id
+                WHERE cp.user_id = $2 AND c.deck_id = $1 AND c.deleted_at IS NULL
+                "#,
+            )
+            .bind(deck_id)
+            .bind(user_id)
+            .bind(MATURE_INTERVAL_DAYS)
+            .bind(MAX_COUNTED_RESPONSE_MS)
+            .fetch_one(db)
+            .await?;
+
+        let studied: Vec<(f32, i32)> = cards
+            .iter()
+            .filter(|(seen, _, _)| seen.unwrap_or(0) > 0)
+            .map(|(_, ease, interval)| (ease.unwrap_or(DEFAULT_EASE_FACTOR), interval.unwrap_or(0)))
+            .collect();
+        let eases: Vec<f32> = studied.iter().map(|(ease, _)| *ease).collect();
+        let intervals: Vec<i32> = studied.iter().map(|(_, interval)| *interval).collect();
+        let mature_cards = intervals.iter().filter(|&&i| i >= MATURE_INTERVAL_DAYS).count() as i64;
+        let average = |sum: f64| (!studied.is_empty()).then(|| sum / studied.len() as f64);
+        let rate = |correct: i64, total: i64| (total > 0).then(|| correct as f64 / total as f64);
+
+        Ok(DeckStatistics {
+            deck_id,
+            total_cards: cards.len() as i64,
+            new_cards: (cards.len() - studied.len()) as i64,
+            young_cards: studied.len() as i64 - mature_cards,
+            mature_cards,
+            total_reviews,
+            mature_reviews,
+            true_retention: rate(mature_correct, mature_reviews),
+            young_retention: rate(young_correct, young_reviews),
+            average_ease: average(eases.iter().map(|&e| e as f64).sum()),
+            average_interval_days: average(intervals.iter().map(|&i| i as f64).sum()),
+            average_time_per_card_ms: time_ms.map(|ms| ms.round() as i64),
+            ease_distribution: ease_distribution(&eases),
+            interval_histogram: interval_histogram(&intervals),
+        })
+    }
+
+    fn response(mut stats: UserStats) -> UserStatsResponse {
+        // A streak is over once a whole day passes without answers
+        let yesterday = Utc::now().date_naive() - chrono::Duration::days(1);
+        if !matches!(stats.last_study_date, Some(date) if date >= yesterday) {
+            stats.current_streak_days = 0;
+        }
+
+        let level_points = points_for_level(stats.level);
+        let next_level_points = points_for_level(stats.level + 1);
+        UserStatsResponse {
+            points_to_next_level: next_level_points - stats.total_points,
+            level_progress: (stats.total_points - level_points) as f64
+                / (next_level_points - level_points) as f64,
+            level_points,
+            next_level_points,
+            stats,
+        }
+    }
+}
+
+pub fn answer_points(rating: Rating) -> i32 {
+    if rating.is_correct() {
+        CORRECT_ANSWER_POINTS
+    } else {
+        AGAIN_ANSWER_POINTS
+    }
+}
+
+/// Count ease factors into fixed 0.2-wide buckets from 1.3, the lowest
+/// ease SM-2 allows. Every bucket is returned, empty ones with zero cards.
+pub fn ease_distribution(eases: &[f32]) -> Vec<EaseBucket> {
+    EASE_BUCKETS
+        .iter()
+        .enumerate()
+        .map(|(i, &min)| {
+            let max = EASE_BUCKETS.get(i + 1).copied();
+            // Compare in hundredths so 1.5 stored as f32 lands in its bucket
+            let in_bucket = |ease: f64| {
+                let ease = (ease * 100.0).round();
+                ease >= (min * 100.0).round() && max.map_or(true, |max| ease < (max * 100.0).round())
+            };
+            EaseBucket {
+                min,
+                max,
+                cards: eases.iter().filter(|&&e| in_bucket(e as f64)).count() as i64,
+            }
+        })
+        .collect()
+}
+
+/// Count intervals into buckets of growing width: 0, 1, 2-3, 4-7, 8-14,
+/// 15-30, 31-90, 91-180 and 181+ days. Every bucket is returned.
+pub fn interval_histogram(intervals: &[i32]) -> Vec<IntervalBucket> {
+    INTERVAL_BUCKETS
+        .iter()
+        .enumerate()
+        .map(|(i, &min_days)| {
+            let max_days = INTERVAL_BUCKETS.get(i + 1).map(|next| next - 1);
+            IntervalBucket {
+                min_days,
+                max_days,
+                cards: intervals
+                    .iter()
+                    .filter(|&&d| d.max(0) >= min_days && max_days.map_or(true, |max| d.max(0) <= max))
+                    .count() as i64,
+            }
+        })
+        .collect()
+}
+
+/// Total points needed to reach `level`: 0 for level 1, then 100, 300, 600,
+/// ... so each level takes 100 more points than the one before
+pub fn points_for_level(level: i32) -> i32 {
+    50 * level * (level - 1)
+}
+
+/// The highest level whose threshold `points` has reached
+pub fn level_for_points(points: i32) -> i32 {
+    let mut level = 1;
+    while points_for_level(level + 1) <= points {
+        level += 1;
+    }
+    level
+}
@@ -0,0 +1,420 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    config::{Config, S3Config},
+    utils::{AppError, Result},
+};
+
+/// Path prefix of the download route serving app-signed URLs
+const SIGNED_URL_PREFIX: &str = "/api/v1/media/";
+
+/// S3 rejects presigned URLs valid for longer than a week
+const MAX_PRESIGN_SECONDS: i64 = 7 * 24 * 3600;
+
+/// Extension → content type for attachments we know how to serve. Only types
+/// browsers render inertly are listed; SVG can carry scripts.
+const MEDIA_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("m4a", "audio/mp4"),
+];
+
+pub fn media_content_type(filename: &str) -> Option<&'static str> {
+    let (_, extension) = filename.rsplit_once('.')?;
+    let extension = extension.to_ascii_lowercase();
+    MEDIA_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, content_type)| *content_type)
+}
+
+/// Object storage for card media, addressed by slash-separated keys
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+    /// Deleting a missing object is not an error
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// URL the client can fetch the object from directly, if the backend can
+    /// issue one. Otherwise downloads go through this server.
+    fn presigned_url(&self, _key: &str, _expires_in: Duration) -> Option<String> {
+        None
+    }
+}
+
+/// Files under a directory on the server's disk
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        if !is_safe_key(key) {
+            return Err(AppError::NotFound("Resource not found".to_string()));
+        }
+        Ok(self.root.join(Path::new(key)))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>, _content_type: &str) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(storage_error)?;
+        }
+        tokio::fs::write(&path, data).await.map_err(storage_error)
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(AppError::NotFound("Resource not found".to_string()))
+            }
+            Err(e) => Err(storage_error(e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(storage_error(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An S3-compatible bucket, signed with AWS Signature Version 4. Every
+/// request uses a presigned URL, so reads and writes share one signer.
+pub struct S3Storage {
+    config: S3Config,
+    base_url: String, // scheme://host[:port] without a trailing slash
+    host: String,
+    client: reqwest::Client,
+}
+
+impl S3Storage {
+    pub fn new(config: S3Config) -> std::result::Result<Self, String> {
+        let url = reqwest::Url::parse(&config.endpoint)
+            .map_err(|e| format!("Invalid S3 endpoint '{}': {}", config.endpoint, e))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(format!("S3 endpoint '{}' has no host", config.endpoint)),
+        };
+
+        Ok(Self {
+            base_url: format!("{}://{}", url.scheme(), host),
+            host,
+            config,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Query-string authenticated URL for `method` on `key`, valid for
+    /// `expires_in` from `now`
+    pub fn presign(&self, method: &str, key: &str, expires_in: Duration, now: DateTime<Utc>) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.config.region);
+        let path = format!("/{}/{}", uri_encode(&self.config.bucket, true), uri_encode(key, false));
+        let expires = expires_in.num_seconds().clamp(1, MAX_PRESIGN_SECONDS);
+
+        // Already in the byte order SigV4 requires
+        let query = [
+            ("X-Amz-Algorithm", "AWS4-HMAC-SHA256".to_string()),
+            ("X-Amz-Credential", format!("{}/{}", self.config.access_key_id, scope)),
+            ("X-Amz-Date", amz_date.clone()),
+            ("X-Amz-Expires", expires.to_string()),
+            ("X-Amz-SignedHeaders", "host".to_string()),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, uri_encode(value, true)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD",
+            method, path, query, self.host
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [self.config.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(format!("AWS4{}", self.config.secret_access_key).as_bytes(), date.as_bytes()),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        format!("{}{}?{}&X-Amz-Signature={}", self.base_url, path, query, signature)
+    }
+
+    fn request_url(&self, method: &str, key: &str) -> String {
+        self.presign(method, key, Duration::minutes(5), Utc::now())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Storage {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()> {
+        self.client
+            .put(self.request_url("PUT", key))
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(data)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get(self.request_url("GET", key))
+            .send()
+            .await
+            .map_err(storage_error)?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(AppError::NotFound("Resource not found".to_string()));
+        }
+
+        let data = response
+            .error_for_status()
+            .map_err(storage_error)?
+            .bytes()
+            .await
+            .map_err(storage_error)?;
+        Ok(data.to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.client
+            .delete(self.request_url("DELETE", key))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn presigned_url(&self, key: &str, expires_in: Duration) -> Option<String> {
+        Some(self.presign("GET", key, expires_in, Utc::now()))
+    }
+}
+
+/// The configured storage backend plus the upload rules and URL signing
+/// shared by every code path that touches card media
+pub struct MediaStore {
+    backend: Arc<dyn StorageBackend>,
+    signing_key: String,
+    url_ttl: Duration,
+    max_file_size: usize,
+    allowed_types: Vec<String>,
+}
+
+impl MediaStore {
+    pub fn new(backend: Arc<dyn StorageBackend>, config: &Config) -> Self {
+        Self {
+            backend,
+            signing_key: config.storage.signing_key.clone(),
+            url_ttl: Duration::seconds(config.storage.signed_url_ttl_seconds as i64),
+            max_file_size: config.upload.max_file_size,
+            allowed_types: config.upload.allowed_media_types.clone(),
+        }
+    }
+
+    /// Store backed by whichever backend `STORAGE_BACKEND` selects
+    pub fn from_config(config: &Config) -> std::result::Result<Self, String> {
+        let backend: Arc<dyn StorageBackend> = match config.storage.backend.as_str() {
+            "local" => Arc::new(LocalStorage::new(&config.storage.local_path)),
+            "s3" => {
+                let s3 = config
+                    .storage
+                    .s3
+                    .clone()
+                    .ok_or("STORAGE_S3_BUCKET is required for the s3 storage backend")?;
+                Arc::new(S3Storage::new(s3)?)
+            }
+            other => return Err(format!("Unknown storage backend '{}'", other)),
+        };
+
+        Ok(Self::new(backend, config))
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    pub fn max_file_size(&self) -> usize {
+        self.max_file_size
+    }
+
+    /// Check an attachment against the upload limits, returning its content
+    /// type
+    pub fn check_upload(&self, filename: &str, size: usize) -> Result<&'static str> {
+        if size > self.max_file_size {
+            return Err(AppError::FileTooLarge {
+                limit_bytes: self.max_file_size,
+            });
+        }
+        if size == 0 {
+            return Err(AppError::FileUploadError("File is empty".to_string()));
+        }
+
+        let extension = filename
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        match media_content_type(filename) {
+            Some(content_type) if self.allowed_types.contains(&extension) => Ok(content_type),
+            _ => Err(AppError::FileUploadError(format!(
+                "Unsupported media type; allowed: {}",
+                self.allowed_types.join(", ")
+            ))),
+        }
+    }
+
+    pub async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()> {
+        self.backend.put(key, data, content_type).await
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.backend.get(key).await
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        self.backend.delete(key).await
+    }
+
+    /// Time-limited download URL for `key`: presigned by the backend when it
+    /// can, otherwise a signed link to this server's media route
+    pub fn signed_url(&self, key: &str) -> (String, DateTime<Utc>) {
+        self.signed_url_valid_for(key, self.url_ttl)
+    }
+
+    /// [`MediaStore::signed_url`] with its own lifetime instead of the
+    /// configured one, for links that must outlive a page view
+    pub fn signed_url_valid_for(&self, key: &str, ttl: Duration) -> (String, DateTime<Utc>) {
+        let expires_at = Utc::now() + ttl;
+        if let Some(url) = self.backend.presigned_url(key, ttl) {
+            return (url, expires_at);
+        }
+
+        let expires = expires_at.timestamp();
+        let url = format!(
+            "{}{}?expires={}&signature={}",
+            SIGNED_URL_PREFIX,
+            key,
+            expires,
+            hex::encode(self.url_mac(key, expires).finalize().into_bytes())
+        );
+        (url, expires_at)
+    }
+
+    /// Index of the current signing window, one URL lifetime long. Responses
+    /// revalidated by ETag that embed [`MediaStore::window_signed_url`]
+    /// links fold it into their version so a cached copy is refetched before
+    /// its links expire.
+    pub fn url_window(&self) -> i64 {
+        Utc::now().timestamp() / self.url_ttl.num_seconds().max(1)
+    }
+
+    /// Signed URL valid until the end of the window after the current one,
+    /// so it outlives any response cached during this window
+    pub fn window_signed_url(&self, key: &str) -> (String, DateTime<Utc>) {
+        let window = self.url_ttl.num_seconds().max(1);
+        let expires = (self.url_window() + 2) * window;
+        self.signed_url_valid_for(key, Duration::seconds(expires - Utc::now().timestamp()))
+    }
+
+    /// Check a signature issued by [`MediaStore::signed_url`]
+    pub fn verify_signature(&self, key: &str, expires: i64, signature: &str) -> bool {
+        if expires < Utc::now().timestamp() {
+            return false;
+        }
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        self.url_mac(key, expires).verify_slice(&signature).is_ok()
+    }
+
+    fn url_mac(&self, key: &str, expires: i64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.signing_key.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(key.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+}
+
+// Helper functions
+
+/// Keys are generated by the server, but the download route takes them from
+/// the URL, so never let one escape the storage root
+fn is_safe_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.contains('\\')
+        && key
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+}
+
+/// SigV4 URI encoding; `/` is kept in object keys and encoded everywhere else
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn storage_error(error: impl std::fmt::Display) -> AppError {
+    tracing::error!("Media storage error: {}", error);
+    AppError::InternalServerError
+}
@@ -1,8 +1,25 @@
 use crate::{
     models::{
-        Achievement, AchievementWithStatus, CardProgress, CardStatus, CreateStudySessionDto,
-        StudySession, SubmitCardAnswerDto, UpdateStudySessionDto, UserAchievement, UserCardStats,
-        UserStats,
+        ai::SpacedRepetitionParams,
+        Achievement, AchievementWithStatus, ActiveStudySession, Card, CardHistory, CardProgress, CardReview,
+        CardStatus, CreateStudySessionDto, DeckRole,
+        DeckSettings, GradedCardProgress, LapseAction, RecordProgressDto,
+        Rating, RatingDistribution, ScheduleDecision, SessionCardStats, SessionDeckBreakdown, SessionReplay,
+        SessionReplayEvent, SessionStats, StudySession, SubmitCardAnswerDto,
+        UpdateStudySessionDto, UserAchievement, UserCardStats, UserStats,
+    },
+    services::{
+        deck::DeckService,
+        deck_settings::DeckSettingsService,
+        encryption::EncryptionService,
+        folder::FolderService,
+        grading,
+        leech::LeechService,
+        card::CardService,
+        scheduler::{self, Sm2Scheduler, DEFAULT_EASE_FACTOR},
+        sharing::SharingService,
+        smart_deck::SmartDeckService,
+        stats::StatsService,
     },
     utils::{AppError, Result},
 };
@@ -10,45 +27,127 @@ use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+/// Sessions in this mode are graded by answering multiple-choice questions
+pub const QUIZ_STUDY_MODE: &str = "quiz";
+
+/// Sessions in this mode must set a time limit
+pub const TIMED_STUDY_MODE: &str = "timed";
+
 pub struct StudyService;
 
+/// Session totals with the per-card breakdown aggregated into JSON, so the
+/// whole summary comes from one query
+#[derive(sqlx::FromRow)]
+struct SessionStatsRow {
+    total_answers: i64,
+    total_cards: i64,
+    correct_answers: i64,
+    again_count: i64,
+    hard_count: i64,
+    good_count: i64,
+    easy_count: i64,
+    average_response_time_ms: Option<f64>,
+    median_response_time_ms: Option<f64>,
+    cards: sqlx::types::Json<Vec<SessionCardStats>>,
+}
+
+/// Flat `card_progress` row joined with its card; decision columns are NULL
+/// for answers recorded before replay support
+#[derive(sqlx::FromRow)]
+struct ReplayRow {
+    progress_id: Uuid,
+    session_id: Uuid,
+    card_id: Uuid,
+    front: String,
+    back: String,
+    studied_at: DateTime<Utc>,
+    status: CardStatus,
+    rating: Rating,
+    response_time_ms: Option<i32>,
+    user_answer: Option<String>,
+    quality: Option<i16>,
+    ease_factor_before: Option<f32>,
+    interval_days_before: Option<i32>,
+    repetitions_before: Option<i32>,
+    ease_factor_after: Option<f32>,
+    interval_days_after: Option<i32>,
+    repetitions_after: Option<i32>,
+    next_review_at: Option<DateTime<Utc>>,
+    lapsed: Option<bool>,
+}
+
+impl ReplayRow {
+    fn decision(&self) -> Option<ScheduleDecision> {
+        Some(ScheduleDecision {
+            quality: self.quality? as i32,
+            ease_factor_before: self.ease_factor_before?,
+            interval_days_before: self.interval_days_before?,
+            repetitions_before: self.repetitions_before?,
+            ease_factor_after: self.ease_factor_after?,
+            interval_days_after: self.interval_days_after?,
+            repetitions_after: self.repetitions_after?,
+            next_review_at: self.next_review_at?,
+            lapsed: self.lapsed.unwrap_or(false),
+        })
+    }
+}
+
 impl StudyService {
     pub async fn create_study_session(
         db: &PgPool,
         user_id: Uuid,
         dto: CreateStudySessionDto,
     ) -> Result<StudySession> {
-        // Verify deck access
-        let deck_access = sqlx::query!(
-            r#"
-            SELECT EXISTS(
-                SELECT 1 FROM decks
-                WHERE id = $1 AND owner_id = $2
-            ) as "exists!"
-            "#,
-            dto.deck_id,
-            user_id
-        )
-        .fetch_one(db)
-        .await?
-        .exists;
+        let study_mode = dto.study_mode.as_deref().unwrap_or("standard");
+        if study_mode == TIMED_STUDY_MODE && dto.time_limit_seconds.is_none() {
+            return Err(AppError::ValidationError(
+                "time_limit_seconds is required for timed sessions".to_string(),
+            ));
+        }
+
+        if study_mode == QUIZ_STUDY_MODE && (dto.folder_id.is_some() || dto.smart_deck_id.is_some()) {
+            return Err(AppError::BadRequest(
+                "Quiz sessions study a single deck".to_string(),
+            ));
+        }
 
-        if !deck_access {
-            return Err(AppError::NotFound("Resource not found".to_string()));
+        // Verify deck, folder or smart deck access
+        match (dto.deck_id, dto.folder_id, dto.smart_deck_id) {
+            (Some(deck_id), None, None) => {
+                SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+            }
+            (None, Some(folder_id), None) => {
+                FolderService::get_folder(db, folder_id, user_id).await?;
+            }
+            (None, None, Some(smart_deck_id)) => {
+                SmartDeckService::get(db, smart_deck_id, user_id).await?;
+            }
+            _ => {
+                return Err(AppError::ValidationError(
+                    "Provide one of deck_id, folder_id or smart_deck_id".to_string(),
+                ))
+            }
         }
 
         let session = sqlx::query_as!(
             StudySession,
             r#"
-            INSERT INTO study_sessions (user_id, deck_id, study_mode)
-            VALUES ($1, $2, $3)
-            RETURNING id, user_id, deck_id, study_mode, total_cards, cards_studied, 
+            INSERT INTO study_sessions (user_id, deck_id, folder_id, smart_deck_id, study_mode, time_limit_seconds, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW() + $6::INT * INTERVAL '1 second')
+            RETURNING id, user_id, deck_id, folder_id, smart_deck_id, study_mode, total_cards, cards_studied, 
                      cards_correct, cards_incorrect, cards_skipped, duration_seconds,
-                     started_at, completed_at, created_at, updated_at
+                     time_limit_seconds, expires_at,
+                     CASE WHEN completed_at IS NULL AND expires_at IS NOT NULL
+                         THEN GREATEST(0, CEIL(EXTRACT(EPOCH FROM expires_at - NOW())))::INT
+                     END as remaining_seconds,
+                     started_at, completed_at, abandoned_at, created_at, updated_at
             "#,
             user_id,
             dto.deck_id,
-            dto.study_mode.as_deref().unwrap_or("standard")
+            dto.folder_id,
+            dto.smart_deck_id,
+            study_mode,
+            dto.time_limit_seconds
         )
         .fetch_one(db)
         .await?;
@@ -64,9 +163,13 @@ impl StudyService {
         let session = sqlx::query_as!(
             StudySession,
             r#"
-            SELECT id, user_id, deck_id, study_mode, total_cards, cards_studied,
+            SELECT id, user_id, deck_id, folder_id, smart_deck_id, study_mode, total_cards, cards_studied,
                    cards_correct, cards_incorrect, cards_skipped, duration_seconds,
-                   started_at, completed_at, created_at, updated_at
+                   time_limit_seconds, expires_at,
+                   CASE WHEN completed_at IS NULL AND expires_at IS NOT NULL
+                       THEN GREATEST(0, CEIL(EXTRACT(EPOCH FROM expires_at - NOW())))::INT
+                   END as remaining_seconds,
+                   started_at, completed_at, abandoned_at, created_at, updated_at
             FROM study_sessions
             WHERE id = $1 AND user_id = $2
             "#,
@@ -75,7 +178,7 @@ impl StudyService {
         )
         .fetch_optional(db)
         .await?
-        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+        .ok_or(AppError::StudySessionNotFound)?;
 
         Ok(session)
     }
@@ -85,69 +188,711 @@ impl StudyService {
         session_id: Uuid,
         card_id: Uuid,
         user_id: Uuid,
-        status: CardStatus,
+        rating: Rating,
         response_time_ms: Option<i32>,
     ) -> Result<CardProgress> {
-        // Verify session ownership
-        let session = Self::get_study_session(db, session_id, user_id).await?;
+        let session = Self::self_graded_session(db, session_id, user_id).await?;
+        Self::record_answer(db, &session, card_id, rating, response_time_ms).await
+    }
 
-        // Verify card belongs to the deck being studied
-        let card_in_deck = sqlx::query!(
-            r#"
-            SELECT EXISTS(
-                SELECT 1 FROM cards
-                WHERE id = $1 AND deck_id = $2
-            ) as "exists!"
-            "#,
-            card_id,
-            session.deck_id
+    /// Grade `dto.user_answer` against the card back and record it. A wrong
+    /// answer is always rated "again"; a correct one keeps the user's rating
+    /// unless that rating is "again", and defaults to "good".
+    pub async fn record_typed_answer(
+        db: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+        dto: &RecordProgressDto,
+        similarity_threshold: f64,
+    ) -> Result<GradedCardProgress> {
+        let session = Self::self_graded_session(db, session_id, user_id).await?;
+        let deck_id = Self::card_deck(db, &session, dto.card_id).await?;
+        // Encrypted backs are ciphertext to the server
+        EncryptionService::ensure_plaintext(db, deck_id).await?;
+
+        let back = sqlx::query_scalar::<_, String>(
+            "SELECT back FROM cards WHERE id = $1 AND deleted_at IS NULL",
         )
+        .bind(dto.card_id)
         .fetch_one(db)
-        .await?
-        .exists;
+        .await?;
+
+        let user_answer = dto.user_answer.as_deref().unwrap_or_default();
+        let grade = grading::grade(&back, user_answer, similarity_threshold);
+        let rating = match dto.rating.or(dto.status.map(Rating::from_status)) {
+            _ if !grade.is_correct => Rating::Again,
+            Some(rating) if rating.is_correct() => rating,
+            _ => Rating::Good,
+        };
+
+        let mut progress =
+            Self::record_answer(db, &session, dto.card_id, rating, dto.response_time_ms).await?;
+        Self::set_answer(db, &mut progress, user_answer, grade.is_correct).await?;
+
+        Ok(GradedCardProgress {
+            progress,
+            grade: Some(grade),
+            new_achievements: vec![],
+        })
+    }
+
+    /// Store the answer the server graded on a recorded review
+    pub async fn set_answer(
+        db: &PgPool,
+        progress: &mut CardProgress,
+        user_answer: &str,
+        is_correct: bool,
+    ) -> Result<()> {
+        sqlx::query("UPDATE card_progress SET user_answer = $2, is_correct = $3 WHERE id = $1")
+            .bind(progress.id)
+            .bind(user_answer)
+            .bind(is_correct)
+            .execute(db)
+            .await?;
 
-        if !card_in_deck {
-            return Err(AppError::BadRequest("Card not in study deck".to_string()));
+        progress.user_answer = Some(user_answer.to_string());
+        progress.is_correct = Some(is_correct);
+        Ok(())
+    }
+
+    /// Record an answer in `session`, update its counters and reschedule the
+    /// card. Callers have already checked the session belongs to the user.
+    pub async fn record_answer(
+        db: &PgPool,
+        session: &StudySession,
+        card_id: Uuid,
+        rating: Rating,
+        response_time_ms: Option<i32>,
+    ) -> Result<CardProgress> {
+        let (session_id, user_id) = (session.id, session.user_id);
+        Self::ensure_accepting_answers(session)?;
+
+        // Verify card belongs to a deck being studied
+        let deck_id = Self::card_deck(db, session, card_id).await?;
+
+        let scale = DeckService::rating_scale_of(db, deck_id).await?;
+        if !scale.allows(rating) {
+            return Err(AppError::BadRequest(format!(
+                "'{}' is not a rating on this deck's {}-button scale",
+                rating.as_str(),
+                scale.buttons()
+            )));
         }
 
+        // The answer, session counters and user totals move together
+        let mut tx = db.begin().await?;
+
         // Record the progress
         let progress = sqlx::query_as!(
             CardProgress,
             r#"
-            INSERT INTO card_progress (session_id, card_id, user_id, status, response_time_ms)
-            VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, session_id, card_id, user_id, status as "status: CardStatus", 
-                     response_time_ms, user_answer, is_correct, studied_at, created_at
+            INSERT INTO card_progress (session_id, card_id, user_id, status, rating, response_time_ms)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, session_id, card_id, user_id, status as "status: CardStatus",
+                     rating as "rating: Rating", response_time_ms, user_answer, is_correct,
+                     studied_at, created_at
             "#,
             session_id,
             card_id,
             user_id,
-            status as CardStatus,
+            CardStatus::from_rating(rating) as CardStatus,
+            rating as Rating,
             response_time_ms
         )
-        .fetch_one(db)
+        .fetch_one(&mut *tx)
         .await?;
 
         // Update session statistics
-        let is_correct = matches!(status, CardStatus::Easy | CardStatus::Medium);
+        let is_correct = rating.is_correct();
         
         sqlx::query!(
             r#"
             UPDATE study_sessions
             SET 
                 cards_studied = cards_studied + 1,
-                cards_correct = cards_correct + $2
+                cards_correct = cards_correct + $2,
+                last_card_id = $3,
+                last_activity_at = NOW()
             WHERE id = $1
             "#,
             session_id,
-            if is_correct { 1 } else { 0 }
+            if is_correct { 1 } else { 0 },
+            card_id
         )
-        .execute(db)
+        .execute(&mut *tx)
         .await?;
 
+        StatsService::record_answer(&mut tx, user_id, rating, response_time_ms).await?;
+        tx.commit().await?;
+
+        let settings = DeckSettingsService::settings_of(db, deck_id).await?;
+        let decision =
+            Self::update_card_schedule(db, user_id, card_id, &settings, rating, response_time_ms).await?;
+        Self::record_schedule_decision(db, progress.id, &decision).await?;
+
         Ok(progress)
     }
 
+    /// Reject answers to an abandoned session, or to a timed session once
+    /// its limit has passed even before the sweeper has completed it
+    pub fn ensure_accepting_answers(session: &StudySession) -> Result<()> {
+        if session.abandoned_at.is_some() {
+            return Err(AppError::BadRequest(
+                "The session was abandoned; start a new one".to_string(),
+            ));
+        }
+        match session.expires_at {
+            Some(expires_at) if expires_at <= Utc::now() => Err(AppError::BadRequest(
+                "The session's time limit has expired".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Complete unfinished timed sessions whose limit has passed, as of the
+    /// moment they expired. Returns how many were completed.
+    pub async fn complete_expired_sessions(db: &PgPool) -> Result<u64> {
+        let completed = sqlx::query(
+            r#"
+            UPDATE study_sessions
+            SET completed_at = expires_at, duration_seconds = time_limit_seconds, updated_at = NOW()
+            WHERE completed_at IS NULL AND expires_at <= NOW()
+            "#,
+        )
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        Ok(completed)
+    }
+
+    /// Mark unfinished sessions without activity for `idle_for` as
+    /// abandoned, keeping their counters and the time studied until the
+    /// last answer. Returns how many were abandoned.
+    pub async fn abandon_stale_sessions(db: &PgPool, idle_for: std::time::Duration) -> Result<u64> {
+        let abandoned = sqlx::query(
+            r#"
+            UPDATE study_sessions
+            SET abandoned_at = NOW(),
+                duration_seconds = COALESCE(
+                    duration_seconds,
+                    EXTRACT(EPOCH FROM COALESCE(last_activity_at, started_at) - started_at)::INT
+                ),
+                active_device_id = NULL,
+                updated_at = NOW()
+            WHERE completed_at IS NULL AND abandoned_at IS NULL
+                AND COALESCE(last_activity_at, started_at) < NOW() - make_interval(secs => $1)
+            "#,
+        )
+        .bind(idle_for.as_secs_f64())
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        Ok(abandoned)
+    }
+
+    /// Every `every`, complete expired timed sessions and, when
+    /// `abandon_after` is set, abandon sessions idle for that long
+    pub fn spawn_session_sweeper(
+        db: PgPool,
+        every: std::time::Duration,
+        abandon_after: Option<std::time::Duration>,
+    ) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match Self::complete_expired_sessions(&db).await {
+                    Ok(0) => {}
+                    Ok(completed) => {
+                        tracing::info!("Completed {} expired timed study sessions", completed);
+                    }
+                    Err(e) => tracing::warn!("Study session expiry sweep failed: {}", e),
+                }
+                let Some(idle_for) = abandon_after else { continue };
+                match Self::abandon_stale_sessions(&db, idle_for).await {
+                    Ok(0) => {}
+                    Ok(abandoned) => tracing::info!("Abandoned {} stale study sessions", abandoned),
+                    Err(e) => tracing::warn!("Abandoned study session sweep failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// The decks a session studies: its deck, the decks currently in its
+    /// folder and subfolders, or the decks its smart deck draws from
+    pub async fn session_deck_ids(db: &PgPool, session: &StudySession) -> Result<Vec<Uuid>> {
+        match (session.deck_id, session.folder_id, session.smart_deck_id) {
+            (Some(deck_id), _, _) => Ok(vec![deck_id]),
+            (None, Some(folder_id), _) => {
+                FolderService::subtree_deck_ids(db, folder_id, session.user_id).await
+            }
+            (None, None, Some(smart_deck_id)) => {
+                let smart_deck = SmartDeckService::get(db, smart_deck_id, session.user_id).await?;
+                SmartDeckService::deck_ids(db, session.user_id, &smart_deck.filter).await
+            }
+            // The session's folder or smart deck was deleted
+            (None, None, None) => Ok(vec![]),
+        }
+    }
+
+    /// Answers in the session grouped by the deck of each card
+    pub async fn session_deck_breakdown(
+        db: &PgPool,
+        session_id: Uuid,
+    ) -> Result<Vec<SessionDeckBreakdown>> {
+        let decks = sqlx::query_as::<_, SessionDeckBreakdown>(
+            r#"
+            SELECT d.id as deck_id, d.title as deck_name, COUNT(*) as cards_studied,
+                   COUNT(*) FILTER (WHERE cp.rating <> 'again') as cards_correct,
+                   COUNT(*) FILTER (WHERE cp.rating = 'again') as cards_incorrect
+            FROM card_progress cp
+            JOIN cards c ON c.id = cp.card_id
+            JOIN decks d ON d.id = c.deck_id
+            WHERE cp.session_id = $1
+            GROUP BY d.id, d.title
+            ORDER BY cards_studied DESC, d.title
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(decks)
+    }
+
+    /// The deck of `card_id`, when it is one of the decks the session studies
+    pub(crate) async fn card_deck(db: &PgPool, session: &StudySession, card_id: Uuid) -> Result<Uuid> {
+        let deck_ids = Self::session_deck_ids(db, session).await?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT deck_id FROM cards WHERE id = $1 AND deck_id = ANY($2) AND deleted_at IS NULL",
+        )
+        .bind(card_id)
+        .bind(&deck_ids)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Card not in study deck".to_string()))
+    }
+
+    /// The user's session, unless answers in it are graded by quiz questions
+    async fn self_graded_session(db: &PgPool, session_id: Uuid, user_id: Uuid) -> Result<StudySession> {
+        let session = Self::get_study_session(db, session_id, user_id).await?;
+        if session.study_mode == QUIZ_STUDY_MODE {
+            return Err(AppError::BadRequest(
+                "Quiz sessions are graded by answering quiz questions".to_string(),
+            ));
+        }
+        Ok(session)
+    }
+
+    /// Apply a review to the user's per-card scheduling stats, with the
+    /// deck's algorithm and lapse action
+    async fn update_card_schedule(
+        db: &PgPool,
+        user_id: Uuid,
+        card_id: Uuid,
+        settings: &DeckSettings,
+        rating: Rating,
+        response_time_ms: Option<i32>,
+    ) -> Result<ScheduleDecision> {
+        let current = sqlx::query_as::<_, (f32, i32, i32, Option<i32>)>(
+            r#"
+            SELECT ease_factor, interval_days, repetitions, leitner_box
+            FROM user_card_stats WHERE user_id = $1 AND card_id = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .fetch_optional(db)
+        .await?;
+
+        let (ease_factor, interval, repetitions, leitner_box) =
+            current.unwrap_or((DEFAULT_EASE_FACTOR, 0, 0, None));
+        let quality = Sm2Scheduler::quality_for_rating(rating);
+        let mut next = scheduler::schedule(
+            settings,
+            &SpacedRepetitionParams {
+                algorithm: settings.algorithm.as_str().to_string(),
+                ease_factor,
+                interval,
+                repetitions,
+                leitner_box,
+                quality,
+            },
+        );
+        let is_correct = quality >= 3;
+        // Forgetting a card that had already been learned counts as a lapse
+        let lapsed = !is_correct && repetitions > 0;
+        if lapsed && settings.lapse_action == LapseAction::Reset {
+            next.next_ease_factor = DEFAULT_EASE_FACTOR;
+            next.difficulty_adjustment = DEFAULT_EASE_FACTOR - ease_factor;
+        }
+
+        let newly_leech = sqlx::query_scalar::<_, bool>(
+            r#"
+            INSERT INTO user_card_stats (
+                user_id, card_id, times_seen, times_correct, times_incorrect,
+                average_response_time_ms, last_seen_at, next_review_at,
+                ease_factor, interval_days, repetitions, lapses, leitner_box
+            )
+            VALUES ($1, $2, 1, $3, $4, $5, NOW(), $6, $7, $8, $9, $10, $12)
+            ON CONFLICT (user_id, card_id) DO UPDATE SET
+                times_seen = user_card_stats.times_seen + 1,
+                times_correct = user_card_stats.times_correct + $3,
+                times_incorrect = user_card_stats.times_incorrect + $4,
+                average_response_time_ms = CASE
+                    WHEN $5::INTEGER IS NULL THEN user_card_stats.average_response_time_ms
+                    WHEN user_card_stats.average_response_time_ms IS NULL THEN $5
+                    ELSE (user_card_stats.average_response_time_ms * user_card_stats.times_seen + $5)
+                         / (user_card_stats.times_seen + 1)
+                END,
+                last_seen_at = NOW(),
+                next_review_at = $6,
+                ease_factor = $7,
+                interval_days = $8,
+                repetitions = $9,
+                lapses = user_card_stats.lapses + $10,
+                leitner_box = $12,
+                leech_flagged_at = CASE
+                    WHEN user_card_stats.leech_flagged_at IS NULL
+                        AND user_card_stats.lapses + $10 >= $11 THEN NOW()
+                    ELSE user_card_stats.leech_flagged_at
+                END,
+                updated_at = NOW()
+            RETURNING leech_flagged_at IS NOT NULL AND leech_flagged_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .bind(if is_correct { 1 } else { 0 })
+        .bind(if is_correct { 0 } else { 1 })
+        .bind(response_time_ms)
+        .bind(next.next_review_date)
+        .bind(next.next_ease_factor)
+        .bind(next.next_interval)
+        .bind(next.next_repetitions)
+        .bind(if lapsed { 1 } else { 0 })
+        .bind(settings.leech_threshold)
+        .bind(next.next_leitner_box)
+        .fetch_one(db)
+        .await?;
+
+        if newly_leech {
+            LeechService::mark_leech(db, user_id, card_id, settings.leech_action).await?;
+        }
+
+        Ok(ScheduleDecision {
+            quality,
+            ease_factor_before: ease_factor,
+            interval_days_before: interval,
+            repetitions_before: repetitions,
+            ease_factor_after: next.next_ease_factor,
+            interval_days_after: next.next_interval,
+            repetitions_after: next.next_repetitions,
+            next_review_at: next.next_review_date,
+            lapsed,
+        })
+    }
+
+    /// Keep the scheduler's decision with the answer for session replay
+    async fn record_schedule_decision(
+        db: &PgPool,
+        progress_id: Uuid,
+        decision: &ScheduleDecision,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE card_progress
+            SET quality = $2, ease_factor_before = $3, interval_days_before = $4,
+                repetitions_before = $5, ease_factor_after = $6, interval_days_after = $7,
+                repetitions_after = $8, next_review_at = $9, lapsed = $10
+            WHERE id = $1
+            "#,
+        )
+        .bind(progress_id)
+        .bind(decision.quality as i16)
+        .bind(decision.ease_factor_before)
+        .bind(decision.interval_days_before)
+        .bind(decision.repetitions_before)
+        .bind(decision.ease_factor_after)
+        .bind(decision.interval_days_after)
+        .bind(decision.repetitions_after)
+        .bind(decision.next_review_at)
+        .bind(decision.lapsed)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The session's answers in order with the scheduler's decision after each
+    pub async fn get_session_replay(
+        db: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<SessionReplay> {
+        let session = Self::get_study_session(db, session_id, user_id).await?;
+
+        let rows = sqlx::query_as::<_, ReplayRow>(
+            r#"
+            SELECT cp.id as progress_id, cp.session_id, cp.card_id, c.front, c.back, cp.studied_at,
+                   cp.status, cp.rating, cp.response_time_ms, cp.user_answer,
+                   cp.quality, cp.ease_factor_before, cp.interval_days_before,
+                   cp.repetitions_before, cp.ease_factor_after, cp.interval_days_after,
+                   cp.repetitions_after, cp.next_review_at, cp.lapsed
+            FROM card_progress cp
+            JOIN cards c ON c.id = cp.card_id
+            WHERE cp.session_id = $1
+            ORDER BY cp.studied_at, cp.created_at, cp.id
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(db)
+        .await?;
+
+        let events = rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| SessionReplayEvent {
+                sequence: i as i64 + 1,
+                scheduler: row.decision(),
+                progress_id: row.progress_id,
+                card_id: row.card_id,
+                elapsed_ms: (row.studied_at - session.started_at).num_milliseconds().max(0),
+                studied_at: row.studied_at,
+                status: row.status,
+                rating: row.rating,
+                response_time_ms: row.response_time_ms,
+                front: row.front,
+                back: row.back,
+            })
+            .collect();
+
+        Ok(SessionReplay { session, events })
+    }
+
+    /// The user's answers to a card across all their sessions, with the
+    /// scheduler's decision after each. Card edits are not versioned; the
+    /// card's `created_at` and `updated_at` are all there is.
+    pub async fn get_card_history(db: &PgPool, card_id: Uuid, user_id: Uuid) -> Result<CardHistory> {
+        let card = CardService::get_card(db, card_id, user_id).await?;
+
+        let rows = sqlx::query_as::<_, ReplayRow>(
+            r#"
+            SELECT cp.id as progress_id, cp.session_id, cp.card_id, c.front, c.back, cp.studied_at,
+                   cp.status, cp.rating, cp.response_time_ms, cp.user_answer,
+                   cp.quality, cp.ease_factor_before, cp.interval_days_before,
+                   cp.repetitions_before, cp.ease_factor_after, cp.interval_days_after,
+                   cp.repetitions_after, cp.next_review_at, cp.lapsed
+            FROM card_progress cp
+            JOIN cards c ON c.id = cp.card_id
+            JOIN study_sessions s ON s.id = cp.session_id
+            WHERE cp.card_id = $1 AND cp.user_id = $2 AND s.deleted_at IS NULL
+            ORDER BY cp.studied_at, cp.created_at, cp.id
+            "#,
+        )
+        .bind(card_id)
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        let reviews: Vec<CardReview> = rows
+            .into_iter()
+            .map(|row| CardReview {
+                scheduler: row.decision(),
+                progress_id: row.progress_id,
+                session_id: row.session_id,
+                studied_at: row.studied_at,
+                status: row.status,
+                rating: row.rating,
+                response_time_ms: row.response_time_ms,
+                user_answer: row.user_answer,
+            })
+            .collect();
+
+        let response_times: Vec<i64> =
+            reviews.iter().filter_map(|r| r.response_time_ms).map(i64::from).collect();
+        let average_response_time_ms = (!response_times.is_empty())
+            .then(|| (response_times.iter().sum::<i64>() / response_times.len() as i64) as i32);
+
+        Ok(CardHistory {
+            card,
+            total_reviews: reviews.len() as i64,
+            lapses: reviews
+                .iter()
+                .filter(|r| r.scheduler.as_ref().is_some_and(|s| s.lapsed))
+                .count() as i64,
+            average_response_time_ms,
+            next_review_at: reviews.iter().rev().find_map(|r| r.scheduler.as_ref()).map(|s| s.next_review_at),
+            reviews,
+        })
+    }
+
+    /// Accuracy, response times, ratings and the hardest cards of a session.
+    /// Cards rank by how often they were forgotten, then rated hard, then by
+    /// how long they took.
+    pub async fn get_session_stats(
+        db: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<SessionStats> {
+        Self::get_study_session(db, session_id, user_id).await?;
+
+        let row = sqlx::query_as::<_, SessionStatsRow>(
+            r#"
+            WITH answers AS (
+                SELECT id, card_id, rating, response_time_ms, studied_at, created_at
+                FROM card_progress
+                WHERE session_id = $1
+            ),
+            per_card AS (
+                SELECT a.card_id, c.front,
+                       COUNT(*) as answers,
+                       COUNT(*) FILTER (WHERE a.rating = 'again') as again_count,
+                       COUNT(*) FILTER (WHERE a.rating = 'hard') as hard_count,
+                       AVG(a.response_time_ms)::DOUBLE PRECISION as average_response_time_ms,
+                       (ARRAY_AGG(a.rating ORDER BY a.studied_at DESC, a.created_at DESC, a.id DESC))[1]
+                           as last_rating
+                FROM answers a
+                JOIN cards c ON c.id = a.card_id
+                GROUP BY a.card_id, c.front
+            )
+            SELECT COUNT(*) as total_answers,
+                   COUNT(DISTINCT card_id) as total_cards,
+                   COUNT(*) FILTER (WHERE rating <> 'again') as correct_answers,
+                   COUNT(*) FILTER (WHERE rating = 'again') as again_count,
+                   COUNT(*) FILTER (WHERE rating = 'hard') as hard_count,
+                   COUNT(*) FILTER (WHERE rating = 'good') as good_count,
+                   COUNT(*) FILTER (WHERE rating = 'easy') as easy_count,
+                   AVG(response_time_ms)::DOUBLE PRECISION as average_response_time_ms,
+                   PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY response_time_ms)
+                       as median_response_time_ms,
+                   COALESCE((
+                       SELECT JSONB_AGG(TO_JSONB(p) ORDER BY p.again_count DESC, p.hard_count DESC,
+                                        p.average_response_time_ms DESC NULLS LAST)
+                       FROM per_card p
+                   ), '[]'::JSONB) as cards
+            FROM answers
+            "#,
+        )
+        .bind(session_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(SessionStats {
+            session_id,
+            accuracy: if row.total_answers > 0 {
+                row.correct_answers as f64 / row.total_answers as f64
+            } else {
+                0.0
+            },
+            total_answers: row.total_answers,
+            total_cards: row.total_cards,
+            correct_answers: row.correct_answers,
+            average_response_time_ms: row.average_response_time_ms,
+            median_response_time_ms: row.median_response_time_ms,
+            rating_distribution: RatingDistribution {
+                again: row.again_count,
+                hard: row.hard_count,
+                good: row.good_count,
+                easy: row.easy_count,
+            },
+            cards: row.cards.0,
+        })
+    }
+
+    /// Most recently used unfinished session with the cards still to study
+    pub async fn get_active_session(db: &PgPool, user_id: Uuid) -> Result<ActiveStudySession> {
+        let session_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM study_sessions
+            WHERE user_id = $1 AND completed_at IS NULL AND abandoned_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+            ORDER BY COALESCE(last_activity_at, started_at) DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("No active study session".to_string()))?;
+
+        let session = Self::get_study_session(db, session_id, user_id).await?;
+
+        let (active_device_id, last_activity_at, last_card_id) =
+            sqlx::query_as::<_, (Option<String>, Option<DateTime<Utc>>, Option<Uuid>)>(
+                "SELECT active_device_id, last_activity_at, last_card_id FROM study_sessions WHERE id = $1",
+            )
+            .bind(session_id)
+            .fetch_one(db)
+            .await?;
+
+        let last_card = match last_card_id {
+            Some(card_id) => {
+                sqlx::query_as::<_, Card>("SELECT * FROM cards WHERE id = $1")
+                    .bind(card_id)
+                    .fetch_optional(db)
+                    .await?
+            }
+            None => None,
+        };
+
+        // Folder sessions alternate between decks at each position
+        let deck_ids = Self::session_deck_ids(db, &session).await?;
+        let remaining_cards = sqlx::query_as::<_, Card>(
+            r#"
+            SELECT c.* FROM cards c
+            WHERE c.deck_id = ANY($1) AND c.deleted_at IS NULL
+                AND NOT EXISTS(
+                    SELECT 1 FROM card_progress cp
+                    WHERE cp.session_id = $2 AND cp.card_id = c.id
+                )
+            ORDER BY c.position, c.deck_id
+            "#,
+        )
+        .bind(&deck_ids)
+        .bind(session_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(ActiveStudySession {
+            session,
+            active_device_id,
+            last_activity_at,
+            last_card,
+            remaining_cards,
+        })
+    }
+
+    /// Record `device_id` as the device driving the session. Returns the
+    /// previous device when this is a takeover from another device.
+    pub async fn claim_session_device(
+        db: &PgPool,
+        session_id: Uuid,
+        user_id: Uuid,
+        device_id: &str,
+    ) -> Result<Option<String>> {
+        let previous = sqlx::query_scalar::<_, Option<String>>(
+            r#"
+            UPDATE study_sessions s
+            SET active_device_id = $3, last_activity_at = NOW()
+            FROM (SELECT active_device_id FROM study_sessions WHERE id = $1 FOR UPDATE) prev
+            WHERE s.id = $1 AND s.user_id = $2
+            RETURNING prev.active_device_id
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(device_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::StudySessionNotFound)?;
+
+        Ok(previous.filter(|previous| previous != device_id))
+    }
+
     pub async fn complete_study_session(
         db: &PgPool,
         session_id: Uuid,
@@ -160,11 +905,15 @@ impl StudyService {
             StudySession,
             r#"
             UPDATE study_sessions
-            SET completed_at = $2, updated_at = $2
+            SET completed_at = LEAST($2, COALESCE(expires_at, $2)), updated_at = $2
             WHERE id = $1 AND user_id = $3
-            RETURNING id, user_id, deck_id, study_mode, total_cards, cards_studied,
+            RETURNING id, user_id, deck_id, folder_id, smart_deck_id, study_mode, total_cards, cards_studied,
                      cards_correct, cards_incorrect, cards_skipped, duration_seconds,
-                     started_at, completed_at, created_at, updated_at
+                     time_limit_seconds, expires_at,
+                     CASE WHEN completed_at IS NULL AND expires_at IS NOT NULL
+                         THEN GREATEST(0, CEIL(EXTRACT(EPOCH FROM expires_at - NOW())))::INT
+                     END as remaining_seconds,
+                     started_at, completed_at, abandoned_at, created_at, updated_at
             "#,
             session_id,
             Utc::now(),
@@ -186,9 +935,13 @@ impl StudyService {
         let sessions = sqlx::query_as!(
             StudySession,
             r#"
-            SELECT id, user_id, deck_id, study_mode, total_cards, cards_studied,
+            SELECT id, user_id, deck_id, folder_id, smart_deck_id, study_mode, total_cards, cards_studied,
                    cards_correct, cards_incorrect, cards_skipped, duration_seconds,
-                   started_at, completed_at, created_at, updated_at
+                   time_limit_seconds, expires_at,
+                   CASE WHEN completed_at IS NULL AND expires_at IS NOT NULL
+                       THEN GREATEST(0, CEIL(EXTRACT(EPOCH FROM expires_at - NOW())))::INT
+                   END as remaining_seconds,
+                   started_at, completed_at, abandoned_at, created_at, updated_at
             FROM study_sessions
             WHERE user_id = $1
             ORDER BY started_at DESC
@@ -214,8 +967,9 @@ impl StudyService {
         let progress = sqlx::query_as!(
             CardProgress,
             r#"
-            SELECT id, session_id, card_id, user_id, status as "status: CardStatus", 
-                   response_time_ms, user_answer, is_correct, studied_at, created_at
+            SELECT id, session_id, card_id, user_id, status as "status: CardStatus",
+                   rating as "rating: Rating", response_time_ms, user_answer, is_correct,
+                   studied_at, created_at
             FROM card_progress
             WHERE session_id = $1
             ORDER BY studied_at
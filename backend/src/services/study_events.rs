@@ -0,0 +1,104 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::ai::{BatchAnalyticsEvent, BatchIngestResult},
+    utils::Result,
+};
+
+/// Raw study events for analytics, sent by clients one batch at a time
+pub struct StudyEventService;
+
+impl StudyEventService {
+    /// Store a batch in one statement. Events are numbered by their place in
+    /// the batch, so resending it only inserts the ones missing before.
+    /// Client timestamps in the future are taken as now. Nothing is stored
+    /// while the user has analytics tracking turned off.
+    pub async fn ingest_batch(
+        db: &PgPool,
+        user_id: Uuid,
+        batch: BatchAnalyticsEvent,
+    ) -> Result<BatchIngestResult> {
+        let received = batch.events.len();
+        let tracked = sqlx::query_scalar::<_, bool>(
+            "SELECT track_analytics FROM ai_privacy_settings WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .unwrap_or(true);
+        if !tracked {
+            return Ok(BatchIngestResult {
+                batch_id: batch.batch_id,
+                received,
+                inserted: 0,
+                duplicates: 0,
+                rejected: 0,
+            });
+        }
+
+        let events = &batch.events;
+        let (inserted, valid) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            WITH batch AS (
+                SELECT e.*, (e.ordinality - 1)::INTEGER as batch_index
+                FROM UNNEST(
+                    $3::UUID[], $4::UUID[], $5::UUID[], $6::TEXT[], $7::TEXT[],
+                    $8::INTEGER[], $9::INTEGER[], $10::TIMESTAMPTZ[]
+                ) WITH ORDINALITY AS e(
+                    card_id, deck_id, session_id, event_type, outcome,
+                    response_time_ms, confidence_rating, occurred_at, ordinality
+                )
+            ),
+            valid AS (
+                SELECT b.*, s.id as owned_session_id,
+                       COALESCE(st.ease_factor, 2.5) as ease_factor,
+                       COALESCE(st.interval_days, 0) as interval_days,
+                       COALESCE(st.repetitions, 0) as repetition_number
+                FROM batch b
+                JOIN cards c ON c.id = b.card_id AND c.deck_id = b.deck_id AND c.deleted_at IS NULL
+                JOIN decks d ON d.id = c.deck_id AND d.deleted_at IS NULL
+                LEFT JOIN study_sessions s ON s.id = b.session_id AND s.user_id = $1
+                LEFT JOIN user_card_stats st ON st.card_id = c.id AND st.user_id = $1
+                WHERE d.owner_id = $1 OR d.is_public = true
+                    OR EXISTS(SELECT 1 FROM deck_shares ds WHERE ds.deck_id = d.id AND ds.user_id = $1)
+            ),
+            inserted AS (
+                INSERT INTO study_events (
+                    user_id, card_id, deck_id, session_id, event_type, outcome, response_time_ms,
+                    confidence_rating, ease_factor, interval_days, repetition_number,
+                    occurred_at, batch_id, batch_index
+                )
+                SELECT $1, card_id, deck_id, owned_session_id, event_type, outcome, response_time_ms,
+                       confidence_rating, ease_factor, interval_days, repetition_number,
+                       LEAST(COALESCE(occurred_at, $11), NOW()), $2, batch_index
+                FROM valid
+                ON CONFLICT (user_id, batch_id, batch_index) WHERE batch_id IS NOT NULL DO NOTHING
+                RETURNING 1
+            )
+            SELECT (SELECT COUNT(*) FROM inserted), (SELECT COUNT(*) FROM valid)
+            "#,
+        )
+        .bind(user_id)
+        .bind(batch.batch_id)
+        .bind(events.iter().map(|e| e.card_id).collect::<Vec<_>>())
+        .bind(events.iter().map(|e| e.deck_id).collect::<Vec<_>>())
+        .bind(events.iter().map(|e| e.session_id).collect::<Vec<_>>())
+        .bind(events.iter().map(|e| e.event_type.clone()).collect::<Vec<_>>())
+        .bind(events.iter().map(|e| e.outcome.clone()).collect::<Vec<_>>())
+        .bind(events.iter().map(|e| e.response_time_ms).collect::<Vec<_>>())
+        .bind(events.iter().map(|e| e.confidence_rating).collect::<Vec<_>>())
+        .bind(events.iter().map(|e| e.occurred_at).collect::<Vec<_>>())
+        .bind(batch.timestamp)
+        .fetch_one(db)
+        .await?;
+
+        Ok(BatchIngestResult {
+            batch_id: batch.batch_id,
+            received,
+            inserted,
+            duplicates: valid - inserted,
+            rejected: received as i64 - valid,
+        })
+    }
+}
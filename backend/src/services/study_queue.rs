@@ -0,0 +1,445 @@
+use chrono::{DateTime, Datelike, Utc};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use sqlx::{FromRow, PgPool};
+use std::collections::{HashMap, HashSet, VecDeque};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        ai::{StudyCardSuggestion, StudyQueue, StudyQueueCounts, StudyQueueQuery},
+        DeckRole, DeckSettings, NewCardOrder,
+    },
+    services::{
+        deck_settings::DeckSettingsService, folder::FolderService, preferences::PreferencesService,
+        scheduler::DEFAULT_EASE_FACTOR, sharing::SharingService, smart_deck::SmartDeckService,
+    },
+    utils::{AppError, Result},
+};
+
+const MAX_NEW_CARDS_LIMIT: i32 = 500;
+
+/// Cards are reviews once their interval passes this many days; shorter
+/// intervals are still being learned
+const LEARNING_MAX_INTERVAL_DAYS: i32 = 1;
+
+/// Fewest other cards between two siblings queued the same day
+pub const MIN_SIBLING_GAP: usize = 5;
+
+/// A deck's answers since the start of the day (UTC)
+#[derive(Debug, FromRow)]
+struct StudiedToday {
+    deck_id: Uuid,
+    new_cards: i64,
+    reviews: i64,
+}
+
+/// What a deck's daily limits still allow today
+#[derive(Debug, Clone, Copy, Default)]
+struct DailyAllowance {
+    new_cards: usize,
+    reviews: usize,
+}
+
+#[derive(Debug, FromRow)]
+struct QueueCandidate {
+    card_id: Uuid,
+    deck_id: Uuid,
+    position: i32,
+    times_seen: Option<i32>,
+    times_correct: Option<i32>,
+    times_incorrect: Option<i32>,
+    average_response_time_ms: Option<i32>,
+    next_review_at: Option<DateTime<Utc>>,
+    ease_factor: Option<f32>,
+    interval_days: Option<i32>,
+    starred: bool,
+    /// Shared by a card and its reverse sibling
+    sibling_group: Uuid,
+    sibling_studied_today: bool,
+}
+
+impl QueueCandidate {
+    fn is_new(&self) -> bool {
+        self.times_seen.unwrap_or(0) == 0
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_review_at.map_or(true, |due| due <= now)
+    }
+
+    fn accuracy(&self) -> Option<f32> {
+        let seen = self.times_seen.unwrap_or(0);
+        (seen > 0).then(|| self.times_correct.unwrap_or(0) as f32 / seen as f32)
+    }
+
+    /// Difficulty estimate in 0.0-1.0 derived from ease factor (1.3 = hardest)
+    fn estimated_difficulty(&self) -> f32 {
+        let ease = self.ease_factor.unwrap_or(DEFAULT_EASE_FACTOR);
+        ((DEFAULT_EASE_FACTOR + 0.5 - ease) / 1.7).clamp(0.0, 1.0)
+    }
+
+    fn last_performance(&self) -> Option<String> {
+        self.accuracy().map(|accuracy| {
+            format!(
+                "{} correct, {} incorrect ({:.0}% accuracy)",
+                self.times_correct.unwrap_or(0),
+                self.times_incorrect.unwrap_or(0),
+                accuracy * 100.0
+            )
+        })
+    }
+
+    fn suggestion(&self, reason: String, priority_score: f32) -> StudyCardSuggestion {
+        StudyCardSuggestion {
+            card_id: self.card_id,
+            deck_id: self.deck_id,
+            reason,
+            priority_score,
+            estimated_difficulty: self.estimated_difficulty(),
+            last_performance: self.last_performance(),
+            suggested_time_seconds: self.average_response_time_ms.map(|ms| (ms / 1000).max(1)),
+        }
+    }
+}
+
+pub struct StudyQueueService;
+
+impl StudyQueueService {
+    /// Build an Anki-style queue for a deck: learning cards first, then due
+    /// reviews (most overdue first), then unseen cards. Each deck's daily
+    /// new-card and review limits, less what was studied today, cap its
+    /// share; `max_new_cards` can lower the new-card total further.
+    /// Suspended cards are left out. In decks that bury siblings, a card
+    /// whose reverse sibling was answered today is buried until tomorrow and
+    /// only one card of each pair is queued; elsewhere siblings are kept at
+    /// least `MIN_SIBLING_GAP` cards apart.
+    /// A folder queue covers every deck in the folder and its subfolders,
+    /// taking turns between decks within each group unless the user turned
+    /// `interleave_decks` off, in which case decks come one after another.
+    /// A smart deck queue works the same over the cards its filter matches.
+    /// With `new_card_order` set to random, new cards are shuffled once a
+    /// day instead of following deck position.
+    pub async fn build_queue(
+        db: &PgPool,
+        user_id: Uuid,
+        query: &StudyQueueQuery,
+    ) -> Result<StudyQueue> {
+        // A smart deck narrows its decks to the cards matching its filter now
+        let (deck_ids, card_ids) = match (query.deck_id, query.folder_id, query.smart_deck_id) {
+            (Some(deck_id), None, None) => {
+                SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Viewer).await?;
+                (vec![deck_id], None)
+            }
+            (None, Some(folder_id), None) => {
+                (FolderService::subtree_deck_ids(db, folder_id, user_id).await?, None)
+            }
+            (None, None, Some(smart_deck_id)) => {
+                let smart_deck = SmartDeckService::get(db, smart_deck_id, user_id).await?;
+                let deck_ids = SmartDeckService::deck_ids(db, user_id, &smart_deck.filter).await?;
+                let card_ids = SmartDeckService::card_ids(db, user_id, &smart_deck.filter).await?;
+                (deck_ids, Some(card_ids))
+            }
+            _ => {
+                return Err(AppError::ValidationError(
+                    "Provide one of deck_id, folder_id or smart_deck_id".to_string(),
+                ))
+            }
+        };
+
+        let candidates = sqlx::query_as::<_, QueueCandidate>(
+            r#"
+            SELECT c.id as card_id, c.deck_id, c.position, s.times_seen, s.times_correct, s.times_incorrect,
+                   s.average_response_time_ms, s.next_review_at, s.ease_factor, s.interval_days,
+                   COALESCE(f.starred, false) as starred,
+                   COALESCE(c.reverse_of, c.id) as sibling_group,
+                   EXISTS(
+                       SELECT 1 FROM card_progress cp
+                       JOIN cards o ON o.id = cp.card_id
+                       WHERE cp.user_id = $2 AND (o.id = c.reverse_of OR o.reverse_of = c.id)
+                         AND cp.created_at >= date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+                   ) as sibling_studied_today
+            FROM cards c
+            LEFT JOIN user_card_stats s ON s.card_id = c.id AND s.user_id = $2
+            LEFT JOIN user_card_flags f ON f.card_id = c.id AND f.user_id = $2
+            WHERE c.deck_id = ANY($1) AND c.deleted_at IS NULL
+              AND ($3::UUID[] IS NULL OR c.id = ANY($3))
+              AND NOT COALESCE(f.suspended, false)
+            ORDER BY c.position, c.created_at
+            "#,
+        )
+        .bind(&deck_ids)
+        .bind(user_id)
+        .bind(&card_ids)
+        .fetch_all(db)
+        .await?;
+
+        let settings = DeckSettingsService::settings_for(db, &deck_ids).await?;
+        let buries_siblings = |deck_id: Uuid| settings.get(&deck_id).map_or(true, |s| s.bury_siblings);
+        let allowances = Self::daily_allowances(db, user_id, &settings).await?;
+        let allowance = |deck_id: Uuid| allowances.get(&deck_id).copied().unwrap_or_default();
+
+        let preferences = PreferencesService::get(db, user_id).await?;
+        let interleave = preferences.interleave_decks;
+
+        let now = Utc::now();
+        let max_new = query
+            .max_new_cards
+            .map_or(usize::MAX, |max| max.clamp(0, MAX_NEW_CARDS_LIMIT) as usize);
+        let focus_weak = query.focus_weak_cards.unwrap_or(false);
+        let include_overdue = query.include_overdue.unwrap_or(true);
+        let prioritize_starred = query.prioritize_starred.unwrap_or(false);
+
+        let mut new_candidates = Vec::new();
+        let mut learning = Vec::new();
+        let mut review = Vec::new();
+        let mut buried = 0;
+
+        for candidate in &candidates {
+            if !candidate.is_new() && !candidate.is_due(now) {
+                continue;
+            }
+
+            if candidate.sibling_studied_today && buries_siblings(candidate.deck_id) {
+                buried += 1;
+                continue;
+            }
+
+            if candidate.is_new() {
+                new_candidates.push(candidate);
+                continue;
+            }
+
+            let weakness = 1.0 - candidate.accuracy().unwrap_or(1.0);
+            let weak_bonus = if focus_weak { weakness * 2.0 } else { 0.0 };
+
+            if candidate.interval_days.unwrap_or(0) <= LEARNING_MAX_INTERVAL_DAYS {
+                let reason = if candidate.times_incorrect.unwrap_or(0) > 0 {
+                    "Learning: recently forgotten"
+                } else {
+                    "Learning: early repetition"
+                };
+                learning.push(candidate.suggestion(reason.to_string(), 3.0 + weak_bonus));
+                continue;
+            }
+
+            let interval = candidate.interval_days.unwrap_or(1).max(1) as f32;
+            let overdue_days = candidate
+                .next_review_at
+                .map(|due| (now - due).num_hours() as f32 / 24.0)
+                .unwrap_or(0.0)
+                .max(0.0);
+
+            if !include_overdue && overdue_days >= 1.0 {
+                continue;
+            }
+
+            let reason = if overdue_days >= 1.0 {
+                format!("Review overdue by {} day(s)", overdue_days.floor() as i64)
+            } else {
+                "Review due today".to_string()
+            };
+            review.push(candidate.suggestion(reason, 2.0 + overdue_days / interval + weak_bonus));
+        }
+
+        let by_priority = |a: &StudyCardSuggestion, b: &StudyCardSuggestion| {
+            b.priority_score.total_cmp(&a.priority_score)
+        };
+        learning.sort_by(by_priority);
+        review.sort_by(by_priority);
+        if prioritize_starred {
+            // Stable, so each half keeps its priority order
+            let starred: HashSet<Uuid> =
+                candidates.iter().filter(|c| c.starred).map(|c| c.card_id).collect();
+            learning.sort_by_key(|s| !starred.contains(&s.card_id));
+            review.sort_by_key(|s| !starred.contains(&s.card_id));
+        }
+
+        // Preserve deck order for new cards unless the user shuffles them,
+        // starred ones first when asked
+        if preferences.new_card_order == NewCardOrder::Random {
+            let seed = user_id.as_u128() as u64 ^ now.date_naive().num_days_from_ce() as u64;
+            new_candidates.shuffle(&mut StdRng::seed_from_u64(seed));
+        }
+        if prioritize_starred {
+            new_candidates.sort_by_key(|c| !c.starred);
+        }
+
+        // One card per sibling pair: learning before review before new
+        let sibling_group: HashMap<Uuid, Uuid> =
+            candidates.iter().map(|c| (c.card_id, c.sibling_group)).collect();
+        let mut queued_groups = HashSet::new();
+        let queued = learning.len() + review.len() + new_candidates.len();
+        learning.retain(|s| !buries_siblings(s.deck_id) || queued_groups.insert(sibling_group[&s.card_id]));
+        review.retain(|s| !buries_siblings(s.deck_id) || queued_groups.insert(sibling_group[&s.card_id]));
+        new_candidates.retain(|c| !buries_siblings(c.deck_id) || queued_groups.insert(c.sibling_group));
+        buried += queued - (learning.len() + review.len() + new_candidates.len());
+
+        let new_available = new_candidates.len();
+        let new_candidates =
+            take_per_deck(new_candidates, |c| c.deck_id, |deck_id| allowance(deck_id).new_cards);
+        let new: Vec<StudyCardSuggestion> = order_decks(new_candidates, |c| c.deck_id, interleave)
+            .into_iter()
+            .take(max_new)
+            .enumerate()
+            .map(|(i, candidate)| candidate.suggestion("New card".to_string(), 1.0 / (1.0 + i as f32)))
+            .collect();
+
+        let review_available = review.len();
+        let review = take_per_deck(review, |s| s.deck_id, |deck_id| allowance(deck_id).reviews);
+        let group_of = |s: &StudyCardSuggestion| sibling_group[&s.card_id];
+        let new = space_siblings(new, group_of, MIN_SIBLING_GAP);
+        let learning = space_siblings(order_decks(learning, |s| s.deck_id, interleave), group_of, MIN_SIBLING_GAP);
+        let review = space_siblings(order_decks(review, |s| s.deck_id, interleave), group_of, MIN_SIBLING_GAP);
+
+        Ok(StudyQueue {
+            deck_id: query.deck_id,
+            folder_id: query.folder_id,
+            smart_deck_id: query.smart_deck_id,
+            generated_at: now,
+            counts: StudyQueueCounts {
+                new: new.len(),
+                learning: learning.len(),
+                review: review.len(),
+                new_available,
+                review_available,
+                buried,
+            },
+            new,
+            learning,
+            review,
+        })
+    }
+
+    /// Each deck's daily limits less today's answers. A first answer to a
+    /// card counts as a new card; an answer to a card past the learning
+    /// interval counts as a review.
+    async fn daily_allowances(
+        db: &PgPool,
+        user_id: Uuid,
+        settings: &HashMap<Uuid, DeckSettings>,
+    ) -> Result<HashMap<Uuid, DailyAllowance>> {
+        let deck_ids: Vec<Uuid> = settings.keys().copied().collect();
+        let studied = sqlx::query_as::<_, StudiedToday>(
+            r#"
+            SELECT c.deck_id,
+                   COUNT(DISTINCT cp.card_id) FILTER (WHERE cp.interval_days_before = 0) as new_cards,
+                   COUNT(*) FILTER (WHERE cp.interval_days_before > $3) as reviews
+            FROM card_progress cp
+            JOIN cards c ON c.id = cp.card_id
+            WHERE cp.user_id = $1 AND c.deck_id = ANY($2)
+              AND cp.created_at >= date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+            GROUP BY c.deck_id
+            "#,
+        )
+        .bind(user_id)
+        .bind(&deck_ids)
+        .bind(LEARNING_MAX_INTERVAL_DAYS)
+        .fetch_all(db)
+        .await?;
+        let studied: HashMap<Uuid, StudiedToday> =
+            studied.into_iter().map(|s| (s.deck_id, s)).collect();
+
+        Ok(settings
+            .iter()
+            .map(|(&deck_id, settings)| {
+                let (new_cards, reviews) = studied
+                    .get(&deck_id)
+                    .map_or((0, 0), |s| (s.new_cards, s.reviews));
+                let allowance = DailyAllowance {
+                    new_cards: (settings.new_cards_per_day as i64 - new_cards).max(0) as usize,
+                    reviews: (settings.max_reviews_per_day as i64 - reviews).max(0) as usize,
+                };
+                (deck_id, allowance)
+            })
+            .collect())
+    }
+}
+
+/// Keep at most `limit(deck)` items of each deck, the first ones in order
+pub fn take_per_deck<T>(
+    items: Vec<T>,
+    deck_of: impl Fn(&T) -> Uuid,
+    limit: impl Fn(Uuid) -> usize,
+) -> Vec<T> {
+    let mut taken: HashMap<Uuid, usize> = HashMap::new();
+    items
+        .into_iter()
+        .filter(|item| {
+            let deck_id = deck_of(item);
+            let count = taken.entry(deck_id).or_insert(0);
+            *count += 1;
+            *count <= limit(deck_id)
+        })
+        .collect()
+}
+
+/// Take one item from each deck in turn, in the order decks first appear.
+/// Items of the same deck keep their relative order, so a single-deck list
+/// is unchanged.
+pub fn interleave_by_deck<T>(items: Vec<T>, deck_of: impl Fn(&T) -> Uuid) -> Vec<T> {
+    let mut decks: Vec<(Uuid, VecDeque<T>)> = Vec::new();
+    for item in items {
+        let deck_id = deck_of(&item);
+        match decks.iter_mut().find(|(id, _)| *id == deck_id) {
+            Some((_, queue)) => queue.push_back(item),
+            None => decks.push((deck_id, VecDeque::from([item]))),
+        }
+    }
+
+    let mut interleaved = Vec::new();
+    while !decks.is_empty() {
+        decks.retain_mut(|(_, queue)| match queue.pop_front() {
+            Some(item) => {
+                interleaved.push(item);
+                true
+            }
+            None => false,
+        });
+    }
+    interleaved
+}
+
+/// Put each deck's items together, decks in the order they first appear.
+/// Items of the same deck keep their relative order.
+pub fn group_by_deck<T>(items: Vec<T>, deck_of: impl Fn(&T) -> Uuid) -> Vec<T> {
+    let mut decks: Vec<(Uuid, Vec<T>)> = Vec::new();
+    for item in items {
+        let deck_id = deck_of(&item);
+        match decks.iter_mut().find(|(id, _)| *id == deck_id) {
+            Some((_, group)) => group.push(item),
+            None => decks.push((deck_id, vec![item])),
+        }
+    }
+    decks.into_iter().flat_map(|(_, group)| group).collect()
+}
+
+fn order_decks<T>(items: Vec<T>, deck_of: impl Fn(&T) -> Uuid, interleave: bool) -> Vec<T> {
+    if interleave {
+        interleave_by_deck(items, deck_of)
+    } else {
+        group_by_deck(items, deck_of)
+    }
+}
+
+/// Reorder items so no two of the same group are within `min_gap` items of
+/// each other where possible. An item that would come too soon waits and
+/// goes in at the first place far enough from its sibling; items that never
+/// fit go at the end. Items without siblings keep their order.
+pub fn space_siblings<T>(items: Vec<T>, group_of: impl Fn(&T) -> Uuid, min_gap: usize) -> Vec<T> {
+    let too_close = |spaced: &[T], group: Uuid| spaced.iter().rev().take(min_gap).any(|s| group_of(s) == group);
+
+    let mut spaced: Vec<T> = Vec::with_capacity(items.len());
+    let mut waiting: VecDeque<T> = VecDeque::new();
+    for item in items {
+        if let Some(i) = waiting.iter().position(|w| !too_close(&spaced, group_of(w))) {
+            spaced.extend(waiting.remove(i));
+        }
+        if too_close(&spaced, group_of(&item)) {
+            waiting.push_back(item);
+        } else {
+            spaced.push(item);
+        }
+    }
+    spaced.extend(waiting);
+    spaced
+}
@@ -0,0 +1,531 @@
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    models::{
+        sync::{
+            CardSyncData, ConflictResolution, DeckSyncData, FolderSyncData, SyncChange,
+            SyncChangesPage, SyncChangesQuery, SyncEntity, SyncOp, SyncPushChange, SyncPushDto,
+            SyncPushResponse, SyncPushResult, SyncPushStatus,
+        },
+        Card, Deck, Folder, MoveFolderDto,
+    },
+    services::{
+        card::CardService, deck::DeckService, encryption::EncryptionService, folder::FolderService,
+        reverse_cards::ReverseCardService,
+    },
+    utils::{AppError, Result},
+};
+
+const DEFAULT_PULL_LIMIT: i64 = 500;
+const MAX_PULL_LIMIT: i64 = 1000;
+
+/// A `sync_changes` row: the latest change to one entity
+#[derive(Debug, FromRow)]
+struct ChangeRow {
+    entity: SyncEntity,
+    entity_id: Uuid,
+    user_id: Uuid,
+    op: SyncOp,
+    version: i64,
+    txid: i64,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A card a push wrote, for the handler to announce to the webhooks of
+/// `deck_id` as the card endpoints do
+#[derive(Debug)]
+pub struct SyncCardEvent {
+    pub deck_id: Uuid,
+    pub event: &'static str,
+    pub card: Card,
+}
+
+/// The outcome of a push: a result per change for the client, and the card
+/// events it caused
+#[derive(Debug)]
+pub struct SyncPushOutcome {
+    pub response: SyncPushResponse,
+    pub card_events: Vec<SyncCardEvent>,
+}
+
+/// Two-way sync of a user's own folders, decks and cards for offline
+/// clients. Database triggers log every write in `sync_changes`, so changes
+/// made through any endpoint reach clients. Pushed changes that raced a
+/// newer server change are settled by last write wins.
+pub struct SyncService;
+
+impl SyncService {
+    /// Changes after the `since` cursor, in commit order. The cursor is the
+    /// id of the last transaction served; transactions are served once every
+    /// older one has finished, so none can commit behind the cursor. A page
+    /// ends on a transaction boundary, and holds a whole transaction even
+    /// when it has more than `limit` changes.
+    pub async fn pull(db: &PgPool, user_id: Uuid, query: &SyncChangesQuery) -> Result<SyncChangesPage> {
+        let since = query.since.unwrap_or(0).max(0);
+        let limit = query.limit.unwrap_or(DEFAULT_PULL_LIMIT).clamp(1, MAX_PULL_LIMIT);
+
+        let mut rows = sqlx::query_as::<_, ChangeRow>(
+            r#"
+            SELECT entity, entity_id, user_id, op, version, txid, updated_at
+            FROM sync_changes
+            WHERE user_id = $1 AND txid > $2
+                AND txid < pg_snapshot_xmin(pg_current_snapshot())::text::BIGINT
+            ORDER BY txid, version
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(limit + 1)
+        .fetch_all(db)
+        .await?;
+
+        let has_more = rows.len() as i64 > limit;
+        // Keep the transaction cut by the limit for the next page, or serve
+        // all of it when it is the only one
+        if has_more {
+            let cut = rows.pop().map_or(since, |row| row.txid);
+            if rows.last().is_some_and(|row| row.txid == cut) {
+                rows.retain(|row| row.txid != cut);
+                if rows.is_empty() {
+                    rows = Self::transaction_changes(db, user_id, cut).await?;
+                }
+            }
+        }
+        let next_cursor = rows.last().map_or(since, |row| row.txid);
+
+        Ok(SyncChangesPage {
+            changes: Self::with_data(db, rows).await?,
+            next_cursor,
+            has_more,
+        })
+    }
+
+    /// Every change one transaction made to the user's content
+    async fn transaction_changes(db: &PgPool, user_id: Uuid, txid: i64) -> Result<Vec<ChangeRow>> {
+        let rows = sqlx::query_as::<_, ChangeRow>(
+            r#"
+            SELECT entity, entity_id, user_id, op, version, txid, updated_at
+            FROM sync_changes
+            WHERE user_id = $1 AND txid = $2
+            ORDER BY version
+            "#,
+        )
+        .bind(user_id)
+        .bind(txid)
+        .fetch_all(db)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Apply the client's changes in order, each on its own
+    pub async fn push(db: &PgPool, user_id: Uuid, dto: SyncPushDto) -> Result<SyncPushOutcome> {
+        let mut results = Vec::with_capacity(dto.changes.len());
+        let mut card_events = Vec::new();
+        for change in dto.changes {
+            let (entity, id) = (change.entity, change.id);
+            let result = match Self::push_one(db, user_id, change, &mut card_events).await {
+                Ok(result) => result,
+                Err(AppError::Database(e)) => return Err(AppError::Database(e)),
+                Err(e) => SyncPushResult {
+                    entity,
+                    id,
+                    status: SyncPushStatus::Rejected,
+                    resolution: None,
+                    version: None,
+                    server: None,
+                    error: Some(e.to_string()),
+                },
+            };
+            results.push(result);
+        }
+        Ok(SyncPushOutcome {
+            response: SyncPushResponse { results },
+            card_events,
+        })
+    }
+
+    async fn push_one(
+        db: &PgPool,
+        user_id: Uuid,
+        change: SyncPushChange,
+        card_events: &mut Vec<SyncCardEvent>,
+    ) -> Result<SyncPushResult> {
+        let current = Self::current(db, change.entity, change.id).await?;
+        if matches!(&current, Some(current) if current.user_id != user_id) {
+            return Err(AppError::Forbidden);
+        }
+
+        let result = |status, resolution, version, server| SyncPushResult {
+            entity: change.entity,
+            id: change.id,
+            status,
+            resolution,
+            version,
+            server,
+            error: None,
+        };
+
+        // Deleting what is already deleted agrees with the server whatever
+        // happened in between
+        if change.op == SyncOp::Delete && current.as_ref().map_or(true, |c| c.op == SyncOp::Delete) {
+            let version = current.map(|c| c.version);
+            return Ok(result(SyncPushStatus::Applied, None, version, None));
+        }
+
+        let mut resolution = None;
+        if let Some(current) = current {
+            let changed_since = change.base_version.map_or(true, |base| current.version > base);
+            if changed_since {
+                if change.updated_at <= current.updated_at {
+                    let version = Some(current.version);
+                    let server = Self::with_data(db, vec![current]).await?.pop();
+                    return Ok(result(
+                        SyncPushStatus::Conflict,
+                        Some(ConflictResolution::ServerWins),
+                        version,
+                        server,
+                    ));
+                }
+                resolution = Some(ConflictResolution::ClientWins);
+            }
+        }
+
+        match (change.entity, change.op) {
+            (SyncEntity::Folder, SyncOp::Upsert) => {
+                Self::upsert_folder(db, user_id, change.id, parse_data(&change)?).await?;
+            }
+            (SyncEntity::Deck, SyncOp::Upsert) => {
+                Self::upsert_deck(db, user_id, change.id, parse_data(&change)?).await?;
+            }
+            (SyncEntity::Card, SyncOp::Upsert) => {
+                let events = Self::upsert_card(db, user_id, change.id, parse_data(&change)?).await?;
+                card_events.extend(events);
+            }
+            (SyncEntity::Folder, SyncOp::Delete) => {
+                FolderService::delete_folder(db, change.id, user_id).await?;
+            }
+            (SyncEntity::Deck, SyncOp::Delete) => {
+                DeckService::delete_deck(db, change.id, user_id).await?;
+            }
+            (SyncEntity::Card, SyncOp::Delete) => {
+                let card = CardService::delete_card(db, change.id, user_id).await?;
+                card_events.push(SyncCardEvent {
+                    deck_id: card.deck_id,
+                    event: "card.deleted",
+                    card,
+                });
+            }
+        }
+
+        let version = Self::current(db, change.entity, change.id).await?.map(|c| c.version);
+        let status = if resolution.is_some() {
+            SyncPushStatus::Conflict
+        } else {
+            SyncPushStatus::Applied
+        };
+        Ok(result(status, resolution, version, None))
+    }
+
+    async fn current(db: &PgPool, entity: SyncEntity, id: Uuid) -> Result<Option<ChangeRow>> {
+        let row = sqlx::query_as::<_, ChangeRow>(
+            r#"
+            SELECT entity, entity_id, user_id, op, version, txid, updated_at
+            FROM sync_changes
+            WHERE entity = $1 AND entity_id = $2
+            "#,
+        )
+        .bind(entity)
+        .bind(id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Attach each upserted entity's current fields. Anything gone since the
+    /// change was logged is reported as deleted.
+    async fn with_data(db: &PgPool, rows: Vec<ChangeRow>) -> Result<Vec<SyncChange>> {
+        let ids_of = |entity: SyncEntity| -> Vec<Uuid> {
+            rows.iter()
+                .filter(|row| row.entity == entity && row.op == SyncOp::Upsert)
+                .map(|row| row.entity_id)
+                .collect()
+        };
+
+        let mut data: HashMap<(SyncEntity, Uuid), serde_json::Value> = HashMap::new();
+        let folders = sqlx::query_as::<_, Folder>(
+            r#"
+            SELECT id, user_id, parent_folder_id, name, position, created_at, updated_at
+            FROM folders WHERE id = ANY($1)
+            "#,
+        )
+        .bind(ids_of(SyncEntity::Folder))
+        .fetch_all(db)
+        .await?;
+        data.extend(folders.into_iter().map(|f| ((SyncEntity::Folder, f.id), json!(f))));
+
+        let decks = sqlx::query_as::<_, Deck>(
+            r#"
+            SELECT id, folder_id, owner_id, title, description, is_public, front_language, back_language, created_at, updated_at
+            FROM decks WHERE id = ANY($1) AND deleted_at IS NULL
+            "#,
+        )
+        .bind(ids_of(SyncEntity::Deck))
+        .fetch_all(db)
+        .await?;
+        data.extend(decks.into_iter().map(|d| ((SyncEntity::Deck, d.id), json!(d))));
+
+        let cards = sqlx::query_as::<_, Card>(
+            r#"
+            SELECT id, deck_id, front, back, position, created_at, updated_at
+            FROM cards WHERE id = ANY($1) AND deleted_at IS NULL
+            "#,
+        )
+        .bind(ids_of(SyncEntity::Card))
+        .fetch_all(db)
+        .await?;
+        data.extend(cards.into_iter().map(|c| ((SyncEntity::Card, c.id), json!(c))));
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let data = match row.op {
+                    SyncOp::Upsert => data.remove(&(row.entity, row.entity_id)),
+                    SyncOp::Delete => None,
+                };
+                SyncChange {
+                    entity: row.entity,
+                    id: row.entity_id,
+                    op: if data.is_some() { SyncOp::Upsert } else { SyncOp::Delete },
+                    version: row.version,
+                    updated_at: row.updated_at,
+                    data,
+                }
+            })
+            .collect())
+    }
+
+    /// Create a folder under the client's id, or rename and move it
+    async fn upsert_folder(db: &PgPool, user_id: Uuid, id: Uuid, data: FolderSyncData) -> Result<()> {
+        if let Some(parent_id) = data.parent_folder_id {
+            FolderService::get_folder(db, parent_id, user_id).await?;
+        }
+
+        let existing = sqlx::query_scalar::<_, Option<Uuid>>(
+            "SELECT parent_folder_id FROM folders WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        match existing {
+            Some(parent_folder_id) => {
+                if parent_folder_id != data.parent_folder_id {
+                    let move_dto = MoveFolderDto {
+                        parent_folder_id: data.parent_folder_id,
+                        position: None,
+                    };
+                    FolderService::move_folder(db, id, user_id, move_dto).await?;
+                }
+                sqlx::query("UPDATE folders SET name = $3 WHERE id = $1 AND user_id = $2")
+                    .bind(id)
+                    .bind(user_id)
+                    .bind(&data.name)
+                    .execute(db)
+                    .await?;
+            }
+            None => {
+                let inserted = sqlx::query(
+                    r#"
+                    INSERT INTO folders (id, user_id, parent_folder_id, name, position)
+                    SELECT $1, $2, $3, $4, COALESCE(MAX(position) + 1, 0)
+                    FROM folders
+                    WHERE user_id = $2 AND parent_folder_id IS NOT DISTINCT FROM $3
+                    ON CONFLICT (id) DO NOTHING
+                    "#,
+                )
+                .bind(id)
+                .bind(user_id)
+                .bind(data.parent_folder_id)
+                .bind(&data.name)
+                .execute(db)
+                .await?;
+                ensure_inserted(inserted.rows_affected())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create a deck under the client's id, or update it. A deck in the
+    /// trash comes back out.
+    async fn upsert_deck(db: &PgPool, user_id: Uuid, id: Uuid, data: DeckSyncData) -> Result<()> {
+        if let Some(folder_id) = data.folder_id {
+            FolderService::get_folder(db, folder_id, user_id).await?;
+        }
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE decks
+            SET title = $3, description = $4, folder_id = $5, deleted_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND owner_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(&data.name)
+        .bind(&data.description)
+        .bind(data.folder_id)
+        .execute(db)
+        .await?;
+        if updated.rows_affected() > 0 {
+            return Ok(());
+        }
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO decks (id, owner_id, folder_id, title, description, is_public)
+            VALUES ($1, $2, $3, $4, $5, false)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(data.folder_id)
+        .bind(&data.name)
+        .bind(&data.description)
+        .execute(db)
+        .await?;
+        ensure_inserted(inserted.rows_affected())
+    }
+
+    /// Create a card under the client's id, or update it. Cards sync only in
+    /// decks the user owns, and not in encrypted ones, whose content the
+    /// server cannot check. Reverse siblings follow as with the card endpoints.
+    async fn upsert_card(db: &PgPool, user_id: Uuid, id: Uuid, data: CardSyncData) -> Result<Vec<SyncCardEvent>> {
+        let owns_deck = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM decks WHERE id = $1 AND owner_id = $2 AND deleted_at IS NULL)",
+        )
+        .bind(data.deck_id)
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+        if !owns_deck {
+            return Err(AppError::DeckNotFound);
+        }
+        EncryptionService::ensure_plaintext(db, data.deck_id).await?;
+
+        let from_deck_id = sqlx::query_scalar::<_, Uuid>(
+            "SELECT c.deck_id FROM cards c JOIN decks d ON d.id = c.deck_id WHERE c.id = $1 AND d.owner_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some(from_deck_id) = from_deck_id {
+            if from_deck_id != data.deck_id {
+                EncryptionService::ensure_plaintext(db, from_deck_id).await?;
+            }
+
+            let card = sqlx::query_as::<_, Card>(
+                r#"
+                UPDATE cards
+                SET deck_id = $2, front = $3, back = $4, position = COALESCE($5, position),
+                    deleted_at = NULL, updated_at = NOW()
+                WHERE id = $1
+                RETURNING id, deck_id, front, back, position, created_at, updated_at
+                "#,
+            )
+            .bind(id)
+            .bind(data.deck_id)
+            .bind(&data.front)
+            .bind(&data.back)
+            .bind(data.position)
+            .fetch_one(db)
+            .await?;
+            ReverseCardService::sync_sibling(db, &card).await?;
+            ReverseCardService::generate_if_enabled(db, card.deck_id).await?;
+
+            // A move leaves one deck and arrives in another
+            if from_deck_id != card.deck_id {
+                return Ok(vec![
+                    SyncCardEvent {
+                        deck_id: from_deck_id,
+                        event: "card.deleted",
+                        card: card.clone(),
+                    },
+                    SyncCardEvent {
+                        deck_id: card.deck_id,
+                        event: "card.created",
+                        card,
+                    },
+                ]);
+            }
+            return Ok(vec![SyncCardEvent {
+                deck_id: card.deck_id,
+                event: "card.updated",
+                card,
+            }]);
+        }
+
+        let card = sqlx::query_as::<_, Card>(
+            r#"
+            INSERT INTO cards (id, deck_id, front, back, position)
+            SELECT $1, $2, $3, $4, COALESCE($5, MAX(position) + 1, 0)
+            FROM cards
+            WHERE deck_id = $2
+            ON CONFLICT (id) DO NOTHING
+            RETURNING id, deck_id, front, back, position, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(data.deck_id)
+        .bind(&data.front)
+        .bind(&data.back)
+        .bind(data.position)
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(id_in_use)?;
+        ReverseCardService::generate_if_enabled(db, card.deck_id).await?;
+
+        Ok(vec![SyncCardEvent {
+            deck_id: card.deck_id,
+            event: "card.created",
+            card,
+        }])
+    }
+}
+
+/// The upsert fields sent with a change, checked like the regular endpoints
+fn parse_data<T: DeserializeOwned + Validate>(change: &SyncPushChange) -> Result<T> {
+    let value = change
+        .data
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("Upserts need data".to_string()))?;
+    let data: T = serde_json::from_value(value).map_err(|e| {
+        AppError::BadRequest(format!("Invalid {} data: {}", change.entity.as_str(), e))
+    })?;
+    data.validate()?;
+    Ok(data)
+}
+
+/// An insert that hit an existing id the user does not own
+fn ensure_inserted(rows_affected: u64) -> Result<()> {
+    if rows_affected == 0 {
+        return Err(id_in_use());
+    }
+    Ok(())
+}
+
+fn id_in_use() -> AppError {
+    AppError::BadRequest("Id is already in use".to_string())
+}
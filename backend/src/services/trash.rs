@@ -0,0 +1,135 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Card, Deck, DeckRole, RestoredItem, TrashItem},
+    services::sharing::SharingService,
+    utils::{AppError, Result},
+};
+
+/// Days a deleted deck or card stays restorable before the sweeper purges it
+pub const TRASH_RETENTION_DAYS: i32 = 30;
+
+/// Soft-deleted decks and cards. Deleting moves an item here; restoring
+/// brings it back, and the sweeper removes it for good after
+/// `TRASH_RETENTION_DAYS`.
+pub struct TrashService;
+
+impl TrashService {
+    /// The user's deleted decks, and deleted cards from decks they can edit.
+    /// Cards of a deleted deck are not listed; they return with the deck.
+    pub async fn list(db: &PgPool, user_id: Uuid) -> Result<Vec<TrashItem>> {
+        let items = sqlx::query_as::<_, TrashItem>(
+            r#"
+            SELECT d.id, 'deck' as item_type, d.title, NULL::UUID as deck_id, d.deleted_at,
+                   d.deleted_at + make_interval(days => $2) as purge_at
+            FROM decks d
+            WHERE d.owner_id = $1 AND d.deleted_at IS NOT NULL
+            UNION ALL
+            SELECT c.id, 'card' as item_type, c.front as title, c.deck_id, c.deleted_at,
+                   c.deleted_at + make_interval(days => $2) as purge_at
+            FROM cards c
+            JOIN decks d ON d.id = c.deck_id
+            WHERE c.deleted_at IS NOT NULL AND d.deleted_at IS NULL
+                AND (
+                    d.owner_id = $1
+                    OR EXISTS(
+                        SELECT 1 FROM deck_shares s
+                        WHERE s.deck_id = d.id AND s.user_id = $1 AND s.role = 'editor'
+                    )
+                )
+            ORDER BY deleted_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .bind(TRASH_RETENTION_DAYS)
+        .fetch_all(db)
+        .await?;
+
+        Ok(items)
+    }
+
+    /// Restore a deleted deck (owner only) or card (editors of its deck)
+    pub async fn restore(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<RestoredItem> {
+        let deck = sqlx::query_as::<_, Deck>(
+            r#"
+            UPDATE decks SET deleted_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND owner_id = $2 AND deleted_at IS NOT NULL
+            RETURNING id, folder_id, owner_id, title, description, is_public, front_language, back_language, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some(deck) = deck {
+            return Ok(RestoredItem::Deck { deck });
+        }
+
+        let deck_id = sqlx::query_scalar::<_, Uuid>(
+            "SELECT deck_id FROM cards WHERE id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+
+        // Also fails while the deck itself is in the trash
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+
+        let card = sqlx::query_as::<_, Card>(
+            r#"
+            UPDATE cards SET deleted_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING id, deck_id, front, back, position, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("Resource not found".to_string()))?;
+
+        Ok(RestoredItem::Card { card })
+    }
+
+    /// Permanently delete items past the retention period, returning how
+    /// many decks and cards were removed
+    pub async fn purge_expired(db: &PgPool) -> Result<(u64, u64)> {
+        let cards = sqlx::query(
+            "DELETE FROM cards WHERE deleted_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(TRASH_RETENTION_DAYS)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        // Cascades to the deck's remaining cards
+        let decks = sqlx::query(
+            "DELETE FROM decks WHERE deleted_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(TRASH_RETENTION_DAYS)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        Ok((decks, cards))
+    }
+
+    /// Run `purge_expired` in the background every `every`
+    pub fn spawn_sweeper(db: PgPool, every: std::time::Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match Self::purge_expired(&db).await {
+                    Ok((0, 0)) => {}
+                    Ok((decks, cards)) => {
+                        tracing::info!("Trash sweeper purged {} decks and {} cards", decks, cards);
+                    }
+                    Err(e) => tracing::warn!("Trash sweeper failed: {}", e),
+                }
+            }
+        });
+    }
+}
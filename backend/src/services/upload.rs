@@ -0,0 +1,345 @@
+//! Checks on uploaded files before anything parses them.
+//!
+//! The format a client names for an upload is only trusted when the file
+//! agrees with it: the extension must be in `ALLOWED_FILE_TYPES` and one the
+//! format uses, a declared content type must not name a different kind of
+//! file, and the first bytes must be what that kind of file starts with: a
+//! ZIP header for `.apkg`, `.docx` and `.zip`, `%PDF` for PDFs, the OLE2
+//! header for `.doc`, and valid UTF-8 for text formats.
+//!
+//! Uploads are read with `SpooledFile`, which keeps small files in memory
+//! and writes larger ones to a temporary file as they stream in. Only the
+//! first `SNIFF_BYTES` are needed for the checks; importers read the rest
+//! through an `UploadSource`.
+
+use axum::extract::multipart::Field;
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{BufRead, BufReader, Cursor},
+    path::{Path, PathBuf},
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::{
+    config::UploadConfig,
+    models::import_export::ImportFormat,
+    utils::{AppError, Result},
+};
+
+pub const PDF_MAGIC: &[u8] = b"%PDF";
+pub const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// An empty ZIP archive has only the end of central directory record
+pub const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+pub const OLE2_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+/// How much of a file is read to tell what it holds
+pub const SNIFF_BYTES: usize = 8 * 1024;
+
+/// What an uploaded file holds, as far as its first bytes tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileContent {
+    Zip,
+    Pdf,
+    Ole2,
+    Text,
+}
+
+impl FileContent {
+    fn describe(&self) -> &'static str {
+        match self {
+            FileContent::Zip => "a ZIP archive",
+            FileContent::Pdf => "a PDF",
+            FileContent::Ole2 => "a legacy Office document",
+            FileContent::Text => "UTF-8 text",
+        }
+    }
+}
+
+/// Identify a file by its magic bytes. Anything else counts as text when it
+/// is valid UTF-8 without NUL bytes; `None` means unrecognised binary.
+/// `data` may be just the head of the file, so a character cut off at the
+/// end does not count against it.
+pub fn sniff(data: &[u8]) -> Option<FileContent> {
+    if data.starts_with(ZIP_MAGIC) || data.starts_with(EMPTY_ZIP_MAGIC) {
+        return Some(FileContent::Zip);
+    }
+    if data.starts_with(PDF_MAGIC) {
+        return Some(FileContent::Pdf);
+    }
+    if data.starts_with(OLE2_MAGIC) {
+        return Some(FileContent::Ole2);
+    }
+    let text = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let is_utf8 = match std::str::from_utf8(text) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    (is_utf8 && !text.contains(&0)).then_some(FileContent::Text)
+}
+
+/// What a file with this extension has to contain
+pub fn expected_content(extension: &str) -> Option<FileContent> {
+    match extension {
+        "apkg" | "colpkg" | "zip" | "docx" | "xlsx" => Some(FileContent::Zip),
+        "pdf" => Some(FileContent::Pdf),
+        "doc" => Some(FileContent::Ole2),
+        "csv" | "tsv" | "txt" | "md" | "markdown" | "json" | "xml" => Some(FileContent::Text),
+        _ => None,
+    }
+}
+
+// The kind of file a Content-Type header claims; generic types such as
+// application/octet-stream claim nothing
+fn declared_content(content_type: &str) -> Option<FileContent> {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    match mime.as_str() {
+        "application/zip" | "application/x-zip-compressed" | "application/apkg" => Some(FileContent::Zip),
+        m if m.starts_with("application/vnd.openxmlformats-officedocument.") => Some(FileContent::Zip),
+        "application/pdf" => Some(FileContent::Pdf),
+        "application/msword" => Some(FileContent::Ole2),
+        "application/json" | "application/xml" => Some(FileContent::Text),
+        m if m.starts_with("text/") => Some(FileContent::Text),
+        _ => None,
+    }
+}
+
+/// Lowercase extension of an uploaded file's name
+pub fn file_extension(filename: &str) -> Option<String> {
+    filename
+        .rsplit(['/', '\\'])
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .filter(|extension| !extension.is_empty())
+}
+
+/// Reject an upload over `MAX_FILE_SIZE`
+pub fn check_size(config: &UploadConfig, size: usize) -> Result<()> {
+    if size > config.max_file_size {
+        return Err(AppError::FileTooLarge {
+            limit_bytes: config.max_file_size,
+        });
+    }
+    Ok(())
+}
+
+/// Check a file uploaded for import against the format it was declared as.
+/// The name and content type are optional in multipart forms and only
+/// checked when sent; the content is always checked. `data` is the file or
+/// its first `SNIFF_BYTES`.
+pub fn check_import(
+    config: &UploadConfig,
+    format: &ImportFormat,
+    filename: Option<&str>,
+    content_type: Option<&str>,
+    data: &[u8],
+) -> Result<()> {
+    check_size(config, data.len())?;
+    if data.is_empty() {
+        return Err(AppError::FileUploadError("File is empty".to_string()));
+    }
+
+    let allowed: Vec<&str> = format
+        .extensions()
+        .iter()
+        .copied()
+        .filter(|extension| config.allowed_file_types.iter().any(|allowed| allowed == extension))
+        .collect();
+    if allowed.is_empty() {
+        return Err(AppError::FileUploadError(format!(
+            "{} imports are not accepted by this server",
+            format.name()
+        )));
+    }
+
+    let extension = match filename.and_then(file_extension) {
+        Some(extension) if allowed.contains(&extension.as_str()) => extension,
+        Some(extension) => {
+            return Err(AppError::FileUploadError(format!(
+                "A .{} file cannot be imported as {}; expected {}",
+                extension,
+                format.name(),
+                allowed.iter().map(|e| format!(".{}", e)).collect::<Vec<_>>().join(", ")
+            )))
+        }
+        None => allowed[0].to_string(),
+    };
+    let expected = expected_content(&extension).unwrap_or(FileContent::Text);
+
+    if let Some(declared) = content_type.and_then(declared_content) {
+        if declared != expected {
+            return Err(AppError::FileUploadError(format!(
+                "File was sent as {} but {} imports must be {}",
+                declared.describe(),
+                format.name(),
+                expected.describe()
+            )));
+        }
+    }
+
+    match sniff(data) {
+        Some(actual) if actual == expected => Ok(()),
+        Some(actual) => Err(AppError::FileUploadError(format!(
+            "File content is {}, not {} as a .{} file should be",
+            actual.describe(),
+            expected.describe(),
+            extension
+        ))),
+        None => Err(AppError::FileUploadError(format!(
+            "File content is not {} as a .{} file should be",
+            expected.describe(),
+            extension
+        ))),
+    }
+}
+
+/// A multipart file field with what the client said about it. Files up to
+/// `spool_threshold` stay in memory; larger ones are written to `temp_dir`
+/// while they stream in, and the file is removed when this is dropped.
+pub struct SpooledFile {
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    len: usize,
+    contents: SpooledContents,
+}
+
+enum SpooledContents {
+    Memory(Vec<u8>),
+    Disk(TempPath),
+}
+
+/// A temporary file, deleted on drop
+struct TempPath(PathBuf);
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0) {
+            tracing::warn!("Could not remove spooled upload {}: {}", self.0.display(), e);
+        }
+    }
+}
+
+impl SpooledFile {
+    /// Read the field, stopping as soon as it passes `MAX_FILE_SIZE`
+    pub async fn read(mut field: Field<'_>, config: &UploadConfig) -> Result<Self> {
+        let filename = field.file_name().map(str::to_string);
+        let content_type = field.content_type().map(str::to_string);
+
+        let mut buffer = Vec::new();
+        let mut spool: Option<(TempPath, tokio::fs::File)> = None;
+        let mut len = 0;
+        while let Some(chunk) = field.chunk().await? {
+            len += chunk.len();
+            check_size(config, len)?;
+
+            match &mut spool {
+                Some((_, file)) => file.write_all(&chunk).await.map_err(spool_error)?,
+                None if len > config.spool_threshold => {
+                    let path = Path::new(&config.temp_dir).join(format!("deckoracle-upload-{}", Uuid::new_v4()));
+                    let mut file = tokio::fs::File::create(&path).await.map_err(spool_error)?;
+                    let path = TempPath(path);
+                    file.write_all(&buffer).await.map_err(spool_error)?;
+                    file.write_all(&chunk).await.map_err(spool_error)?;
+                    buffer = Vec::new();
+                    spool = Some((path, file));
+                }
+                None => buffer.extend_from_slice(&chunk),
+            }
+        }
+
+        let contents = match spool {
+            Some((path, mut file)) => {
+                file.flush().await.map_err(spool_error)?;
+                SpooledContents::Disk(path)
+            }
+            None => SpooledContents::Memory(buffer),
+        };
+
+        Ok(Self {
+            filename,
+            content_type,
+            len,
+            contents,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Where the file was spooled, when it was too large to keep in memory
+    pub fn path(&self) -> Option<&Path> {
+        match &self.contents {
+            SpooledContents::Memory(_) => None,
+            SpooledContents::Disk(path) => Some(&path.0),
+        }
+    }
+
+    /// The contents, when they were kept in memory
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match &self.contents {
+            SpooledContents::Memory(data) => Some(data),
+            SpooledContents::Disk(_) => None,
+        }
+    }
+
+    /// Up to `len` bytes from the start of the file
+    pub async fn head(&self, len: usize) -> Result<Vec<u8>> {
+        match &self.contents {
+            SpooledContents::Memory(data) => Ok(data[..len.min(data.len())].to_vec()),
+            SpooledContents::Disk(path) => {
+                let file = tokio::fs::File::open(&path.0).await.map_err(spool_error)?;
+                let mut head = Vec::with_capacity(len);
+                file.take(len as u64).read_to_end(&mut head).await.map_err(spool_error)?;
+                Ok(head)
+            }
+        }
+    }
+
+    /// The contents, wherever they were kept
+    pub fn source(&self) -> UploadSource<'_> {
+        match &self.contents {
+            SpooledContents::Memory(data) => UploadSource::Bytes(data),
+            SpooledContents::Disk(path) => UploadSource::File(&path.0),
+        }
+    }
+}
+
+/// Uploaded contents as importers read them: from memory, or from the file
+/// a large upload was spooled to
+#[derive(Debug, Clone, Copy)]
+pub enum UploadSource<'a> {
+    Bytes(&'a [u8]),
+    File(&'a Path),
+}
+
+impl<'a> UploadSource<'a> {
+    /// Read the contents front to back without loading them whole
+    pub fn reader(&self) -> Result<Box<dyn BufRead + Send + 'a>> {
+        match *self {
+            UploadSource::Bytes(data) => Ok(Box::new(Cursor::new(data))),
+            UploadSource::File(path) => {
+                let file = File::open(path).map_err(spool_error)?;
+                Ok(Box::new(BufReader::new(file)))
+            }
+        }
+    }
+
+    /// The whole contents, for parsers that need to see all of it at once
+    pub fn read_all(&self) -> Result<Cow<'a, [u8]>> {
+        match *self {
+            UploadSource::Bytes(data) => Ok(Cow::Borrowed(data)),
+            UploadSource::File(path) => std::fs::read(path).map(Cow::Owned).map_err(spool_error),
+        }
+    }
+}
+
+fn spool_error(error: std::io::Error) -> AppError {
+    tracing::error!("Spooling an upload to disk failed: {}", error);
+    AppError::InternalServerError
+}
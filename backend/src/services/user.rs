@@ -0,0 +1,376 @@
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{ChangePasswordDto, DeleteAccountDto, UpdateProfileDto, User, UserResponse},
+    services::auth::{hash_token, AuthService},
+    utils::{AppError, Result},
+};
+
+const EMAIL_VERIFICATION_HOURS: i64 = 24;
+/// Days a deleted account is kept before it is erased for good
+pub const ACCOUNT_ERASURE_GRACE_DAYS: i32 = 30;
+
+/// Self-service management of the signed-in account
+pub struct UserService;
+
+impl UserService {
+    pub async fn get_profile(db: &PgPool, user_id: Uuid) -> Result<UserResponse> {
+        let user = Self::find_user(db, user_id).await?;
+        Ok(AuthService::user_to_response(&user))
+    }
+
+    /// Update the display name and leaderboard opt-out and/or request an
+    /// email change. A new email only takes effect once the link sent to it
+    /// is confirmed.
+    pub async fn update_profile(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: UpdateProfileDto,
+    ) -> Result<UserResponse> {
+        let user = Self::find_user(db, user_id).await?;
+
+        if let Some(display_name) = &dto.display_name {
+            sqlx::query("UPDATE users SET display_name = $2, updated_at = NOW() WHERE id = $1")
+                .bind(user_id)
+                .bind(display_name.trim())
+                .execute(db)
+                .await?;
+        }
+
+        if let Some(opt_out) = dto.leaderboard_opt_out {
+            sqlx::query("UPDATE users SET leaderboard_opt_out = $2, updated_at = NOW() WHERE id = $1")
+                .bind(user_id)
+                .bind(opt_out)
+                .execute(db)
+                .await?;
+        }
+
+        if let Some(email) = &dto.email {
+            let email = email.trim().to_lowercase();
+            if !email.eq_ignore_ascii_case(&user.email) {
+                // A stolen access token must not be enough to take over the account
+                let password = dto.current_password.as_deref().ok_or_else(|| {
+                    AppError::BadRequest("current_password is required to change the email".to_string())
+                })?;
+                Self::require_password(&user, password)?;
+                Self::request_email_change(db, &user, &email).await?;
+            }
+        }
+
+        Self::get_profile(db, user_id).await
+    }
+
+    pub async fn is_email_verified(db: &PgPool, user_id: Uuid) -> Result<bool> {
+        let verified = sqlx::query_scalar::<_, bool>(
+            "SELECT email_verified FROM users WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .unwrap_or(false);
+
+        Ok(verified)
+    }
+
+    /// Send a confirmation link for the account's current address. Accounts
+    /// start unverified, and some servers refuse writes until this is done.
+    pub async fn request_verification(db: &PgPool, user_id: Uuid) -> Result<()> {
+        let user = Self::find_user(db, user_id).await?;
+        if user.email_verified {
+            return Err(AppError::BadRequest("Email address is already verified".to_string()));
+        }
+
+        Self::issue_verification_token(db, &user, &user.email).await
+    }
+
+    /// Apply the email change a verification token was issued for
+    pub async fn verify_email(db: &PgPool, token: &str) -> Result<UserResponse> {
+        let record = sqlx::query_as::<_, (Uuid, Uuid, String)>(
+            r#"
+            UPDATE email_verification_tokens SET used_at = NOW()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING id, user_id, email
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::BadRequest("Invalid or expired token".to_string()))?;
+        let (_, user_id, email) = record;
+
+        Self::ensure_email_available(db, &email, Some(user_id)).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET email = $2, email_verified = true, email_verified_at = NOW(),
+                pending_email = NULL, updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(&email)
+        .execute(db)
+        .await?;
+
+        Self::get_profile(db, user_id).await
+    }
+
+    /// Change the password, ending every session. Clients log in again.
+    pub async fn change_password(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: ChangePasswordDto,
+    ) -> Result<()> {
+        let user = Self::find_user(db, user_id).await?;
+        Self::require_password(&user, &dto.current_password)?;
+
+        let password_hash = AuthService::hash_password(&dto.new_password)?;
+
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE users SET password_hash = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(&password_hash)
+            .execute(&mut *tx)
+            .await?;
+
+        Self::revoke_refresh_tokens(&mut tx, user_id, "password_change").await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The account with this address, for operator tooling
+    pub async fn find_by_email(db: &PgPool, email: &str) -> Result<User> {
+        sqlx::query_as::<_, User>(
+            "SELECT * FROM users WHERE LOWER(email) = LOWER($1) AND deleted_at IS NULL",
+        )
+        .bind(email)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("User not found".to_string()))
+    }
+
+    /// Replace the password without knowing the current one, ending every
+    /// session. Callers validate the new password.
+    pub async fn set_password(db: &PgPool, user_id: Uuid, new_password: &str) -> Result<()> {
+        Self::find_user(db, user_id).await?;
+        let password_hash = AuthService::hash_password(new_password)?;
+
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE users SET password_hash = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(&password_hash)
+            .execute(&mut *tx)
+            .await?;
+
+        Self::revoke_refresh_tokens(&mut tx, user_id, "password_reset").await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Mark the current address verified without a confirmation link
+    pub async fn mark_email_verified(db: &PgPool, user_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE users SET email_verified = true, email_verified_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL AND NOT email_verified
+            "#,
+        )
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Soft-delete the account with its decks, cards and study sessions, and
+    /// revoke its tokens
+    pub async fn delete_account(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: DeleteAccountDto,
+    ) -> Result<()> {
+        let user = Self::find_user(db, user_id).await?;
+        Self::require_password(&user, &dto.password)?;
+
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE users SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Unpublish too, so nothing of the account stays reachable
+        sqlx::query(
+            r#"
+            UPDATE decks SET deleted_at = NOW(), is_public = false
+            WHERE owner_id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE cards SET deleted_at = NOW()
+            WHERE deleted_at IS NULL
+                AND deck_id IN (SELECT id FROM decks WHERE owner_id = $1)
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE study_sessions SET deleted_at = NOW() WHERE user_id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        Self::revoke_refresh_tokens(&mut tx, user_id, "account_deleted").await?;
+
+        sqlx::query("UPDATE email_verification_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        tracing::info!("Account {} deleted", user_id);
+        Ok(())
+    }
+
+    /// Permanently remove accounts deleted more than
+    /// `ACCOUNT_ERASURE_GRACE_DAYS` ago. Everything the user owns cascades
+    /// with the row. Returns how many accounts were erased.
+    pub async fn erase_deleted_accounts(db: &PgPool) -> Result<u64> {
+        let erased = sqlx::query(
+            "DELETE FROM users WHERE deleted_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(ACCOUNT_ERASURE_GRACE_DAYS)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+        Ok(erased)
+    }
+
+    /// Run `erase_deleted_accounts` in the background every `every`
+    pub fn spawn_account_eraser(db: PgPool, every: std::time::Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match Self::erase_deleted_accounts(&db).await {
+                    Ok(0) => {}
+                    Ok(erased) => tracing::info!("Erased {} deleted accounts", erased),
+                    Err(e) => tracing::warn!("Account erasure failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Helper methods
+
+    async fn find_user(db: &PgPool, user_id: Uuid) -> Result<User> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 AND deleted_at IS NULL")
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?
+            .ok_or(AppError::UserNotFound)
+    }
+
+    fn require_password(user: &User, password: &str) -> Result<()> {
+        if !AuthService::verify_password(password, &user.password_hash)? {
+            return Err(AppError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// `owner` may already hold the address, when confirming their own email
+    async fn ensure_email_available(db: &PgPool, email: &str, owner: Option<Uuid>) -> Result<()> {
+        let taken = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE LOWER(email) = LOWER($1) AND id IS DISTINCT FROM $2)",
+        )
+        .bind(email)
+        .bind(owner)
+        .fetch_one(db)
+        .await?;
+
+        if taken {
+            return Err(AppError::BadRequest("Email already registered".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn request_email_change(db: &PgPool, user: &User, email: &str) -> Result<()> {
+        Self::ensure_email_available(db, email, None).await?;
+        Self::issue_verification_token(db, user, email).await
+    }
+
+    async fn issue_verification_token(db: &PgPool, user: &User, email: &str) -> Result<()> {
+        let token = AuthService::generate_random_token();
+        let expires_at = Utc::now() + Duration::hours(EMAIL_VERIFICATION_HOURS);
+
+        let mut tx = db.begin().await?;
+
+        // Only the latest request can be confirmed
+        sqlx::query("UPDATE email_verification_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(user.id)
+        .bind(email)
+        .bind(hash_token(&token))
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        // Confirming the current address is not a change
+        let pending_email = (email != user.email).then_some(email);
+        sqlx::query("UPDATE users SET pending_email = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user.id)
+            .bind(pending_email)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        // TODO: Send email with verification link
+        tracing::info!("Email verification token generated for user {} ({}): {}", user.id, email, token);
+        Ok(())
+    }
+
+    async fn revoke_refresh_tokens(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        reason: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW(), revoked_reason = $2
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(reason)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+}
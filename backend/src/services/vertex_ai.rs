@@ -1,18 +1,29 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::{DateTime, Duration, Utc};
-use reqwest::{Client, StatusCode};
+use rand::Rng;
+use reqwest::{header, Client, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::time::timeout;
 use tracing::{error, info, warn};
 
 use crate::{
-    config::VertexAiConfig,
-    models::ai::{VertexAiRequest, VertexAiResponse},
+    config::{AiConfig, OpenAiConfig, VertexAiConfig},
+    models::ai::{AiProviderKind, VertexAiRequest, VertexAiResponse},
+    services::{
+        ai_provider::{flashcard_request, AiProvider, FlashcardGenerationOptions, FlashcardStream},
+        circuit_breaker::CircuitBreaker,
+        openai::OpenAiClient,
+    },
 };
 
+/// Longest wait between retries, whatever the backoff or `Retry-After` says
+const MAX_RETRY_DELAY_MS: u64 = 30_000;
+
 // Google OAuth2 token
 #[derive(Debug, Clone)]
 struct AccessToken {
@@ -99,6 +110,33 @@ struct UsageMetadata {
     total_token_count: i32,
 }
 
+// streamGenerateContent sends one of these per SSE event; the final one
+// carries usage metadata and may have no candidates
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamChunk {
+    #[serde(default)]
+    candidates: Vec<StreamCandidate>,
+    usage_metadata: Option<StreamUsageMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamCandidate {
+    content: Option<StreamContent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamContent {
+    #[serde(default)]
+    parts: Vec<PartResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamUsageMetadata {
+    total_token_count: Option<i32>,
+}
+
 #[derive(Debug, Deserialize)]
 struct SafetyRating {
     category: String,
@@ -107,16 +145,35 @@ struct SafetyRating {
 
 pub struct VertexAiClient {
     config: VertexAiConfig,
+    local_fallback: Option<OpenAiClient>,
     http_client: Client,
     access_token: Option<AccessToken>,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl VertexAiClient {
-    pub fn new(config: VertexAiConfig) -> Self {
+    pub fn new(ai: &AiConfig, breaker: Arc<CircuitBreaker>) -> Self {
+        let generation = &ai.content_generation;
+        let local_fallback = generation
+            .local_model_url
+            .as_ref()
+            .filter(|_| generation.use_local_fallback)
+            .map(|url| {
+                OpenAiClient::new(OpenAiConfig {
+                    base_url: url.clone(),
+                    api_key: None,
+                    model: generation.local_model.clone(),
+                    timeout_seconds: ai.vertex_ai.timeout_seconds,
+                    cost_per_1k_tokens: 0.0,
+                })
+            });
+
         Self {
-            config,
+            config: ai.vertex_ai.clone(),
+            local_fallback,
             http_client: Client::new(),
             access_token: None,
+            breaker,
         }
     }
 
@@ -211,21 +268,141 @@ impl VertexAiClient {
         Ok("mock-jwt-assertion".to_string())
     }
 
-    // Generate content using Vertex AI
-    pub async fn generate_content(&mut self, request: VertexAiRequest) -> Result<VertexAiResponse> {
+    async fn generate_with_vertex(&mut self, request: &VertexAiRequest) -> Result<VertexAiResponse> {
         let access_token = self.get_access_token().await?;
-        
-        let api_url = format!(
-            "https://{}-aiplatform.googleapis.com/v1/projects/{}/locations/{}/publishers/google/models/{}:generateContent",
+        let api_url = self.model_url(&request.model, "generateContent");
+        let generate_request = self.build_generate_request(request);
+
+        let response = self
+            .send_with_retry(|client| {
+                client
+                    .post(&api_url)
+                    .header("Authorization", format!("Bearer {}", access_token))
+                    .header("Content-Type", "application/json")
+                    .json(&generate_request)
+            })
+            .await?;
+
+        let generate_response: GenerateContentResponse = response.json().await?;
+
+        if let Some(candidate) = generate_response.candidates.first() {
+            if let Some(part) = candidate.content.parts.first() {
+                if let Some(text) = &part.text {
+                    let tokens_used = generate_response.usage_metadata
+                        .map(|u| u.total_token_count)
+                        .unwrap_or(0);
+
+                    return Ok(VertexAiResponse {
+                        text: text.clone(),
+                        tokens_used,
+                        model: request.model.clone(),
+                        finish_reason: candidate.finish_reason.clone(),
+                    });
+                }
+            }
+        }
+
+        Err(anyhow::anyhow!("No valid response from Vertex AI"))
+    }
+
+    // Send a request, retrying timeouts, connection errors, 429 and 5xx
+    // responses with exponential backoff. Every attempt goes through the
+    // shared circuit breaker; other errors are returned at once.
+    async fn send_with_retry(&self, build: impl Fn(&Client) -> RequestBuilder) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            if !self.breaker.allow() {
+                return Err(anyhow::anyhow!("Vertex AI is unavailable after repeated failures"));
+            }
+
+            let outcome = timeout(
+                std::time::Duration::from_secs(self.config.timeout_seconds),
+                build(&self.http_client).send(),
+            )
+            .await;
+
+            let (error, retry_after) = match outcome {
+                Ok(Ok(response)) if response.status().is_success() => {
+                    self.breaker.record_success();
+                    return Ok(response);
+                }
+                Ok(Ok(response)) if is_retryable(response.status()) => {
+                    let status = response.status();
+                    let retry_after = retry_after(&response);
+                    let error_text = response.text().await.unwrap_or_default();
+                    (anyhow::anyhow!("Vertex AI API error ({}): {}", status, error_text), retry_after)
+                }
+                Ok(Ok(response)) => {
+                    // The request itself was refused; the service is up
+                    self.breaker.record_success();
+                    let error_text = response.text().await?;
+                    error!("Vertex AI API error: {}", error_text);
+                    return Err(anyhow::anyhow!("Vertex AI API error: {}", error_text));
+                }
+                Ok(Err(e)) => (e.into(), None),
+                Err(_) => (
+                    anyhow::anyhow!("Vertex AI did not respond within {}s", self.config.timeout_seconds),
+                    None,
+                ),
+            };
+
+            self.breaker.record_failure();
+            if attempt >= self.config.max_retries {
+                error!("Vertex AI call failed after {} attempt(s): {}", attempt + 1, error);
+                return Err(error);
+            }
+
+            let delay = retry_after.unwrap_or_else(|| backoff_delay(self.config.retry_base_delay_ms, attempt));
+            warn!("Vertex AI call failed ({}); retrying in {:?}", error, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    // Start a streamed generation with streamGenerateContent; the returned
+    // stream yields text as the model produces it
+    pub async fn stream_content(&mut self, request: VertexAiRequest) -> Result<ContentStream> {
+        let access_token = self.get_access_token().await?;
+        let api_url = format!("{}?alt=sse", self.model_url(&request.model, "streamGenerateContent"));
+        let generate_request = self.build_generate_request(&request);
+        let idle_timeout = std::time::Duration::from_secs(self.config.timeout_seconds);
+
+        // Only opening the stream is retried
+        let response = self
+            .send_with_retry(|client| {
+                client
+                    .post(&api_url)
+                    .header("Authorization", format!("Bearer {}", access_token))
+                    .header("Content-Type", "application/json")
+                    .json(&generate_request)
+            })
+            .await?;
+
+        Ok(ContentStream {
+            response,
+            idle_timeout,
+            buffer: Vec::new(),
+            model: request.model,
+            tokens_used: 0,
+            finished: false,
+        })
+    }
+
+    fn model_url(&self, model: &str, method: &str) -> String {
+        format!(
+            "https://{}-aiplatform.googleapis.com/v1/projects/{}/locations/{}/publishers/google/models/{}:{}",
             self.config.location,
             self.config.project_id,
             self.config.location,
-            request.model.as_str()
-        );
+            model,
+            method
+        )
+    }
 
-        let generate_request = GenerateContentRequest {
+    fn build_generate_request(&self, request: &VertexAiRequest) -> GenerateContentRequest {
+        GenerateContentRequest {
             contents: vec![Content {
-                parts: vec![Part::Text { text: request.prompt }],
+                parts: vec![Part::Text { text: request.prompt.clone() }],
                 role: "user".to_string(),
             }],
             generation_config: GenerationConfig {
@@ -253,238 +430,156 @@ impl VertexAiClient {
                     threshold: "BLOCK_MEDIUM_AND_ABOVE".to_string(),
                 },
             ],
-        };
+        }
+    }
+}
 
-        let response = timeout(
-            std::time::Duration::from_secs(self.config.timeout_seconds),
-            self.http_client
-                .post(&api_url)
-                .header("Authorization", format!("Bearer {}", access_token))
-                .header("Content-Type", "application/json")
-                .json(&generate_request)
-                .send()
-        ).await??;
+#[async_trait]
+impl AiProvider for VertexAiClient {
+    fn kind(&self) -> AiProviderKind {
+        AiProviderKind::VertexAi
+    }
 
-        if response.status().is_success() {
-            let generate_response: GenerateContentResponse = response.json().await?;
-            
-            if let Some(candidate) = generate_response.candidates.first() {
-                if let Some(part) = candidate.content.parts.first() {
-                    if let Some(text) = &part.text {
-                        let tokens_used = generate_response.usage_metadata
-                            .map(|u| u.total_token_count)
-                            .unwrap_or(0);
-                        
-                        return Ok(VertexAiResponse {
-                            text: text.clone(),
-                            tokens_used,
-                            model: request.model,
-                            finish_reason: candidate.finish_reason.clone(),
-                        });
-                    }
-                }
+    fn default_model(&self) -> &str {
+        &self.config.default_model
+    }
+
+    // Generate content using Vertex AI, or the local model when Vertex AI
+    // fails and the fallback is configured
+    async fn generate_content(&mut self, request: VertexAiRequest) -> Result<VertexAiResponse> {
+        let error = match self.generate_with_vertex(&request).await {
+            Ok(response) => return Ok(response),
+            Err(e) => e,
+        };
+
+        match self.local_fallback.as_mut() {
+            Some(local) => {
+                warn!("Vertex AI failed ({}); falling back to local model {}", error, local.default_model());
+                let model = local.default_model().to_string();
+                local.generate_content(VertexAiRequest { model, ..request }).await
             }
-            
-            Err(anyhow::anyhow!("No valid response from Vertex AI"))
-        } else {
-            let error_text = response.text().await?;
-            error!("Vertex AI API error: {}", error_text);
-            Err(anyhow::anyhow!("Vertex AI API error: {}", error_text))
+            None => Err(error),
         }
     }
 
-    // Generate flashcards from text content
-    pub async fn generate_flashcards(
+    // Generate flashcards with a streamed response, yielding each card as
+    // soon as its JSON object is complete
+    async fn stream_flashcards(
         &mut self,
         text: &str,
         options: &FlashcardGenerationOptions,
-    ) -> Result<Vec<GeneratedFlashcard>> {
-        let prompt = self.build_flashcard_prompt(text, options);
-        
-        let request = VertexAiRequest {
-            prompt,
-            model: self.config.default_model.clone(),
-            max_tokens: Some(2048),
-            temperature: Some(0.7),
-            top_p: Some(0.95),
-            top_k: Some(40),
+    ) -> Result<FlashcardStream> {
+        let request = flashcard_request(&self.config.default_model, text, options);
+        let error = match self.stream_content(request).await {
+            Ok(content) => return Ok(FlashcardStream::new(content)),
+            Err(e) => e,
         };
 
-        let response = self.generate_content(request).await?;
-        self.parse_flashcards(&response.text)
-    }
-
-    // Build prompt for flashcard generation
-    fn build_flashcard_prompt(&self, text: &str, options: &FlashcardGenerationOptions) -> String {
-        let max_cards = options.max_cards.unwrap_or(10);
-        let difficulty = options.difficulty.as_deref().unwrap_or("medium");
-        let format = options.format.as_deref().unwrap_or("question_answer");
-        
-        format!(
-            r#"Generate {} flashcards from the following text. 
-            Difficulty level: {}
-            Format: {}
-            
-            Requirements:
-            1. Each flashcard should test understanding, not just memorization
-            2. Include a mix of factual and conceptual questions
-            3. Make the answers clear and concise
-            4. If the text contains examples, use them in the flashcards
-            
-            Format the output as JSON array with objects containing:
-            - "front": the question or prompt
-            - "back": the answer
-            - "explanation": optional additional context (only if helpful)
-            - "difficulty": estimated difficulty (1-5)
-            - "tags": relevant topic tags as array
-            
-            Text to process:
-            {}
-            
-            Generate exactly {} flashcards as a valid JSON array:"#,
-            max_cards, difficulty, format, text, max_cards
-        )
-    }
-
-    // Parse flashcards from AI response
-    fn parse_flashcards(&self, response: &str) -> Result<Vec<GeneratedFlashcard>> {
-        // Try to extract JSON from the response
-        let json_start = response.find('[').unwrap_or(0);
-        let json_end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
-        let json_str = &response[json_start..json_end];
-        
-        match serde_json::from_str::<Vec<GeneratedFlashcard>>(json_str) {
-            Ok(flashcards) => Ok(flashcards),
-            Err(e) => {
-                warn!("Failed to parse flashcards JSON: {}", e);
-                // Try to extract flashcards manually as fallback
-                self.parse_flashcards_fallback(response)
+        // The local model answers in one go rather than streaming
+        match self.local_fallback.as_mut() {
+            Some(local) => {
+                warn!("Vertex AI failed ({}); falling back to local model {}", error, local.default_model());
+                let batch = local.generate_flashcards(text, options).await?;
+                Ok(FlashcardStream::from_batch(batch))
             }
+            None => Err(error),
         }
     }
+}
 
-    // Fallback parser for flashcards
-    fn parse_flashcards_fallback(&self, response: &str) -> Result<Vec<GeneratedFlashcard>> {
-        let mut flashcards = Vec::new();
-        
-        // Simple pattern matching for Q&A format
-        let lines: Vec<&str> = response.lines().collect();
-        let mut i = 0;
-        
-        while i < lines.len() {
-            if lines[i].contains("Front:") || lines[i].contains("Question:") {
-                let front = lines[i]
-                    .replace("Front:", "")
-                    .replace("Question:", "")
-                    .trim()
-                    .to_string();
-                
-                if i + 1 < lines.len() && (lines[i + 1].contains("Back:") || lines[i + 1].contains("Answer:")) {
-                    let back = lines[i + 1]
-                        .replace("Back:", "")
-                        .replace("Answer:", "")
-                        .trim()
-                        .to_string();
-                    
-                    flashcards.push(GeneratedFlashcard {
-                        front,
-                        back,
-                        explanation: None,
-                        difficulty: Some(3),
-                        tags: vec![],
-                    });
-                    
-                    i += 2;
-                } else {
-                    i += 1;
+/// Throttling and server-side failures are worth another try
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The server's `Retry-After` in seconds, capped like the backoff
+fn retry_after(response: &Response) -> Option<std::time::Duration> {
+    let seconds: u64 = response
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(std::time::Duration::from_millis(seconds.saturating_mul(1000).min(MAX_RETRY_DELAY_MS)))
+}
+
+/// Delay before retry number `attempt` (0-based): `base_ms` doubled per
+/// attempt, capped, with the upper half randomized so clients that failed
+/// together do not retry together
+pub fn backoff_delay(base_ms: u64, attempt: u32) -> std::time::Duration {
+    let ceiling = base_ms
+        .saturating_mul(1u64 << attempt.min(20))
+        .min(MAX_RETRY_DELAY_MS);
+    let delay = rand::thread_rng().gen_range(ceiling / 2..=ceiling);
+    std::time::Duration::from_millis(delay)
+}
+
+/// Text of a streamed Vertex AI response, read incrementally from its
+/// server-sent events
+pub struct ContentStream {
+    response: reqwest::Response,
+    idle_timeout: std::time::Duration,
+    buffer: Vec<u8>,
+    model: String,
+    tokens_used: i32,
+    finished: bool,
+}
+
+impl ContentStream {
+    /// Next piece of generated text, or `None` once the response has ended
+    pub async fn next_text(&mut self) -> Result<Option<String>> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                if let Some(text) = self.parse_event_line(&line)? {
+                    return Ok(Some(text));
                 }
-            } else {
-                i += 1;
+                continue;
+            }
+
+            if self.finished {
+                // A final event without a trailing newline
+                let line = std::mem::take(&mut self.buffer);
+                return self.parse_event_line(&line);
+            }
+
+            match timeout(self.idle_timeout, self.response.chunk()).await?? {
+                Some(bytes) => self.buffer.extend_from_slice(&bytes),
+                None => self.finished = true,
             }
-        }
-        
-        if flashcards.is_empty() {
-            Err(anyhow::anyhow!("Could not parse any flashcards from response"))
-        } else {
-            Ok(flashcards)
         }
     }
 
-    // Summarize document content
-    pub async fn summarize_document(&mut self, text: &str, max_length: Option<i32>) -> Result<String> {
-        let max_length = max_length.unwrap_or(500);
-        
-        let prompt = format!(
-            r#"Provide a concise summary of the following text in approximately {} words.
-            Focus on the main ideas, key concepts, and important details.
-            
-            Text:
-            {}
-            
-            Summary:"#,
-            max_length, text
-        );
-
-        let request = VertexAiRequest {
-            prompt,
-            model: self.config.default_model.clone(),
-            max_tokens: Some(max_length * 2), // Approximate tokens
-            temperature: Some(0.3), // Lower temperature for more focused summaries
-            top_p: Some(0.9),
-            top_k: Some(30),
-        };
+    pub fn model(&self) -> &str {
+        &self.model
+    }
 
-        let response = self.generate_content(request).await?;
-        Ok(response.text)
+    /// Token usage, known once the stream has been read to the end
+    pub fn tokens_used(&self) -> i32 {
+        self.tokens_used
     }
 
-    // Extract key concepts from text
-    pub async fn extract_concepts(&mut self, text: &str) -> Result<Vec<String>> {
-        let prompt = format!(
-            r#"Extract the key concepts, terms, and topics from the following text.
-            List each concept on a new line, without numbering or bullets.
-            Focus on important nouns, technical terms, and main ideas.
-            
-            Text:
-            {}
-            
-            Key concepts:"#,
-            text
-        );
-
-        let request = VertexAiRequest {
-            prompt,
-            model: self.config.default_model.clone(),
-            max_tokens: Some(500),
-            temperature: Some(0.2),
-            top_p: Some(0.9),
-            top_k: Some(20),
+    fn parse_event_line(&mut self, line: &[u8]) -> Result<Option<String>> {
+        let line = std::str::from_utf8(line)?.trim();
+        let Some(data) = line.strip_prefix("data:") else {
+            return Ok(None);
         };
 
-        let response = self.generate_content(request).await?;
-        
-        Ok(response.text
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| line.trim().to_string())
-            .collect())
-    }
-}
+        let chunk: StreamChunk = serde_json::from_str(data.trim())?;
+        if let Some(total) = chunk.usage_metadata.and_then(|u| u.total_token_count) {
+            self.tokens_used = total;
+        }
 
-// Helper structures for flashcard generation
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FlashcardGenerationOptions {
-    pub max_cards: Option<i32>,
-    pub difficulty: Option<String>,
-    pub format: Option<String>,
-    pub include_explanations: Option<bool>,
-}
+        let text: String = chunk
+            .candidates
+            .into_iter()
+            .filter_map(|c| c.content)
+            .flat_map(|c| c.parts)
+            .filter_map(|p| p.text)
+            .collect();
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GeneratedFlashcard {
-    pub front: String,
-    pub back: String,
-    pub explanation: Option<String>,
-    pub difficulty: Option<i32>,
-    pub tags: Vec<String>,
+        Ok((!text.is_empty()).then_some(text))
+    }
 }
@@ -0,0 +1,324 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{redirect, Url};
+use serde_json::{json, Value as JsonValue};
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        CreateDeckWebhookDto, CreatedDeckWebhook, DeckRole, DeckWebhook, UpdateDeckWebhookDto,
+        WEBHOOK_EVENTS,
+    },
+    services::{auth::AuthService, sharing::SharingService},
+    utils::{AppError, Result},
+};
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Webhooks are switched off after this many failed deliveries in a row
+pub const MAX_CONSECUTIVE_FAILURES: i32 = 10;
+
+pub const EVENT_HEADER: &str = "X-DeckOracle-Event";
+pub const DELIVERY_HEADER: &str = "X-DeckOracle-Delivery";
+pub const SIGNATURE_HEADER: &str = "X-DeckOracle-Signature";
+
+/// Deck-scoped webhooks. Deliveries are signed with HMAC-SHA256 over the
+/// raw body using the secret returned when the webhook was created.
+pub struct WebhookService;
+
+impl WebhookService {
+    pub async fn list(db: &PgPool, deck_id: Uuid, user_id: Uuid) -> Result<Vec<DeckWebhook>> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+
+        let webhooks = sqlx::query_as::<_, DeckWebhook>(
+            "SELECT * FROM deck_webhooks WHERE deck_id = $1 ORDER BY created_at",
+        )
+        .bind(deck_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(webhooks)
+    }
+
+    pub async fn create(
+        db: &PgPool,
+        deck_id: Uuid,
+        user_id: Uuid,
+        dto: CreateDeckWebhookDto,
+    ) -> Result<CreatedDeckWebhook> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+        validate_url(&dto.url).await?;
+        let events = normalize_events(dto.events)?;
+
+        let secret = AuthService::generate_random_token();
+        let webhook = sqlx::query_as::<_, DeckWebhook>(
+            r#"
+            INSERT INTO deck_webhooks (deck_id, created_by, url, secret, events)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(deck_id)
+        .bind(user_id)
+        .bind(&dto.url)
+        .bind(&secret)
+        .bind(&events)
+        .fetch_one(db)
+        .await?;
+
+        Ok(CreatedDeckWebhook { webhook, secret })
+    }
+
+    pub async fn update(
+        db: &PgPool,
+        deck_id: Uuid,
+        webhook_id: Uuid,
+        user_id: Uuid,
+        dto: UpdateDeckWebhookDto,
+    ) -> Result<DeckWebhook> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+        if let Some(url) = &dto.url {
+            validate_url(url).await?;
+        }
+        let events = dto.events.map(normalize_events).transpose()?;
+
+        sqlx::query_as::<_, DeckWebhook>(
+            r#"
+            UPDATE deck_webhooks
+            SET url = COALESCE($3, url),
+                events = COALESCE($4, events),
+                is_active = COALESCE($5, is_active),
+                consecutive_failures = CASE WHEN $5 THEN 0 ELSE consecutive_failures END,
+                updated_at = NOW()
+            WHERE id = $1 AND deck_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(webhook_id)
+        .bind(deck_id)
+        .bind(&dto.url)
+        .bind(&events)
+        .bind(dto.is_active)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("Resource not found".to_string()))
+    }
+
+    pub async fn delete(db: &PgPool, deck_id: Uuid, webhook_id: Uuid, user_id: Uuid) -> Result<()> {
+        SharingService::require_deck_role(db, deck_id, user_id, DeckRole::Editor).await?;
+
+        let result = sqlx::query("DELETE FROM deck_webhooks WHERE id = $1 AND deck_id = $2")
+            .bind(webhook_id)
+            .bind(deck_id)
+            .execute(db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Resource not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Deliver an event to the deck's active webhooks in the background.
+    /// Failures are recorded on the webhook and never reach the caller.
+    pub fn dispatch(db: PgPool, deck_id: Uuid, event: &'static str, data: JsonValue) {
+        tokio::spawn(async move {
+            if let Err(e) = Self::deliver(&db, deck_id, event, data).await {
+                tracing::error!("Webhook dispatch for deck {} failed: {}", deck_id, e);
+            }
+        });
+    }
+
+    /// Deliver an event to each active webhook of the deck subscribed to it
+    pub async fn deliver(db: &PgPool, deck_id: Uuid, event: &str, data: JsonValue) -> Result<()> {
+        let webhooks = sqlx::query_as::<_, DeckWebhook>(
+            "SELECT * FROM deck_webhooks WHERE deck_id = $1 AND is_active AND $2 = ANY(events)",
+        )
+        .bind(deck_id)
+        .bind(event)
+        .fetch_all(db)
+        .await?;
+        if webhooks.is_empty() {
+            return Ok(());
+        }
+
+        let delivery_id = Uuid::new_v4();
+        let body = serde_json::to_vec(&json!({
+            "id": delivery_id,
+            "event": event,
+            "deck_id": deck_id,
+            "occurred_at": Utc::now(),
+            "data": data,
+        }))?;
+
+        for webhook in webhooks {
+            // The host may resolve somewhere else than when it was registered
+            let client = match validate_url(&webhook.url).await.and_then(pinned_client) {
+                Ok(client) => client,
+                Err(e) => {
+                    Self::record_delivery(db, webhook.id, None, Some(&e.to_string())).await?;
+                    continue;
+                }
+            };
+
+            let outcome = client
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event)
+                .header(DELIVERY_HEADER, delivery_id.to_string())
+                .header(SIGNATURE_HEADER, sign(&webhook.secret, &body))
+                .body(body.clone())
+                .send()
+                .await;
+
+            let (status, error) = match outcome {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16() as i32), None)
+                }
+                Ok(response) => (
+                    Some(response.status().as_u16() as i32),
+                    Some(format!("Endpoint responded with {}", response.status())),
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            };
+
+            Self::record_delivery(db, webhook.id, status, error.as_deref()).await?;
+        }
+
+        Ok(())
+    }
+
+    // Helper methods
+
+    async fn record_delivery(
+        db: &PgPool,
+        webhook_id: Uuid,
+        status: Option<i32>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE deck_webhooks
+            SET last_delivery_at = NOW(),
+                last_status = $2,
+                last_error = $3,
+                consecutive_failures = CASE WHEN $3 IS NULL THEN 0 ELSE consecutive_failures + 1 END,
+                is_active = is_active AND ($3 IS NULL OR consecutive_failures + 1 < $4)
+            WHERE id = $1
+            "#,
+        )
+        .bind(webhook_id)
+        .bind(status)
+        .bind(error)
+        .bind(MAX_CONSECUTIVE_FAILURES)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// `sha256=<hex HMAC of the body>`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// A webhook host and the addresses it resolved to when it was checked
+struct CheckedHost {
+    host: String,
+    addresses: Vec<SocketAddr>,
+}
+
+/// Webhooks must point at a public http(s) endpoint. Every address the host
+/// resolves to is checked, so internal services cannot be reached through
+/// a name that resolves to them.
+async fn validate_url(url: &str) -> Result<CheckedHost> {
+    let parsed = Url::parse(url)
+        .map_err(|_| AppError::ValidationError("Webhook URL is not a valid URL".to_string()))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::ValidationError(
+            "Webhook URL must use http or https".to_string(),
+        ));
+    }
+    let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+        return Err(AppError::ValidationError("Webhook URL must have a host".to_string()));
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| AppError::ValidationError(format!("Webhook host {} does not resolve", host)))?
+        .collect();
+    if addresses.is_empty() || !addresses.iter().all(|address| is_public_address(address.ip())) {
+        return Err(AppError::ValidationError(
+            "Webhook URL must not point at a private or local address".to_string(),
+        ));
+    }
+    Ok(CheckedHost { host: host.to_string(), addresses })
+}
+
+/// Built per delivery with the host pinned to the addresses `validate_url`
+/// checked, so a second lookup cannot rebind it to an internal one. Redirects
+/// are not followed, so an endpoint cannot bounce a delivery elsewhere either.
+fn pinned_client(checked: CheckedHost) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .redirect(redirect::Policy::none())
+        .resolve_to_addrs(&checked.host, &checked.addresses)
+        .build()
+        .map_err(|e| {
+            tracing::error!("Failed to build webhook client: {}", e);
+            AppError::InternalServerError
+        })
+}
+
+fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(v6),
+        },
+    }
+}
+
+fn is_public_v4(address: Ipv4Addr) -> bool {
+    let [a, b, ..] = address.octets();
+    !(address.is_loopback()
+        || address.is_private()
+        || address.is_link_local()
+        || address.is_unspecified()
+        || address.is_broadcast()
+        || address.is_multicast()
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b)))
+}
+
+fn is_public_v6(address: Ipv6Addr) -> bool {
+    let first = address.segments()[0];
+    !(address.is_loopback()
+        || address.is_unspecified()
+        || address.is_multicast()
+        // Unique local, fc00::/7
+        || (first & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10
+        || (first & 0xffc0) == 0xfe80)
+}
+
+fn normalize_events(mut events: Vec<String>) -> Result<Vec<String>> {
+    if let Some(unknown) = events.iter().find(|e| !WEBHOOK_EVENTS.contains(&e.as_str())) {
+        return Err(AppError::ValidationError(format!("Unknown webhook event: {}", unknown)));
+    }
+    events.sort();
+    events.dedup();
+    Ok(events)
+}
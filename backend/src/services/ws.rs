@@ -0,0 +1,211 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use rand::{distributions::Alphanumeric, Rng};
+use sqlx::PgPool;
+use tokio::sync::{mpsc, Mutex, RwLock};
+use uuid::Uuid;
+
+use crate::{
+    models::ai::{WsMessage, WsSubscription},
+    utils::{AppError, Result},
+};
+
+pub const SUBSCRIPTION_TYPES: &[&str] = &["recommendations", "study_insights", "progress_updates"];
+
+/// How long a connection ticket can be redeemed after it was issued
+pub const TICKET_TTL_SECONDS: u64 = 30;
+const TICKET_LENGTH: usize = 32;
+
+struct Connection {
+    id: String,
+    sender: mpsc::UnboundedSender<WsMessage>,
+    subscriptions: HashSet<String>,
+}
+
+/// In-memory registry of live WebSocket connections, keyed by user
+#[derive(Default)]
+pub struct WsHub {
+    connections: RwLock<HashMap<Uuid, Vec<Connection>>>,
+    // Single-use tickets browsers connect with, since they cannot send an
+    // Authorization header on the upgrade request
+    tickets: Mutex<HashMap<String, (Uuid, Instant)>>,
+}
+
+impl WsHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue a short-lived ticket that lets the user open one connection
+    pub async fn issue_ticket(&self, user_id: Uuid) -> String {
+        let ticket: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TICKET_LENGTH)
+            .map(char::from)
+            .collect();
+        let expires_at = Instant::now() + Duration::from_secs(TICKET_TTL_SECONDS);
+
+        let mut tickets = self.tickets.lock().await;
+        // Tickets that were never used would otherwise pile up
+        let now = Instant::now();
+        tickets.retain(|_, (_, expires_at)| *expires_at > now);
+        tickets.insert(ticket.clone(), (user_id, expires_at));
+        ticket
+    }
+
+    /// The user a ticket was issued to; the ticket cannot be used again
+    pub async fn redeem_ticket(&self, ticket: &str) -> Option<Uuid> {
+        let (user_id, expires_at) = self.tickets.lock().await.remove(ticket)?;
+        (expires_at > Instant::now()).then_some(user_id)
+    }
+
+    pub async fn register(
+        &self,
+        user_id: Uuid,
+        connection_id: &str,
+        sender: mpsc::UnboundedSender<WsMessage>,
+    ) {
+        self.connections
+            .write()
+            .await
+            .entry(user_id)
+            .or_default()
+            .push(Connection {
+                id: connection_id.to_string(),
+                sender,
+                subscriptions: HashSet::new(),
+            });
+    }
+
+    pub async fn unregister(&self, user_id: Uuid, connection_id: &str) {
+        let mut connections = self.connections.write().await;
+        if let Some(user_connections) = connections.get_mut(&user_id) {
+            user_connections.retain(|c| c.id != connection_id);
+            if user_connections.is_empty() {
+                connections.remove(&user_id);
+            }
+        }
+    }
+
+    pub async fn set_subscribed(
+        &self,
+        user_id: Uuid,
+        connection_id: &str,
+        subscription_type: &str,
+        subscribed: bool,
+    ) {
+        let mut connections = self.connections.write().await;
+        let connection = connections
+            .get_mut(&user_id)
+            .and_then(|list| list.iter_mut().find(|c| c.id == connection_id));
+
+        if let Some(connection) = connection {
+            if subscribed {
+                connection.subscriptions.insert(subscription_type.to_string());
+            } else {
+                connection.subscriptions.remove(subscription_type);
+            }
+        }
+    }
+
+    /// Push a message to every connection of the user subscribed to the given type
+    pub async fn publish(&self, user_id: Uuid, subscription_type: &str, message: WsMessage) {
+        let connections = self.connections.read().await;
+        if let Some(user_connections) = connections.get(&user_id) {
+            for connection in user_connections
+                .iter()
+                .filter(|c| c.subscriptions.contains(subscription_type))
+            {
+                // A closed receiver just means the socket is shutting down
+                let _ = connection.sender.send(message.clone());
+            }
+        }
+    }
+
+    /// Push a message to every connection of the user regardless of subscriptions
+    pub async fn send_to_user(&self, user_id: Uuid, message: WsMessage) {
+        let connections = self.connections.read().await;
+        if let Some(user_connections) = connections.get(&user_id) {
+            for connection in user_connections {
+                let _ = connection.sender.send(message.clone());
+            }
+        }
+    }
+}
+
+/// Persistence for connection and subscription rows
+pub struct WsSubscriptionService;
+
+impl WsSubscriptionService {
+    pub async fn subscribe(
+        db: &PgPool,
+        user_id: Uuid,
+        connection_id: &str,
+        subscription_type: &str,
+    ) -> Result<WsSubscription> {
+        if !SUBSCRIPTION_TYPES.contains(&subscription_type) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown subscription type: {}",
+                subscription_type
+            )));
+        }
+
+        let subscription = sqlx::query_as::<_, WsSubscription>(
+            r#"
+            INSERT INTO ws_subscriptions (user_id, connection_id, subscription_type)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (connection_id, subscription_type)
+            DO UPDATE SET active = true, disconnected_at = NULL
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(connection_id)
+        .bind(subscription_type)
+        .fetch_one(db)
+        .await?;
+
+        Ok(subscription)
+    }
+
+    pub async fn unsubscribe(db: &PgPool, connection_id: &str, subscription_type: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE ws_subscriptions SET active = false
+            WHERE connection_id = $1 AND subscription_type = $2
+            "#,
+        )
+        .bind(connection_id)
+        .bind(subscription_type)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn touch(db: &PgPool, connection_id: &str) -> Result<()> {
+        sqlx::query("UPDATE ws_subscriptions SET last_ping_at = NOW() WHERE connection_id = $1")
+            .bind(connection_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn disconnect(db: &PgPool, connection_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE ws_subscriptions SET active = false, disconnected_at = NOW()
+            WHERE connection_id = $1 AND active = true
+            "#,
+        )
+        .bind(connection_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,24 +1,102 @@
 use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use crate::config::Config;
+use crate::{
+    config::{Config, DatabaseConfig},
+    middleware::rate_limit::{self, RateLimitBackend},
+    services::{
+        circuit_breaker::CircuitBreaker,
+        exporters::ExporterRegistry,
+        mailer::{self, Mailer},
+        queue_cache::QueueCache,
+        storage::MediaStore,
+        ws::WsHub,
+    },
+};
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
     pub config: Arc<Config>,
+    pub ws: Arc<WsHub>,
+    pub exporters: Arc<ExporterRegistry>,
+    pub media: Arc<MediaStore>,
+    pub mailer: Arc<dyn Mailer>,
+    /// Shared by every Vertex AI call so an outage trips it for all requests
+    pub ai_breaker: Arc<CircuitBreaker>,
+    /// Request history for per-API-key rate limits
+    pub api_key_limits: Arc<dyn RateLimitBackend>,
+    /// Built study queues, reused until the user studies or edits cards
+    pub queue_cache: Arc<QueueCache>,
+    /// When the server started, for the uptime in `/health/detailed`
+    pub started_at: Instant,
 }
 
 impl AppState {
     pub async fn new(config: Config) -> Result<Self, sqlx::Error> {
-        let db = PgPoolOptions::new()
-            .max_connections(config.database.max_connections)
-            .connect(&config.database.url)
-            .await?;
+        let db = connect_with_retry(&config.database).await?;
 
-        Ok(Self {
+        Ok(Self::from_parts(db, config))
+    }
+
+    /// Build state from an existing pool and configuration (used by tests to
+    /// inject their own database and settings)
+    pub fn from_parts(db: PgPool, config: Config) -> Self {
+        let media = MediaStore::from_config(&config).expect("Invalid media storage configuration");
+        let mailer = mailer::from_config(&config.email).expect("Invalid email configuration");
+        let vertex_ai = &config.ai.vertex_ai;
+        let ai_breaker = CircuitBreaker::new(
+            vertex_ai.circuit_breaker_threshold,
+            Duration::from_secs(vertex_ai.circuit_breaker_cooldown_seconds),
+        );
+
+        let api_key_limits = rate_limit::backend_from_config(&config);
+        let queue_cache = QueueCache::from_config(&config.study);
+
+        Self {
             db,
             config: Arc::new(config),
-        })
+            ws: Arc::new(WsHub::new()),
+            exporters: Arc::new(ExporterRegistry::with_builtin()),
+            media: Arc::new(media),
+            mailer,
+            ai_breaker: Arc::new(ai_breaker),
+            api_key_limits,
+            queue_cache: Arc::new(queue_cache),
+            started_at: Instant::now(),
+        }
+    }
+}
+
+/// Connect to Postgres, retrying with exponential backoff while it is not
+/// up yet. Orchestrators often start the server alongside the database.
+async fn connect_with_retry(config: &DatabaseConfig) -> Result<PgPool, sqlx::Error> {
+    let options = PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_seconds));
+
+    let mut backoff = Duration::from_millis(config.connect_backoff_ms);
+    let max_backoff = Duration::from_millis(config.connect_max_backoff_ms);
+    let mut attempt = 1;
+    loop {
+        match options.clone().connect(&config.url).await {
+            Ok(db) => return Ok(db),
+            Err(e) if attempt < config.connect_attempts => {
+                tracing::warn!(
+                    "Database connection attempt {}/{} failed: {}; retrying in {:?}",
+                    attempt,
+                    config.connect_attempts,
+                    e,
+                    backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
     }
 }
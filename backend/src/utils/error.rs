@@ -1,10 +1,17 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
 use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+use crate::{middleware::request_id::current_request_id, utils::etag};
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -14,6 +21,21 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Deck not found")]
+    DeckNotFound,
+
+    #[error("Card not found")]
+    CardNotFound,
+
+    #[error("Folder not found")]
+    FolderNotFound,
+
+    #[error("User not found")]
+    UserNotFound,
+
+    #[error("Study session not found")]
+    StudySessionNotFound,
+
     #[error("Bad request: {0}")]
     BadRequest(String),
 
@@ -23,51 +45,313 @@ pub enum AppError {
     #[error("Forbidden")]
     Forbidden,
 
+    /// The account's email address has not been confirmed yet, and the
+    /// server requires that before content can be created
+    #[error("Email address not verified")]
+    EmailNotVerified,
+
     #[error("Internal server error")]
     InternalServerError,
 
     #[error("Validation error: {0}")]
     ValidationError(String),
 
+    /// A DTO failed its `validator` rules; each failing field is reported
+    #[error("Validation error: {}", invalid_fields_message(.0))]
+    InvalidFields(#[from] ValidationErrors),
+
     #[error("CSV parsing error: {0}")]
     CsvError(String),
 
     #[error("File upload error: {0}")]
     FileUploadError(String),
 
+    /// An upload is bigger than `MAX_FILE_SIZE`
+    #[error("File too large: the limit is {limit_bytes} bytes")]
+    FileTooLarge { limit_bytes: usize },
+
     #[error("Configuration error: {0}")]
     ConfigError(String),
+
+    /// A refresh token was presented after it had been rotated; the whole
+    /// token family has been revoked and the client must log in again
+    #[error("Refresh token reused")]
+    RefreshTokenReused,
+
+    #[error("Too many requests: {message}")]
+    TooManyRequests { message: String, retry_after_seconds: u64 },
+
+    /// The user's AI token quota is used up until the period resets
+    #[error("AI quota exceeded: {message}")]
+    AiQuotaExceeded { message: String, retry_after_seconds: u64 },
+
+    /// An update was based on an outdated version of a deck or card;
+    /// `current` is the entity as stored now, with its ETag
+    #[error("Edit conflict: {message}")]
+    EditConflict { message: String, current: Value, etag: HeaderValue },
+}
+
+/// Stable machine-readable error codes. Messages may change and are English
+/// only; clients should branch on and localize by these instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    InvalidCsv,
+    InvalidUpload,
+    FileTooLarge,
+    Unauthorized,
+    RefreshTokenReused,
+    Forbidden,
+    EmailNotVerified,
+    NotFound,
+    DeckNotFound,
+    CardNotFound,
+    FolderNotFound,
+    UserNotFound,
+    StudySessionNotFound,
+    EditConflict,
+    RateLimited,
+    AiQuotaExceeded,
+    InternalError,
+}
+
+/// Body of every error response
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    /// Human-readable message, in English
+    pub error: String,
+    /// HTTP status code, repeated for clients that only see the body
+    pub status: u16,
+    pub code: ErrorCode,
+    /// Failing fields, on `VALIDATION_FAILED` errors from request bodies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Vec<FieldError>>,
+    /// Seconds to wait before retrying, on 429 responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    /// The deck or card as it is now, on `EDIT_CONFLICT` errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub current: Option<Value>,
+    /// Matches the `X-Request-Id` response header; quote it when reporting
+    /// a failure so it can be found in the server logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// One failed rule on one field
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Path to the field, e.g. `password` or `cards[2].front`
+    pub field: String,
+    /// The rule that failed, e.g. `LENGTH`, `EMAIL`, `RANGE` or `WEAK_PASSWORD`
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Bounds of the rule, e.g. `{"min": 8}`
+    #[schema(value_type = Object)]
+    pub params: Map<String, Value>,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        if let AppError::TooManyRequests { message, retry_after_seconds }
+        | AppError::AiQuotaExceeded { message, retry_after_seconds } = &self
+        {
+            let body = Json(ErrorResponse {
+                error: message.clone(),
+                status: StatusCode::TOO_MANY_REQUESTS.as_u16(),
+                code: self.code(),
+                details: None,
+                retry_after: Some(*retry_after_seconds),
+                current: None,
+                request_id: current_request_id(),
+            });
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after_seconds.to_string())],
+                body,
+            )
+                .into_response();
+        }
+
+        if let AppError::EditConflict { message, current, etag } = &self {
+            let body = Json(ErrorResponse {
+                error: message.clone(),
+                status: StatusCode::CONFLICT.as_u16(),
+                code: self.code(),
+                details: None,
+                retry_after: None,
+                current: Some(current.clone()),
+                request_id: current_request_id(),
+            });
+            return (StatusCode::CONFLICT, [(header::ETAG, etag.clone())], body).into_response();
+        }
+
         let (status, error_message) = match self {
             AppError::Database(ref e) => {
                 tracing::error!("Database error: {:?}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Database error occurred")
+                (StatusCode::INTERNAL_SERVER_ERROR, "Database error occurred".to_string())
             }
-            AppError::NotFound(ref msg) => (StatusCode::NOT_FOUND, msg.as_str()),
-            AppError::BadRequest(ref msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
-            AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
-            AppError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden"),
+            AppError::NotFound(ref msg) => (StatusCode::NOT_FOUND, msg.clone()),
+            AppError::DeckNotFound
+            | AppError::CardNotFound
+            | AppError::FolderNotFound
+            | AppError::UserNotFound
+            | AppError::StudySessionNotFound => (StatusCode::NOT_FOUND, self.to_string()),
+            AppError::BadRequest(ref msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()),
+            AppError::RefreshTokenReused => (
+                StatusCode::UNAUTHORIZED,
+                "Refresh token has already been used; all sessions from this login were signed out"
+                    .to_string(),
+            ),
+            AppError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden".to_string()),
+            AppError::EmailNotVerified => (
+                StatusCode::FORBIDDEN,
+                "Verify your email address before creating content".to_string(),
+            ),
             AppError::InternalServerError => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
             }
-            AppError::ValidationError(ref msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
-            AppError::CsvError(ref msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
-            AppError::FileUploadError(ref msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
+            AppError::ValidationError(ref msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            AppError::InvalidFields(ref errors) => {
+                (StatusCode::BAD_REQUEST, invalid_fields_message(errors))
+            }
+            AppError::CsvError(ref msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            AppError::FileUploadError(ref msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            AppError::FileTooLarge { limit_bytes } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("File too large; uploads are limited to {}", format_size(limit_bytes)),
+            ),
             AppError::ConfigError(ref msg) => {
                 tracing::error!("Configuration error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Configuration error")
+                (StatusCode::INTERNAL_SERVER_ERROR, "Configuration error".to_string())
             }
+            AppError::TooManyRequests { .. }
+            | AppError::AiQuotaExceeded { .. }
+            | AppError::EditConflict { .. } => unreachable!("handled above"),
+        };
+
+        let body = ErrorResponse {
+            error: error_message,
+            status: status.as_u16(),
+            code: self.code(),
+            details: self.details(),
+            retry_after: None,
+            current: None,
+            request_id: current_request_id(),
         };
 
-        let body = Json(json!({
-            "error": error_message,
-            "status": status.as_u16(),
-        }));
+        (status, Json(body)).into_response()
+    }
+}
+
+impl AppError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Database(_) | AppError::InternalServerError | AppError::ConfigError(_) => {
+                ErrorCode::InternalError
+            }
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::DeckNotFound => ErrorCode::DeckNotFound,
+            AppError::CardNotFound => ErrorCode::CardNotFound,
+            AppError::FolderNotFound => ErrorCode::FolderNotFound,
+            AppError::UserNotFound => ErrorCode::UserNotFound,
+            AppError::StudySessionNotFound => ErrorCode::StudySessionNotFound,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::Unauthorized => ErrorCode::Unauthorized,
+            AppError::RefreshTokenReused => ErrorCode::RefreshTokenReused,
+            AppError::Forbidden => ErrorCode::Forbidden,
+            AppError::EmailNotVerified => ErrorCode::EmailNotVerified,
+            AppError::ValidationError(_) | AppError::InvalidFields(_) => ErrorCode::ValidationFailed,
+            AppError::CsvError(_) => ErrorCode::InvalidCsv,
+            AppError::FileUploadError(_) => ErrorCode::InvalidUpload,
+            AppError::FileTooLarge { .. } => ErrorCode::FileTooLarge,
+            AppError::TooManyRequests { .. } => ErrorCode::RateLimited,
+            AppError::AiQuotaExceeded { .. } => ErrorCode::AiQuotaExceeded,
+            AppError::EditConflict { .. } => ErrorCode::EditConflict,
+        }
+    }
+
+    /// `EditConflict` for a `kind` ("deck" or "card") changed by someone
+    /// else since the client read it
+    pub fn edit_conflict(kind: &str, id: Uuid, updated_at: DateTime<Utc>, current: &impl Serialize) -> Self {
+        AppError::EditConflict {
+            message: format!("The {} was changed since you loaded it", kind),
+            current: serde_json::to_value(current).unwrap_or(Value::Null),
+            etag: etag::entity_etag(kind, id, updated_at),
+        }
+    }
 
-        (status, body).into_response()
+    /// Per-field failures, for errors raised by DTO validation
+    pub fn details(&self) -> Option<Vec<FieldError>> {
+        match self {
+            AppError::InvalidFields(errors) => Some(field_errors(errors)),
+            _ => None,
+        }
+    }
+}
+
+// Whole megabytes or kilobytes when the size is one, bytes otherwise
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 && b % (1024 * 1024) == 0 => format!("{} MB", b / (1024 * 1024)),
+        b if b >= 1024 && b % 1024 == 0 => format!("{} KB", b / 1024),
+        b => format!("{} bytes", b),
+    }
+}
+
+// validator's own Display includes the rejected values, passwords included
+fn invalid_fields_message(errors: &ValidationErrors) -> String {
+    let mut fields: Vec<String> = field_errors(errors).into_iter().map(|e| e.field).collect();
+    fields.dedup();
+    format!("Invalid value for {}", fields.join(", "))
+}
+
+/// Flatten `validator` errors, including those of nested structs and lists,
+/// into one entry per failed rule, sorted by field
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields = Vec::new();
+    collect_field_errors(errors, "", &mut fields);
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, fields: &mut Vec<FieldError>) {
+    for (name, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                for error in errors {
+                    fields.push(FieldError {
+                        field: path.clone(),
+                        code: error.code.to_uppercase(),
+                        message: error.message.as_ref().map(|m| m.to_string()),
+                        // The rejected value is echoed back by validator;
+                        // leave it out so passwords never reach a response
+                        params: error
+                            .params
+                            .iter()
+                            .filter(|(key, _)| *key != "value")
+                            .map(|(key, value)| (key.to_string(), value.clone()))
+                            .collect(),
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{}[{}]", path, index), fields);
+                }
+            }
+        }
     }
 }
 
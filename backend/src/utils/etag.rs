@@ -0,0 +1,60 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// For per-user collections: browsers may keep a copy but must revalidate it
+/// with `If-None-Match` before every use
+pub const REVALIDATE_CACHE_CONTROL: &str = "private, no-cache";
+
+/// Strong ETag for a response identified by `kind` whose content changes
+/// whenever `version` does
+pub fn etag(kind: &str, version: &str) -> HeaderValue {
+    let digest = Sha256::digest(format!("{}:{}", kind, version));
+    let value = format!("\"{}\"", &hex::encode(digest)[..32]);
+    HeaderValue::from_str(&value).expect("valid ETag")
+}
+
+/// Whether the client's `If-None-Match` already names `etag`. Weak
+/// validators match too, as GET only needs weak comparison.
+pub fn is_fresh(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.split(',').any(|candidate| {
+                let candidate = candidate.trim();
+                candidate == "*" || candidate.trim_start_matches("W/") == etag
+            })
+        })
+}
+
+/// ETag of a single deck or card, which changes whenever its `updated_at`
+/// does. Updates accept it back in `If-Match`.
+pub fn entity_etag(kind: &str, id: Uuid, updated_at: DateTime<Utc>) -> HeaderValue {
+    etag(kind, &format!("{}:{}", id, updated_at.timestamp_micros()))
+}
+
+/// Whether the request's `If-Match` names `etag`; `None` without the header.
+/// Weak validators never match, as writes need strong comparison.
+pub fn if_match(headers: &HeaderMap, etag: &HeaderValue) -> Option<bool> {
+    let value = headers.get(header::IF_MATCH)?.to_str().ok()?;
+    Some(value.split(',').any(|candidate| {
+        let candidate = candidate.trim();
+        candidate == "*" || candidate == etag
+    }))
+}
+
+pub fn not_modified(etag: &HeaderValue, cache_control: &'static str) -> Response {
+    (
+        StatusCode::NOT_MODIFIED,
+        [
+            (header::CACHE_CONTROL, HeaderValue::from_static(cache_control)),
+            (header::ETAG, etag.clone()),
+        ],
+    )
+        .into_response()
+}
@@ -0,0 +1,21 @@
+use ammonia::Builder;
+use pulldown_cmark::{html, Options, Parser};
+
+/// Render card Markdown to HTML that is safe to inject into a page.
+///
+/// Raw HTML in the source is passed through the parser and then cleaned by
+/// ammonia, so scripts, event handlers and `javascript:` links never survive.
+pub fn render_markdown(source: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(source, options);
+
+    let mut unsafe_html = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, parser);
+
+    Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(&unsafe_html)
+        .to_string()
+}
@@ -1,5 +1,10 @@
 pub mod error;
+pub mod etag;
+pub mod markdown;
 pub mod pagination;
 
-pub use error::{AppError, Result};
-pub use pagination::{PaginatedResponse, PaginationParams, PaginationMeta};
+pub use error::{AppError, ErrorCode, ErrorResponse, FieldError, Result};
+pub use markdown::render_markdown;
+pub use pagination::{
+    CursorMeta, CursorPage, CursorParams, PaginatedResponse, PaginationMeta, PaginationParams,
+};
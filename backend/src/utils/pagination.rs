@@ -1,6 +1,11 @@
-use serde::{Deserialize, Serialize};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Clone, Deserialize)]
+use super::AppError;
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PaginationParams {
     #[serde(default = "default_page")]
     pub page: u32,
@@ -52,13 +57,13 @@ impl PaginationParams {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct PaginatedResponse<T> {
     pub data: Vec<T>,
     pub pagination: PaginationMeta,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct PaginationMeta {
     pub page: u32,
     pub limit: u32,
@@ -101,3 +106,91 @@ macro_rules! paginate_query {
             .offset(params.offset() as i64)
     }};
 }
+
+/// Keyset pagination: instead of a page number the client passes back the
+/// opaque `next_cursor` of the previous page. Pages stay stable while rows
+/// are inserted or deleted, and deep pages cost the same as the first.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CursorParams {
+    pub cursor: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+impl Default for CursorParams {
+    fn default() -> Self {
+        Self {
+            cursor: None,
+            limit: default_limit(),
+        }
+    }
+}
+
+impl CursorParams {
+    pub fn validate(&mut self) {
+        self.limit = self.limit.clamp(1, 100);
+    }
+
+    /// Decode the cursor into the key of the last row already returned
+    pub fn key<K: DeserializeOwned>(&self) -> Result<Option<K>, AppError> {
+        self.cursor.as_deref().map(decode_cursor).transpose()
+    }
+
+    pub fn limit_plus_one(&self) -> u32 {
+        self.limit + 1
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CursorPage<T> {
+    pub data: Vec<T>,
+    pub pagination: CursorMeta,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CursorMeta {
+    pub limit: u32,
+    pub has_next: bool,
+    pub next_cursor: Option<String>, // Pass as `cursor` to get the next page
+}
+
+impl<T> CursorPage<T> {
+    /// Build a page from up to `limit + 1` rows fetched after the cursor.
+    /// `key` gives the sort key a row resumes from.
+    pub fn new<K: Serialize>(mut data: Vec<T>, params: &CursorParams, key: impl Fn(&T) -> K) -> Self {
+        let has_next = data.len() > params.limit as usize;
+
+        // Remove the extra item used to check for next page
+        if has_next {
+            data.pop();
+        }
+
+        let next_cursor = if has_next {
+            data.last().map(|last| encode_cursor(&key(last)))
+        } else {
+            None
+        };
+
+        Self {
+            data,
+            pagination: CursorMeta {
+                limit: params.limit,
+                has_next,
+                next_cursor,
+            },
+        }
+    }
+}
+
+fn encode_cursor<K: Serialize>(key: &K) -> String {
+    let json = serde_json::to_vec(key).expect("cursor keys serialize");
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+fn decode_cursor<K: DeserializeOwned>(cursor: &str) -> Result<K, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or(AppError::BadRequest("Invalid cursor".to_string()))
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::{CreateCardDto, CreateDeckDto, CreateStudySessionDto},
+    services::{card::CardService, deck::DeckService, study::StudyService},
+};
+use serde_json::{json, Value};
+
+fn find<'a>(achievements: &'a Value, name: &str) -> &'a Value {
+    achievements.as_array().unwrap().iter().find(|a| a["name"] == name).unwrap()
+}
+
+#[tokio::test]
+async fn test_achievements_show_what_the_user_earned() {
+    let state = common::create_test_state().await;
+    let (user_id, learner) = common::register_with_token(&state, "learner@example.com").await;
+    let (_, other) = common::register_with_token(&state, "other@example.com").await;
+    let learner: HeaderValue = learner.parse().unwrap();
+    let other: HeaderValue = other.parse().unwrap();
+
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let dto = CreateCardDto {
+        front: "France".to_string(),
+        back: "Paris".to_string(),
+        position: None,
+    };
+    let card = CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap();
+    let dto = CreateStudySessionDto {
+        deck_id: Some(deck_id),
+        folder_id: None,
+        smart_deck_id: None,
+        study_mode: None,
+        card_ids: None,
+        time_limit_seconds: None,
+    };
+    let session = StudyService::create_study_session(&state.db, user_id, dto).await.unwrap();
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    // The first answer earns the first achievement, and says so
+    let response = server
+        .post(&format!("/api/v1/study/sessions/{}/progress", session.id))
+        .add_header(header::AUTHORIZATION, learner.clone())
+        .json(&json!({ "card_id": card.id, "rating": "good", "response_time_ms": 1500 }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::CREATED);
+    let earned: Vec<String> = response.json::<Value>()["new_achievements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(earned, vec!["First Steps"]);
+
+    let response = server.get("/api/v1/study/achievements").add_header(header::AUTHORIZATION, learner).await;
+    response.assert_status_ok();
+    let achievements: Value = response.json();
+    assert_eq!(achievements.as_array().unwrap().len(), 9);
+    let first_steps = find(&achievements, "First Steps");
+    assert_eq!(first_steps["earned"], true);
+    assert!(first_steps["earned_at"].is_string());
+    assert_eq!(first_steps["progress"], 1);
+    let getting_started = find(&achievements, "Getting Started");
+    assert_eq!(getting_started["earned"], false);
+    assert!(getting_started["earned_at"].is_null());
+    assert_eq!(getting_started["progress"], 1);
+    assert_eq!(find(&achievements, "Flawless")["progress"], 0);
+
+    // Other users see the catalog with their own progress only
+    let response = server.get("/api/v1/study/achievements").add_header(header::AUTHORIZATION, other).await;
+    let achievements: Value = response.json();
+    assert!(achievements.as_array().unwrap().iter().all(|a| a["earned"] == false));
+    assert_eq!(find(&achievements, "First Steps")["progress"], 0);
+
+    let response = server.get("/api/v1/study/achievements").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
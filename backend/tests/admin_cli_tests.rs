@@ -0,0 +1,67 @@
+mod common;
+
+use deckoracle_backend::models::{ClientInfo, LoginDto, RegisterDto};
+use deckoracle_backend::services::{
+    admin::AdminService, auth::AuthService, search::SearchService, user::UserService,
+};
+use deckoracle_backend::utils::AppError;
+
+fn login(email: &str, password: &str) -> LoginDto {
+    LoginDto {
+        email: email.to_string(),
+        password: password.to_string(),
+        remember_me: None,
+    }
+}
+
+#[tokio::test]
+async fn test_operator_can_reset_password_by_email() {
+    let state = common::create_test_state().await;
+    let register = RegisterDto {
+        email: "forgetful@example.com".to_string(),
+        password: "Original-pass1".to_string(),
+        display_name: None,
+    };
+    let user_id = AuthService::register(&state.db, &state.config, register).await.unwrap().user.id;
+
+    let user = UserService::find_by_email(&state.db, "Forgetful@Example.com").await.unwrap();
+    assert_eq!(user.id, user_id);
+    UserService::set_password(&state.db, user_id, "Replaced-pass2").await.unwrap();
+
+    let client = ClientInfo::default();
+    let old = AuthService::login(&state.db, &state.config, login("forgetful@example.com", "Original-pass1"), &client).await;
+    assert!(matches!(old, Err(AppError::Unauthorized)));
+    AuthService::login(&state.db, &state.config, login("forgetful@example.com", "Replaced-pass2"), &client)
+        .await
+        .unwrap();
+
+    let missing = UserService::find_by_email(&state.db, "nobody@example.com").await;
+    assert!(matches!(missing, Err(AppError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_operator_created_users_can_be_verified_admins() {
+    let state = common::create_test_state().await;
+    let register = RegisterDto {
+        email: "ops@example.com".to_string(),
+        password: "Operator-pass1".to_string(),
+        display_name: Some("Ops".to_string()),
+    };
+    let user_id = AuthService::register(&state.db, &state.config, register).await.unwrap().user.id;
+
+    UserService::mark_email_verified(&state.db, user_id).await.unwrap();
+    AdminService::set_admin(&state.db, user_id, true).await.unwrap();
+
+    assert!(UserService::is_email_verified(&state.db, user_id).await.unwrap());
+    assert!(AdminService::is_admin(&state.db, user_id).await.unwrap());
+    let missing = AdminService::set_admin(&state.db, uuid::Uuid::new_v4(), true).await;
+    assert!(matches!(missing, Err(AppError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_reindex_search_rebuilds_trigram_indexes() {
+    let state = common::create_test_state().await;
+
+    let indexes = SearchService::reindex(&state.db).await.unwrap();
+    assert!(indexes.contains(&"idx_decks_title_trgm"));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{create_app, services::admin::AdminService, state::AppState};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// A server with one admin and one regular user signed in
+async fn admin_server(state: AppState) -> (TestServer, HeaderValue, HeaderValue) {
+    let (admin_id, admin) = common::register_with_token(&state, "admin@example.com").await;
+    let (_, member) = common::register_with_token(&state, "member@example.com").await;
+    AdminService::set_admin(&state.db, admin_id, true).await.unwrap();
+
+    let server = TestServer::new(create_app(state)).unwrap();
+    (server, admin.parse().unwrap(), member.parse().unwrap())
+}
+
+#[tokio::test]
+async fn test_instance_stats_are_for_admins_only() {
+    let state = common::create_test_state().await;
+    let (server, admin, member) = admin_server(state).await;
+
+    let response = server
+        .get("/api/v1/admin/stats")
+        .add_query_param("days", 7)
+        .add_header(header::AUTHORIZATION, admin)
+        .await;
+    response.assert_status_ok();
+    let stats: Value = response.json();
+    assert_eq!(stats["period_days"], 7);
+    assert_eq!(stats["totals"]["users"], 2);
+    assert!(stats["daily"].is_array());
+
+    let response = server.get("/api/v1/admin/stats").add_header(header::AUTHORIZATION, member).await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    let response = server.get("/api/v1/admin/stats").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_consistency_check_is_queued_for_admins_only() {
+    let state = common::create_test_state().await;
+    let (server, admin, member) = admin_server(state).await;
+
+    let response = server
+        .post("/api/v1/admin/consistency-check")
+        .add_header(header::AUTHORIZATION, admin.clone())
+        .json(&json!({ "dry_run": true }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::ACCEPTED);
+    let mut job: Value = response.json();
+    assert_eq!(job["job_type"], "consistency_check");
+    assert_eq!(job["cancellable"], true);
+
+    // The report is read back through the job center
+    let url = format!("/api/v1/jobs/{}", job["id"].as_str().unwrap());
+    for _ in 0..100 {
+        if job["status"] == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        job = server.get(&url).add_header(header::AUTHORIZATION, admin.clone()).await.json();
+    }
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["result"]["dry_run"], true);
+    assert_eq!(job["result"]["total_repaired"], 0);
+
+    let response = server
+        .post("/api/v1/admin/consistency-check")
+        .add_header(header::AUTHORIZATION, member.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    let response = server.post("/api/v1/admin/consistency-check").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    // Nor can a member read the admin's report
+    let response = server.get(&url).add_header(header::AUTHORIZATION, member).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
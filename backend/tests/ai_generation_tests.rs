@@ -0,0 +1,555 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::{routing::post, Json, Router};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    config::OllamaConfig,
+    create_app,
+    models::{CreateCardDto, CreateDeckDto, DeckRole, ShareDeckDto},
+    services::{card::CardService, deck::DeckService, sharing::SharingService},
+    state::AppState,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const CONTENT: &str = "Photosynthesis turns light into chemical energy. Chlorophyll absorbs the light.";
+
+/// Ollama stand-in answering card requests with two grounded cards and one
+/// the model itself doubts, and quiz requests with one sound question and
+/// one with a repeated option
+async fn mock_ollama() -> String {
+    async fn generate(Json(body): Json<Value>) -> Json<Value> {
+        let prompt = body["prompt"].as_str().unwrap_or_default();
+        let answer = if prompt.contains("multiple-choice exam questions") {
+            json!([
+                { "card": 1, "question": "Which answer matches the first card?",
+                  "options": ["Paris", "Madrid", "Rome", "Berlin"], "answer": 0 },
+                { "card": 2, "question": "Which answer matches the second card?",
+                  "options": ["Lisbon", "Vienna", "Oslo", "Oslo"], "answer": 1 }
+            ])
+        } else {
+            flashcards()
+        };
+        Json(json!({
+            "model": body["model"],
+            "response": answer.to_string(),
+            "done": true,
+            "prompt_eval_count": 120,
+            "eval_count": 60,
+        }))
+    }
+
+    fn flashcards() -> Value {
+        json!([
+            { "front": "What does photosynthesis produce?", "back": "Chemical energy", "tags": ["biology"],
+              "source": "turns light into chemical energy", "confidence": 0.9 },
+            { "front": "What absorbs the light?", "back": "Chlorophyll", "tags": ["biology"],
+              "source": "Chlorophyll absorbs the light", "confidence": 0.9 },
+            { "front": "Who discovered photosynthesis?", "back": "Jan Ingenhousz", "tags": [],
+              "confidence": 0.2 }
+        ])
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, Router::new().route("/api/generate", post(generate)))
+            .await
+            .unwrap();
+    });
+
+    format!("http://{}/", addr)
+}
+
+struct Generator {
+    state: AppState,
+    server: TestServer,
+    user_id: Uuid,
+    authorization: HeaderValue,
+    deck_id: Uuid,
+}
+
+/// A signed-in user with an empty deck, served with AI generation backed by
+/// the Ollama stand-in
+async fn generator(email: &str) -> Generator {
+    let mut config = common::test_config();
+    config.ai.enabled = true;
+    config.ai.provider = "ollama".to_string();
+    config.ai.ollama = Some(OllamaConfig {
+        base_url: mock_ollama().await,
+        model: "llama3.1".to_string(),
+        timeout_seconds: 5,
+    });
+    config.ai.content_generation.min_confidence_score = 0.7;
+    config.account.require_email_verification = false;
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+
+    let (user_id, token) = common::register_with_token(&state, email).await;
+    let deck_id = create_deck(&state, user_id, "Biology").await;
+
+    Generator {
+        server: TestServer::new(create_app(state.clone())).unwrap(),
+        state,
+        user_id,
+        authorization: token.parse().unwrap(),
+        deck_id,
+    }
+}
+
+async fn create_deck(state: &AppState, user_id: Uuid, name: &str) -> Uuid {
+    DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: name.to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+fn generate_body(deck_id: Uuid) -> Value {
+    json!({
+        "deck_id": deck_id,
+        "content_type": "text",
+        "content": CONTENT,
+        "options": { "maxCards": 5 }
+    })
+}
+
+impl Generator {
+    async fn generate(&self) -> Value {
+        let response = self
+            .server
+            .post("/api/v1/ai/generate-cards")
+            .add_header(header::AUTHORIZATION, self.authorization.clone())
+            .json(&generate_body(self.deck_id))
+            .await;
+        response.assert_status_ok();
+        response.json()
+    }
+
+    async fn other_user(&self, email: &str) -> (Uuid, HeaderValue) {
+        let (user_id, token) = common::register_with_token(&self.state, email).await;
+        (user_id, token.parse().unwrap())
+    }
+}
+
+#[tokio::test]
+async fn test_generated_cards_are_stored_for_review() {
+    let generator = generator("author@example.com").await;
+
+    let result = generator.generate().await;
+    assert_eq!(result["provider"], "ollama");
+    assert_eq!(result["tokens_used"], 180);
+    let cards = result["cards"].as_array().unwrap();
+    // The card the model doubted is discarded
+    assert_eq!(cards.len(), 2);
+    for card in cards {
+        assert_eq!(card["job_id"], result["job_id"]);
+        assert_eq!(card["deck_id"], json!(generator.deck_id));
+        assert_eq!(card["approved"], false);
+        assert!(card["confidence_score"].as_f64().unwrap() >= 0.7);
+    }
+
+    // Nothing reaches the deck until it is approved
+    let in_deck: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cards WHERE deck_id = $1")
+        .bind(generator.deck_id)
+        .fetch_one(&generator.state.db)
+        .await
+        .unwrap();
+    assert_eq!(in_deck, 0);
+    let (status, output): (String, Value) =
+        sqlx::query_as("SELECT status, output_data FROM ai_content_generation_jobs WHERE id = $1")
+            .bind(Uuid::parse_str(result["job_id"].as_str().unwrap()).unwrap())
+            .fetch_one(&generator.state.db)
+            .await
+            .unwrap();
+    assert_eq!(status, "completed");
+    assert_eq!(output["discarded_low_confidence"], 1);
+}
+
+#[tokio::test]
+async fn test_generating_into_a_deck_needs_edit_access() {
+    let generator = generator("author@example.com").await;
+    let (_, stranger) = generator.other_user("stranger@example.com").await;
+    let (_, viewer) = generator.other_user("viewer@example.com").await;
+    let dto = ShareDeckDto {
+        email: "viewer@example.com".to_string(),
+        role: DeckRole::Viewer,
+    };
+    SharingService::share_with_user(&generator.state.db, generator.deck_id, generator.user_id, dto)
+        .await
+        .unwrap();
+
+    let generate = |authorization: HeaderValue| {
+        generator
+            .server
+            .post("/api/v1/ai/generate-cards")
+            .add_header(header::AUTHORIZATION, authorization)
+            .json(&generate_body(generator.deck_id))
+    };
+    assert_eq!(generate(stranger).await.status_code(), StatusCode::NOT_FOUND);
+    assert_eq!(generate(viewer).await.status_code(), StatusCode::FORBIDDEN);
+
+    let response = generator
+        .server
+        .post("/api/v1/ai/generate-cards")
+        .json(&generate_body(generator.deck_id))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let jobs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ai_content_generation_jobs")
+        .fetch_one(&generator.state.db)
+        .await
+        .unwrap();
+    assert_eq!(jobs, 0);
+}
+
+fn card_ids(result: &Value) -> Vec<Uuid> {
+    result["cards"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|card| Uuid::parse_str(card["id"].as_str().unwrap()).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_generated_cards_are_approved_into_a_deck_or_rejected() {
+    let generator = generator("author@example.com").await;
+    let result = generator.generate().await;
+    let ids = card_ids(&result);
+    let auth = generator.authorization.clone();
+    let pending = |include_reviewed: bool| {
+        generator
+            .server
+            .get("/api/v1/ai/generated-cards")
+            .add_query_param("job_id", result["job_id"].as_str().unwrap())
+            .add_query_param("include_reviewed", include_reviewed)
+            .add_header(header::AUTHORIZATION, auth.clone())
+    };
+
+    let response = generator
+        .server
+        .post("/api/v1/ai/generated-cards/approve")
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "card_ids": [ids[0]], "deck_id": generator.deck_id }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::CREATED);
+    let created: Value = response.json();
+    assert_eq!(created[0]["deck_id"], json!(generator.deck_id));
+    assert_eq!(created[0]["front"], "What does photosynthesis produce?");
+
+    let response = generator
+        .server
+        .post("/api/v1/ai/generated-cards/reject")
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "card_ids": [ids[1]] }))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["rejected"], 1);
+
+    assert!(pending(false).await.json::<Value>().as_array().unwrap().is_empty());
+    let reviewed: Value = pending(true).await.json();
+    assert_eq!(reviewed[0]["approved"], true);
+    assert!(reviewed[1]["rejected_at"].is_string());
+
+    // Reviewed cards cannot be reviewed again
+    let response = generator
+        .server
+        .post("/api/v1/ai/generated-cards/approve")
+        .add_header(header::AUTHORIZATION, auth)
+        .json(&json!({ "card_ids": [ids[1]], "deck_id": generator.deck_id }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_generated_cards_are_reviewed_by_their_author_only() {
+    let generator = generator("author@example.com").await;
+    let result = generator.generate().await;
+    let ids = card_ids(&result);
+    let (stranger_id, stranger) = generator.other_user("stranger@example.com").await;
+    let stranger_deck = create_deck(&generator.state, stranger_id, "Mine").await;
+
+    let response = generator
+        .server
+        .get("/api/v1/ai/generated-cards")
+        .add_query_param("job_id", result["job_id"].as_str().unwrap())
+        .add_header(header::AUTHORIZATION, stranger.clone())
+        .await;
+    response.assert_status_ok();
+    assert!(response.json::<Value>().as_array().unwrap().is_empty());
+
+    let response = generator
+        .server
+        .post("/api/v1/ai/generated-cards/approve")
+        .add_header(header::AUTHORIZATION, stranger.clone())
+        .json(&json!({ "card_ids": [ids[0]], "deck_id": stranger_deck }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    let response = generator
+        .server
+        .post("/api/v1/ai/generated-cards/reject")
+        .add_header(header::AUTHORIZATION, stranger)
+        .json(&json!({ "card_ids": ids }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    // Nor can the author file them into someone else's deck
+    let response = generator
+        .server
+        .post("/api/v1/ai/generated-cards/approve")
+        .add_header(header::AUTHORIZATION, generator.authorization.clone())
+        .json(&json!({ "card_ids": [ids[0]], "deck_id": stranger_deck }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    let response = generator
+        .server
+        .post("/api/v1/ai/generated-cards/reject")
+        .json(&json!({ "card_ids": ids }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    // Every card is still waiting for its author
+    let response = generator
+        .server
+        .get("/api/v1/ai/generated-cards")
+        .add_query_param("job_id", result["job_id"].as_str().unwrap())
+        .add_header(header::AUTHORIZATION, generator.authorization.clone())
+        .await;
+    assert_eq!(response.json::<Value>().as_array().unwrap().len(), 2);
+}
+
+impl Generator {
+    /// Queue a streaming job, returning its id and stream URL
+    async fn queue_stream(&self) -> (String, String) {
+        let mut body = generate_body(self.deck_id);
+        body["stream"] = json!(true);
+        let response = self
+            .server
+            .post("/api/v1/ai/generate-cards")
+            .add_header(header::AUTHORIZATION, self.authorization.clone())
+            .json(&body)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::ACCEPTED);
+        let queued: Value = response.json();
+        (
+            queued["job_id"].as_str().unwrap().to_string(),
+            queued["stream_url"].as_str().unwrap().to_string(),
+        )
+    }
+}
+
+fn event_names(body: &str) -> Vec<&str> {
+    body.lines().filter_map(|line| line.strip_prefix("event: ")).collect()
+}
+
+#[tokio::test]
+async fn test_generated_cards_are_streamed_as_server_sent_events() {
+    let generator = generator("author@example.com").await;
+    let (job_id, stream_url) = generator.queue_stream().await;
+    assert_eq!(stream_url, format!("/api/v1/ai/generate-cards/stream?job_id={}", job_id));
+
+    let response = generator
+        .server
+        .get(&stream_url)
+        .add_header(header::AUTHORIZATION, generator.authorization.clone())
+        .await;
+    response.assert_status_ok();
+    assert!(response.header(header::CONTENT_TYPE).to_str().unwrap().starts_with("text/event-stream"));
+    let body = response.text();
+    assert_eq!(event_names(&body), vec!["card", "card", "done"]);
+    assert!(body.contains("\"discarded_low_confidence\":1"));
+
+    // The streamed cards wait for review like any others
+    let response = generator
+        .server
+        .get("/api/v1/ai/generated-cards")
+        .add_query_param("job_id", &job_id)
+        .add_header(header::AUTHORIZATION, generator.authorization.clone())
+        .await;
+    assert_eq!(response.json::<Value>().as_array().unwrap().len(), 2);
+
+    // A job streams once
+    let response = generator
+        .server
+        .get(&stream_url)
+        .add_header(header::AUTHORIZATION, generator.authorization.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_streams_are_for_the_job_owner_only() {
+    let generator = generator("author@example.com").await;
+    let (_, stream_url) = generator.queue_stream().await;
+    let (_, stranger) = generator.other_user("stranger@example.com").await;
+
+    let response = generator.server.get(&stream_url).add_header(header::AUTHORIZATION, stranger.clone()).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let stranger_token = stranger.to_str().unwrap().trim_start_matches("Bearer ");
+    let response = generator.server.get(&stream_url).add_query_param("token", stranger_token).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = generator.server.get(&stream_url).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    let response = generator.server.get(&stream_url).add_query_param("token", "forged").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    // EventSource clients pass their token in the query string
+    let token = generator.authorization.to_str().unwrap().trim_start_matches("Bearer ");
+    let response = generator.server.get(&stream_url).add_query_param("token", token).await;
+    response.assert_status_ok();
+    assert_eq!(event_names(&response.text()).last(), Some(&"done"));
+}
+
+impl Generator {
+    /// Fill the deck with enough cards for a quiz
+    async fn add_cards(&self) {
+        for (front, back) in [("France", "Paris"), ("Spain", "Madrid"), ("Italy", "Rome"), ("Germany", "Berlin")] {
+            let dto = CreateCardDto {
+                front: front.to_string(),
+                back: back.to_string(),
+                position: None,
+            };
+            CardService::create_card(&self.state.db, self.deck_id, self.user_id, dto).await.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_quizzes_are_generated_and_kept_per_user() {
+    let generator = generator("author@example.com").await;
+    generator.add_cards().await;
+    let url = format!("/api/v1/decks/{}/ai/quiz", generator.deck_id);
+    let auth = generator.authorization.clone();
+
+    let response = generator
+        .server
+        .post(&url)
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "question_count": 2, "title": "Capitals check" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::CREATED);
+    let exam: Value = response.json();
+    assert_eq!(exam["title"], "Capitals check");
+    // The question with a repeated option is dropped
+    assert_eq!(exam["question_count"], 1);
+    assert_eq!(exam["questions"][0]["options"], json!(["Paris", "Madrid", "Rome", "Berlin"]));
+    assert_eq!(exam["questions"][0]["correct_index"], 0);
+
+    let response = generator.server.get(&url).add_header(header::AUTHORIZATION, auth.clone()).await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()[0]["id"], exam["id"]);
+    let exam_url = format!("{}/{}", url, exam["id"].as_str().unwrap());
+    let response = generator.server.get(&exam_url).add_header(header::AUTHORIZATION, auth).await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["questions"].as_array().unwrap().len(), 1);
+
+    // Viewers may quiz themselves, but cannot see the owner's exams
+    let (_, viewer) = generator.other_user("viewer@example.com").await;
+    let dto = ShareDeckDto {
+        email: "viewer@example.com".to_string(),
+        role: DeckRole::Viewer,
+    };
+    SharingService::share_with_user(&generator.state.db, generator.deck_id, generator.user_id, dto)
+        .await
+        .unwrap();
+    let response = generator.server.get(&url).add_header(header::AUTHORIZATION, viewer.clone()).await;
+    assert!(response.json::<Value>().as_array().unwrap().is_empty());
+    let response = generator.server.get(&exam_url).add_header(header::AUTHORIZATION, viewer).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_quizzes_are_refused_without_deck_access() {
+    let generator = generator("author@example.com").await;
+    generator.add_cards().await;
+    let (_, stranger) = generator.other_user("stranger@example.com").await;
+    let url = format!("/api/v1/decks/{}/ai/quiz", generator.deck_id);
+
+    let response = generator
+        .server
+        .post(&url)
+        .add_header(header::AUTHORIZATION, stranger.clone())
+        .json(&json!({ "question_count": 2 }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = generator.server.get(&url).add_header(header::AUTHORIZATION, stranger).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = generator.server.post(&url).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let exams: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM deck_exams")
+        .fetch_one(&generator.state.db)
+        .await
+        .unwrap();
+    assert_eq!(exams, 0);
+}
+
+#[tokio::test]
+async fn test_privacy_settings_can_turn_off_generation() {
+    let generator = generator("author@example.com").await;
+    generator.add_cards().await;
+    let auth = generator.authorization.clone();
+
+    let response = generator
+        .server
+        .get("/api/v1/ai/privacy-settings")
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .await;
+    response.assert_status_ok();
+    let settings: Value = response.json();
+    assert_eq!(settings["user_id"], json!(generator.user_id));
+    assert_eq!(settings["enable_content_generation"], true);
+
+    let response = generator
+        .server
+        .patch("/api/v1/ai/privacy-settings")
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "enable_content_generation": false }))
+        .await;
+    response.assert_status_ok();
+    let updated: Value = response.json();
+    assert_eq!(updated["enable_content_generation"], false);
+    assert_eq!(updated["track_analytics"], settings["track_analytics"]);
+
+    // Nothing is sent to the provider any more
+    let response = generator
+        .server
+        .post("/api/v1/ai/generate-cards")
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&generate_body(generator.deck_id))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    let response = generator
+        .server
+        .post(&format!("/api/v1/decks/{}/ai/quiz", generator.deck_id))
+        .add_header(header::AUTHORIZATION, auth)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    // Settings are per user and need a sign-in
+    let (_, other) = generator.other_user("other@example.com").await;
+    let response = generator
+        .server
+        .get("/api/v1/ai/privacy-settings")
+        .add_header(header::AUTHORIZATION, other)
+        .await;
+    assert_eq!(response.json::<Value>()["enable_content_generation"], true);
+    let response = generator
+        .server
+        .patch("/api/v1/ai/privacy-settings")
+        .json(&json!({ "enable_content_generation": true }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
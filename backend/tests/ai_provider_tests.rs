@@ -0,0 +1,123 @@
+mod common;
+
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use deckoracle_backend::{
+    config::{AiConfig, OllamaConfig},
+    models::ai::AiProviderKind,
+    services::{
+        ai_provider::{resolve_provider, AiProvider, FlashcardGenerationOptions},
+        ollama::OllamaClient,
+    },
+    utils::AppError,
+};
+
+fn ai_config() -> AiConfig {
+    let mut ai = common::test_config().ai;
+    ai.provider = "vertex_ai".to_string();
+    ai.openai = None;
+    ai.ollama = None;
+    ai
+}
+
+/// Ollama stand-in answering `/api/generate` with two flashcards
+async fn mock_ollama() -> String {
+    async fn generate(Json(body): Json<Value>) -> Json<Value> {
+        assert_eq!(body["stream"], false);
+        Json(json!({
+            "model": body["model"],
+            "response": "Here you go:\n[{\"front\": \"Capital of France?\", \"back\": \"Paris\", \"tags\": []}, \
+                         {\"front\": \"Capital of Italy?\", \"back\": \"Rome\", \"tags\": []}]",
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 120,
+            "eval_count": 40,
+        }))
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, Router::new().route("/api/generate", post(generate)))
+            .await
+            .unwrap();
+    });
+
+    format!("http://{}/", addr)
+}
+
+fn options() -> FlashcardGenerationOptions {
+    FlashcardGenerationOptions {
+        max_cards: Some(2),
+        difficulty: None,
+        format: None,
+        include_explanations: None,
+        languages: Default::default(),
+    }
+}
+
+#[test]
+fn test_resolve_provider_uses_default_and_rejects_unconfigured() {
+    let mut ai = ai_config();
+
+    let (kind, model) = resolve_provider(&ai, None).unwrap();
+    assert_eq!(kind, AiProviderKind::VertexAi);
+    assert_eq!(model, ai.vertex_ai.default_model);
+
+    assert!(matches!(
+        resolve_provider(&ai, Some(AiProviderKind::Ollama)),
+        Err(AppError::BadRequest(_))
+    ));
+    assert!(matches!(
+        resolve_provider(&ai, Some(AiProviderKind::OpenAi)),
+        Err(AppError::BadRequest(_))
+    ));
+
+    ai.ollama = Some(OllamaConfig {
+        base_url: "http://localhost:11434".to_string(),
+        model: "mistral".to_string(),
+        timeout_seconds: 5,
+    });
+    ai.provider = "ollama".to_string();
+    let (kind, model) = resolve_provider(&ai, None).unwrap();
+    assert_eq!(kind, AiProviderKind::Ollama);
+    assert_eq!(model, "mistral");
+
+    // A request can still pick another configured provider
+    let (kind, _) = resolve_provider(&ai, Some(AiProviderKind::VertexAi)).unwrap();
+    assert_eq!(kind, AiProviderKind::VertexAi);
+}
+
+#[test]
+fn test_provider_kind_round_trips() {
+    for kind in [AiProviderKind::VertexAi, AiProviderKind::OpenAi, AiProviderKind::Ollama] {
+        assert_eq!(kind.as_str().parse::<AiProviderKind>().unwrap(), kind);
+        assert_eq!(serde_json::to_value(kind).unwrap(), json!(kind.as_str()));
+    }
+    assert!("gemini".parse::<AiProviderKind>().is_err());
+}
+
+#[tokio::test]
+async fn test_ollama_generates_flashcards() {
+    let mut client = OllamaClient::new(OllamaConfig {
+        base_url: mock_ollama().await,
+        model: "llama3.1".to_string(),
+        timeout_seconds: 5,
+    });
+
+    let batch = client.generate_flashcards("France and Italy", &options()).await.unwrap();
+    assert_eq!(batch.cards.len(), 2);
+    assert_eq!(batch.cards[1].back, "Rome");
+    assert_eq!(batch.model, "llama3.1");
+    assert_eq!(batch.tokens_used, 160);
+
+    // Without native streaming the whole batch is yielded card by card
+    let mut stream = client.stream_flashcards("France and Italy", &options()).await.unwrap();
+    let mut fronts = Vec::new();
+    while let Some(card) = stream.next_card().await.unwrap() {
+        fronts.push(card.front);
+    }
+    assert_eq!(fronts, ["Capital of France?", "Capital of Italy?"]);
+    assert_eq!(stream.tokens_used(), 160);
+}
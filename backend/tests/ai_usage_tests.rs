@@ -0,0 +1,63 @@
+mod common;
+
+use deckoracle_backend::models::{
+    ai::{AiProviderKind, AiUsageQuery},
+    RegisterDto,
+};
+use deckoracle_backend::services::{ai_usage::AiUsageService, auth::AuthService};
+use deckoracle_backend::utils::AppError;
+
+#[tokio::test]
+async fn test_usage_is_recorded_and_quota_enforced() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "usage@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+
+    let mut ai = state.config.ai.clone();
+    ai.usage.daily_token_limit = 1_000;
+    ai.usage.monthly_token_limit = 0;
+    ai.vertex_ai.cost_per_1k_tokens = 0.5;
+
+    AiUsageService::check_quota(&state.db, &ai, user_id).await.unwrap();
+
+    AiUsageService::record(&state.db, &ai, user_id, AiProviderKind::VertexAi, 600).await.unwrap();
+    AiUsageService::record(&state.db, &ai, user_id, AiProviderKind::VertexAi, 200).await.unwrap();
+    AiUsageService::record(&state.db, &ai, user_id, AiProviderKind::Ollama, 100).await.unwrap();
+
+    let usage = AiUsageService::get(&state.db, &ai, user_id, &AiUsageQuery { days: None }).await.unwrap();
+    assert_eq!(usage.tokens_today, 900);
+    assert_eq!(usage.tokens_this_month, 900);
+    assert!((usage.cost_this_month_usd - 0.4).abs() < 1e-9); // Ollama is free
+    assert_eq!(usage.daily_token_limit, Some(1_000));
+    assert_eq!(usage.monthly_token_limit, None);
+    assert_eq!(usage.days.len(), 2);
+    let vertex = usage.days.iter().find(|d| d.provider == "vertex_ai").unwrap();
+    assert_eq!(vertex.tokens_used, 800);
+    assert_eq!(vertex.request_count, 2);
+
+    // Still under the limit
+    AiUsageService::check_quota(&state.db, &ai, user_id).await.unwrap();
+
+    AiUsageService::record(&state.db, &ai, user_id, AiProviderKind::VertexAi, 100).await.unwrap();
+    match AiUsageService::check_quota(&state.db, &ai, user_id).await {
+        Err(AppError::AiQuotaExceeded { retry_after_seconds, .. }) => {
+            assert!(retry_after_seconds > 0 && retry_after_seconds <= 24 * 3600);
+        }
+        other => panic!("expected quota error, got {:?}", other),
+    }
+
+    // Without limits nothing is refused
+    ai.usage.daily_token_limit = 0;
+    AiUsageService::check_quota(&state.db, &ai, user_id).await.unwrap();
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use deckoracle_backend::models::{
+    import_export::{DuplicateStrategy, HtmlHandling, ImportFormat, ImportJobParameters, TextDelimiters},
+    CreateCardDto, CreateDeckDto, RegisterDto,
+};
+use deckoracle_backend::services::{
+    anki_text::{self, decode_entities, html_to_text, sanitize_html, DEFAULT_DECK_TITLE},
+    auth::AuthService,
+    card::CardService,
+    deck::DeckService,
+    exporters::AnkiTextExporter,
+    import_export::ImportExportService,
+    upload::UploadSource,
+};
+
+fn notes(data: &str, html: HtmlHandling) -> Vec<(String, String)> {
+    anki_text::parse(data.as_bytes(), html).unwrap().decks.remove(0).notes
+}
+
+#[test]
+fn test_modern_export_with_directives() {
+    let data = "#separator:tab\n#html:true\n#notetype:Basic\n#deck:Spanish::Verbs\n\
+                ser\tto <b>be</b>\n\"ir\"\t\"to go\nsomewhere\"\n";
+    let parsed = anki_text::parse(data.as_bytes(), HtmlHandling::Sanitize).unwrap();
+    assert_eq!(parsed.decks.len(), 1);
+    assert_eq!(parsed.decks[0].title, "Spanish::Verbs");
+    assert_eq!(
+        parsed.decks[0].notes,
+        vec![
+            ("ser".to_string(), "to <b>be</b>".to_string()),
+            ("ir".to_string(), "to go\nsomewhere".to_string()),
+        ]
+    );
+    assert_eq!(parsed.skipped_rows, 0);
+}
+
+#[test]
+fn test_meta_columns_are_not_fields() {
+    let data = "#separator:comma\n#html:false\n#guid column:1\n#notetype column:2\n#deck column:3\n#tags column:6\n\
+                a1,Basic,Colors,rojo,red,spanish\n\
+                a2,Basic,Numbers,uno,one,\n\
+                a3,Basic,Colors,azul,blue,spanish\n";
+    let parsed = anki_text::parse(data.as_bytes(), HtmlHandling::Sanitize).unwrap();
+    let decks: Vec<(&str, usize)> = parsed.decks.iter().map(|d| (d.title.as_str(), d.notes.len())).collect();
+    assert_eq!(decks, [("Colors", 2), ("Numbers", 1)]);
+    assert_eq!(parsed.decks[0].notes[1], ("azul".to_string(), "blue".to_string()));
+}
+
+#[test]
+fn test_named_columns_pick_front_and_back() {
+    let data = "#separator:semicolon\n#columns:Back;Extra;Front\nperro;noun;dog\n";
+    assert_eq!(notes(data, HtmlHandling::Sanitize), vec![("dog".into(), "perro".into())]);
+}
+
+#[test]
+fn test_without_header_separator_is_detected() {
+    let parsed = anki_text::parse(b"cat;gato\ndog;perro\nlonely\n", HtmlHandling::Sanitize).unwrap();
+    assert_eq!(parsed.decks[0].title, DEFAULT_DECK_TITLE);
+    assert_eq!(parsed.decks[0].notes.len(), 2);
+    assert_eq!(parsed.skipped_rows, 1);
+}
+
+#[test]
+fn test_html_sanitize_and_strip() {
+    let field = "<div>Hola&nbsp;&amp; adi&oacute;s</div><script>alert(1)</script><div onclick=\"x()\">two</div>";
+    let data = format!("#separator:tab\nfront\t{}\n", field);
+    let (_, sanitized) = notes(&data, HtmlHandling::Sanitize).remove(0);
+    assert!(!sanitized.contains("script") && !sanitized.contains("onclick"));
+    assert!(sanitized.contains("<div>two</div>"));
+    let (_, stripped) = notes(&data, HtmlHandling::Strip).remove(0);
+    assert_eq!(stripped, "Hola & adiós\ntwo");
+
+    assert_eq!(sanitize_html("a &lt; b"), "a < b");
+    assert_eq!(html_to_text("one<br>two<br/><p>three</p><p></p><p>four</p>"), "one\ntwo\nthree\n\nfour");
+    assert_eq!(decode_entities("&#65;&#x42;&unknown; & done"), "AB&unknown; & done");
+}
+
+#[test]
+fn test_invalid_directives_are_rejected() {
+    assert!(anki_text::parse(b"#separator:double tab\na\tb\n", HtmlHandling::Sanitize).is_err());
+    assert!(anki_text::parse(b"#tags column:0\na\tb\n", HtmlHandling::Sanitize).is_err());
+}
+
+#[tokio::test]
+async fn test_anki_text_round_trip() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "ankitext@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Anki Round Trip".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    for (front, back) in [("hola", "hello"), ("salt & pepper", "sal y\tpimienta"), ("**bold**", "line one\nline two")] {
+        CardService::create_card(
+            &state.db,
+            deck.id,
+            user_id,
+            CreateCardDto {
+                front: front.to_string(),
+                back: back.to_string(),
+                position: None,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let data = ImportExportService::export_deck(&state.db, &state.media, user_id, deck.id, &AnkiTextExporter, false, false)
+        .await
+        .unwrap();
+    let text = String::from_utf8(data.clone()).unwrap();
+    assert!(text.starts_with("#separator:tab\n#html:true\n#notetype:Basic\n#deck:Anki Round Trip\n"));
+    assert!(text.contains("<strong>bold</strong>\tline one<br>line two\n"));
+
+    let params = ImportJobParameters {
+        format: ImportFormat::AnkiText,
+        folder_id: None,
+        merge_duplicates: false,
+        delimiters: TextDelimiters::default(),
+        include_progress: false,
+        duplicate_strategy: DuplicateStrategy::default(),
+        html: HtmlHandling::Strip,
+    };
+    let result = ImportExportService::import_decks(&state.db, &state.media, user_id, UploadSource::Bytes(&data), &params, None)
+        .await
+        .unwrap();
+    assert_eq!(result.imported_decks[0].title, "Anki Round Trip");
+    let cards = sqlx::query_as::<_, (String, String)>("SELECT front, back FROM cards WHERE deck_id = $1 ORDER BY position")
+        .bind(result.imported_decks[0].id)
+        .fetch_all(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(
+        cards,
+        [
+            ("hola".to_string(), "hello".to_string()),
+            ("salt & pepper".to_string(), "sal y\tpimienta".to_string()),
+            ("bold".to_string(), "line one\nline two".to_string()),
+        ]
+    );
+}
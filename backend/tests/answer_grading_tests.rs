@@ -0,0 +1,76 @@
+use deckoracle_backend::{
+    models::DiffOp,
+    services::grading::{grade, levenshtein, normalize, MAX_GRADED_CHARS},
+};
+
+const THRESHOLD: f64 = 0.85;
+
+#[test]
+fn test_normalize_ignores_case_punctuation_and_spacing() {
+    assert_eq!(normalize("  The   Mitochondria!  "), "the mitochondria");
+    assert_eq!(normalize("H2O (water)"), "h2o water");
+    assert_eq!(normalize("?!"), "");
+}
+
+#[test]
+fn test_levenshtein_distance() {
+    assert_eq!(levenshtein("kitten", "sitting"), 3);
+    assert_eq!(levenshtein("", "abc"), 3);
+    assert_eq!(levenshtein("café", "cafe"), 1);
+    assert_eq!(levenshtein("same", "same"), 0);
+}
+
+#[test]
+fn test_grade_exact_match_after_normalization() {
+    let grade = grade("Paris", "  paris. ", THRESHOLD);
+    assert!(grade.is_correct);
+    assert_eq!(grade.similarity, 1.0);
+    assert!(grade.diff.is_none());
+}
+
+#[test]
+fn test_grade_accepts_small_typo_with_diff() {
+    let grade = grade("Mitochondria", "mitocondria", THRESHOLD);
+    assert!(grade.is_correct);
+
+    let diff = grade.diff.expect("typo should be shown");
+    let ops: Vec<(DiffOp, &str)> = diff.iter().map(|s| (s.op, s.text.as_str())).collect();
+    assert_eq!(
+        ops,
+        vec![
+            (DiffOp::Equal, "mitoc"),
+            (DiffOp::Missing, "h"),
+            (DiffOp::Equal, "ondria"),
+        ]
+    );
+}
+
+#[test]
+fn test_grade_rejects_distant_answer() {
+    let grade = grade("Marseille", "Marsielle", THRESHOLD);
+    assert!(!grade.is_correct);
+    assert!((grade.similarity - 7.0 / 9.0).abs() < 1e-9);
+
+    let diff = grade.diff.unwrap();
+    assert_eq!(diff.first().unwrap().text, "mars");
+    assert!(diff.iter().any(|s| s.op == DiffOp::Missing && s.text == "ei"));
+    assert!(diff.iter().any(|s| s.op == DiffOp::Extra && s.text == "ie"));
+}
+
+#[test]
+fn test_grade_empty_answer_is_wrong() {
+    let grade = grade("Paris", "", 0.0);
+    assert!(!grade.is_correct);
+    assert_eq!(grade.diff.unwrap()[0].op, DiffOp::Missing);
+}
+
+#[test]
+fn test_grade_long_answers_require_exact_match() {
+    let expected = "a".repeat(MAX_GRADED_CHARS + 1);
+    let typo = format!("b{}", &expected[1..]);
+
+    assert!(grade(&expected, &expected.to_uppercase(), THRESHOLD).is_correct);
+    let grade = grade(&expected, &typo, THRESHOLD);
+    assert!(!grade.is_correct);
+    assert!(grade.diff.is_none());
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use axum::{extract::FromRequestParts, http::Request};
+use chrono::{Duration, Utc};
+use deckoracle_backend::middleware::auth::UserId;
+use deckoracle_backend::models::{ApiKeyScope, CreateApiKeyDto, UpdateApiKeyDto};
+use deckoracle_backend::services::api_key::ApiKeyService;
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use uuid::Uuid;
+
+fn create_dto(scope: ApiKeyScope, rate_limit_per_minute: Option<i32>) -> CreateApiKeyDto {
+    CreateApiKeyDto {
+        name: "script".to_string(),
+        scope,
+        rate_limit_per_minute,
+        expires_at: None,
+    }
+}
+
+async fn user_for(state: &AppState, method: &str, key: &str) -> Result<Uuid, AppError> {
+    let (mut parts, _) = Request::builder()
+        .method(method)
+        .header("X-Api-Key", key)
+        .body(())
+        .unwrap()
+        .into_parts();
+    UserId::from_request_parts(&mut parts, state).await.map(|UserId(id)| id)
+}
+
+#[tokio::test]
+async fn test_keys_are_hashed_scoped_and_revocable() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "api-keys@example.com").await;
+
+    let created = ApiKeyService::create(&state.db, user_id, create_dto(ApiKeyScope::Read, None))
+        .await
+        .unwrap();
+    assert!(created.key.starts_with("dko_"));
+    assert!(created.key.starts_with(&created.api_key.key_prefix));
+    assert_ne!(created.api_key.key_hash, created.key);
+
+    assert_eq!(user_for(&state, "GET", &created.key).await.unwrap(), user_id);
+    assert!(matches!(user_for(&state, "POST", &created.key).await, Err(AppError::Forbidden)));
+    assert!(matches!(user_for(&state, "GET", "dko_unknown").await, Err(AppError::Unauthorized)));
+
+    ApiKeyService::update(
+        &state.db,
+        user_id,
+        created.api_key.id,
+        UpdateApiKeyDto {
+            scope: Some(ApiKeyScope::Write),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(user_for(&state, "POST", &created.key).await.unwrap(), user_id);
+
+    let listed = ApiKeyService::list(&state.db, user_id).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(listed[0].last_used_at.is_some());
+
+    // Other users can neither see nor revoke the key
+    let stranger = common::register(&state, "api-keys-stranger@example.com").await;
+    assert!(ApiKeyService::list(&state.db, stranger).await.unwrap().is_empty());
+    assert!(matches!(
+        ApiKeyService::delete(&state.db, stranger, created.api_key.id).await,
+        Err(AppError::NotFound(_))
+    ));
+
+    ApiKeyService::delete(&state.db, user_id, created.api_key.id).await.unwrap();
+    assert!(matches!(user_for(&state, "GET", &created.key).await, Err(AppError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_expired_keys_are_refused() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "api-keys-expiry@example.com").await;
+
+    let past = CreateApiKeyDto {
+        expires_at: Some(Utc::now() - Duration::minutes(1)),
+        ..create_dto(ApiKeyScope::Read, None)
+    };
+    assert!(matches!(
+        ApiKeyService::create(&state.db, user_id, past).await,
+        Err(AppError::ValidationError(_))
+    ));
+
+    let created = ApiKeyService::create(&state.db, user_id, create_dto(ApiKeyScope::Read, None))
+        .await
+        .unwrap();
+    sqlx::query("UPDATE api_keys SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(created.api_key.id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    assert!(matches!(user_for(&state, "GET", &created.key).await, Err(AppError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_each_key_has_its_own_rate_limit() {
+    let mut config = common::test_config();
+    config.rate_limit.enabled = true;
+    config.rate_limit.redis_url = None;
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+    let user_id = common::register(&state, "api-keys-limit@example.com").await;
+
+    let limited = ApiKeyService::create(&state.db, user_id, create_dto(ApiKeyScope::Read, Some(2)))
+        .await
+        .unwrap();
+    let other = ApiKeyService::create(&state.db, user_id, create_dto(ApiKeyScope::Read, None))
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        assert!(user_for(&state, "GET", &limited.key).await.is_ok());
+    }
+    assert!(matches!(
+        user_for(&state, "GET", &limited.key).await,
+        Err(AppError::TooManyRequests { retry_after_seconds: 60, .. })
+    ));
+    assert!(user_for(&state, "GET", &other.key).await.is_ok());
+}
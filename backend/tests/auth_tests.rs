@@ -0,0 +1,253 @@
+mod common;
+
+use deckoracle_backend::config::LockoutConfig;
+use deckoracle_backend::models::{ClientInfo, LoginDto, RefreshTokenDto, RegisterDto};
+use deckoracle_backend::services::auth::{lockout_seconds, AuthService};
+use deckoracle_backend::utils::{AppError, ErrorCode};
+
+fn login_dto(email: &str, password: &str) -> LoginDto {
+    LoginDto {
+        email: email.to_string(),
+        password: password.to_string(),
+        remember_me: None,
+    }
+}
+
+fn register_dto(email: &str) -> RegisterDto {
+    RegisterDto {
+        email: email.to_string(),
+        password: "Password123".to_string(),
+        display_name: Some("Test User".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_register_uses_injected_config() {
+    let state = common::create_test_state().await;
+
+    let response = AuthService::register(&state.db, &state.config, register_dto("config@example.com"))
+        .await
+        .expect("registration should succeed");
+
+    assert_eq!(response.expires_in, 3600);
+
+    // The token must be signed with the injected secret
+    let claims = AuthService::validate_jwt(&response.access_token, &state.config).unwrap();
+    assert_eq!(claims.sub, response.user.id);
+
+    let mut other_config = common::test_config();
+    other_config.jwt.secret = "another_secret".to_string();
+    assert!(AuthService::validate_jwt(&response.access_token, &other_config).is_err());
+}
+
+#[tokio::test]
+async fn test_login_and_refresh_with_injected_config() {
+    let state = common::create_test_state().await;
+
+    AuthService::register(&state.db, &state.config, register_dto("login@example.com"))
+        .await
+        .unwrap();
+
+    let login = AuthService::login(
+        &state.db,
+        &state.config,
+        LoginDto {
+            email: "login@example.com".to_string(),
+            password: "Password123".to_string(),
+            remember_me: None,
+        },
+        &ClientInfo::default(),
+    )
+    .await
+    .expect("login should succeed");
+
+    let refreshed = AuthService::refresh_token(
+        &state.db,
+        &state.config,
+        RefreshTokenDto {
+            refresh_token: login.refresh_token.clone(),
+        },
+    )
+    .await
+    .expect("refresh should succeed");
+
+    assert_ne!(refreshed.refresh_token, login.refresh_token);
+    assert!(AuthService::validate_jwt(&refreshed.access_token, &state.config).is_ok());
+}
+
+#[tokio::test]
+async fn test_login_wrong_password_is_unauthorized() {
+    let state = common::create_test_state().await;
+
+    AuthService::register(&state.db, &state.config, register_dto("wrong@example.com"))
+        .await
+        .unwrap();
+
+    let result = AuthService::login(
+        &state.db,
+        &state.config,
+        LoginDto {
+            email: "wrong@example.com".to_string(),
+            password: "NotThePassword1".to_string(),
+            remember_me: None,
+        },
+        &ClientInfo::default(),
+    )
+    .await;
+
+    assert!(matches!(result, Err(AppError::Unauthorized)));
+}
+
+#[test]
+fn test_lockout_backs_off_exponentially() {
+    let lockout = LockoutConfig {
+        enabled: true,
+        threshold: 3,
+        base_seconds: 60,
+        max_seconds: 600,
+    };
+
+    assert_eq!(lockout_seconds(2, &lockout), None);
+    assert_eq!(lockout_seconds(3, &lockout), Some(60));
+    assert_eq!(lockout_seconds(4, &lockout), Some(120));
+    assert_eq!(lockout_seconds(5, &lockout), Some(240));
+    assert_eq!(lockout_seconds(8, &lockout), Some(600));
+    assert_eq!(lockout_seconds(1000, &lockout), Some(600));
+}
+
+#[tokio::test]
+async fn test_repeated_failures_lock_account_and_are_audited() {
+    let state = common::create_test_state().await;
+    let client = ClientInfo {
+        ip_address: Some("203.0.113.7".parse().unwrap()),
+        user_agent: Some("auth-tests".to_string()),
+    };
+
+    let user = AuthService::register(&state.db, &state.config, register_dto("locked@example.com"))
+        .await
+        .unwrap()
+        .user;
+
+    for _ in 0..state.config.lockout.threshold {
+        let result = AuthService::login(
+            &state.db,
+            &state.config,
+            login_dto("locked@example.com", "NotThePassword1"),
+            &client,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    // Even the right password is refused while locked
+    let result = AuthService::login(
+        &state.db,
+        &state.config,
+        login_dto("locked@example.com", "Password123"),
+        &client,
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(AppError::TooManyRequests { retry_after_seconds, .. }) if retry_after_seconds > 0
+    ));
+
+    let history = AuthService::login_history(&state.db, user.id, None).await.unwrap();
+    assert_eq!(history.len(), state.config.lockout.threshold as usize + 1);
+    assert_eq!(history[0].failure_reason.as_deref(), Some("locked_out"));
+    assert_eq!(history[1].failure_reason.as_deref(), Some("invalid_credentials"));
+    assert_eq!(history[1].ip_address.as_deref(), Some("203.0.113.7"));
+    assert_eq!(history[1].user_agent.as_deref(), Some("auth-tests"));
+}
+
+#[tokio::test]
+async fn test_refresh_tokens_are_stored_hashed() {
+    let state = common::create_test_state().await;
+
+    let response = AuthService::register(&state.db, &state.config, register_dto("hashed@example.com"))
+        .await
+        .unwrap();
+
+    let plaintext_rows = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM refresh_tokens WHERE token_hash = $1",
+    )
+    .bind(&response.refresh_token)
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    assert_eq!(plaintext_rows, 0);
+}
+
+#[tokio::test]
+async fn test_refresh_token_replay_revokes_family() {
+    let state = common::create_test_state().await;
+
+    let original = AuthService::register(&state.db, &state.config, register_dto("replay@example.com"))
+        .await
+        .unwrap()
+        .refresh_token;
+    // A separate login is a separate family and must survive the replay
+    let other_login = AuthService::login(
+        &state.db,
+        &state.config,
+        login_dto("replay@example.com", "Password123"),
+        &ClientInfo::default(),
+    )
+    .await
+    .unwrap()
+    .refresh_token;
+
+    let rotated = AuthService::refresh_token(
+        &state.db,
+        &state.config,
+        RefreshTokenDto { refresh_token: original.clone() },
+    )
+    .await
+    .expect("first refresh should succeed")
+    .refresh_token;
+
+    // An attacker replays the token that was already rotated
+    let replay = AuthService::refresh_token(
+        &state.db,
+        &state.config,
+        RefreshTokenDto { refresh_token: original },
+    )
+    .await;
+    assert!(matches!(replay, Err(AppError::RefreshTokenReused)));
+    assert_eq!(replay.unwrap_err().code(), ErrorCode::RefreshTokenReused);
+
+    // The legitimate client's newer token died with the family
+    let legitimate = AuthService::refresh_token(
+        &state.db,
+        &state.config,
+        RefreshTokenDto { refresh_token: rotated },
+    )
+    .await;
+    assert!(matches!(legitimate, Err(AppError::Unauthorized)));
+
+    AuthService::refresh_token(
+        &state.db,
+        &state.config,
+        RefreshTokenDto { refresh_token: other_login },
+    )
+    .await
+    .expect("other login's token should be unaffected");
+}
+
+#[tokio::test]
+async fn test_refresh_after_logout_is_not_reuse() {
+    let state = common::create_test_state().await;
+
+    let response = AuthService::register(&state.db, &state.config, register_dto("logout@example.com"))
+        .await
+        .unwrap();
+    AuthService::logout(&state.db, response.user.id).await.unwrap();
+
+    let result = AuthService::refresh_token(
+        &state.db,
+        &state.config,
+        RefreshTokenDto { refresh_token: response.refresh_token },
+    )
+    .await;
+    assert!(matches!(result, Err(AppError::Unauthorized)));
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::{CreateCardDto, CreateDeckDto},
+    services::{card::CardService, deck::DeckService},
+    state::AppState,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// A deck with two cards, owned by a new user
+async fn deck(state: &AppState, email: &str, is_public: bool) -> Uuid {
+    let user_id = common::register(state, email).await;
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: Some(is_public),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    for front in ["France", "Spain"] {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: "capital".to_string(),
+            position: None,
+        };
+        CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap();
+    }
+    deck_id
+}
+
+async fn rate(state: &AppState, deck_id: Uuid, email: &str, rating: i16) {
+    let user_id = common::register(state, email).await;
+    sqlx::query("INSERT INTO deck_ratings (deck_id, user_id, rating) VALUES ($1, $2, $3)")
+        .bind(deck_id)
+        .bind(user_id)
+        .bind(rating)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_public_deck_badges_show_cards_and_rating() {
+    let state = common::create_test_state().await;
+    let deck_id = deck(&state, "author@example.com", true).await;
+    rate(&state, deck_id, "fan@example.com", 5).await;
+    rate(&state, deck_id, "critic@example.com", 4).await;
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    // No sign-in needed: badges are embedded in third-party pages
+    let response = server.get(&format!("/api/v1/public/decks/{}/badge.json", deck_id)).await;
+    response.assert_status_ok();
+    let badge: Value = response.json();
+    assert_eq!(badge["schemaVersion"], 1);
+    assert_eq!(badge["label"], "flashcards");
+    assert_eq!(badge["message"], "2 cards | ★ 4.5");
+    assert_eq!(badge["color"], "#4c1");
+    assert_eq!(badge["deck_id"], json!(deck_id));
+    assert_eq!(badge["rating_count"], 2);
+
+    let url = format!("/api/v1/public/decks/{}/badge.svg", deck_id);
+    let response = server.get(&url).await;
+    response.assert_status_ok();
+    assert_eq!(response.header(header::CONTENT_TYPE), "image/svg+xml; charset=utf-8");
+    assert!(response.text().contains("flashcards: 2 cards | ★ 4.5"));
+
+    // Caches revalidate with the ETag
+    let etag = response.header(header::ETAG);
+    let response = server.get(&url).add_header(header::IF_NONE_MATCH, etag).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+    let response = server
+        .get(&url)
+        .add_header(header::IF_NONE_MATCH, HeaderValue::from_static("\"stale\""))
+        .await;
+    response.assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_private_decks_have_no_badge() {
+    let state = common::create_test_state().await;
+    let deck_id = deck(&state, "author@example.com", false).await;
+    let (_, reader) = common::register_with_token(&state, "reader@example.com").await;
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    for format in ["svg", "json"] {
+        let url = format!("/api/v1/public/decks/{}/badge.{}", deck_id, format);
+        let response = server.get(&url).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        // Signing in does not unlock it either
+        let response = server
+            .get(&url)
+            .add_header(header::AUTHORIZATION, reader.parse::<HeaderValue>().unwrap())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,25 @@
+use deckoracle_backend::services::import_export::archive_file_name;
+
+#[test]
+fn test_archive_file_name_slugs_title() {
+    assert_eq!(archive_file_name(0, "Spanish Basics", "csv"), "001-spanish-basics.csv");
+    assert_eq!(archive_file_name(11, "  C++ / Rust: Traits!  ", "json"), "012-c-rust-traits.json");
+}
+
+#[test]
+fn test_archive_file_name_keeps_same_titles_apart() {
+    assert_ne!(archive_file_name(0, "Verbs", "md"), archive_file_name(1, "Verbs", "md"));
+}
+
+#[test]
+fn test_archive_file_name_falls_back_for_non_ascii_titles() {
+    assert_eq!(archive_file_name(2, "日本語", "html"), "003-deck.html");
+}
+
+#[test]
+fn test_archive_file_name_truncates_long_titles() {
+    let name = archive_file_name(0, &"word ".repeat(50), "csv");
+    let stem = name.trim_start_matches("001-").trim_end_matches(".csv");
+    assert!(stem.len() <= 60);
+    assert!(!stem.ends_with('-'));
+}
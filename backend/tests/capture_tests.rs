@@ -0,0 +1,139 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    config::OllamaConfig,
+    create_app,
+    models::{CreateDeckDto, DeckRole, ShareDeckDto},
+    services::{deck::DeckService, sharing::SharingService},
+    state::AppState,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Captures are served with AI generation on; queued jobs are never run here
+async fn capture_state(require_email_verification: bool) -> AppState {
+    let mut config = common::test_config();
+    config.ai.enabled = true;
+    config.ai.provider = "ollama".to_string();
+    config.ai.ollama = Some(OllamaConfig {
+        base_url: "http://127.0.0.1:9/".to_string(),
+        model: "llama3.1".to_string(),
+        timeout_seconds: 5,
+    });
+    config.account.require_email_verification = require_email_verification;
+    AppState::from_parts(common::setup_test_db().await, config)
+}
+
+async fn create_deck(state: &AppState, user_id: Uuid) -> Uuid {
+    DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Reading notes".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+fn selection() -> Value {
+    json!({
+        "text": "  The mitochondria is the powerhouse of the cell.  ",
+        "source_url": "https://example.com/biology",
+        "page_title": "Cell biology"
+    })
+}
+
+#[tokio::test]
+async fn test_captures_become_cards_or_generation_jobs() {
+    let state = capture_state(false).await;
+    let (_, token) = common::register_with_token(&state, "reader@example.com").await;
+    let auth: HeaderValue = token.parse().unwrap();
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server.post("/api/v1/capture").add_header(header::AUTHORIZATION, auth.clone()).json(&selection()).await;
+    assert_eq!(response.status_code(), StatusCode::CREATED);
+    let result: Value = response.json();
+    assert_eq!(result["type"], "card");
+    assert_eq!(result["card"]["front"], "Cell biology");
+    assert_eq!(
+        result["card"]["back"],
+        "The mitochondria is the powerhouse of the cell.\n\nSource: Cell biology (https://example.com/biology)"
+    );
+    let captures_deck = result["card"]["deck_id"].clone();
+
+    // Later captures go to the same deck, or are queued for AI generation
+    let mut body = selection();
+    body["mode"] = json!("generate");
+    let response = server.post("/api/v1/capture").add_header(header::AUTHORIZATION, auth.clone()).json(&body).await;
+    assert_eq!(response.status_code(), StatusCode::ACCEPTED);
+    let result: Value = response.json();
+    assert_eq!(result["type"], "generation");
+    assert_eq!(result["deck_id"], captures_deck);
+    let job_id = result["job_id"].as_str().unwrap();
+    assert_eq!(result["stream_url"], format!("/api/v1/ai/generate-cards/stream?job_id={}", job_id));
+
+    let response = server
+        .get(&format!("/api/v1/decks/{}", captures_deck.as_str().unwrap()))
+        .add_header(header::AUTHORIZATION, auth)
+        .await;
+    assert_eq!(response.json::<Value>()["name"], "Web Captures");
+}
+
+#[tokio::test]
+async fn test_captures_need_edit_access_to_the_target_deck() {
+    let state = capture_state(false).await;
+    let (owner_id, _) = common::register_with_token(&state, "owner@example.com").await;
+    let (_, stranger) = common::register_with_token(&state, "stranger@example.com").await;
+    let (_, viewer) = common::register_with_token(&state, "viewer@example.com").await;
+    let deck_id = create_deck(&state, owner_id).await;
+    let dto = ShareDeckDto {
+        email: "viewer@example.com".to_string(),
+        role: DeckRole::Viewer,
+    };
+    SharingService::share_with_user(&state.db, deck_id, owner_id, dto).await.unwrap();
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+
+    let mut body = selection();
+    body["deck_id"] = json!(deck_id);
+    let capture = |authorization: &str| {
+        server
+            .post("/api/v1/capture")
+            .add_header(header::AUTHORIZATION, authorization.parse::<HeaderValue>().unwrap())
+            .json(&body)
+    };
+    assert_eq!(capture(&stranger).await.status_code(), StatusCode::NOT_FOUND);
+    assert_eq!(capture(&viewer).await.status_code(), StatusCode::FORBIDDEN);
+    let response = server.post("/api/v1/capture").json(&body).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let cards: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cards WHERE deck_id = $1")
+        .bind(deck_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(cards, 0);
+}
+
+#[tokio::test]
+async fn test_unverified_accounts_cannot_capture() {
+    let state = capture_state(true).await;
+    let (_, token) = common::register_with_token(&state, "unverified@example.com").await;
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server
+        .post("/api/v1/capture")
+        .add_header(header::AUTHORIZATION, token.parse::<HeaderValue>().unwrap())
+        .json(&selection())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(response.json::<Value>()["code"], "EMAIL_NOT_VERIFIED");
+}
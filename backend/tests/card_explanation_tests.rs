@@ -0,0 +1,205 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use deckoracle_backend::{
+    config::OllamaConfig,
+    models::{
+        ai::{AiUsageQuery, ExplainCardDto},
+        CreateCardDto, CreateDeckDto, CreateStudySessionDto, RegisterDto, UpdateCardDto,
+    },
+    services::{
+        ai_usage::AiUsageService, auth::AuthService, card::CardService,
+        card_explanation::CardExplanationService, deck::DeckService, study::StudyService,
+    },
+    utils::AppError,
+};
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Ollama stand-in that echoes whether the prompt mentioned a wrong answer
+async fn mock_ollama() -> String {
+    async fn generate(Json(body): Json<Value>) -> Json<Value> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        let prompt = body["prompt"].as_str().unwrap_or_default();
+        let text = if prompt.contains("The learner answered: Lyon") {
+            "  Paris is the capital; Lyon is a large city but not the capital.\n"
+        } else {
+            "Paris has been the capital of France for centuries."
+        };
+        Json(json!({
+            "model": body["model"],
+            "response": text,
+            "done": true,
+            "prompt_eval_count": 80,
+            "eval_count": 20,
+        }))
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, Router::new().route("/api/generate", post(generate)))
+            .await
+            .unwrap();
+    });
+
+    format!("http://{}/", addr)
+}
+
+#[tokio::test]
+async fn test_explanations_are_cached_per_card_and_wrong_answer() {
+    let state = common::create_test_state().await;
+    let mut ai = state.config.ai.clone();
+    ai.enabled = true;
+    ai.provider = "ollama".to_string();
+    ai.ollama = Some(OllamaConfig {
+        base_url: mock_ollama().await,
+        model: "llama3.1".to_string(),
+        timeout_seconds: 5,
+    });
+
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "explain@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let card = CardService::create_card(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateCardDto {
+            front: "Capital of France?".to_string(),
+            back: "Paris".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck.id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let explain = |wrong_answer: Option<&str>| {
+        let dto = ExplainCardDto {
+            wrong_answer: wrong_answer.map(str::to_string),
+            provider: None,
+        };
+        CardExplanationService::explain(&state.db, &ai, &state.ai_breaker, session.id, card.id, user_id, dto)
+    };
+
+    let first = explain(None).await.unwrap();
+    assert!(!first.cached);
+    assert_eq!(first.tokens_used, 100);
+    assert_eq!(first.model_name, "llama3.1");
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+    let again = explain(None).await.unwrap();
+    assert!(again.cached);
+    assert_eq!(again.explanation, first.explanation);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+    // A wrong answer gets its own explanation, shared by spellings that
+    // normalize the same
+    let mistake = explain(Some("Lyon")).await.unwrap();
+    assert!(!mistake.cached);
+    assert_eq!(mistake.explanation, "Paris is the capital; Lyon is a large city but not the capital.");
+    assert!(explain(Some(" lyon! ")).await.unwrap().cached);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+    // Only provider calls count against the quota
+    let usage = AiUsageService::get(&state.db, &ai, user_id, &AiUsageQuery { days: None }).await.unwrap();
+    assert_eq!(usage.tokens_today, 200);
+
+    // Editing the card invalidates its explanations
+    CardService::update_card(
+        &state.db,
+        card.id,
+        user_id,
+        UpdateCardDto {
+            front: None,
+            back: Some("Paris (since 987)".to_string()),
+            position: None,
+            updated_at: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!explain(None).await.unwrap().cached);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+
+    // Cards outside the session's deck are refused
+    let other = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Other".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let stray = CardService::create_card(
+        &state.db,
+        other.id,
+        user_id,
+        CreateCardDto {
+            front: "Capital of Spain?".to_string(),
+            back: "Madrid".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+    let result = CardExplanationService::explain(
+        &state.db,
+        &ai,
+        &state.ai_breaker,
+        session.id,
+        stray.id,
+        user_id,
+        ExplainCardDto::default(),
+    )
+    .await;
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use deckoracle_backend::models::{
+    ai::StudyQueueQuery, CreateCardDto, CreateDeckDto, RegisterDto, UpdateCardFlagsDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, card_flags::CardFlagsService, deck::DeckService,
+    study_queue::StudyQueueService,
+};
+
+fn queue_query(deck_id: uuid::Uuid, prioritize_starred: bool) -> StudyQueueQuery {
+    StudyQueueQuery {
+        deck_id: Some(deck_id),
+        folder_id: None,
+        smart_deck_id: None,
+        max_new_cards: None,
+        focus_weak_cards: None,
+        include_overdue: None,
+        prioritize_starred: Some(prioritize_starred),
+    }
+}
+
+#[tokio::test]
+async fn test_flags_change_the_study_queue() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "flags@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Verbs".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut cards = Vec::new();
+    for front in ["ser", "estar", "tener"] {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: "back".to_string(),
+            position: None,
+        };
+        cards.push(CardService::create_card(&state.db, deck.id, user_id, dto).await.unwrap().id);
+    }
+
+    let flags = CardFlagsService::get_flags(&state.db, cards[0], user_id).await.unwrap();
+    assert!(!flags.starred && !flags.suspended && flags.notes.is_none());
+
+    CardFlagsService::update_flags(
+        &state.db,
+        cards[0],
+        user_id,
+        UpdateCardFlagsDto {
+            suspended: Some(true),
+            notes: Some("Too easy".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let flags = CardFlagsService::update_flags(
+        &state.db,
+        cards[2],
+        user_id,
+        UpdateCardFlagsDto {
+            starred: Some(true),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(flags.starred);
+
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(deck.id, false))
+        .await
+        .unwrap();
+    let queued: Vec<_> = queue.new.iter().map(|s| s.card_id).collect();
+    assert_eq!(queued, vec![cards[1], cards[2]]);
+
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(deck.id, true))
+        .await
+        .unwrap();
+    let queued: Vec<_> = queue.new.iter().map(|s| s.card_id).collect();
+    assert_eq!(queued, vec![cards[2], cards[1]]);
+
+    // Unsuspending keeps the notes, and an empty string clears them
+    let flags = CardFlagsService::update_flags(
+        &state.db,
+        cards[0],
+        user_id,
+        UpdateCardFlagsDto {
+            suspended: Some(false),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(flags.notes.as_deref(), Some("Too easy"));
+    let flags = CardFlagsService::update_flags(
+        &state.db,
+        cards[0],
+        user_id,
+        UpdateCardFlagsDto {
+            notes: Some(String::new()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(flags.notes.is_none());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto, CreateStudySessionDto, Rating};
+use deckoracle_backend::services::{card::CardService, deck::DeckService, study::StudyService};
+use deckoracle_backend::utils::AppError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn answer(
+    db: &PgPool,
+    user_id: Uuid,
+    deck_id: Uuid,
+    card_id: Uuid,
+    rating: Rating,
+    response_time_ms: i32,
+) {
+    let session = StudyService::create_study_session(
+        db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck_id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+    StudyService::record_answer(db, &session, card_id, rating, Some(response_time_ms))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_card_history_lists_the_users_answers_in_order() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "history@example.com").await;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Verbs".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let card = CardService::create_card(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateCardDto {
+            front: "ser".to_string(),
+            back: "to be".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let history = StudyService::get_card_history(&state.db, card.id, user_id).await.unwrap();
+    assert_eq!(history.total_reviews, 0);
+    assert!(history.next_review_at.is_none());
+
+    answer(&state.db, user_id, deck.id, card.id, Rating::Good, 3000).await;
+    answer(&state.db, user_id, deck.id, card.id, Rating::Again, 5000).await;
+
+    let history = StudyService::get_card_history(&state.db, card.id, user_id).await.unwrap();
+    assert_eq!(history.card.id, card.id);
+    assert_eq!(history.total_reviews, 2);
+    assert_eq!(history.average_response_time_ms, Some(4000));
+    let ratings: Vec<Rating> = history.reviews.iter().map(|r| r.rating).collect();
+    assert_eq!(ratings, vec![Rating::Good, Rating::Again]);
+    assert_ne!(history.reviews[0].session_id, history.reviews[1].session_id);
+
+    // Each answer carries the interval it started from and the one it set
+    let first = history.reviews[0].scheduler.as_ref().unwrap();
+    let second = history.reviews[1].scheduler.as_ref().unwrap();
+    assert_eq!(second.interval_days_before, first.interval_days_after);
+    assert_eq!(history.next_review_at, Some(second.next_review_at));
+
+    // Someone without access to the deck cannot see the card or its history
+    let stranger = common::register(&state, "stranger@example.com").await;
+    let error = StudyService::get_card_history(&state.db, card.id, stranger).await.unwrap_err();
+    assert!(matches!(error, AppError::CardNotFound));
+}
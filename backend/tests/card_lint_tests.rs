@@ -0,0 +1,139 @@
+mod common;
+
+use uuid::Uuid;
+
+use deckoracle_backend::models::{
+    ai::{ApplyLintSuggestionsDto, LintDeckDto, LintIssueKind, LintSource},
+    CreateCardDto, CreateDeckDto, RegisterDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService,
+    card::CardService,
+    card_lint::{heuristic_issues, CardLintService},
+    deck::DeckService,
+};
+
+#[test]
+fn test_heuristics_flag_leaks_long_answers_and_duplicates() {
+    let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+    let cards = vec![
+        (ids[0], "Is Paris the capital of France?".to_string(), "Paris".to_string()),
+        (ids[1], "Explain the Treaty of Versailles".to_string(), "x".repeat(250)),
+        (ids[2], "What is the capital of Spain?".to_string(), "Madrid".to_string()),
+        (ids[3], "what is the capital of spain".to_string(), "Madrid".to_string()),
+    ];
+
+    let findings = heuristic_issues(&cards);
+    let kinds: Vec<(Uuid, LintIssueKind)> = findings.iter().map(|f| (f.card_id, f.kind)).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (ids[0], LintIssueKind::AnswerLeak),
+            (ids[1], LintIssueKind::LongAnswer),
+            (ids[3], LintIssueKind::Duplicate),
+        ]
+    );
+    assert!(findings.iter().all(|f| f.source == LintSource::Heuristic));
+    assert_eq!(findings[0].front.as_deref(), Some("Is _____ the capital of France?"));
+    assert_eq!(findings[2].message, "Asks the same question as card 3");
+
+    // Short answers and partial words are not leaks
+    let cards = vec![
+        (ids[0], "Is it true?".to_string(), "No".to_string()),
+        (ids[1], "What is Parisian art deco known for?".to_string(), "Paris".to_string()),
+    ];
+    assert!(heuristic_issues(&cards).is_empty());
+}
+
+#[tokio::test]
+async fn test_lint_deck_and_apply_fix() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "lint@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let card = CardService::create_card(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateCardDto {
+            front: "Rome is the capital of which country? (Italy)".to_string(),
+            back: "Italy".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let dto = LintDeckDto { use_ai: Some(false), provider: None };
+    let report = CardLintService::lint_deck(&state.db, &state.config.ai, &state.ai_breaker, deck.id, user_id, dto.clone())
+        .await
+        .unwrap();
+    assert_eq!(report.cards_checked, 1);
+    assert!(!report.ai_reviewed);
+    assert_eq!(report.suggestions.len(), 1);
+    let suggestion = &report.suggestions[0];
+    assert_eq!(suggestion.card_id, card.id);
+    assert_eq!(suggestion.kind, LintIssueKind::AnswerLeak);
+
+    // Linting again replaces the unapplied suggestion
+    let report = CardLintService::lint_deck(&state.db, &state.config.ai, &state.ai_breaker, deck.id, user_id, dto)
+        .await
+        .unwrap();
+    let stale = suggestion.id;
+    let suggestion = report.suggestions[0].clone();
+    assert_ne!(suggestion.id, stale);
+    assert!(CardLintService::apply(
+        &state.db,
+        deck.id,
+        user_id,
+        ApplyLintSuggestionsDto { suggestion_ids: vec![stale] },
+    )
+    .await
+    .is_err());
+
+    let updated = CardLintService::apply(
+        &state.db,
+        deck.id,
+        user_id,
+        ApplyLintSuggestionsDto { suggestion_ids: vec![suggestion.id] },
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.len(), 1);
+    assert_eq!(updated[0].front, "Rome is the capital of which country? (_____)");
+    assert_eq!(updated[0].back, "Italy");
+
+    // Applied suggestions cannot be applied twice
+    assert!(CardLintService::apply(
+        &state.db,
+        deck.id,
+        user_id,
+        ApplyLintSuggestionsDto { suggestion_ids: vec![suggestion.id] },
+    )
+    .await
+    .is_err());
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use deckoracle_backend::models::{
+    CreateCardDto, CreateDeckDto, DeckRole, ShareDeckDto, UpdateCardFlagsDto,
+};
+use deckoracle_backend::services::{
+    card::CardService, card_flags::CardFlagsService, deck::DeckService, sharing::SharingService,
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use uuid::Uuid;
+
+/// A deck with one card per front, returning the deck and card ids
+async fn deck_with_cards(state: &AppState, user_id: Uuid, name: &str, fronts: &[&str]) -> (Uuid, Vec<Uuid>) {
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: name.to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut cards = Vec::new();
+    for front in fronts {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: "back".to_string(),
+            position: None,
+        };
+        cards.push(CardService::create_card(&state.db, deck.id, user_id, dto).await.unwrap().id);
+    }
+    (deck.id, cards)
+}
+
+/// Fronts and positions of the deck's live cards, in order
+async fn layout(state: &AppState, deck_id: Uuid) -> Vec<(String, i32)> {
+    sqlx::query_as(
+        "SELECT front, position FROM cards WHERE deck_id = $1 AND deleted_at IS NULL ORDER BY position",
+    )
+    .bind(deck_id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap()
+}
+
+fn expected(fronts: &[&str]) -> Vec<(String, i32)> {
+    fronts.iter().enumerate().map(|(i, front)| (front.to_string(), i as i32)).collect()
+}
+
+#[tokio::test]
+async fn test_move_renumbers_both_decks_and_keeps_flags() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "move@example.com").await;
+    let (source, cards) = deck_with_cards(&state, user_id, "Source", &["a", "b", "c"]).await;
+    let (target, _) = deck_with_cards(&state, user_id, "Target", &["x", "y"]).await;
+    let starred = UpdateCardFlagsDto {
+        starred: Some(true),
+        ..Default::default()
+    };
+    CardFlagsService::update_flags(&state.db, cards[1], user_id, starred).await.unwrap();
+
+    let moved = CardService::move_cards(&state.db, &[cards[1]], target, user_id, Some(1)).await.unwrap();
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].card.id, cards[1]);
+    assert_eq!((moved[0].card.deck_id, moved[0].from_deck_id), (target, source));
+
+    assert_eq!(layout(&state, source).await, expected(&["a", "c"]));
+    assert_eq!(layout(&state, target).await, expected(&["x", "b", "y"]));
+    assert!(CardFlagsService::get_flags(&state.db, cards[1], user_id).await.unwrap().starred);
+
+    let counts: Vec<i32> = sqlx::query_scalar("SELECT card_count FROM decks WHERE id = ANY($1) ORDER BY title")
+        .bind(vec![source, target])
+        .fetch_all(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(counts, [2, 3]);
+
+    assert!(matches!(
+        CardService::move_cards(&state.db, &[cards[1]], target, user_id, None).await,
+        Err(AppError::BadRequest(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_bulk_move_appends_in_request_order() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "bulk-move@example.com").await;
+    let (first, first_cards) = deck_with_cards(&state, user_id, "First", &["a", "b"]).await;
+    let (second, second_cards) = deck_with_cards(&state, user_id, "Second", &["c", "d"]).await;
+    let (target, _) = deck_with_cards(&state, user_id, "Target", &["x"]).await;
+
+    let ids = [second_cards[1], first_cards[0], second_cards[1]];
+    let moved = CardService::move_cards(&state.db, &ids, target, user_id, None).await.unwrap();
+    assert_eq!(moved.len(), 2);
+
+    assert_eq!(layout(&state, target).await, expected(&["x", "d", "a"]));
+    assert_eq!(layout(&state, first).await, expected(&["b"]));
+    assert_eq!(layout(&state, second).await, expected(&["c"]));
+
+    assert!(matches!(
+        CardService::move_cards(&state.db, &[Uuid::new_v4()], target, user_id, None).await,
+        Err(AppError::CardNotFound)
+    ));
+}
+
+#[tokio::test]
+async fn test_move_requires_owning_the_target() {
+    let state = common::create_test_state().await;
+    let owner = common::register(&state, "move-owner@example.com").await;
+    let editor = common::register(&state, "move-editor@example.com").await;
+    let (shared, _) = deck_with_cards(&state, owner, "Shared", &["s"]).await;
+    let (own, cards) = deck_with_cards(&state, editor, "Own", &["o"]).await;
+    SharingService::share_with_user(
+        &state.db,
+        shared,
+        owner,
+        ShareDeckDto {
+            email: "move-editor@example.com".to_string(),
+            role: DeckRole::Editor,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert!(CardService::move_cards(&state.db, &[cards[0]], shared, editor, None).await.is_err());
+    assert_eq!(layout(&state, own).await, expected(&["o"]));
+    assert_eq!(layout(&state, shared).await, expected(&["s"]));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use deckoracle_backend::models::{CardOrder, CreateCardDto, CreateDeckDto, RegisterDto};
+use deckoracle_backend::services::{auth::AuthService, card::CardService, deck::DeckService};
+use deckoracle_backend::utils::{AppError, CursorParams};
+use uuid::Uuid;
+
+async fn deck_with_cards(state: &deckoracle_backend::state::AppState, email: &str, count: i32) -> (Uuid, Uuid) {
+    let user = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: email.to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user;
+
+    let deck = DeckService::create_deck(
+        &state.db,
+        user.id,
+        CreateDeckDto {
+            name: "Paging".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: Some(false),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    for i in 0..count {
+        CardService::create_card(
+            &state.db,
+            deck.id,
+            user.id,
+            CreateCardDto {
+                front: format!("front {}", i),
+                back: "back".to_string(),
+                position: Some(i),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    (user.id, deck.id)
+}
+
+#[tokio::test]
+async fn test_cursor_walks_every_card_once() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = deck_with_cards(&state, "paging-walk@example.com", 5).await;
+
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let params = CursorParams { cursor, limit: 2 };
+        let page = CardService::list_deck_cards(&state.db, deck_id, user_id, CardOrder::Position, params)
+            .await
+            .unwrap();
+        assert!(page.data.len() <= 2);
+        seen.extend(page.data.iter().map(|card| card.position));
+
+        if !page.pagination.has_next {
+            assert!(page.pagination.next_cursor.is_none());
+            break;
+        }
+        cursor = page.pagination.next_cursor;
+    }
+
+    assert_eq!(seen, vec![0, 1, 2, 3, 4]);
+}
+
+#[tokio::test]
+async fn test_invalid_cursor_is_rejected() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = deck_with_cards(&state, "paging-invalid@example.com", 1).await;
+
+    let params = CursorParams {
+        cursor: Some("not-a-cursor".to_string()),
+        limit: 10,
+    };
+    let result = CardService::list_deck_cards(&state.db, deck_id, user_id, CardOrder::Created, params).await;
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+}
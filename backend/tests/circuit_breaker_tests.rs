@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use deckoracle_backend::services::{circuit_breaker::CircuitBreaker, vertex_ai::backoff_delay};
+
+#[test]
+fn test_breaker_opens_after_threshold_and_recovers() {
+    let breaker = CircuitBreaker::new(3, Duration::from_millis(50));
+
+    for _ in 0..2 {
+        assert!(breaker.allow());
+        breaker.record_failure();
+    }
+    assert!(!breaker.is_open());
+
+    breaker.record_failure();
+    assert!(breaker.is_open());
+    assert!(!breaker.allow());
+
+    // After the cooldown exactly one trial call goes through
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.allow());
+    assert!(!breaker.allow());
+
+    // A failed trial reopens the breaker; a successful one closes it
+    breaker.record_failure();
+    assert!(!breaker.allow());
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.allow());
+    breaker.record_success();
+    assert!(!breaker.is_open());
+    assert!(breaker.allow());
+}
+
+#[test]
+fn test_success_resets_failure_count() {
+    let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+    breaker.record_failure();
+    breaker.record_success();
+    breaker.record_failure();
+    assert!(!breaker.is_open());
+}
+
+#[test]
+fn test_zero_threshold_disables_breaker() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60));
+    for _ in 0..10 {
+        breaker.record_failure();
+    }
+    assert!(breaker.allow());
+}
+
+#[test]
+fn test_backoff_doubles_with_jitter_and_caps() {
+    for attempt in 0..4 {
+        let ceiling = 100 * 2u64.pow(attempt);
+        let delay = backoff_delay(100, attempt).as_millis() as u64;
+        assert!((ceiling / 2..=ceiling).contains(&delay), "attempt {}: {}ms", attempt, delay);
+    }
+    assert!(backoff_delay(1_000, 30) <= Duration::from_secs(30));
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use axum::http::{header, HeaderMap, HeaderValue};
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto, UpdateCardDto};
+use deckoracle_backend::services::{card::CardService, deck::DeckService};
+use deckoracle_backend::utils::{etag, AppError};
+use uuid::Uuid;
+
+fn card_dto(front: &str) -> CreateCardDto {
+    CreateCardDto {
+        front: front.to_string(),
+        back: "back".to_string(),
+        position: None,
+    }
+}
+
+#[tokio::test]
+async fn test_deck_list_version_tracks_decks_and_cards() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "etag-decks@example.com").await;
+
+    let empty = DeckService::list_user_decks_version(&state.db, user_id).await.unwrap();
+    assert_eq!(empty, DeckService::list_user_decks_version(&state.db, user_id).await.unwrap());
+
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "ETag".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: Some(false),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let with_deck = DeckService::list_user_decks_version(&state.db, user_id).await.unwrap();
+    assert_ne!(empty, with_deck);
+
+    CardService::create_card(&state.db, deck.id, user_id, card_dto("one")).await.unwrap();
+    let with_card = DeckService::list_user_decks_version(&state.db, user_id).await.unwrap();
+    assert_ne!(with_deck, with_card);
+}
+
+#[tokio::test]
+async fn test_card_list_version_tracks_edits_and_requires_access() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "etag-cards@example.com").await;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "ETag".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: Some(false),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let card = CardService::create_card(&state.db, deck.id, user_id, card_dto("one")).await.unwrap();
+
+    let before = CardService::deck_cards_version(&state.db, deck.id, user_id).await.unwrap();
+    CardService::update_card(
+        &state.db,
+        card.id,
+        user_id,
+        UpdateCardDto {
+            front: Some("edited".to_string()),
+            back: None,
+            position: None,
+            updated_at: None,
+        },
+    )
+    .await
+    .unwrap();
+    let after = CardService::deck_cards_version(&state.db, deck.id, user_id).await.unwrap();
+    assert_ne!(before, after);
+
+    let stranger = common::register(&state, "etag-stranger@example.com").await;
+    let result = CardService::deck_cards_version(&state.db, deck.id, stranger).await;
+    assert!(matches!(result, Err(AppError::DeckNotFound)));
+}
+
+#[test]
+fn test_if_none_match_comparison() {
+    let tag = etag::etag("cards", "deck:1:2");
+    assert_eq!(tag, etag::etag("cards", "deck:1:2"));
+    assert_ne!(tag, etag::etag("decks", "deck:1:2"));
+
+    let mut headers = HeaderMap::new();
+    assert!(!etag::is_fresh(&headers, &tag));
+
+    let listed = format!("\"other\", W/{}", tag.to_str().unwrap());
+    headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&listed).unwrap());
+    assert!(etag::is_fresh(&headers, &tag));
+
+    headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"stale\""));
+    assert!(!etag::is_fresh(&headers, &tag));
+}
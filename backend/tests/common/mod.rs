@@ -1,8 +1,13 @@
+// Each test binary compiles this module and uses only some of its helpers
+#![allow(dead_code)]
+
 use deckoracle_backend::config::Config;
+use deckoracle_backend::models::RegisterDto;
+use deckoracle_backend::services::auth::AuthService;
 use deckoracle_backend::state::AppState;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::{PgPool, Postgres};
-use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
 /// Create a test database pool with a unique database name
@@ -30,10 +35,12 @@ pub async fn setup_test_db() -> PgPool {
         .await
         .expect("Failed to create test database");
     
-    // Connect to the test database
+    // Connect to the test database, sized like the app's own pool
+    let database = test_config().database;
     let test_db_url = format!("{}/{}", base_url, test_db_name);
     let pool = PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(database.max_connections)
+        .acquire_timeout(Duration::from_secs(database.acquire_timeout_seconds))
         .connect(&test_db_url)
         .await
         .expect("Failed to connect to test database");
@@ -69,18 +76,44 @@ pub async fn cleanup_test_db(pool: PgPool, db_name: String) {
         .ok(); // Ignore errors on cleanup
 }
 
+/// Load configuration and pin the settings tests depend on
+pub fn test_config() -> Config {
+    let mut config = Config::from_env().expect("Failed to load configuration");
+    config.jwt.secret = "test_secret".to_string();
+    config.jwt.expiration = 3600;
+    // Tests share one client address, so per-IP limits would trip
+    config.rate_limit.enabled = false;
+    config.lockout.enabled = true;
+    config.lockout.threshold = 5;
+    config
+}
+
 /// Create test app state
-pub async fn create_test_state() -> Arc<AppState> {
+pub async fn create_test_state() -> AppState {
     let pool = setup_test_db().await;
-    let config = Config {
-        database_url: "test".to_string(),
-        server_host: "127.0.0.1".to_string(),
-        server_port: 0,
-        jwt_secret: "test_secret".to_string(),
-        environment: "test".to_string(),
-    };
     
-    Arc::new(AppState::new(pool, config))
+    AppState::from_parts(pool, test_config())
+}
+
+/// Register a user with the standard test password, returning their id
+pub async fn register(state: &AppState, email: &str) -> Uuid {
+    register_with_token(state, email).await.0
+}
+
+/// Register a user, returning their id and a `Bearer` authorization value
+pub async fn register_with_token(state: &AppState, email: &str) -> (Uuid, String) {
+    let registered = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: email.to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .expect("Failed to register test user");
+    (registered.user.id, format!("Bearer {}", registered.access_token))
 }
 
 /// Test data fixtures
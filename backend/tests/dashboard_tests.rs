@@ -0,0 +1,99 @@
+mod common;
+
+use deckoracle_backend::models::{
+    CreateCardDto, CreateDeckDto, CreateFolderDto, CreateStudySessionDto, Rating, RegisterDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, dashboard::DashboardService, deck::DeckService,
+    folder::FolderService, study::StudyService,
+};
+
+#[tokio::test]
+async fn test_dashboard_aggregates_home_screen() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "dashboard@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+
+    let empty = DashboardService::get(&state.db, user_id).await.unwrap();
+    assert!(empty.folders.is_empty() && empty.decks.is_empty() && empty.recent_sessions.is_empty());
+    assert_eq!(empty.streak.current_days, 0);
+    assert!(!empty.streak.studied_today);
+
+    let folder = FolderService::create_folder(
+        &state.db,
+        user_id,
+        CreateFolderDto {
+            name: "Languages".to_string(),
+            parent_folder_id: None,
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Spanish".to_string(),
+            description: None,
+            folder_id: Some(folder.id),
+            is_public: Some(false),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut cards = Vec::new();
+    for front in ["uno", "dos"] {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: "number".to_string(),
+            position: None,
+        };
+        cards.push(CardService::create_card(&state.db, deck.id, user_id, dto).await.unwrap());
+    }
+
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck.id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+    StudyService::record_answer(&state.db, &session, cards[0].id, Rating::Good, None)
+        .await
+        .unwrap();
+
+    let dashboard = DashboardService::get(&state.db, user_id).await.unwrap();
+    assert_eq!(dashboard.folders.len(), 1);
+    assert_eq!(dashboard.folders[0].folder.id, folder.id);
+    assert_eq!(dashboard.decks.len(), 1);
+    let summary = &dashboard.decks[0];
+    assert_eq!(summary.deck.deck.id, deck.id);
+    assert_eq!(summary.deck.card_count, 2);
+    assert_eq!((summary.due_count, summary.new_count), (0, 1));
+    assert_eq!((dashboard.total_due, dashboard.total_new), (0, 1));
+    assert_eq!(dashboard.streak.current_days, 1);
+    assert!(dashboard.streak.studied_today);
+    assert_eq!(dashboard.recent_sessions.len(), 1);
+    assert_eq!(dashboard.recent_sessions[0].id, session.id);
+}
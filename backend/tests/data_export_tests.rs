@@ -0,0 +1,136 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto, DeleteAccountDto, RegisterDto};
+use deckoracle_backend::services::{
+    auth::AuthService,
+    card::CardService,
+    data_export::{archive_key, export_ready_email, DataExportService, DATA_EXPORT_JOB_TYPE},
+    deck::DeckService,
+    job::JobService,
+    mailer::LogMailer,
+    storage::{LocalStorage, MediaStore},
+    user::UserService,
+};
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[test]
+fn test_export_ready_email() {
+    let expires_at = Utc.with_ymd_and_hms(2026, 3, 9, 14, 30, 0).unwrap();
+    let (subject, body) = export_ready_email(Some("Ana"), "https://api.example.com/x.zip", expires_at);
+    assert_eq!(subject, "Your data export is ready");
+    assert!(body.starts_with("Hi Ana,"));
+    assert!(body.contains("Download it: https://api.example.com/x.zip"));
+    assert!(body.contains("2026-03-09 14:30 UTC"));
+
+    let (_, body) = export_ready_email(None, "https://api.example.com/x.zip", expires_at);
+    assert!(body.starts_with("Hi,"));
+}
+
+#[tokio::test]
+async fn test_data_export_and_account_erasure() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "export@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: Some("Ana".to_string()),
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Spanish Basics".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    CardService::create_card(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateCardDto {
+            front: "hola".to_string(),
+            back: "hello".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut config = common::test_config();
+    config.storage.local_path = std::env::temp_dir()
+        .join(format!("deckoracle_exports_{}", Uuid::new_v4().simple()))
+        .to_string_lossy()
+        .into_owned();
+    config.email.api_url = "https://api.example.com/".to_string();
+    let media = MediaStore::new(Arc::new(LocalStorage::new(&config.storage.local_path)), &config);
+
+    let job = JobService::create_job(&state.db, user_id, DATA_EXPORT_JOB_TYPE, json!({}), None, false)
+        .await
+        .unwrap();
+    let (result, url) = DataExportService::run(&state.db, &media, &LogMailer, &config.email, &job)
+        .await
+        .unwrap();
+    assert!(url.starts_with("https://api.example.com/api/v1/media/exports/"));
+    let files: Vec<&str> = result["files"].as_array().unwrap().iter().map(|f| f.as_str().unwrap()).collect();
+    assert_eq!(
+        files,
+        [
+            "decks/001-spanish-basics.json",
+            "profile.json",
+            "study_history.json",
+            "stats.json",
+            "achievements.json",
+            "ai_settings.json",
+            "preferences.json",
+        ]
+    );
+    let archive = media.get(&archive_key(user_id, job.id)).await.unwrap();
+    assert_eq!(archive.len() as u64, result["size_bytes"].as_u64().unwrap());
+    assert!(archive.starts_with(b"PK"));
+    JobService::complete_job(&state.db, job.id, result, Some(url)).await.unwrap();
+
+    // Fresh archives stay until the account is deleted
+    assert_eq!(DataExportService::purge_expired(&state.db, &media).await.unwrap(), 0);
+    UserService::delete_account(
+        &state.db,
+        user_id,
+        DeleteAccountDto {
+            password: "Password123".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(DataExportService::purge_expired(&state.db, &media).await.unwrap(), 1);
+    assert!(media.get(&archive_key(user_id, job.id)).await.is_err());
+
+    // Erased only once the grace period is over
+    assert_eq!(UserService::erase_deleted_accounts(&state.db).await.unwrap(), 0);
+    sqlx::query("UPDATE users SET deleted_at = NOW() - INTERVAL '31 days' WHERE id = $1")
+        .bind(user_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(UserService::erase_deleted_accounts(&state.db).await.unwrap(), 1);
+    let decks_left = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM decks WHERE owner_id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(decks_left, 0);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto};
+use deckoracle_backend::services::{
+    card::CardService, deck::DeckService, media::MediaService,
+    storage::{LocalStorage, MediaStore}, trash::TrashService,
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn create_deck(state: &AppState, user_id: Uuid, name: &str) -> Uuid {
+    DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: name.to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+async fn card_count(state: &AppState, user_id: Uuid, deck_id: Uuid) -> i64 {
+    DeckService::get_deck_with_stats(&state.db, deck_id, user_id).await.unwrap().card_count
+}
+
+#[tokio::test]
+async fn test_card_count_follows_cards() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "card-count@example.com").await;
+    let first = create_deck(&state, user_id, "First").await;
+    let second = create_deck(&state, user_id, "Second").await;
+
+    let mut cards = Vec::new();
+    for front in ["one", "two", "three"] {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: "back".to_string(),
+            position: None,
+        };
+        cards.push(CardService::create_card(&state.db, first, user_id, dto).await.unwrap().id);
+    }
+    assert_eq!(card_count(&state, user_id, first).await, 3);
+
+    CardService::delete_card(&state.db, cards[0], user_id).await.unwrap();
+    assert_eq!(card_count(&state, user_id, first).await, 2);
+    TrashService::restore(&state.db, user_id, cards[0]).await.unwrap();
+    assert_eq!(card_count(&state, user_id, first).await, 3);
+
+    sqlx::query("UPDATE cards SET deck_id = $1 WHERE id = $2")
+        .bind(second)
+        .bind(cards[1])
+        .execute(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(card_count(&state, user_id, first).await, 2);
+    assert_eq!(card_count(&state, user_id, second).await, 1);
+
+    // Hard deletes of cards already in the trash leave the count alone
+    CardService::delete_card(&state.db, cards[2], user_id).await.unwrap();
+    sqlx::query("DELETE FROM cards WHERE id = $1").bind(cards[2]).execute(&state.db).await.unwrap();
+    assert_eq!(card_count(&state, user_id, first).await, 1);
+    sqlx::query("DELETE FROM cards WHERE id = $1").bind(cards[0]).execute(&state.db).await.unwrap();
+    assert_eq!(card_count(&state, user_id, first).await, 0);
+
+    let listed = DeckService::list_user_decks(&state.db, user_id).await.unwrap();
+    let counts: Vec<(String, i64)> = listed.into_iter().map(|d| (d.deck.name, d.card_count)).collect();
+    assert_eq!(counts, [("First".to_string(), 0), ("Second".to_string(), 1)]);
+}
+
+#[tokio::test]
+async fn test_cover_upload_replace_and_delete() {
+    let state = common::create_test_state().await;
+    let mut config = common::test_config();
+    config.storage.local_path = std::env::temp_dir()
+        .join(format!("deckoracle_covers_{}", Uuid::new_v4().simple()))
+        .to_string_lossy()
+        .into_owned();
+    let store = MediaStore::new(Arc::new(LocalStorage::new(&config.storage.local_path)), &config);
+
+    let owner = common::register(&state, "cover-owner@example.com").await;
+    let stranger = common::register(&state, "cover-stranger@example.com").await;
+    let deck_id = create_deck(&state, owner, "Covered").await;
+
+    let deck = MediaService::upload_deck_cover(&state.db, &store, deck_id, owner, "cover.png", b"first".to_vec())
+        .await
+        .unwrap();
+    let first_key = deck.cover_storage_key.clone().unwrap();
+    assert!(first_key.starts_with(&format!("decks/{}/cover-", deck_id)));
+    assert!(deck.cover_url.unwrap().starts_with(&format!("/api/v1/media/{}?", first_key)));
+
+    let replaced = MediaService::upload_deck_cover(&state.db, &store, deck_id, owner, "cover.jpg", b"second".to_vec())
+        .await
+        .unwrap();
+    let second_key = replaced.cover_storage_key.unwrap();
+    assert_ne!(first_key, second_key);
+    assert_eq!(store.get(&second_key).await.unwrap(), b"second");
+    assert!(store.get(&first_key).await.is_err());
+
+    // Audio is a valid attachment but not a cover
+    let audio = MediaService::upload_deck_cover(&state.db, &store, deck_id, owner, "clip.mp3", b"id3".to_vec()).await;
+    assert!(matches!(audio, Err(AppError::FileUploadError(_))));
+    let stranger_upload =
+        MediaService::upload_deck_cover(&state.db, &store, deck_id, stranger, "cover.png", b"x".to_vec()).await;
+    assert!(stranger_upload.is_err());
+
+    MediaService::delete_deck_cover(&state.db, &store, deck_id, owner).await.unwrap();
+    assert!(store.get(&second_key).await.is_err());
+    let listed = DeckService::list_user_decks(&state.db, owner).await.unwrap();
+    assert!(listed[0].cover_storage_key.is_none());
+    assert!(matches!(
+        MediaService::delete_deck_cover(&state.db, &store, deck_id, owner).await,
+        Err(AppError::NotFound(_))
+    ));
+
+    std::fs::remove_dir_all(&config.storage.local_path).ok();
+}
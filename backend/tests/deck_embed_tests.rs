@@ -0,0 +1,148 @@
+mod common;
+
+use axum::http::{header, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::create_app;
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto, CreateShareLinkDto, RegisterDto};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, deck::DeckService, sharing::SharingService,
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_share_link_serves_sanitized_embed() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "embed@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Private but shared".to_string(),
+            description: Some("Embedded".to_string()),
+            folder_id: None,
+            is_public: Some(false),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    for (front, back) in [("**bold**", "<img src=x onerror=alert(1)>"), ("second", "two")] {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: back.to_string(),
+            position: None,
+        };
+        CardService::create_card(&state.db, deck.id, user_id, dto).await.unwrap();
+    }
+
+    let link = SharingService::create_link(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateShareLinkDto {
+            role: None,
+            expires_in_hours: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let embed = SharingService::deck_embed(&state.db, &link.token).await.unwrap();
+    assert_eq!(embed.title, "Private but shared");
+    assert_eq!(embed.card_count, 2);
+    assert_eq!(embed.cards[0].front, "**bold**");
+    assert!(embed.cards[0].front_html.contains("<strong>bold</strong>"));
+    assert!(!embed.cards[0].back_html.contains("onerror"));
+    assert_eq!(embed.cards[1].front, "second");
+
+    let json = serde_json::to_value(&embed).unwrap();
+    assert!(json.get("id").is_none() && json.get("owner_id").is_none());
+
+    SharingService::revoke_link(&state.db, deck.id, user_id, link.id).await.unwrap();
+    assert!(matches!(
+        SharingService::deck_embed(&state.db, &link.token).await,
+        Err(AppError::NotFound(_))
+    ));
+    assert!(matches!(
+        SharingService::deck_embed(&state.db, "no-such-token").await,
+        Err(AppError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_embed_can_be_framed_by_configured_pages() {
+    let mut config = common::test_config();
+    config.security.embed_frame_ancestors = "https://blog.example".to_string();
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+    let user_id = common::register(&state, "framed@example.com").await;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Framed".to_string(),
+            description: Some("Shared on a blog".to_string()),
+            folder_id: None,
+            is_public: Some(false),
+            front_language: Some("es".to_string()),
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let dto = CreateCardDto {
+        front: "*hola*".to_string(),
+        back: "hello".to_string(),
+        position: None,
+    };
+    CardService::create_card(&state.db, deck.id, user_id, dto).await.unwrap();
+    let dto = CreateShareLinkDto {
+        role: None,
+        expires_in_hours: None,
+    };
+    let link = SharingService::create_link(&state.db, deck.id, user_id, dto).await.unwrap();
+    let db = state.db.clone();
+    let server = TestServer::new(create_app(state)).unwrap();
+    let path = format!("/api/v1/public/decks/{}", link.token);
+
+    let response = server.get(&path).await;
+    response.assert_status_ok();
+    let embed: Value = response.json();
+    assert_eq!(embed["title"], "Framed");
+    assert_eq!(embed["description"], "Shared on a blog");
+    assert_eq!(embed["front_language"], "es");
+    assert_eq!(embed["card_count"], 1);
+    assert_eq!(embed["cards"][0]["front"], "*hola*");
+    assert_eq!(embed["cards"][0]["front_html"], "<p><em>hola</em></p>\n");
+    assert_eq!(embed["cards"][0]["back"], "hello");
+    assert!(embed.get("id").is_none() && embed.get("owner_id").is_none());
+    assert_eq!(
+        response.header(header::CONTENT_SECURITY_POLICY),
+        "frame-ancestors https://blog.example"
+    );
+    assert!(response.maybe_header(header::X_FRAME_OPTIONS).is_none());
+    let etag = response.header(header::ETAG);
+
+    // Revalidation keeps the framing policy too
+    let response = server.get(&path).add_header(header::IF_NONE_MATCH, etag).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+    assert!(response.maybe_header(header::X_FRAME_OPTIONS).is_none());
+
+    SharingService::revoke_link(&db, deck.id, user_id, link.id).await.unwrap();
+    let response = server.get(&path).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
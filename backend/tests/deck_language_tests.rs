@@ -0,0 +1,120 @@
+mod common;
+
+use deckoracle_backend::models::{CreateDeckDto, PublicDeckQuery, RegisterDto, UpdateDeckDto};
+use deckoracle_backend::services::{
+    auth::AuthService,
+    deck::DeckService,
+    language::{language_name, tts_voice_locale, DeckLanguages},
+    marketplace::MarketplaceService,
+};
+use deckoracle_backend::utils::PaginationParams;
+
+#[test]
+fn test_language_names_and_voices() {
+    assert_eq!(language_name("pt-br"), "Portuguese");
+    assert_eq!(language_name("tlh"), "the language tagged \"tlh\"");
+
+    assert_eq!(tts_voice_locale("pt-br").as_deref(), Some("pt-BR"));
+    assert_eq!(tts_voice_locale("de").as_deref(), Some("de-DE"));
+    assert_eq!(tts_voice_locale("zh-hant-tw").as_deref(), Some("zh-TW"));
+    assert_eq!(tts_voice_locale("tlh"), None);
+}
+
+#[test]
+fn test_prompt_instructions() {
+    let vocabulary = DeckLanguages {
+        front: Some("es".to_string()),
+        back: Some("en-gb".to_string()),
+    };
+    assert!(vocabulary.card_instruction().contains("fronts are in Spanish and card backs are in English"));
+    assert_eq!(vocabulary.explanation_instruction(), "Write the explanation in English.");
+
+    let french = DeckLanguages {
+        front: Some("fr".to_string()),
+        back: Some("fr-ca".to_string()),
+    };
+    assert_eq!(french.card_instruction(), "Write all card text in French.");
+
+    assert_eq!(DeckLanguages::default().card_instruction(), "");
+    assert_eq!(DeckLanguages::default().explanation_instruction(), "");
+}
+
+#[tokio::test]
+async fn test_deck_languages_are_stored_and_filterable() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "languages@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Spanish verbs".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: Some("ES".to_string()),
+            back_language: Some(" en-GB".to_string()),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(deck.front_language.as_deref(), Some("es"));
+    assert_eq!(deck.back_language.as_deref(), Some("en-gb"));
+
+    let deck = DeckService::update_deck(
+        &state.db,
+        deck.id,
+        user_id,
+        UpdateDeckDto {
+            name: None,
+            description: None,
+            folder_id: None,
+            is_public: Some(true),
+            tags: None,
+            language: None,
+            front_language: Some("es-MX".to_string()),
+            back_language: None,
+            updated_at: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(deck.front_language.as_deref(), Some("es-mx"));
+    assert_eq!(deck.back_language.as_deref(), Some("en-gb"));
+    assert_eq!(
+        DeckLanguages::for_deck(&state.db, deck.id).await.unwrap(),
+        DeckLanguages {
+            front: Some("es-mx".to_string()),
+            back: Some("en-gb".to_string()),
+        }
+    );
+
+    // Either side matches, and a bare language matches its regional tags
+    let params = PaginationParams { page: 1, limit: 100 };
+    for (language, listed) in [("es", true), ("EN-GB", true), ("en", true), ("fr", false), ("e", false)] {
+        let query = PublicDeckQuery {
+            language: Some(language.to_string()),
+            ..Default::default()
+        };
+        let page = MarketplaceService::list_public(&state.db, user_id, &query, &params)
+            .await
+            .unwrap();
+        assert_eq!(page.data.iter().any(|d| d.id == deck.id), listed, "{}", language);
+    }
+
+    let copy = MarketplaceService::duplicate(&state.db, deck.id, user_id).await.unwrap();
+    assert_eq!(copy.front_language.as_deref(), Some("es-mx"));
+    assert_eq!(copy.back_language.as_deref(), Some("en-gb"));
+}
@@ -0,0 +1,261 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::create_app;
+use deckoracle_backend::models::{
+    CreateCardDto, CreateDeckDto, MergeDecksDto, SplitDeckDto, UpdateCardFlagsDto,
+};
+use deckoracle_backend::services::{
+    card::CardService, card_flags::CardFlagsService, deck::DeckService,
+    deck_merge::DeckMergeService,
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// A deck with one card per front, returning the deck and card ids
+async fn deck_with_cards(state: &AppState, user_id: Uuid, name: &str, fronts: &[&str]) -> (Uuid, Vec<Uuid>) {
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: name.to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut cards = Vec::new();
+    for front in fronts {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: "back".to_string(),
+            position: None,
+        };
+        cards.push(CardService::create_card(&state.db, deck.id, user_id, dto).await.unwrap().id);
+    }
+    (deck.id, cards)
+}
+
+async fn fronts(state: &AppState, deck_id: Uuid) -> Vec<String> {
+    sqlx::query_scalar("SELECT front FROM cards WHERE deck_id = $1 AND deleted_at IS NULL ORDER BY position")
+        .bind(deck_id)
+        .fetch_all(&state.db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_merge_moves_cards_and_drops_duplicates() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "merge@example.com").await;
+    let (first, _) = deck_with_cards(&state, user_id, "First", &["Hola", "Adiós"]).await;
+    let (second, second_cards) = deck_with_cards(&state, user_id, "Second", &["hola!", "Gracias"]).await;
+    let (third, _) = deck_with_cards(&state, user_id, "Third", &["gracias", "Por favor"]).await;
+
+    let result = DeckMergeService::merge(
+        &state.db,
+        user_id,
+        MergeDecksDto {
+            deck_ids: vec![second, first, third],
+            target_deck_id: Some(first),
+            deduplicate: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.deck.deck.id, first);
+    assert_eq!((result.moved_cards, result.duplicates_removed), (2, 2));
+    assert_eq!(result.deck.card_count, 4);
+    assert_eq!(result.merged_deck_ids, [second, third]);
+    assert_eq!(fronts(&state, first).await, ["Hola", "Adiós", "Gracias", "Por favor"]);
+
+    // The moved card is the same card, not a copy
+    let moved: Uuid = sqlx::query_scalar("SELECT id FROM cards WHERE deck_id = $1 AND front = 'Gracias'")
+        .bind(first)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(moved, second_cards[1]);
+
+    let listed = DeckService::list_user_decks(&state.db, user_id).await.unwrap();
+    assert_eq!(listed.len(), 1);
+}
+
+#[tokio::test]
+async fn test_merge_requires_owned_distinct_decks() {
+    let state = common::create_test_state().await;
+    let owner = common::register(&state, "merge-owner@example.com").await;
+    let stranger = common::register(&state, "merge-stranger@example.com").await;
+    let (mine, _) = deck_with_cards(&state, owner, "Mine", &["a"]).await;
+    let (theirs, _) = deck_with_cards(&state, stranger, "Theirs", &["b"]).await;
+
+    let same_deck = MergeDecksDto {
+        deck_ids: vec![mine, mine],
+        target_deck_id: None,
+        deduplicate: None,
+    };
+    assert!(matches!(
+        DeckMergeService::merge(&state.db, owner, same_deck).await,
+        Err(AppError::ValidationError(_))
+    ));
+
+    let foreign = MergeDecksDto {
+        deck_ids: vec![mine, theirs],
+        target_deck_id: None,
+        deduplicate: None,
+    };
+    assert!(DeckMergeService::merge(&state.db, owner, foreign).await.is_err());
+    assert_eq!(fronts(&state, theirs).await, ["b"]);
+}
+
+#[tokio::test]
+async fn test_split_moves_matching_cards_with_their_flags() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "split@example.com").await;
+    let (deck_id, cards) =
+        deck_with_cards(&state, user_id, "Spanish", &["correr (verb)", "casa", "comer (verb)", "mesa"]).await;
+    let starred = UpdateCardFlagsDto {
+        starred: Some(true),
+        ..Default::default()
+    };
+    CardFlagsService::update_flags(&state.db, cards[2], user_id, starred).await.unwrap();
+
+    let result = DeckMergeService::split(
+        &state.db,
+        deck_id,
+        user_id,
+        SplitDeckDto {
+            name: "Verbs".to_string(),
+            query: Some("(VERB)".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(result.moved_cards, 2);
+    assert_eq!(result.deck.deck.name, "Verbs");
+    assert_eq!(fronts(&state, result.deck.deck.id).await, ["correr (verb)", "comer (verb)"]);
+    assert_eq!(fronts(&state, deck_id).await, ["casa", "mesa"]);
+    let flags = CardFlagsService::get_flags(&state.db, cards[2], user_id).await.unwrap();
+    assert!(flags.starred);
+
+    let nothing = SplitDeckDto {
+        name: "Empty".to_string(),
+        query: Some("zzz".to_string()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        DeckMergeService::split(&state.db, deck_id, user_id, nothing).await,
+        Err(AppError::BadRequest(_))
+    ));
+    assert_eq!(DeckService::list_user_decks(&state.db, user_id).await.unwrap().len(), 2);
+
+    let unfiltered = SplitDeckDto {
+        name: "All".to_string(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        DeckMergeService::split(&state.db, deck_id, user_id, unfiltered).await,
+        Err(AppError::ValidationError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_split_moves_cards_by_the_callers_tags() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "split-tags@example.com").await;
+    let (deck_id, cards) = deck_with_cards(&state, user_id, "Spanish", &["correr", "casa", "comer", "mesa"]).await;
+    for (card_id, tags) in [(cards[0], vec![" Verbs ", "verbs"]), (cards[2], vec!["irregular"]), (cards[3], vec!["nouns"])] {
+        let dto = UpdateCardFlagsDto {
+            tags: Some(tags.into_iter().map(String::from).collect()),
+            ..Default::default()
+        };
+        CardFlagsService::update_flags(&state.db, card_id, user_id, dto).await.unwrap();
+    }
+    let flags = CardFlagsService::get_flags(&state.db, cards[0], user_id).await.unwrap();
+    assert_eq!(flags.tags, ["verbs"]);
+
+    let by_tag = SplitDeckDto {
+        name: "Verbs".to_string(),
+        tags: Some(vec!["VERBS".to_string(), "irregular".to_string()]),
+        ..Default::default()
+    };
+    let result = DeckMergeService::split(&state.db, deck_id, user_id, by_tag).await.unwrap();
+    assert_eq!(result.moved_cards, 2);
+    assert_eq!(fronts(&state, result.deck.deck.id).await, ["correr", "comer"]);
+    assert_eq!(fronts(&state, deck_id).await, ["casa", "mesa"]);
+
+    let blank = SplitDeckDto {
+        name: "Blank".to_string(),
+        tags: Some(vec!["  ".to_string()]),
+        ..Default::default()
+    };
+    assert!(matches!(
+        DeckMergeService::split(&state.db, deck_id, user_id, blank).await,
+        Err(AppError::ValidationError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_split_query_matches_pattern_characters_literally() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "split-like@example.com").await;
+    let (deck_id, _) =
+        deck_with_cards(&state, user_id, "Maths", &["50% of 10", "a_b", "ab", "back\\slash", "plain"]).await;
+    let split = |name: &str, query: &str| SplitDeckDto {
+        name: name.to_string(),
+        query: Some(query.to_string()),
+        ..Default::default()
+    };
+
+    // A bare `%` would otherwise match every card
+    let result = DeckMergeService::split(&state.db, deck_id, user_id, split("Percent", "%")).await.unwrap();
+    assert_eq!(fronts(&state, result.deck.deck.id).await, ["50% of 10"]);
+
+    let result = DeckMergeService::split(&state.db, deck_id, user_id, split("Underscore", "_")).await.unwrap();
+    assert_eq!(fronts(&state, result.deck.deck.id).await, ["a_b"]);
+
+    let result = DeckMergeService::split(&state.db, deck_id, user_id, split("Backslash", "\\")).await.unwrap();
+    assert_eq!(fronts(&state, result.deck.deck.id).await, ["back\\slash"]);
+
+    assert_eq!(fronts(&state, deck_id).await, ["ab", "plain"]);
+}
+
+#[tokio::test]
+async fn test_unverified_accounts_cannot_merge_or_split() {
+    let mut config = common::test_config();
+    config.account.require_email_verification = true;
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+    let (user_id, token) = common::register_with_token(&state, "split-unverified@example.com").await;
+    let (first, _) = deck_with_cards(&state, user_id, "First", &["a"]).await;
+    let (second, _) = deck_with_cards(&state, user_id, "Second", &["b"]).await;
+    let auth: HeaderValue = token.parse().unwrap();
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+
+    let response = server
+        .post(&format!("/api/v1/decks/{}/split", first))
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "name": "Split", "query": "a" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(response.json::<Value>()["code"], "EMAIL_NOT_VERIFIED");
+
+    let response = server
+        .post("/api/v1/decks/merge")
+        .add_header(header::AUTHORIZATION, auth)
+        .json(&json!({ "deck_ids": [first, second] }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(response.json::<Value>()["code"], "EMAIL_NOT_VERIFIED");
+
+    assert_eq!(DeckService::list_user_decks(&state.db, user_id).await.unwrap().len(), 2);
+}
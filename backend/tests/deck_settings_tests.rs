@@ -0,0 +1,102 @@
+mod common;
+
+use deckoracle_backend::models::{
+    CreateDeckDto, LapseAction, LeitnerDemotion, RegisterDto, SchedulingAlgorithm, UpdateDeckSettingsDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService, deck::DeckService, deck_settings::DeckSettingsService, study_queue::take_per_deck,
+};
+use deckoracle_backend::utils::AppError;
+use uuid::Uuid;
+
+#[test]
+fn test_take_per_deck_applies_each_decks_limit() {
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    let cards = vec![(a, 1), (b, 1), (a, 2), (b, 2), (a, 3), (b, 3)];
+
+    let taken = take_per_deck(cards, |card| card.0, |deck| if deck == a { 2 } else { 0 });
+
+    assert_eq!(taken, vec![(a, 1), (a, 2)]);
+}
+
+#[tokio::test]
+async fn test_settings_default_until_changed_by_the_owner() {
+    let state = common::create_test_state().await;
+    let register = |email: &str| RegisterDto {
+        email: email.to_string(),
+        password: "Password123".to_string(),
+        display_name: None,
+    };
+    let owner = AuthService::register(&state.db, &state.config, register("owner@example.com"))
+        .await
+        .unwrap()
+        .user
+        .id;
+    let stranger = AuthService::register(&state.db, &state.config, register("other@example.com"))
+        .await
+        .unwrap()
+        .user
+        .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        owner,
+        CreateDeckDto {
+            name: "Spanish".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let settings = DeckSettingsService::get_settings(&state.db, deck.id, owner).await.unwrap();
+    assert_eq!(settings.new_cards_per_day, 20);
+    assert_eq!(settings.algorithm, SchedulingAlgorithm::Sm2);
+
+    let updated = DeckSettingsService::update_settings(
+        &state.db,
+        deck.id,
+        owner,
+        UpdateDeckSettingsDto {
+            new_cards_per_day: Some(5),
+            lapse_action: Some(LapseAction::Reset),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.new_cards_per_day, 5);
+    assert_eq!(updated.max_reviews_per_day, 200);
+    assert_eq!(updated.lapse_action, LapseAction::Reset);
+
+    // Later changes keep earlier ones
+    let updated = DeckSettingsService::update_settings(
+        &state.db,
+        deck.id,
+        owner,
+        UpdateDeckSettingsDto {
+            algorithm: Some(SchedulingAlgorithm::Leitner),
+            leitner_boxes: Some(7),
+            leitner_demotion: Some(LeitnerDemotion::PreviousBox),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.new_cards_per_day, 5);
+    assert_eq!(updated.algorithm, SchedulingAlgorithm::Leitner);
+    assert_eq!(updated.leitner_boxes, 7);
+    assert_eq!(updated.leitner_demotion, LeitnerDemotion::PreviousBox);
+
+    let denied = DeckSettingsService::update_settings(
+        &state.db,
+        deck.id,
+        stranger,
+        UpdateDeckSettingsDto::default(),
+    )
+    .await;
+    assert!(matches!(denied, Err(AppError::Forbidden)));
+}
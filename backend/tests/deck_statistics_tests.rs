@@ -0,0 +1,133 @@
+mod common;
+
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto, CreateStudySessionDto, Rating, RegisterDto};
+use deckoracle_backend::services::{
+    auth::AuthService,
+    card::CardService,
+    deck::DeckService,
+    stats::{ease_distribution, interval_histogram, StatsService},
+    study::StudyService,
+};
+use deckoracle_backend::utils::AppError;
+
+#[test]
+fn test_ease_and_interval_buckets() {
+    let eases = ease_distribution(&[1.3, 1.5, 1.69, 2.5, 2.5, 3.4]);
+    assert_eq!(eases.len(), 9);
+    assert_eq!((eases[0].min, eases[0].max, eases[0].cards), (1.3, Some(1.5), 1));
+    assert_eq!(eases[1].cards, 2);
+    assert_eq!(eases[6].cards, 2);
+    assert_eq!((eases[8].min, eases[8].max, eases[8].cards), (2.9, None, 1));
+
+    let intervals = interval_histogram(&[0, 1, 3, 4, 30, 31, 400]);
+    let counts: Vec<i64> = intervals.iter().map(|b| b.cards).collect();
+    assert_eq!(counts, [1, 1, 1, 1, 0, 1, 1, 0, 1]);
+    assert_eq!((intervals[2].min_days, intervals[2].max_days), (2, Some(3)));
+    assert_eq!((intervals[8].min_days, intervals[8].max_days), (181, None));
+}
+
+#[tokio::test]
+async fn test_deck_statistics_from_answers() {
+    let state = common::create_test_state().await;
+    let register = |email: &str| RegisterDto {
+        email: email.to_string(),
+        password: "Password123".to_string(),
+        display_name: None,
+    };
+    let user_id = AuthService::register(&state.db, &state.config, register("deckstats@example.com"))
+        .await
+        .unwrap()
+        .user
+        .id;
+    let stranger = AuthService::register(&state.db, &state.config, register("stranger@example.com"))
+        .await
+        .unwrap()
+        .user
+        .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut cards = Vec::new();
+    for (front, back) in [("France", "Paris"), ("Spain", "Madrid"), ("Italy", "Rome")] {
+        let card = CardService::create_card(
+            &state.db,
+            deck.id,
+            user_id,
+            CreateCardDto {
+                front: front.to_string(),
+                back: back.to_string(),
+                position: None,
+            },
+        )
+        .await
+        .unwrap();
+        cards.push(card);
+    }
+
+    let empty = StatsService::deck_statistics(&state.db, deck.id, user_id).await.unwrap();
+    assert_eq!((empty.total_cards, empty.new_cards, empty.total_reviews), (3, 3, 0));
+    assert_eq!(empty.true_retention, None);
+    assert_eq!(empty.average_ease, None);
+    assert!(empty.interval_histogram.iter().all(|b| b.cards == 0));
+
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck.id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+    for (card, rating, time_ms) in [
+        (&cards[0], Rating::Good, 4000),
+        (&cards[0], Rating::Again, 6000),
+        (&cards[1], Rating::Good, 2000),
+    ] {
+        StudyService::record_answer(&state.db, &session, card.id, rating, Some(time_ms))
+            .await
+            .unwrap();
+    }
+
+    // Pretend the first card's answers were mature reviews
+    sqlx::query("UPDATE card_progress SET interval_days_before = 30 WHERE card_id = $1")
+        .bind(cards[0].id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE user_card_stats SET interval_days = 40 WHERE card_id = $1")
+        .bind(cards[0].id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    let statistics = StatsService::deck_statistics(&state.db, deck.id, user_id).await.unwrap();
+    assert_eq!(statistics.total_cards, 3);
+    assert_eq!(statistics.new_cards, 1);
+    assert_eq!((statistics.young_cards, statistics.mature_cards), (1, 1));
+    assert_eq!((statistics.total_reviews, statistics.mature_reviews), (3, 2));
+    assert_eq!(statistics.true_retention, Some(0.5));
+    assert_eq!(statistics.average_time_per_card_ms, Some(4000));
+    assert_eq!(statistics.ease_distribution.iter().map(|b| b.cards).sum::<i64>(), 2);
+    assert_eq!(statistics.interval_histogram.last().unwrap().cards, 0);
+    assert_eq!(statistics.interval_histogram[6].cards, 1);
+
+    let forbidden = StatsService::deck_statistics(&state.db, deck.id, stranger).await;
+    assert!(matches!(forbidden, Err(AppError::Forbidden | AppError::DeckNotFound)));
+}
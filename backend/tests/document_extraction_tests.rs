@@ -0,0 +1,63 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::multipart::{MultipartForm, Part};
+use axum_test::TestServer;
+use deckoracle_backend::create_app;
+use serde_json::Value;
+
+const NOTES: &str = "# Photosynthesis\n\nPlants turn light into chemical energy.\n\n## Inputs\n\nWater and carbon dioxide.\n";
+
+fn upload(bytes: &[u8], filename: &str, content_type: &str) -> MultipartForm {
+    let part = Part::bytes(bytes.to_vec()).file_name(filename).mime_type(content_type);
+    MultipartForm::new().add_part("file", part)
+}
+
+#[tokio::test]
+async fn test_extract_returns_document_sections() {
+    let state = common::create_test_state().await;
+    let (_, token) = common::register_with_token(&state, "reader@example.com").await;
+    let token: HeaderValue = token.parse().unwrap();
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server
+        .post("/api/v1/ai/extract")
+        .add_header(header::AUTHORIZATION, token.clone())
+        .multipart(upload(NOTES.as_bytes(), "notes.md", "text/markdown"))
+        .await;
+    response.assert_status_ok();
+    let document: Value = response.json();
+    assert_eq!(document["kind"], "txt");
+    assert_eq!(document["filename"], "notes.md");
+    assert_eq!(document["title"], "Photosynthesis");
+    let headings: Vec<&str> = document["sections"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|section| section["heading"].as_str().unwrap())
+        .collect();
+    assert_eq!(headings, vec!["Photosynthesis", "Inputs"]);
+    assert_eq!(document["sections"][1]["level"], 2);
+
+    // Legacy Word files are named rather than misread
+    let legacy = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1, 0x00, 0x00];
+    let response = server
+        .post("/api/v1/ai/extract")
+        .add_header(header::AUTHORIZATION, token)
+        .multipart(upload(&legacy, "notes.doc", "application/msword"))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    assert!(response.text().contains(".docx"), "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_extract_requires_sign_in() {
+    let state = common::create_test_state().await;
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server
+        .post("/api/v1/ai/extract")
+        .multipart(upload(NOTES.as_bytes(), "notes.md", "text/markdown"))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,83 @@
+use deckoracle_backend::{
+    models::import_export::{DuplicateStrategy, ImportCardActions},
+    services::duplicates::{DuplicateDetector, MatchKind},
+};
+use uuid::Uuid;
+
+fn detector(fronts: &[&str]) -> (DuplicateDetector, Vec<Uuid>) {
+    let mut detector = DuplicateDetector::new();
+    let ids: Vec<Uuid> = fronts.iter().map(|_| Uuid::new_v4()).collect();
+    for (id, front) in ids.iter().zip(fronts) {
+        detector.add(*id, front);
+    }
+    (detector, ids)
+}
+
+#[test]
+fn test_exact_and_normalized_matches() {
+    let (detector, ids) = detector(&["What is the capital of France?"]);
+    assert_eq!(
+        detector.find("What is the capital of France?"),
+        Some((ids[0], MatchKind::Exact))
+    );
+    assert_eq!(
+        detector.find("  what is the capital of  france "),
+        Some((ids[0], MatchKind::Normalized))
+    );
+}
+
+#[test]
+fn test_fuzzy_match_on_small_typos() {
+    let (detector, ids) = detector(&["What is the capital of France?"]);
+    assert_eq!(
+        detector.find("What is the capitol of France"),
+        Some((ids[0], MatchKind::Fuzzy))
+    );
+}
+
+#[test]
+fn test_short_fronts_need_to_be_close() {
+    let (detector, _) = detector(&["cat", "perro"]);
+    assert_eq!(detector.find("car"), None);
+    assert_eq!(detector.find("perros"), None);
+}
+
+#[test]
+fn test_punctuation_only_fronts_match_exactly_only() {
+    let (detector, ids) = detector(&["???"]);
+    assert_eq!(detector.find("???"), Some((ids[0], MatchKind::Exact)));
+    assert_eq!(detector.find("!!!"), None);
+}
+
+#[test]
+fn test_first_card_wins() {
+    let (detector, ids) = detector(&["Hola", "hola"]);
+    assert_eq!(detector.find("HOLA"), Some((ids[0], MatchKind::Normalized)));
+}
+
+#[test]
+fn test_duplicate_strategy_names() {
+    assert_eq!(DuplicateStrategy::from_name("skip"), Some(DuplicateStrategy::Skip));
+    assert_eq!(DuplicateStrategy::from_name("overwrite"), Some(DuplicateStrategy::Overwrite));
+    assert_eq!(DuplicateStrategy::from_name("keep_both"), Some(DuplicateStrategy::KeepBoth));
+    assert_eq!(DuplicateStrategy::from_name("merge"), None);
+    assert_eq!(DuplicateStrategy::default(), DuplicateStrategy::KeepBoth);
+}
+
+#[test]
+fn test_card_actions_totals() {
+    let mut actions = ImportCardActions {
+        created: 3,
+        skipped: 2,
+        overwritten: 1,
+        kept_both: 0,
+    };
+    actions.merge(ImportCardActions {
+        created: 1,
+        skipped: 0,
+        overwritten: 0,
+        kept_both: 4,
+    });
+    assert_eq!(actions.imported(), 9);
+    assert_eq!(actions.skipped, 2);
+}
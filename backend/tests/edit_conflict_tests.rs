@@ -0,0 +1,123 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::{CreateCardDto, CreateDeckDto, UpdateCardDto},
+    services::{card::CardService, deck::DeckService},
+    state::AppState,
+    utils::AppError,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn create_deck(state: &AppState, user_id: Uuid) -> Uuid {
+    DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Rivers".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+fn edit(front: &str) -> UpdateCardDto {
+    UpdateCardDto {
+        front: Some(front.to_string()),
+        back: None,
+        position: None,
+        updated_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_stale_card_update_returns_the_current_card() {
+    let state = AppState::from_parts(common::setup_test_db().await, common::test_config());
+    let (user_id, _) = common::register_with_token(&state, "editor@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+    let dto = CreateCardDto {
+        front: "Nile".to_string(),
+        back: "Africa".to_string(),
+        position: None,
+    };
+    let card = CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap();
+
+    // Two editors load the card; the first to save wins
+    let first = UpdateCardDto {
+        updated_at: Some(card.updated_at),
+        ..edit("The Nile")
+    };
+    let saved = CardService::update_card(&state.db, card.id, user_id, first).await.unwrap();
+    assert_ne!(saved.updated_at, card.updated_at);
+
+    let second = UpdateCardDto {
+        updated_at: Some(card.updated_at),
+        ..edit("Nile river")
+    };
+    let error = CardService::update_card(&state.db, card.id, user_id, second).await.unwrap_err();
+    let AppError::EditConflict { current, .. } = error else {
+        panic!("expected an edit conflict, got {:?}", error);
+    };
+    assert_eq!(current["front"], "The Nile");
+
+    let stored = CardService::get_card(&state.db, card.id, user_id).await.unwrap();
+    assert_eq!(stored.front, "The Nile");
+
+    // Without a precondition the last write still wins
+    let saved = CardService::update_card(&state.db, card.id, user_id, edit("Nile river")).await.unwrap();
+    assert_eq!(saved.front, "Nile river");
+}
+
+#[tokio::test]
+async fn test_deck_updates_honour_if_match() {
+    let state = AppState::from_parts(common::setup_test_db().await, common::test_config());
+    let (user_id, token) = common::register_with_token(&state, "owner@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+    let server = TestServer::new(create_app(state)).unwrap();
+    let path = format!("/api/v1/decks/{}", deck_id);
+    let authorization: HeaderValue = token.parse().unwrap();
+
+    let response = server.get(&path).add_header(header::AUTHORIZATION, authorization.clone()).await;
+    response.assert_status_ok();
+    let etag = response.headers().get(header::ETAG).unwrap().clone();
+
+    let response = server
+        .patch(&path)
+        .add_header(header::AUTHORIZATION, authorization.clone())
+        .add_header(header::IF_MATCH, etag.clone())
+        .json(&json!({ "name": "Great Rivers" }))
+        .await;
+    response.assert_status_ok();
+    let new_etag = response.headers().get(header::ETAG).unwrap().clone();
+    assert_ne!(new_etag, etag);
+
+    // The old ETag no longer matches
+    let response = server
+        .patch(&path)
+        .add_header(header::AUTHORIZATION, authorization.clone())
+        .add_header(header::IF_MATCH, etag)
+        .json(&json!({ "name": "Rivers of the World" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::CONFLICT);
+    assert_eq!(response.headers().get(header::ETAG), Some(&new_etag));
+    let body: Value = response.json();
+    assert_eq!(body["code"], "EDIT_CONFLICT");
+    assert_eq!(body["current"]["name"], "Great Rivers");
+
+    // So does an outdated `updated_at` in the body
+    let response = server
+        .patch(&path)
+        .add_header(header::AUTHORIZATION, authorization)
+        .json(&json!({ "name": "Rivers of the World", "updated_at": "2020-01-01T00:00:00Z" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::CONFLICT);
+}
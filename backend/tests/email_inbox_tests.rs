@@ -0,0 +1,117 @@
+mod common;
+
+use chrono::Utc;
+use deckoracle_backend::config::InboundEmailConfig;
+use deckoracle_backend::models::InboundEmail;
+use deckoracle_backend::services::email_inbox::{EmailInboxService, InboundOutcome};
+use deckoracle_backend::utils::AppError;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+fn inbound_config() -> InboundEmailConfig {
+    InboundEmailConfig {
+        domain: Some("in.example.com".to_string()),
+        signing_key: Some("signing-key".to_string()),
+    }
+}
+
+/// A message signed the way the provider signs it, `age_seconds` ago
+fn signed_email(recipient: &str, sender: &str, message_id: &str, age_seconds: i64) -> InboundEmail {
+    let timestamp = (Utc::now().timestamp() - age_seconds).to_string();
+    let token = Uuid::new_v4().simple().to_string();
+    let mut mac = Hmac::<Sha256>::new_from_slice(b"signing-key").unwrap();
+    mac.update(timestamp.as_bytes());
+    mac.update(token.as_bytes());
+
+    InboundEmail {
+        recipient: recipient.to_string(),
+        sender: sender.to_string(),
+        from: Some(format!("Sender <{}>", sender)),
+        subject: Some("Fwd: Capital of Peru".to_string()),
+        body_plain: Some("Lima".to_string()),
+        stripped_text: None,
+        message_id: Some(message_id.to_string()),
+        timestamp,
+        token,
+        signature: hex::encode(mac.finalize().into_bytes()),
+    }
+}
+
+#[test]
+fn test_signature_must_match_and_be_recent() {
+    let config = inbound_config();
+    let email = signed_email("x@in.example.com", "a@example.com", "<1@mail>", 0);
+    EmailInboxService::verify_signature(&config, &email).unwrap();
+
+    let tampered = InboundEmail {
+        token: "other-token".to_string(),
+        ..email.clone()
+    };
+    let error = EmailInboxService::verify_signature(&config, &tampered).unwrap_err();
+    assert!(matches!(error, AppError::Unauthorized));
+
+    // Correctly signed, but captured too long ago
+    let stale = signed_email("x@in.example.com", "a@example.com", "<1@mail>", 600);
+    let error = EmailInboxService::verify_signature(&config, &stale).unwrap_err();
+    assert!(matches!(error, AppError::Unauthorized));
+
+    let future = signed_email("x@in.example.com", "a@example.com", "<1@mail>", -600);
+    assert!(EmailInboxService::verify_signature(&config, &future).is_err());
+}
+
+#[tokio::test]
+async fn test_only_the_account_holder_can_create_cards() {
+    let state = common::create_test_state().await;
+    let config = inbound_config();
+    let user_id = common::register(&state, "inbox@example.com").await;
+    let address = EmailInboxService::get_address(&state.db, &config, user_id).await.unwrap();
+
+    let email = signed_email(&address.address, "someone@example.com", "<spoof@mail>", 0);
+    let outcome = EmailInboxService::receive(&state.db, &config, &email).await.unwrap();
+    assert!(matches!(outcome, InboundOutcome::Rejected("sender does not match the account email")));
+
+    // The address is matched without regard to case or display name
+    let email = signed_email(&address.address, "Inbox@Example.com", "<ok@mail>", 0);
+    let outcome = EmailInboxService::receive(&state.db, &config, &email).await.unwrap();
+    let InboundOutcome::Created(card) = outcome else {
+        panic!("expected a card, got {:?}", outcome);
+    };
+    assert_eq!(card.front, "Capital of Peru");
+    assert_eq!(card.back, "Lima");
+}
+
+#[tokio::test]
+async fn test_duplicate_and_replayed_messages_create_one_card() {
+    let state = common::create_test_state().await;
+    let config = inbound_config();
+    let user_id = common::register(&state, "replay@example.com").await;
+    let address = EmailInboxService::get_address(&state.db, &config, user_id).await.unwrap();
+
+    let email = signed_email(&address.address, "replay@example.com", "<once@mail>", 0);
+    let outcome = EmailInboxService::receive(&state.db, &config, &email).await.unwrap();
+    assert!(matches!(outcome, InboundOutcome::Created(_)));
+
+    // A provider retry signs again but keeps the Message-Id
+    let retry = signed_email(&address.address, "replay@example.com", "<once@mail>", 0);
+    let outcome = EmailInboxService::receive(&state.db, &config, &retry).await.unwrap();
+    assert!(matches!(outcome, InboundOutcome::Duplicate));
+
+    // A captured request replayed with a different message is not processed
+    let replay = InboundEmail {
+        message_id: Some("<forged@mail>".to_string()),
+        subject: Some("Forged".to_string()),
+        ..email
+    };
+    let outcome = EmailInboxService::receive(&state.db, &config, &replay).await.unwrap();
+    assert!(matches!(outcome, InboundOutcome::Duplicate));
+
+    let cards: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM cards c JOIN decks d ON d.id = c.deck_id WHERE d.owner_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    assert_eq!(cards, 1);
+}
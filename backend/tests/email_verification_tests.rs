@@ -0,0 +1,82 @@
+mod common;
+
+use axum::{http::header, routing::post, Router};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    middleware::auth::VerifiedUser,
+    services::user::UserService,
+    state::AppState,
+    utils::AppError,
+};
+
+async fn state(require_email_verification: bool) -> AppState {
+    let mut config = common::test_config();
+    config.account.require_email_verification = require_email_verification;
+    AppState::from_parts(common::setup_test_db().await, config)
+}
+
+fn server(state: AppState) -> TestServer {
+    let app = Router::new()
+        .route("/write", post(|VerifiedUser(user_id): VerifiedUser| async move { user_id.to_string() }))
+        .with_state(state);
+    TestServer::new(app).unwrap()
+}
+
+#[tokio::test]
+async fn test_unverified_accounts_cannot_write_when_required() {
+    let state = state(true).await;
+    let (user_id, token) = common::register_with_token(&state, "new@example.com").await;
+    let server = server(state.clone());
+
+    let response = server
+        .post("/write")
+        .add_header(header::AUTHORIZATION, token.parse().unwrap())
+        .await;
+    response.assert_status_forbidden();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "EMAIL_NOT_VERIFIED");
+
+    sqlx::query("UPDATE users SET email_verified = true WHERE id = $1")
+        .bind(user_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    let response = server
+        .post("/write")
+        .add_header(header::AUTHORIZATION, token.parse().unwrap())
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.text(), user_id.to_string());
+}
+
+#[tokio::test]
+async fn test_unverified_accounts_can_write_by_default() {
+    let state = state(false).await;
+    let (_, token) = common::register_with_token(&state, "new@example.com").await;
+
+    server(state)
+        .post("/write")
+        .add_header(header::AUTHORIZATION, token.parse().unwrap())
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_verification_can_only_be_requested_while_unverified() {
+    let state = state(true).await;
+    let (user_id, _) = common::register_with_token(&state, "new@example.com").await;
+
+    UserService::request_verification(&state.db, user_id).await.unwrap();
+    // Confirming the current address is not an email change
+    let profile = UserService::get_profile(&state.db, user_id).await.unwrap();
+    assert!(profile.pending_email.is_none());
+
+    sqlx::query("UPDATE users SET email_verified = true WHERE id = $1")
+        .bind(user_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    let again = UserService::request_verification(&state.db, user_id).await;
+    assert!(matches!(again, Err(AppError::BadRequest(_))));
+}
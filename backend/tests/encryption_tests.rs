@@ -0,0 +1,248 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::{TestResponse, TestServer};
+use deckoracle_backend::{
+    create_app,
+    models::{CreateCardDto, CreateDeckDto, CreateShareLinkDto, DeckRole, ShareDeckDto},
+    services::{card::CardService, deck::DeckService, sharing::SharingService},
+    state::AppState,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+struct Vault {
+    server: TestServer,
+    owner_id: Uuid,
+    authorization: HeaderValue,
+    deck_id: Uuid,
+    card_id: Uuid,
+}
+
+/// A signed-in owner with a one-card deck, served with AI features on
+async fn vault(email: &str) -> (AppState, Vault) {
+    let mut config = common::test_config();
+    config.ai.enabled = true;
+    config.account.require_email_verification = false;
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+
+    let (user_id, token) = common::register_with_token(&state, email).await;
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Diary".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let dto = CreateCardDto {
+        front: "secret front".to_string(),
+        back: "secret back".to_string(),
+        position: None,
+    };
+    let card_id = CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap().id;
+
+    let vault = Vault {
+        server: TestServer::new(create_app(state.clone())).unwrap(),
+        owner_id: user_id,
+        authorization: token.parse().unwrap(),
+        deck_id,
+        card_id,
+    };
+    (state, vault)
+}
+
+fn encryption_body(card_id: Uuid, key_check: &str) -> Value {
+    json!({
+        "algorithm": "aes-256-gcm",
+        "kdf": "pbkdf2-sha256",
+        "kdf_salt": "c2FsdHNhbHRzYWx0",
+        "kdf_params": { "iterations": 600000 },
+        "key_check": key_check,
+        "cards": [{ "card_id": card_id, "front": "Y2lwaGVy", "back": "dGV4dA==" }]
+    })
+}
+
+impl Vault {
+    async fn encrypt(&self, key_check: &str) -> TestResponse {
+        self.server
+            .put(&format!("/api/v1/decks/{}/encryption", self.deck_id))
+            .add_header(header::AUTHORIZATION, self.authorization.clone())
+            .json(&encryption_body(self.card_id, key_check))
+            .await
+    }
+
+    async fn decrypt(&self) -> TestResponse {
+        self.server
+            .post(&format!("/api/v1/decks/{}/encryption/disable", self.deck_id))
+            .add_header(header::AUTHORIZATION, self.authorization.clone())
+            .json(&json!({ "cards": [{ "card_id": self.card_id, "front": "front", "back": "back" }] }))
+            .await
+    }
+
+    async fn get(&self, url: &str, authorization: &HeaderValue) -> TestResponse {
+        self.server.get(url).add_header(header::AUTHORIZATION, authorization.clone()).await
+    }
+}
+
+fn assert_refused(response: &TestResponse) {
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    assert!(response.text().contains("ncrypted"), "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_encryption_metadata_round_trips() {
+    let (state, vault) = vault("vault@example.com").await;
+    let url = format!("/api/v1/decks/{}/encryption", vault.deck_id);
+    let owner = vault.authorization.clone();
+
+    assert_eq!(vault.get(&url, &owner).await.status_code(), StatusCode::NOT_FOUND);
+
+    vault.encrypt("check-1").await.assert_status_ok();
+    let response = vault.get(&url, &owner).await;
+    response.assert_status_ok();
+    let metadata: Value = response.json();
+    assert_eq!(metadata["deck_id"], json!(vault.deck_id));
+    assert_eq!(metadata["algorithm"], "aes-256-gcm");
+    assert_eq!(metadata["kdf"], "pbkdf2-sha256");
+    assert_eq!(metadata["kdf_salt"], "c2FsdHNhbHRzYWx0");
+    assert_eq!(metadata["kdf_params"]["iterations"], 600000);
+    assert_eq!(metadata["key_check"], "check-1");
+
+    // Rotating the key replaces the metadata
+    vault.encrypt("check-2").await.assert_status_ok();
+    assert_eq!(vault.get(&url, &owner).await.json::<Value>()["key_check"], "check-2");
+
+    // Members need the metadata to derive the key; strangers get nothing
+    let (_, member) = common::register_with_token(&state, "member@example.com").await;
+    let (_, stranger) = common::register_with_token(&state, "stranger@example.com").await;
+    let member: HeaderValue = member.parse().unwrap();
+    let stranger: HeaderValue = stranger.parse().unwrap();
+    let dto = ShareDeckDto {
+        email: "member@example.com".to_string(),
+        role: DeckRole::Viewer,
+    };
+    SharingService::share_with_user(&state.db, vault.deck_id, vault.owner_id, dto).await.unwrap();
+
+    assert_eq!(vault.get(&url, &member).await.json::<Value>()["key_check"], "check-2");
+    assert_eq!(vault.get(&url, &stranger).await.status_code(), StatusCode::NOT_FOUND);
+
+    // Only the owner turns encryption on or off
+    let response = vault
+        .server
+        .post(&format!("{}/disable", url))
+        .add_header(header::AUTHORIZATION, member.clone())
+        .json(&json!({ "cards": [] }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    let response = vault
+        .server
+        .put(&url)
+        .add_header(header::AUTHORIZATION, member)
+        .json(&encryption_body(vault.card_id, "check-3"))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    assert_eq!(vault.decrypt().await.status_code(), StatusCode::NO_CONTENT);
+    assert_eq!(vault.get(&url, &owner).await.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_encrypted_decks_are_kept_away_from_search_and_ai() {
+    let (_state, vault) = vault("private@example.com").await;
+    let auth = vault.authorization.clone();
+    let search = |path: &'static str, query: &'static str| {
+        vault
+            .server
+            .get(path)
+            .add_query_param("q", query)
+            .add_header(header::AUTHORIZATION, auth.clone())
+    };
+
+    let response = search("/api/v1/search/cards", "secret").await;
+    assert_eq!(response.json::<Value>()["data"].as_array().unwrap().len(), 1);
+
+    vault.encrypt("check").await.assert_status_ok();
+
+    // Ciphertext is never matched, even where it happens to contain the term
+    for query in ["secret", "Y2lwaGVy"] {
+        let response = search("/api/v1/search/cards", query).await;
+        response.assert_status_ok();
+        assert!(response.json::<Value>()["data"].as_array().unwrap().is_empty());
+        let response = search("/api/v1/search", query).await;
+        assert!(response.json::<Value>()["cards"].as_array().unwrap().is_empty());
+    }
+
+    // Refused before anything is sent to the AI provider
+    let response = vault
+        .server
+        .post("/api/v1/ai/generate-cards")
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({
+            "deck_id": vault.deck_id,
+            "content_type": "text",
+            "content": "Photosynthesis turns light into chemical energy",
+            "options": {}
+        }))
+        .await;
+    assert_refused(&response);
+
+    let response = vault
+        .server
+        .post(&format!("/api/v1/decks/{}/ai/quiz", vault.deck_id))
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "question_count": 5 }))
+        .await;
+    assert_refused(&response);
+}
+
+#[tokio::test]
+async fn test_encrypted_decks_cannot_be_published_or_shared_by_link() {
+    let (state, vault) = vault("unpublished@example.com").await;
+    let auth = vault.authorization.clone();
+    let dto = CreateShareLinkDto {
+        role: None,
+        expires_in_hours: None,
+    };
+    let link = SharingService::create_link(&state.db, vault.deck_id, vault.owner_id, dto).await.unwrap();
+    let embed_url = format!("/api/v1/public/decks/{}", link.token);
+    vault.server.get(&embed_url).await.assert_status_ok();
+
+    vault.encrypt("check").await.assert_status_ok();
+
+    let response = vault
+        .server
+        .patch(&format!("/api/v1/decks/{}", vault.deck_id))
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "is_public": true }))
+        .await;
+    assert_refused(&response);
+
+    let response = vault
+        .server
+        .post(&format!("/api/v1/decks/{}/duplicate", vault.deck_id))
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .await;
+    assert_refused(&response);
+
+    // Links handed out before the deck was encrypted stop serving it
+    assert_refused(&vault.server.get(&embed_url).await);
+
+    // Public decks have to be made private before they can be encrypted
+    assert_eq!(vault.decrypt().await.status_code(), StatusCode::NO_CONTENT);
+    vault
+        .server
+        .patch(&format!("/api/v1/decks/{}", vault.deck_id))
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "is_public": true }))
+        .await
+        .assert_status_ok();
+    assert_eq!(vault.encrypt("check").await.status_code(), StatusCode::BAD_REQUEST);
+}
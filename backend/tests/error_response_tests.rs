@@ -0,0 +1,111 @@
+use axum::{http::StatusCode, response::IntoResponse};
+use deckoracle_backend::models::{CreateCardDto, RegisterDto};
+use deckoracle_backend::utils::{error::field_errors, AppError, ErrorCode};
+use serde_json::{json, Value};
+use validator::Validate;
+
+async fn render(error: AppError) -> (StatusCode, Value) {
+    let response = error.into_response();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn register_dto(email: &str, password: &str) -> RegisterDto {
+    RegisterDto {
+        email: email.to_string(),
+        password: password.to_string(),
+        display_name: None,
+    }
+}
+
+#[tokio::test]
+async fn test_not_found_errors_name_the_resource() {
+    let (status, body) = render(AppError::DeckNotFound).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body, json!({ "error": "Deck not found", "status": 404, "code": "DECK_NOT_FOUND" }));
+}
+
+#[tokio::test]
+async fn test_every_error_has_a_code() {
+    let (_, body) = render(AppError::BadRequest("Unsupported export format: xyz".to_string())).await;
+    assert_eq!(body["code"], "BAD_REQUEST");
+
+    let (status, body) = render(AppError::Database(sqlx::Error::RowNotFound)).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["code"], "INTERNAL_ERROR");
+    assert_eq!(body["error"], "Database error occurred");
+
+    let (_, body) = render(AppError::RefreshTokenReused).await;
+    assert_eq!(body["code"], "REFRESH_TOKEN_REUSED");
+
+    let (_, body) = render(AppError::TooManyRequests {
+        message: "Slow down".to_string(),
+        retry_after_seconds: 30,
+    })
+    .await;
+    assert_eq!(body["code"], "RATE_LIMITED");
+    assert_eq!(body["retry_after"], 30);
+}
+
+#[tokio::test]
+async fn test_validation_errors_list_each_field() {
+    let errors = register_dto("not-an-email", "alllowercase").validate().unwrap_err();
+    let error = AppError::from(errors);
+    assert_eq!(error.code(), ErrorCode::ValidationFailed);
+
+    let (status, body) = render(error).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "Invalid value for email, password");
+    assert_eq!(
+        body["details"],
+        json!([
+            { "field": "email", "code": "EMAIL", "params": {} },
+            { "field": "password", "code": "WEAK_PASSWORD", "params": {} },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_validation_errors_never_echo_the_submitted_value() {
+    let errors = register_dto("someone@example.com", "Sh0rt").validate().unwrap_err();
+
+    let (_, body) = render(AppError::from(errors)).await;
+    assert!(!body.to_string().contains("Sh0rt"));
+    assert_eq!(body["details"][0]["code"], "LENGTH");
+    assert_eq!(body["details"][0]["params"], json!({ "min": 8, "max": 128 }));
+}
+
+#[test]
+fn test_nested_fields_use_paths() {
+    #[derive(Validate)]
+    struct Batch {
+        #[validate(nested)]
+        cards: Vec<CreateCardDto>,
+    }
+
+    let card = |front: &str| CreateCardDto {
+        front: front.to_string(),
+        back: "back".to_string(),
+        position: None,
+    };
+    let batch = Batch {
+        cards: vec![card("ok"), card(""), card("")],
+    };
+
+    let fields: Vec<String> = field_errors(&batch.validate().unwrap_err())
+        .into_iter()
+        .map(|error| error.field)
+        .collect();
+    assert_eq!(fields, vec!["cards[1].front", "cards[2].front"]);
+}
+
+#[tokio::test]
+async fn test_plain_validation_errors_have_no_details() {
+    let (_, body) =
+        render(AppError::ValidationError("Unknown timezone: Mars/Olympus".to_string())).await;
+
+    assert_eq!(body["code"], "VALIDATION_FAILED");
+    assert!(body.get("details").is_none());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::{CreateCardDto, CreateDeckDto},
+    services::{card::CardService, deck::DeckService},
+};
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_export_formats_are_listed_for_signed_in_users() {
+    let state = common::create_test_state().await;
+    let (_, token) = common::register_with_token(&state, "exporter@example.com").await;
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server
+        .get("/api/v1/import-export/export/formats")
+        .add_header(header::AUTHORIZATION, token.parse::<HeaderValue>().unwrap())
+        .await;
+    response.assert_status_ok();
+    let formats: Value = response.json();
+    let names: Vec<&str> = formats.as_array().unwrap().iter().map(|f| f["name"].as_str().unwrap()).collect();
+    for name in ["anki", "anki_text", "csv", "html", "json", "markdown"] {
+        assert!(names.contains(&name), "{} missing from {:?}", name, names);
+    }
+    let markdown = formats.as_array().unwrap().iter().find(|f| f["name"] == "markdown").unwrap();
+    assert_eq!(markdown["content_type"], "text/markdown");
+    assert_eq!(markdown["extension"], "md");
+
+    let response = server.get("/api/v1/import-export/export/formats").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_deck_export_uses_the_registered_exporter() {
+    let state = common::create_test_state().await;
+    let (user_id, owner) = common::register_with_token(&state, "owner@example.com").await;
+    let (_, stranger) = common::register_with_token(&state, "stranger@example.com").await;
+    let owner: HeaderValue = owner.parse().unwrap();
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let dto = CreateCardDto {
+        front: "France".to_string(),
+        back: "Paris".to_string(),
+        position: None,
+    };
+    CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap();
+    let server = TestServer::new(create_app(state)).unwrap();
+    let url = format!("/api/v1/import-export/export/{}", deck_id);
+
+    let response = server
+        .get(&url)
+        .add_query_param("format", "Markdown")
+        .add_header(header::AUTHORIZATION, owner.clone())
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.header(header::CONTENT_TYPE), "text/markdown");
+    let disposition = response.header(header::CONTENT_DISPOSITION);
+    assert!(disposition.to_str().unwrap().ends_with(".md\""));
+    let body = response.text();
+    assert!(body.starts_with("# Capitals"));
+    assert!(body.contains("**Front:** France"));
+
+    let response = server
+        .get(&url)
+        .add_query_param("format", "docx")
+        .add_header(header::AUTHORIZATION, owner)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    // Private decks are not exported for anyone else
+    let response = server
+        .get(&url)
+        .add_query_param("format", "markdown")
+        .add_header(header::AUTHORIZATION, stranger.parse::<HeaderValue>().unwrap())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = server.get(&url).add_query_param("format", "markdown").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
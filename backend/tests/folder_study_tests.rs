@@ -0,0 +1,34 @@
+use deckoracle_backend::services::study_queue::interleave_by_deck;
+use uuid::Uuid;
+
+#[test]
+fn test_interleave_takes_turns_between_decks() {
+    let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let cards = vec![(a, 1), (a, 2), (a, 3), (b, 1), (c, 1), (c, 2)];
+
+    let interleaved = interleave_by_deck(cards, |card| card.0);
+
+    assert_eq!(
+        interleaved,
+        vec![(a, 1), (b, 1), (c, 1), (a, 2), (c, 2), (a, 3)]
+    );
+}
+
+#[test]
+fn test_interleave_keeps_order_within_a_deck() {
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    let cards = vec![(b, 1), (a, 1), (b, 2), (b, 3), (a, 2)];
+
+    let interleaved = interleave_by_deck(cards, |card| card.0);
+
+    assert_eq!(interleaved, vec![(b, 1), (a, 1), (b, 2), (a, 2), (b, 3)]);
+}
+
+#[test]
+fn test_interleave_leaves_a_single_deck_unchanged() {
+    let deck = Uuid::new_v4();
+    let cards: Vec<(Uuid, i32)> = (0..5).map(|i| (deck, i)).collect();
+
+    assert_eq!(interleave_by_deck(cards.clone(), |card| card.0), cards);
+    assert!(interleave_by_deck(Vec::<(Uuid, i32)>::new(), |card| card.0).is_empty());
+}
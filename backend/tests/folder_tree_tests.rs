@@ -0,0 +1,66 @@
+use chrono::Utc;
+use deckoracle_backend::models::{Folder, FolderNode};
+use deckoracle_backend::services::folder::build_tree;
+use uuid::Uuid;
+
+fn folder(name: &str, parent: Option<&Folder>, position: i32) -> Folder {
+    Folder {
+        id: Uuid::new_v4(),
+        user_id: Uuid::nil(),
+        parent_folder_id: parent.map(|p| p.id),
+        name: name.to_string(),
+        position,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn names(nodes: &[FolderNode]) -> Vec<&str> {
+    nodes.iter().map(|node| node.folder.name.as_str()).collect()
+}
+
+#[test]
+fn test_tree_nests_folders_under_parents() {
+    let languages = folder("Languages", None, 0);
+    let science = folder("Science", None, 1);
+    let spanish = folder("Spanish", Some(&languages), 0);
+    let verbs = folder("Verbs", Some(&spanish), 0);
+    let french = folder("French", Some(&languages), 1);
+
+    let tree = build_tree(vec![
+        languages.clone(),
+        science.clone(),
+        spanish.clone(),
+        french.clone(),
+        verbs.clone(),
+    ]);
+
+    assert_eq!(names(&tree), vec!["Languages", "Science"]);
+    assert_eq!(names(&tree[0].children), vec!["Spanish", "French"]);
+    assert_eq!(names(&tree[0].children[0].children), vec!["Verbs"]);
+    assert!(tree[1].children.is_empty());
+}
+
+#[test]
+fn test_tree_drops_folders_without_a_reachable_parent() {
+    let root = folder("Root", None, 0);
+    let missing_parent = folder("Missing", None, 0);
+    let orphan = folder("Orphan", Some(&missing_parent), 0);
+
+    let tree = build_tree(vec![root, orphan]);
+
+    assert_eq!(names(&tree), vec!["Root"]);
+    assert!(tree[0].children.is_empty());
+}
+
+#[test]
+fn test_tree_serializes_children_inline() {
+    let root = folder("Root", None, 0);
+    let child = folder("Child", Some(&root), 0);
+
+    let json = serde_json::to_value(build_tree(vec![root, child])).unwrap();
+
+    assert_eq!(json[0]["name"], "Root");
+    assert_eq!(json[0]["children"][0]["name"], "Child");
+    assert_eq!(json[0]["children"][0]["children"], serde_json::json!([]));
+}
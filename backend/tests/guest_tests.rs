@@ -0,0 +1,212 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::{
+        CardContentDto, CreateCardDto, CreateDeckDto, CreateGuestTokenDto, DeckRole,
+        EncryptDeckDto, ShareDeckDto,
+    },
+    services::{
+        card::CardService, deck::DeckService, encryption::EncryptionService, guest::GuestService,
+        sharing::SharingService,
+    },
+    state::AppState,
+    utils::AppError,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// A deck with two cards, owned by a new user
+async fn demo_deck(state: &AppState, email: &str) -> (Uuid, Uuid, Vec<Uuid>) {
+    let user_id = common::register(state, email).await;
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Planets".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+
+    let mut card_ids = Vec::new();
+    for (front, back) in [("Largest planet", "Jupiter"), ("Red planet", "Mars")] {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: back.to_string(),
+            position: None,
+        };
+        card_ids.push(CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap().id);
+    }
+    (user_id, deck_id, card_ids)
+}
+
+async fn guest_token(state: &AppState, deck_id: Uuid, user_id: Uuid) -> (Uuid, HeaderValue) {
+    let dto = CreateGuestTokenDto {
+        label: Some("Projector".to_string()),
+        expires_in_hours: Some(2),
+    };
+    let token = GuestService::create_token(&state.db, deck_id, user_id, dto).await.unwrap();
+    (token.id, format!("Bearer {}", token.token).parse().unwrap())
+}
+
+#[tokio::test]
+async fn test_guest_token_shows_and_studies_its_deck() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id, _) = demo_deck(&state, "teacher@example.com").await;
+    let (_, token) = guest_token(&state, deck_id, user_id).await;
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+
+    let response = server.get("/api/v1/guest/deck").add_header(header::AUTHORIZATION, token.clone()).await;
+    response.assert_status_ok();
+    let deck: Value = response.json();
+    assert_eq!(deck["id"], json!(deck_id));
+    assert_eq!(deck["cards"].as_array().unwrap().len(), 2);
+
+    let response = server
+        .get("/api/v1/guest/study")
+        .add_query_param("shuffle", true)
+        .add_header(header::AUTHORIZATION, token.clone())
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>().as_array().unwrap().len(), 2);
+
+    let tokens = GuestService::list_tokens(&state.db, deck_id, user_id).await.unwrap();
+    assert_eq!(tokens[0].use_count, 2);
+    assert!(tokens[0].last_used_at.is_some());
+}
+
+#[tokio::test]
+async fn test_expired_and_revoked_tokens_are_rejected() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id, _) = demo_deck(&state, "teacher@example.com").await;
+    let (expired_id, expired) = guest_token(&state, deck_id, user_id).await;
+    let (revoked_id, revoked) = guest_token(&state, deck_id, user_id).await;
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+
+    sqlx::query("UPDATE deck_guest_tokens SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(expired_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    let response = server.get("/api/v1/guest/deck").add_header(header::AUTHORIZATION, expired).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    server
+        .get("/api/v1/guest/deck")
+        .add_header(header::AUTHORIZATION, revoked.clone())
+        .await
+        .assert_status_ok();
+    GuestService::revoke_token(&state.db, deck_id, user_id, revoked_id).await.unwrap();
+    let response = server.get("/api/v1/guest/study").add_header(header::AUTHORIZATION, revoked).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let error = GuestService::revoke_token(&state.db, deck_id, user_id, revoked_id).await.unwrap_err();
+    assert!(matches!(error, AppError::NotFound(_)));
+
+    let response = server.get("/api/v1/guest/deck").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_guest_tokens_are_read_only_and_owner_issued() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id, card_ids) = demo_deck(&state, "teacher@example.com").await;
+    let (_, token) = guest_token(&state, deck_id, user_id).await;
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+
+    // Guest tokens are not accepted by the signed-in API
+    let response = server
+        .get(&format!("/api/v1/decks/{}", deck_id))
+        .add_header(header::AUTHORIZATION, token.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    let response = server
+        .patch(&format!("/api/v1/decks/{}", deck_id))
+        .add_header(header::AUTHORIZATION, token.clone())
+        .json(&json!({ "name": "Renamed" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    let response = server
+        .post(&format!("/api/v1/decks/{}/csv", deck_id))
+        .add_header(header::AUTHORIZATION, token.clone())
+        .text("front,back\nSmallest planet,Mercury\n")
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    // And the guest routes only read
+    let response = server.post("/api/v1/guest/deck").add_header(header::AUTHORIZATION, token.clone()).await;
+    assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+
+    // Studying leaves no trace
+    server.get("/api/v1/guest/study").add_header(header::AUTHORIZATION, token).await.assert_status_ok();
+    let answers: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user_card_stats WHERE card_id = ANY($1)")
+        .bind(&card_ids)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(answers, 0);
+    assert_eq!(DeckService::get_deck(&state.db, deck_id, user_id).await.unwrap().name, "Planets");
+
+    // Only the owner hands out tokens
+    let editor = common::register(&state, "editor@example.com").await;
+    let dto = ShareDeckDto {
+        email: "editor@example.com".to_string(),
+        role: DeckRole::Editor,
+    };
+    SharingService::share_with_user(&state.db, deck_id, user_id, dto).await.unwrap();
+    let dto = CreateGuestTokenDto {
+        label: None,
+        expires_in_hours: None,
+    };
+    let error = GuestService::create_token(&state.db, deck_id, editor, dto).await.unwrap_err();
+    assert!(matches!(error, AppError::Forbidden));
+    let error = GuestService::list_tokens(&state.db, deck_id, editor).await.unwrap_err();
+    assert!(matches!(error, AppError::Forbidden));
+}
+
+#[tokio::test]
+async fn test_encrypted_decks_are_not_shown_to_guests() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id, card_ids) = demo_deck(&state, "teacher@example.com").await;
+    let (_, token) = guest_token(&state, deck_id, user_id).await;
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+
+    let dto = EncryptDeckDto {
+        algorithm: "aes-256-gcm".to_string(),
+        kdf: "argon2id".to_string(),
+        kdf_salt: "c2FsdA==".to_string(),
+        kdf_params: None,
+        key_check: "check".to_string(),
+        cards: card_ids
+            .iter()
+            .map(|&card_id| CardContentDto {
+                card_id,
+                front: "Y2lwaGVy".to_string(),
+                back: "dGV4dA==".to_string(),
+            })
+            .collect(),
+    };
+    EncryptionService::encrypt_deck(&state.db, deck_id, user_id, dto).await.unwrap();
+
+    // Tokens issued before the deck was encrypted stop serving it
+    for path in ["/api/v1/guest/deck", "/api/v1/guest/study"] {
+        let response = server.get(path).add_header(header::AUTHORIZATION, token.clone()).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    let dto = CreateGuestTokenDto {
+        label: None,
+        expires_in_hours: None,
+    };
+    let error = GuestService::create_token(&state.db, deck_id, user_id, dto).await.unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use axum_test::TestServer;
+use deckoracle_backend::{create_app, services::migrations::MIGRATOR};
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_detailed_health_reports_pool_and_uptime() {
+    let state = common::create_test_state().await;
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let body: Value = server.get("/api/v1/health/detailed").await.json();
+
+    assert_eq!(body["status"], "ok");
+    assert!(body["uptime"].as_u64().unwrap() >= 1);
+
+    let database = &body["database"];
+    assert_eq!(database["status"], "healthy");
+    assert_eq!(database["pool_size"], state.config.database.max_connections);
+    assert_eq!(
+        database["acquire_timeout_seconds"],
+        state.config.database.acquire_timeout_seconds
+    );
+    let connections = database["connections"].as_u64().unwrap();
+    assert!(connections >= 1);
+    assert_eq!(
+        database["idle_connections"].as_u64().unwrap() + database["in_use_connections"].as_u64().unwrap(),
+        connections
+    );
+}
+
+#[tokio::test]
+async fn test_migration_status_lists_applied_versions() {
+    let state = common::create_test_state().await;
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let body: Value = server.get("/api/v1/health/migrations").await.json();
+
+    assert_eq!(body["status"], "up_to_date");
+    assert_eq!(body["pending"], serde_json::json!([]));
+    let applied = body["applied"].as_array().unwrap();
+    assert_eq!(applied.len(), MIGRATOR.iter().count());
+    assert!(applied
+        .iter()
+        .all(|migration| migration["success"] == true && migration["checksum_mismatch"] == false));
+}
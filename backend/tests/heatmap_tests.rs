@@ -0,0 +1,42 @@
+mod common;
+
+use deckoracle_backend::{
+    models::RegisterDto,
+    services::{auth::AuthService, stats::StatsService},
+    utils::AppError,
+};
+
+#[tokio::test]
+async fn test_heatmap_has_an_entry_for_every_day() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "heatmap@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+
+    let heatmap = StatsService::heatmap(&state.db, user_id, Some(2025), None).await.unwrap();
+    assert_eq!(heatmap.timezone, "UTC");
+    assert_eq!(heatmap.start_date.to_string(), "2025-01-01");
+    assert_eq!(heatmap.reviews.len(), 365);
+    assert_eq!(heatmap.minutes.len(), 365);
+    assert_eq!((heatmap.total_reviews, heatmap.active_days, heatmap.max_reviews), (0, 0, 0));
+
+    let leap = StatsService::heatmap(&state.db, user_id, Some(2024), Some("Europe/Madrid".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(leap.reviews.len(), 366);
+
+    let unknown = StatsService::heatmap(&state.db, user_id, Some(2025), Some("Mars/Olympus".to_string())).await;
+    assert!(matches!(unknown, Err(AppError::ValidationError(_))));
+    let out_of_range = StatsService::heatmap(&state.db, user_id, Some(0), None).await;
+    assert!(matches!(out_of_range, Err(AppError::ValidationError(_))));
+}
@@ -0,0 +1,258 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::create_app;
+use deckoracle_backend::models::import_export::{
+    DuplicateStrategy, HtmlHandling, ImportFormat, ImportJobParameters, TextDelimiters,
+};
+use deckoracle_backend::services::{
+    import_export::ImportExportService,
+    job::JobService,
+    upload::UploadSource,
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use serde_json::{json, Value};
+use std::time::Duration;
+use uuid::Uuid;
+
+fn csv_params() -> ImportJobParameters {
+    ImportJobParameters {
+        format: ImportFormat::Csv,
+        folder_id: None,
+        merge_duplicates: false,
+        delimiters: TextDelimiters::default(),
+        include_progress: false,
+        duplicate_strategy: DuplicateStrategy::KeepBoth,
+        html: HtmlHandling::default(),
+    }
+}
+
+fn csv_with_cards(count: usize) -> Vec<u8> {
+    let mut csv = String::from("front,back\n");
+    for i in 0..count {
+        csv.push_str(&format!("question {},answer {}\n", i, i));
+    }
+    csv.into_bytes()
+}
+
+async fn deck_count(state: &AppState, user_id: Uuid) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM decks WHERE owner_id = $1 AND deleted_at IS NULL")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_cancelled_import_stops_and_can_be_retried() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "jobs@example.com").await;
+    let data = csv_with_cards(250);
+    let params = csv_params();
+
+    let job = JobService::create_job(
+        &state.db,
+        user_id,
+        "import",
+        serde_json::to_value(&params).unwrap(),
+        Some(&data),
+        true,
+    )
+    .await
+    .unwrap();
+    JobService::mark_processing(&state.db, job.id).await.unwrap();
+
+    // Running jobs are only flagged; the import notices at its next checkpoint
+    let cancelled = JobService::cancel_job(&state.db, job.id, user_id).await.unwrap();
+    assert_eq!(cancelled.status, "processing");
+    assert!(cancelled.cancel_requested);
+
+    let error = ImportExportService::import_decks(
+        &state.db,
+        &state.media,
+        user_id,
+        UploadSource::Bytes(&data),
+        &params,
+        Some(job.id),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+    JobService::fail_job(&state.db, job.id, &error.to_string()).await.unwrap();
+
+    let summary = JobService::get_user_job(&state.db, job.id, user_id).await.unwrap();
+    assert_eq!(summary.status, "cancelled");
+    assert_eq!(deck_count(&state, user_id).await, 0);
+
+    // Someone else cannot retry it
+    let stranger = common::register(&state, "stranger@example.com").await;
+    let error = JobService::retry_job(&state.db, &state.media, &state.queue_cache, &state.config.ai, job.id, stranger)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AppError::NotFound(_)));
+
+    let retry = JobService::retry_job(&state.db, &state.media, &state.queue_cache, &state.config.ai, job.id, user_id)
+        .await
+        .unwrap();
+    assert_eq!(retry.retry_of, Some(job.id));
+
+    let mut summary = retry;
+    for _ in 0..100 {
+        if summary.status == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        summary = JobService::get_user_job(&state.db, summary.id, user_id).await.unwrap();
+    }
+    assert_eq!(summary.status, "completed");
+    assert_eq!(summary.result.as_ref().unwrap()["total_cards_imported"], 250);
+    assert_eq!(deck_count(&state, user_id).await, 1);
+
+    // The uploaded file is dropped once the import succeeded
+    let payload: Option<Vec<u8>> =
+        sqlx::query_scalar("SELECT payload FROM background_jobs WHERE id = $1")
+            .bind(summary.id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert!(payload.is_none());
+
+    let error = JobService::retry_job(&state.db, &state.media, &state.queue_cache, &state.config.ai, summary.id, user_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+}
+
+#[tokio::test]
+async fn test_failed_generation_is_queued_again_for_streaming() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "generation@example.com").await;
+
+    let insert = r#"
+        INSERT INTO ai_content_generation_jobs
+            (user_id, job_type, status, input_metadata, provider, model_name, completed_at)
+        VALUES ($1, 'generate_questions', 'failed', $2, 'vertex_ai', 'gemini-pro', NOW())
+        RETURNING id
+    "#;
+    let metadata = json!({ "options": { "max_cards": 5 }, "content_length": 11, "content": "Photosynthe" });
+    let failed: Uuid = sqlx::query_scalar(insert)
+        .bind(user_id)
+        .bind(&metadata)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+
+    let retry = JobService::retry_job(&state.db, &state.media, &state.queue_cache, &state.config.ai, failed, user_id)
+        .await
+        .unwrap();
+    assert_eq!(retry.source, "ai_generation");
+    assert_eq!(retry.status, "pending");
+    assert_eq!(retry.retry_of, Some(failed));
+    let stored: serde_json::Value =
+        sqlx::query_scalar("SELECT input_metadata FROM ai_content_generation_jobs WHERE id = $1")
+            .bind(retry.id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(stored["content"], "Photosynthe");
+    assert_eq!(stored["stream"], true);
+
+    // Without its input there is nothing to run again
+    let without_input: Uuid = sqlx::query_scalar(insert)
+        .bind(user_id)
+        .bind(json!({ "options": {}, "content_length": 11 }))
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    let error = JobService::retry_job(&state.db, &state.media, &state.queue_cache, &state.config.ai, without_input, user_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+}
+
+#[tokio::test]
+async fn test_spooled_uploads_import_from_disk_and_are_not_kept() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "spooled@example.com").await;
+    let params = csv_params();
+    let path = std::env::temp_dir().join(format!("deckoracle-test-{}", Uuid::new_v4()));
+    std::fs::write(&path, csv_with_cards(250)).unwrap();
+
+    let result = ImportExportService::import_decks(
+        &state.db,
+        &state.media,
+        user_id,
+        UploadSource::File(&path),
+        &params,
+        None,
+    )
+    .await;
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(result.unwrap().total_cards_imported, 250);
+
+    // Without the file there is nothing to run again
+    let job = JobService::create_job(
+        &state.db,
+        user_id,
+        "import",
+        serde_json::to_value(&params).unwrap(),
+        None,
+        true,
+    )
+    .await
+    .unwrap();
+    JobService::fail_job(&state.db, job.id, "Connection reset").await.unwrap();
+    let error = JobService::retry_job(&state.db, &state.media, &state.queue_cache, &state.config.ai, job.id, user_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+}
+
+#[tokio::test]
+async fn test_job_center_shows_users_only_their_own_jobs() {
+    let state = common::create_test_state().await;
+    let (alice, alice_token) = common::register_with_token(&state, "alice@example.com").await;
+    let (_, bob_token) = common::register_with_token(&state, "bob@example.com").await;
+    let alice_token: HeaderValue = alice_token.parse().unwrap();
+    let bob_token: HeaderValue = bob_token.parse().unwrap();
+    let params = serde_json::to_value(csv_params()).unwrap();
+    let job = JobService::create_job(&state.db, alice, "import", params, Some(&b"front,back\n"[..]), true)
+        .await
+        .unwrap();
+    let server = TestServer::new(create_app(state)).unwrap();
+    let job_url = format!("/api/v1/jobs/{}", job.id);
+
+    assert_eq!(server.get("/api/v1/jobs").await.status_code(), StatusCode::UNAUTHORIZED);
+
+    let response = server
+        .get("/api/v1/jobs")
+        .add_query_param("status", "pending")
+        .add_header(header::AUTHORIZATION, alice_token.clone())
+        .await;
+    response.assert_status_ok();
+    let jobs: Value = response.json();
+    assert_eq!(jobs.as_array().unwrap().len(), 1);
+    assert_eq!(jobs[0]["id"], json!(job.id));
+    assert_eq!(jobs[0]["source"], "background");
+
+    let response = server.get("/api/v1/jobs").add_header(header::AUTHORIZATION, bob_token.clone()).await;
+    assert!(response.json::<Value>().as_array().unwrap().is_empty());
+    let response = server.get(&job_url).add_header(header::AUTHORIZATION, bob_token.clone()).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = server
+        .post(&format!("{}/cancel", job_url))
+        .add_header(header::AUTHORIZATION, bob_token)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    let response = server
+        .post(&format!("{}/cancel", job_url))
+        .add_header(header::AUTHORIZATION, alice_token.clone())
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["status"], "cancelled");
+    let response = server.get(&job_url).add_header(header::AUTHORIZATION, alice_token).await;
+    assert_eq!(response.json::<Value>()["status"], "cancelled");
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::{CreateCardDto, CreateDeckDto, CreateStudySessionDto},
+    services::{card::CardService, deck::DeckService, leaderboard::LeaderboardService, study::StudyService},
+    state::AppState,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Register a user and give them a study session over a deck of `cards` cards
+async fn learner(state: &AppState, email: &str, cards: usize) -> (Uuid, HeaderValue, Uuid, Vec<Uuid>) {
+    let (user_id, token) = common::register_with_token(state, email).await;
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let mut card_ids = Vec::new();
+    for i in 0..cards {
+        let dto = CreateCardDto {
+            front: format!("Country {}", i),
+            back: format!("Capital {}", i),
+            position: None,
+        };
+        card_ids.push(CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap().id);
+    }
+    let dto = CreateStudySessionDto {
+        deck_id: Some(deck_id),
+        folder_id: None,
+        smart_deck_id: None,
+        study_mode: None,
+        card_ids: None,
+        time_limit_seconds: None,
+    };
+    let session = StudyService::create_study_session(&state.db, user_id, dto).await.unwrap();
+    (user_id, token.parse().unwrap(), session.id, card_ids)
+}
+
+async fn answer(server: &TestServer, auth: &HeaderValue, session_id: Uuid, card_id: Uuid, rating: &str) {
+    let response = server
+        .post(&format!("/api/v1/study/sessions/{}/progress", session_id))
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "card_id": card_id, "rating": rating, "response_time_ms": 1500 }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::CREATED);
+}
+
+async fn rename(server: &TestServer, auth: &HeaderValue, display_name: &str) {
+    let response = server
+        .patch("/api/v1/users/me")
+        .add_header(header::AUTHORIZATION, auth.clone())
+        .json(&json!({ "display_name": display_name }))
+        .await;
+    response.assert_status_ok();
+}
+
+fn names(leaderboard: &Value) -> Vec<&str> {
+    leaderboard["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["display_name"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_leaderboard_ranks_weekly_points() {
+    let state = common::create_test_state().await;
+    let (alice_id, alice, alice_session, alice_cards) = learner(&state, "alice@example.com", 2).await;
+    let (_, bob, bob_session, bob_cards) = learner(&state, "bob@example.com", 1).await;
+    let (_, idle, _, _) = learner(&state, "idle@example.com", 0).await;
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+    rename(&server, &alice, "Alice").await;
+    rename(&server, &bob, "Bob").await;
+
+    for card_id in alice_cards {
+        answer(&server, &alice, alice_session, card_id, "good").await;
+    }
+    answer(&server, &bob, bob_session, bob_cards[0], "again").await;
+    LeaderboardService::refresh(&state.db).await.unwrap();
+
+    let response = server.get("/api/v1/progress/leaderboard").add_header(header::AUTHORIZATION, bob.clone()).await;
+    response.assert_status_ok();
+    let leaderboard: Value = response.json();
+    assert_eq!(leaderboard["board"], "weekly_points");
+    assert!(leaderboard["refreshed_at"].is_string());
+    assert_eq!(names(&leaderboard), vec!["Alice", "Bob"]);
+    assert_eq!(leaderboard["entries"][0]["rank"], 1);
+    assert_eq!(leaderboard["entries"][0]["user_id"], json!(alice_id));
+    assert_eq!(leaderboard["entries"][0]["weekly_points"], 20);
+    assert_eq!(leaderboard["entries"][1]["weekly_points"], 2);
+    assert_eq!(leaderboard["you"]["rank"], 2);
+
+    // The caller's standing is reported even when it falls below the limit
+    let response = server
+        .get("/api/v1/progress/leaderboard")
+        .add_query_param("limit", 1)
+        .add_header(header::AUTHORIZATION, bob)
+        .await;
+    let leaderboard: Value = response.json();
+    assert_eq!(names(&leaderboard), vec!["Alice"]);
+    assert_eq!(leaderboard["you"]["display_name"], "Bob");
+
+    // Both studied today; users with nothing to rank on are left out
+    let response = server
+        .get("/api/v1/progress/leaderboard")
+        .add_query_param("board", "streak")
+        .add_header(header::AUTHORIZATION, idle)
+        .await;
+    response.assert_status_ok();
+    let leaderboard: Value = response.json();
+    assert_eq!(leaderboard["board"], "streak");
+    assert!(leaderboard["you"].is_null());
+    let mut ranked = names(&leaderboard);
+    ranked.sort();
+    assert_eq!(ranked, vec!["Alice", "Bob"]);
+}
+
+#[tokio::test]
+async fn test_opted_out_users_are_hidden_from_the_leaderboard() {
+    let state = common::create_test_state().await;
+    let (_, alice, alice_session, alice_cards) = learner(&state, "alice@example.com", 1).await;
+    let (_, shy, shy_session, shy_cards) = learner(&state, "shy@example.com", 1).await;
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+    rename(&server, &alice, "Alice").await;
+    rename(&server, &shy, "Shy").await;
+
+    answer(&server, &alice, alice_session, alice_cards[0], "good").await;
+    answer(&server, &shy, shy_session, shy_cards[0], "good").await;
+    LeaderboardService::refresh(&state.db).await.unwrap();
+
+    let response = server.get("/api/v1/progress/leaderboard").add_header(header::AUTHORIZATION, alice.clone()).await;
+    assert_eq!(names(&response.json::<Value>()), vec!["Alice", "Shy"]);
+
+    // Opting out applies before the next refresh
+    let response = server
+        .patch("/api/v1/users/me")
+        .add_header(header::AUTHORIZATION, shy.clone())
+        .json(&json!({ "leaderboard_opt_out": true }))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["leaderboard_opt_out"], true);
+
+    let response = server.get("/api/v1/progress/leaderboard").add_header(header::AUTHORIZATION, alice).await;
+    assert_eq!(names(&response.json::<Value>()), vec!["Alice"]);
+    let response = server.get("/api/v1/progress/leaderboard").add_header(header::AUTHORIZATION, shy).await;
+    response.assert_status_ok();
+    assert!(response.json::<Value>()["you"].is_null());
+
+    let response = server.get("/api/v1/progress/leaderboard").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use deckoracle_backend::models::{
+    ai::{BatchAnalyticsEvent, CreateStudyEventDto, UpdatePrivacySettingsDto},
+    CreateCardDto, CreateDeckDto, RegisterDto,
+};
+use deckoracle_backend::services::{
+    ai_privacy::AiPrivacyService,
+    auth::AuthService,
+    card::CardService,
+    deck::DeckService,
+    learning_patterns::{difficulty_profile, session_length_sweet_spot, LearningPatternService},
+    study_events::StudyEventService,
+};
+use uuid::Uuid;
+
+#[test]
+fn test_session_length_sweet_spot() {
+    let mut sessions = vec![(900, 10, 9); 4]; // 15 minutes, 90% right
+    sessions.extend(vec![(3000, 30, 15); 4]); // 50 minutes, 50% right
+    sessions.push((60, 3, 0)); // Too short to count
+
+    let spot = session_length_sweet_spot(&sessions).unwrap();
+    assert_eq!((spot.min_minutes, spot.max_minutes), (10, Some(20)));
+    assert_eq!(spot.sessions, 4);
+    assert!((spot.accuracy - 0.9).abs() < 1e-9);
+    assert!((spot.overall_accuracy - 0.6).abs() < 1e-9);
+
+    assert_eq!(session_length_sweet_spot(&sessions[..5]), None);
+}
+
+#[test]
+fn test_difficulty_profile() {
+    let bands = [
+        ("easy".to_string(), 50, 45),
+        ("hard".to_string(), 20, 8),
+        ("medium".to_string(), 30, 24),
+    ];
+    let profile = difficulty_profile(&bands).unwrap();
+    let names: Vec<&str> = profile.bands.iter().map(|b| b.band.as_str()).collect();
+    assert_eq!(names, ["hard", "medium", "easy"]);
+    assert_eq!(profile.preferred, "easy");
+    assert_eq!(profile.weakest.as_deref(), Some("hard"));
+    assert!((profile.bands[0].share - 0.2).abs() < 1e-9);
+
+    assert_eq!(difficulty_profile(&bands[1..2]), None);
+}
+
+#[tokio::test]
+async fn test_patterns_are_detected_from_study_events() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "patterns@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Biology".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let card = CardService::create_card(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateCardDto {
+            front: "Powerhouse of the cell?".to_string(),
+            back: "Mitochondria".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // A week of quick morning sessions that go well and long evening ones that do not
+    let mut events = Vec::new();
+    for days_ago in 1..=7 {
+        let day = (Utc::now() - Duration::days(days_ago)).date_naive();
+        let sessions: [(u32, u32, i64, &[i64]); 2] = [(9, 55, 1, &[0, 5]), (20, 0, 5, &[4, 5, 6, 7, 8, 9])];
+        for (hour, minute, minute_step, wrong) in sessions {
+            let start = day.and_hms_opt(hour, minute, 0).unwrap().and_utc();
+            for i in 0..10 {
+                events.push(CreateStudyEventDto {
+                    card_id: card.id,
+                    deck_id: deck.id,
+                    session_id: None,
+                    event_type: "answer".to_string(),
+                    outcome: Some(if wrong.contains(&i) { "incorrect" } else { "correct" }.to_string()),
+                    response_time_ms: Some(3000),
+                    confidence_rating: None,
+                    occurred_at: Some(start + Duration::minutes(i * minute_step)),
+                });
+            }
+        }
+    }
+    StudyEventService::ingest_batch(
+        &state.db,
+        user_id,
+        BatchAnalyticsEvent {
+            events,
+            batch_id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert!(LearningPatternService::insights(&state.db, user_id).await.unwrap().is_empty());
+
+    let patterns = LearningPatternService::analyze_user(&state.db, user_id).await.unwrap();
+    let mut kinds: Vec<&str> = patterns.iter().map(|p| p.pattern_type.as_str()).collect();
+    kinds.sort();
+    assert_eq!(kinds, ["difficulty_preference", "session_length", "time_of_day"]);
+
+    let session_length = patterns.iter().find(|p| p.pattern_type == "session_length").unwrap();
+    assert_eq!(session_length.pattern_data["min_minutes"], 0);
+    assert_eq!(session_length.pattern_data["max_minutes"], 10);
+
+    let insights = LearningPatternService::insights(&state.db, user_id).await.unwrap();
+    assert_eq!(insights.len(), 3);
+    let time_of_day = insights.iter().find(|i| i.insight_type == "time_of_day").unwrap();
+    assert!(time_of_day.description.contains("between 09:00 and 11:00 (UTC)"));
+    assert!(time_of_day.actionable);
+    // Every card is new, so there is no difficulty to work on
+    let difficulty = insights.iter().find(|i| i.insight_type == "difficulty_preference").unwrap();
+    assert_eq!(difficulty.data["preferred"], "medium");
+    assert!(!difficulty.actionable);
+
+    // Analyzing again updates the patterns in place
+    let again = LearningPatternService::analyze_user(&state.db, user_id).await.unwrap();
+    assert_eq!(again.len(), 3);
+    assert!(again.iter().all(|p| patterns.iter().any(|q| q.id == p.id)));
+
+    AiPrivacyService::update(
+        &state.db,
+        user_id,
+        UpdatePrivacySettingsDto {
+            track_analytics: None,
+            enable_ai_recommendations: None,
+            enable_content_generation: None,
+            share_anonymous_data: None,
+            personalized_learning: Some(false),
+        },
+    )
+    .await
+    .unwrap();
+    assert!(LearningPatternService::insights(&state.db, user_id).await.unwrap().is_empty());
+}
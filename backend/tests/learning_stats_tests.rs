@@ -0,0 +1,91 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto, RegisterDto};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, deck::DeckService, learning_stats::LearningStatsService,
+};
+
+#[tokio::test]
+async fn test_learning_stats_are_served_after_refresh() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "learning-stats@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Chemistry".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut cards = Vec::new();
+    for front in ["H", "He"] {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: "element".to_string(),
+            position: None,
+        };
+        cards.push(CardService::create_card(&state.db, deck.id, user_id, dto).await.unwrap().id);
+    }
+
+    let empty = LearningStatsService::get(&state.db, user_id).await.unwrap();
+    assert_eq!(empty.user_id, user_id);
+    assert_eq!(empty.total_study_events, None);
+
+    let yesterday = Utc::now() - Duration::days(1);
+    let events = [
+        (cards[0], "correct", 1000, Some(yesterday)),
+        (cards[0], "incorrect", 3000, None),
+        (cards[1], "correct", 2000, None),
+        (cards[1], "partial", 2000, None),
+    ];
+    for (card_id, outcome, response_time_ms, occurred_at) in events {
+        sqlx::query(
+            r#"
+            INSERT INTO study_events (user_id, card_id, deck_id, event_type, outcome, response_time_ms, occurred_at)
+            VALUES ($1, $2, $3, 'answer', $4, $5, $6)
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .bind(deck.id)
+        .bind(outcome)
+        .bind(response_time_ms)
+        .bind(occurred_at)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    }
+
+    // Nothing shows until the view is recomputed
+    let stale = LearningStatsService::get(&state.db, user_id).await.unwrap();
+    assert_eq!(stale.total_study_events, None);
+
+    LearningStatsService::refresh(&state.db).await.unwrap();
+    let stats = LearningStatsService::get(&state.db, user_id).await.unwrap();
+    assert_eq!(stats.unique_cards_studied, Some(2));
+    assert_eq!(stats.total_study_events, Some(4));
+    assert_eq!(stats.avg_response_time_ms, Some(2000));
+    assert_eq!((stats.total_correct, stats.total_incorrect), (Some(2), Some(2)));
+    assert!((stats.accuracy_rate.unwrap() - 0.5).abs() < 1e-6);
+    assert_eq!(stats.study_days, Some(2));
+    assert!(stats.refreshed_at.is_some());
+}
@@ -0,0 +1,267 @@
+mod common;
+
+use deckoracle_backend::models::{
+    ai::{self, SuggestedCard},
+    CreateCardDto, CreateDeckDto, CreateStudySessionDto, LeechAction, LeechSuggestion, Rating,
+    RegisterDto, UpdateDeckSettingsDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService,
+    card::CardService,
+    card_flags::CardFlagsService,
+    deck::DeckService,
+    deck_settings::DeckSettingsService,
+    leech::{suggested_actions, usable_suggestions, LeechService, MAX_SUGGESTED_CARDS},
+    study::StudyService,
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use serde_json::json;
+use uuid::Uuid;
+
+fn suggestion(strategy: &str, cards: usize) -> ai::LeechSuggestion {
+    ai::LeechSuggestion {
+        strategy: strategy.to_string(),
+        cards: (0..cards)
+            .map(|i| SuggestedCard {
+                front: format!("front {}", i),
+                back: format!("back {}", i),
+            })
+            .collect(),
+        mnemonic: None,
+        rationale: None,
+    }
+}
+
+async fn insert_remediation(state: &AppState, user_id: Uuid, card_id: Uuid, strategy: &str) -> Uuid {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO leech_remediations (user_id, card_id, strategy, suggested_cards)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(card_id)
+    .bind(strategy)
+    .bind(json!([
+        { "front": "estar (preterite, yo)", "back": "estuve" },
+        { "front": "estar (preterite, él)", "back": "estuvo" }
+    ]))
+    .fetch_one(&state.db)
+    .await
+    .unwrap()
+}
+
+#[test]
+fn test_suggested_actions() {
+    assert_eq!(
+        suggested_actions("short", false, false, 1),
+        vec![
+            LeechSuggestion::ReviewRemediations,
+            LeechSuggestion::AddMnemonic,
+            LeechSuggestion::Suspend
+        ]
+    );
+    assert_eq!(suggested_actions(&"x".repeat(201), true, true, 0), vec![LeechSuggestion::Split]);
+    assert!(suggested_actions("short", true, true, 0).is_empty());
+}
+
+#[test]
+fn test_only_known_strategies_and_a_few_cards_are_kept() {
+    let kept = usable_suggestions(vec![
+        suggestion("delete", 1),
+        suggestion("split", 10),
+        suggestion("split", 2),
+        suggestion("simplify", 0),
+        suggestion("mnemonic", 1),
+    ]);
+    let strategies: Vec<&str> = kept.iter().map(|s| s.strategy.as_str()).collect();
+    assert_eq!(strategies, vec!["split", "mnemonic"]);
+    assert_eq!(kept[0].cards.len(), MAX_SUGGESTED_CARDS);
+
+    // Cards missing a side are dropped before counting
+    let mut blank = suggestion("simplify", 2);
+    blank.cards[0].back = "  ".to_string();
+    let kept = usable_suggestions(vec![blank]);
+    assert_eq!(kept[0].cards.len(), 1);
+    assert_eq!(kept[0].cards[0].front, "front 1");
+}
+
+#[tokio::test]
+async fn test_accepted_suggestion_tracks_accuracy_from_then_on() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "remedy@example.com").await;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Preterite".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let card = CardService::create_card(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateCardDto {
+            front: "estar".to_string(),
+            back: "estuve, estuviste, estuvo".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck.id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Two right out of five before the rewrite
+    for rating in [Rating::Again, Rating::Good, Rating::Again, Rating::Good, Rating::Again] {
+        StudyService::record_answer(&state.db, &session, card.id, rating, None).await.unwrap();
+    }
+    let split = insert_remediation(&state, user_id, card.id, "split").await;
+    let mnemonic = insert_remediation(&state, user_id, card.id, "mnemonic").await;
+
+    // Only the owner of the suggestion can decide on it
+    let stranger = common::register(&state, "stranger@example.com").await;
+    let error = LeechService::accept(&state.db, split, stranger).await.unwrap_err();
+    assert!(matches!(error, AppError::NotFound(_)));
+
+    let accepted = LeechService::accept(&state.db, split, user_id).await.unwrap();
+    assert_eq!(accepted.status, "accepted");
+    assert!((accepted.accuracy_before.unwrap() - 0.4).abs() < 1e-6);
+    assert_eq!(accepted.reviews_since_accept, Some(0));
+    assert!(accepted.accuracy_after.is_none());
+
+    let other = LeechService::get_remediation(&state.db, mnemonic, user_id).await.unwrap();
+    assert_eq!(other.status, "superseded");
+    let error = LeechService::reject(&state.db, mnemonic, user_id).await.unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+    let error = LeechService::accept(&state.db, split, user_id).await.unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+
+    let cards: Vec<(String, String)> =
+        sqlx::query_as("SELECT front, back FROM cards WHERE deck_id = $1 ORDER BY position")
+            .bind(deck.id)
+            .fetch_all(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(cards.len(), 2);
+    assert_eq!(cards[0], ("estar (preterite, yo)".to_string(), "estuve".to_string()));
+    assert!(LeechService::list_leeches(&state.db, user_id, None).await.unwrap().is_empty());
+
+    // Only reviews after the rewrite count towards its accuracy
+    for rating in [Rating::Good, Rating::Good, Rating::Again] {
+        StudyService::record_answer(&state.db, &session, card.id, rating, None).await.unwrap();
+    }
+    let tracked = LeechService::get_remediation(&state.db, split, user_id).await.unwrap();
+    assert_eq!(tracked.reviews_since_accept, Some(3));
+    assert!((tracked.accuracy_after.unwrap() - 2.0 / 3.0).abs() < 1e-6);
+    assert!((tracked.accuracy_before.unwrap() - 0.4).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_repeated_lapses_mark_and_suspend_a_leech() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "leech@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Irregular Verbs".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    DeckSettingsService::update_settings(
+        &state.db,
+        deck.id,
+        user_id,
+        UpdateDeckSettingsDto {
+            leech_threshold: Some(2),
+            leech_action: Some(LeechAction::Suspend),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let card = CardService::create_card(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateCardDto {
+            front: "estar".to_string(),
+            back: "estuve".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck.id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Forgetting a card that was never learned is not a lapse
+    for rating in [Rating::Again, Rating::Good, Rating::Again, Rating::Good] {
+        StudyService::record_answer(&state.db, &session, card.id, rating, None).await.unwrap();
+    }
+    assert!(LeechService::list_leeches(&state.db, user_id, None).await.unwrap().is_empty());
+
+    StudyService::record_answer(&state.db, &session, card.id, Rating::Again, None).await.unwrap();
+    let leeches = LeechService::list_leeches(&state.db, user_id, Some(deck.id)).await.unwrap();
+    assert_eq!(leeches.len(), 1);
+    assert_eq!(leeches[0].card_id, card.id);
+    assert_eq!(leeches[0].lapses, 2);
+    assert!(leeches[0].suspended);
+    assert!(leeches[0].flagged_at.is_some());
+    assert_eq!(leeches[0].suggested_actions, vec![LeechSuggestion::AddMnemonic]);
+
+    let flags = CardFlagsService::get_flags(&state.db, card.id, user_id).await.unwrap();
+    assert!(flags.leech && flags.suspended);
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use deckoracle_backend::config::LoggingConfig;
+use deckoracle_backend::logging::OnResponse;
+
+fn on_response(request_sample_rate: f64) -> OnResponse {
+    OnResponse::from_config(&LoggingConfig {
+        format: "json".to_string(),
+        request_sample_rate,
+        slow_request_ms: 500,
+    })
+}
+
+#[test]
+fn test_errors_and_slow_requests_bypass_sampling() {
+    let never = on_response(0.0);
+
+    assert!(!never.should_log(200, Duration::from_millis(20)));
+    assert!(!never.should_log(404, Duration::from_millis(20)));
+    assert!(never.should_log(503, Duration::from_millis(20)));
+    assert!(never.should_log(200, Duration::from_millis(750)));
+}
+
+#[test]
+fn test_full_sample_rate_logs_every_request() {
+    let always = on_response(1.0);
+
+    assert!((0..100).all(|_| always.should_log(200, Duration::from_millis(5))));
+}
+
+#[test]
+fn test_format_selection_ignores_case() {
+    let config = |format: &str| LoggingConfig {
+        format: format.to_string(),
+        request_sample_rate: 1.0,
+        slow_request_ms: 1000,
+    };
+
+    assert!(config("JSON").is_json());
+    assert!(!config("pretty").is_json());
+}
@@ -0,0 +1,25 @@
+use deckoracle_backend::utils::render_markdown;
+
+#[test]
+fn test_markdown_renders_formatting() {
+    let html = render_markdown("**bold** and _italic_\n\n- one\n- two");
+    assert!(html.contains("<strong>bold</strong>"));
+    assert!(html.contains("<em>italic</em>"));
+    assert!(html.contains("<li>one</li>"));
+}
+
+#[test]
+fn test_markdown_strips_scripts_and_handlers() {
+    let html = render_markdown("<script>alert(1)</script><img src=\"x.png\" onerror=\"alert(1)\">");
+    assert!(!html.contains("<script"));
+    assert!(!html.contains("onerror"));
+    assert!(html.contains("<img src=\"x.png\">"));
+}
+
+#[test]
+fn test_markdown_drops_javascript_links() {
+    let html = render_markdown("[click](javascript:alert(1)) [safe](https://example.com)");
+    assert!(!html.contains("javascript:"));
+    assert!(html.contains("href=\"https://example.com\""));
+    assert!(html.contains("rel=\"noopener noreferrer nofollow\""));
+}
@@ -0,0 +1,42 @@
+use deckoracle_backend::models::{PublicDeckQuery, PublicDeckSort};
+use deckoracle_backend::services::marketplace::{
+    normalize_language, normalize_tags, MAX_TAGS, MAX_TAG_CHARS,
+};
+
+#[test]
+fn test_tags_are_lowercased_and_deduplicated() {
+    let tags = vec![
+        " Spanish ".to_string(),
+        "verbs".to_string(),
+        "SPANISH".to_string(),
+        "  ".to_string(),
+    ];
+
+    assert_eq!(normalize_tags(&tags).unwrap(), vec!["spanish", "verbs"]);
+}
+
+#[test]
+fn test_tag_limits() {
+    let too_many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("tag{}", i)).collect();
+    assert!(normalize_tags(&too_many).is_err());
+
+    let repeated: Vec<String> = (0..=MAX_TAGS).map(|_| "same".to_string()).collect();
+    assert_eq!(normalize_tags(&repeated).unwrap(), vec!["same"]);
+
+    assert!(normalize_tags(&["a".repeat(MAX_TAG_CHARS)]).is_ok());
+    assert!(normalize_tags(&["a".repeat(MAX_TAG_CHARS + 1)]).is_err());
+}
+
+#[test]
+fn test_language_is_case_insensitive() {
+    assert_eq!(normalize_language(" pt-BR"), "pt-br");
+}
+
+#[test]
+fn test_browse_defaults_to_most_popular() {
+    let query: PublicDeckQuery = serde_json::from_str("{}").unwrap();
+    assert_eq!(query.sort, PublicDeckSort::Popular);
+
+    let query: PublicDeckQuery = serde_json::from_str(r#"{"sort": "rating"}"#).unwrap();
+    assert_eq!(query.sort, PublicDeckSort::Rating);
+}
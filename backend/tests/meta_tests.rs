@@ -0,0 +1,54 @@
+mod common;
+
+use axum::http::{header, HeaderValue};
+use axum_test::TestServer;
+use deckoracle_backend::{create_app, state::AppState};
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_meta_describes_the_server() {
+    let mut config = common::test_config();
+    config.ai.enabled = true;
+    config.ai.content_generation.max_cards_per_batch = 25;
+    config.upload.max_file_size = 1024 * 1024;
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server.get("/api/v1/meta").await;
+    response.assert_status_ok();
+    let meta: Value = response.json();
+    assert_eq!(meta["api_version"], "v1");
+    assert_eq!(meta["server_version"], env!("CARGO_PKG_VERSION"));
+    assert!(meta["formats"]["import"].as_array().unwrap().iter().any(|f| f == "csv"));
+    assert!(meta["formats"]["export"].as_array().unwrap().iter().any(|f| f["name"] == "json"));
+    assert!(meta["formats"]["json_version"].is_string());
+    assert_eq!(meta["features"]["ai_generation"], true);
+    assert_eq!(meta["features"]["pdf_export"], cfg!(feature = "pdf-export"));
+    assert_eq!(meta["limits"]["max_upload_bytes"], 1024 * 1024);
+    assert_eq!(meta["limits"]["max_ai_cards_per_request"], 25);
+
+    // Tests run with rate limiting off
+    assert_eq!(meta["features"]["rate_limiting"], false);
+    assert!(meta["limits"]["api_requests_per_minute"].is_null());
+}
+
+#[tokio::test]
+async fn test_meta_is_public_and_reveals_no_configuration_secrets() {
+    let mut config = common::test_config();
+    config.jwt.secret = "hunter2-signing-secret".to_string();
+    let database_url = config.database.url.clone();
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    // A bad token is ignored rather than refused
+    let response = server
+        .get("/api/v1/meta")
+        .add_header(header::AUTHORIZATION, HeaderValue::from_static("Bearer forged"))
+        .await;
+    response.assert_status_ok();
+
+    let body = response.text();
+    for secret in ["hunter2", database_url.as_str()] {
+        assert!(!body.contains(secret), "/meta exposes {}", secret);
+    }
+}
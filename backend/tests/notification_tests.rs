@@ -0,0 +1,18 @@
+use deckoracle_backend::services::notification::reminder_email;
+
+#[test]
+fn test_reminder_email_summarizes_due_cards() {
+    let (subject, body) = reminder_email(Some("Ana"), 12, 3, "https://app.example.com/");
+    assert_eq!(subject, "12 cards due for review");
+    assert!(body.starts_with("Hi Ana,"));
+    assert!(body.contains("12 cards due across 3 decks"));
+    assert!(body.contains("https://app.example.com/study"));
+}
+
+#[test]
+fn test_reminder_email_singular_and_no_name() {
+    let (subject, body) = reminder_email(None, 1, 1, "http://localhost:5173");
+    assert_eq!(subject, "1 card due for review");
+    assert!(body.starts_with("Hi,"));
+    assert!(body.contains("1 card due across 1 deck."));
+}
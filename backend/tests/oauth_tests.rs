@@ -0,0 +1,112 @@
+mod common;
+
+use deckoracle_backend::config::{Config, OAuthClientConfig};
+use deckoracle_backend::models::{ClientInfo, RegisterDto};
+use deckoracle_backend::services::{
+    auth::AuthService,
+    oauth::{OAuthProfile, OAuthProvider, OAuthService},
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+
+fn oauth_config() -> Config {
+    let mut config = common::test_config();
+    config.email.api_url = "https://api.example.com".to_string();
+    config.oauth.google = Some(OAuthClientConfig {
+        client_id: "google-client".to_string(),
+        client_secret: "google-secret".to_string(),
+    });
+    config.oauth.github = None;
+    config
+}
+
+fn profile(id: &str, email: &str, email_verified: bool) -> OAuthProfile {
+    OAuthProfile {
+        provider_user_id: id.to_string(),
+        email: Some(email.to_string()),
+        email_verified,
+        display_name: Some("Ada".to_string()),
+    }
+}
+
+async fn sign_in(state: &AppState, profile: OAuthProfile) -> Result<uuid::Uuid, AppError> {
+    OAuthService::sign_in(&state.db, &state.config, OAuthProvider::Google, profile, &ClientInfo::default())
+        .await
+        .map(|auth| auth.user.id)
+}
+
+#[test]
+fn test_authorization_url_and_unconfigured_providers() {
+    let config = oauth_config();
+    let provider = OAuthService::provider(&config, "google").unwrap();
+    let url = OAuthService::authorization_url(&config, provider, "abc123").unwrap();
+    assert!(url.starts_with("https://accounts.google.com/o/oauth2/v2/auth?"));
+    assert!(url.contains("client_id=google-client"));
+    assert!(url.contains(
+        "redirect_uri=https%3A%2F%2Fapi.example.com%2Fapi%2Fv1%2Fauth%2Foauth%2Fgoogle%2Fcallback"
+    ));
+    assert!(url.contains("state=abc123"));
+
+    assert!(matches!(OAuthService::provider(&config, "github"), Err(AppError::NotFound(_))));
+    assert!(matches!(OAuthService::provider(&config, "myspace"), Err(AppError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_sign_in_creates_then_reuses_account() {
+    let state = AppState::from_parts(common::setup_test_db().await, oauth_config());
+
+    let user_id = sign_in(&state, profile("g-1", "new@example.com", true)).await.unwrap();
+    let (verified, display_name) = sqlx::query_as::<_, (bool, Option<String>)>(
+        "SELECT email_verified, display_name FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    assert!(verified);
+    assert_eq!(display_name.as_deref(), Some("Ada"));
+
+    // The identity is what counts once linked, even if the email changes
+    let again = sign_in(&state, profile("g-1", "renamed@example.com", false)).await.unwrap();
+    assert_eq!(again, user_id);
+
+    let unverified = sign_in(&state, profile("g-2", "other@example.com", false)).await;
+    assert!(matches!(unverified, Err(AppError::BadRequest(_))));
+}
+
+#[tokio::test]
+async fn test_sign_in_links_only_verified_accounts() {
+    let state = AppState::from_parts(common::setup_test_db().await, oauth_config());
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "existing@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+
+    // Someone else may have registered the address without owning it
+    let refused = sign_in(&state, profile("g-3", "existing@example.com", true)).await;
+    assert!(matches!(refused, Err(AppError::BadRequest(_))));
+
+    sqlx::query("UPDATE users SET email_verified = true WHERE id = $1")
+        .bind(user_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    let linked = sign_in(&state, profile("g-3", "existing@example.com", true)).await.unwrap();
+    assert_eq!(linked, user_id);
+
+    let links: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM linked_accounts WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(links, 1);
+}
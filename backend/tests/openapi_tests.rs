@@ -0,0 +1,80 @@
+use deckoracle_backend::openapi::ApiDoc;
+use serde_json::Value;
+use utoipa::OpenApi;
+
+fn document() -> Value {
+    serde_json::to_value(ApiDoc::openapi()).unwrap()
+}
+
+#[test]
+fn test_paths_are_prefixed_with_api_version() {
+    let doc = document();
+    let paths = doc["paths"].as_object().unwrap();
+
+    for path in [
+        "/api/v1/auth/login",
+        "/api/v1/auth/refresh",
+        "/api/v1/decks",
+        "/api/v1/decks/{id}",
+        "/api/v1/cards/{id}/media",
+        "/api/v1/import-export/import",
+        "/api/v1/health",
+        "/api/v1/meta",
+    ] {
+        assert!(paths.contains_key(path), "missing {}", path);
+    }
+    assert!(paths.keys().all(|path| path.starts_with("/api/v1")));
+}
+
+#[test]
+fn test_auth_flows_are_public_and_the_rest_needs_a_bearer_token() {
+    let doc = document();
+
+    assert_eq!(doc["security"], serde_json::json!([{ "bearer_auth": [] }]));
+    assert_eq!(doc["components"]["securitySchemes"]["bearer_auth"]["scheme"], "bearer");
+    for path in ["/api/v1/auth/login", "/api/v1/auth/register", "/api/v1/auth/refresh"] {
+        assert_eq!(
+            doc["paths"][path]["post"]["security"],
+            serde_json::json!([{}]),
+            "{} should not require a token",
+            path
+        );
+    }
+    assert!(doc["paths"]["/api/v1/decks"]["get"]["security"].is_null());
+}
+
+#[test]
+fn test_every_operation_documents_the_error_envelope() {
+    let doc = document();
+    let error = &doc["components"]["schemas"]["ErrorResponse"];
+    for field in ["error", "status", "code", "details", "retry_after"] {
+        assert!(error["properties"][field].is_object(), "missing {}", field);
+    }
+
+    for item in doc["paths"].as_object().unwrap().values() {
+        for operation in item.as_object().unwrap().values() {
+            let schema = &operation["responses"]["4XX"]["content"]["application/json"]["schema"];
+            assert_eq!(schema["$ref"], "#/components/schemas/ErrorResponse");
+        }
+    }
+}
+
+#[test]
+fn test_uploads_are_documented_as_multipart() {
+    let doc = document();
+
+    for (path, method) in [
+        ("/api/v1/import-export/import", "post"),
+        ("/api/v1/import-export/import/validate", "post"),
+        ("/api/v1/cards/{id}/media", "post"),
+        ("/api/v1/ai/extract", "post"),
+    ] {
+        let content = &doc["paths"][path][method]["requestBody"]["content"];
+        assert!(
+            content["multipart/form-data"].is_object(),
+            "{} {} should take a multipart form",
+            method,
+            path
+        );
+    }
+}
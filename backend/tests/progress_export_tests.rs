@@ -0,0 +1,160 @@
+mod common;
+
+use chrono::{DateTime, Utc};
+use deckoracle_backend::models::{
+    import_export::{
+        DuplicateStrategy, ExportedDeck, HtmlHandling, ImportFormat, ImportJobParameters, TextDelimiters,
+    },
+    CreateCardDto, CreateDeckDto, CreateStudySessionDto, Rating, RegisterDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, deck::DeckService, exporters::JsonExporter,
+    import_export::ImportExportService, study::StudyService, upload::UploadSource,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// (ease_factor, interval_days, next_review_at, times_seen) of a user's card
+async fn card_stats(db: &PgPool, user_id: Uuid, card_id: Uuid) -> Option<(f32, i32, Option<DateTime<Utc>>, i32)> {
+    sqlx::query_as::<_, (f32, i32, Option<DateTime<Utc>>, i32)>(
+        "SELECT ease_factor, interval_days, next_review_at, times_seen FROM user_card_stats WHERE user_id = $1 AND card_id = $2",
+    )
+    .bind(user_id)
+    .bind(card_id)
+    .fetch_optional(db)
+    .await
+    .unwrap()
+}
+
+fn import_params(include_progress: bool) -> ImportJobParameters {
+    ImportJobParameters {
+        format: ImportFormat::Json,
+        folder_id: None,
+        merge_duplicates: false,
+        delimiters: TextDelimiters::default(),
+        include_progress,
+        duplicate_strategy: DuplicateStrategy::default(),
+        html: HtmlHandling::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_json_progress_round_trips_into_the_same_account() {
+    let state = common::create_test_state().await;
+    let register = |email: &str| RegisterDto {
+        email: email.to_string(),
+        password: "Password123".to_string(),
+        display_name: None,
+    };
+    let user_id = AuthService::register(&state.db, &state.config, register("roundtrip@example.com"))
+        .await
+        .unwrap()
+        .user
+        .id;
+    let other_id = AuthService::register(&state.db, &state.config, register("other@example.com"))
+        .await
+        .unwrap()
+        .user
+        .id;
+
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut cards = Vec::new();
+    for (front, back) in [("France", "Paris"), ("Spain", "Madrid")] {
+        let card = CardService::create_card(
+            &state.db,
+            deck.id,
+            user_id,
+            CreateCardDto {
+                front: front.to_string(),
+                back: back.to_string(),
+                position: None,
+            },
+        )
+        .await
+        .unwrap();
+        cards.push(card);
+    }
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck.id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+    StudyService::record_answer(&state.db, &session, cards[0].id, Rating::Easy, None)
+        .await
+        .unwrap();
+    let studied = card_stats(&state.db, user_id, cards[0].id).await.unwrap();
+
+    let data = ImportExportService::export_deck(&state.db, &state.media, user_id, deck.id, &JsonExporter, true, false)
+        .await
+        .unwrap();
+    let exported: ExportedDeck = serde_json::from_slice(&data).unwrap();
+    assert!(exported.metadata.includes_progress);
+    assert_eq!(exported.metadata.exported_by, Some(user_id));
+    let progress = exported.cards[0].progress.as_ref().unwrap();
+    assert_eq!((progress.review_count, progress.correct_count), (1, 1));
+    assert_eq!(progress.interval_days, studied.1);
+    assert!(exported.cards[1].progress.is_none());
+
+    // Without progress nothing about the user is written
+    let plain = ImportExportService::export_deck(&state.db, &state.media, user_id, deck.id, &JsonExporter, false, false)
+        .await
+        .unwrap();
+    let plain: ExportedDeck = serde_json::from_slice(&plain).unwrap();
+    assert_eq!(plain.metadata.exported_by, None);
+    assert!(plain.cards.iter().all(|c| c.progress.is_none()));
+
+    DeckService::delete_deck(&state.db, deck.id, user_id).await.unwrap();
+    let result = ImportExportService::import_decks(&state.db, &state.media, user_id, UploadSource::Bytes(&data), &import_params(true), None)
+        .await
+        .unwrap();
+    assert!(result.warnings.is_empty());
+    let imported_cards = sqlx::query_scalar::<_, Uuid>("SELECT id FROM cards WHERE deck_id = $1 ORDER BY position")
+        .bind(result.imported_decks[0].id)
+        .fetch_all(&state.db)
+        .await
+        .unwrap();
+    let restored = card_stats(&state.db, user_id, imported_cards[0]).await.unwrap();
+    assert!((restored.0 - studied.0).abs() < 1e-6);
+    assert_eq!((restored.1, restored.2, restored.3), (studied.1, studied.2, studied.3));
+    assert!(card_stats(&state.db, user_id, imported_cards[1]).await.is_none());
+
+    // Someone else's copy of the file gets the cards but not the schedule
+    let result = ImportExportService::import_decks(&state.db, &state.media, other_id, UploadSource::Bytes(&data), &import_params(true), None)
+        .await
+        .unwrap();
+    assert_eq!(result.total_cards_imported, 2);
+    assert_eq!(
+        result.warnings,
+        ["Review history of 1 cards was not imported because it was exported from another account"]
+    );
+    let other_cards = sqlx::query_scalar::<_, Uuid>("SELECT id FROM cards WHERE deck_id = $1")
+        .bind(result.imported_decks[0].id)
+        .fetch_all(&state.db)
+        .await
+        .unwrap();
+    for card_id in other_cards {
+        assert!(card_stats(&state.db, other_id, card_id).await.is_none());
+    }
+}
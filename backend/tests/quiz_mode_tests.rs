@@ -0,0 +1,37 @@
+use deckoracle_backend::models::DistractorSource;
+use deckoracle_backend::services::quiz_session::{build_options, DISTRACTOR_COUNT};
+
+fn answers(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn test_options_shuffle_in_the_answer() {
+    let (options, correct_index, source) =
+        build_options("Paris", &answers(&["Lyon", "Nice", "Lille"]), &[]).unwrap();
+
+    assert_eq!(options.len(), DISTRACTOR_COUNT + 1);
+    assert_eq!(options[correct_index], "Paris");
+    assert_eq!(source, DistractorSource::Deck);
+}
+
+#[test]
+fn test_options_skip_duplicates_of_the_answer() {
+    // " paris " is the same answer and "lyon" repeats "Lyon"
+    let deck = answers(&[" paris ", "Lyon", "lyon", "Nice"]);
+    assert!(build_options("Paris", &deck, &[]).is_none());
+
+    let (options, correct_index, source) =
+        build_options("Paris", &deck, &answers(&["Paris", "Lille", "Brest"])).unwrap();
+    assert_eq!(options.len(), 4);
+    assert_eq!(options[correct_index], "Paris");
+    assert!(options.contains(&"Lille".to_string()));
+    assert_eq!(source, DistractorSource::Mixed);
+}
+
+#[test]
+fn test_options_fall_back_to_ai_distractors() {
+    let (_, _, source) =
+        build_options("Paris", &[], &answers(&["Lyon", "Nice", "Lille", "Brest"])).unwrap();
+    assert_eq!(source, DistractorSource::Ai);
+}
@@ -0,0 +1,90 @@
+use deckoracle_backend::{
+    models::import_export::TextDelimiters,
+    services::quizlet::{self, detect_delimiters, TEXT_SET_TITLE},
+};
+
+fn terms(data: &str, delimiters: &TextDelimiters) -> Vec<(String, String)> {
+    let parsed = quizlet::parse(data.as_bytes(), delimiters).unwrap();
+    parsed.sets[0]
+        .terms
+        .iter()
+        .map(|t| (t.term.clone(), t.definition.clone()))
+        .collect()
+}
+
+#[test]
+fn test_default_text_export() {
+    let parsed = quizlet::parse(b"cat\tgato\r\ndog\tperro\r\n", &TextDelimiters::default()).unwrap();
+    assert_eq!(parsed.sets.len(), 1);
+    assert_eq!(parsed.sets[0].title, TEXT_SET_TITLE);
+    assert_eq!(parsed.sets[0].terms.len(), 2);
+    assert_eq!(parsed.sets[0].terms[1].definition, "perro");
+    assert_eq!(parsed.skipped_rows, 0);
+}
+
+#[test]
+fn test_detects_comma_and_semicolon() {
+    assert_eq!(detect_delimiters("cat,gato;dog,perro;", None, None), (",", ";"));
+    assert_eq!(
+        terms("cat,gato;dog,perro;", &TextDelimiters::default()),
+        vec![("cat".into(), "gato".into()), ("dog".into(), "perro".into())]
+    );
+}
+
+#[test]
+fn test_prefers_dash_over_commas_in_definitions() {
+    let text = "run - to move fast, on foot\nwalk - to move, slowly";
+    assert_eq!(detect_delimiters(text, None, None), (" - ", "\n"));
+}
+
+#[test]
+fn test_custom_delimiters_are_unescaped() {
+    let delimiters = TextDelimiters {
+        term: Some(" => ".to_string()),
+        row: Some("\\n\\n".to_string()),
+    };
+    assert_eq!(
+        terms("one => uno\n\ntwo => dos, 2\n\n", &delimiters),
+        vec![("one".into(), "uno".into()), ("two".into(), "dos, 2".into())]
+    );
+}
+
+#[test]
+fn test_rows_without_definition_are_skipped() {
+    let parsed = quizlet::parse(b"cat\tgato\nlonely\n\tno term\n", &TextDelimiters::default()).unwrap();
+    assert_eq!(parsed.sets[0].terms.len(), 1);
+    assert_eq!(parsed.skipped_rows, 2);
+}
+
+#[test]
+fn test_equal_delimiters_are_rejected() {
+    let delimiters = TextDelimiters {
+        term: Some(";".to_string()),
+        row: Some(";".to_string()),
+    };
+    assert!(quizlet::parse(b"a;b", &delimiters).is_err());
+}
+
+#[test]
+fn test_api_json_sets_are_sorted_by_rank() {
+    let json = r#"[
+        {"title": "Verbs", "terms": [
+            {"term": "ir", "definition": "to go", "rank": 1},
+            {"term": "ser", "definition": "to be", "rank": 0}
+        ]},
+        {"title": "Nouns", "description": "Basics", "terms": []}
+    ]"#;
+    let parsed = quizlet::parse(json.as_bytes(), &TextDelimiters::default()).unwrap();
+    assert_eq!(parsed.sets.len(), 2);
+    assert_eq!(parsed.sets[0].title, "Verbs");
+    assert_eq!(parsed.sets[0].terms[0].term, "ser");
+    assert_eq!(parsed.sets[1].description.as_deref(), Some("Basics"));
+}
+
+#[test]
+fn test_single_set_json() {
+    let json = r#"{"title": "Colors", "terms": [{"term": "rojo", "definition": "red"}]}"#;
+    let parsed = quizlet::parse(json.as_bytes(), &TextDelimiters::default()).unwrap();
+    assert_eq!(parsed.sets[0].title, "Colors");
+    assert_eq!(parsed.sets[0].terms[0].definition, "red");
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    middleware::from_fn_with_state,
+    routing::get,
+    Router,
+};
+use axum_test::TestServer;
+use deckoracle_backend::middleware::rate_limit::{
+    backend_from_config, rate_limit_middleware, MemoryRateLimitBackend, RateLimitBackend,
+    RateLimitConfig, RateLimitStore,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn limited_server(store: RateLimitStore) -> TestServer {
+    let app = Router::new()
+        .route("/", get(|| async { "ok" }))
+        .layer(from_fn_with_state(store, rate_limit_middleware));
+    TestServer::new(app).unwrap()
+}
+
+fn two_per_minute() -> RateLimitStore {
+    RateLimitStore::new(RateLimitConfig {
+        max_requests: 2,
+        window_seconds: 60,
+    })
+}
+
+async fn status(server: &TestServer, authorization: Option<&str>, forwarded_for: Option<&str>) -> StatusCode {
+    let mut request = server.get("/");
+    if let Some(authorization) = authorization {
+        request = request.add_header(header::AUTHORIZATION, HeaderValue::from_str(authorization).unwrap());
+    }
+    if let Some(forwarded_for) = forwarded_for {
+        request = request.add_header("x-forwarded-for", HeaderValue::from_str(forwarded_for).unwrap());
+    }
+    request.await.status_code()
+}
+
+#[tokio::test]
+async fn test_signed_in_requests_are_limited_per_user() {
+    let state = common::create_test_state().await;
+    let (_, alice) = common::register_with_token(&state, "alice@example.com").await;
+    let (_, bob) = common::register_with_token(&state, "bob@example.com").await;
+    let server = limited_server(two_per_minute().keyed_by_user(state.config.clone()));
+
+    for _ in 0..2 {
+        assert_eq!(status(&server, Some(&alice), None).await, StatusCode::OK);
+    }
+    assert_eq!(status(&server, Some(&alice), None).await, StatusCode::TOO_MANY_REQUESTS);
+
+    // Other users, and anonymous clients on the same address, have their own buckets
+    assert_eq!(status(&server, Some(&bob), None).await, StatusCode::OK);
+    assert_eq!(status(&server, None, None).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_invalid_tokens_count_against_the_client_address() {
+    let state = common::create_test_state().await;
+    let (_, alice) = common::register_with_token(&state, "alice@example.com").await;
+    let server = limited_server(two_per_minute().keyed_by_user(state.config.clone()));
+
+    // Made-up tokens cannot mint a fresh bucket each
+    assert_eq!(status(&server, Some("Bearer forged-1"), None).await, StatusCode::OK);
+    assert_eq!(status(&server, Some("Bearer forged-2"), None).await, StatusCode::OK);
+    assert_eq!(status(&server, None, None).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(status(&server, Some("Bearer forged-3"), None).await, StatusCode::TOO_MANY_REQUESTS);
+
+    assert_eq!(status(&server, Some(&alice), None).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_ip_keyed_limits_ignore_tokens() {
+    let state = common::create_test_state().await;
+    let (_, alice) = common::register_with_token(&state, "alice@example.com").await;
+    let (_, bob) = common::register_with_token(&state, "bob@example.com").await;
+    let server = limited_server(two_per_minute());
+
+    assert_eq!(status(&server, Some(&alice), None).await, StatusCode::OK);
+    assert_eq!(status(&server, Some(&bob), None).await, StatusCode::OK);
+    assert_eq!(status(&server, Some(&alice), None).await, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_forwarded_for_is_only_used_when_trusted() {
+    // Without a trusted proxy a client-supplied header changes nothing
+    let server = limited_server(two_per_minute());
+    assert_eq!(status(&server, None, Some("203.0.113.1")).await, StatusCode::OK);
+    assert_eq!(status(&server, None, Some("203.0.113.2")).await, StatusCode::OK);
+    assert_eq!(status(&server, None, Some("203.0.113.3")).await, StatusCode::TOO_MANY_REQUESTS);
+
+    // Behind one, the address the proxy appended is the client
+    let server = limited_server(two_per_minute().trust_forwarded_for(true));
+    for _ in 0..2 {
+        assert_eq!(status(&server, None, Some("198.51.100.9, 203.0.113.1")).await, StatusCode::OK);
+    }
+    assert_eq!(status(&server, None, Some("203.0.113.1")).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(status(&server, None, Some("203.0.113.1, 203.0.113.2")).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_memory_backend_keeps_a_sliding_window_per_key() {
+    let backend = MemoryRateLimitBackend::default();
+    let config = RateLimitConfig {
+        max_requests: 2,
+        window_seconds: 1,
+    };
+
+    assert_eq!(backend.hit("a", &config).await, Ok(true));
+    assert_eq!(backend.hit("a", &config).await, Ok(true));
+    assert_eq!(backend.hit("a", &config).await, Ok(false));
+    assert_eq!(backend.hit("b", &config).await, Ok(true));
+
+    // Refused requests do not extend the wait
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(backend.hit("a", &config).await, Ok(true));
+
+    // Cleanup only drops history that has left the window
+    backend.cleanup(&config).await;
+    assert_eq!(backend.hit("a", &config).await, Ok(true));
+    assert_eq!(backend.hit("a", &config).await, Ok(false));
+}
+
+#[tokio::test]
+async fn test_stores_sharing_a_backend_keep_separate_buckets() {
+    let backend: Arc<dyn RateLimitBackend> = Arc::new(MemoryRateLimitBackend::default());
+    let auth = limited_server(two_per_minute().with_backend(backend.clone(), "auth"));
+    let api = limited_server(two_per_minute().with_backend(backend.clone(), "api"));
+    let api_replica = limited_server(two_per_minute().with_backend(backend, "api"));
+
+    for _ in 0..2 {
+        assert_eq!(status(&auth, None, None).await, StatusCode::OK);
+    }
+    assert_eq!(status(&auth, None, None).await, StatusCode::TOO_MANY_REQUESTS);
+
+    // The same group on another replica shares the bucket; other groups do not
+    assert_eq!(status(&api, None, None).await, StatusCode::OK);
+    assert_eq!(status(&api_replica, None, None).await, StatusCode::OK);
+    assert_eq!(status(&api, None, None).await, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_backend_from_config_falls_back_to_memory() {
+    let config = RateLimitConfig {
+        max_requests: 1,
+        window_seconds: 60,
+    };
+
+    // No Redis configured, or a URL that cannot be used: limits still apply
+    for redis_url in [None, Some("not a redis url".to_string())] {
+        let mut app_config = common::test_config();
+        app_config.rate_limit.redis_url = redis_url;
+        let backend = backend_from_config(&app_config);
+        assert_eq!(backend.hit("client", &config).await, Ok(true));
+        assert_eq!(backend.hit("client", &config).await, Ok(false));
+    }
+}
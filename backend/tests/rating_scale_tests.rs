@@ -0,0 +1,52 @@
+use deckoracle_backend::models::{CardStatus, Rating, RatingScale};
+use deckoracle_backend::services::scheduler::Sm2Scheduler;
+
+#[test]
+fn test_scales_offer_anki_buttons() {
+    assert_eq!(RatingScale::TwoButton.ratings(), &[Rating::Again, Rating::Good]);
+    assert_eq!(
+        RatingScale::ThreeButton.ratings(),
+        &[Rating::Again, Rating::Good, Rating::Easy]
+    );
+    assert_eq!(RatingScale::FourButton.buttons(), 4);
+
+    assert!(!RatingScale::TwoButton.allows(Rating::Hard));
+    assert!(!RatingScale::ThreeButton.allows(Rating::Hard));
+    assert!(RatingScale::FourButton.allows(Rating::Hard));
+
+    assert_eq!(RatingScale::from_buttons(3), Some(RatingScale::ThreeButton));
+    assert_eq!(RatingScale::from_buttons(5), None);
+}
+
+#[test]
+fn test_legacy_statuses_keep_their_quality() {
+    let statuses = [
+        (CardStatus::Easy, 5),
+        (CardStatus::Medium, 4),
+        (CardStatus::Hard, 3),
+        (CardStatus::Forgot, 1),
+    ];
+
+    for (status, quality) in statuses {
+        let rating = Rating::from_status(status);
+        assert_eq!(Sm2Scheduler::quality_for_rating(rating), quality);
+        assert_eq!(Sm2Scheduler::quality_for_status(status), quality);
+        assert!(matches!(
+            (CardStatus::from_rating(rating), status),
+            (CardStatus::Easy, CardStatus::Easy)
+                | (CardStatus::Medium, CardStatus::Medium)
+                | (CardStatus::Hard, CardStatus::Hard)
+                | (CardStatus::Forgot, CardStatus::Forgot)
+        ));
+    }
+}
+
+#[test]
+fn test_only_again_is_incorrect() {
+    // Analytics and the scheduler agree: every rating but "again" is a pass
+    for rating in RatingScale::FourButton.ratings() {
+        let passed = Sm2Scheduler::quality_for_rating(*rating) >= 3;
+        assert_eq!(rating.is_correct(), passed);
+    }
+    assert!(!Rating::Again.is_correct());
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use deckoracle_backend::models::{
+    ai::{BatchAnalyticsEvent, CreateStudyEventDto, RecommendationFeedbackDto, UpdatePrivacySettingsDto},
+    CreateCardDto, CreateDeckDto, CreateStudySessionDto, Rating, RegisterDto,
+};
+use deckoracle_backend::services::{
+    ai_privacy::AiPrivacyService,
+    auth::AuthService,
+    card::CardService,
+    deck::DeckService,
+    recommendation::{best_study_window, RecommendationService},
+    study::StudyService,
+    study_events::StudyEventService,
+};
+use uuid::Uuid;
+
+#[test]
+fn test_best_study_window() {
+    // 90% right around 09:00, 40% in the evening
+    let hours = [(9, 30, 27), (10, 10, 9), (21, 40, 16)];
+    let window = best_study_window(&hours).unwrap();
+    assert_eq!(window.start_hour, 9);
+    assert_eq!(window.answers, 40);
+    assert!((window.accuracy - 0.9).abs() < 1e-9);
+    assert!((window.overall_accuracy - 0.65).abs() < 1e-9);
+
+    // Windows wrap around midnight
+    let hours = [(23, 20, 19), (0, 20, 19), (12, 40, 20)];
+    assert_eq!(best_study_window(&hours).unwrap().start_hour, 23);
+
+    // Too few answers, or no time of day clearly better than the rest
+    assert_eq!(best_study_window(&[(9, 30, 27)]), None);
+    assert_eq!(best_study_window(&[(9, 40, 30), (21, 40, 30)]), None);
+}
+
+fn answers(card_id: Uuid, deck_id: Uuid, hour: u32, count: usize, correct: usize) -> Vec<CreateStudyEventDto> {
+    let day = (Utc::now() - Duration::days(1)).date_naive();
+    (0..count)
+        .map(|i| CreateStudyEventDto {
+            card_id,
+            deck_id,
+            session_id: None,
+            event_type: "answer".to_string(),
+            outcome: Some(if i < correct { "correct" } else { "incorrect" }.to_string()),
+            response_time_ms: Some(3000),
+            confidence_rating: None,
+            occurred_at: Some(day.and_hms_opt(hour, i as u32, 0).unwrap().and_utc()),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_recommendations_from_study_history() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "recommend@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+
+    let mut decks = Vec::new();
+    for name in ["History", "Chemistry"] {
+        let deck = DeckService::create_deck(
+            &state.db,
+            user_id,
+            CreateDeckDto {
+                name: name.to_string(),
+                description: None,
+                folder_id: None,
+                is_public: None,
+                front_language: None,
+                back_language: None,
+            },
+        )
+        .await
+        .unwrap();
+        let card = CardService::create_card(
+            &state.db,
+            deck.id,
+            user_id,
+            CreateCardDto {
+                front: format!("{} question", name),
+                back: "answer".to_string(),
+                position: None,
+            },
+        )
+        .await
+        .unwrap();
+        decks.push((deck.id, card.id));
+    }
+    let (history, history_card) = decks[0];
+    let (chemistry, chemistry_card) = decks[1];
+    sqlx::query("UPDATE decks SET tags = '{chemistry}' WHERE id = $1")
+        .bind(chemistry)
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    // Mornings go well; evening chemistry does not
+    let mut events = answers(history_card, history, 9, 15, 14);
+    events.extend(answers(history_card, history, 10, 15, 14));
+    events.extend(answers(chemistry_card, chemistry, 21, 30, 6));
+    StudyEventService::ingest_batch(
+        &state.db,
+        user_id,
+        BatchAnalyticsEvent {
+            events,
+            batch_id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+        },
+    )
+    .await
+    .unwrap();
+
+    // A review of the history card that is now three days overdue
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(history),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+    StudyService::record_answer(&state.db, &session, history_card, Rating::Good, None)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE user_card_stats SET next_review_at = NOW() - INTERVAL '3 days' WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    let recommendations = RecommendationService::list(&state.db, user_id).await.unwrap();
+    let mut kinds: Vec<&str> = recommendations.iter().map(|r| r.recommendation_type.as_str()).collect();
+    kinds.sort();
+    assert_eq!(kinds, ["deck_suggestion", "review_schedule", "study_time"]);
+    assert!(recommendations.iter().all(|r| r.shown_at.is_some() && r.expires_at.is_some()));
+
+    let study_time = recommendations.iter().find(|r| r.recommendation_type == "study_time").unwrap();
+    assert_eq!(study_time.payload["action_data"]["start_hour"], 9);
+    assert_eq!(study_time.payload["action_data"]["timezone"], "UTC");
+    let overdue = recommendations.iter().find(|r| r.recommendation_type == "review_schedule").unwrap();
+    assert_eq!(overdue.payload["action_data"]["deck_id"], history.to_string());
+    let weak = recommendations.iter().find(|r| r.recommendation_type == "deck_suggestion").unwrap();
+    assert_eq!(weak.payload["action_data"]["tag"], "chemistry");
+
+    // Listing again does not regenerate
+    let again = RecommendationService::list(&state.db, user_id).await.unwrap();
+    assert_eq!(again.len(), 3);
+    assert!(again.iter().any(|r| r.id == weak.id));
+
+    let updated = RecommendationService::record_feedback(
+        &state.db,
+        user_id,
+        RecommendationFeedbackDto {
+            recommendation_id: weak.id,
+            feedback: "not_helpful".to_string(),
+            accepted: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.feedback.as_deref(), Some("not_helpful"));
+    assert!(RecommendationService::record_feedback(
+        &state.db,
+        user_id,
+        RecommendationFeedbackDto {
+            recommendation_id: weak.id,
+            feedback: "meh".to_string(),
+            accepted: false,
+        },
+    )
+    .await
+    .is_err());
+
+    // Regenerating replaces the pending ones and leaves out the dismissed tag
+    RecommendationService::refresh(&state.db, user_id).await.unwrap();
+    let refreshed = RecommendationService::list(&state.db, user_id).await.unwrap();
+    let mut kinds: Vec<&str> = refreshed.iter().map(|r| r.recommendation_type.as_str()).collect();
+    kinds.sort();
+    assert_eq!(kinds, ["review_schedule", "study_time"]);
+    assert!(refreshed.iter().all(|r| r.id != study_time.id && r.id != overdue.id));
+
+    AiPrivacyService::update(
+        &state.db,
+        user_id,
+        UpdatePrivacySettingsDto {
+            track_analytics: None,
+            enable_ai_recommendations: Some(false),
+            enable_content_generation: None,
+            share_anonymous_data: None,
+            personalized_learning: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(RecommendationService::list(&state.db, user_id).await.unwrap().is_empty());
+}
@@ -0,0 +1,76 @@
+use axum::{middleware::from_fn, routing::get, Router};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    middleware::request_id::{
+        current_request_id, is_valid_request_id, request_id, MAX_REQUEST_ID_LEN, REQUEST_ID_HEADER,
+    },
+    utils::{AppError, Result},
+};
+use uuid::Uuid;
+
+fn app() -> Router {
+    Router::new()
+        .route("/ok", get(|| async { current_request_id().unwrap_or_default() }))
+        .route("/missing", get(|| async { Result::<()>::Err(AppError::DeckNotFound) }))
+        .layer(from_fn(request_id))
+}
+
+#[tokio::test]
+async fn test_generates_an_id_when_none_is_sent() {
+    let server = TestServer::new(app()).unwrap();
+    let response = server.get("/ok").await;
+
+    let id = response.header(&REQUEST_ID_HEADER);
+    let id = id.to_str().unwrap();
+    assert!(Uuid::parse_str(id).is_ok());
+    // Handlers see the same id
+    assert_eq!(response.text(), id);
+}
+
+#[tokio::test]
+async fn test_propagates_the_callers_id() {
+    let server = TestServer::new(app()).unwrap();
+    let response = server
+        .get("/ok")
+        .add_header(REQUEST_ID_HEADER.clone(), "edge-7f3a.42".parse().unwrap())
+        .await;
+
+    assert_eq!(response.header(&REQUEST_ID_HEADER), "edge-7f3a.42");
+    assert_eq!(response.text(), "edge-7f3a.42");
+}
+
+#[tokio::test]
+async fn test_replaces_unusable_ids() {
+    let server = TestServer::new(app()).unwrap();
+    let response = server
+        .get("/ok")
+        .add_header(REQUEST_ID_HEADER.clone(), "id with spaces".parse().unwrap())
+        .await;
+
+    let id = response.header(&REQUEST_ID_HEADER);
+    assert!(Uuid::parse_str(id.to_str().unwrap()).is_ok());
+}
+
+#[tokio::test]
+async fn test_error_responses_carry_the_id() {
+    let server = TestServer::new(app()).unwrap();
+    let response = server
+        .get("/missing")
+        .add_header(REQUEST_ID_HEADER.clone(), "req-123".parse().unwrap())
+        .await;
+
+    response.assert_status_not_found();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["request_id"], "req-123");
+    assert_eq!(body["code"], "DECK_NOT_FOUND");
+}
+
+#[test]
+fn test_request_id_validation() {
+    assert!(is_valid_request_id("3f0a9c2e-5b1d-4e7a-9c1f-2d8b6e4a7c10"));
+    assert!(is_valid_request_id("lb:edge_01.42"));
+    assert!(!is_valid_request_id(""));
+    assert!(!is_valid_request_id("line\nbreak"));
+    assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    assert!(current_request_id().is_none());
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use deckoracle_backend::models::{
+    ai::StudyQueueQuery, CreateCardDto, CreateDeckDto, CreateStudySessionDto, Rating, RegisterDto,
+    UpdateCardDto, UpdateDeckSettingsDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, deck::DeckService, deck_settings::DeckSettingsService,
+    study::StudyService, study_queue::{space_siblings, StudyQueueService},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// (front, back, reverse_of) of the deck's live cards in order
+async fn deck_cards(db: &PgPool, deck_id: Uuid) -> Vec<(String, String, Option<Uuid>)> {
+    sqlx::query_as::<_, (String, String, Option<Uuid>)>(
+        "SELECT front, back, reverse_of FROM cards WHERE deck_id = $1 AND deleted_at IS NULL ORDER BY position",
+    )
+    .bind(deck_id)
+    .fetch_all(db)
+    .await
+    .unwrap()
+}
+
+fn queue_query(deck_id: Uuid) -> StudyQueueQuery {
+    StudyQueueQuery {
+        deck_id: Some(deck_id),
+        folder_id: None,
+        smart_deck_id: None,
+        max_new_cards: None,
+        focus_weak_cards: None,
+        include_overdue: None,
+        prioritize_starred: None,
+    }
+}
+
+async fn set_reverse_cards(db: &PgPool, deck_id: Uuid, user_id: Uuid, enabled: bool) {
+    let settings = DeckSettingsService::update_settings(
+        db,
+        deck_id,
+        user_id,
+        UpdateDeckSettingsDto {
+            generate_reverse_cards: Some(enabled),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(settings.generate_reverse_cards, enabled);
+}
+
+#[test]
+fn test_space_siblings_keeps_pairs_apart() {
+    let (a, b, c, d) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let cards = vec![(a, 1), (a, 2), (b, 1), (c, 1), (d, 1)];
+
+    let spaced = space_siblings(cards.clone(), |card| card.0, 2);
+    assert_eq!(spaced, vec![(a, 1), (b, 1), (c, 1), (a, 2), (d, 1)]);
+
+    // Siblings that can never be far enough apart still come last
+    let spaced = space_siblings(cards, |card| card.0, 10);
+    assert_eq!(spaced, vec![(a, 1), (b, 1), (c, 1), (d, 1), (a, 2)]);
+}
+
+#[tokio::test]
+async fn test_reverse_siblings_are_generated_synced_and_buried() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "reverse@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Spanish words".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let create = |front: &str, back: &str| CreateCardDto {
+        front: front.to_string(),
+        back: back.to_string(),
+        position: None,
+    };
+    let perro = CardService::create_card(&state.db, deck.id, user_id, create("perro", "dog"))
+        .await
+        .unwrap();
+    CardService::create_card(&state.db, deck.id, user_id, create("gato", "cat"))
+        .await
+        .unwrap();
+
+    set_reverse_cards(&state.db, deck.id, user_id, true).await;
+    let cards = deck_cards(&state.db, deck.id).await;
+    assert_eq!(cards.len(), 4);
+    assert_eq!((cards[2].0.as_str(), cards[2].1.as_str()), ("dog", "perro"));
+    assert_eq!(cards[2].2, Some(perro.id));
+
+    // New cards get a reverse right away, and edits carry over to it
+    CardService::create_card(&state.db, deck.id, user_id, create("casa", "house"))
+        .await
+        .unwrap();
+    CardService::update_card(
+        &state.db,
+        perro.id,
+        user_id,
+        UpdateCardDto {
+            front: None,
+            back: Some("the dog".to_string()),
+            position: None,
+            updated_at: None,
+        },
+    )
+    .await
+    .unwrap();
+    let cards = deck_cards(&state.db, deck.id).await;
+    assert_eq!(cards.len(), 6);
+    assert!(cards.iter().any(|c| c.0 == "house" && c.1 == "casa"));
+    assert!(cards.iter().any(|c| c.0 == "the dog" && c.1 == "perro" && c.2 == Some(perro.id)));
+
+    // Only one direction of each pair is queued
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(deck.id))
+        .await
+        .unwrap();
+    assert_eq!(queue.counts.new, 3);
+    assert_eq!(queue.counts.buried, 3);
+    assert!(queue.new.iter().any(|s| s.card_id == perro.id));
+
+    // Once one direction is answered, the other waits until tomorrow
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck.id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+    StudyService::record_answer(&state.db, &session, perro.id, Rating::Good, None)
+        .await
+        .unwrap();
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(deck.id))
+        .await
+        .unwrap();
+    assert_eq!(queue.counts.new, 2);
+    assert_eq!(queue.counts.buried, 3);
+    let reverse_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM cards WHERE reverse_of = $1")
+        .bind(perro.id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert!(queue.new.iter().chain(&queue.learning).all(|s| s.card_id != reverse_id));
+
+    // Without burying, both directions are queued
+    DeckSettingsService::update_settings(
+        &state.db,
+        deck.id,
+        user_id,
+        UpdateDeckSettingsDto {
+            bury_siblings: Some(false),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(deck.id))
+        .await
+        .unwrap();
+    assert_eq!(queue.counts.new, 5);
+    assert_eq!(queue.counts.buried, 0);
+    assert!(queue.new.iter().any(|s| s.card_id == reverse_id));
+
+    // Turning the setting off trashes the reverses; turning it back on restores them
+    set_reverse_cards(&state.db, deck.id, user_id, false).await;
+    assert_eq!(deck_cards(&state.db, deck.id).await.len(), 3);
+    set_reverse_cards(&state.db, deck.id, user_id, true).await;
+    let cards = deck_cards(&state.db, deck.id).await;
+    assert_eq!(cards.len(), 6);
+    assert_eq!(cards.iter().filter(|c| c.2 == Some(perro.id)).count(), 1);
+
+    // Deleting a card takes its reverse with it
+    CardService::delete_card(&state.db, perro.id, user_id).await.unwrap();
+    assert_eq!(deck_cards(&state.db, deck.id).await.len(), 4);
+}
@@ -0,0 +1,131 @@
+use deckoracle_backend::models::{
+    ai::SpacedRepetitionParams, DeckSettings, LeitnerDemotion, LeitnerPromotion, SchedulingAlgorithm,
+};
+use deckoracle_backend::services::scheduler::{self, LeitnerScheduler, DEFAULT_EASE_FACTOR};
+use uuid::Uuid;
+
+const GOOD: i32 = 4;
+const EASY: i32 = 5;
+const HARD: i32 = 3;
+const AGAIN: i32 = 1;
+
+fn leitner(boxes: i32, promotion: LeitnerPromotion, demotion: LeitnerDemotion) -> LeitnerScheduler {
+    LeitnerScheduler {
+        boxes,
+        promotion,
+        demotion,
+    }
+}
+
+fn default_leitner() -> LeitnerScheduler {
+    leitner(5, LeitnerPromotion::Step, LeitnerDemotion::FirstBox)
+}
+
+fn params(leitner_box: Option<i32>, interval: i32, quality: i32) -> SpacedRepetitionParams {
+    SpacedRepetitionParams {
+        algorithm: String::new(),
+        ease_factor: DEFAULT_EASE_FACTOR,
+        interval,
+        repetitions: 0,
+        leitner_box,
+        quality,
+    }
+}
+
+/// Answer `qualities` in turn with `settings`, returning each new interval
+fn intervals(settings: &DeckSettings, qualities: &[i32]) -> Vec<i32> {
+    let mut state = params(None, 0, 0);
+    let mut intervals = Vec::new();
+    for &quality in qualities {
+        let next = scheduler::schedule(settings, &SpacedRepetitionParams { quality, ..state });
+        intervals.push(next.next_interval);
+        state = SpacedRepetitionParams {
+            ease_factor: next.next_ease_factor,
+            interval: next.next_interval,
+            repetitions: next.next_repetitions,
+            leitner_box: next.next_leitner_box,
+            ..params(None, 0, 0)
+        };
+    }
+    intervals
+}
+
+#[test]
+fn test_leitner_intervals_double_per_box() {
+    let scheduler = default_leitner();
+    let intervals: Vec<i32> = (0..=5).map(|n| scheduler.interval_for(n)).collect();
+
+    assert_eq!(intervals, vec![1, 1, 2, 4, 8, 16]);
+    assert_eq!(leitner(8, LeitnerPromotion::Step, LeitnerDemotion::FirstBox).interval_for(8), 128);
+}
+
+#[test]
+fn test_leitner_promotion_rules() {
+    let step = default_leitner();
+    let next = step.schedule(&params(Some(2), 2, EASY));
+    assert_eq!((next.next_leitner_box, next.next_interval), (Some(3), 4));
+
+    let skip = leitner(5, LeitnerPromotion::SkipOnEasy, LeitnerDemotion::FirstBox);
+    let next = skip.schedule(&params(Some(2), 2, EASY));
+    assert_eq!((next.next_leitner_box, next.next_interval), (Some(4), 8));
+    let next = skip.schedule(&params(Some(2), 2, GOOD));
+    assert_eq!(next.next_leitner_box, Some(3));
+
+    // Hard keeps the card in place, and promotion stops at the last box
+    assert_eq!(step.schedule(&params(Some(3), 4, HARD)).next_leitner_box, Some(3));
+    assert_eq!(skip.schedule(&params(Some(4), 8, EASY)).next_leitner_box, Some(5));
+}
+
+#[test]
+fn test_leitner_demotion_rules() {
+    let first_box = default_leitner();
+    let next = first_box.schedule(&params(Some(4), 8, AGAIN));
+    assert_eq!((next.next_leitner_box, next.next_interval), (Some(0), 1));
+    assert_eq!(next.next_repetitions, 0);
+    assert_eq!(next.next_ease_factor, DEFAULT_EASE_FACTOR);
+
+    let previous_box = leitner(5, LeitnerPromotion::Step, LeitnerDemotion::PreviousBox);
+    let next = previous_box.schedule(&params(Some(4), 8, AGAIN));
+    assert_eq!((next.next_leitner_box, next.next_interval), (Some(3), 4));
+}
+
+#[test]
+fn test_cards_from_sm2_are_placed_by_interval() {
+    let scheduler = default_leitner();
+    assert_eq!(scheduler.box_for_interval(0), 0);
+    assert_eq!(scheduler.box_for_interval(6), 3);
+    assert_eq!(scheduler.box_for_interval(90), 5);
+
+    // A card forgotten into box 0 stays there rather than being re-placed
+    let next = scheduler.schedule(&params(Some(0), 1, GOOD));
+    assert_eq!(next.next_leitner_box, Some(1));
+}
+
+#[test]
+fn test_intervals_across_algorithms() {
+    let sm2 = DeckSettings::defaults(Uuid::new_v4());
+    let leitner = DeckSettings {
+        algorithm: SchedulingAlgorithm::Leitner,
+        ..sm2.clone()
+    };
+    let recalls = [GOOD; 6];
+
+    // SM-2 grows intervals by the ease factor, Leitner doubles them up to
+    // the last box
+    assert_eq!(intervals(&sm2, &recalls), vec![1, 6, 15, 38, 95, 238]);
+    assert_eq!(intervals(&leitner, &recalls), vec![1, 2, 4, 8, 16, 16]);
+
+    // Both restart at one day after a lapse, but only SM-2 remembers it
+    // through a lower ease factor
+    let lapse = [GOOD, GOOD, GOOD, AGAIN, GOOD, GOOD, GOOD];
+    assert_eq!(intervals(&sm2, &lapse), vec![1, 6, 15, 1, 1, 6, 12]);
+    assert_eq!(intervals(&leitner, &lapse), vec![1, 2, 4, 1, 1, 2, 4]);
+}
+
+#[test]
+fn test_sm2_does_not_track_boxes() {
+    let settings = DeckSettings::defaults(Uuid::new_v4());
+    let next = scheduler::schedule(&settings, &params(Some(3), 4, GOOD));
+
+    assert_eq!(next.next_leitner_box, None);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use deckoracle_backend::handlers::search::SuggestionKind;
+use deckoracle_backend::models::{CreateDeckDto, CreateFolderDto};
+use deckoracle_backend::services::{deck::DeckService, folder::FolderService, search::SearchService};
+use deckoracle_backend::state::AppState;
+use uuid::Uuid;
+
+async fn create_deck(state: &AppState, user_id: Uuid, name: &str, tags: &[&str]) -> Uuid {
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: name.to_string(),
+            description: None,
+            folder_id: None,
+            is_public: Some(true),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    sqlx::query("UPDATE decks SET tags = $2 WHERE id = $1")
+        .bind(deck.id)
+        .bind(tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>())
+        .execute(&state.db)
+        .await
+        .unwrap();
+    deck.id
+}
+
+#[tokio::test]
+async fn test_suggestions_rank_prefixes_and_cover_all_kinds() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "suggest@example.com").await;
+    let stranger = common::register(&state, "suggest-stranger@example.com").await;
+
+    let spanish = create_deck(&state, user_id, "Spanish Verbs", &["spanish", "verbs"]).await;
+    let basic = create_deck(&state, user_id, "Basic Spanish", &[]).await;
+    let folder = FolderService::create_folder(
+        &state.db,
+        user_id,
+        CreateFolderDto {
+            name: "Spanish Course".to_string(),
+            parent_folder_id: None,
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+    // Public decks of other users are left to the full search
+    create_deck(&state, stranger, "Spanish for Travel", &["spanish"]).await;
+
+    let suggestions = SearchService::suggest(&state.db, user_id, "Span", None).await.unwrap();
+    let found: Vec<(SuggestionKind, Option<Uuid>, &str)> = suggestions
+        .iter()
+        .map(|s| (s.kind, s.id, s.label.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (SuggestionKind::Tag, None, "spanish"),
+            (SuggestionKind::Deck, Some(spanish), "Spanish Verbs"),
+            (SuggestionKind::Folder, Some(folder.id), "Spanish Course"),
+            (SuggestionKind::Deck, Some(basic), "Basic Spanish"),
+        ]
+    );
+    assert!(suggestions.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+    let limited = SearchService::suggest(&state.db, user_id, "span", Some(2)).await.unwrap();
+    assert_eq!(limited.len(), 2);
+
+    // Typos still find the deck, and wildcards are taken literally
+    let fuzzy = SearchService::suggest(&state.db, user_id, "spansh verbs", None).await.unwrap();
+    assert_eq!(fuzzy.first().map(|s| s.id), Some(Some(spanish)));
+    assert!(SearchService::suggest(&state.db, user_id, "%", None).await.unwrap().is_empty());
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use axum::{
+    http::{header, HeaderValue},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    config::SecurityConfig,
+    create_app,
+    middleware::security_headers::{security_headers, SecurityHeaders},
+    models::{CreateDeckDto, CreateShareLinkDto},
+    services::{deck::DeckService, sharing::SharingService},
+    state::AppState,
+};
+
+fn policy() -> SecurityHeaders {
+    SecurityHeaders::from_config(&SecurityConfig {
+        hsts_max_age_seconds: 600,
+        hsts_include_subdomains: false,
+        referrer_policy: "no-referrer".to_string(),
+        embed_frame_ancestors: "https://example.com".to_string(),
+    })
+}
+
+fn app() -> Router {
+    let embed = Router::new()
+        .route("/embed", get(|| async { "embed" }))
+        .route_layer(from_fn_with_state(policy().embeddable(), security_headers));
+
+    Router::new()
+        .route("/plain", get(|| async { "plain" }))
+        .route(
+            "/custom",
+            get(|| async { ([(header::REFERRER_POLICY, "same-origin")], "custom").into_response() }),
+        )
+        .merge(embed)
+        .layer(from_fn_with_state(policy(), security_headers))
+}
+
+#[tokio::test]
+async fn test_default_headers_deny_framing() {
+    let server = TestServer::new(app()).unwrap();
+    let response = server.get("/plain").await;
+
+    assert_eq!(response.header(header::STRICT_TRANSPORT_SECURITY), "max-age=600");
+    assert_eq!(response.header(header::X_CONTENT_TYPE_OPTIONS), "nosniff");
+    assert_eq!(response.header(header::REFERRER_POLICY), "no-referrer");
+    assert_eq!(response.header(header::X_FRAME_OPTIONS), "DENY");
+    assert_eq!(
+        response.header(header::CONTENT_SECURITY_POLICY),
+        "frame-ancestors 'none'"
+    );
+}
+
+#[tokio::test]
+async fn test_embed_routes_allow_configured_ancestors() {
+    let server = TestServer::new(app()).unwrap();
+    let response = server.get("/embed").await;
+
+    assert_eq!(
+        response.header(header::CONTENT_SECURITY_POLICY),
+        "frame-ancestors https://example.com"
+    );
+    assert!(response.maybe_header(header::X_FRAME_OPTIONS).is_none());
+}
+
+#[tokio::test]
+async fn test_handler_headers_take_precedence() {
+    let server = TestServer::new(app()).unwrap();
+    let response = server.get("/custom").await;
+
+    assert_eq!(
+        response.header(header::REFERRER_POLICY),
+        HeaderValue::from_static("same-origin")
+    );
+}
+
+#[tokio::test]
+async fn test_app_lets_only_public_embeds_be_framed() {
+    let mut config = common::test_config();
+    config.security.embed_frame_ancestors = "https://school.example".to_string();
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+    let user_id = common::register(&state, "framing@example.com").await;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Embedded".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let dto = CreateShareLinkDto {
+        role: None,
+        expires_in_hours: None,
+    };
+    let link = SharingService::create_link(&state.db, deck.id, user_id, dto).await.unwrap();
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server.get(&format!("/api/v1/public/decks/{}", link.token)).await;
+    response.assert_status_ok();
+    assert_eq!(
+        response.header(header::CONTENT_SECURITY_POLICY),
+        "frame-ancestors https://school.example"
+    );
+    assert!(response.maybe_header(header::X_FRAME_OPTIONS).is_none());
+    assert_eq!(response.header(header::X_CONTENT_TYPE_OPTIONS), "nosniff");
+
+    // Everything else keeps denying framing
+    let response = server.get("/api/v1/health").await;
+    assert_eq!(response.header(header::CONTENT_SECURITY_POLICY), "frame-ancestors 'none'");
+    assert_eq!(response.header(header::X_FRAME_OPTIONS), "DENY");
+}
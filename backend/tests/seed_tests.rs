@@ -0,0 +1,54 @@
+mod common;
+
+use deckoracle_backend::models::{ClientInfo, LoginDto};
+use deckoracle_backend::services::auth::AuthService;
+use deckoracle_backend::services::seed::{SeedService, DEMO_EMAIL, DEMO_PASSWORD};
+use deckoracle_backend::utils::AppError;
+
+#[tokio::test]
+async fn test_seed_creates_demo_user_with_history() {
+    let state = common::create_test_state().await;
+
+    let summary = SeedService::seed_demo(&state.db).await.unwrap();
+    assert_eq!(summary.email, DEMO_EMAIL);
+    assert_eq!(summary.folders, 3);
+    assert_eq!(summary.decks, 4);
+    assert!(summary.study_sessions > 0 && summary.answers >= summary.study_sessions);
+
+    let answers: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM card_progress WHERE user_id = $1")
+        .bind(summary.user_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(answers as usize, summary.answers);
+
+    // History ends yesterday, so the streak is still alive
+    let (streak, last_study_date): (i32, chrono::NaiveDate) = sqlx::query_as(
+        "SELECT current_streak_days, last_study_date FROM user_stats WHERE user_id = $1",
+    )
+    .bind(summary.user_id)
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    assert!(streak >= 1);
+    assert!(last_study_date < chrono::Utc::now().date_naive());
+
+    let login = LoginDto {
+        email: DEMO_EMAIL.to_string(),
+        password: DEMO_PASSWORD.to_string(),
+        remember_me: None,
+    };
+    let response = AuthService::login(&state.db, &state.config, login, &ClientInfo::default())
+        .await
+        .unwrap();
+    assert_eq!(response.user.id, summary.user_id);
+}
+
+#[tokio::test]
+async fn test_seed_refuses_to_run_twice() {
+    let state = common::create_test_state().await;
+
+    SeedService::seed_demo(&state.db).await.unwrap();
+    let result = SeedService::seed_demo(&state.db).await;
+    assert!(matches!(result, Err(AppError::BadRequest(_))));
+}
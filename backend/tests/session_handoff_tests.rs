@@ -0,0 +1,82 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::{CreateCardDto, CreateDeckDto, CreateStudySessionDto},
+    services::{card::CardService, deck::DeckService, study::StudyService},
+};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_active_session_is_handed_to_the_claiming_device() {
+    let state = common::create_test_state().await;
+    let (user_id, owner) = common::register_with_token(&state, "learner@example.com").await;
+    let (_, stranger) = common::register_with_token(&state, "stranger@example.com").await;
+    let owner: HeaderValue = owner.parse().unwrap();
+    let stranger: HeaderValue = stranger.parse().unwrap();
+
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let dto = CreateCardDto {
+        front: "France".to_string(),
+        back: "Paris".to_string(),
+        position: None,
+    };
+    CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap();
+    let dto = CreateStudySessionDto {
+        deck_id: Some(deck_id),
+        folder_id: None,
+        smart_deck_id: None,
+        study_mode: None,
+        card_ids: None,
+        time_limit_seconds: None,
+    };
+    let session = StudyService::create_study_session(&state.db, user_id, dto).await.unwrap();
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server
+        .get("/api/v1/study/sessions/active")
+        .add_header(header::AUTHORIZATION, owner.clone())
+        .add_header("x-device-id", HeaderValue::from_static("phone"))
+        .await;
+    response.assert_status_ok();
+    let active: Value = response.json();
+    assert_eq!(active["id"], json!(session.id));
+    assert_eq!(active["active_device_id"], "phone");
+    assert_eq!(active["remaining_cards"].as_array().unwrap().len(), 1);
+
+    // Picking the session up elsewhere moves it to the new device
+    server
+        .get("/api/v1/study/sessions/active")
+        .add_header(header::AUTHORIZATION, owner.clone())
+        .add_header("x-device-id", HeaderValue::from_static("laptop"))
+        .await
+        .assert_status_ok();
+    let response = server.get("/api/v1/study/sessions/active").add_header(header::AUTHORIZATION, owner).await;
+    assert_eq!(response.json::<Value>()["active_device_id"], "laptop");
+
+    // Nobody else sees it
+    let response = server
+        .get("/api/v1/study/sessions/active")
+        .add_header(header::AUTHORIZATION, stranger)
+        .add_header("x-device-id", HeaderValue::from_static("phone"))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = server.get("/api/v1/study/sessions/active").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::{CreateCardDto, CreateDeckDto, CreateStudySessionDto, Rating},
+    services::{card::CardService, deck::DeckService, study::StudyService},
+};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_session_replay_lists_answers_with_scheduler_decisions() {
+    let state = common::create_test_state().await;
+    let (user_id, owner) = common::register_with_token(&state, "learner@example.com").await;
+    let (_, stranger) = common::register_with_token(&state, "stranger@example.com").await;
+
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let mut card_ids = Vec::new();
+    for (front, back) in [("France", "Paris"), ("Spain", "Madrid")] {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: back.to_string(),
+            position: None,
+        };
+        card_ids.push(CardService::create_card(&state.db, deck_id, user_id, dto).await.unwrap().id);
+    }
+    let dto = CreateStudySessionDto {
+        deck_id: Some(deck_id),
+        folder_id: None,
+        smart_deck_id: None,
+        study_mode: None,
+        card_ids: None,
+        time_limit_seconds: None,
+    };
+    let session = StudyService::create_study_session(&state.db, user_id, dto).await.unwrap();
+    for (card_id, rating, response_time_ms) in [(card_ids[0], Rating::Good, 2400), (card_ids[1], Rating::Again, 5100)] {
+        StudyService::record_card_progress(&state.db, session.id, card_id, user_id, rating, Some(response_time_ms))
+            .await
+            .unwrap();
+    }
+    let server = TestServer::new(create_app(state)).unwrap();
+    let url = format!("/api/v1/study/sessions/{}/replay", session.id);
+
+    let response = server.get(&url).add_header(header::AUTHORIZATION, owner.parse::<HeaderValue>().unwrap()).await;
+    response.assert_status_ok();
+    let replay: Value = response.json();
+    assert_eq!(replay["session"]["id"], json!(session.id));
+    let events = replay["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+
+    assert_eq!(events[0]["sequence"], 1);
+    assert_eq!(events[0]["card_id"], json!(card_ids[0]));
+    assert_eq!(events[0]["front"], "France");
+    assert_eq!(events[0]["rating"], "good");
+    assert_eq!(events[0]["response_time_ms"], 2400);
+    assert_eq!(events[0]["scheduler"]["repetitions_before"], 0);
+    assert_eq!(events[0]["scheduler"]["repetitions_after"], 1);
+
+    assert_eq!(events[1]["sequence"], 2);
+    assert_eq!(events[1]["rating"], "again");
+    assert_eq!(events[1]["scheduler"]["repetitions_after"], 0);
+    assert!(events[1]["elapsed_ms"].as_i64().unwrap() >= events[0]["elapsed_ms"].as_i64().unwrap());
+
+    // Sessions are replayed for the learner only
+    let response = server.get(&url).add_header(header::AUTHORIZATION, stranger.parse::<HeaderValue>().unwrap()).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = server.get(&url).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto, CreateStudySessionDto, Rating, RegisterDto};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, deck::DeckService, study::StudyService,
+};
+
+#[tokio::test]
+async fn test_session_stats_rank_forgotten_cards_first() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "session-stats@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let card = |front: &str| CreateCardDto {
+        front: front.to_string(),
+        back: "back".to_string(),
+        position: None,
+    };
+    let easy = CardService::create_card(&state.db, deck.id, user_id, card("France")).await.unwrap();
+    let hard = CardService::create_card(&state.db, deck.id, user_id, card("Bhutan")).await.unwrap();
+
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck.id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+    StudyService::record_answer(&state.db, &session, easy.id, Rating::Easy, Some(2000)).await.unwrap();
+    StudyService::record_answer(&state.db, &session, hard.id, Rating::Again, Some(1000)).await.unwrap();
+    StudyService::record_answer(&state.db, &session, hard.id, Rating::Good, Some(6000)).await.unwrap();
+
+    let stats = StudyService::get_session_stats(&state.db, session.id, user_id).await.unwrap();
+    assert_eq!((stats.total_answers, stats.total_cards, stats.correct_answers), (3, 2, 2));
+    assert!((stats.accuracy - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(stats.average_response_time_ms, Some(3000.0));
+    assert_eq!(stats.median_response_time_ms, Some(2000.0));
+    let ratings = &stats.rating_distribution;
+    assert_eq!((ratings.again, ratings.hard, ratings.good, ratings.easy), (1, 0, 1, 1));
+
+    assert_eq!(stats.cards.len(), 2);
+    assert_eq!(stats.cards[0].card_id, hard.id);
+    assert_eq!((stats.cards[0].answers, stats.cards[0].again_count), (2, 1));
+    assert_eq!(stats.cards[0].last_rating, Rating::Good);
+    assert_eq!(stats.cards[1].card_id, easy.id);
+}
@@ -0,0 +1,222 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::create_app;
+use deckoracle_backend::models::{
+    CreateCardDto, CreateDeckDto, CreateShareLinkDto, DeckRole, ShareDeckDto, UpdateCardDto,
+};
+use deckoracle_backend::services::{card::CardService, deck::DeckService, sharing::SharingService};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn create_deck(state: &AppState, user_id: Uuid, name: &str) -> Uuid {
+    DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: name.to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+fn card(front: &str) -> CreateCardDto {
+    CreateCardDto {
+        front: front.to_string(),
+        back: "back".to_string(),
+        position: None,
+    }
+}
+
+async fn share(state: &AppState, deck_id: Uuid, owner_id: Uuid, email: &str, role: DeckRole) {
+    let dto = ShareDeckDto {
+        email: email.to_string(),
+        role,
+    };
+    SharingService::share_with_user(&state.db, deck_id, owner_id, dto).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_viewers_can_read_but_not_edit() {
+    let state = common::create_test_state().await;
+    let owner = common::register(&state, "owner@example.com").await;
+    let viewer = common::register(&state, "viewer@example.com").await;
+    let deck_id = create_deck(&state, owner, "Shared").await;
+    let card = CardService::create_card(&state.db, deck_id, owner, card("hola")).await.unwrap();
+    share(&state, deck_id, owner, "viewer@example.com", DeckRole::Viewer).await;
+
+    assert_eq!(DeckService::get_deck(&state.db, deck_id, viewer).await.unwrap().name, "Shared");
+
+    let error = CardService::create_card(&state.db, deck_id, viewer, card("adiós")).await.unwrap_err();
+    assert!(matches!(error, AppError::Forbidden));
+    let dto = UpdateCardDto {
+        front: Some("changed".to_string()),
+        back: None,
+        position: None,
+        updated_at: None,
+    };
+    let error = CardService::update_card(&state.db, card.id, viewer, dto).await.unwrap_err();
+    assert!(matches!(error, AppError::Forbidden));
+}
+
+#[tokio::test]
+async fn test_editors_cannot_share_or_delete() {
+    let state = common::create_test_state().await;
+    let owner = common::register(&state, "owner@example.com").await;
+    let editor = common::register(&state, "editor@example.com").await;
+    common::register(&state, "friend@example.com").await;
+    let deck_id = create_deck(&state, owner, "Shared").await;
+    share(&state, deck_id, owner, "editor@example.com", DeckRole::Editor).await;
+
+    CardService::create_card(&state.db, deck_id, editor, card("hola")).await.unwrap();
+
+    let dto = ShareDeckDto {
+        email: "friend@example.com".to_string(),
+        role: DeckRole::Viewer,
+    };
+    let error = SharingService::share_with_user(&state.db, deck_id, editor, dto).await.unwrap_err();
+    assert!(matches!(error, AppError::Forbidden));
+    let dto = CreateShareLinkDto {
+        role: None,
+        expires_in_hours: None,
+    };
+    let error = SharingService::create_link(&state.db, deck_id, editor, dto).await.unwrap_err();
+    assert!(matches!(error, AppError::Forbidden));
+    let error = SharingService::list_shares(&state.db, deck_id, editor).await.unwrap_err();
+    assert!(matches!(error, AppError::Forbidden));
+
+    let error = DeckService::delete_deck(&state.db, deck_id, editor).await.unwrap_err();
+    assert!(matches!(error, AppError::DeckNotFound));
+    DeckService::get_deck(&state.db, deck_id, owner).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_revoked_share_loses_access() {
+    let state = common::create_test_state().await;
+    let owner = common::register(&state, "owner@example.com").await;
+    let editor = common::register(&state, "editor@example.com").await;
+    let first = create_deck(&state, owner, "Verbs").await;
+    let second = create_deck(&state, owner, "Nouns").await;
+    share(&state, first, owner, "editor@example.com", DeckRole::Editor).await;
+    share(&state, second, owner, "editor@example.com", DeckRole::Viewer).await;
+
+    let shared = SharingService::list_shared_with_user(&state.db, editor).await.unwrap();
+    let listed: Vec<(&str, &str)> =
+        shared.iter().map(|s| (s.deck.name.as_str(), s.role.as_str())).collect();
+    assert_eq!(listed, vec![("Nouns", "viewer"), ("Verbs", "editor")]);
+    assert!(shared.iter().all(|s| s.deck.user_id == owner));
+
+    SharingService::revoke_share(&state.db, first, owner, editor).await.unwrap();
+
+    let error = DeckService::get_deck(&state.db, first, editor).await.unwrap_err();
+    assert!(matches!(error, AppError::DeckNotFound));
+    let error = CardService::create_card(&state.db, first, editor, card("hola")).await.unwrap_err();
+    assert!(matches!(error, AppError::Forbidden));
+    let shared = SharingService::list_shared_with_user(&state.db, editor).await.unwrap();
+    assert_eq!(shared.len(), 1);
+    assert_eq!(shared[0].deck.id, second);
+
+    let error = SharingService::revoke_share(&state.db, first, owner, editor).await.unwrap_err();
+    assert!(matches!(error, AppError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn test_accepting_a_share_link_grants_its_role() {
+    let state = common::create_test_state().await;
+    let owner = common::register(&state, "owner@example.com").await;
+    let reader = common::register(&state, "reader@example.com").await;
+    let deck_id = create_deck(&state, owner, "Linked").await;
+    let dto = CreateShareLinkDto {
+        role: Some(DeckRole::Editor),
+        expires_in_hours: Some(24),
+    };
+    let link = SharingService::create_link(&state.db, deck_id, owner, dto).await.unwrap();
+
+    let accepted = SharingService::accept_link(&state.db, &link.token, reader).await.unwrap();
+    assert_eq!(accepted.deck.id, deck_id);
+    assert_eq!(accepted.role, "editor");
+    CardService::create_card(&state.db, deck_id, reader, card("hola")).await.unwrap();
+
+    // The owner opening their own link stays the owner
+    let own = SharingService::accept_link(&state.db, &link.token, owner).await.unwrap();
+    assert_eq!(own.role, "owner");
+
+    // Revoked links stop working for anyone who has not used them yet
+    SharingService::revoke_link(&state.db, deck_id, owner, link.id).await.unwrap();
+    let latecomer = common::register(&state, "latecomer@example.com").await;
+    let error = SharingService::accept_link(&state.db, &link.token, latecomer).await.unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+    let error = DeckService::get_deck(&state.db, deck_id, latecomer).await.unwrap_err();
+    assert!(matches!(error, AppError::DeckNotFound));
+}
+
+#[tokio::test]
+async fn test_sharing_does_not_reveal_which_emails_have_accounts() {
+    let state = common::create_test_state().await;
+    let (owner, token) = common::register_with_token(&state, "owner@example.com").await;
+    common::register(&state, "member@example.com").await;
+    let deck_id = create_deck(&state, owner, "Shared").await;
+    let auth: HeaderValue = token.parse().unwrap();
+    let server = TestServer::new(create_app(state.clone())).unwrap();
+    let url = format!("/api/v1/decks/{}/shares", deck_id);
+
+    let mut answers = Vec::new();
+    for email in ["member@example.com", "newcomer@example.com"] {
+        let response = server
+            .post(&url)
+            .add_header(header::AUTHORIZATION, auth.clone())
+            .json(&json!({ "email": email, "role": "viewer" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::ACCEPTED);
+        let mut body: Value = response.json();
+        assert_eq!(body["email"], email);
+        body["email"] = Value::Null;
+        answers.push(body);
+    }
+    assert_eq!(answers[0], answers[1]);
+    assert_eq!(answers[0], json!({ "deck_id": deck_id, "email": null, "role": "viewer" }));
+
+    // The invite becomes a share once the address signs up
+    let newcomer = common::register(&state, "newcomer@example.com").await;
+    assert_eq!(DeckService::get_deck(&state.db, deck_id, newcomer).await.unwrap().name, "Shared");
+    let shares = SharingService::list_shares(&state.db, deck_id, owner).await.unwrap();
+    let mut emails: Vec<&str> = shares.iter().map(|share| share.email.as_str()).collect();
+    emails.sort();
+    assert_eq!(emails, ["member@example.com", "newcomer@example.com"]);
+}
+
+#[tokio::test]
+async fn test_lapsed_invites_are_not_claimed() {
+    let state = common::create_test_state().await;
+    let owner = common::register(&state, "owner@example.com").await;
+    let deck_id = create_deck(&state, owner, "Shared").await;
+    let dto = ShareDeckDto {
+        email: "late@example.com".to_string(),
+        role: DeckRole::Editor,
+    };
+    let invite = SharingService::share_with_user(&state.db, deck_id, owner, dto).await.unwrap();
+    assert_eq!(invite.role, "editor");
+    sqlx::query("UPDATE deck_share_invites SET created_at = NOW() - INTERVAL '31 days'")
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    let late = common::register(&state, "late@example.com").await;
+    let error = DeckService::get_deck(&state.db, deck_id, late).await.unwrap_err();
+    assert!(matches!(error, AppError::DeckNotFound));
+    let invites: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM deck_share_invites")
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(invites, 0);
+}
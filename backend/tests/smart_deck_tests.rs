@@ -0,0 +1,222 @@
+mod common;
+
+use deckoracle_backend::models::{
+    ai::StudyQueueQuery, CreateCardDto, CreateDeckDto, CreateSmartDeckDto, CreateStudySessionDto, Rating,
+    SmartDeckFilter, SmartDeckStatus, UpdateSmartDeckDto,
+};
+use deckoracle_backend::services::{
+    card::CardService, deck::DeckService, smart_deck::SmartDeckService, study::StudyService,
+    study_queue::StudyQueueService,
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use uuid::Uuid;
+
+/// A deck with the given tags and one card per front, returning the deck and card ids
+async fn deck_with_cards(
+    state: &AppState,
+    user_id: Uuid,
+    name: &str,
+    tags: &[&str],
+    fronts: &[&str],
+) -> (Uuid, Vec<Uuid>) {
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: name.to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    sqlx::query("UPDATE decks SET tags = $2 WHERE id = $1")
+        .bind(deck.id)
+        .bind(tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>())
+        .execute(&state.db)
+        .await
+        .unwrap();
+    let mut cards = Vec::new();
+    for front in fronts {
+        let dto = CreateCardDto {
+            front: front.to_string(),
+            back: "back".to_string(),
+            position: None,
+        };
+        cards.push(CardService::create_card(&state.db, deck.id, user_id, dto).await.unwrap().id);
+    }
+    (deck.id, cards)
+}
+
+fn create_dto(filter: SmartDeckFilter) -> CreateSmartDeckDto {
+    CreateSmartDeckDto {
+        name: "Smart".to_string(),
+        description: None,
+        filter,
+    }
+}
+
+fn queue_query(smart_deck_id: Uuid) -> StudyQueueQuery {
+    StudyQueueQuery {
+        deck_id: None,
+        folder_id: None,
+        smart_deck_id: Some(smart_deck_id),
+        max_new_cards: None,
+        focus_weak_cards: None,
+        include_overdue: None,
+        prioritize_starred: None,
+    }
+}
+
+#[tokio::test]
+async fn test_smart_deck_queue_matches_tags_text_and_status() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "smart@example.com").await;
+    let (spanish, spanish_cards) =
+        deck_with_cards(&state, user_id, "Spanish", &["spanish"], &["correr (verb)", "casa", "comer (verb)"]).await;
+    let (_, french_cards) = deck_with_cards(&state, user_id, "French", &["french"], &["manger (verb)"]).await;
+
+    let verbs = SmartDeckService::create(
+        &state.db,
+        user_id,
+        create_dto(SmartDeckFilter {
+            tags: Some(vec![" SPANISH ".to_string()]),
+            query: Some("(verb)".to_string()),
+            ..Default::default()
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(verbs.filter.tags.as_deref(), Some(&["spanish".to_string()][..]));
+
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(verbs.id)).await.unwrap();
+    let queued: Vec<Uuid> = queue.new.iter().map(|s| s.card_id).collect();
+    assert_eq!(queued, [spanish_cards[0], spanish_cards[2]]);
+    assert_eq!(queue.smart_deck_id, Some(verbs.id));
+
+    // Answering a card moves it out of "new" and, once overdue, into "overdue"
+    let session = StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: None,
+            folder_id: None,
+            smart_deck_id: Some(verbs.id),
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(session.smart_deck_id, Some(verbs.id));
+    StudyService::record_answer(&state.db, &session, spanish_cards[0], Rating::Good, None)
+        .await
+        .unwrap();
+    assert!(matches!(
+        StudyService::record_answer(&state.db, &session, french_cards[0], Rating::Good, None).await,
+        Err(AppError::BadRequest(_))
+    ));
+    sqlx::query(
+        "UPDATE user_card_stats SET interval_days = 10, next_review_at = NOW() - INTERVAL '3 days' WHERE card_id = $1",
+    )
+        .bind(spanish_cards[0])
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    let overdue = SmartDeckService::update(
+        &state.db,
+        verbs.id,
+        user_id,
+        UpdateSmartDeckDto {
+            filter: Some(SmartDeckFilter {
+                deck_ids: Some(vec![spanish]),
+                status: Some(SmartDeckStatus::Overdue),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        SmartDeckService::card_ids(&state.db, user_id, &overdue.filter).await.unwrap(),
+        [spanish_cards[0]]
+    );
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(verbs.id)).await.unwrap();
+    assert!(queue.new.is_empty());
+    assert_eq!(queue.review.iter().map(|s| s.card_id).collect::<Vec<_>>(), [spanish_cards[0]]);
+
+    // Sessions outlive their smart deck
+    SmartDeckService::delete(&state.db, verbs.id, user_id).await.unwrap();
+    let session = StudyService::get_study_session(&state.db, session.id, user_id).await.unwrap();
+    assert_eq!(session.smart_deck_id, None);
+}
+
+#[tokio::test]
+async fn test_smart_deck_filters_are_validated_and_private() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "smart-validate@example.com").await;
+    let stranger = common::register(&state, "smart-stranger@example.com").await;
+    let (theirs, _) = deck_with_cards(&state, stranger, "Private", &[], &["secret"]).await;
+
+    let invalid = [
+        SmartDeckFilter::default(),
+        SmartDeckFilter {
+            min_difficulty: Some(0.8),
+            max_difficulty: Some(0.2),
+            ..Default::default()
+        },
+        SmartDeckFilter {
+            max_difficulty: Some(1.5),
+            ..Default::default()
+        },
+        SmartDeckFilter {
+            query: Some("   ".to_string()),
+            ..Default::default()
+        },
+        SmartDeckFilter {
+            tags: Some(vec![" ".to_string()]),
+            ..Default::default()
+        },
+    ];
+    for filter in invalid {
+        assert!(matches!(
+            SmartDeckService::create(&state.db, user_id, create_dto(filter)).await,
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    let foreign = SmartDeckFilter {
+        deck_ids: Some(vec![theirs]),
+        ..Default::default()
+    };
+    assert!(SmartDeckService::create(&state.db, user_id, create_dto(foreign)).await.is_err());
+
+    // Unknown criteria are rejected rather than silently ignored
+    assert!(serde_json::from_str::<SmartDeckFilter>(r#"{"difficulty": 0.5}"#).is_err());
+
+    let mine = SmartDeckService::create(
+        &state.db,
+        user_id,
+        create_dto(SmartDeckFilter {
+            status: Some(SmartDeckStatus::New),
+            ..Default::default()
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(SmartDeckService::list(&state.db, user_id).await.unwrap().len(), 1);
+    assert!(matches!(
+        SmartDeckService::get(&state.db, mine.id, stranger).await,
+        Err(AppError::NotFound(_))
+    ));
+    assert!(
+        StudyQueueService::build_queue(&state.db, stranger, &queue_query(mine.id)).await.is_err()
+    );
+}
@@ -0,0 +1,135 @@
+use chrono::{TimeZone, Utc};
+use deckoracle_backend::services::import_export::{
+    parse_mnemosyne, parse_supermemo, MAX_IMPORTED_EASE_FACTOR, MNEMOSYNE_DEFAULT_DECK,
+    SUPERMEMO_DEFAULT_DECK,
+};
+
+const MNEMOSYNE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<mnemosyne core_version="1" time_of_start="1672531200">
+<category active="1"><name>Spanish</name></category>
+<item id="a1" gr="4" e="2.36" ac_rp="1" rt_rp="5" lps="1" ac_rp_l="0" rt_rp_l="3" l_rp="100" n_rp="112">
+<cat>Spanish</cat>
+<Q>gato</Q>
+<A>cat</A>
+</item>
+<item id="a2" gr="1" e="2.5" ac_rp="2" rt_rp="0" lps="0" ac_rp_l="2" rt_rp_l="0" l_rp="101" n_rp="101">
+<cat>Spanish</cat>
+<Q>perro</Q>
+<A>dog</A>
+</item>
+<item id="a3" gr="-1" e="2.5" ac_rp="0" rt_rp="0" lps="0" ac_rp_l="0" rt_rp_l="0" l_rp="0" n_rp="0">
+<Q>new card</Q>
+<A>unseen</A>
+</item>
+</mnemosyne>"#;
+
+const SUPERMEMO: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<SuperMemoCollection>
+  <Count>4</Count>
+  <SuperMemoElement>
+    <ID>1</ID>
+    <Title>Geography</Title>
+    <Type>Topic</Type>
+    <SuperMemoElement>
+      <ID>2</ID>
+      <Type>Item</Type>
+      <Content>
+        <Question>Capital of France?</Question>
+        <Answer>Paris</Answer>
+      </Content>
+      <LearningData>
+        <Interval>30</Interval>
+        <Repetitions>5</Repetitions>
+        <Lapses>1</Lapses>
+        <LastRepetition>15.03.2023</LastRepetition>
+        <AFactor>6.2</AFactor>
+        <UFactor>2.1</UFactor>
+      </LearningData>
+    </SuperMemoElement>
+  </SuperMemoElement>
+  <SuperMemoElement>
+    <ID>3</ID>
+    <Type>Item</Type>
+    <Content>
+      <Question>Loose item</Question>
+      <Answer>No topic</Answer>
+    </Content>
+    <LearningData>
+      <Repetitions>2</Repetitions>
+      <AFactor>9.5</AFactor>
+    </LearningData>
+  </SuperMemoElement>
+</SuperMemoCollection>"#;
+
+#[test]
+fn test_mnemosyne_cards_grouped_by_category() {
+    let now = Utc::now();
+    let decks = parse_mnemosyne(MNEMOSYNE.as_bytes(), now).unwrap();
+    assert_eq!(decks.len(), 2);
+    assert_eq!(decks[0].title, "Spanish");
+    assert_eq!(decks[0].cards.len(), 2);
+    assert_eq!(decks[1].title, MNEMOSYNE_DEFAULT_DECK);
+    assert_eq!(decks[0].cards[0].front, "gato");
+    assert_eq!(decks[0].cards[0].back, "cat");
+}
+
+#[test]
+fn test_mnemosyne_schedule_is_kept() {
+    let now = Utc::now();
+    let decks = parse_mnemosyne(MNEMOSYNE.as_bytes(), now).unwrap();
+    let progress = decks[0].cards[0].progress.clone().unwrap();
+    let start = Utc.timestamp_opt(1672531200, 0).unwrap();
+    assert_eq!(progress.times_seen, 6);
+    assert_eq!(progress.lapses, 1);
+    assert_eq!(progress.repetitions, 3);
+    assert_eq!(progress.interval_days, 12);
+    assert!((progress.ease_factor - 2.36).abs() < 1e-6);
+    assert_eq!(progress.last_review, Some(start + chrono::Duration::days(100)));
+    assert_eq!(progress.next_review, start + chrono::Duration::days(112));
+}
+
+#[test]
+fn test_mnemosyne_unlearned_and_unseen_cards() {
+    let now = Utc::now();
+    let decks = parse_mnemosyne(MNEMOSYNE.as_bytes(), now).unwrap();
+    let unlearned = decks[0].cards[1].progress.clone().unwrap();
+    assert_eq!(unlearned.next_review, now);
+    assert_eq!(unlearned.interval_days, 0);
+    assert!(decks[1].cards[0].progress.is_none());
+}
+
+#[test]
+fn test_supermemo_topics_become_decks() {
+    let decks = parse_supermemo(SUPERMEMO.as_bytes(), Utc::now()).unwrap();
+    assert_eq!(decks.len(), 2);
+    assert_eq!(decks[0].title, "Geography");
+    assert_eq!(decks[0].cards[0].back, "Paris");
+    assert_eq!(decks[1].title, SUPERMEMO_DEFAULT_DECK);
+}
+
+#[test]
+fn test_supermemo_schedule_is_kept() {
+    let now = Utc::now();
+    let decks = parse_supermemo(SUPERMEMO.as_bytes(), now).unwrap();
+    let progress = decks[0].cards[0].progress.clone().unwrap();
+    let last = Utc.with_ymd_and_hms(2023, 3, 15, 0, 0, 0).unwrap();
+    assert_eq!(progress.last_review, Some(last));
+    assert_eq!(progress.next_review, last + chrono::Duration::days(30));
+    assert_eq!(progress.interval_days, 30);
+    assert!((progress.ease_factor - 2.1).abs() < 1e-6);
+
+    // No date to schedule from, and an A-factor beyond SM-2's range
+    let loose = decks[1].cards[0].progress.clone().unwrap();
+    assert_eq!(loose.next_review, now);
+    assert_eq!(loose.ease_factor, MAX_IMPORTED_EASE_FACTOR);
+}
+
+#[test]
+fn test_malformed_exports_are_rejected() {
+    let no_question = b"<mnemosyne><item gr=\"1\"><A>answer</A></item></mnemosyne>";
+    assert!(parse_mnemosyne(no_question, Utc::now()).is_err());
+
+    let bad_interval = b"<SuperMemoCollection><SuperMemoElement><LearningData>\
+        <Interval>soon</Interval></LearningData></SuperMemoElement></SuperMemoCollection>";
+    assert!(parse_supermemo(bad_interval, Utc::now()).is_err());
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use deckoracle_backend::models::{
+    ai::{BatchAnalyticsEvent, CreateStudyEventDto},
+    CreateCardDto, CreateDeckDto, RegisterDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, deck::DeckService, study_events::StudyEventService,
+};
+use uuid::Uuid;
+use validator::Validate;
+
+fn event(card_id: Uuid, deck_id: Uuid) -> CreateStudyEventDto {
+    CreateStudyEventDto {
+        card_id,
+        deck_id,
+        session_id: None,
+        event_type: "answer".to_string(),
+        outcome: Some("correct".to_string()),
+        response_time_ms: Some(2500),
+        confidence_rating: Some(4),
+        occurred_at: Some(Utc::now() - Duration::hours(3)),
+    }
+}
+
+#[test]
+fn test_batches_are_limited_in_size() {
+    let batch = |count: usize| BatchAnalyticsEvent {
+        events: vec![event(Uuid::new_v4(), Uuid::new_v4()); count],
+        batch_id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+    };
+
+    assert!(batch(0).validate().is_err());
+    assert!(batch(500).validate().is_ok());
+    assert!(batch(501).validate().is_err());
+}
+
+#[tokio::test]
+async fn test_resent_batches_are_not_stored_twice() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "offline@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Offline".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    let card = CardService::create_card(
+        &state.db,
+        deck.id,
+        user_id,
+        CreateCardDto {
+            front: "front".to_string(),
+            back: "back".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut batch = BatchAnalyticsEvent {
+        events: vec![event(card.id, deck.id), event(card.id, Uuid::new_v4())],
+        batch_id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+    };
+    let result = StudyEventService::ingest_batch(&state.db, user_id, batch.clone()).await.unwrap();
+    assert_eq!((result.received, result.inserted, result.duplicates, result.rejected), (2, 1, 0, 1));
+
+    // The retry carries one more event recorded since
+    batch.events.push(event(card.id, deck.id));
+    let result = StudyEventService::ingest_batch(&state.db, user_id, batch).await.unwrap();
+    assert_eq!((result.received, result.inserted, result.duplicates, result.rejected), (3, 1, 1, 1));
+
+    let stored = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM study_events WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(stored, 2);
+}
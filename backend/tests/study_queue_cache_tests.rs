@@ -0,0 +1,229 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::config::Config;
+use deckoracle_backend::create_app;
+use deckoracle_backend::models::{
+    ai::StudyQueueQuery, CreateCardDto, CreateDeckDto, CreateStudySessionDto, RegisterDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, deck::DeckService, queue_cache::QueueCache,
+    study::StudyService,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+async fn user_with_deck(db: &PgPool, config: &Config, email: &str) -> (Uuid, Uuid) {
+    let user_id = AuthService::register(
+        db,
+        config,
+        RegisterDto {
+            email: email.to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+    let deck = DeckService::create_deck(
+        db,
+        user_id,
+        CreateDeckDto {
+            name: "Capitals".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+    add_card(db, deck.id, user_id, "France").await;
+    (user_id, deck.id)
+}
+
+async fn add_card(db: &PgPool, deck_id: Uuid, user_id: Uuid, front: &str) {
+    let dto = CreateCardDto {
+        front: front.to_string(),
+        back: "capital".to_string(),
+        position: None,
+    };
+    CardService::create_card(db, deck_id, user_id, dto).await.unwrap();
+}
+
+fn deck_query(deck_id: Uuid) -> StudyQueueQuery {
+    StudyQueueQuery {
+        deck_id: Some(deck_id),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_cached_queue_is_reused_until_invalidated() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = user_with_deck(&state.db, &state.config, "cache@example.com").await;
+    let cache = QueueCache::new(Duration::from_secs(300), 100);
+
+    let first = cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    assert_eq!(first.counts.new, 1);
+
+    // Served from the cache, so a card added behind its back is not seen
+    add_card(&state.db, deck_id, user_id, "Spain").await;
+    let second = cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    assert_eq!(second.generated_at, first.generated_at);
+    assert_eq!(second.counts.new, 1);
+
+    // Other queries are cached separately
+    let capped = StudyQueueQuery {
+        max_new_cards: Some(0),
+        ..deck_query(deck_id)
+    };
+    let queue = cache.get_or_build(&state.db, user_id, &capped).await.unwrap();
+    assert_eq!(queue.counts.new, 0);
+
+    cache.invalidate_user(user_id).await;
+    let rebuilt = cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    assert_eq!(rebuilt.counts.new, 2);
+
+    let stats = cache.stats().await;
+    assert!(stats.enabled);
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 3);
+    assert_eq!(stats.invalidations, 1);
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.users, 1);
+    assert!((stats.hit_rate - 0.25).abs() < f64::EPSILON);
+}
+
+#[tokio::test]
+async fn test_invalidating_leaves_no_empty_entries_behind() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = user_with_deck(&state.db, &state.config, "entries@example.com").await;
+    let cache = QueueCache::new(Duration::from_secs(300), 100);
+
+    // Users without cached queues are not tracked just for being invalidated
+    for _ in 0..3 {
+        cache.invalidate_user(Uuid::new_v4()).await;
+    }
+    let stats = cache.stats().await;
+    assert_eq!((stats.entries, stats.users, stats.invalidations), (0, 0, 3));
+
+    cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    assert_eq!(cache.stats().await.users, 1);
+    cache.invalidate_user(user_id).await;
+    let stats = cache.stats().await;
+    assert_eq!((stats.entries, stats.users), (0, 0));
+
+    // Failed builds do not leave the user behind either
+    let stranger = Uuid::new_v4();
+    assert!(cache.get_or_build(&state.db, stranger, &deck_query(deck_id)).await.is_err());
+    assert_eq!(cache.stats().await.users, 0);
+
+    // Queues are cached again after the invalidation
+    cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    let stats = cache.stats().await;
+    assert_eq!((stats.entries, stats.users, stats.hits), (1, 1, 1));
+}
+
+#[tokio::test]
+async fn test_disabled_cache_always_builds() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = user_with_deck(&state.db, &state.config, "nocache@example.com").await;
+    let cache = QueueCache::new(Duration::ZERO, 100);
+
+    cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    add_card(&state.db, deck_id, user_id, "Spain").await;
+    let queue = cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    assert_eq!(queue.counts.new, 2);
+
+    let stats = cache.stats().await;
+    assert!(!stats.enabled);
+    assert_eq!((stats.hits, stats.misses, stats.entries), (0, 0, 0));
+}
+
+#[tokio::test]
+async fn test_precompute_builds_queues_of_recently_studied_decks() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = user_with_deck(&state.db, &state.config, "precompute@example.com").await;
+    let cache = QueueCache::new(Duration::from_secs(300), 100);
+
+    StudyService::create_study_session(
+        &state.db,
+        user_id,
+        CreateStudySessionDto {
+            deck_id: Some(deck_id),
+            folder_id: None,
+            smart_deck_id: None,
+            study_mode: None,
+            card_ids: None,
+            time_limit_seconds: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let built = cache.precompute(&state.db, Duration::from_secs(60)).await.unwrap();
+    assert!(built >= 1);
+    // Fresh entries outlast the horizon and are left alone
+    assert_eq!(cache.precompute(&state.db, Duration::from_secs(60)).await.unwrap(), 0);
+
+    let queue = cache.get_or_build(&state.db, user_id, &deck_query(deck_id)).await.unwrap();
+    assert_eq!(queue.counts.new, 1);
+    let stats = cache.stats().await;
+    assert_eq!((stats.hits, stats.misses), (1, 0));
+    assert_eq!(stats.precomputed, built as u64);
+}
+
+#[tokio::test]
+async fn test_queue_endpoint_serves_decks_the_user_can_study() {
+    let state = common::create_test_state().await;
+    let (user_id, owner) = common::register_with_token(&state, "queue@example.com").await;
+    let owner: HeaderValue = owner.parse().unwrap();
+    let deck_id = DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Rivers".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    add_card(&state.db, deck_id, user_id, "Nile").await;
+    let (_, stranger) = common::register_with_token(&state, "stranger@example.com").await;
+    let stranger: HeaderValue = stranger.parse().unwrap();
+    let server = TestServer::new(create_app(state)).unwrap();
+    let deck_id = deck_id.to_string();
+
+    let response = server
+        .get("/api/v1/study/queue")
+        .add_query_param("deck_id", &deck_id)
+        .add_header(header::AUTHORIZATION, owner)
+        .await;
+    response.assert_status_ok();
+    let queue: Value = response.json();
+    assert_eq!(queue["counts"]["new"], 1);
+    assert_eq!(queue["new"][0]["deck_id"], deck_id.as_str());
+
+    let response = server
+        .get("/api/v1/study/queue")
+        .add_query_param("deck_id", &deck_id)
+        .add_header(header::AUTHORIZATION, stranger)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    let response = server.get("/api/v1/study/queue").add_query_param("deck_id", &deck_id).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
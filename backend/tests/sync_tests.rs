@@ -0,0 +1,387 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use chrono::{Duration, Utc};
+use deckoracle_backend::create_app;
+use deckoracle_backend::models::sync::{
+    ConflictResolution, SyncChangesQuery, SyncEntity, SyncOp, SyncPushChange, SyncPushDto,
+    SyncPushStatus,
+};
+use deckoracle_backend::models::{CreateCardDto, CreateDeckDto, UpdateDeckSettingsDto};
+use deckoracle_backend::services::{
+    card::CardService, deck::DeckService, deck_settings::DeckSettingsService, sync::SyncService,
+};
+use deckoracle_backend::state::AppState;
+use serde_json::json;
+use std::time::Duration as StdDuration;
+use uuid::Uuid;
+
+async fn create_deck(state: &AppState, user_id: Uuid) -> Uuid {
+    DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Sync Test".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: Some(false),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+fn everything() -> SyncChangesQuery {
+    SyncChangesQuery { since: None, limit: None }
+}
+
+fn card(front: &str) -> CreateCardDto {
+    CreateCardDto {
+        front: front.to_string(),
+        back: "back".to_string(),
+        position: None,
+    }
+}
+
+/// Wait until pulls can serve every logged change. Pulls hold back changes
+/// while any older transaction on the server is running, including those of
+/// tests running alongside.
+async fn settle(state: &AppState) {
+    loop {
+        let settled: bool = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(txid), 0) < pg_snapshot_xmin(pg_current_snapshot())::text::BIGINT FROM sync_changes",
+        )
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+        if settled {
+            return;
+        }
+        tokio::time::sleep(StdDuration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_pull_returns_changes_and_tombstones_after_cursor() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "sync-pull@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+    let card = CardService::create_card(
+        &state.db,
+        deck_id,
+        user_id,
+        CreateCardDto {
+            front: "hola".to_string(),
+            back: "hello".to_string(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    settle(&state).await;
+    let page = SyncService::pull(&state.db, user_id, &everything()).await.unwrap();
+    assert!(!page.has_more);
+    assert_eq!(page.changes.len(), 2);
+    assert_eq!(page.changes[0].entity, SyncEntity::Deck);
+    assert_eq!(page.changes[1].entity, SyncEntity::Card);
+    assert_eq!(page.changes[1].op, SyncOp::Upsert);
+    assert_eq!(page.changes[1].data.as_ref().unwrap()["front"], "hola");
+
+    CardService::delete_card(&state.db, card.id, user_id).await.unwrap();
+
+    settle(&state).await;
+    let since = SyncChangesQuery { since: Some(page.next_cursor), limit: None };
+    let page = SyncService::pull(&state.db, user_id, &since).await.unwrap();
+    assert_eq!(page.changes.len(), 1);
+    assert_eq!(page.changes[0].id, card.id);
+    assert_eq!(page.changes[0].op, SyncOp::Delete);
+    assert!(page.changes[0].data.is_none());
+
+    let other = common::register(&state, "sync-other@example.com").await;
+    let page = SyncService::pull(&state.db, other, &everything()).await.unwrap();
+    assert!(page.changes.is_empty());
+}
+
+#[tokio::test]
+async fn test_pull_never_moves_past_a_transaction_still_running() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "sync-order@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+    let slow = CardService::create_card(&state.db, deck_id, user_id, card("slow")).await.unwrap();
+    let fast = CardService::create_card(&state.db, deck_id, user_id, card("fast")).await.unwrap();
+    settle(&state).await;
+    let cursor = SyncService::pull(&state.db, user_id, &everything()).await.unwrap().next_cursor;
+
+    // The first edit takes the earlier version but commits last
+    let mut tx = state.db.begin().await.unwrap();
+    sqlx::query("UPDATE cards SET front = 'slow, edited' WHERE id = $1")
+        .bind(slow.id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE cards SET front = 'fast, edited' WHERE id = $1")
+        .bind(fast.id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    let since = SyncChangesQuery { since: Some(cursor), limit: None };
+    let page = SyncService::pull(&state.db, user_id, &since).await.unwrap();
+    assert!(page.changes.is_empty());
+    assert_eq!(page.next_cursor, cursor);
+
+    tx.commit().await.unwrap();
+    settle(&state).await;
+    let page = SyncService::pull(&state.db, user_id, &since).await.unwrap();
+    let mut fronts: Vec<_> = page.changes.iter().map(|c| c.data.as_ref().unwrap()["front"].clone()).collect();
+    fronts.sort_by_key(|front| front.to_string());
+    assert_eq!(fronts, vec![json!("fast, edited"), json!("slow, edited")]);
+}
+
+#[tokio::test]
+async fn test_pull_pages_end_on_transaction_boundaries() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "sync-pages@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+
+    // Three cards written together, then one on its own
+    let mut tx = state.db.begin().await.unwrap();
+    for front in ["one", "two", "three"] {
+        sqlx::query("INSERT INTO cards (deck_id, front, back, position) VALUES ($1, $2, 'back', 0)")
+            .bind(deck_id)
+            .bind(front)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+    }
+    tx.commit().await.unwrap();
+    CardService::create_card(&state.db, deck_id, user_id, card("four")).await.unwrap();
+    settle(&state).await;
+
+    // The deck fits, the batch does not, so the page stops before it
+    let page = SyncService::pull(&state.db, user_id, &SyncChangesQuery { since: None, limit: Some(2) })
+        .await
+        .unwrap();
+    assert!(page.has_more);
+    assert_eq!(page.changes.len(), 1);
+    assert_eq!(page.changes[0].entity, SyncEntity::Deck);
+
+    // A batch bigger than the limit still comes whole
+    let since = Some(page.next_cursor);
+    let page = SyncService::pull(&state.db, user_id, &SyncChangesQuery { since, limit: Some(2) })
+        .await
+        .unwrap();
+    assert_eq!(page.changes.len(), 3);
+
+    let since = Some(page.next_cursor);
+    let page = SyncService::pull(&state.db, user_id, &SyncChangesQuery { since, limit: Some(2) })
+        .await
+        .unwrap();
+    assert!(!page.has_more);
+    assert_eq!(page.changes.len(), 1);
+    assert_eq!(page.changes[0].data.as_ref().unwrap()["front"], "four");
+}
+
+#[tokio::test]
+async fn test_push_creates_and_resolves_conflicts_by_last_write() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "sync-push@example.com").await;
+    let deck_id = Uuid::new_v4();
+
+    let created = SyncService::push(
+        &state.db,
+        user_id,
+        SyncPushDto {
+            changes: vec![SyncPushChange {
+                entity: SyncEntity::Deck,
+                id: deck_id,
+                op: SyncOp::Upsert,
+                base_version: None,
+                updated_at: Utc::now(),
+                data: Some(json!({ "name": "Offline Deck", "description": null, "folder_id": null })),
+            }],
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(created.response.results[0].status, SyncPushStatus::Applied);
+    let base_version = created.response.results[0].version;
+    assert!(base_version.is_some());
+
+    // A newer server edit beats an older offline edit
+    DeckService::update_deck(
+        &state.db,
+        deck_id,
+        user_id,
+        serde_json::from_value(json!({ "name": "Server Name" })).unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let stale = SyncPushChange {
+        entity: SyncEntity::Deck,
+        id: deck_id,
+        op: SyncOp::Upsert,
+        base_version,
+        updated_at: Utc::now() - Duration::hours(1),
+        data: Some(json!({ "name": "Stale Name", "description": null, "folder_id": null })),
+    };
+    let outcome = SyncService::push(&state.db, user_id, SyncPushDto { changes: vec![stale.clone()] })
+        .await
+        .unwrap();
+    let result = &outcome.response.results[0];
+    assert_eq!(result.status, SyncPushStatus::Conflict);
+    assert_eq!(result.resolution, Some(ConflictResolution::ServerWins));
+    assert_eq!(result.server.as_ref().unwrap().data.as_ref().unwrap()["name"], "Server Name");
+
+    // A later offline edit wins
+    let newer = SyncPushChange {
+        updated_at: Utc::now() + Duration::minutes(1),
+        data: Some(json!({ "name": "Client Name", "description": null, "folder_id": null })),
+        ..stale
+    };
+    let outcome = SyncService::push(&state.db, user_id, SyncPushDto { changes: vec![newer] })
+        .await
+        .unwrap();
+    assert_eq!(outcome.response.results[0].resolution, Some(ConflictResolution::ClientWins));
+    let deck = DeckService::get_deck(&state.db, deck_id, user_id).await.unwrap();
+    assert_eq!(deck.name, "Client Name");
+
+    // Someone else's deck is rejected
+    let other = common::register(&state, "sync-push-other@example.com").await;
+    let outcome = SyncService::push(
+        &state.db,
+        other,
+        SyncPushDto {
+            changes: vec![SyncPushChange {
+                entity: SyncEntity::Deck,
+                id: deck_id,
+                op: SyncOp::Delete,
+                base_version: None,
+                updated_at: Utc::now() + Duration::hours(1),
+                data: None,
+            }],
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(outcome.response.results[0].status, SyncPushStatus::Rejected);
+}
+
+fn card_upsert(id: Uuid, deck_id: Uuid, front: &str, base_version: Option<i64>) -> SyncPushDto {
+    SyncPushDto {
+        changes: vec![SyncPushChange {
+            entity: SyncEntity::Card,
+            id,
+            op: SyncOp::Upsert,
+            base_version,
+            updated_at: Utc::now(),
+            data: Some(json!({ "deck_id": deck_id, "front": front, "back": "hello" })),
+        }],
+    }
+}
+
+#[tokio::test]
+async fn test_pushed_cards_get_reverses_and_webhook_events() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "sync-hooks@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+    let settings = UpdateDeckSettingsDto {
+        generate_reverse_cards: Some(true),
+        ..Default::default()
+    };
+    DeckSettingsService::update_settings(&state.db, deck_id, user_id, settings).await.unwrap();
+    let card_id = Uuid::new_v4();
+
+    let outcome = SyncService::push(&state.db, user_id, card_upsert(card_id, deck_id, "hola", None))
+        .await
+        .unwrap();
+    assert_eq!(outcome.response.results[0].status, SyncPushStatus::Applied);
+    assert_eq!(outcome.card_events.len(), 1);
+    assert_eq!(outcome.card_events[0].event, "card.created");
+    assert_eq!(outcome.card_events[0].deck_id, deck_id);
+    assert_eq!(outcome.card_events[0].card.id, card_id);
+
+    let reverse = sqlx::query_as::<_, (String, String)>("SELECT front, back FROM cards WHERE reverse_of = $1")
+        .bind(card_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(reverse, ("hello".to_string(), "hola".to_string()));
+
+    // Edits reach the reverse too
+    let base_version = outcome.response.results[0].version;
+    let outcome = SyncService::push(&state.db, user_id, card_upsert(card_id, deck_id, "buenas", base_version))
+        .await
+        .unwrap();
+    assert_eq!(outcome.card_events[0].event, "card.updated");
+    let reverse_back: String = sqlx::query_scalar("SELECT back FROM cards WHERE reverse_of = $1")
+        .bind(card_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(reverse_back, "buenas");
+}
+
+#[tokio::test]
+async fn test_push_rejects_cards_for_encrypted_decks() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "sync-vault@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+    sqlx::query("UPDATE decks SET is_encrypted = true WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    let card_id = Uuid::new_v4();
+    let outcome = SyncService::push(&state.db, user_id, card_upsert(card_id, deck_id, "plaintext", None))
+        .await
+        .unwrap();
+    assert_eq!(outcome.response.results[0].status, SyncPushStatus::Rejected);
+    assert!(outcome.response.results[0].error.as_ref().unwrap().contains("encrypted"));
+    assert!(outcome.card_events.is_empty());
+
+    let cards: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cards WHERE deck_id = $1")
+        .bind(deck_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(cards, 0);
+}
+
+#[tokio::test]
+async fn test_unverified_accounts_can_pull_but_not_push() {
+    let mut config = common::test_config();
+    config.account.require_email_verification = true;
+    let state = AppState::from_parts(common::setup_test_db().await, config);
+    let (_, token) = common::register_with_token(&state, "sync-unverified@example.com").await;
+    let auth: HeaderValue = token.parse().unwrap();
+    let db = state.db.clone();
+    let server = TestServer::new(create_app(state)).unwrap();
+
+    let response = server.get("/api/v1/sync/changes").add_header(header::AUTHORIZATION, auth.clone()).await;
+    response.assert_status_ok();
+
+    let deck = json!({
+        "changes": [{
+            "entity": "deck",
+            "id": Uuid::new_v4(),
+            "op": "upsert",
+            "base_version": null,
+            "updated_at": Utc::now(),
+            "data": { "name": "Offline Deck", "description": null, "folder_id": null }
+        }]
+    });
+    let response = server.post("/api/v1/sync/push").add_header(header::AUTHORIZATION, auth).json(&deck).await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(response.json::<serde_json::Value>()["code"], "EMAIL_NOT_VERIFIED");
+
+    let decks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM decks").fetch_one(&db).await.unwrap();
+    assert_eq!(decks, 0);
+}
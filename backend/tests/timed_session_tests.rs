@@ -0,0 +1,50 @@
+use chrono::{Duration, Utc};
+use deckoracle_backend::{models::StudySession, services::study::StudyService};
+use uuid::Uuid;
+
+fn session(expires_in: Option<Duration>) -> StudySession {
+    let now = Utc::now();
+    StudySession {
+        id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        deck_id: Some(Uuid::new_v4()),
+        folder_id: None,
+        study_mode: "timed".to_string(),
+        total_cards: 10,
+        cards_studied: 0,
+        cards_correct: 0,
+        cards_incorrect: 0,
+        cards_skipped: 0,
+        duration_seconds: None,
+        time_limit_seconds: expires_in.map(|_| 300),
+        expires_at: expires_in.map(|d| now + d),
+        remaining_seconds: None,
+        started_at: now,
+        completed_at: None,
+        abandoned_at: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+#[test]
+fn test_untimed_session_accepts_answers() {
+    assert!(StudyService::ensure_accepting_answers(&session(None)).is_ok());
+}
+
+#[test]
+fn test_timed_session_accepts_answers_before_expiry() {
+    assert!(StudyService::ensure_accepting_answers(&session(Some(Duration::seconds(60)))).is_ok());
+}
+
+#[test]
+fn test_timed_session_rejects_answers_after_expiry() {
+    assert!(StudyService::ensure_accepting_answers(&session(Some(Duration::seconds(-1)))).is_err());
+}
+
+#[test]
+fn test_abandoned_session_rejects_answers() {
+    let mut abandoned = session(None);
+    abandoned.abandoned_at = Some(Utc::now());
+    assert!(StudyService::ensure_accepting_answers(&abandoned).is_err());
+}
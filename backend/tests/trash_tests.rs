@@ -0,0 +1,129 @@
+mod common;
+
+use deckoracle_backend::models::{Card, CardOrder, CreateCardDto, CreateDeckDto, RegisterDto, RestoredItem};
+use deckoracle_backend::services::{
+    auth::AuthService, card::CardService, deck::DeckService, trash::TrashService,
+};
+use deckoracle_backend::utils::{AppError, CursorParams};
+use uuid::Uuid;
+
+async fn user_with_deck(state: &deckoracle_backend::state::AppState, email: &str) -> (Uuid, Uuid) {
+    let user = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: email.to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user;
+
+    let deck = DeckService::create_deck(
+        &state.db,
+        user.id,
+        CreateDeckDto {
+            name: "Trash Test".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: Some(false),
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    (user.id, deck.id)
+}
+
+async fn deck_cards(state: &deckoracle_backend::state::AppState, deck_id: Uuid, user_id: Uuid) -> Vec<Card> {
+    CardService::list_deck_cards(&state.db, deck_id, user_id, CardOrder::Position, CursorParams::default())
+        .await
+        .unwrap()
+        .data
+}
+
+fn card_dto(front: &str) -> CreateCardDto {
+    CreateCardDto {
+        front: front.to_string(),
+        back: "back".to_string(),
+        position: None,
+    }
+}
+
+#[tokio::test]
+async fn test_deleted_deck_goes_to_trash_and_restores() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = user_with_deck(&state, "trash-deck@example.com").await;
+    CardService::create_card(&state.db, deck_id, user_id, card_dto("kept")).await.unwrap();
+
+    DeckService::delete_deck(&state.db, deck_id, user_id).await.unwrap();
+
+    assert!(matches!(
+        DeckService::get_deck(&state.db, deck_id, user_id).await,
+        Err(AppError::DeckNotFound)
+    ));
+    assert!(DeckService::list_user_decks(&state.db, user_id).await.unwrap().is_empty());
+
+    let trash = TrashService::list(&state.db, user_id).await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].id, deck_id);
+    assert_eq!(trash[0].item_type, "deck");
+
+    let restored = TrashService::restore(&state.db, user_id, deck_id).await.unwrap();
+    assert!(matches!(restored, RestoredItem::Deck { .. }));
+
+    // The deck's cards come back with it
+    let cards = deck_cards(&state, deck_id, user_id).await;
+    assert_eq!(cards.len(), 1);
+    assert!(TrashService::list(&state.db, user_id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_deleted_card_is_hidden_until_restored() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = user_with_deck(&state, "trash-card@example.com").await;
+    let card = CardService::create_card(&state.db, deck_id, user_id, card_dto("gone")).await.unwrap();
+
+    CardService::delete_card(&state.db, card.id, user_id).await.unwrap();
+
+    assert!(deck_cards(&state, deck_id, user_id).await.is_empty());
+    assert!(matches!(
+        CardService::get_card(&state.db, card.id, user_id).await,
+        Err(AppError::CardNotFound)
+    ));
+
+    // Another user cannot restore it
+    let (other_id, _) = user_with_deck(&state, "trash-other@example.com").await;
+    assert!(TrashService::restore(&state.db, other_id, card.id).await.is_err());
+
+    let restored = TrashService::restore(&state.db, user_id, card.id).await.unwrap();
+    assert!(matches!(restored, RestoredItem::Card { card: ref c } if c.id == card.id));
+    assert_eq!(deck_cards(&state, deck_id, user_id).await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_purge_removes_only_expired_items() {
+    let state = common::create_test_state().await;
+    let (user_id, deck_id) = user_with_deck(&state, "trash-purge@example.com").await;
+    let old = CardService::create_card(&state.db, deck_id, user_id, card_dto("old")).await.unwrap();
+    let recent = CardService::create_card(&state.db, deck_id, user_id, card_dto("recent")).await.unwrap();
+
+    CardService::delete_card(&state.db, old.id, user_id).await.unwrap();
+    CardService::delete_card(&state.db, recent.id, user_id).await.unwrap();
+    sqlx::query("UPDATE cards SET deleted_at = NOW() - INTERVAL '31 days' WHERE id = $1")
+        .bind(old.id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+    let (decks, cards) = TrashService::purge_expired(&state.db).await.unwrap();
+    assert_eq!((decks, cards), (0, 1));
+
+    let trash = TrashService::list(&state.db, user_id).await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].id, recent.id);
+}
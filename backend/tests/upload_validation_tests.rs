@@ -0,0 +1,127 @@
+use axum::{http::StatusCode, response::IntoResponse};
+use docx_rs::{Docx, Paragraph, Run};
+use deckoracle_backend::config::UploadConfig;
+use deckoracle_backend::models::import_export::ImportFormat;
+use deckoracle_backend::services::document::DocumentService;
+use deckoracle_backend::services::upload::{check_import, file_extension, sniff, FileContent};
+use deckoracle_backend::utils::{AppError, ErrorCode};
+
+fn config() -> UploadConfig {
+    UploadConfig {
+        max_file_size: 1024,
+        allowed_file_types: ["csv", "txt", "json", "md"].map(String::from).to_vec(),
+        allowed_media_types: vec![],
+        spool_threshold: 64,
+        temp_dir: std::env::temp_dir().to_string_lossy().into_owned(),
+        max_body_size: 4096,
+        max_inbound_body_size: 4096,
+    }
+}
+
+fn rejection(result: Result<(), AppError>) -> String {
+    match result {
+        Err(AppError::FileUploadError(message)) => message,
+        other => panic!("expected an upload error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sniff_magic_bytes() {
+    assert_eq!(sniff(b"PK\x03\x04rest"), Some(FileContent::Zip));
+    assert_eq!(sniff(b"%PDF-1.7"), Some(FileContent::Pdf));
+    assert_eq!(sniff(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1, 0]), Some(FileContent::Ole2));
+    assert_eq!(sniff("\u{feff}front,back\nhola,hello".as_bytes()), Some(FileContent::Text));
+    assert_eq!(sniff(b"front\0back"), None);
+    assert_eq!(sniff(&[0xff, 0xfe, 0x41]), None);
+    // Only the head of a file may be sniffed, cutting a character in two
+    assert_eq!(sniff(&"front,señal".as_bytes()[..9]), Some(FileContent::Text));
+
+    assert_eq!(file_extension("C:\\decks\\Spanish.CSV").as_deref(), Some("csv"));
+    assert_eq!(file_extension("README"), None);
+}
+
+#[test]
+fn test_matching_imports_pass() {
+    let config = config();
+    assert!(check_import(&config, &ImportFormat::Csv, Some("deck.csv"), Some("text/csv"), b"a,b").is_ok());
+    // Windows browsers send CSV as an Excel type, which claims nothing
+    assert!(check_import(&config, &ImportFormat::Csv, Some("deck.csv"), Some("application/vnd.ms-excel"), b"a,b").is_ok());
+    assert!(check_import(&config, &ImportFormat::Json, None, None, b"{}").is_ok());
+}
+
+#[test]
+fn test_mismatches_are_rejected_before_parsing() {
+    let config = config();
+
+    let message = rejection(check_import(&config, &ImportFormat::Json, Some("deck.pdf"), None, b"{}"));
+    assert_eq!(message, "A .pdf file cannot be imported as json; expected .json");
+
+    let message = rejection(check_import(&config, &ImportFormat::Csv, Some("deck.csv"), None, b"PK\x03\x04zip"));
+    assert!(message.starts_with("File content is a ZIP archive, not UTF-8 text"));
+
+    let message = rejection(check_import(&config, &ImportFormat::Csv, None, Some("application/pdf"), b"a,b"));
+    assert!(message.starts_with("File was sent as a PDF"));
+
+    let message = rejection(check_import(&config, &ImportFormat::Markdown, Some("notes.md"), None, b"# \xff\xfe"));
+    assert!(message.starts_with("File content is not UTF-8 text"));
+
+    // xml is not in this server's ALLOWED_FILE_TYPES
+    let message = rejection(check_import(&config, &ImportFormat::Mnemosyne, Some("export.xml"), None, b"<x/>"));
+    assert_eq!(message, "mnemosyne imports are not accepted by this server");
+
+    let message = rejection(check_import(&config, &ImportFormat::Csv, Some("deck.csv"), None, b""));
+    assert_eq!(message, "File is empty");
+}
+
+#[tokio::test]
+async fn test_oversized_uploads_are_413() {
+    let result = check_import(&config(), &ImportFormat::Csv, Some("deck.csv"), None, &[b'a'; 1025]);
+    let error = result.unwrap_err();
+    assert_eq!(error.code(), ErrorCode::FileTooLarge);
+
+    let response = error.into_response();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["code"], "FILE_TOO_LARGE");
+    assert_eq!(body["error"], "File too large; uploads are limited to 1 KB");
+}
+
+#[tokio::test]
+async fn test_spooled_documents_extract_like_buffered_ones() {
+    let text = b"# Cells\n\nThe mitochondria is the powerhouse of the cell.\n";
+    let path = std::env::temp_dir().join(format!("deckoracle-test-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, text).unwrap();
+
+    let from_file = DocumentService::extract_file(&path, Some("notes.md"), None).await;
+    std::fs::remove_file(&path).unwrap();
+    let from_file = from_file.unwrap();
+    let from_memory = DocumentService::extract(text, Some("notes.md"), None).unwrap();
+
+    assert_eq!(from_file.kind, from_memory.kind);
+    assert_eq!(from_file.word_count, from_memory.word_count);
+    assert_eq!(from_file.sections.len(), from_memory.sections.len());
+}
+
+#[tokio::test]
+async fn test_spooled_docx_is_read_without_loading_it_whole() {
+    let mut docx = std::io::Cursor::new(Vec::new());
+    Docx::new()
+        .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Cells")).style("Heading1"))
+        .add_paragraph(Paragraph::new().add_run(Run::new().add_text("The mitochondria is the powerhouse.")))
+        .build()
+        .pack(&mut docx)
+        .unwrap();
+    let docx = docx.into_inner();
+    let path = std::env::temp_dir().join(format!("deckoracle-test-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, &docx).unwrap();
+
+    let from_file = DocumentService::extract_file(&path, Some("notes.docx"), None).await;
+    std::fs::remove_file(&path).unwrap();
+    let from_file = from_file.unwrap();
+    let from_memory = DocumentService::extract(&docx, Some("notes.docx"), None).unwrap();
+
+    assert_eq!(from_file.kind, "docx");
+    assert_eq!(from_file.title, from_memory.title);
+    assert_eq!(from_file.word_count, from_memory.word_count);
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use deckoracle_backend::models::{
+    ai::StudyQueueQuery, CreateCardDto, CreateDeckDto, CreateFolderDto, NewCardOrder, RegisterDto,
+    UpdateUserPreferencesDto,
+};
+use deckoracle_backend::services::{
+    auth::AuthService,
+    card::CardService,
+    deck::DeckService,
+    folder::FolderService,
+    preferences::PreferencesService,
+    study_queue::{group_by_deck, StudyQueueService},
+};
+use uuid::Uuid;
+
+fn queue_query(deck_id: Option<Uuid>, folder_id: Option<Uuid>) -> StudyQueueQuery {
+    StudyQueueQuery {
+        deck_id,
+        folder_id,
+        smart_deck_id: None,
+        max_new_cards: None,
+        focus_weak_cards: None,
+        include_overdue: None,
+        prioritize_starred: None,
+    }
+}
+
+#[test]
+fn test_group_by_deck_keeps_first_appearance_order() {
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    let cards = vec![(a, 1), (b, 1), (a, 2), (b, 2), (a, 3)];
+
+    let grouped = group_by_deck(cards, |card| card.0);
+    assert_eq!(grouped, vec![(a, 1), (a, 2), (a, 3), (b, 1), (b, 2)]);
+}
+
+#[tokio::test]
+async fn test_preferences_order_the_study_queue() {
+    let state = common::create_test_state().await;
+    let user_id = AuthService::register(
+        &state.db,
+        &state.config,
+        RegisterDto {
+            email: "preferences@example.com".to_string(),
+            password: "Password123".to_string(),
+            display_name: None,
+        },
+    )
+    .await
+    .unwrap()
+    .user
+    .id;
+
+    let defaults = PreferencesService::get(&state.db, user_id).await.unwrap();
+    assert_eq!(defaults.new_card_order, NewCardOrder::Position);
+    assert!(defaults.interleave_decks);
+
+    let folder = FolderService::create_folder(
+        &state.db,
+        user_id,
+        CreateFolderDto {
+            name: "Languages".to_string(),
+            parent_folder_id: None,
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut decks = Vec::new();
+    for name in ["Spanish", "French"] {
+        let deck = DeckService::create_deck(
+            &state.db,
+            user_id,
+            CreateDeckDto {
+                name: name.to_string(),
+                description: None,
+                folder_id: Some(folder.id),
+                is_public: None,
+                front_language: None,
+                back_language: None,
+            },
+        )
+        .await
+        .unwrap();
+        let mut card_ids = Vec::new();
+        for i in 0..8 {
+            let card = CardService::create_card(
+                &state.db,
+                deck.id,
+                user_id,
+                CreateCardDto {
+                    front: format!("{} {}", name, i),
+                    back: i.to_string(),
+                    position: None,
+                },
+            )
+            .await
+            .unwrap();
+            card_ids.push(card.id);
+        }
+        decks.push((deck.id, card_ids));
+    }
+    let (spanish, spanish_cards) = decks[0].clone();
+
+    // Position order by default, taking turns between decks
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(Some(spanish), None))
+        .await
+        .unwrap();
+    let order: Vec<Uuid> = queue.new.iter().map(|s| s.card_id).collect();
+    assert_eq!(order, spanish_cards);
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(None, Some(folder.id)))
+        .await
+        .unwrap();
+    assert_ne!(queue.new[0].deck_id, queue.new[1].deck_id);
+
+    let updated = PreferencesService::update(
+        &state.db,
+        user_id,
+        UpdateUserPreferencesDto {
+            new_card_order: Some(NewCardOrder::Random),
+            interleave_decks: Some(false),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.new_card_order, NewCardOrder::Random);
+    assert!(!updated.interleave_decks);
+
+    // Shuffled, but the same all day
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(Some(spanish), None))
+        .await
+        .unwrap();
+    let shuffled: Vec<Uuid> = queue.new.iter().map(|s| s.card_id).collect();
+    assert_ne!(shuffled, spanish_cards);
+    let mut sorted = shuffled.clone();
+    sorted.sort();
+    let mut expected = spanish_cards.clone();
+    expected.sort();
+    assert_eq!(sorted, expected);
+    let again = StudyQueueService::build_queue(&state.db, user_id, &queue_query(Some(spanish), None))
+        .await
+        .unwrap();
+    assert!(again.new.iter().map(|s| s.card_id).eq(shuffled));
+
+    // One deck after the other
+    let queue = StudyQueueService::build_queue(&state.db, user_id, &queue_query(None, Some(folder.id)))
+        .await
+        .unwrap();
+    assert_eq!(queue.new.len(), 16);
+    assert!(queue.new[..8].iter().all(|s| s.deck_id == queue.new[0].deck_id));
+    assert!(queue.new[8..].iter().all(|s| s.deck_id != queue.new[0].deck_id));
+
+    // Fields left out stay as they are
+    let updated = PreferencesService::update(&state.db, user_id, UpdateUserPreferencesDto::default())
+        .await
+        .unwrap();
+    assert_eq!(updated.new_card_order, NewCardOrder::Random);
+    assert!(!updated.interleave_decks);
+}
@@ -0,0 +1,29 @@
+use deckoracle_backend::{
+    models::Rating,
+    services::stats::{answer_points, level_for_points, points_for_level},
+};
+
+#[test]
+fn test_level_thresholds() {
+    assert_eq!(points_for_level(1), 0);
+    assert_eq!(points_for_level(2), 100);
+    assert_eq!(points_for_level(3), 300);
+    assert_eq!(points_for_level(4), 600);
+}
+
+#[test]
+fn test_level_for_points_levels_up_at_threshold() {
+    assert_eq!(level_for_points(0), 1);
+    assert_eq!(level_for_points(99), 1);
+    assert_eq!(level_for_points(100), 2);
+    assert_eq!(level_for_points(299), 2);
+    assert_eq!(level_for_points(300), 3);
+    assert_eq!(level_for_points(10_000), 14);
+}
+
+#[test]
+fn test_answer_points() {
+    assert_eq!(answer_points(Rating::Again), 2);
+    assert_eq!(answer_points(Rating::Hard), 10);
+    assert_eq!(answer_points(Rating::Easy), 10);
+}
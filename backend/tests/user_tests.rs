@@ -0,0 +1,161 @@
+mod common;
+
+use deckoracle_backend::models::{
+    ChangePasswordDto, ClientInfo, DeleteAccountDto, LoginDto, RefreshTokenDto, RegisterDto,
+    UpdateProfileDto,
+};
+use deckoracle_backend::services::{auth::AuthService, user::UserService};
+use deckoracle_backend::utils::AppError;
+
+fn login_dto(email: &str, password: &str) -> LoginDto {
+    LoginDto {
+        email: email.to_string(),
+        password: password.to_string(),
+        remember_me: None,
+    }
+}
+
+fn register_dto(email: &str) -> RegisterDto {
+    RegisterDto {
+        email: email.to_string(),
+        password: "Password123".to_string(),
+        display_name: Some("Test User".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_email_change_requires_password_and_stays_pending() {
+    let state = common::create_test_state().await;
+
+    let registered = AuthService::register(&state.db, &state.config, register_dto("old@example.com"))
+        .await
+        .unwrap();
+
+    let without_password = UserService::update_profile(
+        &state.db,
+        registered.user.id,
+        UpdateProfileDto {
+            email: Some("new@example.com".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(matches!(without_password, Err(AppError::BadRequest(_))));
+
+    let updated = UserService::update_profile(
+        &state.db,
+        registered.user.id,
+        UpdateProfileDto {
+            display_name: Some("Renamed".to_string()),
+            email: Some("new@example.com".to_string()),
+            current_password: Some("Password123".to_string()),
+        },
+    )
+    .await
+    .expect("profile update should succeed");
+
+    // The old email stays in use until the new one is verified
+    assert_eq!(updated.email, "old@example.com");
+    assert_eq!(updated.pending_email.as_deref(), Some("new@example.com"));
+    assert_eq!(updated.display_name.as_deref(), Some("Renamed"));
+}
+
+#[tokio::test]
+async fn test_change_password_checks_current_and_ends_sessions() {
+    let state = common::create_test_state().await;
+
+    let registered = AuthService::register(&state.db, &state.config, register_dto("pw@example.com"))
+        .await
+        .unwrap();
+
+    let wrong = UserService::change_password(
+        &state.db,
+        registered.user.id,
+        ChangePasswordDto {
+            current_password: "WrongPassword1".to_string(),
+            new_password: "NewPassword123".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(wrong, Err(AppError::Unauthorized)));
+
+    UserService::change_password(
+        &state.db,
+        registered.user.id,
+        ChangePasswordDto {
+            current_password: "Password123".to_string(),
+            new_password: "NewPassword123".to_string(),
+        },
+    )
+    .await
+    .expect("password change should succeed");
+
+    let refresh = AuthService::refresh_token(
+        &state.db,
+        &state.config,
+        RefreshTokenDto {
+            refresh_token: registered.refresh_token,
+        },
+    )
+    .await;
+    assert!(matches!(refresh, Err(AppError::Unauthorized)));
+
+    let client = ClientInfo::default();
+    let old = AuthService::login(&state.db, &state.config, login_dto("pw@example.com", "Password123"), &client).await;
+    assert!(matches!(old, Err(AppError::Unauthorized)));
+    AuthService::login(&state.db, &state.config, login_dto("pw@example.com", "NewPassword123"), &client)
+        .await
+        .expect("login with the new password should succeed");
+}
+
+#[tokio::test]
+async fn test_deleted_account_cannot_log_in() {
+    let state = common::create_test_state().await;
+
+    let registered = AuthService::register(&state.db, &state.config, register_dto("gone@example.com"))
+        .await
+        .unwrap();
+
+    UserService::delete_account(
+        &state.db,
+        registered.user.id,
+        DeleteAccountDto {
+            password: "Password123".to_string(),
+        },
+    )
+    .await
+    .expect("deletion should succeed");
+
+    // Soft-deleted: the row remains but is no longer usable
+    let deleted_at: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT deleted_at FROM users WHERE id = $1")
+            .bind(registered.user.id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert!(deleted_at.is_some());
+
+    let login = AuthService::login(
+        &state.db,
+        &state.config,
+        login_dto("gone@example.com", "Password123"),
+        &ClientInfo::default(),
+    )
+    .await;
+    assert!(matches!(login, Err(AppError::Unauthorized)));
+
+    let refresh = AuthService::refresh_token(
+        &state.db,
+        &state.config,
+        RefreshTokenDto {
+            refresh_token: registered.refresh_token,
+        },
+    )
+    .await;
+    assert!(matches!(refresh, Err(AppError::Unauthorized)));
+
+    assert!(matches!(
+        UserService::get_profile(&state.db, registered.user.id).await,
+        Err(AppError::UserNotFound)
+    ));
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use deckoracle_backend::models::{CreateDeckDto, CreateDeckWebhookDto, DeckWebhook};
+use deckoracle_backend::services::{
+    deck::DeckService,
+    webhook::{self, WebhookService, MAX_CONSECUTIVE_FAILURES},
+};
+use deckoracle_backend::state::AppState;
+use deckoracle_backend::utils::AppError;
+use serde_json::json;
+use uuid::Uuid;
+
+async fn create_deck(state: &AppState, user_id: Uuid) -> Uuid {
+    DeckService::create_deck(
+        &state.db,
+        user_id,
+        CreateDeckDto {
+            name: "Hooks".to_string(),
+            description: None,
+            folder_id: None,
+            is_public: None,
+            front_language: None,
+            back_language: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+/// Stored directly, as a webhook whose host resolved to a public address
+/// when it was registered and to a local one by the time it is delivered
+async fn insert_webhook(state: &AppState, deck_id: Uuid, user_id: Uuid, events: &[&str]) -> Uuid {
+    let events: Vec<String> = events.iter().map(|e| e.to_string()).collect();
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO deck_webhooks (deck_id, created_by, url, secret, events)
+        VALUES ($1, $2, 'http://127.0.0.1:9/hook', 'secret', $3)
+        RETURNING id
+        "#,
+    )
+    .bind(deck_id)
+    .bind(user_id)
+    .bind(&events)
+    .fetch_one(&state.db)
+    .await
+    .unwrap()
+}
+
+async fn fetch_webhook(state: &AppState, webhook_id: Uuid) -> DeckWebhook {
+    sqlx::query_as("SELECT * FROM deck_webhooks WHERE id = $1")
+        .bind(webhook_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_deliveries_are_signed_with_hmac_sha256() {
+    // Well-known HMAC-SHA256 test vector
+    assert_eq!(
+        webhook::sign("key", b"The quick brown fox jumps over the lazy dog"),
+        "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+    );
+    assert_ne!(webhook::sign("other", b"body"), webhook::sign("key", b"body"));
+}
+
+#[tokio::test]
+async fn test_webhooks_cannot_target_internal_addresses() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "hooks@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+
+    for url in [
+        "http://127.0.0.1/hook",
+        "http://localhost:8080/hook",
+        "http://10.0.0.5/hook",
+        "http://192.168.1.1/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://0.0.0.0/hook",
+        "http://[::1]/hook",
+        "http://[fd00::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+        "ftp://example.com/hook",
+    ] {
+        let dto = CreateDeckWebhookDto {
+            url: url.to_string(),
+            events: vec!["card.created".to_string()],
+        };
+        let error = WebhookService::create(&state.db, deck_id, user_id, dto).await.unwrap_err();
+        assert!(matches!(error, AppError::ValidationError(_)), "{} was accepted", url);
+    }
+
+    // Public addresses are fine
+    let dto = CreateDeckWebhookDto {
+        url: "https://93.184.216.34/hook".to_string(),
+        events: vec!["card.created".to_string()],
+    };
+    let created = WebhookService::create(&state.db, deck_id, user_id, dto).await.unwrap();
+    assert!(created.webhook.is_active);
+}
+
+#[tokio::test]
+async fn test_only_subscribed_events_are_delivered() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "events@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+    let webhook_id = insert_webhook(&state, deck_id, user_id, &["card.updated"]).await;
+
+    WebhookService::deliver(&state.db, deck_id, "card.created", json!({})).await.unwrap();
+    let webhook = fetch_webhook(&state, webhook_id).await;
+    assert!(webhook.last_delivery_at.is_none());
+
+    // Delivery time checks refuse the local address without sending anything
+    WebhookService::deliver(&state.db, deck_id, "card.updated", json!({})).await.unwrap();
+    let webhook = fetch_webhook(&state, webhook_id).await;
+    assert!(webhook.last_delivery_at.is_some());
+    assert!(webhook.last_status.is_none());
+    assert!(webhook.last_error.unwrap().contains("private or local"));
+    assert_eq!(webhook.consecutive_failures, 1);
+}
+
+#[tokio::test]
+async fn test_webhook_is_disabled_after_repeated_failures() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "failures@example.com").await;
+    let deck_id = create_deck(&state, user_id).await;
+    let webhook_id = insert_webhook(&state, deck_id, user_id, &["card.created"]).await;
+
+    for _ in 0..MAX_CONSECUTIVE_FAILURES - 1 {
+        WebhookService::deliver(&state.db, deck_id, "card.created", json!({})).await.unwrap();
+    }
+    assert!(fetch_webhook(&state, webhook_id).await.is_active);
+
+    WebhookService::deliver(&state.db, deck_id, "card.created", json!({})).await.unwrap();
+    let webhook = fetch_webhook(&state, webhook_id).await;
+    assert!(!webhook.is_active);
+    assert_eq!(webhook.consecutive_failures, MAX_CONSECUTIVE_FAILURES);
+
+    // Disabled webhooks are skipped
+    WebhookService::deliver(&state.db, deck_id, "card.created", json!({})).await.unwrap();
+    let webhook = fetch_webhook(&state, webhook_id).await;
+    assert_eq!(webhook.consecutive_failures, MAX_CONSECUTIVE_FAILURES);
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use deckoracle_backend::{
+    create_app,
+    models::ai::WsMessage,
+    services::ws::{WsHub, WsSubscriptionService},
+    utils::AppError,
+};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_connecting_needs_a_valid_single_use_ticket() {
+    let state = common::create_test_state().await;
+    let (_, token) = common::register_with_token(&state, "socket@example.com").await;
+    let server = TestServer::new(create_app(state)).unwrap();
+    let authorization: HeaderValue = token.parse().unwrap();
+
+    assert_eq!(server.get("/api/v1/ws").await.status_code(), StatusCode::UNAUTHORIZED);
+    assert_eq!(server.post("/api/v1/ws/ticket").await.status_code(), StatusCode::UNAUTHORIZED);
+
+    // Access tokens are no longer taken from the query string
+    let bare_token = token.trim_start_matches("Bearer ");
+    let response = server.get("/api/v1/ws").add_query_param("token", bare_token).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let response = server.get("/api/v1/ws").add_query_param("ticket", "made-up").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let response = server
+        .post("/api/v1/ws/ticket")
+        .add_header(header::AUTHORIZATION, authorization)
+        .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["expires_in"], 30);
+    let ticket = body["ticket"].as_str().unwrap().to_string();
+
+    // This is not an upgrade request, but the ticket is accepted and spent
+    let response = server.get("/api/v1/ws").add_query_param("ticket", &ticket).await;
+    assert_ne!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let response = server.get("/api/v1/ws").add_query_param("ticket", &ticket).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_tickets_belong_to_the_user_they_were_issued_to() {
+    let hub = WsHub::new();
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+
+    let first = hub.issue_ticket(alice).await;
+    let second = hub.issue_ticket(bob).await;
+    assert_ne!(first, second);
+
+    assert_eq!(hub.redeem_ticket(&second).await, Some(bob));
+    assert_eq!(hub.redeem_ticket(&first).await, Some(alice));
+    assert_eq!(hub.redeem_ticket(&first).await, None);
+}
+
+#[tokio::test]
+async fn test_messages_reach_only_the_subscribed_connections_of_the_user() {
+    let hub = WsHub::new();
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+    let (alice_progress_tx, mut alice_progress) = mpsc::unbounded_channel();
+    let (alice_other_tx, mut alice_other) = mpsc::unbounded_channel();
+    let (bob_tx, mut bob_rx) = mpsc::unbounded_channel();
+    hub.register(alice, "alice-1", alice_progress_tx).await;
+    hub.register(alice, "alice-2", alice_other_tx).await;
+    hub.register(bob, "bob-1", bob_tx).await;
+    hub.set_subscribed(alice, "alice-1", "progress_updates", true).await;
+    hub.set_subscribed(bob, "bob-1", "progress_updates", true).await;
+
+    // Another user's connection id does not let anyone subscribe it
+    hub.set_subscribed(bob, "alice-2", "progress_updates", true).await;
+
+    hub.publish(alice, "progress_updates", WsMessage::new("study_progress", json!({}))).await;
+    assert_eq!(alice_progress.try_recv().unwrap().message_type, "study_progress");
+    assert!(alice_other.try_recv().is_err());
+    assert!(bob_rx.try_recv().is_err());
+
+    hub.set_subscribed(alice, "alice-1", "progress_updates", false).await;
+    hub.publish(alice, "progress_updates", WsMessage::new("study_progress", json!({}))).await;
+    assert!(alice_progress.try_recv().is_err());
+
+    // Direct messages go to every connection of the user only
+    hub.send_to_user(alice, WsMessage::new("session_handoff", json!({}))).await;
+    assert!(alice_progress.try_recv().is_ok());
+    assert!(alice_other.try_recv().is_ok());
+    assert!(bob_rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_unknown_subscription_types_are_refused() {
+    let state = common::create_test_state().await;
+    let user_id = common::register(&state, "subscriber@example.com").await;
+    let connection_id = Uuid::new_v4().to_string();
+
+    let error = WsSubscriptionService::subscribe(&state.db, user_id, &connection_id, "admin_events")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AppError::BadRequest(_)));
+
+    let subscription =
+        WsSubscriptionService::subscribe(&state.db, user_id, &connection_id, "study_insights")
+            .await
+            .unwrap();
+    assert_eq!(subscription.user_id, user_id);
+    assert!(subscription.active);
+}
@@ -1,6 +1,11 @@
 import { createApi, fetchBaseQuery } from '@reduxjs/toolkit/query/react';
 import type { Folder, Deck, Card } from '../../types';
 
+interface CardPage {
+  data: Card[];
+  pagination: { limit: number; has_next: boolean; next_cursor?: string | null };
+}
+
 export const api = createApi({
   reducerPath: 'deckOracleApi',
   baseQuery: fetchBaseQuery({ 
@@ -88,7 +93,21 @@ export const api = createApi({
 
     // Cards
     getCards: builder.query<Card[], string>({
-      query: (deckId) => `/cards?deck_id=${deckId}`,
+      // The endpoint is cursor-paginated; follow next_cursor until the deck is exhausted
+      async queryFn(deckId, _api, _extraOptions, fetchWithBQ) {
+        const cards: Card[] = [];
+        let cursor: string | null = null;
+        do {
+          const params = new URLSearchParams({ deck_id: deckId, limit: '100' });
+          if (cursor) params.set('cursor', cursor);
+          const result = await fetchWithBQ(`/cards?${params.toString()}`);
+          if (result.error) return { error: result.error };
+          const page = result.data as CardPage;
+          cards.push(...page.data);
+          cursor = page.pagination.next_cursor ?? null;
+        } while (cursor);
+        return { data: cards };
+      },
       providesTags: ['Card'],
     }),
     getCard: builder.query<Card, string>({
@@ -37,6 +37,8 @@ export interface Card {
   front: string;
   back: string;
   position: number;  // Added from backend model
+  renderedFront?: string;  // Sanitized HTML, only with ?render=html
+  renderedBack?: string;
   tags?: string[];  // TODO: backend-sync - not in backend yet
   createdAt: string;
   updatedAt: string;